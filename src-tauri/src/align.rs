@@ -0,0 +1,336 @@
+// src-tauri/src/align.rs
+use std::collections::HashSet;
+use std::fs;
+use std::sync::Mutex;
+
+use crate::chunk;
+use crate::embed;
+use crate::error::{AppError, LockExt};
+use crate::tm::TranslationMemory;
+use crate::ModelManager;
+
+// variance of the target/source length ratio per source character (Gale & Church)
+const LENGTH_VARIANCE: f32 = 6.8;
+// (source sentences, target sentences, prior probability) of each bead type
+const BEADS: &[(usize, usize, f32)] = &[(1, 1, 0.89), (1, 0, 0.005), (0, 1, 0.005), (2, 1, 0.045), (1, 2, 0.045), (2, 2, 0.011)];
+// cost taken off a bead per shared anchor (number, name, URL) on both sides
+const ANCHOR_BONUS: f32 = 1.5;
+// cost taken off a bead per sentence it covers, times the cosine similarity of both sides' embeddings
+const EMBEDDING_BONUS: f32 = 4.0;
+// pairs below this confidence are returned but not imported by default
+const DEFAULT_MIN_CONFIDENCE: f32 = 0.5;
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct AlignedPair {
+  pub source: String,
+  pub target: String,
+  // sentences merged on each side (1 for a plain 1:1 match)
+  pub source_sentences: usize,
+  pub target_sentences: usize,
+  // 0..1, from how well the lengths fit, shared anchors and how close the embeddings are
+  pub confidence: f32,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct AlignmentReport {
+  pub pairs: Vec<AlignedPair>,
+  // sentences with no counterpart on the other side
+  pub unmatched_source: usize,
+  pub unmatched_target: usize,
+  // pairs written to the translation memory
+  pub imported: usize,
+  // why sentences were aligned by length and anchors only (no model, or it can't embed); None
+  // when embeddings were used
+  pub embedding_error: Option<String>,
+}
+
+// standard normal CDF (Abramowitz & Stegun 7.1.26)
+fn normal_cdf(x: f32) -> f32 {
+  let t = 1.0 / (1.0 + 0.3275911 * x.abs() / std::f32::consts::SQRT_2);
+  let poly = t * (0.2548296 + t * (-0.28449672 + t * (1.4214138 + t * (-1.4531521 + t * 1.0614054))));
+  let erf = 1.0 - poly * (-(x * x) / 2.0).exp();
+  if x >= 0.0 {
+    0.5 * (1.0 + erf)
+  } else {
+    0.5 * (1.0 - erf)
+  }
+}
+
+// probability that a source of `l1` characters and a target of `l2` are translations, by length alone
+fn length_match(l1: usize, l2: usize, ratio: f32) -> f32 {
+  if l1 == 0 && l2 == 0 {
+    return 1.0;
+  }
+  let mean = (l1 as f32 + l2 as f32 / ratio) / 2.0;
+  let delta = (l2 as f32 - l1 as f32 * ratio) / (mean.max(1.0) * LENGTH_VARIANCE).sqrt();
+  (2.0 * (1.0 - normal_cdf(delta.abs()))).max(1e-6)
+}
+
+// tokens that survive translation unchanged: numbers, URLs, e-mail addresses, identifiers
+fn anchors(text: &str) -> HashSet<String> {
+  text
+    .split(|c: char| c.is_whitespace() || matches!(c, ',' | ';' | '(' | ')' | '"' | '«' | '»' | '“' | '”'))
+    .map(|w| w.trim_matches(|c: char| matches!(c, '.' | ':' | '!' | '?' | '\'')))
+    .filter(|w| w.chars().any(|c| c.is_ascii_digit()) || w.contains("://") || w.contains('@') || w.contains('_'))
+    .map(|w| w.to_string())
+    .collect()
+}
+
+fn shared_anchors(source: &[HashSet<String>], target: &[HashSet<String>]) -> usize {
+  let a: HashSet<&String> = source.iter().flatten().collect();
+  let b: HashSet<&String> = target.iter().flatten().collect();
+  a.intersection(&b).count()
+}
+
+// one embedding per source sentence and one per target sentence
+pub type SentenceVectors<'a> = (&'a [Vec<f32>], &'a [Vec<f32>]);
+
+// cosine similarity of two runs of sentences, each pooled by summing its (unit length) vectors
+fn meaning_match(source: &[Vec<f32>], target: &[Vec<f32>]) -> f32 {
+  let pool = |vectors: &[Vec<f32>]| {
+    let mut sum = vec![0.0; vectors.first().map_or(0, |v| v.len())];
+    for v in vectors {
+      sum.iter_mut().zip(v).for_each(|(s, x)| *s += x);
+    }
+    sum
+  };
+  embed::similarity(&pool(source), &pool(target))
+}
+
+fn sentences(text: &str) -> Vec<String> {
+  text.split("\n\n").flat_map(chunk::sentences).map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect()
+}
+
+// Gale-Church style dynamic programming over sentence beads (1:1, 1:0, 0:1, 2:1, 1:2, 2:2), scored by
+// character lengths, shared anchors such as numbers and URLs and, given one vector per source and
+// target sentence, how close their meanings are; within a band around the diagonal
+pub fn align(source: &[String], target: &[String], embeddings: Option<SentenceVectors>) -> Vec<AlignedPair> {
+  let (n, m) = (source.len(), target.len());
+  if n == 0 || m == 0 {
+    return Vec::new();
+  }
+  let len = |s: &String| s.chars().count();
+  let ratio = (target.iter().map(len).sum::<usize>() as f32 / source.iter().map(len).sum::<usize>().max(1) as f32).max(0.1);
+  let embeddings = embeddings.filter(|(s, t)| s.len() == n && t.len() == m);
+  let meaning = |i: usize, di: usize, j: usize, dj: usize| embeddings.map(|(s, t)| meaning_match(&s[i - di..i], &t[j - dj..j]));
+  let source_anchors: Vec<HashSet<String>> = source.iter().map(|s| anchors(s)).collect();
+  let target_anchors: Vec<HashSet<String>> = target.iter().map(|s| anchors(s)).collect();
+  let band = (n.abs_diff(m) + 20).max(n.max(m) / 10);
+  // only cells near the diagonal are stored: row i covers target positions lo[i]..=hi[i]
+  let lo: Vec<usize> = (0..=n).map(|i| (i * m / n).saturating_sub(band)).collect();
+  let hi: Vec<usize> = (0..=n).map(|i| (i * m / n + band).min(m)).collect();
+  // cost: best cost of aligning the first i source and j target sentences; back: bead that got there
+  let mut cost: Vec<Vec<f32>> = (0..=n).map(|i| vec![f32::INFINITY; hi[i] - lo[i] + 1]).collect();
+  let mut back: Vec<Vec<u8>> = (0..=n).map(|i| vec![u8::MAX; hi[i] - lo[i] + 1]).collect();
+  let at = |cost: &[Vec<f32>], i: usize, j: usize| if j < lo[i] || j > hi[i] { f32::INFINITY } else { cost[i][j - lo[i]] };
+  cost[0][0] = 0.0;
+  for i in 0..=n {
+    for j in lo[i]..=hi[i] {
+      if (i, j) == (0, 0) {
+        continue;
+      }
+      for (b, &(di, dj, prior)) in BEADS.iter().enumerate() {
+        if di > i || dj > j {
+          continue;
+        }
+        let before = at(&cost, i - di, j - dj);
+        if before.is_infinite() {
+          continue;
+        }
+        let src: Vec<&String> = source[i - di..i].iter().collect();
+        let tgt: Vec<&String> = target[j - dj..j].iter().collect();
+        let l1: usize = src.iter().map(|s| len(s)).sum();
+        let l2: usize = tgt.iter().map(|s| len(s)).sum();
+        let mut c = -prior.ln() - length_match(l1, l2, ratio).ln();
+        if di > 0 && dj > 0 {
+          c -= ANCHOR_BONUS * shared_anchors(&source_anchors[i - di..i], &target_anchors[j - dj..j]) as f32;
+          // per sentence, so a merged bead and the 1:1 beads it could be split into weigh the same
+          c -= EMBEDDING_BONUS * meaning(i, di, j, dj).unwrap_or(0.0).max(0.0) * (di + dj) as f32 / 2.0;
+        }
+        if before + c < cost[i][j - lo[i]] {
+          cost[i][j - lo[i]] = before + c;
+          back[i][j - lo[i]] = b as u8;
+        }
+      }
+    }
+  }
+
+  let mut pairs = Vec::new();
+  let (mut i, mut j) = (n, m);
+  while (i, j) != (0, 0) && j >= lo[i] && j <= hi[i] && back[i][j - lo[i]] != u8::MAX {
+    let (di, dj, _) = BEADS[back[i][j - lo[i]] as usize];
+    if di > 0 && dj > 0 {
+      let src: Vec<&String> = source[i - di..i].iter().collect();
+      let tgt: Vec<&String> = target[j - dj..j].iter().collect();
+      let l1: usize = src.iter().map(|s| len(s)).sum();
+      let l2: usize = tgt.iter().map(|s| len(s)).sum();
+      let anchor_boost = 0.15 * shared_anchors(&source_anchors[i - di..i], &target_anchors[j - dj..j]) as f32;
+      let meaning_boost = 0.3 * meaning(i, di, j, dj).unwrap_or(0.0).max(0.0);
+      let merge_penalty = if di + dj > 2 { 0.8 } else { 1.0 };
+      pairs.push(AlignedPair {
+        source: src.iter().map(|s| s.as_str()).collect::<Vec<_>>().join(" "),
+        target: tgt.iter().map(|s| s.as_str()).collect::<Vec<_>>().join(" "),
+        source_sentences: di,
+        target_sentences: dj,
+        confidence: ((length_match(l1, l2, ratio) + anchor_boost + meaning_boost) * merge_penalty).min(1.0),
+      });
+    }
+    i -= di;
+    j -= dj;
+  }
+  pairs.reverse();
+  pairs
+}
+
+// ------------------ Tauri commands ------------------
+
+// align the sentences of a document and its translation and add the confident pairs to the
+// translation memory (origin "aligned", never over accepted entries); `import: false` only previews
+// the alignment. Sentences are matched by length (Gale & Church), shared anchors and embeddings
+// from `model_id` (else the loaded or default model) when it can embed
+#[tauri::command(async)]
+#[allow(clippy::too_many_arguments)]
+pub fn align_documents(
+  source_path: String,
+  target_path: String,
+  source_lang: String,
+  target_lang: String,
+  min_confidence: Option<f32>,
+  import: Option<bool>,
+  model_id: Option<String>,
+  tm: tauri::State<'_, Mutex<TranslationMemory>>,
+  models: tauri::State<'_, Mutex<ModelManager>>
+) -> Result<AlignmentReport, AppError> {
+  let read = |p: &str| fs::read_to_string(p).map_err(|e| format!("failed to read {}: {}", p, e));
+  let source = sentences(&read(&source_path)?);
+  let target = sentences(&read(&target_path)?);
+  if source.is_empty() || target.is_empty() {
+    return Err(AppError::InvalidInput("Both documents must contain text".into()));
+  }
+  // both sides in one run of the model; a multilingual model puts translations close together
+  let model = models.locked().model_for_request(model_id.as_deref());
+  let vectors = model.and_then(|(model, config)| embed::embed(&model.path, &config, &[source.clone(), target.clone()].concat()));
+  let (vectors, embedding_error) = match vectors {
+    Ok(v) => (Some(v), None),
+    Err(e) => (None, Some(e)),
+  };
+  let pairs = align(&source, &target, vectors.as_ref().map(|v| v.split_at(source.len())));
+  let aligned_source: usize = pairs.iter().map(|p| p.source_sentences).sum();
+  let aligned_target: usize = pairs.iter().map(|p| p.target_sentences).sum();
+
+  let mut imported = 0;
+  if import.unwrap_or(true) {
+    let min = min_confidence.unwrap_or(DEFAULT_MIN_CONFIDENCE);
+    let keep: Vec<(String, String)> = pairs.iter().filter(|p| p.confidence >= min).map(|p| (p.source.clone(), p.target.clone())).collect();
+    imported = tm.locked().import(&source_lang, &target_lang, &keep, "aligned")?;
+  }
+  Ok(AlignmentReport {
+    unmatched_source: source.len() - aligned_source,
+    unmatched_target: target.len() - aligned_target,
+    pairs,
+    imported,
+    embedding_error,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn strings(items: &[&str]) -> Vec<String> {
+    items.iter().map(|s| s.to_string()).collect()
+  }
+
+  #[test]
+  fn normal_cdf_is_accurate() {
+    assert!((normal_cdf(0.0) - 0.5).abs() < 1e-4);
+    assert!((normal_cdf(1.96) - 0.975).abs() < 1e-3);
+    assert!((normal_cdf(-1.0) - 0.1587).abs() < 1e-3);
+  }
+
+  #[test]
+  fn length_match_prefers_the_expected_ratio() {
+    assert!(length_match(100, 100, 1.0) > 0.9);
+    assert!(length_match(100, 120, 1.2) > 0.9);
+    assert!(length_match(100, 100, 1.0) > length_match(100, 150, 1.0));
+    assert!(length_match(100, 10, 1.0) < 0.01);
+    assert_eq!(length_match(0, 0, 1.0), 1.0);
+  }
+
+  #[test]
+  fn anchors_are_numbers_links_and_identifiers() {
+    let found = anchors("Call 555-0100, visit https://example.org or write to a@b.org about max_tokens. Plain words (none).");
+    let expected: HashSet<String> = ["555-0100", "https://example.org", "a@b.org", "max_tokens"].iter().map(|s| s.to_string()).collect();
+    assert_eq!(found, expected);
+  }
+
+  #[test]
+  fn aligns_one_to_one() {
+    let source = strings(&["The meeting starts at nine.", "Please bring your laptop.", "Lunch is provided for everyone."]);
+    let target = strings(&["Die Besprechung beginnt um neun.", "Bitte bringen Sie Ihren Laptop mit.", "Für alle gibt es ein Mittagessen."]);
+    let pairs = align(&source, &target, None);
+    assert_eq!(pairs.len(), 3);
+    for (pair, (s, t)) in pairs.iter().zip(source.iter().zip(&target)) {
+      assert_eq!((&pair.source, &pair.target), (s, t));
+      assert_eq!((pair.source_sentences, pair.target_sentences), (1, 1));
+    }
+  }
+
+  #[test]
+  fn merges_a_sentence_split_in_two() {
+    let source = strings(&["Short one.", "This sentence was translated as two separate sentences in the target text.", "Last one here."]);
+    let target = strings(&["Kurz einer.", "Dieser Satz wurde übersetzt.", "Und zwar als zwei getrennte Sätze im Zieltext.", "Letzter hier."]);
+    let pairs = align(&source, &target, None);
+    let merged = pairs.iter().find(|p| p.target_sentences == 2).expect("a 1:2 bead");
+    assert_eq!(merged.source, source[1]);
+    assert_eq!(merged.target, format!("{} {}", target[1], target[2]));
+    assert!(merged.confidence <= 1.0);
+  }
+
+  #[test]
+  fn anchors_pull_pairs_together() {
+    let source = strings(&["Version 2.4.1 was released.", "It fixes bug 1234."]);
+    let target = strings(&["Version 2.4.1 ist erschienen und bringt viele Verbesserungen.", "Fehler 1234 ist behoben."]);
+    let pairs = align(&source, &target, None);
+    assert_eq!(pairs.len(), 2);
+    assert!(pairs[1].target.contains("1234"));
+  }
+
+  #[test]
+  fn nothing_to_align() {
+    assert!(align(&[], &strings(&["a"]), None).is_empty());
+    assert!(align(&strings(&["a"]), &[], None).is_empty());
+  }
+
+  // two readings with the same lengths: [x y][z] or [x][y z]; the embeddings decide
+  #[test]
+  fn embeddings_decide_between_equally_long_merges() {
+    let source = strings(&["Twenty characters xx", "Twenty characters yy", "Twenty characters zz"]);
+    let target = strings(&["Thirty characters long, aaaaa", "Thirty characters long, bbbbb"]);
+    let (a, b) = (vec![1.0, 0.0], vec![0.0, 1.0]);
+    let first = |pairs: &[AlignedPair]| (pairs[0].source_sentences, pairs[0].target_sentences);
+
+    let xy_z = [a.clone(), a.clone(), b.clone()];
+    let pairs = align(&source, &target, Some((&xy_z, &[a.clone(), b.clone()])));
+    assert_eq!(first(&pairs), (2, 1), "{:?}", pairs);
+
+    let x_yz = [a.clone(), b.clone(), b.clone()];
+    let pairs = align(&source, &target, Some((&x_yz, &[a.clone(), b.clone()])));
+    assert_eq!(first(&pairs), (1, 1), "{:?}", pairs);
+    assert_eq!(pairs[1].source_sentences, 2, "{:?}", pairs);
+  }
+
+  #[test]
+  fn close_embeddings_raise_confidence() {
+    let source = strings(&["A sentence of some length here.", "Another one that is longer than that."]);
+    let target = strings(&["Ein Satz von einiger Länge hier.", "Noch einer, der länger ist als jener."]);
+    let plain = align(&source, &target, None);
+    let same = [vec![1.0, 0.0], vec![0.0, 1.0]];
+    let embedded = align(&source, &target, Some((&same, &same)));
+    assert_eq!(plain.len(), embedded.len());
+    assert!(plain.iter().zip(&embedded).all(|(p, e)| e.confidence >= p.confidence), "{:?} {:?}", plain, embedded);
+    // vectors that don't match the sentences are ignored
+    let wrong = align(&source, &target, Some((&same[..1], &same)));
+    assert_eq!(wrong.iter().map(|p| p.confidence).collect::<Vec<_>>(), plain.iter().map(|p| p.confidence).collect::<Vec<_>>());
+  }
+}
@@ -0,0 +1,298 @@
+// src-tauri/src/annotate.rs
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use std::thread;
+
+use crate::error::AppError;
+use crate::runtime;
+use crate::settings;
+
+// CC-CEDICT / CC-Canto files (*.u8, *.txt) and a MeCab dictionary (UniDic or IPADIC, with its dicrc)
+// live in <models dir>/dict/zh and <models dir>/dict/ja
+fn dict_dir(lang: &str) -> PathBuf {
+  settings::models_dir().join("dict").join(lang)
+}
+
+// longest dictionary word tried when segmenting Chinese
+const MAX_WORD_CHARS: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReadingSystem {
+  Furigana,
+  Pinyin,
+  Jyutping,
+}
+
+// One piece of the input; joining every `text` reproduces the input exactly
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct AnnotatedToken {
+  pub text: String,
+  // None for kana, latin, punctuation and words missing from the dictionary
+  pub reading: Option<String>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct Annotation {
+  pub system: ReadingSystem,
+  pub tokens: Vec<AnnotatedToken>,
+}
+
+// readings for one headword (traditional and simplified forms share an entry)
+#[derive(Default)]
+struct ZhEntry {
+  pinyin: Option<String>,
+  jyutping: Option<String>,
+}
+
+// loaded on first use; restart the app after adding dictionaries
+fn zh_dict() -> &'static HashMap<String, ZhEntry> {
+  static DICT: OnceLock<HashMap<String, ZhEntry>> = OnceLock::new();
+  DICT.get_or_init(|| {
+    let mut dict: HashMap<String, ZhEntry> = HashMap::new();
+    let mut files: Vec<PathBuf> = fs::read_dir(dict_dir("zh")).map(|rd| rd.flatten().map(|e| e.path()).collect()).unwrap_or_default();
+    files.sort();
+    for file in files.iter().filter(|p| p.extension().is_some_and(|x| x == "u8" || x == "txt")) {
+      let Ok(text) = fs::read_to_string(file) else { continue };
+      for line in text.lines().filter(|l| !l.starts_with('#')) {
+        // "傳統 传统 [chuan2 tong3] {cyun4 tung2} /tradition/" (the {jyutping} part only in CC-Canto)
+        let mut words = line.splitn(3, ' ');
+        let (Some(trad), Some(simp), Some(rest)) = (words.next(), words.next(), words.next()) else { continue };
+        let between = |open: char, close: char| rest.split_once(open).and_then(|(_, r)| r.split_once(close)).map(|(v, _)| v.trim().to_string());
+        let pinyin = between('[', ']');
+        let jyutping = between('{', '}');
+        for word in [trad, simp] {
+          let entry = dict.entry(word.to_string()).or_default();
+          // first definition wins, like CEDICT's most common reading ordering
+          if entry.pinyin.is_none() {
+            entry.pinyin = pinyin.clone();
+          }
+          if entry.jyutping.is_none() {
+            entry.jyutping = jyutping.clone();
+          }
+        }
+      }
+    }
+    dict
+  })
+}
+
+fn is_han(c: char) -> bool {
+  matches!(c as u32, 0x4E00..=0x9FFF | 0x3400..=0x4DBF | 0xF900..=0xFAFF)
+}
+
+// "zhong1" -> "zhōng", "lu:4" -> "lǜ"; tone 5 (neutral) and unknown syllables stay unmarked
+fn pinyin_syllable(syllable: &str) -> String {
+  let (body, tone) = match syllable.chars().last().and_then(|c| c.to_digit(10)) {
+    Some(t) => (&syllable[..syllable.len() - 1], t as usize),
+    None => (syllable, 5),
+  };
+  let body = body.replace("u:", "ü").replace("v", "ü").replace("U:", "Ü");
+  if !(1..=4).contains(&tone) {
+    return body;
+  }
+  const MARKS: [(char, [char; 4]); 12] = [
+    ('a', ['ā', 'á', 'ǎ', 'à']),
+    ('e', ['ē', 'é', 'ě', 'è']),
+    ('i', ['ī', 'í', 'ǐ', 'ì']),
+    ('o', ['ō', 'ó', 'ǒ', 'ò']),
+    ('u', ['ū', 'ú', 'ǔ', 'ù']),
+    ('ü', ['ǖ', 'ǘ', 'ǚ', 'ǜ']),
+    ('A', ['Ā', 'Á', 'Ǎ', 'À']),
+    ('E', ['Ē', 'É', 'Ě', 'È']),
+    ('I', ['Ī', 'Í', 'Ǐ', 'Ì']),
+    ('O', ['Ō', 'Ó', 'Ǒ', 'Ò']),
+    ('U', ['Ū', 'Ú', 'Ǔ', 'Ù']),
+    ('Ü', ['Ǖ', 'Ǘ', 'Ǚ', 'Ǜ']),
+  ];
+  let chars: Vec<char> = body.chars().collect();
+  let is_vowel = |c: char| MARKS.iter().any(|(v, _)| *v == c);
+  let lower: String = body.to_lowercase();
+  // a and e always take the mark, "ou" marks the o, otherwise the last vowel
+  let target = if let Some(i) = lower.chars().position(|c| c == 'a' || c == 'e') {
+    Some(i)
+  } else if let Some(i) = lower.find("ou") {
+    Some(lower[..i].chars().count())
+  } else {
+    chars.iter().rposition(|c| is_vowel(*c))
+  };
+  chars
+    .iter()
+    .enumerate()
+    .map(|(i, c)| match (Some(i) == target, MARKS.iter().find(|(v, _)| v == c)) {
+      (true, Some((_, marks))) => marks[tone - 1],
+      _ => *c,
+    })
+    .collect()
+}
+
+fn annotate_chinese(text: &str, system: ReadingSystem) -> Result<Vec<AnnotatedToken>, String> {
+  let dict = zh_dict();
+  if dict.is_empty() {
+    return Err(format!("No Chinese dictionary (CC-CEDICT) in {}", dict_dir("zh").to_string_lossy()));
+  }
+  let reading_of = |entry: &ZhEntry| match system {
+    ReadingSystem::Jyutping => entry.jyutping.clone(),
+    _ => entry.pinyin.clone(),
+  };
+  let chars: Vec<char> = text.chars().collect();
+  let mut tokens: Vec<AnnotatedToken> = Vec::new();
+  let mut i = 0;
+  while i < chars.len() {
+    if !is_han(chars[i]) {
+      // merge runs of non-Han text into one unannotated token
+      let start = i;
+      while i < chars.len() && !is_han(chars[i]) {
+        i += 1;
+      }
+      tokens.push(AnnotatedToken { text: chars[start..i].iter().collect(), reading: None });
+      continue;
+    }
+    let longest = (1..=MAX_WORD_CHARS.min(chars.len() - i)).rev().find_map(|n| {
+      let word: String = chars[i..i + n].iter().collect();
+      dict.get(&word).and_then(reading_of).map(|r| (n, word, r))
+    });
+    match longest {
+      Some((n, word, reading)) => {
+        let syllables: Vec<String> = reading
+          .split_whitespace()
+          .map(|s| if system == ReadingSystem::Pinyin { pinyin_syllable(s) } else { s.to_string() })
+          .collect();
+        // one reading per character when they line up, so ruby sits over each character
+        if syllables.len() == n {
+          for (c, s) in chars[i..i + n].iter().zip(syllables) {
+            tokens.push(AnnotatedToken { text: c.to_string(), reading: Some(s) });
+          }
+        } else {
+          tokens.push(AnnotatedToken { text: word, reading: Some(syllables.join(" ")) });
+        }
+        i += n;
+      }
+      None => {
+        tokens.push(AnnotatedToken { text: chars[i].to_string(), reading: None });
+        i += 1;
+      }
+    }
+  }
+  Ok(tokens)
+}
+
+fn katakana_to_hiragana(s: &str) -> String {
+  s.chars().map(|c| if ('\u{30A1}'..='\u{30F6}').contains(&c) { char::from_u32(c as u32 - 0x60).unwrap_or(c) } else { c }).collect()
+}
+
+// which feature holds the surface reading: UniDic 2.2+/3.x "kana" (29 fields), older UniDic "pron"
+// (17/26 fields), IPADIC "reading" (9 fields)
+fn reading_field(features: &[&str]) -> Option<usize> {
+  match features.len() {
+    n if n >= 29 => Some(20),
+    n if n >= 17 => Some(9),
+    n if n >= 8 => Some(7),
+    _ => None,
+  }
+}
+
+// put the reading only over the kanji: "食べる"/"たべる" -> ("食", "た") + "べる"
+fn split_okurigana(surface: &str, reading: &str) -> Vec<AnnotatedToken> {
+  let s: Vec<char> = surface.chars().collect();
+  let r: Vec<char> = reading.chars().collect();
+  let prefix = s.iter().zip(&r).take_while(|(a, b)| a == b).count();
+  let suffix = s[prefix..].iter().rev().zip(r[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+  let mut tokens = Vec::new();
+  let piece = |c: &[char]| c.iter().collect::<String>();
+  if prefix > 0 {
+    tokens.push(AnnotatedToken { text: piece(&s[..prefix]), reading: None });
+  }
+  let core = piece(&s[prefix..s.len() - suffix]);
+  let core_reading = piece(&r[prefix..r.len() - suffix]);
+  if !core.is_empty() {
+    tokens.push(AnnotatedToken { text: core, reading: (!core_reading.is_empty()).then_some(core_reading) });
+  }
+  if suffix > 0 {
+    tokens.push(AnnotatedToken { text: piece(&s[s.len() - suffix..]), reading: None });
+  }
+  tokens
+}
+
+// morphological analysis with MeCab; readings only on tokens that contain kanji
+fn annotate_japanese(text: &str) -> Result<Vec<AnnotatedToken>, String> {
+  let exe = runtime::bundled_tool("mecab").ok_or("mecab not found in ./src-tauri/bin")?;
+  let mut c = Command::new(exe);
+  let ja_dict = dict_dir("ja");
+  if ja_dict.join("dicrc").is_file() {
+    c.arg("-d").arg(ja_dict);
+  }
+  let mut child = c
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .map_err(|e| format!("failed to run mecab: {}", e))?;
+  // written from a thread: mecab answers as it reads, and with both pipes full neither side moves
+  let writer = child.stdin.take().map(|mut stdin| {
+    let input = text.to_string();
+    thread::spawn(move || stdin.write_all(input.as_bytes()))
+  });
+  let out = child.wait_with_output().map_err(|e| format!("mecab failed: {}", e))?;
+  if let Some(Ok(Err(e))) = writer.map(|w| w.join()) {
+    return Err(format!("failed to write to mecab: {}", e));
+  }
+  if !out.status.success() {
+    return Err(format!("mecab failed: {}", String::from_utf8_lossy(&out.stderr).trim()));
+  }
+
+  // mecab drops whitespace and line breaks, so walk the input to keep them
+  let mut tokens = Vec::new();
+  let mut rest = text;
+  for line in String::from_utf8_lossy(&out.stdout).lines() {
+    let Some((surface, features)) = line.split_once('\t') else { continue };
+    let Some(at) = rest.find(surface) else { continue };
+    if at > 0 {
+      tokens.push(AnnotatedToken { text: rest[..at].to_string(), reading: None });
+    }
+    rest = &rest[at + surface.len()..];
+    let features: Vec<&str> = features.split(',').collect();
+    let reading = reading_field(&features).and_then(|i| features.get(i)).filter(|r| !r.is_empty() && **r != "*");
+    match reading {
+      Some(r) if surface.chars().any(is_han) => tokens.extend(split_okurigana(surface, &katakana_to_hiragana(r))),
+      _ => tokens.push(AnnotatedToken { text: surface.to_string(), reading: None }),
+    }
+  }
+  if !rest.is_empty() {
+    tokens.push(AnnotatedToken { text: rest.to_string(), reading: None });
+  }
+  Ok(tokens)
+}
+
+// furigana for Japanese, jyutping for Cantonese, pinyin for other Chinese
+pub fn default_system(lang: &str) -> Option<ReadingSystem> {
+  let lower = lang.to_lowercase().replace('_', "-");
+  match lower.split('-').next().unwrap_or("") {
+    "ja" => Some(ReadingSystem::Furigana),
+    "yue" => Some(ReadingSystem::Jyutping),
+    "zh" if lower.ends_with("-hk") || lower.ends_with("-mo") || lower.contains("yue") => Some(ReadingSystem::Jyutping),
+    "zh" => Some(ReadingSystem::Pinyin),
+    _ => None,
+  }
+}
+
+pub fn annotate(text: &str, system: ReadingSystem) -> Result<Annotation, String> {
+  let tokens = match system {
+    ReadingSystem::Furigana => annotate_japanese(text)?,
+    _ => annotate_chinese(text, system)?,
+  };
+  Ok(Annotation { system, tokens })
+}
+
+// ------------------ Tauri commands ------------------
+
+// per-token readings for Japanese or Chinese text; `system` overrides the language default
+#[tauri::command(async)]
+pub fn annotate_text(text: String, lang: String, system: Option<ReadingSystem>) -> Result<Annotation, AppError> {
+  let system = system.or(default_system(&lang)).ok_or(format!("No reading system for '{}' (use ja, zh or yue)", lang))?;
+  Ok(annotate(&text, system)?)
+}
@@ -0,0 +1,111 @@
+// src-tauri/src/ansi.rs
+
+const ESC: char = '\u{1b}';
+const BEL: char = '\u{7}';
+
+// drop one escape sequence whose ESC was just read: CSI (colors, cursor moves), OSC (window titles,
+// hyperlinks) or a two-character sequence
+fn skip_escape(chars: &mut std::iter::Peekable<std::str::Chars>) {
+  match chars.next() {
+    // CSI: parameters and intermediates up to a final byte in @..~
+    Some('[') => {
+      for c in chars.by_ref() {
+        if ('@'..='~').contains(&c) {
+          break;
+        }
+      }
+    }
+    // OSC: up to BEL or ESC \
+    Some(']') => {
+      while let Some(c) = chars.next() {
+        if c == BEL {
+          break;
+        }
+        if c == ESC {
+          if chars.peek() == Some(&'\\') {
+            chars.next();
+          }
+          break;
+        }
+      }
+    }
+    // charset selection and the like take one more character
+    Some('(' | ')' | '#' | '%') => {
+      chars.next();
+    }
+    _ => {}
+  }
+}
+
+// text as it would end up on a terminal, minus the styling: escape sequences removed, a carriage
+// return starts the line over (spinners, progress bars), backspace erases, other controls dropped
+pub fn strip(line: &str) -> String {
+  if !has_controls(line) {
+    return line.to_string();
+  }
+  let mut out = String::with_capacity(line.len());
+  let mut chars = line.chars().peekable();
+  while let Some(c) = chars.next() {
+    match c {
+      ESC => skip_escape(&mut chars),
+      // a trailing \r (CRLF output) keeps the line
+      '\r' if chars.peek().is_some() => out.clear(),
+      '\u{8}' => {
+        out.pop();
+      }
+      '\t' | '\n' => out.push(c),
+      c if c.is_control() => {}
+      c => out.push(c),
+    }
+  }
+  out
+}
+
+// whether the line carries anything strip() would change
+pub fn has_controls(line: &str) -> bool {
+  line.chars().any(|c| c.is_control() && c != '\t')
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn plain_text_is_untouched() {
+    assert_eq!(strip("Hola\tmundo"), "Hola\tmundo");
+    assert!(!has_controls("Hola\tmundo"));
+  }
+
+  #[test]
+  fn removes_colors_and_cursor_moves() {
+    assert_eq!(strip("\u{1b}[1;32mok\u{1b}[0m done"), "ok done");
+    assert_eq!(strip("\u{1b}[2K\u{1b}[1Gloading"), "loading");
+    assert_eq!(strip("\u{1b}(Bx"), "x");
+  }
+
+  #[test]
+  fn removes_titles_and_hyperlinks() {
+    assert_eq!(strip("\u{1b}]0;title\u{7}text"), "text");
+    assert_eq!(strip("\u{1b}]8;;https://a.org\u{1b}\\link\u{1b}]8;;\u{1b}\\"), "link");
+  }
+
+  #[test]
+  fn carriage_return_starts_over() {
+    assert_eq!(strip("10%\r50%\r100%"), "100%");
+    // CRLF line endings keep the line
+    assert_eq!(strip("done\r"), "done");
+  }
+
+  #[test]
+  fn backspace_erases() {
+    assert_eq!(strip("ab\u{8}c"), "ac");
+    assert_eq!(strip("\u{8}x"), "x");
+  }
+
+  #[test]
+  fn other_controls_are_dropped() {
+    assert_eq!(strip("a\u{7}b\u{0}c"), "abc");
+    // a lone ESC at the end
+    assert_eq!(strip("end\u{1b}"), "end");
+  }
+}
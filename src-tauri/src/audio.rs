@@ -0,0 +1,416 @@
+// src-tauri/src/audio.rs
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SampleFormat, SizedSample};
+use tauri::{Emitter, Manager, Window};
+
+use crate::error::{AppError, LockExt};
+use crate::events;
+use crate::runtime;
+use crate::speech;
+use crate::storage;
+use crate::ModelManager;
+
+// whisper wants 16 kHz mono
+const WHISPER_RATE: u32 = 16_000;
+// re-transcribe the open utterance once this much new audio came in
+const PARTIAL_EVERY_MS: u64 = 2_000;
+// past this an utterance is closed at the next pause, and at the latest at UTTERANCE_MAX_MS, since
+// whisper only looks at 30 seconds at a time
+const UTTERANCE_SOFT_MS: u64 = 15_000;
+const UTTERANCE_MAX_MS: u64 = 28_000;
+// a 200 ms stretch quieter than this counts as a pause
+const PAUSE_RMS: f32 = 0.01;
+const PAUSE_MS: u64 = 200;
+
+// Payload of "listening-output" events: one per partial pass, then a final one once stopped
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ListeningEvent {
+  pub listen_id: u64,
+  // everything heard so far
+  pub text: String,
+  // the tail of `text` that is still being revised as more audio comes in
+  pub pending: String,
+  pub is_final: bool,
+  pub lang: Option<String>,
+  pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct InputDevice {
+  pub name: String,
+  pub is_default: bool,
+}
+
+// Microphone captures in progress (managed by Tauri); each maps to its stop flag
+pub struct Listeners {
+  active: HashMap<u64, Arc<AtomicBool>>,
+  counter: u64,
+}
+
+impl Listeners {
+  pub fn new() -> Self {
+    Self { active: HashMap::new(), counter: 0 }
+  }
+}
+
+// Mono samples at the device rate, appended by the capture callback
+struct Recording {
+  samples: Vec<f32>,
+  rate: u32,
+}
+
+fn device_name(device: &cpal::Device) -> Option<String> {
+  device.description().ok().map(|d| d.name().to_string())
+}
+
+// the input device called `name`, or the system default
+fn input_device(name: Option<&str>) -> Result<cpal::Device, String> {
+  let host = cpal::default_host();
+  match name {
+    Some(name) => host
+      .input_devices()
+      .map_err(|e| format!("can't list input devices: {}", e))?
+      .find(|d| device_name(d).as_deref() == Some(name))
+      .ok_or(format!("Input device '{}' not found", name)),
+    None => host.default_input_device().ok_or("No microphone found".into()),
+  }
+}
+
+// downmix each frame to mono and append it
+fn push_frames<T>(recording: &Mutex<Recording>, data: &[T], channels: usize)
+where
+  T: SizedSample,
+  f32: FromSample<T>,
+{
+  let mut rec = recording.locked();
+  rec.samples.extend(data.chunks(channels).map(|frame| frame.iter().map(|s| s.to_sample::<f32>()).sum::<f32>() / frame.len() as f32));
+}
+
+fn input_stream<T>(device: &cpal::Device, config: cpal::StreamConfig, recording: Arc<Mutex<Recording>>) -> Result<cpal::Stream, String>
+where
+  T: SizedSample,
+  f32: FromSample<T>,
+{
+  let channels = config.channels.max(1) as usize;
+  device
+    .build_input_stream::<T, _, _>(config, move |data, _| push_frames(&recording, data, channels), |e| events::log(format!("[audio] input stream error: {}", e)), None)
+    .map_err(|e| format!("can't open the microphone: {}", e))
+}
+
+// open the device and record into `recording` until `stop` is set. The stream stays on this thread
+// since it can't move across threads on every platform; `ready` reports whether it started
+fn capture(device: Option<String>, recording: Arc<Mutex<Recording>>, stop: Arc<AtomicBool>, ready: mpsc::Sender<Result<(), String>>) {
+  let opened = (|| {
+    let device = input_device(device.as_deref())?;
+    let supported = device.default_input_config().map_err(|e| format!("can't read the microphone's format: {}", e))?;
+    recording.locked().rate = supported.sample_rate();
+    let config = supported.config();
+    let stream = match supported.sample_format() {
+      SampleFormat::F32 => input_stream::<f32>(&device, config, recording.clone()),
+      SampleFormat::I16 => input_stream::<i16>(&device, config, recording.clone()),
+      SampleFormat::U16 => input_stream::<u16>(&device, config, recording.clone()),
+      SampleFormat::I32 => input_stream::<i32>(&device, config, recording.clone()),
+      SampleFormat::U8 => input_stream::<u8>(&device, config, recording.clone()),
+      other => Err(format!("Unsupported microphone sample format {:?}", other)),
+    }?;
+    stream.play().map_err(|e| format!("can't start the microphone: {}", e))?;
+    Ok(stream)
+  })();
+  match opened {
+    Ok(stream) => {
+      let _ = ready.send(Ok(()));
+      while !stop.load(Ordering::Relaxed) {
+        thread::sleep(Duration::from_millis(50));
+      }
+      drop(stream);
+    }
+    Err(e) => {
+      let _ = ready.send(Err(e));
+    }
+  }
+}
+
+// linear resampling from `from` Hz to `to` Hz
+fn resample(samples: &[f32], from: u32, to: u32) -> Vec<f32> {
+  if from == to || samples.is_empty() {
+    return samples.to_vec();
+  }
+  let len = (samples.len() as u64 * to as u64 / from as u64) as usize;
+  let step = from as f64 / to as f64;
+  (0..len)
+    .map(|i| {
+      let pos = i as f64 * step;
+      let j = pos as usize;
+      let frac = (pos - j as f64) as f32;
+      let a = samples[j.min(samples.len() - 1)];
+      let b = samples[(j + 1).min(samples.len() - 1)];
+      a + (b - a) * frac
+    })
+    .collect()
+}
+
+// 16-bit PCM mono WAV at 16 kHz, the format whisper-cli reads
+fn write_wav(path: &Path, samples: &[f32], rate: u32) -> Result<(), String> {
+  let pcm = resample(samples, rate, WHISPER_RATE);
+  let data_len = (pcm.len() * 2) as u32;
+  let mut bytes = Vec::with_capacity(44 + data_len as usize);
+  bytes.extend_from_slice(b"RIFF");
+  bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+  bytes.extend_from_slice(b"WAVEfmt ");
+  bytes.extend_from_slice(&16u32.to_le_bytes());
+  bytes.extend_from_slice(&1u16.to_le_bytes()); // PCM
+  bytes.extend_from_slice(&1u16.to_le_bytes()); // mono
+  bytes.extend_from_slice(&WHISPER_RATE.to_le_bytes());
+  bytes.extend_from_slice(&(WHISPER_RATE * 2).to_le_bytes());
+  bytes.extend_from_slice(&2u16.to_le_bytes());
+  bytes.extend_from_slice(&16u16.to_le_bytes());
+  bytes.extend_from_slice(b"data");
+  bytes.extend_from_slice(&data_len.to_le_bytes());
+  for s in pcm {
+    bytes.extend_from_slice(&((s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).to_le_bytes());
+  }
+  fs::write(path, bytes).map_err(|e| format!("failed to write {}: {}", path.to_string_lossy(), e))
+}
+
+// samples of a PCM (16-bit) or float (32-bit) WAV, downmixed to mono, and its sample rate
+pub(crate) fn read_wav(path: &Path) -> Result<(Vec<f32>, u32), String> {
+  let bytes = fs::read(path).map_err(|e| format!("failed to read {}: {}", path.to_string_lossy(), e))?;
+  let invalid = || format!("{} is not a WAV file we can play", path.to_string_lossy());
+  if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+    return Err(invalid());
+  }
+  let u16_at = |i: usize| u16::from_le_bytes([bytes[i], bytes[i + 1]]);
+  let u32_at = |i: usize| u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+  // (format, channels, rate, bits) from the "fmt " chunk
+  let mut format = None;
+  let mut pos = 12;
+  while pos + 8 <= bytes.len() {
+    let size = u32_at(pos + 4) as usize;
+    let body = pos + 8;
+    let end = (body + size).min(bytes.len());
+    match &bytes[pos..pos + 4] {
+      b"fmt " if size >= 16 && end >= body + 16 => format = Some((u16_at(body), u16_at(body + 2).max(1) as usize, u32_at(body + 4), u16_at(body + 14))),
+      b"data" => {
+        let (tag, channels, rate, bits) = format.ok_or_else(invalid)?;
+        let data = &bytes[body..end];
+        let samples: Vec<f32> = match (tag, bits) {
+          (1, 16) => data.chunks_exact(2).map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / i16::MAX as f32).collect(),
+          (3, 32) => data.chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect(),
+          _ => return Err(invalid()),
+        };
+        let mono = samples.chunks(channels).map(|frame| frame.iter().sum::<f32>() / frame.len() as f32).collect();
+        return Ok((mono, rate));
+      }
+      _ => {}
+    }
+    // chunks are padded to an even size
+    pos = body + size + (size & 1);
+  }
+  Err(invalid())
+}
+
+fn output_stream<T>(device: &cpal::Device, config: cpal::StreamConfig, samples: Vec<f32>, finished: Arc<AtomicBool>) -> Result<cpal::Stream, String>
+where
+  T: SizedSample + FromSample<f32>,
+{
+  let channels = config.channels.max(1) as usize;
+  let mut next = 0;
+  device
+    .build_output_stream::<T, _, _>(
+      config,
+      move |data: &mut [T], _| {
+        for frame in data.chunks_mut(channels) {
+          let s = samples.get(next).copied().unwrap_or(0.0);
+          next += 1;
+          frame.fill(T::from_sample(s));
+        }
+        if next >= samples.len() {
+          finished.store(true, Ordering::Relaxed);
+        }
+      },
+      |e| events::log(format!("[audio] output stream error: {}", e)),
+      None,
+    )
+    .map_err(|e| format!("can't open the speakers: {}", e))
+}
+
+// play mono `samples` on the default output device, returning once they are done or `stop` is set
+pub(crate) fn play(samples: &[f32], rate: u32, stop: &AtomicBool) -> Result<(), String> {
+  let device = cpal::default_host().default_output_device().ok_or("No audio output device found")?;
+  let supported = device.default_output_config().map_err(|e| format!("can't read the speakers' format: {}", e))?;
+  let samples = resample(samples, rate, supported.sample_rate());
+  let duration = Duration::from_millis(samples.len() as u64 * 1000 / supported.sample_rate().max(1) as u64);
+  let finished = Arc::new(AtomicBool::new(false));
+  let config = supported.config();
+  let stream = match supported.sample_format() {
+    SampleFormat::F32 => output_stream::<f32>(&device, config, samples, finished.clone()),
+    SampleFormat::I16 => output_stream::<i16>(&device, config, samples, finished.clone()),
+    SampleFormat::U16 => output_stream::<u16>(&device, config, samples, finished.clone()),
+    SampleFormat::I32 => output_stream::<i32>(&device, config, samples, finished.clone()),
+    SampleFormat::U8 => output_stream::<u8>(&device, config, samples, finished.clone()),
+    other => Err(format!("Unsupported speaker sample format {:?}", other)),
+  }?;
+  stream.play().map_err(|e| format!("can't start playback: {}", e))?;
+  // the callback runs ahead of what is heard by a buffer or so; the duration check covers devices
+  // that stop calling back
+  let started = Instant::now();
+  while !finished.load(Ordering::Relaxed) && !stop.load(Ordering::Relaxed) && started.elapsed() < duration + Duration::from_secs(2) {
+    thread::sleep(Duration::from_millis(20));
+  }
+  if !stop.load(Ordering::Relaxed) {
+    // let the last buffer drain
+    thread::sleep(Duration::from_millis(150));
+  }
+  Ok(())
+}
+
+fn ms_to_samples(ms: u64, rate: u32) -> usize {
+  (ms * rate as u64 / 1000) as usize
+}
+
+// where to close an utterance of `samples`: the end of the first pause after UTTERANCE_SOFT_MS, or
+// UTTERANCE_MAX_MS when there was none. None while the utterance can still grow
+fn utterance_cut(samples: &[f32], rate: u32) -> Option<usize> {
+  let soft = ms_to_samples(UTTERANCE_SOFT_MS, rate);
+  let max = ms_to_samples(UTTERANCE_MAX_MS, rate);
+  if samples.len() < soft {
+    return None;
+  }
+  let window = ms_to_samples(PAUSE_MS, rate).max(1);
+  let pause = samples[soft..samples.len().min(max)]
+    .chunks(window)
+    .enumerate()
+    .find(|(_, w)| w.len() == window && (w.iter().map(|s| s * s).sum::<f32>() / w.len() as f32).sqrt() < PAUSE_RMS)
+    .map(|(i, w)| soft + i * window + w.len());
+  pause.or((samples.len() >= max).then_some(max))
+}
+
+// join finished utterances and the open one into the running text
+fn joined(done: &[String], pending: &str) -> String {
+  done.iter().map(|s| s.as_str()).chain(Some(pending)).filter(|s| !s.is_empty()).collect::<Vec<_>>().join(" ")
+}
+
+// re-transcribe the open utterance whenever PARTIAL_EVERY_MS of audio came in, closing it at pauses,
+// until `stop` is set or a pass fails; then transcribe what is left and send the final event. The
+// model stays loaded in a whisper-server when one is bundled, else each pass runs whisper-cli
+fn transcribe_live(window: Window, id: u64, exe: PathBuf, model: PathBuf, lang: Option<String>, recording: Arc<Mutex<Recording>>, stop: Arc<AtomicBool>) {
+  let wav = storage::scratch_dir().join(format!("multilingual-listen-{}.wav", id));
+  let server = speech::WhisperServer::start(&model).map_err(|e| events::log(format!("live transcription runs whisper-cli per pass: {}", e))).ok();
+  let mut done: Vec<String> = Vec::new();
+  let mut detected: Option<String> = None;
+  let mut transcribed = 0;
+  let mut error = None;
+  loop {
+    let stopping = stop.load(Ordering::Relaxed);
+    let (samples, rate) = {
+      let rec = recording.locked();
+      (rec.samples.clone(), rec.rate)
+    };
+    let cut = utterance_cut(&samples, rate);
+    let due = samples.len() >= transcribed + ms_to_samples(PARTIAL_EVERY_MS, rate);
+    if (due || cut.is_some() || stopping) && !samples.is_empty() {
+      let end = if stopping { samples.len() } else { cut.unwrap_or(samples.len()) };
+      let pass = write_wav(&wav, &samples[..end], rate).and_then(|_| match &server {
+        Some(server) => server.transcribe(&wav, lang.as_deref()),
+        None => speech::transcribe_file(&exe, &model, &wav.to_string_lossy(), lang.as_deref()),
+      });
+      match pass {
+        Ok(t) => {
+          detected = detected.or(t.lang);
+          if cut.is_some() || stopping {
+            // close the utterance: its text is settled and its audio no longer needed
+            recording.locked().samples.drain(..end);
+            if !t.text.is_empty() {
+              done.push(t.text);
+            }
+            transcribed = 0;
+            if !stopping {
+              let event = ListeningEvent { listen_id: id, text: joined(&done, ""), pending: String::new(), is_final: false, lang: detected.clone(), error: None };
+              let _ = window.emit("listening-output", event);
+            }
+          } else {
+            transcribed = end;
+            let event = ListeningEvent { listen_id: id, text: joined(&done, &t.text), pending: t.text, is_final: false, lang: detected.clone(), error: None };
+            let _ = window.emit("listening-output", event);
+          }
+        }
+        Err(e) => {
+          error = Some(e);
+          stop.store(true, Ordering::Relaxed);
+          break;
+        }
+      }
+    }
+    if stopping {
+      break;
+    }
+    thread::sleep(Duration::from_millis(250));
+  }
+  drop(server);
+  // gone already after stop_listening; not after a failed pass
+  window.state::<Mutex<Listeners>>().locked().active.remove(&id);
+  let _ = fs::remove_file(&wav);
+  let event = ListeningEvent { listen_id: id, text: joined(&done, ""), pending: String::new(), is_final: true, lang: detected, error };
+  let _ = window.emit("listening-output", event);
+}
+
+// ------------------ Tauri commands ------------------
+
+#[tauri::command]
+pub fn list_input_devices() -> Result<Vec<InputDevice>, AppError> {
+  let host = cpal::default_host();
+  let default = host.default_input_device().and_then(|d| device_name(&d));
+  let devices = host.input_devices().map_err(|e| format!("can't list input devices: {}", e))?;
+  Ok(devices.filter_map(|d| device_name(&d)).map(|name| InputDevice { is_default: default.as_deref() == Some(name.as_str()), name }).collect())
+}
+
+// record from the microphone (`device` by name, else the default) and transcribe it live with
+// whisper. `model_id` picks the whisper model as in transcribe_audio. Returns an id right away;
+// partial transcripts follow as "listening-output" events until stop_listening
+#[tauri::command]
+pub fn start_listening(
+  language: Option<String>,
+  model_id: Option<String>,
+  device: Option<String>,
+  window: Window,
+  listeners: tauri::State<'_, Mutex<Listeners>>,
+  state: tauri::State<'_, Mutex<ModelManager>>
+) -> Result<u64, AppError> {
+  let exe = runtime::bundled_tool("whisper-cli").ok_or("whisper-cli not found in ./src-tauri/bin")?;
+  let model = speech::whisper_model(&state.locked(), model_id.as_deref())?;
+  let recording = Arc::new(Mutex::new(Recording { samples: Vec::new(), rate: WHISPER_RATE }));
+  let stop = Arc::new(AtomicBool::new(false));
+  let (ready_tx, ready_rx) = mpsc::channel();
+  {
+    let (recording, stop) = (recording.clone(), stop.clone());
+    thread::spawn(move || capture(device, recording, stop, ready_tx));
+  }
+  ready_rx.recv().map_err(|_| "The microphone thread stopped unexpectedly".to_string())??;
+  let id = {
+    let mut listeners = listeners.locked();
+    listeners.counter += 1;
+    let id = listeners.counter;
+    listeners.active.insert(id, stop.clone());
+    id
+  };
+  let lang = language.filter(|l| l != "auto");
+  thread::spawn(move || transcribe_live(window, id, exe, model, lang, recording, stop));
+  Ok(id)
+}
+
+// stop recording; the rest of the audio is transcribed and sent as the final "listening-output" event
+#[tauri::command]
+pub fn stop_listening(listen_id: u64, listeners: tauri::State<'_, Mutex<Listeners>>) -> Result<(), AppError> {
+  let stop = listeners.locked().active.remove(&listen_id).ok_or(format!("Listening session {} not found", listen_id))?;
+  stop.store(true, Ordering::Relaxed);
+  Ok(())
+}
@@ -0,0 +1,235 @@
+// src-tauri/src/backup.rs
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tauri::AppHandle;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::error::AppError;
+use crate::events;
+use crate::instance;
+use crate::settings;
+use crate::store;
+
+// used until a backup folder is chosen, in the app folder next to data/
+const DEFAULT_BACKUP_DIR: &str = "backups";
+const DEFAULT_INTERVAL_HOURS: u32 = 24;
+const DEFAULT_KEEP: u32 = 7;
+// how often the scheduler looks whether a backup is due
+const TICK: Duration = Duration::from_secs(10 * 60);
+// in the app folder, outside data/ so it is never backed up itself
+const PENDING_RESTORE: &str = "restore-pending";
+// folders under ./data that are regenerated rather than user data
+const SKIPPED: &[&str] = &["quarantine", "readaloud"];
+
+// A backup archive: ./data under "data/" plus the settings file as "settings.json"
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct BackupInfo {
+  pub path: String,
+  // unix seconds
+  pub created: i64,
+  pub bytes: u64,
+}
+
+pub fn backup_dir() -> PathBuf {
+  settings::get().backup_dir.map(PathBuf::from).unwrap_or_else(|| store::app_path(DEFAULT_BACKUP_DIR))
+}
+
+fn info(path: &Path) -> Option<BackupInfo> {
+  let meta = fs::metadata(path).ok()?;
+  let created = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?.as_secs() as i64;
+  Some(BackupInfo { path: path.to_string_lossy().to_string(), created, bytes: meta.len() })
+}
+
+// backups in the backup folder, newest first
+fn backups() -> Vec<BackupInfo> {
+  let mut list: Vec<BackupInfo> = fs::read_dir(backup_dir())
+    .map(|rd| {
+      rd.flatten()
+        .map(|e| e.path())
+        .filter(|p| p.file_name().is_some_and(|n| n.to_string_lossy().starts_with("multilingual-backup-")) && p.extension().is_some_and(|x| x == "zip"))
+        .filter_map(|p| info(&p))
+        .collect()
+    })
+    .unwrap_or_default();
+  list.sort_by(|a, b| b.created.cmp(&a.created).then(b.path.cmp(&a.path)));
+  list
+}
+
+// files under `dir` as (path, name in the archive)
+fn files(dir: &Path, prefix: &str, out: &mut Vec<(PathBuf, String)>) {
+  let Ok(rd) = fs::read_dir(dir) else { return };
+  for entry in rd.flatten() {
+    let path = entry.path();
+    let name = format!("{}/{}", prefix, entry.file_name().to_string_lossy());
+    if path.is_dir() {
+      // a backup folder set inside ./data is not backed up into itself
+      if !SKIPPED.contains(&entry.file_name().to_string_lossy().as_ref()) && path != backup_dir() {
+        files(&path, &name, out);
+      }
+    } else if !name.ends_with(".tmp") && !entry.file_name().to_string_lossy().starts_with(".lock") {
+      out.push((path, name));
+    }
+  }
+}
+
+// delete the oldest backups past the backup_keep setting. `spared` (a backup about to be restored)
+// is neither deleted nor counted
+fn rotate(spared: Option<&Path>) {
+  let keep = settings::get().backup_keep.unwrap_or(DEFAULT_KEEP).max(1) as usize;
+  for old in backups().into_iter().filter(|b| spared != Some(Path::new(&b.path))).skip(keep) {
+    let _ = fs::remove_file(old.path);
+  }
+}
+
+// zip up ./data and the settings into the backup folder, then apply the retention policy
+pub fn create() -> Result<BackupInfo, String> {
+  create_sparing(None)
+}
+
+fn create_sparing(spared: Option<&Path>) -> Result<BackupInfo, String> {
+  let dir = backup_dir();
+  fs::create_dir_all(&dir).map_err(|e| format!("failed to create {}: {}", dir.to_string_lossy(), e))?;
+  let mut entries = Vec::new();
+  files(&store::data_file(""), "data", &mut entries);
+  if let Some(path) = settings::path().filter(|p| p.is_file()) {
+    entries.push((path, "settings.json".to_string()));
+  }
+
+  let name = format!("multilingual-backup-{}.zip", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+  let path = dir.join(&name);
+  // written under another name first so a backup cut short is never mistaken for a good one
+  let partial = dir.join(format!("{}.partial", name));
+  let write = || -> Result<(), String> {
+    let file = File::create(&partial).map_err(|e| format!("failed to create {}: {}", partial.to_string_lossy(), e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (source, entry) in &entries {
+      // files that disappeared since the listing are skipped
+      let Ok(bytes) = fs::read(source) else { continue };
+      zip.start_file(entry.as_str(), options).map_err(|e| format!("failed to write backup: {}", e))?;
+      zip.write_all(&bytes).map_err(|e| format!("failed to write backup: {}", e))?;
+    }
+    zip.finish().map_err(|e| format!("failed to write backup: {}", e))?;
+    fs::rename(&partial, &path).map_err(|e| format!("failed to write backup: {}", e))
+  };
+  if let Err(e) = write() {
+    let _ = fs::remove_file(&partial);
+    return Err(e);
+  }
+  rotate(spared);
+  info(&path).ok_or(format!("failed to read {}", path.to_string_lossy()))
+}
+
+// where an archive entry goes back to; None for entries a backup never contains. The settings
+// file is restored before settings::init, so its path comes from the config folder then
+fn restore_target(entry: &Path) -> Option<PathBuf> {
+  if entry == Path::new("settings.json") {
+    return Some(settings::path().unwrap_or_else(|| store::config_dir().join("settings.json")));
+  }
+  let rest = entry.strip_prefix("data").ok()?;
+  (!rest.as_os_str().is_empty()).then(|| store::data_file("").join(rest))
+}
+
+// the files of the backup at `path` with where each goes; the whole archive is read and checked
+fn read_backup(path: &Path) -> Result<Vec<(PathBuf, Vec<u8>)>, String> {
+  let file = File::open(path).map_err(|e| format!("failed to open {}: {}", path.to_string_lossy(), e))?;
+  let mut zip = ZipArchive::new(file).map_err(|e| format!("{} is not a backup: {}", path.to_string_lossy(), e))?;
+  let mut contents = Vec::new();
+  for i in 0..zip.len() {
+    let mut entry = zip.by_index(i).map_err(|e| format!("damaged backup: {}", e))?;
+    if entry.is_dir() {
+      continue;
+    }
+    let target = entry.enclosed_name().and_then(|name| restore_target(&name)).ok_or(format!("{} is not a backup made by this app", path.to_string_lossy()))?;
+    let mut bytes = Vec::new();
+    entry.read_to_end(&mut bytes).map_err(|e| format!("damaged backup: {}", e))?;
+    contents.push((target, bytes));
+  }
+  if contents.is_empty() {
+    return Err(format!("{} is empty", path.to_string_lossy()));
+  }
+  Ok(contents)
+}
+
+// the backup to restore on the next start, written by schedule_restore
+fn pending_restore() -> PathBuf {
+  store::app_path(PENDING_RESTORE)
+}
+
+// check the backup at `path` and back up the current data (so the restore can be undone), then
+// leave a note to restore it on the next start. The stores hold what they loaded and save it again
+// at any time, so the files are only put back before they load
+pub fn schedule_restore(path: &Path) -> Result<(), String> {
+  instance::check_writable()?;
+  read_backup(path)?;
+  create_sparing(Some(path)).map_err(|e| format!("not restoring, the current data could not be backed up first: {}", e))?;
+  store::write_atomic(&pending_restore(), path.to_string_lossy().as_bytes())
+}
+
+// put back the files of a backup scheduled by schedule_restore; called in run() before the
+// stores load
+pub fn finish_restore() {
+  let note = pending_restore();
+  let Ok(path) = fs::read_to_string(&note) else { return };
+  // the instance holding the data folder restores it; the note waits for it
+  if instance::read_only() {
+    return;
+  }
+  let restored = read_backup(Path::new(&path)).and_then(|contents| {
+    for (target, bytes) in &contents {
+      if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("failed to create {}: {}", parent.to_string_lossy(), e))?;
+      }
+      store::write_atomic(target, bytes)?;
+    }
+    Ok(contents.len())
+  });
+  match restored {
+    Ok(n) => events::log(format!("[backup] restored {} files from {}", n, path)),
+    Err(e) => events::log(format!("[backup] restoring {} failed: {}", path, e)),
+  }
+  let _ = fs::remove_file(note);
+}
+
+// back up on the backup_interval_hours schedule, counting from the newest backup so restarts don't
+// add extra ones
+pub fn start() {
+  thread::spawn(|| loop {
+    let hours = settings::get().backup_interval_hours.unwrap_or(DEFAULT_INTERVAL_HOURS);
+    // the instance holding the data folder does the scheduled backups
+    if hours > 0 && !instance::read_only() {
+      let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0);
+      let due = backups().first().is_none_or(|b| now - b.created >= hours as i64 * 3600);
+      if due {
+        if let Err(e) = create() {
+          events::log(format!("[backup] scheduled backup failed: {}", e));
+        }
+      }
+    }
+    thread::sleep(TICK);
+  });
+}
+
+// ------------------ Tauri commands ------------------
+
+#[tauri::command(async)]
+pub fn create_backup() -> Result<BackupInfo, AppError> {
+  Ok(create()?)
+}
+
+#[tauri::command]
+pub fn list_backups() -> Vec<BackupInfo> {
+  backups()
+}
+
+// restore a backup: restart and put its files back before the stores load them
+#[tauri::command(async)]
+pub fn restore_backup(path: String, app: AppHandle) -> Result<(), AppError> {
+  schedule_restore(Path::new(&path))?;
+  app.restart()
+}
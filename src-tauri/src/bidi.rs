@@ -0,0 +1,225 @@
+// src-tauri/src/bidi.rs
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use unicode_normalization::UnicodeNormalization;
+
+use crate::error::{AppError, LockExt};
+use crate::store;
+
+// LRM, RLM, ALM, embeddings/overrides (LRE..RLO) and isolates (LRI..PDI)
+const DIRECTIONAL_MARKS: &[char] = &[
+  '\u{200E}', '\u{200F}', '\u{061C}', '\u{202A}', '\u{202B}', '\u{202C}', '\u{202D}', '\u{202E}', '\u{2066}',
+  '\u{2067}', '\u{2068}', '\u{2069}',
+];
+const LRI: char = '\u{2066}';
+const RLI: char = '\u{2067}';
+const PDI: char = '\u{2069}';
+const LRM: char = '\u{200E}';
+const RLM: char = '\u{200F}';
+
+// What to do with directional formatting characters in model output
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MarkMode {
+  // leave the text as the model produced it
+  #[default]
+  Keep,
+  // remove all directional marks/isolates (the UI handles direction itself)
+  Strip,
+  // wrap opposite-direction runs in isolates (LRI/RLI ... PDI)
+  Isolate,
+  // append an LRM/RLM after opposite-direction runs
+  Mark,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct BidiConfig {
+  pub mode: MarkMode,
+  // fold Arabic presentation forms (U+FB50..U+FDFF, U+FE70..U+FEFF) to base letters
+  pub normalize_presentation_forms: bool,
+}
+
+impl Default for BidiConfig {
+  fn default() -> Self {
+    Self { mode: MarkMode::Keep, normalize_presentation_forms: true }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+  Ltr,
+  Rtl,
+}
+
+// A run of text with one strong direction (char offsets)
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct DirectionRun {
+  pub start: usize,
+  pub end: usize,
+  pub direction: Direction,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct BidiInfo {
+  // direction of the first strong character (what the UI should use as `dir`)
+  pub base_direction: Direction,
+  // true when both LTR and RTL text appear
+  pub mixed: bool,
+  pub runs: Vec<DirectionRun>,
+}
+
+pub fn is_rtl_lang(code: &str) -> bool {
+  let base = code.split(['-', '_']).next().unwrap_or(code).to_lowercase();
+  matches!(base.as_str(), "ar" | "he" | "fa" | "ur" | "ps" | "sd" | "yi" | "dv" | "ug" | "ku")
+}
+
+fn is_rtl_char(c: char) -> bool {
+  matches!(c as u32, 0x0590..=0x08FF | 0xFB1D..=0xFDFF | 0xFE70..=0xFEFF | 0x10800..=0x10FFF | 0x1E800..=0x1EFFF)
+}
+
+// strong direction of a character, None for digits, punctuation, spaces and marks
+fn strong_direction(c: char) -> Option<Direction> {
+  if DIRECTIONAL_MARKS.contains(&c) {
+    None
+  } else if is_rtl_char(c) && c.is_alphabetic() {
+    Some(Direction::Rtl)
+  } else if c.is_alphabetic() {
+    Some(Direction::Ltr)
+  } else {
+    None
+  }
+}
+
+pub fn strip_marks(text: &str) -> String {
+  text.chars().filter(|c| !DIRECTIONAL_MARKS.contains(c)).collect()
+}
+
+pub fn normalize_presentation_forms(text: &str) -> String {
+  let mut out = String::with_capacity(text.len());
+  for c in text.chars() {
+    if matches!(c as u32, 0xFB50..=0xFDFF | 0xFE70..=0xFEFF) && c != '\u{FEFF}' {
+      out.extend(c.nfkc());
+    } else {
+      out.push(c);
+    }
+  }
+  out
+}
+
+// split into strong-direction runs; neutrals attach to the preceding run
+pub fn analyze(text: &str) -> BidiInfo {
+  let mut runs: Vec<DirectionRun> = Vec::new();
+  for (i, c) in text.chars().enumerate() {
+    match (strong_direction(c), runs.last_mut()) {
+      (Some(d), Some(last)) if last.direction == d => last.end = i + 1,
+      (Some(d), _) => runs.push(DirectionRun { start: i, end: i + 1, direction: d }),
+      (None, Some(last)) => last.end = i + 1,
+      (None, None) => {}
+    }
+  }
+  // trailing neutrals (spaces, punctuation) belong between runs rather than inside the last one
+  if let Some(last) = runs.last_mut() {
+    let trailing = text.chars().rev().take_while(|c| strong_direction(*c).is_none()).count();
+    last.end -= trailing.min(last.end - last.start - 1);
+  }
+  let base_direction = runs.first().map(|r| r.direction).unwrap_or(Direction::Ltr);
+  let mixed = runs.iter().any(|r| r.direction != base_direction);
+  BidiInfo { base_direction, mixed, runs }
+}
+
+// wrap or mark every run whose direction differs from the base direction
+fn insert_marks(text: &str, info: &BidiInfo, mode: MarkMode) -> String {
+  let chars: Vec<char> = text.chars().collect();
+  let mut out = String::with_capacity(text.len() + info.runs.len() * 6);
+  let mut pos = 0;
+  for run in info.runs.iter().filter(|r| r.direction != info.base_direction) {
+    out.extend(&chars[pos..run.start]);
+    let segment: String = chars[run.start..run.end].iter().collect();
+    match mode {
+      MarkMode::Isolate => {
+        let open = if run.direction == Direction::Rtl { RLI } else { LRI };
+        out.push(open);
+        out.push_str(&segment);
+        out.push(PDI);
+      }
+      MarkMode::Mark => {
+        out.push_str(&segment);
+        out.push(if info.base_direction == Direction::Rtl { RLM } else { LRM });
+      }
+      _ => out.push_str(&segment),
+    }
+    pos = run.end;
+  }
+  out.extend(&chars[pos..]);
+  out
+}
+
+// full pipeline pass: normalize, drop existing marks if we manage them, then re-insert as configured
+pub fn apply(text: &str, config: &BidiConfig) -> (String, BidiInfo) {
+  let mut out = if config.normalize_presentation_forms { normalize_presentation_forms(text) } else { text.to_string() };
+  if config.mode != MarkMode::Keep {
+    out = strip_marks(&out);
+  }
+  let info = analyze(&out);
+  if matches!(config.mode, MarkMode::Isolate | MarkMode::Mark) && info.mixed {
+    out = insert_marks(&out, &info, config.mode);
+  }
+  (out, info)
+}
+
+// streaming variant for model-output lines: only per-line safe transformations
+pub fn process_stream_line(line: &str, config: &BidiConfig) -> String {
+  if !line.chars().any(is_rtl_char) && !line.chars().any(|c| DIRECTIONAL_MARKS.contains(&c)) {
+    return line.to_string();
+  }
+  apply(line, config).0
+}
+
+// Result of apply_bidi: processed text plus direction info for rendering
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct BidiResult {
+  pub text: String,
+  pub info: BidiInfo,
+  // whether the requested language is written right-to-left
+  pub rtl_language: bool,
+}
+
+// Bidi configuration persisted in ./data/bidi.json (managed by Tauri)
+pub struct BidiSettings {
+  path: PathBuf,
+  pub config: BidiConfig,
+}
+
+impl BidiSettings {
+  pub fn load() -> Self {
+    let path = store::data_file("bidi.json");
+    let config = store::load_json(&path);
+    Self { path, config }
+  }
+
+  pub fn set(&mut self, config: BidiConfig) -> Result<(), String> {
+    self.config = config;
+    store::save_json(&self.path, &self.config)
+  }
+}
+
+// ------------------ Tauri commands ------------------
+
+#[tauri::command]
+pub fn apply_bidi(text: String, lang: Option<String>, settings: tauri::State<'_, Mutex<BidiSettings>>) -> BidiResult {
+  let config = settings.locked().config.clone();
+  let (text, info) = apply(&text, &config);
+  BidiResult { text, info, rtl_language: lang.as_deref().is_some_and(is_rtl_lang) }
+}
+
+#[tauri::command]
+pub fn get_bidi_config(settings: tauri::State<'_, Mutex<BidiSettings>>) -> BidiConfig {
+  settings.locked().config.clone()
+}
+
+#[tauri::command]
+pub fn set_bidi_config(config: BidiConfig, settings: tauri::State<'_, Mutex<BidiSettings>>) -> Result<(), AppError> {
+  Ok(settings.locked().set(config)?)
+}
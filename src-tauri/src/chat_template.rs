@@ -0,0 +1,417 @@
+// src-tauri/src/chat_template.rs
+use std::sync::{Mutex, OnceLock};
+
+use regex::Regex;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::{AppError, LockExt};
+use crate::gguf::GgufMetadata;
+use crate::license::ModelWarning;
+use crate::model_config::ModelConfig;
+use crate::session::Turn;
+use crate::ModelManager;
+
+// Role strings of a custom template; "{content}" is replaced by the message text
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CustomTemplate {
+  // empty puts the system text in front of the first user message instead
+  #[serde(default)]
+  pub system: String,
+  pub user: String,
+  pub assistant: String,
+  // opens the reply the model is to write, e.g. "<|assistant|>\n"
+  pub generation_prompt: String,
+}
+
+// How a model expects a conversation to be laid out
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ChatTemplate {
+  // "User: ... / Assistant:" transcript, for models without a known format
+  Plain,
+  ChatMl,
+  Llama2,
+  Llama3,
+  Mistral,
+  Gemma,
+  Custom(CustomTemplate),
+}
+
+// Template a model uses and where it came from
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ModelTemplate {
+  pub template: ChatTemplate,
+  // "override", "gguf" (the model's embedded template), "architecture", "name" or "default"
+  pub source: String,
+  // set when an override doesn't match the format detected for the model
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub warning: Option<String>,
+}
+
+// recognize the embedded Jinja template by the special tokens it writes
+fn from_jinja(template: &str) -> Option<ChatTemplate> {
+  if template.contains("<|im_start|>") {
+    Some(ChatTemplate::ChatMl)
+  } else if template.contains("<|start_header_id|>") {
+    Some(ChatTemplate::Llama3)
+  } else if template.contains("<start_of_turn>") {
+    Some(ChatTemplate::Gemma)
+  } else if template.contains("<<SYS>>") {
+    Some(ChatTemplate::Llama2)
+  } else if template.contains("[INST]") {
+    Some(ChatTemplate::Mistral)
+  } else {
+    None
+  }
+}
+
+fn from_architecture(architecture: &str) -> Option<ChatTemplate> {
+  let arch = architecture.to_lowercase();
+  if arch.starts_with("qwen") {
+    Some(ChatTemplate::ChatMl)
+  } else if arch.starts_with("gemma") {
+    Some(ChatTemplate::Gemma)
+  } else {
+    None
+  }
+}
+
+fn name_pattern() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  // llama-3 / llama3.1 / llama-2, but not codellama-34b
+  RE.get_or_init(|| Regex::new(r"llama-?([23])(?:[^0-9]|$)").unwrap())
+}
+
+// guess from how fine-tunes are usually named ("Meta-Llama-3-8B-Instruct", "OpenHermes-2.5-Mistral")
+fn from_name(name: &str) -> Option<ChatTemplate> {
+  let name = name.to_lowercase().replace(['_', ' ', '.'], "-");
+  let has = |s: &str| name.contains(s);
+  // ChatML fine-tunes of other base models name the base too, so they are checked first
+  if has("qwen") || has("hermes") || has("dolphin") || has("chatml") {
+    return Some(ChatTemplate::ChatMl);
+  }
+  match name_pattern().captures(&name).map(|c| c[1].to_string()).as_deref() {
+    Some("3") => return Some(ChatTemplate::Llama3),
+    // base llama-2 models have no chat format
+    Some("2") if has("chat") => return Some(ChatTemplate::Llama2),
+    _ => {}
+  }
+  if has("mistral") || has("mixtral") {
+    Some(ChatTemplate::Mistral)
+  } else if has("gemma") {
+    Some(ChatTemplate::Gemma)
+  } else {
+    None
+  }
+}
+
+// the format the model itself points to: its embedded template, else its architecture, else its
+// name (`name`, then general.name)
+pub fn detect(name: &str, gguf: Option<&GgufMetadata>) -> Option<ModelTemplate> {
+  let found = gguf
+    .and_then(|g| g.chat_template.as_deref())
+    .and_then(from_jinja)
+    .map(|t| (t, "gguf"))
+    .or_else(|| gguf.and_then(|g| g.architecture.as_deref()).and_then(from_architecture).map(|t| (t, "architecture")))
+    .or_else(|| from_name(name).or_else(|| gguf.and_then(|g| g.name.as_deref()).and_then(from_name)).map(|t| (t, "name")));
+  found.map(|(template, source)| ModelTemplate { template, source: source.to_string(), warning: None })
+}
+
+// why `chosen` doesn't fit the detected format; nothing when either can't be told
+fn conflict(chosen: &ChatTemplate, detected: &ModelTemplate) -> Option<String> {
+  let kind = chosen.kind()?;
+  (kind != detected.template).then(|| {
+    let from = match detected.source.as_str() {
+      "gguf" => "its embedded template",
+      "architecture" => "its architecture",
+      _ => "its name",
+    };
+    format!(
+      "The model is set to use the {} prompt format, but {} suggests {}; answers may be garbled or never end",
+      chosen.label(),
+      from,
+      detected.template.label()
+    )
+  })
+}
+
+// the user's override, else the format detected for the model (stored in its config when it was
+// scanned), else the plain transcript
+pub fn resolve(config: &ModelConfig, gguf: Option<&GgufMetadata>) -> ModelTemplate {
+  let detected = config.detected_template.clone().or_else(|| detect("", gguf));
+  match (&config.chat_template, detected) {
+    (Some(chosen), detected) => {
+      let warning = detected.and_then(|d| conflict(chosen, &d));
+      ModelTemplate { template: chosen.clone(), source: "override".into(), warning }
+    }
+    (None, Some(detected)) => detected,
+    (None, None) => ModelTemplate { template: ChatTemplate::Plain, source: "default".into(), warning: None },
+  }
+}
+
+// emit "model-warning" when the model's template override conflicts with its detected format
+pub fn warn_for(app: &AppHandle, model_id: &str) {
+  let state = app.state::<Mutex<ModelManager>>();
+  let mgr = state.locked();
+  let Some(model) = mgr.models.get(model_id) else { return };
+  if let Some(message) = resolve(&mgr.configs.get(model_id), model.gguf.as_ref()).warning {
+    let _ = app.emit("model-warning", ModelWarning { model_id: model_id.to_string(), message });
+  }
+}
+
+// (role, text) with system turns folded into the next user turn, for formats without a system role
+fn fold_system(turns: &[Turn]) -> Vec<(String, String)> {
+  let mut out: Vec<(String, String)> = Vec::new();
+  let mut pending = String::new();
+  for t in turns {
+    if t.role == "system" {
+      pending.push_str(&t.text);
+      pending.push_str("\n\n");
+    } else if t.role == "assistant" {
+      out.push((t.role.clone(), t.text.clone()));
+    } else {
+      out.push(("user".to_string(), format!("{}{}", std::mem::take(&mut pending), t.text)));
+    }
+  }
+  if !pending.is_empty() {
+    out.push(("user".to_string(), pending.trim_end().to_string()));
+  }
+  out
+}
+
+impl ChatTemplate {
+  // the conversation as the model expects it, ending where the model's reply begins
+  pub fn format(&self, turns: &[Turn]) -> String {
+    let mut prompt = String::new();
+    match self {
+      ChatTemplate::Plain => {
+        for t in turns {
+          let label = match t.role.as_str() {
+            "system" => "System",
+            "assistant" => "Assistant",
+            _ => "User",
+          };
+          prompt.push_str(&format!("{}: {}\n", label, t.text));
+        }
+        prompt.push_str("Assistant:");
+      }
+      ChatTemplate::ChatMl => {
+        for t in turns {
+          prompt.push_str(&format!("<|im_start|>{}\n{}<|im_end|>\n", t.role, t.text));
+        }
+        prompt.push_str("<|im_start|>assistant\n");
+      }
+      ChatTemplate::Llama3 => {
+        for t in turns {
+          prompt.push_str(&format!("<|start_header_id|>{}<|end_header_id|>\n\n{}<|eot_id|>", t.role, t.text));
+        }
+        prompt.push_str("<|start_header_id|>assistant<|end_header_id|>\n\n");
+      }
+      ChatTemplate::Llama2 => {
+        let mut system: Option<&str> = None;
+        for t in turns {
+          match t.role.as_str() {
+            "system" => system = Some(&t.text),
+            "assistant" => prompt.push_str(&format!(" {} </s><s>", t.text)),
+            _ => match system.take() {
+              Some(sys) => prompt.push_str(&format!("[INST] <<SYS>>\n{}\n<</SYS>>\n\n{} [/INST]", sys, t.text)),
+              None => prompt.push_str(&format!("[INST] {} [/INST]", t.text)),
+            },
+          }
+        }
+      }
+      ChatTemplate::Mistral => {
+        for (role, text) in fold_system(turns) {
+          if role == "assistant" {
+            prompt.push_str(&format!(" {}</s>", text));
+          } else {
+            prompt.push_str(&format!("[INST] {} [/INST]", text));
+          }
+        }
+      }
+      ChatTemplate::Gemma => {
+        for (role, text) in fold_system(turns) {
+          let role = if role == "assistant" { "model" } else { "user" };
+          prompt.push_str(&format!("<start_of_turn>{}\n{}<end_of_turn>\n", role, text));
+        }
+        prompt.push_str("<start_of_turn>model\n");
+      }
+      ChatTemplate::Custom(custom) => {
+        let turns: Vec<(String, String)> =
+          if custom.system.is_empty() { fold_system(turns) } else { turns.iter().map(|t| (t.role.clone(), t.text.clone())).collect() };
+        for (role, text) in turns {
+          let pattern = match role.as_str() {
+            "system" => &custom.system,
+            "assistant" => &custom.assistant,
+            _ => &custom.user,
+          };
+          prompt.push_str(&pattern.replace("{content}", &text));
+        }
+        prompt.push_str(&custom.generation_prompt);
+      }
+    }
+    prompt
+  }
+
+  fn label(&self) -> &'static str {
+    match self {
+      ChatTemplate::Plain => "plain",
+      ChatTemplate::ChatMl => "ChatML",
+      ChatTemplate::Llama2 => "Llama 2",
+      ChatTemplate::Llama3 => "Llama 3",
+      ChatTemplate::Mistral => "Mistral",
+      ChatTemplate::Gemma => "Gemma",
+      ChatTemplate::Custom(_) => "custom",
+    }
+  }
+
+  // the built-in format a template amounts to; a custom one is recognized by its special tokens
+  fn kind(&self) -> Option<ChatTemplate> {
+    match self {
+      ChatTemplate::Custom(c) => from_jinja(&format!("{}{}{}{}", c.system, c.user, c.assistant, c.generation_prompt)),
+      t => Some(t.clone()),
+    }
+  }
+
+  fn validate(&self) -> Result<(), String> {
+    if let ChatTemplate::Custom(custom) = self {
+      if !custom.user.contains("{content}") || !custom.assistant.contains("{content}") {
+        return Err("The user and assistant parts of a template need a {content} placeholder".into());
+      }
+      if !custom.system.is_empty() && !custom.system.contains("{content}") {
+        return Err("The system part of a template needs a {content} placeholder".into());
+      }
+    }
+    Ok(())
+  }
+}
+
+// ------------------ Tauri commands ------------------
+
+#[tauri::command]
+pub fn get_chat_template(model_id: String, state: tauri::State<'_, Mutex<ModelManager>>) -> Result<ModelTemplate, AppError> {
+  let mgr = state.locked();
+  let model = mgr.models.get(&model_id).ok_or_else(|| AppError::not_found("Model", &model_id))?;
+  Ok(resolve(&mgr.configs.get(&model_id), model.gguf.as_ref()))
+}
+
+// override the detected prompt format of a model; `None` goes back to detection. The result carries
+// a warning when the override doesn't match what was detected
+#[tauri::command]
+pub fn set_chat_template(model_id: String, template: Option<ChatTemplate>, state: tauri::State<'_, Mutex<ModelManager>>) -> Result<ModelTemplate, AppError> {
+  if let Some(t) = &template {
+    t.validate()?;
+  }
+  let mut mgr = state.locked();
+  let gguf = mgr.models.get(&model_id).ok_or_else(|| AppError::not_found("Model", &model_id))?.gguf.clone();
+  mgr.configs.update(&model_id, |c| c.chat_template = template)?;
+  Ok(resolve(&mgr.configs.get(&model_id), gguf.as_ref()))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn conversation() -> Vec<Turn> {
+    vec![Turn::new("system", "Be brief."), Turn::new("user", "Hi"), Turn::new("assistant", "Hello"), Turn::new("user", "Bye")]
+  }
+
+  #[test]
+  fn formats_chatml_and_llama3() {
+    assert_eq!(
+      ChatTemplate::ChatMl.format(&conversation()),
+      "<|im_start|>system\nBe brief.<|im_end|>\n<|im_start|>user\nHi<|im_end|>\n<|im_start|>assistant\nHello<|im_end|>\n\
+       <|im_start|>user\nBye<|im_end|>\n<|im_start|>assistant\n"
+    );
+    let llama3 = ChatTemplate::Llama3.format(&[Turn::new("user", "Hi")]);
+    assert_eq!(llama3, "<|start_header_id|>user<|end_header_id|>\n\nHi<|eot_id|><|start_header_id|>assistant<|end_header_id|>\n\n");
+  }
+
+  #[test]
+  fn formats_llama2_with_its_system_block() {
+    assert_eq!(
+      ChatTemplate::Llama2.format(&conversation()),
+      "[INST] <<SYS>>\nBe brief.\n<</SYS>>\n\nHi [/INST] Hello </s><s>[INST] Bye [/INST]"
+    );
+  }
+
+  #[test]
+  fn system_text_folds_into_the_user_turn() {
+    assert_eq!(ChatTemplate::Mistral.format(&conversation()), "[INST] Be brief.\n\nHi [/INST] Hello</s>[INST] Bye [/INST]");
+    assert_eq!(
+      ChatTemplate::Gemma.format(&conversation()),
+      "<start_of_turn>user\nBe brief.\n\nHi<end_of_turn>\n<start_of_turn>model\nHello<end_of_turn>\n<start_of_turn>user\nBye<end_of_turn>\n\
+       <start_of_turn>model\n"
+    );
+    // a system turn with nothing after it still reaches the model
+    assert_eq!(ChatTemplate::Gemma.format(&[Turn::new("system", "Only this")]), "<start_of_turn>user\nOnly this<end_of_turn>\n<start_of_turn>model\n");
+  }
+
+  #[test]
+  fn formats_plain_and_custom() {
+    assert_eq!(ChatTemplate::Plain.format(&[Turn::new("user", "Hi")]), "User: Hi\nAssistant:");
+    let custom = ChatTemplate::Custom(CustomTemplate {
+      system: String::new(),
+      user: "<|user|>{content}\n".into(),
+      assistant: "<|assistant|>{content}\n".into(),
+      generation_prompt: "<|assistant|>".into(),
+    });
+    assert_eq!(custom.format(&conversation()), "<|user|>Be brief.\n\nHi\n<|assistant|>Hello\n<|user|>Bye\n<|assistant|>");
+  }
+
+  #[test]
+  fn guesses_the_format_from_the_name() {
+    let cases = [
+      ("Meta-Llama-3-8B-Instruct", Some(ChatTemplate::Llama3)),
+      ("llama3.1-8b", Some(ChatTemplate::Llama3)),
+      ("Llama-2-7b-chat", Some(ChatTemplate::Llama2)),
+      ("llama-2-7b", None),
+      ("codellama-34b", None),
+      ("OpenHermes-2.5-Mistral-7B", Some(ChatTemplate::ChatMl)),
+      ("Qwen2.5_7B_Instruct", Some(ChatTemplate::ChatMl)),
+      ("Mixtral-8x7B-Instruct", Some(ChatTemplate::Mistral)),
+      ("gemma-2-9b-it", Some(ChatTemplate::Gemma)),
+      ("phi-3-mini", None),
+    ];
+    for (name, expected) in cases {
+      assert_eq!(from_name(name), expected, "{}", name);
+    }
+  }
+
+  #[test]
+  fn the_embedded_template_wins() {
+    let gguf = GgufMetadata {
+      architecture: Some("qwen2".into()),
+      chat_template: Some("{% for m in messages %}<start_of_turn>{{ m.content }}{% endfor %}".into()),
+      ..Default::default()
+    };
+    let found = detect("llama-3", Some(&gguf)).unwrap();
+    assert_eq!((found.template, found.source.as_str()), (ChatTemplate::Gemma, "gguf"));
+    let gguf = GgufMetadata { architecture: Some("qwen2".into()), ..Default::default() };
+    assert_eq!(detect("llama-3", Some(&gguf)).unwrap().source, "architecture");
+    assert_eq!(detect("llama-3", None).unwrap().source, "name");
+    assert!(detect("my-model", None).is_none());
+  }
+
+  #[test]
+  fn warns_about_a_mismatched_override() {
+    let detected = ModelTemplate { template: ChatTemplate::ChatMl, source: "gguf".into(), warning: None };
+    assert!(conflict(&ChatTemplate::ChatMl, &detected).is_none());
+    assert!(conflict(&ChatTemplate::Llama3, &detected).unwrap().contains("embedded template"));
+    // a custom template written in ChatML tokens is ChatML
+    let custom = ChatTemplate::Custom(CustomTemplate {
+      system: String::new(),
+      user: "<|im_start|>user\n{content}<|im_end|>\n".into(),
+      assistant: "<|im_start|>assistant\n{content}<|im_end|>\n".into(),
+      generation_prompt: "<|im_start|>assistant\n".into(),
+    });
+    assert!(conflict(&custom, &detected).is_none());
+  }
+
+  #[test]
+  fn custom_templates_need_placeholders() {
+    let custom = |user: &str| ChatTemplate::Custom(CustomTemplate { system: String::new(), user: user.into(), assistant: "{content}".into(), generation_prompt: String::new() });
+    assert!(custom("<u>{content}").validate().is_ok());
+    assert!(custom("<u>").validate().is_err());
+  }
+}
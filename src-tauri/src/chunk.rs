@@ -0,0 +1,78 @@
+// src-tauri/src/chunk.rs
+use crate::engine::estimate_tokens;
+
+// Split text into chunks of at most `max_tokens` (estimated), preferring paragraph
+// boundaries, then sentence boundaries, and only cutting mid-sentence as a last resort.
+pub fn split_into_chunks(text: &str, max_tokens: u32) -> Vec<String> {
+  let max_tokens = max_tokens.max(16);
+  let mut chunks = Vec::new();
+  let mut current = String::new();
+
+  for para in text.split("\n\n").map(|p| p.trim()).filter(|p| !p.is_empty()) {
+    let pieces = if estimate_tokens(para) > max_tokens { split_long(para, max_tokens) } else { vec![para.to_string()] };
+    for piece in pieces {
+      if !current.is_empty() && estimate_tokens(&current) + estimate_tokens(&piece) > max_tokens {
+        chunks.push(std::mem::take(&mut current));
+      }
+      if !current.is_empty() {
+        current.push_str("\n\n");
+      }
+      current.push_str(&piece);
+    }
+  }
+  if !current.is_empty() {
+    chunks.push(current);
+  }
+  chunks
+}
+
+// break an oversized paragraph at sentence ends, hard-splitting sentences that are still too long
+fn split_long(para: &str, max_tokens: u32) -> Vec<String> {
+  let mut out = Vec::new();
+  let mut current = String::new();
+  for sentence in sentences(para) {
+    if estimate_tokens(&sentence) > max_tokens {
+      if !current.is_empty() {
+        out.push(std::mem::take(&mut current));
+      }
+      let chars: Vec<char> = sentence.chars().collect();
+      out.extend(chars.chunks(max_tokens as usize * 4).map(|c| c.iter().collect::<String>()));
+      continue;
+    }
+    if !current.is_empty() && estimate_tokens(&current) + estimate_tokens(&sentence) > max_tokens {
+      out.push(std::mem::take(&mut current));
+    }
+    current.push_str(&sentence);
+  }
+  if !current.is_empty() {
+    out.push(current);
+  }
+  out
+}
+
+// sentence split that keeps the terminator and following whitespace with each sentence
+// (handles latin, CJK and devanagari full stops)
+pub fn sentences(text: &str) -> Vec<String> {
+  let mut out = Vec::new();
+  let mut current = String::new();
+  let mut chars = text.chars().peekable();
+  while let Some(c) = chars.next() {
+    current.push(c);
+    let terminal = matches!(c, '.' | '!' | '?' | '。' | '！' | '？' | '।');
+    if terminal {
+      while let Some(&next) = chars.peek() {
+        if next.is_whitespace() {
+          current.push(next);
+          chars.next();
+        } else {
+          break;
+        }
+      }
+      out.push(std::mem::take(&mut current));
+    }
+  }
+  if !current.is_empty() {
+    out.push(current);
+  }
+  out
+}
@@ -0,0 +1,144 @@
+// src-tauri/src/clarify.rs
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Sender};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tauri::{Emitter, Manager, Window};
+
+use crate::engine;
+use crate::error::{AppError, LockExt};
+use crate::lang;
+use crate::ModelManager;
+
+// how long a translation waits for the user before going with the model's own guess
+const ANSWER_TIMEOUT: Duration = Duration::from_secs(300);
+
+// Payload of "clarification-needed" events
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ClarificationRequest {
+  pub request_id: u64,
+  // "Is the person addressed a man or a woman?"
+  pub question: String,
+  pub options: Vec<String>,
+  pub source: String,
+  pub target_lang: String,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct Clarification {
+  pub question: String,
+  pub answer: String,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ClarifiedTranslation {
+  pub text: String,
+  // set when the model asked and the user answered
+  pub clarification: Option<Clarification>,
+  // true when the model asked but no answer came in time
+  pub unanswered: bool,
+}
+
+// Questions waiting for an answer (managed by Tauri)
+#[derive(Default)]
+pub struct Clarifications {
+  next_id: u64,
+  pending: HashMap<u64, Sender<String>>,
+}
+
+impl Clarifications {
+  pub fn new() -> Self {
+    Self::default()
+  }
+}
+
+fn first_pass_schema() -> serde_json::Value {
+  serde_json::json!({
+    "type": "object",
+    "properties": {
+      "ambiguous": { "type": "boolean" },
+      "question": { "type": "string" },
+      "options": { "type": "array", "items": { "type": "string" }, "maxItems": 5 },
+      "translation": { "type": "string" }
+    },
+    "required": ["ambiguous", "translation"]
+  })
+}
+
+// ------------------ Tauri commands ------------------
+
+// translate, but let the model stop and ask when the target language forces a choice the source
+// leaves open (gender of the addressee, formal or informal "you", ...). The question goes out as a
+// "clarification-needed" event; the translation resumes with the answer from provide_clarification
+#[tauri::command(async)]
+pub fn translate_with_clarification(
+  text: String,
+  source_lang: String,
+  target_lang: String,
+  model_id: Option<String>,
+  window: Window
+) -> Result<ClarifiedTranslation, AppError> {
+  let (model, config) = window.state::<Mutex<ModelManager>>().locked().model_for_request(model_id.as_deref())?;
+  let from = if source_lang == "auto" { String::new() } else { format!(" from {}", lang::language_name(&source_lang)) };
+  let to = lang::language_name(&target_lang);
+  let max_tokens = engine::estimate_tokens(&text) * 3 + 128;
+  let prompt = format!(
+    "Translate the following text{} to {}. If {} forces a choice the text leaves open (for example the gender of \
+     the person addressed or spoken about, or formal versus informal address), set \"ambiguous\" to true, ask one \
+     short question about it and give two to five possible answers; still give your best guess as the translation. \
+     Otherwise set \"ambiguous\" to false.\n\nText:\n{}",
+    from, to, to, text
+  );
+  let json = engine::generate_json(&model.path, &config, &prompt, max_tokens, &first_pass_schema())?;
+  let guess = json.get("translation").and_then(|t| t.as_str()).unwrap_or("").trim().to_string();
+  let question = json.get("question").and_then(|q| q.as_str()).unwrap_or("").trim().to_string();
+  let options: Vec<String> = json
+    .get("options")
+    .and_then(|o| serde_json::from_value::<Vec<String>>(o.clone()).ok())
+    .unwrap_or_default()
+    .into_iter()
+    .map(|o| o.trim().to_string())
+    .filter(|o| !o.is_empty())
+    .collect();
+  let ambiguous = json.get("ambiguous").and_then(|a| a.as_bool()).unwrap_or(false);
+  if !ambiguous || question.is_empty() || options.len() < 2 {
+    return Ok(ClarifiedTranslation { text: guess, clarification: None, unanswered: false });
+  }
+
+  let (tx, rx) = mpsc::channel();
+  let request_id = {
+    let state = window.state::<Mutex<Clarifications>>();
+    let mut state = state.locked();
+    state.next_id += 1;
+    let id = state.next_id;
+    state.pending.insert(id, tx);
+    id
+  };
+  let request = ClarificationRequest { request_id, question: question.clone(), options, source: text.clone(), target_lang };
+  let _ = window.emit("clarification-needed", request);
+  let answer = rx.recv_timeout(ANSWER_TIMEOUT).ok();
+  window.state::<Mutex<Clarifications>>().locked().pending.remove(&request_id);
+  let Some(answer) = answer else {
+    return Ok(ClarifiedTranslation { text: guess, clarification: None, unanswered: true });
+  };
+
+  let prompt = format!(
+    "Translate the following text{} to {}. Asked \"{}\", the user answered: {}. Translate accordingly and reply with \
+     the translation only.\n\nText:\n{}\n\nTranslation:",
+    from, to, question, answer, text
+  );
+  let translation = engine::generate(&model.path, &config, &prompt, max_tokens)?.trim().to_string();
+  Ok(ClarifiedTranslation { text: translation, clarification: Some(Clarification { question, answer }), unanswered: false })
+}
+
+// answer a "clarification-needed" question; any text is accepted, not only the offered options
+#[tauri::command]
+pub fn provide_clarification(request_id: u64, answer: String, clarifications: tauri::State<'_, Mutex<Clarifications>>) -> Result<(), AppError> {
+  let answer = answer.trim().to_string();
+  if answer.is_empty() {
+    return Err(AppError::InvalidInput("The answer is empty".into()));
+  }
+  let sender = clarifications.locked().pending.remove(&request_id).ok_or(format!("No open question {}", request_id))?;
+  Ok(sender.send(answer).map_err(|_| format!("Question {} is no longer waiting for an answer", request_id))?)
+}
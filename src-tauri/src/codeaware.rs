@@ -0,0 +1,335 @@
+// src-tauri/src/codeaware.rs
+use std::fs;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+use regex::Regex;
+use tauri::{Manager, Window};
+
+use crate::error::{AppError, LockExt};
+use crate::filter;
+use crate::model_config::ModelConfig;
+use crate::preload;
+use crate::translate;
+use crate::ModelManager;
+
+// A piece of the document: code is copied verbatim, text is translated.
+// `protected` holds inline code/URLs replaced by ⟦n⟧ placeholders inside `text`.
+#[derive(Clone, Debug)]
+struct Span {
+  code: bool,
+  text: String,
+  protected: Vec<String>,
+}
+
+impl Span {
+  fn code(text: &str) -> Self {
+    Span { code: true, text: text.to_string(), protected: Vec::new() }
+  }
+
+  fn text(text: &str) -> Self {
+    Span { code: false, text: text.to_string(), protected: Vec::new() }
+  }
+}
+
+// Comment and string syntax of a programming language
+struct Syntax {
+  line: &'static [&'static str],
+  block: &'static [(&'static str, &'static str)],
+  quotes: &'static [char],
+}
+
+fn syntax_for(language: &str) -> Option<Syntax> {
+  let s = match language {
+    "rust" | "rs" => Syntax { line: &["//"], block: &[("/*", "*/")], quotes: &['"'] },
+    "c" | "h" | "cpp" | "cc" | "hpp" | "java" | "go" | "cs" | "swift" | "kotlin" | "kt" | "scala" | "dart" => {
+      Syntax { line: &["//"], block: &[("/*", "*/")], quotes: &['"'] }
+    }
+    "javascript" | "js" | "jsx" | "typescript" | "ts" | "tsx" => {
+      Syntax { line: &["//"], block: &[("/*", "*/")], quotes: &['"', '\'', '`'] }
+    }
+    "python" | "py" => Syntax { line: &["#"], block: &[("\"\"\"", "\"\"\""), ("'''", "'''")], quotes: &['"', '\''] },
+    "shell" | "sh" | "bash" | "ruby" | "rb" | "yaml" | "yml" | "toml" | "r" | "perl" | "pl" => {
+      Syntax { line: &["#"], block: &[], quotes: &['"', '\''] }
+    }
+    "sql" | "lua" | "haskell" | "hs" => Syntax { line: &["--"], block: &[("/*", "*/")], quotes: &['\''] },
+    "html" | "xml" | "svg" | "vue" => Syntax { line: &[], block: &[("<!--", "-->")], quotes: &[] },
+    "css" | "scss" => Syntax { line: &[], block: &[("/*", "*/")], quotes: &[] },
+    _ => return None,
+  };
+  Some(s)
+}
+
+fn language_from_path(path: &str) -> Option<String> {
+  Path::new(path).extension().map(|e| {
+    let ext = e.to_string_lossy().to_lowercase();
+    if ext == "md" || ext == "markdown" { "markdown".to_string() } else { ext }
+  })
+}
+
+// strings worth translating contain at least one space and a letter ("Hello world", not "utf-8")
+fn is_prose(s: &str) -> bool {
+  s.contains(' ') && s.chars().any(|c| c.is_alphabetic())
+}
+
+// split source code into verbatim code and translatable comments (and optionally strings)
+fn split_source(src: &str, syntax: &Syntax, translate_strings: bool) -> Vec<Span> {
+  let mut spans = Vec::new();
+  let mut code_start = 0;
+  let mut i = 0;
+  let bytes_len = src.len();
+
+  while i < bytes_len {
+    let rest = &src[i..];
+    if let Some((open, close)) = syntax.block.iter().find(|(open, _)| rest.starts_with(open)) {
+      let body_start = i + open.len();
+      let body_end = src[body_start..].find(close).map(|p| body_start + p).unwrap_or(bytes_len);
+      spans.push(Span::code(&src[code_start..body_start]));
+      spans.push(Span::text(&src[body_start..body_end]));
+      i = (body_end + close.len()).min(bytes_len);
+      code_start = body_end;
+      continue;
+    }
+    if let Some(marker) = syntax.line.iter().find(|m| rest.starts_with(*m)) {
+      let body_start = i + marker.len();
+      let body_end = src[body_start..].find('\n').map(|p| body_start + p).unwrap_or(bytes_len);
+      spans.push(Span::code(&src[code_start..body_start]));
+      spans.push(Span::text(&src[body_start..body_end]));
+      i = body_end;
+      code_start = body_end;
+      continue;
+    }
+    let c = rest.chars().next().unwrap();
+    if syntax.quotes.contains(&c) {
+      // find the closing quote, honouring backslash escapes
+      let body_start = i + c.len_utf8();
+      let mut j = body_start;
+      let mut escaped = false;
+      let mut body_end = bytes_len;
+      for (off, ch) in src[body_start..].char_indices() {
+        if escaped {
+          escaped = false;
+        } else if ch == '\\' {
+          escaped = true;
+        } else if ch == c {
+          body_end = body_start + off;
+          break;
+        } else if ch == '\n' && c != '`' {
+          break;
+        }
+        j = body_start + off + ch.len_utf8();
+      }
+      if body_end == bytes_len {
+        // unterminated: treat as code
+        i = j.max(i + c.len_utf8());
+        continue;
+      }
+      let body = &src[body_start..body_end];
+      if translate_strings && is_prose(body) {
+        spans.push(Span::code(&src[code_start..body_start]));
+        spans.push(Span::text(body));
+        code_start = body_end;
+      }
+      i = body_end + c.len_utf8();
+      continue;
+    }
+    i += c.len_utf8();
+  }
+  spans.push(Span::code(&src[code_start..]));
+  spans
+}
+
+fn inline_pattern() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| Regex::new(r"`[^`\n]+`|https?://[^\s)>\]]+|\]\([^)\n]*\)|<[^>\n]+>").unwrap())
+}
+
+fn marker_pattern() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| Regex::new(r"^\s*(?:#{1,6}\s+|[-*+]\s+(?:\[[ xX]\]\s+)?|\d+[.)]\s+|>\s*)*").unwrap())
+}
+
+// split one Markdown prose line: structural marker stays, inline code/URLs become placeholders
+fn split_markdown_line(line: &str, spans: &mut Vec<Span>) {
+  let marker_end = marker_pattern().find(line).map(|m| m.end()).unwrap_or(0);
+  spans.push(Span::code(&line[..marker_end]));
+  let body = &line[marker_end..];
+  let mut protected = Vec::new();
+  let text = inline_pattern()
+    .replace_all(body, |caps: &regex::Captures| {
+      protected.push(caps[0].to_string());
+      format!("⟦{}⟧", protected.len() - 1)
+    })
+    .into_owned();
+  spans.push(Span { code: false, text, protected });
+}
+
+// prose lines are translated; fenced code is kept, except comments when the fence names a language
+fn split_markdown(src: &str) -> Vec<Span> {
+  let mut spans = Vec::new();
+  let mut fence: Option<(String, Option<Syntax>)> = None;
+  let mut fence_body = String::new();
+
+  for line in src.split_inclusive('\n') {
+    let trimmed = line.trim_start();
+    let is_fence = trimmed.starts_with("```") || trimmed.starts_with("~~~");
+    match (&fence, is_fence) {
+      (None, true) => {
+        spans.push(Span::code(line));
+        let tag = trimmed[3..].trim().to_lowercase();
+        fence = Some((trimmed[..3].to_string(), syntax_for(&tag)));
+      }
+      (Some((marker, syntax)), true) if trimmed.starts_with(marker.as_str()) => {
+        match syntax {
+          Some(sx) => spans.extend(split_source(&fence_body, sx, false)),
+          None => spans.push(Span::code(&fence_body)),
+        }
+        fence_body.clear();
+        spans.push(Span::code(line));
+        fence = None;
+      }
+      (Some(_), _) => fence_body.push_str(line),
+      (None, false) => {
+        let (content, newline) = match line.strip_suffix('\n') {
+          Some(c) => (c, "\n"),
+          None => (line, ""),
+        };
+        if content.trim().is_empty() || content.starts_with("    ") || content.starts_with('\t') || content.trim_start().starts_with('|') {
+          // blank lines, indented code blocks and tables are copied as-is
+          spans.push(Span::code(line));
+        } else {
+          split_markdown_line(content, &mut spans);
+          spans.push(Span::code(newline));
+        }
+      }
+    }
+  }
+  if !fence_body.is_empty() {
+    spans.push(Span::code(&fence_body));
+  }
+  spans
+}
+
+// keep surrounding whitespace of translatable spans verbatim and drop text with nothing to translate
+fn normalize(spans: Vec<Span>) -> Vec<Span> {
+  let mut out: Vec<Span> = Vec::new();
+  let push = |span: Span, out: &mut Vec<Span>| {
+    if span.text.is_empty() {
+      return;
+    }
+    match out.last_mut() {
+      Some(last) if last.code && span.code => last.text.push_str(&span.text),
+      _ => out.push(span),
+    }
+  };
+  for span in spans {
+    if span.code {
+      push(span, &mut out);
+      continue;
+    }
+    let lead_len = span.text.len() - span.text.trim_start().len();
+    let trail_len = span.text.len() - span.text.trim_end().len();
+    let core = span.text.trim();
+    if !core.chars().any(|c| c.is_alphabetic()) || core.chars().all(|c| c == '⟦' || c == '⟧' || c.is_ascii_digit() || c.is_whitespace()) {
+      push(Span::code(&span.text), &mut out);
+      continue;
+    }
+    push(Span::code(&span.text[..lead_len]), &mut out);
+    push(Span { code: false, text: core.to_string(), protected: span.protected }, &mut out);
+    push(Span::code(&span.text[span.text.len() - trail_len..]), &mut out);
+  }
+  out
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct CodeAwareResult {
+  pub text: String,
+  pub language: String,
+  pub translated_spans: usize,
+  // spans left in the source language (e.g. the model dropped a placeholder)
+  pub warnings: Vec<String>,
+}
+
+// the content filter sees each comment or string on its way in and out
+fn translate_spans(
+  window: &Window,
+  spans: Vec<Span>,
+  model_path: &str,
+  config: &ModelConfig,
+  source_lang: &str,
+  target_lang: &str,
+) -> Result<(String, usize, Vec<String>), String> {
+  let mut out = String::new();
+  let mut translated = 0;
+  let mut warnings = Vec::new();
+  for span in spans {
+    if span.code {
+      out.push_str(&span.text);
+      continue;
+    }
+    let (input, _) = filter::check_input(window, span.text.clone())?;
+    let result = translate::translate_text(model_path, config, &input, source_lang, target_lang)?;
+    let (mut result, _) = filter::check_output(window, result)?;
+    // comments must stay on one line when they came from one
+    if !span.text.contains('\n') {
+      result = result.lines().map(|l| l.trim()).filter(|l| !l.is_empty()).collect::<Vec<_>>().join(" ");
+    }
+    let mut ok = true;
+    for (n, original) in span.protected.iter().enumerate() {
+      let placeholder = format!("⟦{}⟧", n);
+      if result.contains(&placeholder) {
+        result = result.replacen(&placeholder, original, 1);
+      } else {
+        ok = false;
+      }
+    }
+    if ok {
+      translated += 1;
+      out.push_str(&result);
+    } else {
+      let mut original = span.text.clone();
+      for (n, p) in span.protected.iter().enumerate() {
+        original = original.replacen(&format!("⟦{}⟧", n), p, 1);
+      }
+      warnings.push(format!("kept untranslated (inline code lost): {}", original));
+      out.push_str(&original);
+    }
+  }
+  Ok((out, translated, warnings))
+}
+
+// ------------------ Tauri commands ------------------
+
+// translate only prose in Markdown, or only comments (and optionally strings) in source code;
+// `language` overrides detection from the file extension ("markdown", "rust", "py", ...)
+#[tauri::command(async)]
+pub fn translate_code_aware(
+  text_or_path: String,
+  source_lang: String,
+  target_lang: String,
+  language: Option<String>,
+  translate_strings: Option<bool>,
+  model_id: Option<String>,
+  window: Window
+) -> Result<CodeAwareResult, AppError> {
+  let is_path = !text_or_path.contains('\n') && Path::new(&text_or_path).is_file();
+  let (src, detected) = if is_path {
+    let src = fs::read_to_string(&text_or_path).map_err(|e| AppError::io("read", &text_or_path, e))?;
+    (src, language_from_path(&text_or_path))
+  } else {
+    (text_or_path, None)
+  };
+  let language = language.map(|l| l.to_lowercase()).or(detected).unwrap_or_else(|| "markdown".into());
+
+  let spans = if language == "markdown" || language == "md" {
+    split_markdown(&src)
+  } else {
+    let syntax = syntax_for(&language).ok_or(format!("Unsupported language '{}'", language))?;
+    split_source(&src, &syntax, translate_strings.unwrap_or(false))
+  };
+
+  let (model, config) = window.state::<Mutex<ModelManager>>().locked().model_for_request(model_id.as_deref())?;
+  preload::record(&window, "translate", &preload::pair(&source_lang, &target_lang), &model.id);
+  let (text, translated_spans, warnings) = translate_spans(&window, normalize(spans), &model.path, &config, &source_lang, &target_lang)?;
+  Ok(CodeAwareResult { text, language, translated_spans, warnings })
+}
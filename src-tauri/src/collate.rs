@@ -0,0 +1,38 @@
+// src-tauri/src/collate.rs
+use icu_collator::options::CollatorOptions;
+use icu_collator::{Collator, CollatorBorrowed};
+use icu_locale_core::Locale;
+
+use crate::events;
+use crate::settings;
+
+fn parse_locale(tag: &str) -> Result<Locale, String> {
+  Locale::try_from_str(&tag.trim().replace('_', "-")).map_err(|e| format!("'{}' is not a locale: {}", tag, e))
+}
+
+pub fn check_locale(tag: &str) -> Result<(), String> {
+  parse_locale(tag).map(|_| ())
+}
+
+// collator for `locale`, else the UI language, else the root order (which already puts "Ä" with
+// "A" and kana in gojūon order); a tag ICU can't use falls back to the root order
+fn collator(locale: Option<&str>) -> CollatorBorrowed<'static> {
+  let tag = locale.map(|l| l.to_string()).or(settings::get().ui_lang);
+  let locale = match tag.as_deref().map(parse_locale) {
+    Some(Ok(locale)) => locale,
+    Some(Err(e)) => {
+      events::log(format!("sorting in root order: {}", e));
+      Locale::UNKNOWN
+    }
+    None => Locale::UNKNOWN,
+  };
+  Collator::try_new((&locale).into(), CollatorOptions::default())
+    .or_else(|_| Collator::try_new((&Locale::UNKNOWN).into(), CollatorOptions::default()))
+    .expect("root collation data is compiled in")
+}
+
+// sort `items` by the text `key` gives, in the order readers of `locale` expect
+pub fn sort_by<T>(items: &mut [T], locale: Option<&str>, key: impl Fn(&T) -> &str) {
+  let collator = collator(locale);
+  items.sort_by(|a, b| collator.compare(key(a), key(b)));
+}
@@ -0,0 +1,114 @@
+// src-tauri/src/compose.rs
+use std::sync::Mutex;
+
+use tauri::{Manager, Window};
+
+use crate::engine;
+use crate::error::{AppError, LockExt};
+use crate::filter::{self, FilterReport};
+use crate::lang;
+use crate::output::{self, OutputFormat};
+use crate::preload;
+use crate::readable::{self, ReadingOptions};
+use crate::ModelManager;
+
+fn formality_rules(formality: &str) -> Result<&'static str, String> {
+  match formality.to_lowercase().as_str() {
+    "formal" => Ok("Use a formal register: polite forms of address (e.g. Sie, vous, usted), a formal greeting and closing."),
+    "neutral" => Ok("Use a polite but neutral, businesslike register."),
+    "informal" => Ok("Use a friendly, informal register as between colleagues or friends, with a casual greeting and closing."),
+    other => Err(format!("Unsupported formality '{}', expected formal, neutral or informal", other)),
+  }
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ComposeResult {
+  pub subject: String,
+  pub body: String,
+  // what the content filter changed, if enabled
+  pub filter: Vec<FilterReport>,
+}
+
+fn build_prompt(intent: &str, lang_name: &str, rules: &str, points: &[String]) -> String {
+  let points = if points.is_empty() {
+    String::new()
+  } else {
+    let list: Vec<String> = points.iter().map(|p| format!("- {}", p)).collect();
+    format!("\nThe message must cover these points:\n{}\n", list.join("\n"))
+  };
+  format!(
+    "Write an email or letter directly in {lang}, as a native speaker would write it (do not translate from English). \
+Purpose: {intent}\n{rules}{points}\n\
+Reply with JSON only, in the form {{\"subject\": \"...\", \"body\": \"...\"}}, where body is the full message including greeting and closing.",
+    lang = lang_name,
+    intent = intent,
+    rules = rules,
+    points = points
+  )
+}
+
+// take subject/body from the JSON reply, or from a "Subject:" line when the model ignored the format
+fn parse_reply(output: &str) -> ComposeResult {
+  if let Some(json) = engine::extract_json(output) {
+    let field = |k: &str| json.get(k).and_then(|v| v.as_str()).map(|s| s.trim().to_string());
+    if let Some(body) = field("body") {
+      return ComposeResult { subject: field("subject").unwrap_or_default(), body, filter: Vec::new() };
+    }
+  }
+  let text = output.trim();
+  if let Some((first, rest)) = text.split_once('\n') {
+    if let Some((label, subject)) = first.split_once(':') {
+      if label.trim().eq_ignore_ascii_case("subject") {
+        return ComposeResult { subject: subject.trim().to_string(), body: rest.trim().to_string(), filter: Vec::new() };
+      }
+    }
+  }
+  ComposeResult { subject: String::new(), body: text.to_string(), filter: Vec::new() }
+}
+
+// ------------------ Tauri commands ------------------
+
+// draft an email/letter natively in `target_lang` from an intent and a list of points
+#[tauri::command(async)]
+#[allow(clippy::too_many_arguments)]
+pub fn compose(
+  intent: String,
+  target_lang: String,
+  formality: String,
+  points: Vec<String>,
+  model_id: Option<String>,
+  output_format: Option<OutputFormat>,
+  reading: Option<ReadingOptions>,
+  window: Window,
+) -> Result<ComposeResult, AppError> {
+  if intent.trim().is_empty() {
+    return Err(AppError::InvalidInput("Intent must not be empty".into()));
+  }
+  let rules = formality_rules(&formality)?;
+  let mut reports = Vec::new();
+  let (intent, report) = filter::check_input(&window, intent)?;
+  reports.extend(report);
+  let mut points = points;
+  for point in points.iter_mut() {
+    let (checked, report) = filter::check_input(&window, std::mem::take(point))?;
+    *point = checked;
+    reports.extend(report);
+  }
+  let (model, config) = window.state::<Mutex<ModelManager>>().locked().model_for_request(model_id.as_deref())?;
+  preload::record(&window, "compose", &target_lang, &model.id);
+  let prompt = build_prompt(&intent, &lang::language_name(&target_lang), rules, &points);
+  let max_tokens = 512 + points.iter().map(|p| engine::estimate_tokens(p) * 4).sum::<u32>();
+  let out = engine::generate(&model.path, &config, &prompt, max_tokens)?;
+  let mut result = parse_reply(&out);
+  if result.body.is_empty() {
+    return Err("The model returned an empty draft".into());
+  }
+  let (subject, report) = filter::check_output(&window, result.subject)?;
+  reports.extend(report);
+  let (body, report) = filter::check_output(&window, result.body)?;
+  reports.extend(report);
+  result.subject = subject;
+  result.body = output::apply(readable::apply(body, reading.as_ref(), &target_lang), output_format, &target_lang, &model.path, &config);
+  result.filter = reports;
+  Ok(result)
+}
@@ -0,0 +1,107 @@
+// src-tauri/src/confidence.rs
+use std::sync::Mutex;
+
+use tauri::{Manager, Window};
+
+use crate::chunk;
+use crate::engine::{self, TokenLogprob};
+use crate::error::{AppError, LockExt};
+use crate::ModelManager;
+
+// segments whose geometric-mean token probability falls below this are flagged for review
+pub const LOW_CONFIDENCE: f32 = 0.5;
+
+// Confidence of one sentence of the output (char offsets into the text)
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct SegmentConfidence {
+  pub start: usize,
+  pub end: usize,
+  pub text: String,
+  // geometric mean of token probabilities, 0..1
+  pub confidence: f32,
+  // least likely token in the segment
+  pub min_prob: f32,
+  pub low: bool,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ConfidenceReport {
+  pub overall: f32,
+  pub segments: Vec<SegmentConfidence>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ScoredGeneration {
+  pub text: String,
+  // None when the backend doesn't expose logprobs
+  pub confidence: Option<ConfidenceReport>,
+}
+
+fn geometric_mean(logprobs: &[f32]) -> f32 {
+  if logprobs.is_empty() {
+    return 1.0;
+  }
+  (logprobs.iter().sum::<f32>() / logprobs.len() as f32).exp()
+}
+
+// Score each sentence of `text` from the tokens that produced it.
+// Tokens are expected to concatenate to the (untrimmed) output; leading whitespace is skipped.
+pub fn score(text: &str, tokens: &[TokenLogprob]) -> Option<ConfidenceReport> {
+  if tokens.is_empty() {
+    return None;
+  }
+  let joined: String = tokens.iter().map(|t| t.token.as_str()).collect();
+  let lead = joined.chars().take_while(|c| c.is_whitespace()).count() as isize;
+
+  // (mid char offset in `text`, logprob) per token
+  let mut pos = 0isize;
+  let positioned: Vec<(isize, f32)> = tokens
+    .iter()
+    .map(|t| {
+      let len = t.token.chars().count() as isize;
+      let mid = pos + len / 2 - lead;
+      pos += len;
+      (mid, t.logprob)
+    })
+    .collect();
+
+  let mut segments = Vec::new();
+  let mut start = 0usize;
+  for sentence in chunk::sentences(text) {
+    let len = sentence.chars().count();
+    let end = start + len;
+    let lps: Vec<f32> = positioned
+      .iter()
+      .filter(|(mid, _)| *mid >= start as isize && *mid < end as isize)
+      .map(|(_, lp)| *lp)
+      .collect();
+    if !sentence.trim().is_empty() {
+      let confidence = geometric_mean(&lps);
+      let min_prob = lps.iter().cloned().fold(0.0f32, f32::min).exp();
+      segments.push(SegmentConfidence {
+        start,
+        end,
+        text: sentence.trim_end().to_string(),
+        confidence,
+        min_prob,
+        low: confidence < LOW_CONFIDENCE,
+      });
+    }
+    start = end;
+  }
+
+  let all: Vec<f32> = tokens.iter().map(|t| t.logprob).collect();
+  Some(ConfidenceReport { overall: geometric_mean(&all), segments })
+}
+
+// ------------------ Tauri commands ------------------
+
+// run a prompt and score the answer per sentence
+#[tauri::command(async)]
+pub fn generate_scored(prompt: String, model_id: Option<String>, window: Window) -> Result<ScoredGeneration, AppError> {
+  let (model, config) = window.state::<Mutex<ModelManager>>().locked().model_for_request(model_id.as_deref())?;
+  let max_tokens = engine::estimate_tokens(&prompt) * 2 + 256;
+  let g = engine::generate_detailed(&model.path, &config, &prompt, max_tokens)?;
+  let confidence = g.logprobs.as_deref().and_then(|lp| score(&g.text, lp));
+  Ok(ScoredGeneration { text: g.text, confidence })
+}
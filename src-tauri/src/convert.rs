@@ -0,0 +1,319 @@
+// src-tauri/src/convert.rs
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use regex::{Captures, Regex};
+
+use crate::error::{AppError, LockExt};
+use crate::localize::{self, LocalizeChange};
+use crate::store;
+
+// rates older than this are reported as stale
+const STALE_AFTER_SECS: i64 = 30 * 24 * 3600;
+
+// approximate USD rates used until the user imports a current table
+const DEFAULT_RATES: &[(&str, f64)] = &[
+  ("USD", 1.0),
+  ("EUR", 0.92),
+  ("GBP", 0.79),
+  ("JPY", 150.0),
+  ("CNY", 7.2),
+  ("INR", 83.0),
+  ("KRW", 1350.0),
+  ("RUB", 92.0),
+  ("CHF", 0.88),
+  ("CAD", 1.36),
+  ("AUD", 1.52),
+  ("BRL", 5.0),
+  ("MXN", 17.0),
+  ("TRY", 32.0),
+  ("PLN", 4.0),
+  ("SEK", 10.5),
+  ("UAH", 39.0),
+  ("ILS", 3.7),
+  ("SAR", 3.75),
+  ("AED", 3.67),
+  ("THB", 36.0),
+  ("VND", 25000.0),
+  ("IDR", 15800.0),
+];
+
+// symbol -> ISO code; "$" is read as US dollars
+const SYMBOLS: &[(&str, &str)] = &[("$", "USD"), ("€", "EUR"), ("£", "GBP"), ("¥", "JPY"), ("₹", "INR"), ("₩", "KRW"), ("₽", "RUB")];
+
+// Exchange rates relative to `base`, persisted in ./data/rates.json
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct RateTable {
+  pub base: String,
+  // unix seconds of the last import; 0 for the built-in table
+  pub updated: i64,
+  pub source: String,
+  pub rates: HashMap<String, f64>,
+}
+
+impl Default for RateTable {
+  fn default() -> Self {
+    Self {
+      base: "USD".into(),
+      updated: 0,
+      source: "built-in (approximate)".into(),
+      rates: DEFAULT_RATES.iter().map(|(c, r)| (c.to_string(), *r)).collect(),
+    }
+  }
+}
+
+impl RateTable {
+  pub fn is_stale(&self) -> bool {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0);
+    now - self.updated > STALE_AFTER_SECS
+  }
+
+  fn convert(&self, amount: f64, from: &str, to: &str) -> Option<f64> {
+    let (f, t) = (self.rates.get(from)?, self.rates.get(to)?);
+    (*f > 0.0).then(|| amount / f * t)
+  }
+}
+
+// Rate table managed by Tauri
+pub struct Rates {
+  path: PathBuf,
+  pub table: RateTable,
+}
+
+impl Rates {
+  pub fn load() -> Self {
+    let path = store::data_file("rates.json");
+    let table = store::load_json(&path);
+    Self { path, table }
+  }
+
+  // replace the table; rates may use any base currency
+  pub fn import(&mut self, mut table: RateTable, source: &str) -> Result<RateTable, String> {
+    table.base = table.base.to_uppercase();
+    table.rates = table.rates.into_iter().map(|(c, r)| (c.to_uppercase(), r)).filter(|(_, r)| *r > 0.0).collect();
+    table.rates.insert(table.base.clone(), 1.0);
+    if table.rates.len() < 2 {
+      return Err("Rate table contains no usable rates".into());
+    }
+    table.updated = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0);
+    table.source = source.to_string();
+    self.table = table;
+    store::save_json(&self.path, &self.table)?;
+    Ok(self.table.clone())
+  }
+}
+
+// currency used in a locale: region first ("de-CH" -> CHF), then language
+fn locale_currency(locale: &str) -> Option<&'static str> {
+  let lower = locale.to_lowercase().replace('_', "-");
+  let mut parts = lower.split('-');
+  let base = parts.next().unwrap_or("");
+  let by_region = match parts.next().unwrap_or("") {
+    "us" => Some("USD"),
+    "gb" | "uk" => Some("GBP"),
+    "in" => Some("INR"),
+    "jp" => Some("JPY"),
+    "cn" => Some("CNY"),
+    "kr" => Some("KRW"),
+    "ru" => Some("RUB"),
+    "ch" => Some("CHF"),
+    "ca" => Some("CAD"),
+    "au" => Some("AUD"),
+    "br" => Some("BRL"),
+    "mx" => Some("MXN"),
+    "de" | "fr" | "es" | "it" | "nl" | "at" | "be" | "pt" | "fi" | "ie" | "gr" => Some("EUR"),
+    _ => None,
+  };
+  by_region.or(match base {
+    "en" => Some("USD"),
+    "de" | "fr" | "es" | "it" | "nl" | "pt" | "fi" | "el" => Some("EUR"),
+    "ja" => Some("JPY"),
+    "zh" => Some("CNY"),
+    "ko" => Some("KRW"),
+    "hi" | "mr" | "ta" | "te" | "ml" | "bn" => Some("INR"),
+    "ru" => Some("RUB"),
+    "tr" => Some("TRY"),
+    "pl" => Some("PLN"),
+    "sv" => Some("SEK"),
+    "uk" => Some("UAH"),
+    "he" => Some("ILS"),
+    "th" => Some("THB"),
+    "vi" => Some("VND"),
+    "id" => Some("IDR"),
+    _ => None,
+  })
+}
+
+fn currency_symbol(code: &str) -> &str {
+  SYMBOLS.iter().find(|(_, c)| *c == code).map(|(s, _)| *s).unwrap_or(code)
+}
+
+// the US (plus Liberia and Myanmar) measure in imperial units
+fn uses_imperial(locale: &str) -> bool {
+  matches!(locale.to_lowercase().replace('_', "-").as_str(), "en" | "en-us" | "en-lr" | "my" | "my-mm")
+}
+
+// unit aliases -> (display symbol, is metric, target symbol, factor); temperatures are special-cased
+const UNITS: &[(&[&str], &str, bool, &str, f64)] = &[
+  (&["mph"], "mph", false, "km/h", 1.609_344),
+  (&["km/h", "kmh"], "km/h", true, "mph", 1.0 / 1.609_344),
+  (&["mi", "mile", "miles"], "mi", false, "km", 1.609_344),
+  (&["km", "kilometer", "kilometers", "kilometre", "kilometres"], "km", true, "mi", 1.0 / 1.609_344),
+  (&["ft", "foot", "feet"], "ft", false, "m", 0.3048),
+  (&["yd", "yard", "yards"], "yd", false, "m", 0.9144),
+  (&["inch", "inches"], "in", false, "cm", 2.54),
+  (&["m", "meter", "meters", "metre", "metres"], "m", true, "ft", 1.0 / 0.3048),
+  (&["cm", "centimeter", "centimeters", "centimetre", "centimetres"], "cm", true, "in", 1.0 / 2.54),
+  (&["lb", "lbs", "pound", "pounds"], "lb", false, "kg", 0.453_592_37),
+  (&["oz", "ounce", "ounces"], "oz", false, "g", 28.349_523),
+  (&["kg", "kilogram", "kilograms"], "kg", true, "lb", 1.0 / 0.453_592_37),
+  (&["g", "gram", "grams"], "g", true, "oz", 1.0 / 28.349_523),
+  (&["gal", "gallon", "gallons"], "gal", false, "L", 3.785_411_8),
+  (&["l", "L", "liter", "liters", "litre", "litres"], "L", true, "gal", 1.0 / 3.785_411_8),
+  (&["°F"], "°F", false, "°C", 0.0),
+  (&["°C"], "°C", true, "°F", 0.0),
+];
+
+fn pattern() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| {
+    let num = r"\d{1,3}(?:[,.\u{a0}\u{202f}]\d{3})+(?:[.,]\d+)?|\d+(?:[.,]\d+)?";
+    let mut units: Vec<&str> = UNITS.iter().flat_map(|(aliases, ..)| aliases.iter().copied()).collect();
+    // longest first so "km/h" wins over "km"
+    units.sort_by_key(|u| std::cmp::Reverse(u.len()));
+    let units: Vec<String> = units.iter().map(|u| regex::escape(u)).collect();
+    let codes: Vec<&str> = DEFAULT_RATES.iter().map(|(c, _)| *c).collect();
+    let symbols: Vec<String> = SYMBOLS.iter().map(|(s, _)| regex::escape(s)).collect();
+    let cur = format!("{}|{}", symbols.join("|"), codes.join("|"));
+    Regex::new(&format!(
+      r"(?P<cur_pre>{cur})\s?(?P<num_pre>{num})|(?P<num_post>{num})\s?(?P<cur_post>{cur})\b|(?P<num_unit>{num})\s?(?P<unit>{units})(?:\b|$)",
+      cur = cur,
+      num = num,
+      units = units.join("|")
+    ))
+    .unwrap()
+  })
+}
+
+// two significant decimals for small values, fewer as values grow
+fn round_for_display(value: f64) -> usize {
+  match value.abs() {
+    v if v >= 100.0 => 0,
+    v if v >= 10.0 => 1,
+    _ => 2,
+  }
+}
+
+fn display(value: f64, locale: &str) -> String {
+  let s = localize::format_decimal(value, round_for_display(value), locale);
+  let decimal = localize::format_decimal(0.5, 1, locale).chars().nth(1).unwrap_or('.');
+  if s.contains(decimal) {
+    s.trim_end_matches('0').trim_end_matches(decimal).to_string()
+  } else {
+    s
+  }
+}
+
+// numbers in the input may follow the source or target conventions, else decimal point or comma
+fn parse_amount(s: &str, source: Option<&str>, target: &str) -> Option<f64> {
+  source
+    .and_then(|l| localize::parse_decimal(s, l))
+    .or_else(|| localize::parse_decimal(s, target))
+    .or_else(|| localize::parse_decimal(s, "en"))
+    .or_else(|| localize::parse_decimal(s, "de"))
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ConvertResult {
+  pub text: String,
+  pub changes: Vec<LocalizeChange>,
+  // when the exchange-rate table was last updated (0 = built-in approximations)
+  pub rates_updated: i64,
+  pub rates_stale: bool,
+}
+
+// annotate amounts and measurements with their value for `target` locale: "5 miles (8 km)"
+pub fn convert(text: &str, source: Option<&str>, target: &str, rates: &RateTable) -> ConvertResult {
+  let target_currency = locale_currency(target);
+  let imperial = uses_imperial(target);
+  let mut changes = Vec::new();
+
+  let out = pattern().replace_all(text, |caps: &Captures| {
+    let original = caps[0].to_string();
+    let converted = if let Some(num) = caps.name("num_pre").or(caps.name("num_post")) {
+      let cur = caps.name("cur_pre").or(caps.name("cur_post")).map(|m| m.as_str()).unwrap_or("");
+      let code = SYMBOLS.iter().find(|(s, _)| *s == cur).map(|(_, c)| *c).unwrap_or(cur);
+      target_currency.filter(|t| *t != code).and_then(|to| {
+        let amount = parse_amount(num.as_str(), source, target)?;
+        let value = rates.convert(amount, code, to)?;
+        let decimals = if value.abs() >= 1000.0 { 0 } else { 2 };
+        let formatted = localize::format_currency(&localize::format_decimal(value, decimals, target), currency_symbol(to), target);
+        Some(("exchange", formatted))
+      })
+    } else {
+      let num = caps.name("num_unit").map(|m| m.as_str()).unwrap_or("");
+      let unit = caps.name("unit").map(|m| m.as_str()).unwrap_or("");
+      UNITS
+        .iter()
+        .find(|(aliases, ..)| aliases.contains(&unit))
+        .filter(|(_, _, metric, ..)| *metric == imperial)
+        .and_then(|(_, symbol, _, to_symbol, factor)| {
+          let value = parse_amount(num, source, target)?;
+          let out = match *symbol {
+            "°F" => (value - 32.0) * 5.0 / 9.0,
+            "°C" => value * 9.0 / 5.0 + 32.0,
+            _ => value * factor,
+          };
+          let gap = if to_symbol.starts_with('°') { "" } else { "\u{a0}" };
+          Some(("unit", format!("{}{}{}", display(out, target), gap, to_symbol)))
+        })
+    };
+
+    match converted {
+      Some((kind, value)) => {
+        let replacement = format!("{} ({})", original, value);
+        changes.push(LocalizeChange { kind: kind.into(), original, replacement: replacement.clone() });
+        replacement
+      }
+      None => original,
+    }
+  });
+
+  ConvertResult { text: out.into_owned(), changes, rates_updated: rates.updated, rates_stale: rates.is_stale() }
+}
+
+// ------------------ Tauri commands ------------------
+
+// standalone converter: "20 USD", "5 miles" or free text with several amounts
+#[tauri::command]
+pub fn convert_units(
+  text_or_value: String,
+  target_locale: String,
+  source_locale: Option<String>,
+  rates: tauri::State<'_, Mutex<Rates>>
+) -> ConvertResult {
+  let rates = rates.locked();
+  convert(&text_or_value, source_locale.as_deref(), &target_locale, &rates.table)
+}
+
+#[tauri::command]
+pub fn get_exchange_rates(rates: tauri::State<'_, Mutex<Rates>>) -> RateTable {
+  rates.locked().table.clone()
+}
+
+// import a rate table from a JSON file: {"base": "EUR", "rates": {"USD": 1.08, ...}}
+#[tauri::command]
+pub fn import_exchange_rates(path: String, rates: tauri::State<'_, Mutex<Rates>>) -> Result<RateTable, AppError> {
+  #[derive(serde::Deserialize)]
+  struct RateFile {
+    base: String,
+    rates: HashMap<String, f64>,
+  }
+  let json = fs::read_to_string(&path).map_err(|e| AppError::io("read", &path, e))?;
+  let file: RateFile = serde_json::from_str(&json).map_err(|e| format!("invalid rate file {}: {}", path, e))?;
+  let table = RateTable { base: file.base, updated: 0, source: path.clone(), rates: file.rates };
+  Ok(rates.locked().import(table, &path)?)
+}
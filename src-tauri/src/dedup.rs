@@ -0,0 +1,262 @@
+// src-tauri/src/dedup.rs
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::UNIX_EPOCH;
+
+use ring::digest::{Context, SHA256};
+use tauri::Manager;
+
+use crate::error::{AppError, LockExt};
+use crate::events;
+use crate::store;
+use crate::ModelManager;
+
+// the latest background grouping; an older one finishing late is dropped
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+// A file's hash, reused while its size and modification time stay the same
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+struct CachedHash {
+  size: u64,
+  modified: u64,
+  hash: String,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct DedupReport {
+  // model ids whose files now link to another copy
+  pub linked: Vec<String>,
+  pub reclaimed_bytes: u64,
+  pub errors: Vec<String>,
+}
+
+// (path relative to the model, full path) of each file
+type ModelFiles = Vec<(PathBuf, PathBuf)>;
+
+// every file of a model with its path relative to the model (a single file has an empty relative path)
+fn model_files(path: &Path) -> ModelFiles {
+  if path.is_file() {
+    return vec![(PathBuf::new(), path.to_path_buf())];
+  }
+  let mut files = Vec::new();
+  let mut stack = vec![path.to_path_buf()];
+  while let Some(dir) = stack.pop() {
+    for entry in fs::read_dir(&dir).into_iter().flatten().flatten() {
+      let p = entry.path();
+      if p.is_dir() {
+        stack.push(p);
+      } else if let Ok(rel) = p.strip_prefix(path) {
+        files.push((rel.to_path_buf(), p));
+      }
+    }
+  }
+  files.sort();
+  files
+}
+
+fn total_size(files: &[(PathBuf, PathBuf)]) -> u64 {
+  files.iter().filter_map(|(_, p)| fs::metadata(p).ok()).map(|m| m.len()).sum()
+}
+
+fn file_hash(path: &Path, cache: &mut HashMap<String, CachedHash>) -> Option<String> {
+  let meta = fs::metadata(path).ok()?;
+  let modified = meta.modified().ok().and_then(|t| t.duration_since(UNIX_EPOCH).ok()).map(|d| d.as_secs()).unwrap_or(0);
+  let key = path.to_string_lossy().to_string();
+  if let Some(c) = cache.get(&key).filter(|c| c.size == meta.len() && c.modified == modified) {
+    return Some(c.hash.clone());
+  }
+  let mut file = File::open(path).ok()?;
+  let mut ctx = Context::new(&SHA256);
+  let mut buf = vec![0u8; 1 << 20];
+  loop {
+    let n = file.read(&mut buf).ok()?;
+    if n == 0 {
+      break;
+    }
+    ctx.update(&buf[..n]);
+  }
+  let hash: String = ctx.finish().as_ref().iter().map(|b| format!("{:02x}", b)).collect();
+  cache.insert(key, CachedHash { size: meta.len(), modified, hash: hash.clone() });
+  Some(hash)
+}
+
+// content hash of a whole model: its files' hashes and relative paths
+fn model_hash(files: &[(PathBuf, PathBuf)], cache: &mut HashMap<String, CachedHash>) -> Option<String> {
+  let mut ctx = Context::new(&SHA256);
+  for (rel, path) in files {
+    ctx.update(rel.to_string_lossy().as_bytes());
+    ctx.update(file_hash(path, cache)?.as_bytes());
+  }
+  Some(ctx.finish().as_ref().iter().take(8).map(|b| format!("{:02x}", b)).collect())
+}
+
+// group id (short content hash) for every model that has an identical copy under another id.
+// Only models whose total size matches another one are hashed, and hashes are cached between scans
+pub fn duplicate_groups(models: &[(String, PathBuf)]) -> HashMap<String, String> {
+  let mut by_size: HashMap<u64, Vec<(&String, ModelFiles)>> = HashMap::new();
+  for (id, path) in models {
+    let files = model_files(path);
+    let size = total_size(&files);
+    if size > 0 {
+      by_size.entry(size).or_default().push((id, files));
+    }
+  }
+
+  let cache_path = store::data_file("model_hashes.json");
+  let mut cache: HashMap<String, CachedHash> = store::load_json(&cache_path);
+  let mut by_hash: HashMap<String, Vec<String>> = HashMap::new();
+  for candidates in by_size.values().filter(|c| c.len() > 1) {
+    for (id, files) in candidates {
+      if let Some(hash) = model_hash(files, &mut cache) {
+        by_hash.entry(hash).or_default().push(id.to_string());
+      }
+    }
+  }
+  let _ = store::save_json(&cache_path, &cache);
+
+  by_hash
+    .into_iter()
+    .filter(|(_, ids)| ids.len() > 1)
+    .flat_map(|(hash, ids)| ids.into_iter().map(move |id| (id, hash.clone())))
+    .collect()
+}
+
+// hash `models` on a background thread (hashing large models takes minutes, so never under the
+// manager lock) and set the groups on the models whose path is still the same. Sends
+// "duplicate-groups" (model id -> group) when done
+pub fn group_in_background(models: Vec<(String, PathBuf)>) {
+  let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+  thread::spawn(move || {
+    let groups = duplicate_groups(&models);
+    let app = events::wait_app();
+    if GENERATION.load(Ordering::SeqCst) != generation {
+      return;
+    }
+    {
+      let state = app.state::<Mutex<ModelManager>>();
+      let mut mgr = state.locked();
+      for (id, path) in &models {
+        if let Some(m) = mgr.models.get_mut(id).filter(|m| Path::new(&m.path) == path) {
+          m.duplicate_group = groups.get(id).cloned();
+        }
+      }
+    }
+    events::emit_global("duplicate-groups", groups);
+  });
+}
+
+// whether two files have the same bytes
+fn same_content(a: &Path, b: &Path) -> bool {
+  let (Ok(mut x), Ok(mut y)) = (File::open(a), File::open(b)) else { return false };
+  if x.metadata().map(|m| m.len()).ok() != y.metadata().map(|m| m.len()).ok() {
+    return false;
+  }
+  let (mut bx, mut by) = (vec![0u8; 1 << 20], vec![0u8; 1 << 20]);
+  loop {
+    let Ok(n) = x.read(&mut bx) else { return false };
+    if n == 0 {
+      return true;
+    }
+    if y.read_exact(&mut by[..n]).is_err() || bx[..n] != by[..n] {
+      return false;
+    }
+  }
+}
+
+// whether two paths already share storage
+#[cfg(unix)]
+fn same_file(a: &Path, b: &Path) -> bool {
+  use std::os::unix::fs::MetadataExt;
+  match (fs::metadata(a), fs::metadata(b)) {
+    (Ok(x), Ok(y)) => x.dev() == y.dev() && x.ino() == y.ino(),
+    _ => false,
+  }
+}
+
+#[cfg(not(unix))]
+fn same_file(a: &Path, b: &Path) -> bool {
+  fs::canonicalize(a).ok().is_some_and(|x| fs::canonicalize(b).ok() == Some(x))
+}
+
+// replace `copy` with a link to `keep`: a hard link when both are on one volume, a symlink otherwise.
+// The link is made next to the copy and renamed over it, so a failure leaves the copy in place
+fn link_over(keep: &Path, copy: &Path) -> Result<(), String> {
+  let tmp = copy.with_extension("dedup-tmp");
+  let _ = fs::remove_file(&tmp);
+  let linked = fs::hard_link(keep, &tmp).or_else(|_| {
+    let target = fs::canonicalize(keep)?;
+    #[cfg(unix)]
+    let symlink = std::os::unix::fs::symlink(target, &tmp);
+    #[cfg(windows)]
+    let symlink = std::os::windows::fs::symlink_file(target, &tmp);
+    symlink
+  });
+  linked.map_err(|e| format!("failed to link {} to {}: {}", copy.to_string_lossy(), keep.to_string_lossy(), e))?;
+  fs::rename(&tmp, copy).map_err(|e| {
+    let _ = fs::remove_file(&tmp);
+    format!("failed to replace {}: {}", copy.to_string_lossy(), e)
+  })
+}
+
+// ------------------ Tauri commands ------------------
+
+// replace duplicate model copies with links to one of them (the loaded model is kept as the original)
+#[tauri::command(async)]
+pub fn deduplicate_models(state: tauri::State<'_, Mutex<ModelManager>>) -> Result<DedupReport, AppError> {
+  let (mut groups, loaded) = {
+    let mgr = state.locked();
+    let mut groups: HashMap<String, Vec<(String, PathBuf)>> = HashMap::new();
+    for m in mgr.models.values() {
+      if let Some(group) = &m.duplicate_group {
+        groups.entry(group.clone()).or_default().push((m.id.clone(), PathBuf::from(&m.path)));
+      }
+    }
+    (groups, mgr.loaded.clone())
+  };
+
+  let mut report = DedupReport { linked: Vec::new(), reclaimed_bytes: 0, errors: Vec::new() };
+  for members in groups.values_mut() {
+    members.sort_by_key(|(id, _)| (Some(id) != loaded.as_ref(), id.clone()));
+    let (keep_id, keep_path) = members[0].clone();
+    let keep_files = model_files(&keep_path);
+    for (id, path) in &members[1..] {
+      // the groups come from the last scan; the files may have changed since, so every pair is
+      // compared again before one replaces the other
+      let files = model_files(path);
+      if files.len() != keep_files.len() || files.iter().zip(&keep_files).any(|((a, _), (b, _))| a != b) {
+        report.errors.push(format!("{}: no longer the same files as {}", id, keep_id));
+        continue;
+      }
+      let mut reclaimed = 0;
+      let mut failed = false;
+      for ((rel, copy), (_, original)) in files.iter().zip(&keep_files) {
+        if same_file(copy, original) {
+          continue;
+        }
+        if !same_content(copy, original) {
+          report.errors.push(format!("{} ({}): no longer the same as {}", id, rel.to_string_lossy(), keep_id));
+          failed = true;
+          continue;
+        }
+        let size = fs::metadata(copy).map(|m| m.len()).unwrap_or(0);
+        match link_over(original, copy) {
+          Ok(()) => reclaimed += size,
+          Err(e) => {
+            report.errors.push(format!("{} ({}): {}", id, rel.to_string_lossy(), e));
+            failed = true;
+          }
+        }
+      }
+      if reclaimed > 0 && !failed {
+        report.linked.push(format!("{} -> {}", id, keep_id));
+      }
+      report.reclaimed_bytes += reclaimed;
+    }
+  }
+  Ok(report)
+}
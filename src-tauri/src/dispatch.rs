@@ -0,0 +1,289 @@
+// src-tauri/src/dispatch.rs
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::error::LockExt;
+
+// fewest tokens a deadline can cut a generation down to
+const MIN_TOKENS: u32 = 16;
+// weight of the newest measurement in a model's running tokens/sec
+const SPEED_WEIGHT: f32 = 0.3;
+
+// Who is asking for the model; higher priorities are served first and preempt batch work
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+  // document jobs, scheduled and watched-folder translations
+  Batch,
+  // interpreter turns, proxied web pages
+  Live,
+  // chat and one-off requests from the UI
+  Interactive,
+}
+
+// Scheduling hints a caller can attach to a request
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct RequestHints {
+  // defaults to the caller's own priority
+  pub priority: Option<Priority>,
+  // soft deadline from the start of the request; output is shortened or a faster model used to meet it
+  pub deadline_ms: Option<u64>,
+}
+
+// A change made to meet a deadline, reported with the result
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Adjustment {
+  // "max_tokens" or "model"
+  pub kind: String,
+  pub from: String,
+  pub to: String,
+  // expected duration before the change, in ms
+  pub expected_ms: u64,
+  pub budget_ms: u64,
+}
+
+// Payload of the get_dispatch_status command: one per model that has requests running or waiting
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct DispatchStatus {
+  pub model_path: String,
+  pub running: Option<Priority>,
+  pub waiting_interactive: usize,
+  pub waiting_live: usize,
+  pub waiting_batch: usize,
+  // prompts the model's own process (or llama-server) is answering
+  pub streaming: usize,
+}
+
+// requests for one model; different models don't wait for each other
+#[derive(Default)]
+struct ModelState {
+  running: Option<(Priority, Arc<AtomicBool>)>,
+  // indexed by Priority as usize
+  waiting: [usize; 3],
+  // streamed prompts generating now, by priority; lower priority generations wait for them
+  streaming: [usize; 3],
+}
+
+impl ModelState {
+  fn idle(&self) -> bool {
+    self.running.is_none() && self.waiting.iter().chain(&self.streaming).all(|n| *n == 0)
+  }
+
+  // a higher priority request came in: a running batch generation makes way
+  fn preempt_for(&self, priority: Priority) {
+    if let Some((running, flag)) = &self.running {
+      if *running == Priority::Batch && priority > Priority::Batch {
+        flag.store(true, Ordering::Relaxed);
+      }
+    }
+  }
+}
+
+struct Dispatcher {
+  models: Mutex<HashMap<String, ModelState>>,
+  freed: Condvar,
+}
+
+fn dispatcher() -> &'static Dispatcher {
+  static D: OnceLock<Dispatcher> = OnceLock::new();
+  D.get_or_init(|| Dispatcher { models: Mutex::new(HashMap::new()), freed: Condvar::new() })
+}
+
+// runs its closure when dropped, so thread-locals are put back even when the work panics
+struct OnDrop<F: FnOnce()>(Option<F>);
+
+impl<F: FnOnce()> Drop for OnDrop<F> {
+  fn drop(&mut self) {
+    if let Some(f) = self.0.take() {
+      f();
+    }
+  }
+}
+
+thread_local! {
+  static CURRENT: Cell<Priority> = const { Cell::new(Priority::Interactive) };
+  static DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+  static ADJUSTMENTS: RefCell<Vec<Adjustment>> = const { RefCell::new(Vec::new()) };
+}
+
+// run `f` with every generation it makes scheduled at `priority`
+pub fn with_priority<T>(priority: Priority, f: impl FnOnce() -> T) -> T {
+  let previous = CURRENT.with(|c| c.replace(priority));
+  let _restore = OnDrop(Some(move || CURRENT.with(|c| c.set(previous))));
+  f()
+}
+
+pub fn current_priority() -> Priority {
+  CURRENT.with(|c| c.get())
+}
+
+// run `f` under the request's hints; returns what was adjusted to meet the deadline
+pub fn with_hints<T>(hints: &RequestHints, f: impl FnOnce() -> T) -> (T, Vec<Adjustment>) {
+  let deadline = hints.deadline_ms.map(|ms| Instant::now() + Duration::from_millis(ms));
+  let previous_deadline = DEADLINE.with(|d| d.replace(deadline.or(d.get())));
+  let previous_adjustments = ADJUSTMENTS.with(|a| a.take());
+  let _restore = OnDrop(Some(move || {
+    DEADLINE.with(|d| d.set(previous_deadline));
+    ADJUSTMENTS.with(|a| a.replace(previous_adjustments));
+  }));
+  let out = with_priority(hints.priority.unwrap_or_else(current_priority), f);
+  (out, ADJUSTMENTS.with(|a| a.take()))
+}
+
+// time left before the current request's deadline, if it has one
+pub fn remaining() -> Option<Duration> {
+  DEADLINE.with(|d| d.get()).map(|d| d.saturating_duration_since(Instant::now()))
+}
+
+pub fn note(adjustment: Adjustment) {
+  ADJUSTMENTS.with(|a| a.borrow_mut().push(adjustment));
+}
+
+fn speeds() -> &'static Mutex<HashMap<String, f32>> {
+  static SPEEDS: OnceLock<Mutex<HashMap<String, f32>>> = OnceLock::new();
+  SPEEDS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// remember how fast a model generates (tokens/sec), smoothed over runs
+pub fn record_speed(model_path: &str, tokens_per_sec: f32) {
+  let mut speeds = speeds().locked();
+  let speed = speeds.entry(model_path.to_string()).or_insert(tokens_per_sec);
+  *speed = *speed * (1.0 - SPEED_WEIGHT) + tokens_per_sec * SPEED_WEIGHT;
+}
+
+pub fn speed(model_path: &str) -> Option<f32> {
+  speeds().locked().get(model_path).copied()
+}
+
+// expected time to generate `tokens` with a model we have measured
+pub fn expected(model_path: &str, tokens: u32) -> Option<Duration> {
+  speed(model_path).filter(|s| *s > 0.0).map(|s| Duration::from_secs_f32(tokens as f32 / s))
+}
+
+// cut `max_tokens` down to what the model can produce before the deadline
+pub fn fit_max_tokens(model_path: &str, max_tokens: u32) -> u32 {
+  let (Some(budget), Some(speed), Some(expected)) = (remaining(), speed(model_path), expected(model_path, max_tokens)) else {
+    return max_tokens;
+  };
+  if expected <= budget {
+    return max_tokens;
+  }
+  let fitted = ((budget.as_secs_f32() * speed) as u32).clamp(MIN_TOKENS, max_tokens);
+  if fitted < max_tokens {
+    note(Adjustment {
+      kind: "max_tokens".into(),
+      from: max_tokens.to_string(),
+      to: fitted.to_string(),
+      expected_ms: expected.as_millis() as u64,
+      budget_ms: budget.as_millis() as u64,
+    });
+  }
+  fitted
+}
+
+// The model slot held while a generation runs; released on drop
+pub struct Slot {
+  model_path: String,
+  preempted: Arc<AtomicBool>,
+}
+
+impl Slot {
+  // set when a higher priority request is waiting; batch generations should stop and retry
+  pub fn preempted(&self) -> bool {
+    self.preempted.load(Ordering::Relaxed)
+  }
+}
+
+// drop a model's entry once nothing is running or waiting for it, and wake the waiters
+fn release(models: &mut HashMap<String, ModelState>, model_path: &str) {
+  if models.get(model_path).is_some_and(|m| m.idle()) {
+    models.remove(model_path);
+  }
+  dispatcher().freed.notify_all();
+}
+
+impl Drop for Slot {
+  fn drop(&mut self) {
+    let mut models = dispatcher().models.locked();
+    if let Some(model) = models.get_mut(&self.model_path) {
+      model.running = None;
+    }
+    release(&mut models, &self.model_path);
+  }
+}
+
+// wait for `model_path`: one generation of a model at a time, highest priority first, and none
+// while a streamed prompt of a higher priority is being answered
+pub fn acquire(model_path: &str, priority: Priority) -> Slot {
+  let d = dispatcher();
+  let mut models = d.models.locked();
+  let model = models.entry(model_path.to_string()).or_default();
+  model.waiting[priority as usize] += 1;
+  model.preempt_for(priority);
+  loop {
+    let model = models.entry(model_path.to_string()).or_default();
+    let higher = priority as usize + 1..;
+    let outranked = model.waiting[higher.clone()].iter().chain(&model.streaming[higher]).any(|n| *n > 0);
+    if model.running.is_none() && !outranked {
+      break;
+    }
+    // a thread that panicked holding the lock left consistent counts; carry on like LockExt does
+    models = d.freed.wait(models).unwrap_or_else(|poisoned| poisoned.into_inner());
+  }
+  let model = models.entry(model_path.to_string()).or_default();
+  model.waiting[priority as usize] -= 1;
+  let preempted = Arc::new(AtomicBool::new(false));
+  model.running = Some((priority, preempted.clone()));
+  Slot { model_path: model_path.to_string(), preempted }
+}
+
+// A streamed prompt being answered by a model's own process; lower priority generations of the
+// model wait until it is dropped
+pub struct Streaming {
+  model_path: String,
+  priority: Priority,
+}
+
+impl Drop for Streaming {
+  fn drop(&mut self) {
+    let mut models = dispatcher().models.locked();
+    if let Some(model) = models.get_mut(&self.model_path) {
+      model.streaming[self.priority as usize] -= 1;
+    }
+    release(&mut models, &self.model_path);
+  }
+}
+
+// count a streamed prompt of `model_path` as generating; doesn't wait, since the process answers
+// its prompts in their own queue, but a running batch generation is preempted
+pub fn streaming(model_path: &str, priority: Priority) -> Streaming {
+  let mut models = dispatcher().models.locked();
+  let model = models.entry(model_path.to_string()).or_default();
+  model.streaming[priority as usize] += 1;
+  model.preempt_for(priority);
+  Streaming { model_path: model_path.to_string(), priority }
+}
+
+// ------------------ Tauri commands ------------------
+
+#[tauri::command]
+pub fn get_dispatch_status() -> Vec<DispatchStatus> {
+  let models = dispatcher().models.locked();
+  let mut status: Vec<DispatchStatus> = models
+    .iter()
+    .map(|(path, model)| DispatchStatus {
+      model_path: path.clone(),
+      running: model.running.as_ref().map(|(p, _)| *p),
+      waiting_interactive: model.waiting[Priority::Interactive as usize],
+      waiting_live: model.waiting[Priority::Live as usize],
+      waiting_batch: model.waiting[Priority::Batch as usize],
+      streaming: model.streaming.iter().sum(),
+    })
+    .collect();
+  status.sort_by(|a, b| a.model_path.cmp(&b.model_path));
+  status
+}
@@ -0,0 +1,436 @@
+// src-tauri/src/document.rs
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::ops::Range;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use regex::{Captures, Regex};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::chunk;
+
+// chunk size used when translating plain text and Markdown
+const CHUNK_TOKENS: u32 = 600;
+// parts of a .docx whose paragraphs are translated, besides word/header*.xml and word/footer*.xml
+const DOCX_PARTS: &[&str] = &["word/document.xml", "word/footnotes.xml", "word/endnotes.xml"];
+
+// How a document is split into segments and put back together
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DocumentFormat {
+  // chunks of paragraphs; the translation separates them with blank lines
+  #[default]
+  #[serde(alias = "txt")]
+  Text,
+  // like text, with fenced code blocks copied as they are
+  #[serde(alias = "md")]
+  Markdown,
+  // the text of each cue; numbers and timings are kept
+  Srt,
+  // each paragraph of a Word document, keeping its style. Formatting that changes within a
+  // paragraph (a bold word) is lost: the translation takes the formatting of its first run
+  Docx,
+}
+
+impl DocumentFormat {
+  // by file extension; None for files that aren't documents
+  pub fn from_path(path: &Path) -> Option<Self> {
+    match path.extension()?.to_string_lossy().to_lowercase().as_str() {
+      "txt" => Some(Self::Text),
+      "md" | "markdown" => Some(Self::Markdown),
+      "srt" => Some(Self::Srt),
+      "docx" => Some(Self::Docx),
+      _ => None,
+    }
+  }
+}
+
+// A text document as what is translated and what is copied around it
+enum Part {
+  Keep(String),
+  Translate(String),
+}
+
+fn keep(parts: &mut Vec<Part>, text: &str) {
+  match parts.last_mut() {
+    Some(Part::Keep(kept)) => kept.push_str(text),
+    _ => parts.push(Part::Keep(text.to_string())),
+  }
+}
+
+// chunks of `text`, separated by blank lines from each other and from what came before
+fn chunk_parts(parts: &mut Vec<Part>, text: &str) {
+  for chunk in chunk::split_into_chunks(text, CHUNK_TOKENS) {
+    if !parts.is_empty() {
+      keep(parts, "\n\n");
+    }
+    parts.push(Part::Translate(chunk));
+  }
+}
+
+fn markdown_parts(text: &str) -> Vec<Part> {
+  let mut parts = Vec::new();
+  let mut prose = String::new();
+  let mut code = String::new();
+  let mut fence: Option<&str> = None;
+  for line in text.split_inclusive('\n') {
+    let trimmed = line.trim_start();
+    match fence {
+      Some(f) => {
+        code.push_str(line);
+        if trimmed.trim_end() == f {
+          fence = None;
+          keep(&mut parts, code.trim_end());
+          code.clear();
+        }
+      }
+      None if trimmed.starts_with("```") || trimmed.starts_with("~~~") => {
+        chunk_parts(&mut parts, &prose);
+        prose.clear();
+        if !parts.is_empty() {
+          keep(&mut parts, "\n\n");
+        }
+        code.push_str(line);
+        fence = Some(&trimmed[..3]);
+      }
+      None => prose.push_str(line),
+    }
+  }
+  // an unclosed fence runs to the end
+  if !code.is_empty() {
+    keep(&mut parts, code.trim_end());
+  }
+  chunk_parts(&mut parts, &prose);
+  keep(&mut parts, "\n");
+  parts
+}
+
+//   12
+//   00:01:02,500 --> 00:01:04,000
+//   text, one or more lines
+fn srt_parts(text: &str) -> Vec<Part> {
+  let text = text.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+  let mut parts = Vec::new();
+  for cue in text.split("\n\n").map(str::trim).filter(|c| !c.is_empty()) {
+    if !parts.is_empty() {
+      keep(&mut parts, "\n\n");
+    }
+    let lines: Vec<&str> = cue.lines().collect();
+    match lines.iter().position(|l| l.contains("-->")) {
+      Some(at) if at + 1 < lines.len() => {
+        keep(&mut parts, &format!("{}\n", lines[..=at].join("\n")));
+        parts.push(Part::Translate(lines[at + 1..].join("\n")));
+      }
+      _ => keep(&mut parts, cue),
+    }
+  }
+  keep(&mut parts, "\n");
+  parts
+}
+
+// the document with `translations` in place of its segments
+fn render(parts: Vec<Part>, translations: &[String]) -> Option<String> {
+  let mut translations = translations.iter();
+  let mut out = String::new();
+  for part in parts {
+    match part {
+      Part::Keep(text) => out.push_str(&text),
+      Part::Translate(_) => out.push_str(translations.next()?),
+    }
+  }
+  translations.next().is_none().then_some(out)
+}
+
+fn text_pattern() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  // <w:t> and <w:t xml:space="preserve">, not <w:tab/> or <w:tc>
+  RE.get_or_init(|| Regex::new(r"<w:t(?:\s[^>]*)?>([^<]*)</w:t>").unwrap())
+}
+
+fn entity_pattern() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| Regex::new(r"&(?:#x([0-9a-fA-F]+)|#([0-9]+)|(amp|lt|gt|quot|apos));").unwrap())
+}
+
+fn unescape(text: &str) -> String {
+  entity_pattern()
+    .replace_all(text, |caps: &Captures| {
+      let code = match (caps.get(1), caps.get(2)) {
+        (Some(hex), _) => u32::from_str_radix(hex.as_str(), 16).ok(),
+        (_, Some(dec)) => dec.as_str().parse().ok(),
+        _ => None,
+      };
+      match code {
+        Some(code) => char::from_u32(code).map(String::from).unwrap_or_default(),
+        None => match &caps[3] {
+          "amp" => "&",
+          "lt" => "<",
+          "gt" => ">",
+          "quot" => "\"",
+          _ => "'",
+        }
+        .to_string(),
+      }
+    })
+    .into_owned()
+}
+
+fn escape(text: &str) -> String {
+  text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+// where the <w:p> elements of a part are. A paragraph in a text box inside another paragraph ends
+// the outer one early; what follows the text box in the outer one stays untranslated
+fn paragraphs(xml: &str) -> Vec<Range<usize>> {
+  let mut out = Vec::new();
+  let mut from = 0;
+  while let Some(at) = xml[from..].find("<w:p").map(|i| from + i) {
+    let Some(end) = xml[at..].find('>').map(|i| at + i) else { break };
+    // <w:pPr> and the like, or an empty <w:p/>
+    let opens = xml[at + 4..].starts_with(|c: char| c == '>' || c.is_whitespace());
+    if !opens || xml[..end].ends_with('/') {
+      from = end;
+      continue;
+    }
+    let Some(close) = xml[end..].find("</w:p>").map(|i| end + i + "</w:p>".len()) else { break };
+    out.push(at..close);
+    from = close;
+  }
+  out
+}
+
+fn paragraph_text(paragraph: &str) -> String {
+  text_pattern().captures_iter(paragraph).map(|caps| unescape(&caps[1])).collect()
+}
+
+// the paragraph with `translation` in its first run and the other runs emptied
+fn fill_paragraph(paragraph: &str, translation: &str) -> String {
+  let mut first = true;
+  text_pattern()
+    .replace_all(paragraph, |_: &Captures| {
+      if std::mem::take(&mut first) {
+        format!("<w:t xml:space=\"preserve\">{}</w:t>", escape(translation))
+      } else {
+        "<w:t></w:t>".to_string()
+      }
+    })
+    .into_owned()
+}
+
+fn is_docx_part(name: &str) -> bool {
+  let Some(file) = name.strip_prefix("word/") else { return false };
+  DOCX_PARTS.contains(&name) || ((file.starts_with("header") || file.starts_with("footer")) && file.ends_with(".xml") && !file.contains('/'))
+}
+
+fn open_docx(path: &Path) -> Result<ZipArchive<File>, String> {
+  let file = File::open(path).map_err(|e| format!("failed to open {}: {}", path.to_string_lossy(), e))?;
+  ZipArchive::new(file).map_err(|e| format!("{} is not a Word document: {}", path.to_string_lossy(), e))
+}
+
+// the text parts of a .docx in archive order, as (name, XML)
+fn docx_parts(zip: &mut ZipArchive<File>) -> Result<Vec<(String, String)>, String> {
+  let mut parts = Vec::new();
+  for i in 0..zip.len() {
+    let mut entry = zip.by_index(i).map_err(|e| format!("damaged document: {}", e))?;
+    let name = entry.name().map_err(|e| format!("damaged document: {}", e))?.into_owned();
+    if is_docx_part(&name) {
+      let mut xml = String::new();
+      entry.read_to_string(&mut xml).map_err(|e| format!("failed to read {}: {}", name, e))?;
+      parts.push((name, xml));
+    }
+  }
+  Ok(parts)
+}
+
+fn docx_segments(path: &Path) -> Result<Vec<String>, String> {
+  let mut zip = open_docx(path)?;
+  let mut segments = Vec::new();
+  for (_, xml) in docx_parts(&mut zip)? {
+    segments.extend(paragraphs(&xml).into_iter().map(|p| paragraph_text(&xml[p])).filter(|t| !t.trim().is_empty()));
+  }
+  Ok(segments)
+}
+
+// a copy of the .docx at `source` with `translations` in place of its paragraphs
+fn write_docx(source: &Path, translations: &[String], out: &Path) -> Result<(), String> {
+  let mut zip = open_docx(source)?;
+  let mut filled = Vec::new();
+  let mut translations = translations.iter();
+  for (name, xml) in docx_parts(&mut zip)? {
+    let mut text = String::new();
+    let mut last = 0;
+    for p in paragraphs(&xml) {
+      if paragraph_text(&xml[p.clone()]).trim().is_empty() {
+        continue;
+      }
+      let translation = translations.next().ok_or_else(|| changed(source))?;
+      text.push_str(&xml[last..p.start]);
+      text.push_str(&fill_paragraph(&xml[p.clone()], translation));
+      last = p.end;
+    }
+    text.push_str(&xml[last..]);
+    filled.push((name, text));
+  }
+  if translations.next().is_some() {
+    return Err(changed(source));
+  }
+
+  let mut bytes = Vec::new();
+  {
+    let mut writer = ZipWriter::new(std::io::Cursor::new(&mut bytes));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for i in 0..zip.len() {
+      let entry = zip.by_index(i).map_err(|e| format!("damaged document: {}", e))?;
+      let name = entry.name().map_err(|e| format!("damaged document: {}", e))?.into_owned();
+      match filled.iter().find(|(n, _)| *n == name) {
+        Some((_, xml)) => {
+          writer.start_file(name.as_str(), options).map_err(|e| format!("failed to write {}: {}", out.to_string_lossy(), e))?;
+          writer.write_all(xml.as_bytes()).map_err(|e| format!("failed to write {}: {}", out.to_string_lossy(), e))?;
+        }
+        None => writer.raw_copy_file(entry).map_err(|e| format!("failed to write {}: {}", out.to_string_lossy(), e))?,
+      }
+    }
+    writer.finish().map_err(|e| format!("failed to write {}: {}", out.to_string_lossy(), e))?;
+  }
+  save(out, &bytes)
+}
+
+fn changed(source: &Path) -> String {
+  format!("{} has changed since its translation started; translate it again", source.to_string_lossy())
+}
+
+fn save(path: &Path, bytes: &[u8]) -> Result<(), String> {
+  fs::write(path, bytes).map_err(|e| format!("failed to write {}: {}", path.to_string_lossy(), e))
+}
+
+fn read(path: &Path) -> Result<String, String> {
+  fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path.to_string_lossy(), e))
+}
+
+fn parts(format: DocumentFormat, text: &str) -> Vec<Part> {
+  match format {
+    DocumentFormat::Markdown => markdown_parts(text),
+    DocumentFormat::Srt => srt_parts(text),
+    _ => {
+      let mut parts = Vec::new();
+      chunk_parts(&mut parts, text);
+      parts
+    }
+  }
+}
+
+// what gets translated of the document at `path`, in order
+pub fn segments(path: &Path, format: DocumentFormat) -> Result<Vec<String>, String> {
+  let segments = match format {
+    DocumentFormat::Docx => docx_segments(path)?,
+    _ => parts(format, &read(path)?)
+      .into_iter()
+      .filter_map(|p| match p {
+        Part::Translate(text) => Some(text),
+        Part::Keep(_) => None,
+      })
+      .collect(),
+  };
+  if segments.is_empty() {
+    return Err(format!("{} contains no text", path.to_string_lossy()));
+  }
+  Ok(segments)
+}
+
+// the document's text, segments separated by blank lines (for term extraction and the like)
+pub fn text(path: &Path) -> Result<String, String> {
+  let format = DocumentFormat::from_path(path).unwrap_or_default();
+  Ok(segments(path, format)?.join("\n\n"))
+}
+
+// write the translation of the document at `source` to `out`: `translations` in place of its
+// segments, around them what the document has besides. The source is read again for that, so it
+// must not have changed since its segments were taken
+pub fn write(source: &Path, format: DocumentFormat, translations: &[String], out: &Path) -> Result<(), String> {
+  let text = match format {
+    // nothing around the chunks but the blank lines between them
+    DocumentFormat::Text => translations.join("\n\n"),
+    DocumentFormat::Docx => return write_docx(source, translations, out),
+    _ => render(parts(format, &read(source)?), translations).ok_or_else(|| changed(source))?,
+  };
+  save(out, text.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn segments(parts: &[Part]) -> Vec<&str> {
+    parts.iter().filter_map(|p| if let Part::Translate(text) = p { Some(text.as_str()) } else { None }).collect()
+  }
+
+  fn upper(parts: &[Part]) -> Vec<String> {
+    segments(parts).iter().map(|s| s.to_uppercase()).collect()
+  }
+
+  #[test]
+  fn srt_keeps_numbers_and_timings() {
+    let srt = "\u{feff}1\r\n00:00:01,000 --> 00:00:02,000\r\nHello\r\nthere\r\n\r\n2\r\n00:00:03,000 --> 00:00:04,000\r\nBye\r\n";
+    let parts = srt_parts(srt);
+    assert_eq!(segments(&parts), ["Hello\nthere", "Bye"]);
+    assert_eq!(
+      render(srt_parts(srt), &upper(&parts)).unwrap(),
+      "1\n00:00:01,000 --> 00:00:02,000\nHELLO\nTHERE\n\n2\n00:00:03,000 --> 00:00:04,000\nBYE\n"
+    );
+  }
+
+  #[test]
+  fn srt_cue_without_text_is_kept() {
+    let parts = srt_parts("1\n00:00:01,000 --> 00:00:02,000\n\n2\n00:00:03,000 --> 00:00:04,000\nHi\n");
+    assert_eq!(segments(&parts), ["Hi"]);
+  }
+
+  #[test]
+  fn markdown_copies_code_fences() {
+    let md = "Intro line.\n\n```rust\nlet x = 1;\n```\n\nOutro line.\n";
+    let parts = markdown_parts(md);
+    let translated = segments(&parts);
+    assert!(translated.iter().all(|s| !s.contains("let x")), "{:?}", translated);
+    let out = render(markdown_parts(md), &upper(&parts)).unwrap();
+    assert!(out.contains("```rust\nlet x = 1;\n```"), "{}", out);
+    assert!(out.contains("INTRO LINE.") && out.contains("OUTRO LINE."), "{}", out);
+    assert!(out.find("INTRO").unwrap() < out.find("```").unwrap() && out.find("```").unwrap() < out.find("OUTRO").unwrap(), "{}", out);
+  }
+
+  #[test]
+  fn markdown_unclosed_fence_runs_to_the_end() {
+    let parts = markdown_parts("Text.\n~~~\ncode\nmore code\n");
+    let translated = segments(&parts);
+    assert!(translated.iter().all(|s| !s.contains("code")), "{:?}", translated);
+  }
+
+  #[test]
+  fn render_needs_one_translation_per_segment() {
+    let srt = "1\n00:00:01,000 --> 00:00:02,000\nHello\n";
+    assert!(render(srt_parts(srt), &[]).is_none());
+    assert!(render(srt_parts(srt), &["a".into(), "b".into()]).is_none());
+  }
+
+  #[test]
+  fn finds_paragraphs_but_not_properties_or_empty_ones() {
+    let xml = r#"<w:body><w:p/><w:p w:rsidR="1"><w:pPr><w:pStyle w:val="Title"/></w:pPr><w:r><w:t>Hi</w:t></w:r></w:p><w:p><w:r><w:t>Two</w:t></w:r></w:p></w:body>"#;
+    let found: Vec<&str> = paragraphs(xml).into_iter().map(|p| &xml[p]).collect();
+    assert_eq!(found.len(), 2, "{:?}", found);
+    assert!(found[0].starts_with("<w:p w:rsidR") && found[0].ends_with("</w:p>"), "{}", found[0]);
+    assert_eq!(paragraph_text(found[1]), "Two");
+  }
+
+  #[test]
+  fn fill_paragraph_puts_the_translation_in_the_first_run() {
+    let paragraph = r#"<w:p><w:r><w:t>Hello </w:t></w:r><w:tab/><w:r><w:rPr><w:b/></w:rPr><w:t xml:space="preserve">bold &amp; world</w:t></w:r></w:p>"#;
+    assert_eq!(paragraph_text(paragraph), "Hello bold & world");
+    let filled = fill_paragraph(paragraph, "Hallo <fett> & Welt");
+    assert_eq!(
+      filled,
+      r#"<w:p><w:r><w:t xml:space="preserve">Hallo &lt;fett&gt; &amp; Welt</w:t></w:r><w:tab/><w:r><w:rPr><w:b/></w:rPr><w:t></w:t></w:r></w:p>"#
+    );
+    assert_eq!(paragraph_text(&filled), "Hallo <fett> & Welt");
+  }
+}
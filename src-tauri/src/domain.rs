@@ -0,0 +1,263 @@
+// src-tauri/src/domain.rs
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use regex::Regex;
+
+use crate::error::{AppError, LockExt};
+use crate::jobs::{JobStore, JobSummary};
+use crate::lang;
+use crate::session::{Session, SessionStore};
+use crate::store;
+
+// packs shipped with the app; a file with the same id in ./data/domains replaces one
+const BUILTIN_PACKS: &[&str] = &[
+  include_str!("../domains/gaming.json"),
+  include_str!("../domains/legal.json"),
+  include_str!("../domains/medical.json"),
+  include_str!("../domains/technical.json"),
+];
+
+// A required translation for a term within a domain
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct DomainTerm {
+  pub source_lang: String,
+  pub target_lang: String,
+  pub source: String,
+  pub target: String,
+}
+
+// letters that words are spaced out of; Chinese, Japanese and Thai run words together, so a term
+// in them can start or end anywhere
+fn word_char(c: char) -> bool {
+  c.is_alphanumeric() && !matches!(c as u32, 0xE00..=0xE7F | 0x3040..=0x30FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF)
+}
+
+impl DomainTerm {
+  // whether the term occurs as whole words in `lower` (text already lowercased): "cat" is in
+  // "the cat sat" but not in "category"
+  pub fn occurs_in(&self, lower: &str) -> bool {
+    let term = self.source.trim().to_lowercase();
+    if term.is_empty() {
+      return false;
+    }
+    lower.match_indices(&term).any(|(at, _)| {
+      let before = lower[..at].chars().next_back();
+      let after = lower[at + term.len()..].chars().next();
+      let edge = |inside: Option<char>, outside: Option<char>| !(inside.is_some_and(word_char) && outside.is_some_and(word_char));
+      edge(term.chars().next(), before) && edge(term.chars().next_back(), after)
+    })
+  }
+}
+
+// A regex replacement run over the output, e.g. unit spacing or typography
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct PostRule {
+  // only applied to output in this language; None applies to every language
+  #[serde(default)]
+  pub lang: Option<String>,
+  pub pattern: String,
+  // may refer to groups as ${1}
+  pub replacement: String,
+}
+
+// System prompt, glossary and post-processing rules applied together
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct DomainPack {
+  pub id: String,
+  pub name: String,
+  #[serde(default)]
+  pub version: u32,
+  pub system_prompt: String,
+  #[serde(default)]
+  pub glossary: Vec<DomainTerm>,
+  #[serde(default)]
+  pub rules: Vec<PostRule>,
+}
+
+impl DomainPack {
+  // glossary terms that occur in `text` for this language pair
+  fn terms_in(&self, text: &str, source_lang: &str, target_lang: &str) -> Vec<&DomainTerm> {
+    let lower = text.to_lowercase();
+    self
+      .glossary
+      .iter()
+      .filter(|t| source_lang == "auto" || lang::same_language(&t.source_lang, source_lang))
+      .filter(|t| target_lang == "auto" || lang::same_language(&t.target_lang, target_lang))
+      .filter(|t| t.occurs_in(&lower))
+      .collect()
+  }
+
+  // instructions put in front of a translation prompt: the domain's system prompt and the
+  // glossary terms found in the text
+  pub fn translation_context(&self, text: &str, source_lang: &str, target_lang: &str) -> String {
+    let mut context = format!("{}\n", self.system_prompt.trim());
+    let terms = self.terms_in(text, source_lang, target_lang);
+    if !terms.is_empty() {
+      context.push_str("Translate these terms as given:\n");
+      for t in terms {
+        context.push_str(&format!("- {} → {}\n", t.source, t.target));
+      }
+    }
+    context.push('\n');
+    context
+  }
+
+  // system turn for a chat: the system prompt plus preferred terms for anything mentioned
+  pub fn system_turn(&self, text: &str) -> String {
+    let terms = self.terms_in(text, "auto", "auto");
+    if terms.is_empty() {
+      return self.system_prompt.trim().to_string();
+    }
+    let listed: Vec<String> =
+      terms.iter().map(|t| format!("{} ({}: {})", t.source, lang::language_name(&t.target_lang), t.target)).collect();
+    format!("{} Preferred terms: {}.", self.system_prompt.trim(), listed.join("; "))
+  }
+
+  // run the rules for `lang` ("auto" only runs rules without a language) over `text`
+  pub fn post_process(&self, text: &str, lang: &str) -> String {
+    let mut out = text.to_string();
+    for rule in &self.rules {
+      let applies = match &rule.lang {
+        Some(l) => lang != "auto" && lang::same_language(l, lang),
+        None => true,
+      };
+      // patterns are checked when a pack is loaded
+      if let (true, Ok(re)) = (applies, Regex::new(&rule.pattern)) {
+        out = re.replace_all(&out, rule.replacement.as_str()).into_owned();
+      }
+    }
+    out
+  }
+}
+
+// Pack listing for the domain picker
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct DomainSummary {
+  pub id: String,
+  pub name: String,
+  pub version: u32,
+  pub terms: usize,
+  pub rules: usize,
+  pub builtin: bool,
+}
+
+// What apply_domain changed
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct AppliedDomain {
+  pub domain: Option<DomainSummary>,
+  pub session: Option<Session>,
+  pub job: Option<JobSummary>,
+}
+
+fn packs_dir() -> PathBuf {
+  store::data_file("domains")
+}
+
+fn valid_id(id: &str) -> bool {
+  !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn parse_pack(json: &str, origin: &str) -> Result<DomainPack, String> {
+  let pack: DomainPack = serde_json::from_str(json).map_err(|e| format!("invalid domain pack {}: {}", origin, e))?;
+  if !valid_id(&pack.id) {
+    return Err(format!("invalid domain id '{}' in {}", pack.id, origin));
+  }
+  for rule in &pack.rules {
+    Regex::new(&rule.pattern).map_err(|e| format!("invalid rule '{}' in {}: {}", rule.pattern, origin, e))?;
+  }
+  Ok(pack)
+}
+
+// built-in packs plus ./data/domains/*.json, with (pack, builtin) sorted by id
+fn packs() -> Vec<(DomainPack, bool)> {
+  let mut packs: Vec<(DomainPack, bool)> = BUILTIN_PACKS.iter().filter_map(|json| parse_pack(json, "built-in pack").ok()).map(|p| (p, true)).collect();
+  let installed = fs::read_dir(packs_dir())
+    .map(|rd| {
+      rd.flatten()
+        .filter(|e| e.path().extension().is_some_and(|x| x == "json"))
+        .filter_map(|e| fs::read_to_string(e.path()).ok().map(|s| (s, e.path())))
+        .filter_map(|(s, path)| parse_pack(&s, &path.to_string_lossy()).ok())
+        .collect::<Vec<_>>()
+    })
+    .unwrap_or_default();
+  for pack in installed {
+    packs.retain(|(p, _)| p.id != pack.id);
+    packs.push((pack, false));
+  }
+  packs.sort_by(|a, b| a.0.id.cmp(&b.0.id));
+  packs
+}
+
+pub fn pack(id: &str) -> Result<DomainPack, String> {
+  packs().into_iter().map(|(p, _)| p).find(|p| p.id == id).ok_or(format!("Domain '{}' not found", id))
+}
+
+// install a pack received from elsewhere unless one with its id exists; true when it was written
+pub fn install(pack: &DomainPack) -> Result<bool, String> {
+  let json = serde_json::to_string(pack).map_err(|e| format!("failed to serialize domain pack: {}", e))?;
+  parse_pack(&json, "shared pack")?;
+  if self::pack(&pack.id).is_ok() {
+    return Ok(false);
+  }
+  store::save_json(&packs_dir().join(format!("{}.json", pack.id)), pack)?;
+  Ok(true)
+}
+
+fn summary(pack: &DomainPack, builtin: bool) -> DomainSummary {
+  DomainSummary {
+    id: pack.id.clone(),
+    name: pack.name.clone(),
+    version: pack.version,
+    terms: pack.glossary.len(),
+    rules: pack.rules.len(),
+    builtin,
+  }
+}
+
+// ------------------ Tauri commands ------------------
+
+#[tauri::command]
+pub fn list_domains() -> Vec<DomainSummary> {
+  packs().iter().map(|(p, builtin)| summary(p, *builtin)).collect()
+}
+
+#[tauri::command]
+pub fn get_domain(domain_id: String) -> Result<DomainPack, AppError> {
+  Ok(pack(&domain_id)?)
+}
+
+// switch a session and/or document job to a domain pack (None clears it); the pack's system
+// prompt, glossary and rules are used from the next message or job run on
+#[tauri::command]
+pub fn apply_domain(
+  domain_id: Option<String>,
+  session_id: Option<String>,
+  job_id: Option<u64>,
+  sessions: tauri::State<'_, Mutex<SessionStore>>,
+  jobs: tauri::State<'_, Mutex<JobStore>>
+) -> Result<AppliedDomain, AppError> {
+  if session_id.is_none() && job_id.is_none() {
+    return Err(AppError::InvalidInput("Pass a session_id or a job_id to apply the domain to".into()));
+  }
+  let domain = match &domain_id {
+    Some(id) => {
+      let (pack, builtin) = packs().into_iter().find(|(p, _)| p.id == *id).ok_or(format!("Domain '{}' not found", id))?;
+      Some(summary(&pack, builtin))
+    }
+    None => None,
+  };
+  let session = match &session_id {
+    Some(id) => Some(sessions.locked().update(id, |session| {
+      session.domain = domain_id.clone();
+      Ok(session.clone())
+    })?),
+    None => None,
+  };
+  let job = match job_id {
+    Some(id) => Some(jobs.locked().set_domain(id, domain_id)?),
+    None => None,
+  };
+  Ok(AppliedDomain { domain, session, job })
+}
@@ -0,0 +1,374 @@
+// src-tauri/src/download.rs
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ring::digest::{Context, SHA256};
+use tauri::{AppHandle, Emitter, Manager};
+use url::Url;
+
+use crate::error::{AppError, LockExt};
+use crate::events;
+use crate::modelcard;
+use crate::settings;
+use crate::store;
+use crate::ModelManager;
+
+// minimum time between "download-progress" events of one download
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DownloadStatus {
+  Running,
+  Paused,
+  Verifying,
+  Done,
+  Failed,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Download {
+  pub id: u64,
+  pub url: String,
+  pub file_name: String,
+  // expected SHA-256 (hex), checked once the file is complete
+  pub sha256: Option<String>,
+  pub downloaded: u64,
+  // from Content-Length / Content-Range, when the server sends it
+  pub total: Option<u64>,
+  pub status: DownloadStatus,
+  pub error: Option<String>,
+}
+
+// Payload of "download-progress" events
+#[derive(Clone, serde::Serialize)]
+struct DownloadProgress {
+  id: u64,
+  downloaded: u64,
+  total: Option<u64>,
+}
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct DownloadFile {
+  next_id: u64,
+  downloads: Vec<Download>,
+}
+
+// Model downloads persisted in ./data/downloads.json (managed by Tauri). Data is written to
+// "<file>.part" in the models folder and renamed once verified, so a paused or interrupted download
+// resumes from the bytes already on disk
+pub struct DownloadManager {
+  path: PathBuf,
+  file: DownloadFile,
+  // pause flags of downloads with a thread in this process
+  active: HashMap<u64, Arc<AtomicBool>>,
+}
+
+fn part_path(file_name: &str) -> PathBuf {
+  settings::models_dir().join(format!("{}.part", file_name))
+}
+
+// last path segment of the URL ("…/resolve/main/model-Q4_K_M.gguf?download=true" -> "model-Q4_K_M.gguf")
+fn file_name_from_url(url: &Url) -> Option<String> {
+  url.path_segments()?.rev().find(|s| !s.is_empty()).map(|s| s.to_string())
+}
+
+fn file_sha256(path: &PathBuf) -> Result<String, String> {
+  let mut file = File::open(path).map_err(|e| format!("failed to open {}: {}", path.to_string_lossy(), e))?;
+  let mut ctx = Context::new(&SHA256);
+  let mut buf = vec![0u8; 1 << 20];
+  loop {
+    let n = file.read(&mut buf).map_err(|e| format!("failed to read {}: {}", path.to_string_lossy(), e))?;
+    if n == 0 {
+      break;
+    }
+    ctx.update(&buf[..n]);
+  }
+  Ok(ctx.finish().as_ref().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+impl DownloadManager {
+  pub fn load() -> Self {
+    let path = store::data_file("downloads.json");
+    let mut file: DownloadFile = store::load_json(&path);
+    // downloads cut off by the last exit wait for the user to resume them
+    for d in file.downloads.iter_mut().filter(|d| matches!(d.status, DownloadStatus::Running | DownloadStatus::Verifying)) {
+      d.status = DownloadStatus::Paused;
+    }
+    Self { path, file, active: HashMap::new() }
+  }
+
+  fn save(&self) -> Result<(), String> {
+    store::save_json(&self.path, &self.file)
+  }
+
+  // where the finished download of `file_name` came from
+  pub fn source_url(&self, file_name: &str) -> Option<String> {
+    self.file.downloads.iter().rev().find(|d| d.file_name == file_name && d.status == DownloadStatus::Done).map(|d| d.url.clone())
+  }
+
+  fn get_mut(&mut self, id: u64) -> Result<&mut Download, String> {
+    self.file.downloads.iter_mut().find(|d| d.id == id).ok_or(format!("Download {} not found", id))
+  }
+
+  fn update<F: FnOnce(&mut Download)>(&mut self, id: u64, f: F) -> Option<Download> {
+    let d = self.get_mut(id).ok()?;
+    f(d);
+    let d = d.clone();
+    let _ = self.save();
+    Some(d)
+  }
+
+  // in-memory only; resuming goes by the size of the .part file
+  fn set_progress(&mut self, id: u64, downloaded: u64) {
+    if let Ok(d) = self.get_mut(id) {
+      d.downloaded = downloaded;
+    }
+  }
+
+  // mark a download running and hand out its pause flag
+  fn begin(&mut self, id: u64) -> Result<(Download, Arc<AtomicBool>), String> {
+    if self.active.contains_key(&id) {
+      return Err(format!("Download {} is already running", id));
+    }
+    let d = self.get_mut(id)?;
+    if d.status == DownloadStatus::Done {
+      return Err(format!("{} is already downloaded", d.file_name));
+    }
+    d.status = DownloadStatus::Running;
+    d.error = None;
+    let d = d.clone();
+    let pause = Arc::new(AtomicBool::new(false));
+    self.active.insert(id, pause.clone());
+    self.save()?;
+    Ok((d, pause))
+  }
+}
+
+// fetch the rest of the file into its .part file; Ok(false) when paused
+fn fetch(app: &AppHandle, download: &Download, pause: &AtomicBool) -> Result<bool, String> {
+  let part = part_path(&download.file_name);
+  let mut offset = fs::metadata(&part).map(|m| m.len()).unwrap_or(0);
+  let mut req = ureq::get(&download.url).header("User-Agent", "multilingual-model-downloader");
+  if offset > 0 {
+    req = req.header("Range", format!("bytes={}-", offset));
+  }
+  let mut resp = req.config().http_status_as_error(false).build().call().map_err(|e| format!("failed to fetch {}: {}", download.url, e))?;
+  let header = |name: &str| resp.headers().get(name).and_then(|v| v.to_str().ok()).map(|v| v.to_string());
+  let status = resp.status().as_u16();
+  if status == 416 && offset > 0 {
+    // the range starts at or past the end: complete when the .part file has exactly the remote
+    // length ("bytes */<length>", else asked for), and the hash is still checked after this
+    let remote = match header("content-range").and_then(|r| r.strip_prefix("bytes */").and_then(|t| t.parse::<u64>().ok())) {
+      Some(len) => Some(len),
+      None => remote_length(&download.url),
+    };
+    if remote == Some(offset) {
+      return Ok(true);
+    }
+    // longer than the remote file, or the file changed: it can't be resumed
+    let _ = fs::remove_file(&part);
+    return Err(format!("the partial download of {} doesn't match the remote file; start it again", download.file_name));
+  }
+  if !(200..300).contains(&status) {
+    return Err(format!("failed to fetch {}: http status: {}", download.url, status));
+  }
+  // a server without range support sends the whole file again
+  let resumed = resp.status().as_u16() == 206;
+  let total = match (resumed, header("content-range"), header("content-length")) {
+    (true, Some(range), _) => range.rsplit('/').next().and_then(|t| t.parse().ok()),
+    (_, _, Some(len)) => len.parse::<u64>().ok().map(|len| if resumed { len + offset } else { len }),
+    _ => None,
+  };
+  if !resumed {
+    offset = 0;
+  }
+
+  let mut out = OpenOptions::new()
+    .create(true)
+    .write(true)
+    .append(resumed)
+    .truncate(!resumed)
+    .open(&part)
+    .map_err(|e| format!("failed to open {}: {}", part.to_string_lossy(), e))?;
+  let downloads = app.state::<Mutex<DownloadManager>>();
+  downloads.locked().update(download.id, |d| {
+    d.downloaded = offset;
+    d.total = total;
+  });
+
+  let mut reader = resp.body_mut().as_reader();
+  let mut buf = vec![0u8; 256 << 10];
+  let mut last_event = Instant::now();
+  loop {
+    if pause.load(Ordering::Relaxed) {
+      out.flush().map_err(|e| e.to_string())?;
+      downloads.locked().update(download.id, |d| d.downloaded = offset);
+      return Ok(false);
+    }
+    let n = reader.read(&mut buf).map_err(|e| format!("download of {} interrupted: {}", download.file_name, e))?;
+    if n == 0 {
+      break;
+    }
+    out.write_all(&buf[..n]).map_err(|e| format!("failed to write {}: {}", part.to_string_lossy(), e))?;
+    offset += n as u64;
+    if last_event.elapsed() >= PROGRESS_INTERVAL {
+      last_event = Instant::now();
+      downloads.locked().set_progress(download.id, offset);
+      events::emit(app, "download-progress", DownloadProgress { id: download.id, downloaded: offset, total });
+    }
+  }
+  out.flush().map_err(|e| e.to_string())?;
+  if total.is_some_and(|t| offset < t) {
+    return Err(format!("download of {} ended early ({} of {} bytes)", download.file_name, offset, total.unwrap_or(0)));
+  }
+  downloads.locked().update(download.id, |d| d.downloaded = offset);
+  events::emit(app, "download-progress", DownloadProgress { id: download.id, downloaded: offset, total });
+  Ok(true)
+}
+
+// Content-Length of a HEAD request, when the server sends one
+fn remote_length(url: &str) -> Option<u64> {
+  let resp = ureq::head(url).header("User-Agent", "multilingual-model-downloader").call().ok()?;
+  resp.headers().get("content-length")?.to_str().ok()?.parse().ok()
+}
+
+// check the finished .part file and move it into the models folder
+fn verify_and_install(app: &AppHandle, download: &Download) -> Result<(), String> {
+  let part = part_path(&download.file_name);
+  if let Some(expected) = &download.sha256 {
+    let verifying = app.state::<Mutex<DownloadManager>>().locked().update(download.id, |d| d.status = DownloadStatus::Verifying);
+    let _ = app.emit("download-status", verifying);
+    let actual = file_sha256(&part)?;
+    if !actual.eq_ignore_ascii_case(expected.trim()) {
+      // a corrupt file can't be resumed into a good one
+      let _ = fs::remove_file(&part);
+      return Err(format!("SHA-256 mismatch for {}: expected {}, got {}", download.file_name, expected.trim(), actual));
+    }
+  }
+  let dest = settings::models_dir().join(&download.file_name);
+  fs::rename(&part, &dest).map_err(|e| format!("failed to move {} into place: {}", download.file_name, e))?;
+  // the model card comes along when there is none yet; the download is fine without it
+  if modelcard::find(&dest).is_none() {
+    if let Err(e) = modelcard::fetch(&download.url, &dest) {
+      events::log(format!("no model card for {}: {}", download.file_name, e));
+    }
+  }
+  // register the new file so it shows up in list_models right away
+  app.state::<Mutex<ModelManager>>().locked().scan_models();
+  Ok(())
+}
+
+// run (or resume) a download on a background thread; "download-status" carries the final state
+fn start(app: AppHandle, id: u64) -> Result<Download, String> {
+  let (download, pause) = app.state::<Mutex<DownloadManager>>().locked().begin(id)?;
+  let started = download.clone();
+  thread::spawn(move || {
+    let result = fetch(&app, &download, &pause).and_then(|complete| {
+      if complete {
+        verify_and_install(&app, &download).map(|_| true)
+      } else {
+        Ok(false)
+      }
+    });
+    let downloads = app.state::<Mutex<DownloadManager>>();
+    let mut downloads = downloads.locked();
+    downloads.active.remove(&id);
+    let status = downloads.update(id, |d| match result {
+      Ok(true) => d.status = DownloadStatus::Done,
+      Ok(false) => d.status = DownloadStatus::Paused,
+      Err(e) => {
+        d.status = DownloadStatus::Failed;
+        d.error = Some(e);
+      }
+    });
+    drop(downloads);
+    if let Some(status) = status {
+      let _ = app.emit("download-status", status);
+    }
+  });
+  Ok(started)
+}
+
+// ------------------ Tauri commands ------------------
+
+// download a model file (e.g. a GGUF from Hugging Face) into the models folder.
+// `sha256` is checked on completion; `file_name` defaults to the last part of the URL
+#[tauri::command]
+pub fn download_model(url: String, sha256: Option<String>, file_name: Option<String>, app: AppHandle) -> Result<Download, AppError> {
+  let parsed = Url::parse(&url).map_err(|e| format!("Invalid URL: {}", e))?;
+  if !matches!(parsed.scheme(), "http" | "https") {
+    return Err(AppError::InvalidInput("Only http and https URLs can be downloaded".into()));
+  }
+  let file_name = file_name.or_else(|| file_name_from_url(&parsed)).ok_or("Could not tell the file name from the URL")?;
+  if file_name.contains(['/', '\\']) || file_name.starts_with('.') {
+    return Err(AppError::InvalidInput(format!("Invalid file name '{}'", file_name)));
+  }
+  if settings::models_dir().join(&file_name).exists() {
+    return Err(AppError::InvalidInput(format!("{} already exists in the models folder", file_name)));
+  }
+  let dir = settings::models_dir();
+  fs::create_dir_all(&dir).map_err(|e| AppError::io("create", &dir, e))?;
+
+  let id = {
+    let downloads = app.state::<Mutex<DownloadManager>>();
+    let mut downloads = downloads.locked();
+    if downloads.file.downloads.iter().any(|d| d.file_name == file_name && d.status != DownloadStatus::Done) {
+      return Err(AppError::Busy(format!("{} is already being downloaded", file_name)));
+    }
+    downloads.file.next_id += 1;
+    let id = downloads.file.next_id;
+    downloads.file.downloads.push(Download {
+      id,
+      url,
+      file_name,
+      sha256: sha256.filter(|s| !s.trim().is_empty()),
+      downloaded: 0,
+      total: None,
+      status: DownloadStatus::Paused,
+      error: None,
+    });
+    downloads.save()?;
+    id
+  };
+  Ok(start(app, id)?)
+}
+
+#[tauri::command]
+pub fn list_downloads(downloads: tauri::State<'_, Mutex<DownloadManager>>) -> Vec<Download> {
+  downloads.locked().file.downloads.clone()
+}
+
+// stop after the current chunk; the partial file is kept for resume_download
+#[tauri::command]
+pub fn pause_download(id: u64, downloads: tauri::State<'_, Mutex<DownloadManager>>) -> Result<(), AppError> {
+  let downloads = downloads.locked();
+  let pause = downloads.active.get(&id).ok_or(format!("Download {} is not running", id))?;
+  pause.store(true, Ordering::Relaxed);
+  Ok(())
+}
+
+// continue a paused, failed or interrupted download from the bytes already on disk
+#[tauri::command]
+pub fn resume_download(id: u64, app: AppHandle) -> Result<Download, AppError> {
+  Ok(start(app, id)?)
+}
+
+// forget a download and delete its partial file (a finished model stays in the models folder)
+#[tauri::command]
+pub fn remove_download(id: u64, downloads: tauri::State<'_, Mutex<DownloadManager>>) -> Result<(), AppError> {
+  let mut downloads = downloads.locked();
+  if downloads.active.contains_key(&id) {
+    return Err(AppError::Busy(format!("Download {} is still running; pause it first", id)));
+  }
+  let pos = downloads.file.downloads.iter().position(|d| d.id == id).ok_or(format!("Download {} not found", id))?;
+  let removed = downloads.file.downloads.remove(pos);
+  let _ = fs::remove_file(part_path(&removed.file_name));
+  Ok(downloads.save()?)
+}
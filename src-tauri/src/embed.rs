@@ -0,0 +1,122 @@
+// src-tauri/src/embed.rs
+use std::fs;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::dispatch;
+use crate::engine;
+use crate::model_config::ModelConfig;
+use crate::runtime;
+use crate::storage;
+
+// llama.cpp's embedding tool, shipped next to the runtime
+const EMBED_TOOL: &str = "llama-embedding";
+// the texts go to the tool in one file, split on this so a text may span lines
+const SEPARATOR: &str = "<#sep#>";
+// tokens the tool embeds at once; longer texts are cut
+const BATCH_TOKENS: u32 = 2048;
+
+static NEXT_INPUT: AtomicU64 = AtomicU64::new(1);
+
+#[derive(serde::Deserialize)]
+struct EmbeddingList {
+  data: Vec<Embedding>,
+}
+
+#[derive(serde::Deserialize)]
+struct Embedding {
+  index: usize,
+  embedding: Vec<f32>,
+}
+
+// one unit-length vector per text from `model_path`, mean-pooled over the tokens (chat models
+// don't declare a pooling of their own). Err for wrapper models (a folder with run.sh/run.bat),
+// which only generate, and when no runtime or llama-embedding is bundled
+pub fn embed(model_path: &str, config: &ModelConfig, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+  if texts.is_empty() {
+    return Ok(Vec::new());
+  }
+  if Path::new(model_path).is_dir() {
+    return Err(format!("{} runs through a wrapper script, which has no embeddings", model_path));
+  }
+  let rt = runtime::select_runtime(model_path).ok_or("no llama.cpp runtime is bundled with this build")?;
+  let exe = runtime::sibling_tool(&rt.exe, EMBED_TOOL).ok_or("llama-embedding is not bundled with this build")?;
+
+  let input = storage::scratch_dir().join(format!("multilingual-embed-{}-{}.txt", std::process::id(), NEXT_INPUT.fetch_add(1, Ordering::Relaxed)));
+  let joined: Vec<String> = texts.iter().map(|t| t.replace(SEPARATOR, " ")).collect();
+  fs::write(&input, joined.join(SEPARATOR)).map_err(|e| format!("failed to write {}: {}", input.to_string_lossy(), e))?;
+
+  let batch = BATCH_TOKENS.to_string();
+  let mut c = storage::command(exe);
+  c.arg("-m").arg(model_path).arg("-f").arg(&input);
+  c.args(["--embd-separator", SEPARATOR, "--embd-output-format", "json", "--embd-normalize", "2", "--pooling", "mean"]);
+  c.args(["-c", &batch, "-b", &batch, "-ub", &batch]);
+  c.args(engine::runtime_args(model_path, config, rt.offload));
+  let output = {
+    // the model is busy while it embeds, like for a generation
+    let _slot = dispatch::acquire(model_path, dispatch::current_priority());
+    c.stdin(Stdio::null()).output()
+  };
+  let _ = fs::remove_file(&input);
+  let output = output.map_err(|e| format!("Failed to spawn llama-embedding: {}", e))?;
+  if !output.status.success() {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let tail: Vec<&str> = stderr.lines().rev().take(5).collect();
+    return Err(format!("llama-embedding exited with {}: {}", output.status, tail.into_iter().rev().collect::<Vec<_>>().join("\n")));
+  }
+  parse_output(&String::from_utf8_lossy(&output.stdout), texts.len())
+}
+
+// the vectors of --embd-output-format json, in input order
+fn parse_output(stdout: &str, expected: usize) -> Result<Vec<Vec<f32>>, String> {
+  // anything the tool prints before the JSON object
+  let json = stdout.find('{').map(|at| &stdout[at..]).ok_or("llama-embedding printed no embeddings")?;
+  let mut list: EmbeddingList = serde_json::from_str(json.trim_end()).map_err(|e| format!("unreadable llama-embedding output: {}", e))?;
+  list.data.sort_by_key(|e| e.index);
+  if list.data.len() != expected || list.data.iter().enumerate().any(|(i, e)| e.index != i) {
+    return Err(format!("llama-embedding returned {} embeddings for {} texts", list.data.len(), expected));
+  }
+  Ok(list.data.into_iter().map(|e| e.embedding).collect())
+}
+
+// cosine similarity, -1..1; 0 when either vector is empty or zero
+pub fn similarity(a: &[f32], b: &[f32]) -> f32 {
+  let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+  let norm = a.iter().map(|x| x * x).sum::<f32>().sqrt() * b.iter().map(|x| x * x).sum::<f32>().sqrt();
+  if a.len() != b.len() || norm == 0.0 {
+    return 0.0;
+  }
+  dot / norm
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn reads_json_output_in_input_order() {
+    let stdout = r#"{"object": "list", "data": [
+      {"object": "embedding", "index": 1, "embedding": [0.0, 1.0]},
+      {"object": "embedding", "index": 0, "embedding": [1.0, 0.0]}
+    ]}
+"#;
+    assert_eq!(parse_output(stdout, 2).unwrap(), vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+  }
+
+  #[test]
+  fn rejects_missing_embeddings() {
+    let stdout = r#"{"object": "list", "data": [{"object": "embedding", "index": 0, "embedding": [1.0]}]}"#;
+    assert!(parse_output(stdout, 2).is_err());
+    assert!(parse_output("", 1).is_err());
+  }
+
+  #[test]
+  fn cosine_similarity() {
+    assert!((similarity(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-6);
+    assert!(similarity(&[1.0, 0.0], &[0.0, 1.0]).abs() < 1e-6);
+    assert!((similarity(&[1.0, 1.0], &[-1.0, -1.0]) + 1.0).abs() < 1e-6);
+    assert_eq!(similarity(&[0.0, 0.0], &[1.0, 0.0]), 0.0);
+    assert_eq!(similarity(&[1.0], &[1.0, 0.0]), 0.0);
+  }
+}
@@ -0,0 +1,184 @@
+// src-tauri/src/encoding.rs
+use encoding_rs::{Encoding, GBK, SHIFT_JIS, UTF_8, WINDOWS_1252};
+
+// Character encoding of a runtime's output
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputEncoding {
+  // UTF-8 while the output is valid UTF-8, else the system code page
+  #[default]
+  Auto,
+  Utf8,
+  Cp1252,
+  Gbk,
+  ShiftJis,
+}
+
+impl OutputEncoding {
+  fn fixed(self) -> Option<&'static Encoding> {
+    match self {
+      OutputEncoding::Auto => None,
+      OutputEncoding::Utf8 => Some(UTF_8),
+      OutputEncoding::Cp1252 => Some(WINDOWS_1252),
+      OutputEncoding::Gbk => Some(GBK),
+      OutputEncoding::ShiftJis => Some(SHIFT_JIS),
+    }
+  }
+}
+
+// DOS code pages, which encoding_rs doesn't have: the characters for bytes 0x80..=0xFF
+#[cfg(windows)]
+const CP437: &str = "ÇüéâäàåçêëèïîìÄÅÉæÆôöòûùÿÖÜ¢£¥₧ƒáíóúñÑªº¿⌐¬½¼¡«»░▒▓│┤╡╢╖╕╣║╗╝╜╛┐└┴┬├─┼╞╟╚╔╩╦╠═╬╧╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀αßΓπΣσµτΦΘΩδ∞φε∩≡±≥≤⌠⌡÷≈°∙·√ⁿ²■\u{a0}";
+#[cfg(windows)]
+const CP850: &str = "ÇüéâäàåçêëèïîìÄÅÉæÆôöòûùÿÖÜø£Ø×ƒáíóúñÑªº¿®¬½¼¡«»░▒▓│┤ÁÂÀ©╣║╗╝¢¥┐└┴┬├─┼ãÃ╚╔╩╦╠═╬¤ðÐÊËÈıÍÎÏ┘┌█▄¦Ì▀ÓßÔÒõÕµþÞÚÛÙýÝ¯´\u{ad}±‗¾¶§÷¸°¨·¹³²■\u{a0}";
+
+// What a line is decoded with: an encoding_rs encoding, or one of the OEM tables above
+#[derive(Clone, Copy)]
+enum Charset {
+  Encoding(&'static Encoding),
+  #[cfg_attr(not(windows), allow(dead_code))]
+  Oem(&'static str),
+}
+
+impl Charset {
+  fn decode(self, bytes: &[u8]) -> String {
+    match self {
+      Charset::Encoding(e) => e.decode_without_bom_handling(bytes).0.into_owned(),
+      Charset::Oem(table) => {
+        let high: Vec<char> = table.chars().collect();
+        bytes.iter().map(|&b| if b < 0x80 { b as char } else { high[b as usize - 0x80] }).collect()
+      }
+    }
+  }
+}
+
+// the code page console programs write in when they don't use UTF-8: the console's output code
+// page, or the OEM code page when the app has no console (the ANSI one is for GUI programs)
+#[cfg(windows)]
+fn system_charset() -> Charset {
+  #[link(name = "kernel32")]
+  extern "system" {
+    fn GetConsoleOutputCP() -> u32;
+    fn GetOEMCP() -> u32;
+  }
+  let code_page = match unsafe { GetConsoleOutputCP() } {
+    0 => unsafe { GetOEMCP() },
+    cp => cp,
+  };
+  Charset::Encoding(match code_page {
+    437 => return Charset::Oem(CP437),
+    850 => return Charset::Oem(CP850),
+    65001 => UTF_8,
+    866 => encoding_rs::IBM866,
+    874 => encoding_rs::WINDOWS_874,
+    936 => GBK,
+    932 => SHIFT_JIS,
+    949 => encoding_rs::EUC_KR,
+    950 => encoding_rs::BIG5,
+    1250 => encoding_rs::WINDOWS_1250,
+    1251 => encoding_rs::WINDOWS_1251,
+    1252 => WINDOWS_1252,
+    1253 => encoding_rs::WINDOWS_1253,
+    1254 => encoding_rs::WINDOWS_1254,
+    1255 => encoding_rs::WINDOWS_1255,
+    1256 => encoding_rs::WINDOWS_1256,
+    1257 => encoding_rs::WINDOWS_1257,
+    1258 => encoding_rs::WINDOWS_1258,
+    // the US OEM page, which the other western DOS pages mostly agree with
+    _ => return Charset::Oem(CP437),
+  })
+}
+
+#[cfg(not(windows))]
+fn system_charset() -> Charset {
+  Charset::Encoding(WINDOWS_1252)
+}
+
+// Turns one process's output lines into text
+pub struct LineDecoder {
+  encoding: OutputEncoding,
+  // in auto mode, set by the first line that isn't UTF-8 and kept from then on
+  detected: Option<Charset>,
+}
+
+impl LineDecoder {
+  pub fn new(encoding: OutputEncoding) -> Self {
+    Self { encoding, detected: None }
+  }
+
+  fn charset(&self) -> Option<Charset> {
+    self.encoding.fixed().map(Charset::Encoding).or(self.detected)
+  }
+
+  pub fn decode(&mut self, bytes: &[u8]) -> String {
+    let charset = match self.charset() {
+      Some(c) => c,
+      None => match std::str::from_utf8(bytes) {
+        Ok(text) => return text.to_string(),
+        Err(_) => *self.detected.insert(system_charset()),
+      },
+    };
+    charset.decode(bytes)
+  }
+
+  // decode without settling the encoding, for an unfinished line that may end mid-character
+  pub fn peek(&self, bytes: &[u8]) -> String {
+    match self.charset() {
+      Some(c) => c.decode(bytes),
+      None => String::from_utf8_lossy(bytes).into_owned(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn auto_reads_utf8() {
+    let mut decoder = LineDecoder::new(OutputEncoding::Auto);
+    assert_eq!(decoder.decode("Grüße, 你好".as_bytes()), "Grüße, 你好");
+    assert!(decoder.detected.is_none());
+  }
+
+  #[test]
+  fn auto_settles_on_the_system_code_page() {
+    let mut decoder = LineDecoder::new(OutputEncoding::Auto);
+    // "Grüße" in a single-byte code page isn't UTF-8
+    let latin = decoder.decode(b"Gr\xfc\xdfe");
+    assert!(latin.starts_with("Gr") && latin.chars().count() == 5 && !latin.contains('\u{fffd}'), "{}", latin);
+    assert!(decoder.detected.is_some());
+    // and stays there, even for a line that would be valid UTF-8
+    assert_eq!(decoder.decode("é".as_bytes()).chars().count(), 2);
+  }
+
+  #[test]
+  fn fixed_encodings_decode() {
+    assert_eq!(LineDecoder::new(OutputEncoding::Cp1252).decode(b"caf\xe9 \x80"), "café €");
+    assert_eq!(LineDecoder::new(OutputEncoding::Gbk).decode(b"\xc4\xe3\xba\xc3"), "你好");
+    assert_eq!(LineDecoder::new(OutputEncoding::ShiftJis).decode(b"\x82\xb1\x82\xf1"), "こん");
+    assert_eq!(LineDecoder::new(OutputEncoding::Utf8).decode(b"ok\xff"), "ok\u{fffd}");
+  }
+
+  #[test]
+  fn peek_leaves_auto_undecided() {
+    let decoder = LineDecoder::new(OutputEncoding::Auto);
+    // half of "é"
+    assert_eq!(decoder.peek(b"caf\xc3"), "caf\u{fffd}");
+    assert!(decoder.detected.is_none());
+  }
+
+  #[test]
+  fn oem_tables_map_the_high_half() {
+    assert_eq!(Charset::Oem("é".repeat(128).leak()).decode(b"a\x80"), "aé");
+  }
+
+  #[cfg(windows)]
+  #[test]
+  fn oem_tables_are_complete() {
+    assert_eq!(CP437.chars().count(), 128);
+    assert_eq!(CP850.chars().count(), 128);
+    assert_eq!(Charset::Oem(CP437).decode(b"\x82\xb0\xe1"), "é░ß");
+    assert_eq!(Charset::Oem(CP850).decode(b"\x9b\xb5"), "øÁ");
+  }
+}
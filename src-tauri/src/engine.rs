@@ -0,0 +1,374 @@
+// src-tauri/src/engine.rs
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tauri::Manager;
+
+use crate::dispatch::{self, Priority};
+use crate::encoding::{LineDecoder, OutputEncoding};
+use crate::error::LockExt;
+use crate::events;
+use crate::gguf::GgufMetadata;
+use crate::hardware;
+use crate::memory;
+use crate::model_config::ModelConfig;
+use crate::protocol;
+use crate::runtime;
+use crate::settings;
+use crate::storage;
+use crate::ModelManager;
+
+// fewest tokens a resumed generation is asked for
+const MIN_RESUME_TOKENS: u32 = 16;
+// how often a running batch generation checks whether it has been preempted
+const PREEMPT_POLL: Duration = Duration::from_millis(50);
+
+// context window assumed when neither the model config nor the runtime tells us otherwise
+const DEFAULT_CTX_SIZE: u32 = 4096;
+
+// rough token estimate (~4 chars per token for latin text) used for context accounting
+pub fn estimate_tokens(text: &str) -> u32 {
+  (text.chars().count() as u32).div_ceil(4)
+}
+
+// Log probability of one generated token
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct TokenLogprob {
+  pub token: String,
+  pub logprob: f32,
+}
+
+// One of several outputs for the same prompt, best first
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Candidate {
+  pub text: String,
+  // backend score (e.g. sequence log probability); higher is better
+  pub score: f32,
+}
+
+// Generated text plus per-token logprobs and n-best candidates when the backend reports them
+#[derive(Clone, Debug)]
+pub struct Generation {
+  pub text: String,
+  pub logprobs: Option<Vec<TokenLogprob>>,
+  pub candidates: Option<Vec<Candidate>>,
+}
+
+// Extras a runner may be asked for
+#[derive(Clone, Copy, Default)]
+struct RunOptions<'a> {
+  // JSON schema the output must follow
+  schema: Option<&'a str>,
+  // number of candidates to return (beam search / sampling), for runners that can
+  n_best: Option<usize>,
+}
+
+// Payload of "generation-metrics" events (debug verbosity); token counts are estimates
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct GenerationMetrics {
+  pub model_path: String,
+  pub prompt_tokens: u32,
+  pub output_tokens: u32,
+  // wall time including waiting for the dispatcher
+  pub millis: u64,
+  pub tokens_per_sec: f32,
+}
+
+// Run a single prompt to completion and return the generated text.
+pub fn generate(model_path: &str, config: &ModelConfig, prompt: &str, max_tokens: u32) -> Result<String, String> {
+  generate_detailed(model_path, config, prompt, max_tokens).map(|g| g.text)
+}
+
+// Tries the same runners as spawn_for_model: run.sh/run.bat wrapper (prompt on stdin),
+// then a bundled runtime, then a built-in mock so dev builds keep working without models.
+// Wrapper runners may print a single JSON object {"text": ..., "logprobs": [{"token", "logprob"}]}
+// instead of plain text to expose token logprobs, or stream protocol::OutputMessage lines.
+pub fn generate_detailed(model_path: &str, config: &ModelConfig, prompt: &str, max_tokens: u32) -> Result<Generation, String> {
+  run(model_path, config, prompt, max_tokens, RunOptions::default())
+}
+
+// Ask the runner for the `n` best outputs of one generation. Only wrapper runners can do this
+// (they get MULTILINGUAL_N_BEST and answer with {"text", "candidates": [{"text", "score"}]});
+// Ok(None) means the backend returned a single output and the caller has to fall back
+pub fn generate_n_best(model_path: &str, config: &ModelConfig, prompt: &str, max_tokens: u32, n: usize) -> Result<Option<Vec<Candidate>>, String> {
+  let out = run(model_path, config, prompt, max_tokens, RunOptions { n_best: Some(n), ..Default::default() })?;
+  Ok(out.candidates.filter(|c| !c.is_empty()))
+}
+
+// Generate output constrained to a JSON schema and parse it.
+// The bundled runtime enforces the schema with --json-schema; wrapper runners get it in
+// the MULTILINGUAL_JSON_SCHEMA env var and the mock can't honour it, so the result is still validated.
+pub fn generate_json(
+  model_path: &str,
+  config: &ModelConfig,
+  prompt: &str,
+  max_tokens: u32,
+  schema: &serde_json::Value
+) -> Result<serde_json::Value, String> {
+  let schema = schema.to_string();
+  let out = run(model_path, config, prompt, max_tokens, RunOptions { schema: Some(&schema), ..Default::default() })?;
+  extract_json(&out.text).ok_or_else(|| format!("model did not return valid JSON: {}", out.text.chars().take(200).collect::<String>()))
+}
+
+// context window of a model: its own ctx_size, else the one from the settings
+pub fn context_size(config: &ModelConfig) -> u32 {
+  config.ctx_size.or(settings::get().ctx_size).unwrap_or(DEFAULT_CTX_SIZE)
+}
+
+// GPU layers (see hardware::gpu_layers) and threads from the settings; `offload` is whether the
+// runtime can use a GPU
+pub fn runtime_args(model_path: &str, config: &ModelConfig, offload: bool) -> Vec<String> {
+  let settings = settings::get();
+  let mut args = Vec::new();
+  if offload {
+    args.extend(["-ngl".to_string(), hardware::gpu_layers(model_path, config).to_string()]);
+  }
+  if let Some(threads) = settings.threads {
+    args.extend(["-t".to_string(), threads.to_string()]);
+  }
+  args
+}
+
+// the GGUF header read when the models were scanned, so a generation doesn't parse it again
+fn cached_metadata(model_path: &str) -> Option<GgufMetadata> {
+  let state = events::app()?.try_state::<Mutex<ModelManager>>()?;
+  let mgr = state.locked();
+  mgr.models.values().find(|m| m.path == model_path).and_then(|m| m.gguf.clone())
+}
+
+fn run(model_path: &str, config: &ModelConfig, prompt: &str, max_tokens: u32, options: RunOptions) -> Result<Generation, String> {
+  let schema = options.schema;
+  // a request with a deadline gets no more tokens than the model can produce in time
+  let max_tokens = dispatch::fit_max_tokens(model_path, max_tokens);
+  let model_dir = PathBuf::from(model_path);
+  let mut command_opt: Option<Command> = None;
+  let mut prompt_on_stdin = false;
+
+  if model_dir.is_dir() {
+    let run_sh = model_dir.join("run.sh");
+    let run_bat = model_dir.join("run.bat");
+    if run_sh.exists() {
+      let mut c = Command::new("sh");
+      c.arg(run_sh.to_string_lossy().to_string());
+      command_opt = Some(c);
+      prompt_on_stdin = true;
+    } else if run_bat.exists() {
+      let mut c = Command::new("cmd");
+      c.arg("/C").arg(run_bat.to_string_lossy().to_string());
+      command_opt = Some(c);
+      prompt_on_stdin = true;
+    }
+    if let Some(c) = command_opt.as_mut() {
+      if let Some(schema) = schema {
+        c.env("MULTILINGUAL_JSON_SCHEMA", schema);
+      }
+      // the whole prompt comes on stdin, then the runner answers once and exits
+      c.env("MULTILINGUAL_ONESHOT", "1");
+      if let Some(n) = options.n_best {
+        c.env("MULTILINGUAL_N_BEST", n.to_string());
+      }
+      if !config.sampling.is_empty() {
+        c.env("MULTILINGUAL_SAMPLING", serde_json::to_string(&config.sampling).unwrap_or_default());
+      }
+    }
+  }
+
+  let mut runtime = None;
+  if command_opt.is_none() {
+    if let Some(rt) = runtime::select_runtime(model_path) {
+      // refuse what would run out of memory; the warnings are for starting a model
+      let id = Path::new(model_path).file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+      let meta = cached_metadata(model_path);
+      memory::preflight(&memory::estimate(&id, model_path, meta.as_ref(), config, rt.offload, context_size(config)))?;
+      runtime = Some(rt);
+    }
+  }
+  if command_opt.is_none() && runtime.is_none() {
+    return Ok(Generation { text: mock_completion(prompt), logprobs: None, candidates: None });
+  }
+
+  // one generation of a model at a time; a preempted batch generation is killed and picks up
+  // where it was once the foreground request that displaced it is done: a runtime continues from
+  // the prompt plus what it had written (not under a JSON schema, whose grammar starts over), a
+  // wrapper runner starts again
+  let priority = dispatch::current_priority();
+  let started = Instant::now();
+  let mut produced = String::new();
+  loop {
+    let slot = dispatch::acquire(model_path, priority);
+    let generation_started = Instant::now();
+    let mut resumed;
+    let c = match (command_opt.as_mut(), &runtime) {
+      (Some(c), _) => c,
+      (None, Some(rt)) => {
+        let remaining = max_tokens.saturating_sub(estimate_tokens(&produced)).max(MIN_RESUME_TOKENS);
+        resumed = runtime_command(rt, model_path, config, &format!("{}{}", prompt, produced), remaining, schema);
+        &mut resumed
+      }
+      (None, None) => unreachable!("the mock answered above"),
+    };
+    let out = match run_process(c, prompt, &produced, prompt_on_stdin, config.encoding.unwrap_or_default(), priority, &slot)? {
+      Run::Done(out) => out,
+      Run::Preempted(partial) => {
+        if runtime.is_some() && schema.is_none() {
+          produced.push_str(&partial);
+        }
+        continue;
+      }
+    };
+    {
+      let millis = started.elapsed().as_millis() as u64;
+      let output_tokens = estimate_tokens(&out.text);
+      // short outputs say more about startup than about speed
+      if output_tokens >= 8 {
+        dispatch::record_speed(model_path, output_tokens as f32 / generation_started.elapsed().as_secs_f32().max(0.001));
+      }
+      events::emit_global(
+        "generation-metrics",
+        GenerationMetrics {
+          model_path: model_path.to_string(),
+          prompt_tokens: estimate_tokens(prompt),
+          output_tokens,
+          millis,
+          tokens_per_sec: output_tokens as f32 * 1000.0 / millis.max(1) as f32,
+        },
+      );
+      return Ok(out);
+    }
+  }
+}
+
+// the bundled runtime generating `max_tokens` after `prompt`
+fn runtime_command(rt: &runtime::RuntimeChoice, model_path: &str, config: &ModelConfig, prompt: &str, max_tokens: u32, schema: Option<&str>) -> Command {
+  let mut c = Command::new(&rt.exe);
+  c.args(["-m", model_path, "-p", prompt, "-n", &max_tokens.to_string(), "--no-display-prompt"]);
+  c.args(["-c", &context_size(config).to_string()]);
+  c.args(runtime_args(model_path, config, rt.offload));
+  c.args(config.sampling.runtime_args());
+  if let Some(split) = &config.gpu {
+    c.args(split.runtime_args());
+  }
+  if let Some(schema) = schema {
+    c.args(["--json-schema", schema]);
+  }
+  c
+}
+
+// How a generation process ended
+enum Run {
+  Done(Generation),
+  // killed to make room for a higher priority request, with what it had written by then
+  Preempted(String),
+}
+
+// `produced` is the output of earlier, preempted runs that this one continues
+fn run_process(
+  c: &mut Command,
+  prompt: &str,
+  produced: &str,
+  prompt_on_stdin: bool,
+  encoding: OutputEncoding,
+  priority: Priority,
+  slot: &dispatch::Slot
+) -> Result<Run, String> {
+  storage::apply(c);
+  c.stdin(if prompt_on_stdin { Stdio::piped() } else { Stdio::null() })
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped());
+  let mut child = c.spawn().map_err(|e| format!("Failed to spawn child: {}", e))?;
+
+  if prompt_on_stdin {
+    if let Some(mut stdin) = child.stdin.take() {
+      let text = prompt.to_string();
+      // write from a thread so a runner that streams output early can't deadlock us
+      thread::spawn(move || {
+        let _ = stdin.write_all(text.as_bytes());
+      });
+    }
+  }
+
+  let mut stderr = child.stderr.take();
+  let err_reader = thread::spawn(move || {
+    let mut buf = Vec::new();
+    if let Some(e) = stderr.as_mut() {
+      let _ = e.read_to_end(&mut buf);
+    }
+    LineDecoder::new(encoding).decode(&buf)
+  });
+
+  let mut stdout = child.stdout.take();
+  let out_reader = thread::spawn(move || {
+    let mut buf = Vec::new();
+    if let Some(o) = stdout.as_mut() {
+      o.read_to_end(&mut buf)?;
+    }
+    Ok::<_, std::io::Error>(LineDecoder::new(encoding).decode(&buf))
+  });
+
+  let status = loop {
+    if let Some(status) = child.try_wait().map_err(|e| format!("failed to wait for model: {}", e))? {
+      break status;
+    }
+    if priority == Priority::Batch && slot.preempted() {
+      let _ = child.kill();
+      let _ = child.wait();
+      // the pipe closes with the process, so the reader returns what it got
+      return Ok(Run::Preempted(out_reader.join().ok().and_then(Result::ok).unwrap_or_default()));
+    }
+    thread::sleep(PREEMPT_POLL);
+  };
+  let out = out_reader
+    .join()
+    .unwrap_or(Ok(String::new()))
+    .map_err(|e| format!("failed to read model output: {}", e))?;
+  let err = err_reader.join().unwrap_or_default();
+  if !status.success() {
+    let tail: Vec<&str> = err.lines().rev().take(5).collect();
+    return Err(format!("model exited with {}: {}", status, tail.into_iter().rev().collect::<Vec<_>>().join("\n")));
+  }
+  let out = out.trim();
+  // wrapper runners that stream structured messages (see protocol::OutputMessage); a CLI runtime's
+  // output is the model's text, JSON or not
+  if prompt_on_stdin {
+    if let Some(collected) = protocol::collect(out)? {
+      return Ok(Run::Done(Generation { text: collected.text.trim().to_string(), logprobs: None, candidates: None }));
+    }
+  }
+  if prompt_on_stdin && out.starts_with('{') {
+    if let Ok(serde_json::Value::Object(obj)) = serde_json::from_str::<serde_json::Value>(out) {
+      if let Some(text) = obj.get("text").and_then(|v| v.as_str()) {
+        let logprobs = obj.get("logprobs").and_then(|v| serde_json::from_value(v.clone()).ok());
+        let candidates = obj.get("candidates").and_then(|v| serde_json::from_value(v.clone()).ok());
+        return Ok(Run::Done(Generation { text: text.trim().to_string(), logprobs, candidates }));
+      }
+    }
+  }
+  let text = format!("{}{}", produced, out);
+  Ok(Run::Done(Generation { text: text.trim().to_string(), logprobs: None, candidates: None }))
+}
+
+// deterministic stand-in for a real model (mirrors the python mock used for streaming)
+fn mock_completion(prompt: &str) -> String {
+  // skip trailing role labels such as "Assistant:"
+  let last = prompt
+    .lines()
+    .rev()
+    .map(|l| l.trim())
+    .find(|l| !l.is_empty() && !l.ends_with(':'))
+    .unwrap_or("");
+  format!("[mock] {}", last)
+}
+
+// pull the first JSON object out of model output (models like to wrap it in prose or ``` fences)
+pub fn extract_json(output: &str) -> Option<serde_json::Value> {
+  let start = output.find('{')?;
+  let end = output.rfind('}')?;
+  if end < start {
+    return None;
+  }
+  serde_json::from_str(&output[start..=end]).ok()
+}
@@ -0,0 +1,103 @@
+// src-tauri/src/error.rs
+use std::io;
+use std::sync::{Mutex, MutexGuard};
+
+use crate::events;
+use crate::instance;
+
+// What a command failed with. The frontend gets {"code", "message", "context"} so it can react to
+// the kind of failure (offer a download for a missing model, point at the instance holding the
+// data folder) instead of matching on the text
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+  // a model, session, download, ... that doesn't exist; `id` goes out as the context
+  #[error("{what} '{id}' not found")]
+  NotFound { what: &'static str, id: String },
+  // arguments the command can't work with
+  #[error("{0}")]
+  InvalidInput(String),
+  // something already in progress that has to finish or be stopped first
+  #[error("{0}")]
+  Busy(String),
+  // another instance holds the data folder, so nothing can be saved
+  #[error("{0}")]
+  ReadOnly(String),
+  // reading or writing `path` failed; the path goes out as the context
+  #[error("failed to {action} {path}: {source}")]
+  Io { action: &'static str, path: String, source: io::Error },
+  // everything the layers below the commands report as text
+  #[error("{0}")]
+  Failed(String),
+}
+
+impl AppError {
+  pub fn not_found(what: &'static str, id: &str) -> Self {
+    AppError::NotFound { what, id: id.to_string() }
+  }
+
+  pub fn io(action: &'static str, path: impl AsRef<std::path::Path>, source: io::Error) -> Self {
+    AppError::Io { action, path: path.as_ref().to_string_lossy().to_string(), source }
+  }
+
+  pub fn code(&self) -> &'static str {
+    match self {
+      AppError::NotFound { .. } => "not_found",
+      AppError::InvalidInput(_) => "invalid_input",
+      AppError::Busy(_) => "busy",
+      AppError::ReadOnly(_) => "read_only",
+      AppError::Io { .. } => "io",
+      AppError::Failed(_) => "failed",
+    }
+  }
+
+  fn context(&self) -> Option<&str> {
+    match self {
+      AppError::NotFound { id, .. } => Some(id),
+      AppError::Io { path, .. } => Some(path),
+      _ => None,
+    }
+  }
+}
+
+// the layers below report a save refused in read-only mode as text too; it keeps its own code
+impl From<String> for AppError {
+  fn from(message: String) -> Self {
+    if message.starts_with(instance::IN_USE) {
+      return AppError::ReadOnly(message);
+    }
+    AppError::Failed(message)
+  }
+}
+
+impl From<&str> for AppError {
+  fn from(message: &str) -> Self {
+    AppError::from(message.to_string())
+  }
+}
+
+impl serde::Serialize for AppError {
+  fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    use serde::ser::SerializeStruct;
+    let mut s = serializer.serialize_struct("AppError", 3)?;
+    s.serialize_field("code", self.code())?;
+    s.serialize_field("message", &self.to_string())?;
+    s.serialize_field("context", &self.context())?;
+    s.end()
+  }
+}
+
+// lock() that carries on with the data as a panicking thread left it, instead of panicking in
+// every later caller too
+pub trait LockExt<T> {
+  fn locked(&self) -> MutexGuard<'_, T>;
+}
+
+impl<T> LockExt<T> for Mutex<T> {
+  fn locked(&self) -> MutexGuard<'_, T> {
+    self.lock().unwrap_or_else(|poisoned| {
+      events::log("[error] a thread panicked while holding a lock; continuing with its data".into());
+      self.clear_poison();
+      poisoned.into_inner()
+    })
+  }
+}
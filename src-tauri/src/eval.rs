@@ -0,0 +1,353 @@
+// src-tauri/src/eval.rs
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use tauri::{Manager, Window};
+
+use crate::dispatch::{self, Priority};
+use crate::error::{AppError, LockExt};
+use crate::events;
+use crate::store;
+use crate::translate;
+use crate::ModelManager;
+
+// BLEU n-gram order and chrF character n-gram order / recall weight (sacrebleu defaults)
+const BLEU_ORDER: usize = 4;
+const CHRF_ORDER: usize = 6;
+const CHRF_BETA: f64 = 2.0;
+
+// One line of a test set
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct TestPair {
+  pub source: String,
+  pub reference: String,
+}
+
+// Scores of one model over the whole test set (0-100)
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct ModelScore {
+  pub model_id: String,
+  pub bleu: f64,
+  pub chrf: f64,
+  pub segments: usize,
+  // segments the model failed to translate (scored as empty output)
+  pub failures: usize,
+  pub millis: u64,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct EvalReport {
+  pub id: u64,
+  // unix seconds
+  pub created: i64,
+  pub test_set: String,
+  pub source_lang: String,
+  pub target_lang: String,
+  // best chrF first
+  pub results: Vec<ModelScore>,
+}
+
+// Payload of "eval-progress" events
+#[derive(Clone, Debug, serde::Serialize)]
+struct EvalProgress {
+  model_id: String,
+  done: usize,
+  total: usize,
+}
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct ReportFile {
+  next_id: u64,
+  reports: Vec<EvalReport>,
+}
+
+// Evaluation reports persisted in ./data/eval.json (managed by Tauri)
+pub struct EvalReports {
+  path: PathBuf,
+  file: ReportFile,
+}
+
+impl EvalReports {
+  pub fn load() -> Self {
+    let path = store::data_file("eval.json");
+    let file = store::load_json(&path);
+    Self { path, file }
+  }
+
+  fn add(&mut self, mut report: EvalReport) -> Result<EvalReport, String> {
+    self.file.next_id += 1;
+    report.id = self.file.next_id;
+    self.file.reports.push(report.clone());
+    store::save_json(&self.path, &self.file)?;
+    Ok(report)
+  }
+}
+
+// a JSON array of {source, reference}, or tab separated "source<TAB>reference" lines
+fn load_test_set(path: &Path) -> Result<Vec<TestPair>, String> {
+  let text = fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path.to_string_lossy(), e))?;
+  let pairs: Vec<TestPair> = if text.trim_start().starts_with('[') {
+    serde_json::from_str(&text).map_err(|e| format!("invalid test set {}: {}", path.to_string_lossy(), e))?
+  } else {
+    text
+      .lines()
+      .filter(|l| !l.trim().is_empty())
+      .map(|l| match l.split_once('\t') {
+        Some((s, r)) => Ok(TestPair { source: s.trim().into(), reference: r.trim().into() }),
+        None => Err(format!("line without a tab in {}: {}", path.to_string_lossy(), l)),
+      })
+      .collect::<Result<_, _>>()?
+  };
+  if pairs.is_empty() {
+    return Err(format!("Test set {} is empty", path.to_string_lossy()));
+  }
+  Ok(pairs)
+}
+
+// scripts written without spaces are scored per character
+fn is_unspaced(c: char) -> bool {
+  matches!(c as u32, 0x3040..=0x30FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xE00..=0xE7F | 0xFF00..=0xFFEF)
+}
+
+// words, with punctuation and CJK/Thai characters as separate tokens
+fn tokenize(text: &str) -> Vec<String> {
+  let mut tokens = Vec::new();
+  let mut word = String::new();
+  for c in text.chars() {
+    if c.is_alphanumeric() && !is_unspaced(c) {
+      word.push(c);
+      continue;
+    }
+    if !word.is_empty() {
+      tokens.push(std::mem::take(&mut word));
+    }
+    if !c.is_whitespace() {
+      tokens.push(c.to_string());
+    }
+  }
+  if !word.is_empty() {
+    tokens.push(word);
+  }
+  tokens
+}
+
+fn ngram_counts<T: std::hash::Hash + Eq + Clone>(items: &[T], n: usize) -> HashMap<Vec<T>, usize> {
+  let mut counts = HashMap::new();
+  if items.len() >= n {
+    for gram in items.windows(n) {
+      *counts.entry(gram.to_vec()).or_insert(0) += 1;
+    }
+  }
+  counts
+}
+
+// (matching, hypothesis total, reference total) n-grams of one order
+fn overlap<T: std::hash::Hash + Eq + Clone>(hyp: &[T], reference: &[T], n: usize) -> (usize, usize, usize) {
+  let h = ngram_counts(hyp, n);
+  let r = ngram_counts(reference, n);
+  let matching = h.iter().map(|(g, c)| (*c).min(*r.get(g).unwrap_or(&0))).sum();
+  (matching, h.values().sum(), r.values().sum())
+}
+
+// corpus BLEU with brevity penalty, 0-100
+fn corpus_bleu(pairs: &[(String, String)]) -> f64 {
+  let mut matches = [0usize; BLEU_ORDER];
+  let mut totals = [0usize; BLEU_ORDER];
+  let (mut hyp_len, mut ref_len) = (0, 0);
+  for (hyp, reference) in pairs {
+    let (h, r) = (tokenize(hyp), tokenize(reference));
+    hyp_len += h.len();
+    ref_len += r.len();
+    for n in 1..=BLEU_ORDER {
+      let (m, t, _) = overlap(&h, &r, n);
+      matches[n - 1] += m;
+      totals[n - 1] += t;
+    }
+  }
+  if hyp_len == 0 || matches.contains(&0) {
+    return 0.0;
+  }
+  let log_precision: f64 = (0..BLEU_ORDER).map(|i| (matches[i] as f64 / totals[i] as f64).ln()).sum::<f64>() / BLEU_ORDER as f64;
+  let brevity = if hyp_len < ref_len { (1.0 - ref_len as f64 / hyp_len as f64).exp() } else { 1.0 };
+  100.0 * brevity * log_precision.exp()
+}
+
+// corpus chrF (character n-grams 1..6, whitespace ignored, recall weighted by beta), 0-100
+fn corpus_chrf(pairs: &[(String, String)]) -> f64 {
+  let mut stats = [(0usize, 0usize, 0usize); CHRF_ORDER];
+  for (hyp, reference) in pairs {
+    let h: Vec<char> = hyp.chars().filter(|c| !c.is_whitespace()).collect();
+    let r: Vec<char> = reference.chars().filter(|c| !c.is_whitespace()).collect();
+    for n in 1..=CHRF_ORDER {
+      let (m, ht, rt) = overlap(&h, &r, n);
+      stats[n - 1].0 += m;
+      stats[n - 1].1 += ht;
+      stats[n - 1].2 += rt;
+    }
+  }
+  let orders: Vec<(f64, f64)> = stats
+    .iter()
+    .filter(|(_, ht, rt)| *ht > 0 && *rt > 0)
+    .map(|(m, ht, rt)| (*m as f64 / *ht as f64, *m as f64 / *rt as f64))
+    .collect();
+  if orders.is_empty() {
+    return 0.0;
+  }
+  let precision = orders.iter().map(|o| o.0).sum::<f64>() / orders.len() as f64;
+  let recall = orders.iter().map(|o| o.1).sum::<f64>() / orders.len() as f64;
+  let beta2 = CHRF_BETA * CHRF_BETA;
+  if precision + recall == 0.0 {
+    return 0.0;
+  }
+  100.0 * (1.0 + beta2) * precision * recall / (beta2 * precision + recall)
+}
+
+fn round2(x: f64) -> f64 {
+  (x * 100.0).round() / 100.0
+}
+
+fn score_model(window: &Window, model_id: &str, pairs: &[TestPair], source_lang: &str, target_lang: &str) -> Result<ModelScore, String> {
+  let (model, config) = window.state::<Mutex<ModelManager>>().locked().model_for_request(Some(model_id))?;
+  let started = Instant::now();
+  let mut scored = Vec::new();
+  let mut failures = 0;
+  for (i, pair) in pairs.iter().enumerate() {
+    let hyp = translate::translate_text(&model.path, &config, &pair.source, source_lang, target_lang).unwrap_or_else(|_| {
+      failures += 1;
+      String::new()
+    });
+    scored.push((hyp, pair.reference.clone()));
+    events::emit(window, "eval-progress", EvalProgress { model_id: model_id.into(), done: i + 1, total: pairs.len() });
+  }
+  Ok(ModelScore {
+    model_id: model_id.into(),
+    bleu: round2(corpus_bleu(&scored)),
+    chrf: round2(corpus_chrf(&scored)),
+    segments: pairs.len(),
+    failures,
+    millis: started.elapsed().as_millis() as u64,
+  })
+}
+
+// ------------------ Tauri commands ------------------
+
+// translate a test set with each model, score the output against the references and save the report
+#[tauri::command(async)]
+pub fn evaluate_models(
+  test_set: String,
+  source_lang: String,
+  target_lang: String,
+  model_ids: Vec<String>,
+  window: Window
+) -> Result<EvalReport, AppError> {
+  if model_ids.is_empty() {
+    return Err(AppError::InvalidInput("Select at least one model to evaluate".into()));
+  }
+  let pairs = load_test_set(Path::new(&test_set))?;
+  let mut results = dispatch::with_priority(Priority::Batch, || {
+    model_ids.iter().map(|id| score_model(&window, id, &pairs, &source_lang, &target_lang)).collect::<Result<Vec<_>, _>>()
+  })?;
+  results.sort_by(|a, b| b.chrf.total_cmp(&a.chrf));
+  let report = EvalReport {
+    id: 0,
+    created: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0),
+    test_set,
+    source_lang,
+    target_lang,
+    results,
+  };
+  Ok(window.state::<Mutex<EvalReports>>().locked().add(report)?)
+}
+
+// newest first, optionally only for one language pair
+#[tauri::command]
+pub fn list_eval_reports(
+  source_lang: Option<String>,
+  target_lang: Option<String>,
+  reports: tauri::State<'_, Mutex<EvalReports>>
+) -> Vec<EvalReport> {
+  let reports = reports.locked();
+  reports
+    .file
+    .reports
+    .iter()
+    .rev()
+    .filter(|r| source_lang.as_ref().is_none_or(|l| &r.source_lang == l))
+    .filter(|r| target_lang.as_ref().is_none_or(|l| &r.target_lang == l))
+    .cloned()
+    .collect()
+}
+
+#[tauri::command]
+pub fn delete_eval_report(id: u64, reports: tauri::State<'_, Mutex<EvalReports>>) -> Result<(), AppError> {
+  let mut reports = reports.locked();
+  let before = reports.file.reports.len();
+  reports.file.reports.retain(|r| r.id != id);
+  if reports.file.reports.len() == before {
+    return Err(AppError::not_found("Report", &id.to_string()));
+  }
+  Ok(store::save_json(&reports.path, &reports.file)?)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn pair(hyp: &str, reference: &str) -> Vec<(String, String)> {
+    vec![(hyp.to_string(), reference.to_string())]
+  }
+
+  fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 0.01
+  }
+
+  #[test]
+  fn tokenizes_words_punctuation_and_cjk() {
+    assert_eq!(tokenize("Hello, world!"), ["Hello", ",", "world", "!"]);
+    assert_eq!(tokenize("東京へ行く"), ["東", "京", "へ", "行", "く"]);
+    assert_eq!(tokenize("l'état 3.5"), ["l", "'", "état", "3", ".", "5"]);
+  }
+
+  #[test]
+  fn bleu_matches_the_reference_implementation() {
+    assert!(close(corpus_bleu(&pair("the cat sat on the mat", "the cat sat on the mat")), 100.0));
+    // precisions 5/6, 3/5, 2/4, 1/3
+    assert!(close(corpus_bleu(&pair("the cat sat on the mat", "the cat sat on a mat")), 53.73));
+    assert_eq!(corpus_bleu(&pair("dog", "the cat sat on the mat")), 0.0);
+    assert_eq!(corpus_bleu(&pair("", "the cat")), 0.0);
+  }
+
+  #[test]
+  fn bleu_penalizes_short_output() {
+    // every n-gram matches, 5 of 7 tokens: exp(1 - 7/5)
+    assert!(close(corpus_bleu(&pair("the cat sat on the", "the cat sat on the mat today")), 67.03));
+  }
+
+  #[test]
+  fn bleu_is_pooled_over_the_corpus() {
+    let pairs = [pair("the cat sat on the mat", "the cat sat on the mat"), pair("a b", "c d")].concat();
+    let score = corpus_bleu(&pairs);
+    assert!(score > 0.0 && score < 100.0, "{}", score);
+  }
+
+  #[test]
+  fn chrf_scores_characters_and_ignores_spaces() {
+    assert!(close(corpus_chrf(&pair("the cat", "the cat")), 100.0));
+    assert!(close(corpus_chrf(&pair("thecat", "the cat")), 100.0));
+    assert_eq!(corpus_chrf(&pair("xyz", "abc")), 0.0);
+    assert_eq!(corpus_chrf(&pair("", "abc")), 0.0);
+    let partial = corpus_chrf(&pair("the cat sat on the mat", "the cat sat on a mat"));
+    assert!(partial > 60.0 && partial < 80.0, "{}", partial);
+  }
+
+  #[test]
+  fn chrf_weights_recall_over_precision() {
+    // the same matches against a longer reference (recall lost) and a longer output (precision lost)
+    let missing = corpus_chrf(&pair("abc", "abcdef"));
+    let extra = corpus_chrf(&pair("abcdef", "abc"));
+    assert!(missing < extra, "{} {}", missing, extra);
+  }
+}
@@ -0,0 +1,126 @@
+// src-tauri/src/events.rs
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+
+use tauri::{AppHandle, Emitter, Runtime};
+
+use crate::error::AppError;
+use crate::store;
+
+// progress and health updates, sent at normal verbosity and above
+const NORMAL_EVENTS: &[&str] = &[
+  "system-pressure",
+  "context-usage",
+  "summarize-progress",
+  "quantize-progress",
+  "download-progress",
+  "eval-progress",
+  "job-progress",
+  "pipeline-stage",
+  "watch-file-status",
+  "schedule-run",
+];
+// internal diagnostics, only sent at debug verbosity
+const DEBUG_EVENTS: &[&str] = &["debug-log", "generation-metrics"];
+
+// How much besides tokens, status and results is sent to the webview
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Verbosity {
+  Quiet,
+  #[default]
+  Normal,
+  Debug,
+}
+
+impl Verbosity {
+  fn from_u8(v: u8) -> Self {
+    match v {
+      0 => Verbosity::Quiet,
+      2 => Verbosity::Debug,
+      _ => Verbosity::Normal,
+    }
+  }
+}
+
+// ./data/events.json
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct EventSettings {
+  verbosity: Verbosity,
+}
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(Verbosity::Normal as u8);
+// for events raised where no window or app handle is at hand (engine, runtime selection)
+static APP: OnceLock<AppHandle> = OnceLock::new();
+
+pub fn verbosity() -> Verbosity {
+  Verbosity::from_u8(VERBOSITY.load(Ordering::Relaxed))
+}
+
+// load the saved level and remember the app handle; called once from setup
+pub fn init(app: &AppHandle) {
+  let settings: EventSettings = store::load_json(&store::data_file("events.json"));
+  VERBOSITY.store(settings.verbosity as u8, Ordering::Relaxed);
+  let _ = APP.set(app.clone());
+}
+
+fn level(event: &str) -> Verbosity {
+  if DEBUG_EVENTS.contains(&event) {
+    Verbosity::Debug
+  } else if NORMAL_EVENTS.contains(&event) {
+    Verbosity::Normal
+  } else {
+    Verbosity::Quiet
+  }
+}
+
+pub fn enabled(event: &str) -> bool {
+  level(event) <= verbosity()
+}
+
+// emit `event` unless the current verbosity filters it out
+pub fn emit<R: Runtime, S: serde::Serialize + Clone>(target: &impl Emitter<R>, event: &str, payload: S) {
+  if enabled(event) {
+    let _ = target.emit(event, payload);
+  }
+}
+
+// the app handle; waits for setup to get that far (the managed state is in place by then)
+pub fn wait_app() -> &'static AppHandle {
+  APP.wait()
+}
+
+// the app handle, once setup has set it
+pub fn app() -> Option<&'static AppHandle> {
+  APP.get()
+}
+
+// emit from anywhere, once the app is running
+pub fn emit_global<S: serde::Serialize + Clone>(event: &str, payload: S) {
+  if let Some(app) = APP.get() {
+    emit(app, event, payload);
+  }
+}
+
+// diagnostic line: printed and sent as "debug-log" at debug verbosity
+pub fn log(message: String) {
+  if verbosity() < Verbosity::Debug {
+    return;
+  }
+  println!("DEBUG: {}", message);
+  emit_global("debug-log", message);
+}
+
+// ------------------ Tauri commands ------------------
+
+#[tauri::command]
+pub fn get_event_verbosity() -> Verbosity {
+  verbosity()
+}
+
+#[tauri::command]
+pub fn set_event_verbosity(verbosity: Verbosity) -> Result<(), AppError> {
+  store::save_json(&store::data_file("events.json"), &EventSettings { verbosity })?;
+  VERBOSITY.store(verbosity as u8, Ordering::Relaxed);
+  Ok(())
+}
@@ -0,0 +1,81 @@
+// src-tauri/src/favorites.rs
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::error::{AppError, LockExt};
+use crate::store;
+
+// A translation the user starred
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Favorite {
+  pub id: u64,
+  pub source_lang: String,
+  pub target_lang: String,
+  pub source_text: String,
+  pub translated_text: String,
+}
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct FavoritesFile {
+  next_id: u64,
+  items: Vec<Favorite>,
+}
+
+// Favorites persisted in ./data/favorites.json (managed by Tauri)
+pub struct Favorites {
+  path: PathBuf,
+  file: FavoritesFile,
+}
+
+impl Favorites {
+  pub fn load() -> Self {
+    let path = store::data_file("favorites.json");
+    let file = store::load_json(&path);
+    Self { path, file }
+  }
+
+  pub fn items(&self) -> Vec<Favorite> {
+    self.file.items.clone()
+  }
+
+  pub fn add(&mut self, mut fav: Favorite) -> Result<Favorite, String> {
+    self.file.next_id += 1;
+    fav.id = self.file.next_id;
+    self.file.items.push(fav.clone());
+    store::save_json(&self.path, &self.file)?;
+    Ok(fav)
+  }
+
+  pub fn remove(&mut self, id: u64) -> Result<(), String> {
+    let before = self.file.items.len();
+    self.file.items.retain(|f| f.id != id);
+    if self.file.items.len() == before {
+      return Err(format!("Favorite {} not found", id));
+    }
+    store::save_json(&self.path, &self.file)
+  }
+}
+
+// ------------------ Tauri commands ------------------
+
+#[tauri::command]
+pub fn list_favorites(favorites: tauri::State<'_, Mutex<Favorites>>) -> Vec<Favorite> {
+  favorites.locked().items()
+}
+
+#[tauri::command]
+pub fn add_favorite(
+  source_lang: String,
+  target_lang: String,
+  source_text: String,
+  translated_text: String,
+  favorites: tauri::State<'_, Mutex<Favorites>>
+) -> Result<Favorite, AppError> {
+  let fav = Favorite { id: 0, source_lang, target_lang, source_text, translated_text };
+  Ok(favorites.locked().add(fav)?)
+}
+
+#[tauri::command]
+pub fn remove_favorite(id: u64, favorites: tauri::State<'_, Mutex<Favorites>>) -> Result<(), AppError> {
+  Ok(favorites.locked().remove(id)?)
+}
@@ -0,0 +1,245 @@
+// src-tauri/src/filter.rs
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use regex::{Regex, RegexBuilder};
+use tauri::{Emitter, Manager, Runtime};
+
+use crate::engine;
+use crate::error::{AppError, LockExt};
+use crate::events;
+use crate::store;
+use crate::ModelManager;
+
+// one term per line, '#' comments, "word*" matches any word starting with "word"; in the data folder
+const LISTS_DIR: &str = "filters";
+// what a filtered output is replaced with when the classifier flags it as a whole
+const REPLACEMENT: &str = "[filtered]";
+// compiled size allowed for the word lists; the regex default (10 MB) is hit by lists of some
+// tens of thousands of words
+const PATTERN_SIZE_LIMIT: usize = 256 << 20;
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FilterAction {
+  // replace matched words with asterisks
+  Mask,
+  // reject the request (input) or the answer (output)
+  Block,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct FilterSettings {
+  pub enabled: bool,
+  pub check_input: bool,
+  pub check_output: bool,
+  pub action: FilterAction,
+  // word lists to use (file stems in ./data/filters); empty uses every list
+  #[serde(default)]
+  pub languages: Vec<String>,
+  // model asked to classify text the word lists let through
+  #[serde(default)]
+  pub classifier_model: Option<String>,
+}
+
+impl Default for FilterSettings {
+  fn default() -> Self {
+    Self {
+      enabled: false,
+      check_input: true,
+      check_output: true,
+      action: FilterAction::Mask,
+      languages: Vec::new(),
+      classifier_model: None,
+    }
+  }
+}
+
+// What the filter did to one text; returned with results and emitted as "content-filter"
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct FilterReport {
+  // "input" | "output"
+  pub stage: String,
+  pub action: FilterAction,
+  pub matches: Vec<String>,
+  // category reported by the classifier model
+  pub classifier: Option<String>,
+}
+
+// Filter settings persisted in ./data/filter.json plus the compiled word lists (managed by Tauri)
+pub struct ContentFilter {
+  path: PathBuf,
+  settings: FilterSettings,
+  pattern: Option<Regex>,
+  // why the word lists didn't compile when the app started; the filter blocks text until fixed
+  error: Option<String>,
+}
+
+// Whether the filter is working, for the settings screen
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct FilterStatus {
+  pub enabled: bool,
+  // word lists compiled and in use
+  pub word_lists: bool,
+  // why the word lists can't be used; while set, an enabled filter blocks what it should check
+  pub error: Option<String>,
+}
+
+fn list_files() -> Vec<PathBuf> {
+  let mut files: Vec<PathBuf> = fs::read_dir(store::data_file(LISTS_DIR))
+    .map(|rd| rd.flatten().map(|e| e.path()).filter(|p| p.extension().is_some_and(|x| x == "txt")).collect())
+    .unwrap_or_default();
+  files.sort();
+  files
+}
+
+// spaced scripts match whole words; terms in scripts without spaces (CJK, Thai) match anywhere
+fn term_pattern(term: &str) -> String {
+  let (stem, prefix) = match term.strip_suffix('*') {
+    Some(s) => (s, true),
+    None => (term, false),
+  };
+  let body = regex::escape(stem);
+  if stem.chars().all(|c| c.is_alphanumeric() && (c as u32) < 0x2E80) {
+    format!(r"\b{}{}", body, if prefix { r"\w*" } else { r"\b" })
+  } else {
+    body
+  }
+}
+
+// the selected word lists as one pattern; None without any terms, Err when they don't compile
+fn compile(settings: &FilterSettings) -> Result<Option<Regex>, String> {
+  let mut terms: Vec<String> = Vec::new();
+  for file in list_files() {
+    let lang = file.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    if !settings.languages.is_empty() && !settings.languages.contains(&lang) {
+      continue;
+    }
+    let text = fs::read_to_string(&file).unwrap_or_default();
+    terms.extend(text.lines().map(|l| l.trim()).filter(|l| !l.is_empty() && !l.starts_with('#')).map(term_pattern));
+  }
+  if terms.is_empty() {
+    return Ok(None);
+  }
+  // longest first so "bad*" doesn't shadow "badword"
+  terms.sort_by_key(|t| std::cmp::Reverse(t.len()));
+  RegexBuilder::new(&format!("(?i)(?:{})", terms.join("|")))
+    .size_limit(PATTERN_SIZE_LIMIT)
+    .build()
+    .map(Some)
+    .map_err(|e| format!("The content filter word lists can't be used: {}", e))
+}
+
+impl ContentFilter {
+  pub fn load() -> Self {
+    let path = store::data_file("filter.json");
+    let settings: FilterSettings = store::load_json(&path);
+    let (pattern, error) = match compile(&settings) {
+      Ok(pattern) => (pattern, None),
+      Err(e) => {
+        events::log(format!("[filter] {}", e));
+        (None, Some(e))
+      }
+    };
+    Self { path, settings, pattern, error }
+  }
+}
+
+// classifier verdict: Some(category) when the model flags the text
+fn classify<R: Runtime>(manager: &impl Manager<R>, model_id: &str, text: &str) -> Result<Option<String>, String> {
+  let (model, config) = manager.state::<Mutex<ModelManager>>().locked().model_for_request(Some(model_id))?;
+  let schema = serde_json::json!({
+    "type": "object",
+    "properties": { "flagged": { "type": "boolean" }, "category": { "type": "string" } },
+    "required": ["flagged", "category"]
+  });
+  let prompt = format!(
+    "You moderate text for an app used by children and in public kiosks. Decide whether the text below contains \
+     profanity, sexual content, hate, harassment, violence or self-harm. Reply with JSON only: \
+     {{\"flagged\": true|false, \"category\": \"none\" or the category}}.\n\nText:\n{}",
+    text
+  );
+  let verdict = engine::generate_json(&model.path, &config, &prompt, 32, &schema)?;
+  let flagged = verdict.get("flagged").and_then(|v| v.as_bool()).unwrap_or(false);
+  Ok(flagged.then(|| verdict.get("category").and_then(|v| v.as_str()).unwrap_or("flagged").to_string()))
+}
+
+// run the filter over `text`; returns the (possibly masked) text and a report when anything was found.
+// Err when the action is block and the text was flagged
+fn run<R: Runtime>(target: &(impl Manager<R> + Emitter<R>), stage: &str, text: String) -> Result<(String, Option<FilterReport>), String> {
+  let (settings, pattern, error) = {
+    let filter = target.state::<Mutex<ContentFilter>>();
+    let filter = filter.locked();
+    (filter.settings.clone(), filter.pattern.clone(), filter.error.clone())
+  };
+  let wanted = if stage == "input" { settings.check_input } else { settings.check_output };
+  if !settings.enabled || !wanted {
+    return Ok((text, None));
+  }
+  // a filter that can't run lets nothing through rather than everything
+  if let Some(error) = error {
+    return Err(error);
+  }
+
+  let matches: Vec<String> = pattern.as_ref().map(|p| p.find_iter(&text).map(|m| m.as_str().to_string()).collect()).unwrap_or_default();
+  let classifier = match (&settings.classifier_model, matches.is_empty()) {
+    (Some(model_id), true) => classify(target, model_id, &text)?,
+    _ => None,
+  };
+  if matches.is_empty() && classifier.is_none() {
+    return Ok((text, None));
+  }
+
+  let report = FilterReport { stage: stage.into(), action: settings.action, matches, classifier };
+  let _ = target.emit("content-filter", report.clone());
+  if settings.action == FilterAction::Block {
+    return Err(format!("The {} was blocked by the content filter", stage));
+  }
+  let out = match &pattern {
+    Some(p) if report.classifier.is_none() => p.replace_all(&text, |c: &regex::Captures| "*".repeat(c[0].chars().count())).into_owned(),
+    _ => REPLACEMENT.to_string(),
+  };
+  Ok((out, Some(report)))
+}
+
+// filter text sent to a model
+pub fn check_input<R: Runtime>(target: &(impl Manager<R> + Emitter<R>), text: String) -> Result<(String, Option<FilterReport>), String> {
+  run(target, "input", text)
+}
+
+// filter a model's answer before it reaches the user
+pub fn check_output<R: Runtime>(target: &(impl Manager<R> + Emitter<R>), text: String) -> Result<(String, Option<FilterReport>), String> {
+  run(target, "output", text)
+}
+
+// ------------------ Tauri commands ------------------
+
+#[tauri::command]
+pub fn get_filter_settings(filter: tauri::State<'_, Mutex<ContentFilter>>) -> FilterSettings {
+  filter.locked().settings.clone()
+}
+
+#[tauri::command]
+pub fn get_filter_status(filter: tauri::State<'_, Mutex<ContentFilter>>) -> FilterStatus {
+  let filter = filter.locked();
+  FilterStatus { enabled: filter.settings.enabled, word_lists: filter.pattern.is_some(), error: filter.error.clone() }
+}
+
+// save settings and recompile the word lists (also picks up edited list files); Err, with nothing
+// changed, when the lists don't compile
+#[tauri::command]
+pub fn set_filter_settings(settings: FilterSettings, filter: tauri::State<'_, Mutex<ContentFilter>>) -> Result<(), AppError> {
+  let pattern = compile(&settings)?;
+  let mut filter = filter.locked();
+  filter.pattern = pattern;
+  filter.error = None;
+  filter.settings = settings;
+  Ok(store::save_json(&filter.path, &filter.settings)?)
+}
+
+// word list names available in ./data/filters
+#[tauri::command]
+pub fn list_filter_lists() -> Vec<String> {
+  list_files().iter().filter_map(|p| p.file_stem().map(|s| s.to_string_lossy().to_string())).collect()
+}
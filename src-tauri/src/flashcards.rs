@@ -0,0 +1,243 @@
+// src-tauri/src/flashcards.rs
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::{AppError, LockExt};
+use crate::favorites::Favorites;
+use crate::glossary::Glossary;
+use crate::store;
+
+const DAY_SECS: i64 = 24 * 60 * 60;
+
+fn now_secs() -> i64 {
+  SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0)
+}
+
+// A card with its SM-2 scheduling state
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Card {
+  pub id: u64,
+  pub deck_id: u64,
+  pub front: String,
+  pub back: String,
+  pub ease: f32,
+  pub interval_days: u32,
+  pub repetitions: u32,
+  pub lapses: u32,
+  // unix seconds when the card is next due
+  pub due: i64,
+}
+
+impl Card {
+  // SM-2: quality 0-5, below 3 counts as a lapse and restarts the schedule
+  fn review(&mut self, quality: u8, now: i64) {
+    let q = quality.min(5) as f32;
+    if quality < 3 {
+      self.repetitions = 0;
+      self.interval_days = 1;
+      self.lapses += 1;
+    } else {
+      self.repetitions += 1;
+      self.interval_days = match self.repetitions {
+        1 => 1,
+        2 => 6,
+        _ => (self.interval_days as f32 * self.ease).round() as u32,
+      };
+    }
+    self.ease = (self.ease + 0.1 - (5.0 - q) * (0.08 + (5.0 - q) * 0.02)).max(1.3);
+    self.due = now + self.interval_days as i64 * DAY_SECS;
+  }
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Deck {
+  pub id: u64,
+  pub name: String,
+  // "glossary" | "favorites"
+  pub source: String,
+  pub cards: Vec<Card>,
+}
+
+// Deck listing without the cards
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct DeckSummary {
+  pub id: u64,
+  pub name: String,
+  pub source: String,
+  pub total: usize,
+  pub due: usize,
+}
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct DeckFile {
+  next_id: u64,
+  decks: Vec<Deck>,
+}
+
+// Decks persisted in ./data/flashcards.json (managed by Tauri)
+pub struct Flashcards {
+  path: PathBuf,
+  file: DeckFile,
+}
+
+impl Flashcards {
+  pub fn load() -> Self {
+    let path = store::data_file("flashcards.json");
+    let file = store::load_json(&path);
+    Self { path, file }
+  }
+
+  fn next_id(&mut self) -> u64 {
+    self.file.next_id += 1;
+    self.file.next_id
+  }
+
+  // create the deck if needed and add any (front, back) pairs it doesn't contain yet;
+  // existing cards keep their review history
+  fn sync_deck(&mut self, name: &str, source: &str, pairs: Vec<(String, String)>) -> Result<DeckSummary, String> {
+    let deck_idx = match self.file.decks.iter().position(|d| d.name == name && d.source == source) {
+      Some(i) => i,
+      None => {
+        let id = self.next_id();
+        self.file.decks.push(Deck { id, name: name.to_string(), source: source.to_string(), cards: Vec::new() });
+        self.file.decks.len() - 1
+      }
+    };
+
+    let now = now_secs();
+    for (front, back) in pairs {
+      if self.file.decks[deck_idx].cards.iter().any(|c| c.front == front && c.back == back) {
+        continue;
+      }
+      let id = self.next_id();
+      let deck = &mut self.file.decks[deck_idx];
+      deck.cards.push(Card {
+        id,
+        deck_id: deck.id,
+        front,
+        back,
+        ease: 2.5,
+        interval_days: 0,
+        repetitions: 0,
+        lapses: 0,
+        due: now,
+      });
+    }
+    store::save_json(&self.path, &self.file)?;
+    Ok(Self::summary(&self.file.decks[deck_idx], now))
+  }
+
+  fn summary(deck: &Deck, now: i64) -> DeckSummary {
+    DeckSummary {
+      id: deck.id,
+      name: deck.name.clone(),
+      source: deck.source.clone(),
+      total: deck.cards.len(),
+      due: deck.cards.iter().filter(|c| c.due <= now).count(),
+    }
+  }
+
+  pub fn decks(&self) -> Vec<DeckSummary> {
+    let now = now_secs();
+    self.file.decks.iter().map(|d| Self::summary(d, now)).collect()
+  }
+
+  // most overdue first
+  pub fn due_cards(&self, deck_id: u64, limit: usize) -> Result<Vec<Card>, String> {
+    let deck = self.file.decks.iter().find(|d| d.id == deck_id).ok_or(format!("Deck {} not found", deck_id))?;
+    let now = now_secs();
+    let mut due: Vec<Card> = deck.cards.iter().filter(|c| c.due <= now).cloned().collect();
+    due.sort_by_key(|c| c.due);
+    due.truncate(limit);
+    Ok(due)
+  }
+
+  pub fn answer(&mut self, card_id: u64, quality: u8) -> Result<Card, String> {
+    if quality > 5 {
+      return Err("quality must be between 0 and 5".into());
+    }
+    let card = self
+      .file
+      .decks
+      .iter_mut()
+      .flat_map(|d| d.cards.iter_mut())
+      .find(|c| c.id == card_id)
+      .ok_or(format!("Card {} not found", card_id))?;
+    card.review(quality, now_secs());
+    let card = card.clone();
+    store::save_json(&self.path, &self.file)?;
+    Ok(card)
+  }
+
+  pub fn delete_deck(&mut self, deck_id: u64) -> Result<(), String> {
+    let before = self.file.decks.len();
+    self.file.decks.retain(|d| d.id != deck_id);
+    if self.file.decks.len() == before {
+      return Err(format!("Deck {} not found", deck_id));
+    }
+    store::save_json(&self.path, &self.file)
+  }
+}
+
+// ------------------ Tauri commands ------------------
+
+#[tauri::command]
+pub fn list_decks(cards: tauri::State<'_, Mutex<Flashcards>>) -> Vec<DeckSummary> {
+  cards.locked().decks()
+}
+
+// build (or refresh) a deck from the glossary entries of a language pair
+#[tauri::command]
+pub fn build_deck_from_glossary(
+  source_lang: String,
+  target_lang: String,
+  glossary: tauri::State<'_, Mutex<Glossary>>,
+  cards: tauri::State<'_, Mutex<Flashcards>>
+) -> Result<DeckSummary, AppError> {
+  let pairs: Vec<(String, String)> = glossary
+    .locked()
+    .entries(Some(&source_lang), Some(&target_lang))
+    .into_iter()
+    .map(|e| (e.source, e.target))
+    .collect();
+  if pairs.is_empty() {
+    return Err(format!("No glossary entries for {} -> {}", source_lang, target_lang).into());
+  }
+  let name = format!("Glossary {} -> {}", source_lang, target_lang);
+  Ok(cards.locked().sync_deck(&name, "glossary", pairs)?)
+}
+
+// build (or refresh) a deck from starred translations
+#[tauri::command]
+pub fn build_deck_from_favorites(
+  favorites: tauri::State<'_, Mutex<Favorites>>,
+  cards: tauri::State<'_, Mutex<Flashcards>>
+) -> Result<DeckSummary, AppError> {
+  let pairs: Vec<(String, String)> = favorites
+    .locked()
+    .items()
+    .into_iter()
+    .map(|f| (f.source_text, f.translated_text))
+    .collect();
+  if pairs.is_empty() {
+    return Err("No favorites to build a deck from".into());
+  }
+  Ok(cards.locked().sync_deck("Favorites", "favorites", pairs)?)
+}
+
+#[tauri::command]
+pub fn get_due_cards(deck_id: u64, limit: Option<usize>, cards: tauri::State<'_, Mutex<Flashcards>>) -> Result<Vec<Card>, AppError> {
+  Ok(cards.locked().due_cards(deck_id, limit.unwrap_or(20))?)
+}
+
+// grade a review: 0 (blackout) .. 5 (perfect recall)
+#[tauri::command]
+pub fn answer_card(card_id: u64, quality: u8, cards: tauri::State<'_, Mutex<Flashcards>>) -> Result<Card, AppError> {
+  Ok(cards.locked().answer(card_id, quality)?)
+}
+
+#[tauri::command]
+pub fn delete_deck(deck_id: u64, cards: tauri::State<'_, Mutex<Flashcards>>) -> Result<(), AppError> {
+  Ok(cards.locked().delete_deck(deck_id)?)
+}
@@ -0,0 +1,443 @@
+// src-tauri/src/gguf.rs
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+// anything longer is a corrupt file rather than a real key or value
+const MAX_STRING: u64 = 16 << 20;
+
+// What the GGUF header says about a model
+#[derive(Clone, Debug, Default, serde::Serialize)]
+pub struct GgufMetadata {
+  // "llama", "qwen2", "gemma2", ...
+  pub architecture: Option<String>,
+  // general.name, as set by whoever converted the model
+  pub name: Option<String>,
+  // summed tensor sizes; None for split models (each shard only lists its own tensors)
+  pub parameters: Option<u64>,
+  // "Q4_K_M", "F16", ...
+  pub quantization: Option<String>,
+  // trained context window in tokens
+  pub context_length: Option<u64>,
+  // transformer blocks, i.e. the layers -ngl counts (the output layer comes on top)
+  pub block_count: Option<u64>,
+  // attention shape, for sizing the KV cache
+  pub embedding_length: Option<u64>,
+  pub head_count: Option<u64>,
+  pub head_count_kv: Option<u64>,
+  // bytes of tensor data, what the weights take in memory; None for split models like parameters
+  pub tensor_bytes: Option<u64>,
+  pub chat_template: Option<String>,
+}
+
+// llama.cpp's llama_ftype, stored as general.file_type
+fn file_type_name(file_type: u64) -> Option<&'static str> {
+  Some(match file_type {
+    0 => "F32",
+    1 => "F16",
+    2 => "Q4_0",
+    3 => "Q4_1",
+    7 => "Q8_0",
+    8 => "Q5_0",
+    9 => "Q5_1",
+    10 => "Q2_K",
+    11 => "Q3_K_S",
+    12 => "Q3_K_M",
+    13 => "Q3_K_L",
+    14 => "Q4_K_S",
+    15 => "Q4_K_M",
+    16 => "Q5_K_S",
+    17 => "Q5_K_M",
+    18 => "Q6_K",
+    19 => "IQ2_XXS",
+    20 => "IQ2_XS",
+    21 => "Q2_K_S",
+    22 => "IQ3_XS",
+    23 => "IQ3_XXS",
+    24 => "IQ1_S",
+    25 => "IQ4_NL",
+    26 => "IQ3_S",
+    27 => "IQ3_M",
+    28 => "IQ2_S",
+    29 => "IQ2_M",
+    30 => "IQ4_XS",
+    31 => "IQ1_M",
+    32 => "BF16",
+    36 => "TQ1_0",
+    37 => "TQ2_0",
+    _ => return None,
+  })
+}
+
+// (elements, bytes) of one block of a ggml tensor type
+fn type_block(tensor_type: u32) -> Option<(u64, u64)> {
+  Some(match tensor_type {
+    0 => (1, 4),
+    1 | 30 => (1, 2),
+    2 => (32, 18),
+    3 => (32, 20),
+    6 => (32, 22),
+    7 => (32, 24),
+    8 => (32, 34),
+    9 => (32, 36),
+    10 => (256, 84),
+    11 => (256, 110),
+    12 => (256, 144),
+    13 => (256, 176),
+    14 => (256, 210),
+    15 => (256, 292),
+    16 => (256, 66),
+    17 => (256, 74),
+    18 => (256, 98),
+    19 => (256, 50),
+    20 => (32, 18),
+    21 => (256, 110),
+    22 => (256, 82),
+    23 => (256, 136),
+    24 => (1, 1),
+    25 => (1, 2),
+    26 => (1, 4),
+    27 | 28 => (1, 8),
+    29 => (256, 56),
+    34 => (256, 54),
+    35 => (256, 66),
+    _ => return None,
+  })
+}
+
+// metadata values we keep; everything else is skipped
+enum Value {
+  Int(u64),
+  Str(String),
+  Other,
+}
+
+struct Reader<R: Read> {
+  inner: R,
+}
+
+impl<R: Read> Reader<R> {
+  fn bytes<const N: usize>(&mut self) -> Result<[u8; N], String> {
+    let mut buf = [0u8; N];
+    self.inner.read_exact(&mut buf).map_err(|e| format!("truncated GGUF header: {}", e))?;
+    Ok(buf)
+  }
+
+  fn u32(&mut self) -> Result<u32, String> {
+    Ok(u32::from_le_bytes(self.bytes()?))
+  }
+
+  fn u64(&mut self) -> Result<u64, String> {
+    Ok(u64::from_le_bytes(self.bytes()?))
+  }
+
+  fn skip(&mut self, n: u64) -> Result<(), String> {
+    let skipped = std::io::copy(&mut (&mut self.inner).take(n), &mut std::io::sink()).map_err(|e| e.to_string())?;
+    if skipped < n {
+      return Err("truncated GGUF header".into());
+    }
+    Ok(())
+  }
+
+  fn string_len(&mut self) -> Result<u64, String> {
+    let len = self.u64()?;
+    if len > MAX_STRING {
+      return Err(format!("GGUF string of {} bytes", len));
+    }
+    Ok(len)
+  }
+
+  fn string(&mut self) -> Result<String, String> {
+    let len = self.string_len()?;
+    let mut buf = vec![0u8; len as usize];
+    self.inner.read_exact(&mut buf).map_err(|e| format!("truncated GGUF header: {}", e))?;
+    Ok(String::from_utf8_lossy(&buf).to_string())
+  }
+
+  // size of a fixed-width value type, None for strings and arrays
+  fn fixed_size(value_type: u32) -> Result<Option<u64>, String> {
+    Ok(match value_type {
+      0 | 1 | 7 => Some(1),
+      2 | 3 => Some(2),
+      4..=6 => Some(4),
+      10..=12 => Some(8),
+      8 | 9 => None,
+      t => return Err(format!("unknown GGUF value type {}", t)),
+    })
+  }
+
+  fn value(&mut self, value_type: u32) -> Result<Value, String> {
+    Ok(match value_type {
+      0 | 7 => Value::Int(self.bytes::<1>()?[0] as u64),
+      2 => Value::Int(u16::from_le_bytes(self.bytes()?) as u64),
+      4 => Value::Int(self.u32()? as u64),
+      5 => Value::Int(i32::from_le_bytes(self.bytes()?).max(0) as u64),
+      10 => Value::Int(self.u64()?),
+      11 => Value::Int(i64::from_le_bytes(self.bytes()?).max(0) as u64),
+      8 => Value::Str(self.string()?),
+      // arrays (vocabularies, merges) are the bulk of the header and are only skipped
+      9 => {
+        let item_type = self.u32()?;
+        let count = self.u64()?;
+        match Self::fixed_size(item_type)? {
+          Some(size) => self.skip(size.saturating_mul(count))?,
+          None => {
+            for _ in 0..count {
+              self.value(item_type)?;
+            }
+          }
+        }
+        Value::Other
+      }
+      t => {
+        let size = Self::fixed_size(t)?.unwrap_or(0);
+        self.skip(size)?;
+        Value::Other
+      }
+    })
+  }
+}
+
+// "<arch>.<key>" of the model's own architecture (a multimodal file also has its encoder's)
+fn arch_value(values: &[(String, u64)], arch: Option<&str>, key: &str) -> Option<u64> {
+  match arch {
+    Some(arch) => values.iter().find(|(k, _)| *k == format!("{}.{}", arch, key)).map(|(_, n)| *n),
+    None => values.iter().find(|(k, _)| k.ends_with(&format!(".{}", key))).map(|(_, n)| *n),
+  }
+}
+
+// parse the key/value header and tensor list of a .gguf file (format v2 and later)
+pub fn read_metadata(path: &Path) -> Result<GgufMetadata, String> {
+  let file = File::open(path).map_err(|e| format!("failed to open {}: {}", path.to_string_lossy(), e))?;
+  let mut r = Reader { inner: BufReader::with_capacity(1 << 16, file) };
+  if &r.bytes::<4>()? != b"GGUF" {
+    return Err(format!("{} is not a GGUF file", path.to_string_lossy()));
+  }
+  let version = r.u32()?;
+  if version < 2 {
+    return Err(format!("GGUF version {} is not supported", version));
+  }
+  let tensor_count = r.u64()?;
+  let kv_count = r.u64()?;
+
+  let mut meta = GgufMetadata::default();
+  let mut context_lengths: Vec<(String, u64)> = Vec::new();
+  let mut block_counts: Vec<(String, u64)> = Vec::new();
+  let mut attention: Vec<(String, u64)> = Vec::new();
+  let mut file_type = None;
+  let mut split_count = 0;
+  for _ in 0..kv_count {
+    let key = r.string()?;
+    let value_type = r.u32()?;
+    match (key.as_str(), r.value(value_type)?) {
+      ("general.architecture", Value::Str(s)) => meta.architecture = Some(s),
+      ("general.name", Value::Str(s)) => meta.name = Some(s),
+      ("general.file_type", Value::Int(n)) => file_type = Some(n),
+      ("tokenizer.chat_template", Value::Str(s)) => meta.chat_template = Some(s),
+      ("split.count", Value::Int(n)) => split_count = n,
+      (k, Value::Int(n)) if k.ends_with(".context_length") => context_lengths.push((k.to_string(), n)),
+      (k, Value::Int(n)) if k.ends_with(".block_count") => block_counts.push((k.to_string(), n)),
+      (k, Value::Int(n)) if k.ends_with(".embedding_length") || k.ends_with(".attention.head_count") || k.ends_with(".attention.head_count_kv") => {
+        attention.push((k.to_string(), n))
+      }
+      _ => {}
+    }
+  }
+  meta.quantization = file_type.and_then(file_type_name).map(|s| s.to_string());
+  let arch = meta.architecture.as_deref();
+  meta.context_length = arch_value(&context_lengths, arch, "context_length");
+  meta.block_count = arch_value(&block_counts, arch, "block_count");
+  meta.embedding_length = arch_value(&attention, arch, "embedding_length");
+  meta.head_count = arch_value(&attention, arch, "attention.head_count");
+  meta.head_count_kv = arch_value(&attention, arch, "attention.head_count_kv");
+
+  if split_count <= 1 {
+    let mut parameters: u64 = 0;
+    let mut tensor_bytes = Some(0u64);
+    for _ in 0..tensor_count {
+      let name_len = r.string_len()?;
+      r.skip(name_len)?;
+      let dims = r.u32()?;
+      let mut elements: u64 = 1;
+      for _ in 0..dims {
+        elements = elements.saturating_mul(r.u64()?);
+      }
+      let tensor_type = r.u32()?;
+      // data offset
+      r.skip(8)?;
+      parameters = parameters.saturating_add(elements);
+      // a type we don't know the size of leaves the total unknown
+      tensor_bytes = tensor_bytes.zip(type_block(tensor_type)).map(|(total, (block, bytes))| total.saturating_add(elements.div_ceil(block) * bytes));
+    }
+    meta.parameters = (parameters > 0).then_some(parameters);
+    meta.tensor_bytes = tensor_bytes.filter(|b| *b > 0);
+  }
+  Ok(meta)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // a GGUF v3 file built field by field
+  #[derive(Default)]
+  struct Builder {
+    kv: Vec<u8>,
+    kv_count: u64,
+    tensors: Vec<u8>,
+    tensor_count: u64,
+  }
+
+  fn string(out: &mut Vec<u8>, s: &str) {
+    out.extend((s.len() as u64).to_le_bytes());
+    out.extend(s.as_bytes());
+  }
+
+  impl Builder {
+    fn key(&mut self, key: &str, value_type: u32) -> &mut Vec<u8> {
+      self.kv_count += 1;
+      string(&mut self.kv, key);
+      self.kv.extend(value_type.to_le_bytes());
+      &mut self.kv
+    }
+
+    fn str(mut self, key: &str, value: &str) -> Self {
+      string(self.key(key, 8), value);
+      self
+    }
+
+    fn u32(mut self, key: &str, value: u32) -> Self {
+      self.key(key, 4).extend(value.to_le_bytes());
+      self
+    }
+
+    fn f32(mut self, key: &str, value: f32) -> Self {
+      self.key(key, 6).extend(value.to_le_bytes());
+      self
+    }
+
+    fn strings(mut self, key: &str, values: &[&str]) -> Self {
+      let out = self.key(key, 9);
+      out.extend(8u32.to_le_bytes());
+      out.extend((values.len() as u64).to_le_bytes());
+      for v in values {
+        string(out, v);
+      }
+      self
+    }
+
+    fn tensor(mut self, name: &str, dims: &[u64], tensor_type: u32) -> Self {
+      self.tensor_count += 1;
+      string(&mut self.tensors, name);
+      self.tensors.extend((dims.len() as u32).to_le_bytes());
+      for d in dims {
+        self.tensors.extend(d.to_le_bytes());
+      }
+      self.tensors.extend(tensor_type.to_le_bytes());
+      self.tensors.extend(0u64.to_le_bytes());
+      self
+    }
+
+    fn read(self, name: &str) -> Result<GgufMetadata, String> {
+      let mut bytes = b"GGUF".to_vec();
+      bytes.extend(3u32.to_le_bytes());
+      bytes.extend(self.tensor_count.to_le_bytes());
+      bytes.extend(self.kv_count.to_le_bytes());
+      bytes.extend(self.kv);
+      bytes.extend(self.tensors);
+      read_file(name, &bytes)
+    }
+  }
+
+  fn read_file(name: &str, bytes: &[u8]) -> Result<GgufMetadata, String> {
+    let path = std::env::temp_dir().join(format!("multilingual-gguf-{}-{}.gguf", std::process::id(), name));
+    std::fs::write(&path, bytes).unwrap();
+    let meta = read_metadata(&path);
+    let _ = std::fs::remove_file(&path);
+    meta
+  }
+
+  #[test]
+  fn reads_the_header() {
+    let meta = Builder::default()
+      .str("general.architecture", "llama")
+      .str("general.name", "Tiny")
+      .u32("general.file_type", 15)
+      .strings("tokenizer.ggml.tokens", &["<s>", "</s>", "hello"])
+      .f32("llama.rope.freq_base", 10000.0)
+      .u32("llama.context_length", 4096)
+      .u32("llama.block_count", 32)
+      .u32("llama.embedding_length", 4096)
+      .u32("llama.attention.head_count", 32)
+      .u32("llama.attention.head_count_kv", 8)
+      .str("tokenizer.chat_template", "{{ messages }}")
+      .tensor("blk.0.attn_q.weight", &[4096, 32], 12)
+      .tensor("output_norm.weight", &[4096], 0)
+      .read("header")
+      .unwrap();
+    assert_eq!(meta.architecture.as_deref(), Some("llama"));
+    assert_eq!(meta.name.as_deref(), Some("Tiny"));
+    assert_eq!(meta.quantization.as_deref(), Some("Q4_K_M"));
+    assert_eq!(meta.context_length, Some(4096));
+    assert_eq!(meta.block_count, Some(32));
+    assert_eq!(meta.embedding_length, Some(4096));
+    assert_eq!(meta.head_count, Some(32));
+    assert_eq!(meta.head_count_kv, Some(8));
+    assert_eq!(meta.chat_template.as_deref(), Some("{{ messages }}"));
+    assert_eq!(meta.parameters, Some(4096 * 32 + 4096));
+    // 512 Q4_K blocks of 144 bytes and 4096 f32 values
+    assert_eq!(meta.tensor_bytes, Some(512 * 144 + 4096 * 4));
+  }
+
+  #[test]
+  fn prefers_the_models_own_architecture() {
+    let meta = Builder::default()
+      .u32("clip.vision.block_count", 24)
+      .str("general.architecture", "gemma3")
+      .u32("gemma3.block_count", 26)
+      .read("arch")
+      .unwrap();
+    assert_eq!(meta.block_count, Some(26));
+  }
+
+  #[test]
+  fn unknown_tensor_types_leave_the_size_open() {
+    let meta = Builder::default().tensor("a", &[256], 12).tensor("b", &[256], 999).read("unknown").unwrap();
+    assert_eq!(meta.parameters, Some(512));
+    assert_eq!(meta.tensor_bytes, None);
+  }
+
+  #[test]
+  fn split_models_have_no_totals() {
+    let meta = Builder::default().u32("split.count", 2).tensor("a", &[256], 12).read("split").unwrap();
+    assert_eq!(meta.parameters, None);
+    assert_eq!(meta.tensor_bytes, None);
+  }
+
+  #[test]
+  fn rejects_what_isnt_gguf() {
+    assert!(read_file("magic", b"GGML\x03\0\0\0").is_err());
+    assert!(read_file("truncated", b"GGUF\x03\0\0\0\x01\0").is_err());
+    let mut huge = b"GGUF".to_vec();
+    huge.extend(3u32.to_le_bytes());
+    huge.extend(0u64.to_le_bytes());
+    huge.extend(1u64.to_le_bytes());
+    huge.extend(u64::MAX.to_le_bytes());
+    assert!(read_file("huge", &huge).unwrap_err().contains("GGUF string"));
+  }
+
+  #[test]
+  fn type_block_sizes_match_ggml() {
+    // bits per weight of the common types
+    let bpw = |t: u32| type_block(t).map(|(elements, bytes)| bytes as f64 * 8.0 / elements as f64);
+    assert_eq!(bpw(0), Some(32.0));
+    assert_eq!(bpw(1), Some(16.0));
+    assert_eq!(bpw(30), Some(16.0));
+    assert_eq!(bpw(2), Some(4.5));
+    assert_eq!(bpw(8), Some(8.5));
+    assert_eq!(bpw(12), Some(4.5));
+    assert_eq!(bpw(13), Some(5.5));
+    assert_eq!(bpw(14), Some(6.5625));
+    assert_eq!(type_block(4), None);
+  }
+}
@@ -0,0 +1,118 @@
+// src-tauri/src/gguf_split.rs
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::thread;
+
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::LockExt;
+use crate::runtime;
+use crate::storage;
+use crate::ModelManager;
+
+// "qwen-72b-Q4_K_M-00001-of-00003.gguf" -> ("qwen-72b-Q4_K_M", 1, 3)
+pub fn parse_split_name(file_name: &str) -> Option<(String, u32, u32)> {
+  let stem = file_name.strip_suffix(".gguf")?;
+  let (rest, count) = stem.rsplit_once("-of-")?;
+  let (base, index) = rest.rsplit_once('-')?;
+  if index.len() != 5 || count.len() != 5 {
+    return None;
+  }
+  let index: u32 = index.parse().ok()?;
+  let count: u32 = count.parse().ok()?;
+  if index == 0 || count == 0 || index > count {
+    return None;
+  }
+  Some((base.to_string(), index, count))
+}
+
+// Payload of the "gguf-split-status" event
+#[derive(Clone, serde::Serialize)]
+struct SplitStatus {
+  id: String,
+  // "merge" or "split"
+  action: String,
+  done: bool,
+  output: String,
+  error: Option<String>,
+}
+
+// run llama-gguf-split in the background, rescan on success and report via "gguf-split-status"
+fn run_tool(app: AppHandle, id: String, action: &str, args: Vec<String>, output: String) -> Result<String, String> {
+  let tool = runtime::bundled_tool("llama-gguf-split").ok_or("llama-gguf-split is not bundled with this build")?;
+  let mut child = storage::command(tool)
+    .args(&args)
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .map_err(|e| format!("Failed to spawn llama-gguf-split: {}", e))?;
+
+  let action = action.to_string();
+  let out = output.clone();
+  thread::spawn(move || {
+    // llama-gguf-split prints its per-tensor progress to stdout; it is read on its own thread so a
+    // large model doesn't fill the pipe and stall the tool
+    let stdout = child.stdout.take().map(|out| thread::spawn(move || BufReader::new(out).lines().map_while(Result::ok).collect::<Vec<_>>()));
+    let mut log: Vec<String> = match child.stderr.take() {
+      Some(err) => BufReader::new(err).lines().map_while(Result::ok).collect(),
+      None => Vec::new(),
+    };
+    if let Some(lines) = stdout.and_then(|t| t.join().ok()) {
+      // the last lines say what went wrong when it fails
+      log.extend(lines.into_iter().rev().take(10).rev());
+    }
+    let error = match child.wait() {
+      Ok(status) if status.success() => None,
+      Ok(status) => Some(format!("llama-gguf-split exited with {}: {}", status, log.join("\n"))),
+      Err(e) => Some(format!("llama-gguf-split failed: {}", e)),
+    };
+    if error.is_none() {
+      let state = app.state::<Mutex<ModelManager>>();
+      state.locked().scan_models();
+    }
+    let _ = app.emit(
+      "gguf-split-status",
+      SplitStatus { id, action, done: error.is_none(), output: out, error },
+    );
+  });
+
+  Ok(output)
+}
+
+// merge all shards of a split model (given its first shard) into a single .gguf next to it
+pub fn merge(app: AppHandle, id: String, first_shard: &str) -> Result<String, String> {
+  let path = PathBuf::from(first_shard);
+  let file_name = path.file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_default();
+  let (base, _, _) = parse_split_name(&file_name).ok_or("Model is not a split GGUF")?;
+  let output = path.with_file_name(format!("{}.gguf", base));
+  if output.exists() {
+    return Err(format!("{} already exists", output.to_string_lossy()));
+  }
+  let output = output.to_string_lossy().to_string();
+  run_tool(app, id, "merge", vec!["--merge".into(), first_shard.to_string(), output.clone()], output)
+}
+
+// split a single .gguf into shards of at most `max_size` (e.g. "4G", "500M")
+pub fn split(app: AppHandle, id: String, source: &str, max_size: &str) -> Result<String, String> {
+  let path = Path::new(source);
+  if path.extension().is_none_or(|e| e != "gguf") {
+    return Err("Only .gguf models can be split".into());
+  }
+  let valid_size = max_size
+    .strip_suffix(['M', 'G'])
+    .is_some_and(|n| n.parse::<u32>().is_ok_and(|n| n > 0));
+  if !valid_size {
+    return Err(format!("Invalid max size '{}', expected e.g. 500M or 4G", max_size));
+  }
+  // llama-gguf-split appends -0000N-of-0000M.gguf to the prefix
+  let prefix = path.with_extension("").to_string_lossy().to_string();
+  run_tool(
+    app,
+    id,
+    "split",
+    vec!["--split".into(), "--split-max-size".into(), max_size.to_string(), source.to_string(), prefix.clone()],
+    prefix,
+  )
+}
@@ -0,0 +1,98 @@
+// src-tauri/src/glossary.rs
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::error::{AppError, LockExt};
+use crate::store;
+
+// A preferred translation for a term in one language pair
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct GlossaryEntry {
+  pub id: u64,
+  pub source_lang: String,
+  pub target_lang: String,
+  pub source: String,
+  pub target: String,
+  #[serde(default)]
+  pub note: Option<String>,
+}
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct GlossaryFile {
+  next_id: u64,
+  entries: Vec<GlossaryEntry>,
+}
+
+// Glossary persisted in ./data/glossary.json (managed by Tauri)
+pub struct Glossary {
+  path: PathBuf,
+  file: GlossaryFile,
+}
+
+impl Glossary {
+  pub fn load() -> Self {
+    let path = store::data_file("glossary.json");
+    let file = store::load_json(&path);
+    Self { path, file }
+  }
+
+  // entries for a language pair; None matches any language
+  pub fn entries(&self, source_lang: Option<&str>, target_lang: Option<&str>) -> Vec<GlossaryEntry> {
+    self
+      .file
+      .entries
+      .iter()
+      .filter(|e| source_lang.is_none_or(|l| e.source_lang == l) && target_lang.is_none_or(|l| e.target_lang == l))
+      .cloned()
+      .collect()
+  }
+
+  pub fn add(&mut self, mut entry: GlossaryEntry) -> Result<GlossaryEntry, String> {
+    if entry.source.trim().is_empty() || entry.target.trim().is_empty() {
+      return Err("Glossary terms must not be empty".into());
+    }
+    self.file.next_id += 1;
+    entry.id = self.file.next_id;
+    self.file.entries.push(entry.clone());
+    store::save_json(&self.path, &self.file)?;
+    Ok(entry)
+  }
+
+  pub fn remove(&mut self, id: u64) -> Result<(), String> {
+    let before = self.file.entries.len();
+    self.file.entries.retain(|e| e.id != id);
+    if self.file.entries.len() == before {
+      return Err(format!("Glossary entry {} not found", id));
+    }
+    store::save_json(&self.path, &self.file)
+  }
+}
+
+// ------------------ Tauri commands ------------------
+
+#[tauri::command]
+pub fn list_glossary(
+  source_lang: Option<String>,
+  target_lang: Option<String>,
+  glossary: tauri::State<'_, Mutex<Glossary>>
+) -> Vec<GlossaryEntry> {
+  glossary.locked().entries(source_lang.as_deref(), target_lang.as_deref())
+}
+
+#[tauri::command]
+pub fn add_glossary_entry(
+  source_lang: String,
+  target_lang: String,
+  source: String,
+  target: String,
+  note: Option<String>,
+  glossary: tauri::State<'_, Mutex<Glossary>>
+) -> Result<GlossaryEntry, AppError> {
+  let entry = GlossaryEntry { id: 0, source_lang, target_lang, source, target, note };
+  Ok(glossary.locked().add(entry)?)
+}
+
+#[tauri::command]
+pub fn remove_glossary_entry(id: u64, glossary: tauri::State<'_, Mutex<Glossary>>) -> Result<(), AppError> {
+  Ok(glossary.locked().remove(id)?)
+}
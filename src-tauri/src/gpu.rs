@@ -0,0 +1,185 @@
+// src-tauri/src/gpu.rs
+use std::collections::HashMap;
+use std::process::Command;
+
+// A GPU the runtime can offload to, as reported by the vendor tools
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct GpuDevice {
+  pub index: u32,
+  pub name: String,
+  pub vendor: String,
+  pub memory_mb: u64,
+}
+
+// How llama.cpp spreads a model across several GPUs (`--split-mode`)
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SplitMode {
+  // everything on `main_gpu`
+  #[default]
+  None,
+  // whole layers are distributed across GPUs
+  Layer,
+  // rows of each tensor are distributed across GPUs
+  Row,
+}
+
+impl SplitMode {
+  fn as_arg(&self) -> &'static str {
+    match self {
+      SplitMode::None => "none",
+      SplitMode::Layer => "layer",
+      SplitMode::Row => "row",
+    }
+  }
+}
+
+// Per-model multi-GPU configuration
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct GpuSplit {
+  #[serde(default)]
+  pub split_mode: SplitMode,
+  // proportion of the model for each GPU, e.g. [3.0, 1.0]; empty = let the runtime decide
+  #[serde(default)]
+  pub tensor_split: Vec<f32>,
+  // GPU used for scratch buffers and small tensors (or the whole model with split_mode none)
+  #[serde(default)]
+  pub main_gpu: Option<u32>,
+}
+
+impl GpuSplit {
+  // check the configuration against the devices that are actually present
+  pub fn validate(&self, devices: &[GpuDevice]) -> Result<(), String> {
+    if let Some(main) = self.main_gpu {
+      if main as usize >= devices.len() {
+        return Err(format!("main_gpu {} does not exist ({} GPU(s) detected)", main, devices.len()));
+      }
+    }
+
+    if self.split_mode == SplitMode::None {
+      if !self.tensor_split.is_empty() {
+        return Err("tensor_split requires split_mode 'layer' or 'row'".into());
+      }
+      return Ok(());
+    }
+
+    if devices.len() < 2 {
+      return Err(format!("split_mode '{}' needs at least 2 GPUs ({} detected)", self.split_mode.as_arg(), devices.len()));
+    }
+    if self.tensor_split.len() > devices.len() {
+      return Err(format!(
+        "tensor_split has {} entries but only {} GPU(s) were detected",
+        self.tensor_split.len(),
+        devices.len()
+      ));
+    }
+    if self.tensor_split.iter().any(|v| !v.is_finite() || *v < 0.0) {
+      return Err("tensor_split entries must be non-negative numbers".into());
+    }
+    if !self.tensor_split.is_empty() && self.tensor_split.iter().all(|v| *v == 0.0) {
+      return Err("tensor_split must assign a non-zero share to at least one GPU".into());
+    }
+    Ok(())
+  }
+
+  // llama.cpp command line flags for this configuration
+  pub fn runtime_args(&self) -> Vec<String> {
+    let mut args = vec!["--split-mode".to_string(), self.split_mode.as_arg().to_string()];
+    if !self.tensor_split.is_empty() {
+      let split: Vec<String> = self.tensor_split.iter().map(|v| v.to_string()).collect();
+      args.push("--tensor-split".into());
+      args.push(split.join(","));
+    }
+    if let Some(main) = self.main_gpu {
+      args.push("--main-gpu".into());
+      args.push(main.to_string());
+    }
+    args
+  }
+}
+
+// query nvidia-smi / rocm-smi for devices; returns an empty list when neither is available
+pub fn detect_devices() -> Vec<GpuDevice> {
+  let mut devices = detect_nvidia();
+  if devices.is_empty() {
+    devices = detect_rocm();
+  }
+  devices
+}
+
+fn detect_nvidia() -> Vec<GpuDevice> {
+  let output = match Command::new("nvidia-smi")
+    .args(["--query-gpu=index,name,memory.total", "--format=csv,noheader,nounits"])
+    .output()
+  {
+    Ok(o) if o.status.success() => o,
+    _ => return Vec::new(),
+  };
+
+  String::from_utf8_lossy(&output.stdout)
+    .lines()
+    .filter_map(|line| {
+      let cols: Vec<&str> = line.split(',').map(|c| c.trim()).collect();
+      if cols.len() < 3 {
+        return None;
+      }
+      Some(GpuDevice {
+        index: cols[0].parse().ok()?,
+        name: cols[1].to_string(),
+        vendor: "nvidia".into(),
+        memory_mb: cols[2].parse().unwrap_or(0),
+      })
+    })
+    .collect()
+}
+
+// GPU memory each process holds, in bytes by pid; only nvidia-smi reports it per process, so this
+// is empty elsewhere
+pub fn process_memory() -> HashMap<u32, u64> {
+  let output = match Command::new("nvidia-smi").args(["--query-compute-apps=pid,used_memory", "--format=csv,noheader,nounits"]).output() {
+    Ok(o) if o.status.success() => o,
+    _ => return HashMap::new(),
+  };
+  let mut used = HashMap::new();
+  for line in String::from_utf8_lossy(&output.stdout).lines() {
+    let Some((pid, mb)) = line.split_once(',') else { continue };
+    if let (Ok(pid), Ok(mb)) = (pid.trim().parse::<u32>(), mb.trim().parse::<u64>()) {
+      // a process using several GPUs is listed once per GPU
+      *used.entry(pid).or_insert(0) += mb * 1024 * 1024;
+    }
+  }
+  used
+}
+
+fn detect_rocm() -> Vec<GpuDevice> {
+  let output = match Command::new("rocm-smi").args(["--showproductname", "--showmeminfo", "vram", "--json"]).output() {
+    Ok(o) if o.status.success() => o,
+    _ => return Vec::new(),
+  };
+
+  let parsed: serde_json::Value = match serde_json::from_slice(&output.stdout) {
+    Ok(v) => v,
+    Err(_) => return Vec::new(),
+  };
+
+  let mut devices = Vec::new();
+  if let Some(cards) = parsed.as_object() {
+    for (key, card) in cards {
+      // keys look like "card0", "card1", ...
+      let index = match key.strip_prefix("card").and_then(|n| n.parse().ok()) {
+        Some(i) => i,
+        None => continue,
+      };
+      let name = card.get("Card series").and_then(|v| v.as_str()).unwrap_or("AMD GPU").to_string();
+      let memory_mb = card
+        .get("VRAM Total Memory (B)")
+        .and_then(|v| v.as_str())
+        .and_then(|s| s.parse::<u64>().ok())
+        .map(|b| b / (1024 * 1024))
+        .unwrap_or(0);
+      devices.push(GpuDevice { index, name, vendor: "amd".into(), memory_mb });
+    }
+  }
+  devices.sort_by_key(|d| d.index);
+  devices
+}
@@ -0,0 +1,160 @@
+// src-tauri/src/hardware.rs
+use std::fs;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+
+use sysinfo::System;
+
+use crate::error::LockExt;
+use crate::gguf;
+use crate::gguf_split;
+use crate::gpu::{self, GpuDevice};
+use crate::model_config::ModelConfig;
+use crate::readaloud;
+use crate::settings;
+
+// -ngl value that offloads every layer (more than any model has)
+pub const ALL_LAYERS: u32 = 99;
+// VRAM left free for the KV cache and compute buffers when fitting layers
+const RESERVED_MB: u64 = 1024;
+
+// What the machine offers the runtime
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct HardwareInfo {
+  pub cuda: bool,
+  pub rocm: bool,
+  pub metal: bool,
+  pub vulkan: bool,
+  pub gpus: Vec<GpuDevice>,
+  // memory the GPUs can hold layers in, summed over devices; 0 when unknown
+  pub vram_mb: u64,
+  // Apple silicon: the GPU shares RAM, of which Metal may use about two thirds (vram_mb)
+  pub unified_memory: bool,
+  pub ram_mb: u64,
+  pub cpu_threads: usize,
+}
+
+// the last probe; the vendor tools take a moment, so it is only redone when asked for
+static INFO: OnceLock<Mutex<Option<HardwareInfo>>> = OnceLock::new();
+
+// GPUs from `system_profiler SPDisplaysDataType` (Intel Macs, where the vendor tools don't exist)
+//   Chipset Model: AMD Radeon Pro 560X
+//   Vendor: AMD (0x1002)
+//   VRAM (Total): 4 GB
+fn macos_displays(out: &str) -> Vec<GpuDevice> {
+  let mut devices: Vec<GpuDevice> = Vec::new();
+  for line in out.lines() {
+    let Some((key, value)) = line.trim().split_once(':') else { continue };
+    let value = value.trim();
+    if key == "Chipset Model" {
+      devices.push(GpuDevice { index: devices.len() as u32, name: value.to_string(), vendor: String::new(), memory_mb: 0 });
+    } else if let Some(device) = devices.last_mut() {
+      if key == "Vendor" {
+        device.vendor = value.split_whitespace().next().unwrap_or_default().to_lowercase();
+      } else if key.starts_with("VRAM") {
+        let mut parts = value.split_whitespace();
+        let amount: u64 = parts.next().and_then(|n| n.parse().ok()).unwrap_or(0);
+        device.memory_mb = if parts.next() == Some("GB") { amount * 1024 } else { amount };
+      }
+    }
+  }
+  devices
+}
+
+// (metal, unified memory, GPUs) on macOS; Metal-capable GPUs list a "Metal Support" line
+fn detect_metal() -> (bool, bool, Vec<GpuDevice>) {
+  if !cfg!(target_os = "macos") {
+    return (false, false, Vec::new());
+  }
+  let out = readaloud::run_capture("system_profiler", &["SPDisplaysDataType"]).unwrap_or_default();
+  let apple_silicon = cfg!(target_arch = "aarch64");
+  (apple_silicon || out.contains("Metal"), apple_silicon, macos_displays(&out))
+}
+
+// a Vulkan driver for a real GPU; CPU implementations like llvmpipe don't count
+fn detect_vulkan() -> bool {
+  if let Some(out) = readaloud::run_capture("vulkaninfo", &["--summary"]) {
+    return out.lines().any(|l| l.contains("deviceType") && !l.contains("CPU"));
+  }
+  // vulkaninfo only comes with the SDK; GPU drivers on Windows install the loader
+  cfg!(target_os = "windows") && Path::new(r"C:\Windows\System32\vulkan-1.dll").exists()
+}
+
+fn probe() -> HardwareInfo {
+  let mut gpus = gpu::detect_devices();
+  let cuda = gpus.iter().any(|g| g.vendor == "nvidia");
+  let rocm = gpus.iter().any(|g| g.vendor == "amd");
+  let (metal, unified_memory, mac_gpus) = detect_metal();
+  if gpus.is_empty() {
+    gpus = mac_gpus;
+  }
+  let mut sys = System::new();
+  sys.refresh_memory();
+  let ram_mb = sys.total_memory() / (1024 * 1024);
+  let vram_mb = if unified_memory { ram_mb * 2 / 3 } else { gpus.iter().map(|g| g.memory_mb).sum() };
+  HardwareInfo {
+    cuda,
+    rocm,
+    metal,
+    vulkan: detect_vulkan(),
+    gpus,
+    vram_mb,
+    unified_memory,
+    ram_mb,
+    cpu_threads: thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+  }
+}
+
+// the cached probe, running it the first time or when `refresh` is set
+pub fn info(refresh: bool) -> HardwareInfo {
+  let mut cached = INFO.get_or_init(|| Mutex::new(None)).locked();
+  if refresh || cached.is_none() {
+    *cached = Some(probe());
+  }
+  cached.clone().unwrap()
+}
+
+// bytes of the model file, all shards of a split model together
+pub fn model_bytes(path: &Path) -> Option<u64> {
+  let name = path.file_name()?.to_string_lossy().to_string();
+  let Some((base, _, count)) = gguf_split::parse_split_name(&name) else {
+    return fs::metadata(path).ok().map(|m| m.len());
+  };
+  let dir = path.parent()?;
+  (1..=count).map(|i| fs::metadata(dir.join(format!("{}-{:05}-of-{:05}.gguf", base, i, count))).ok().map(|m| m.len())).sum()
+}
+
+// as many layers of the model as fit in VRAM; None when the VRAM or the model's size is unknown
+fn fit_layers(path: &Path) -> Option<u32> {
+  let vram_mb = info(false).vram_mb;
+  if vram_mb == 0 {
+    return None;
+  }
+  let size_mb = model_bytes(path)? / (1024 * 1024);
+  let usable = vram_mb.saturating_sub(RESERVED_MB);
+  if size_mb <= usable {
+    return Some(ALL_LAYERS);
+  }
+  // layers are close enough in size to split the file evenly; the output layer is one more
+  let layers = gguf::read_metadata(path).ok()?.block_count? + 1;
+  Some((usable * layers / size_mb.max(1)) as u32)
+}
+
+// -ngl for a model: its own gpu_layers, else the gpu_layers setting, else as many layers as fit in
+// VRAM (everything when that can't be told)
+pub fn gpu_layers(model_path: &str, config: &ModelConfig) -> u32 {
+  if let Some(layers) = config.gpu_layers.or(settings::get().gpu_layers) {
+    return layers;
+  }
+  fit_layers(Path::new(model_path)).unwrap_or(ALL_LAYERS)
+}
+
+// ------------------ Tauri commands ------------------
+
+// GPU backends, VRAM, RAM and CPU threads of this machine; `refresh` probes again (after a driver
+// install or plugging in an eGPU)
+#[tauri::command(async)]
+pub fn get_hardware_info(refresh: Option<bool>) -> HardwareInfo {
+  info(refresh.unwrap_or(false))
+}
@@ -0,0 +1,75 @@
+// src-tauri/src/httpd.rs
+use std::net::{IpAddr, ToSocketAddrs};
+use std::sync::Arc;
+use std::thread;
+
+use subtle::ConstantTimeEq;
+use tiny_http::{Header, Request, Server};
+
+// requests answered at the same time; the rest wait in the listener until a worker is free
+const WORKERS: usize = 4;
+
+// A small HTTP server (web proxy, LAN sharing, OpenAI API) answering on a fixed pool of threads
+pub struct HttpServer {
+  server: Arc<Server>,
+  pub port: u16,
+}
+
+impl HttpServer {
+  // bind `addr` and pass every request to `handle`
+  pub fn start<A: ToSocketAddrs>(addr: A, handle: impl Fn(Request) + Send + Sync + 'static) -> Result<Self, String> {
+    let server = Server::http(addr).map_err(|e| e.to_string())?;
+    let port = server.server_addr().to_ip().map(|a| a.port()).ok_or("server has no TCP address")?;
+    let server = Arc::new(server);
+    let handle = Arc::new(handle);
+    for _ in 0..WORKERS {
+      let (srv, handle) = (server.clone(), handle.clone());
+      thread::spawn(move || {
+        for req in srv.incoming_requests() {
+          handle(req);
+        }
+      });
+    }
+    Ok(Self { server, port })
+  }
+
+  // stop accepting requests; the ones being answered still finish
+  pub fn stop(&self) {
+    // each unblock releases one waiting worker
+    for _ in 0..WORKERS {
+      self.server.unblock();
+    }
+  }
+}
+
+// Content-Type header; a value that can't be sent (e.g. copied from an upstream response) becomes
+// application/octet-stream
+pub fn content_type(value: &str) -> Header {
+  Header::from_bytes("Content-Type", value)
+    .or_else(|_| Header::from_bytes("Content-Type", "application/octet-stream"))
+    .expect("static header is valid")
+}
+
+pub fn request_header<'a>(req: &'a Request, name: &'static str) -> Option<&'a str> {
+  req.headers().iter().find(|h| h.field.equiv(name)).map(|h| h.value.as_str())
+}
+
+// the token of an "Authorization: Bearer <token>" header
+pub fn bearer(req: &Request) -> Option<&str> {
+  let (scheme, token) = request_header(req, "Authorization")?.split_once(' ')?;
+  (scheme.eq_ignore_ascii_case("Bearer") && !token.is_empty() && !token.contains(char::is_whitespace)).then_some(token)
+}
+
+// compare a token with the expected one in time that doesn't depend on where they differ
+pub fn same_secret(given: &str, expected: &str) -> bool {
+  given.as_bytes().ct_eq(expected.as_bytes()).into()
+}
+
+// true when a Host header names this machine: localhost or a loopback address, with or without port
+pub fn loopback_host(host: &str) -> bool {
+  let name = match host.strip_prefix('[') {
+    Some(rest) => rest.split(']').next().unwrap_or(""),
+    None => host.split(':').next().unwrap_or(""),
+  };
+  name.eq_ignore_ascii_case("localhost") || name.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
@@ -0,0 +1,112 @@
+// src-tauri/src/instance.rs
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tauri::{AppHandle, Emitter};
+
+use crate::events;
+use crate::store;
+
+const LOCK_WAIT: Duration = Duration::from_secs(2);
+
+// Whether this instance may write the data folder
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct InstanceStatus {
+  // another instance holds the data folder; nothing is saved until it quits and this one restarts
+  pub read_only: bool,
+  // what the instance holding the lock wrote about itself ("pid 1234"), when known
+  pub owner: Option<String>,
+}
+
+// the locked file, kept open for as long as the app runs (closing it releases the lock), or the
+// status of an instance that didn't get it
+static LOCK: OnceLock<Result<Option<File>, InstanceStatus>> = OnceLock::new();
+
+// take the advisory lock on ./data/.lock. Without it the app runs read-only instead of letting two
+// instances overwrite each other's stores; called first thing in run()
+pub fn acquire() {
+  LOCK.get_or_init(|| {
+    let dir = store::data_file("");
+    let _ = fs::create_dir_all(&dir);
+    let owner_file = dir.join(".lock.owner");
+    let file = OpenOptions::new().create(true).truncate(false).write(true).open(dir.join(".lock"));
+    match file {
+      Ok(file) => match try_lock(&file) {
+        Ok(()) => {
+          let _ = fs::write(&owner_file, format!("pid {}", std::process::id()));
+          Ok(Some(file))
+        }
+        Err(TryLockError::WouldBlock) => {
+          let owner = fs::read_to_string(&owner_file).ok().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+          events::log(format!("[instance] data folder in use by another instance ({}); running read-only", owner.as_deref().unwrap_or("unknown")));
+          Err(InstanceStatus { read_only: true, owner })
+        }
+        // file systems without locking support (some network shares) are used as before
+        Err(TryLockError::Error(e)) => {
+          events::log(format!("[instance] can't lock the data folder: {}", e));
+          Ok(Some(file))
+        }
+      },
+      // saving fails on its own then (read-only media), with the file system's error
+      Err(e) => {
+        events::log(format!("[instance] can't open the data folder lock: {}", e));
+        Ok(None)
+      }
+    }
+  });
+}
+
+// an instance restarting itself (restore_backup) starts the new process before the old one has
+// exited, so a held lock gets a moment to be released
+fn try_lock(file: &File) -> Result<(), TryLockError> {
+  let started = Instant::now();
+  loop {
+    match file.try_lock() {
+      Err(TryLockError::WouldBlock) if started.elapsed() < LOCK_WAIT => thread::sleep(Duration::from_millis(100)),
+      result => return result,
+    }
+  }
+}
+
+pub fn status() -> InstanceStatus {
+  match LOCK.get() {
+    Some(Err(status)) => status.clone(),
+    _ => InstanceStatus { read_only: false, owner: None },
+  }
+}
+
+pub fn read_only() -> bool {
+  status().read_only
+}
+
+// how the read-only error starts; commands report errors starting with it as AppError::ReadOnly
+pub const IN_USE: &str = "The data folder is in use by another instance of the app";
+
+// the error every save returns in read-only mode
+pub fn check_writable() -> Result<(), String> {
+  let status = status();
+  if !status.read_only {
+    return Ok(());
+  }
+  Err(match status.owner {
+    Some(owner) => format!("{} ({}); changes can't be saved until it is closed", IN_USE, owner),
+    None => format!("{}; changes can't be saved until it is closed", IN_USE),
+  })
+}
+
+// tell the frontend it is read-only; called at the end of setup
+pub fn notify(app: &AppHandle) {
+  let status = status();
+  if status.read_only {
+    let _ = app.emit("read-only-mode", status);
+  }
+}
+
+// ------------------ Tauri commands ------------------
+
+#[tauri::command]
+pub fn get_instance_status() -> InstanceStatus {
+  status()
+}
@@ -0,0 +1,154 @@
+// src-tauri/src/integrity.rs
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use tauri::{AppHandle, Emitter};
+
+use crate::error::LockExt;
+use crate::events;
+use crate::instance;
+use crate::store;
+
+// A data file that could not be read and was set aside
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct IntegrityIssue {
+  pub file: String,
+  // copy of the damaged file in data/quarantine, when it could be made
+  pub backup: Option<String>,
+  pub error: String,
+  // "restored" when an interrupted save could be finished, else "reset" (rebuilt from defaults)
+  pub action: String,
+}
+
+// issues found since launch, reported to the frontend once it is up
+static ISSUES: Mutex<Vec<IntegrityIssue>> = Mutex::new(Vec::new());
+
+fn quarantine_dir() -> PathBuf {
+  store::data_file("quarantine")
+}
+
+// the file store::save_json writes before renaming it over `path`
+pub fn temp_path(path: &Path) -> PathBuf {
+  let mut name = path.file_name().unwrap_or_default().to_os_string();
+  name.push(".tmp");
+  path.with_file_name(name)
+}
+
+fn parses(path: &Path) -> Result<(), String> {
+  let text = fs::read(path).map_err(|e| e.to_string())?;
+  serde_json::from_slice::<serde_json::Value>(&text).map(|_| ()).map_err(|e| e.to_string())
+}
+
+// copy a damaged file to data/quarantine/<name>.<time>.corrupt
+fn back_up(path: &Path) -> Option<PathBuf> {
+  let dir = quarantine_dir();
+  fs::create_dir_all(&dir).ok()?;
+  let name = path.file_name()?.to_string_lossy();
+  let backup = dir.join(format!("{}.{}.corrupt", name, chrono::Local::now().format("%Y%m%d-%H%M%S")));
+  fs::copy(path, &backup).ok()?;
+  Some(backup)
+}
+
+fn record(issue: IntegrityIssue) {
+  events::log(format!("[integrity] {}: {} ({})", issue.file, issue.error, issue.action));
+  ISSUES.locked().push(issue);
+}
+
+// set a file that doesn't parse aside so the caller starts over from the default value. The
+// original stays in place when the backup can't be made
+pub fn quarantine(path: &Path, error: &str) {
+  // the instance holding the data folder does the repairs
+  if instance::read_only() {
+    events::log(format!("[integrity] {}: {} (left alone, read-only)", path.to_string_lossy(), error));
+    return;
+  }
+  let backup = back_up(path);
+  if backup.is_some() {
+    let _ = fs::remove_file(path);
+  }
+  record(IntegrityIssue {
+    file: path.to_string_lossy().to_string(),
+    backup: backup.map(|b| b.to_string_lossy().to_string()),
+    error: error.to_string(),
+    action: "reset".into(),
+  });
+}
+
+// a save cut off after writing <name>.tmp but before renaming it holds the newest data; finish it.
+// A temp file that doesn't parse was itself cut off and is dropped
+fn finish_interrupted_save(tmp: &Path) {
+  let path = tmp.with_extension("");
+  if parses(tmp).is_err() {
+    let _ = fs::remove_file(tmp);
+    return;
+  }
+  let damaged = parses(&path).err().filter(|_| path.exists());
+  let backup = damaged.as_ref().and_then(|_| back_up(&path));
+  if fs::rename(tmp, &path).is_ok() {
+    if let Some(error) = damaged {
+      record(IntegrityIssue {
+        file: path.to_string_lossy().to_string(),
+        backup: backup.map(|b| b.to_string_lossy().to_string()),
+        error,
+        action: "restored".into(),
+      });
+    }
+  }
+}
+
+// JSON files (and interrupted saves of them) in `dir`, and in its subfolders when `recurse`
+fn json_files(dir: &Path, recurse: bool, out: &mut Vec<PathBuf>) {
+  let Ok(rd) = fs::read_dir(dir) else { return };
+  for entry in rd.flatten() {
+    let path = entry.path();
+    if path.is_dir() {
+      if recurse && path != quarantine_dir() {
+        json_files(&path, true, out);
+      }
+    } else if path.extension().is_some_and(|x| x == "json") || path.to_string_lossy().ends_with(".json.tmp") {
+      out.push(path);
+    }
+  }
+}
+
+// check every JSON file in the data folder and the config folder (settings.json) before the
+// stores load them: finish interrupted saves, and quarantine files that don't parse so each store
+// rebuilds from its defaults instead of failing
+pub fn check() {
+  if instance::read_only() {
+    return;
+  }
+  let mut files = Vec::new();
+  json_files(&store::data_file(""), true, &mut files);
+  // only the top level: on macOS the config folder is also the app folder, with models/ in it
+  let config = store::config_dir();
+  if config != store::data_file("") {
+    json_files(&config, false, &mut files);
+  }
+  let (temps, files): (Vec<PathBuf>, Vec<PathBuf>) = files.into_iter().partition(|p| p.extension().is_some_and(|x| x == "tmp"));
+  for tmp in &temps {
+    finish_interrupted_save(tmp);
+  }
+  for path in files {
+    if let Err(e) = parses(&path) {
+      quarantine(&path, &e);
+    }
+  }
+}
+
+// tell the frontend what was repaired; called at the end of setup, once every store has loaded
+pub fn notify(app: &AppHandle) {
+  let issues = ISSUES.locked().clone();
+  if !issues.is_empty() {
+    let _ = app.emit("data-integrity", issues);
+  }
+}
+
+// ------------------ Tauri commands ------------------
+
+// files repaired since launch, for a frontend that missed the "data-integrity" event
+#[tauri::command]
+pub fn get_integrity_report() -> Vec<IntegrityIssue> {
+  ISSUES.locked().clone()
+}
@@ -0,0 +1,231 @@
+// src-tauri/src/interpreter.rs
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tauri::{Emitter, Manager, Window};
+
+use crate::dispatch::{self, Priority};
+use crate::engine;
+use crate::error::{AppError, LockExt};
+use crate::lang;
+use crate::model_config::ModelConfig;
+use crate::preload;
+use crate::speech;
+use crate::store;
+use crate::translate;
+use crate::ModelManager;
+
+// What the session is doing right now; a new turn is only accepted while listening
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InterpreterPhase {
+  Listening,
+  Transcribing,
+  Translating,
+  Speaking,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct InterpreterTurn {
+  pub speaker_lang: String,
+  pub listener_lang: String,
+  pub source: String,
+  pub translation: String,
+  // set when the translation went through a pivot language
+  pub pivot_lang: Option<String>,
+  // synthesized speech of the translation, when a voice is available
+  pub audio_path: Option<String>,
+}
+
+// A two-party conversation where each utterance is translated into the other party's language
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct InterpreterSession {
+  pub id: String,
+  pub lang_a: String,
+  pub lang_b: String,
+  pub model_id: Option<String>,
+  // synthesize each translation with TTS
+  pub speak: bool,
+  pub phase: InterpreterPhase,
+  // language we expect next (the party that just listened); used when detection is inconclusive
+  pub expected_lang: String,
+  pub turns: Vec<InterpreterTurn>,
+}
+
+impl InterpreterSession {
+  fn other(&self, lang: &str) -> String {
+    if lang == self.lang_a { self.lang_b.clone() } else { self.lang_a.clone() }
+  }
+}
+
+// Payload of "interpreter-state" events
+#[derive(Clone, serde::Serialize)]
+struct InterpreterState {
+  session_id: String,
+  phase: InterpreterPhase,
+}
+
+// All open interpreter sessions (managed by Tauri)
+pub struct InterpreterStore {
+  sessions: HashMap<String, InterpreterSession>,
+  counter: u64,
+}
+
+impl InterpreterStore {
+  pub fn new() -> Self {
+    Self { sessions: HashMap::new(), counter: 0 }
+  }
+
+  fn get_mut(&mut self, id: &str) -> Result<&mut InterpreterSession, String> {
+    self.sessions.get_mut(id).ok_or(format!("Interpreter session '{}' not found", id))
+  }
+}
+
+// decide which party spoke: recognizer language, then script, then the model, then turn order
+fn detect_speaker(session: &InterpreterSession, text: &str, asr_lang: Option<&str>, model_path: &str, config: &ModelConfig) -> String {
+  let (a, b) = (&session.lang_a, &session.lang_b);
+  if let Some(l) = asr_lang {
+    if lang::same_language(l, a) {
+      return a.clone();
+    }
+    if lang::same_language(l, b) {
+      return b.clone();
+    }
+  }
+  let (script_a, script_b) = (lang::script_for(a), lang::script_for(b));
+  if script_a != script_b {
+    match lang::dominant_script(text) {
+      Some(s) if s == script_a => return a.clone(),
+      Some(s) if s == script_b => return b.clone(),
+      _ => {}
+    }
+  }
+  let (name_a, name_b) = (lang::language_name(a), lang::language_name(b));
+  let prompt = format!(
+    "Is the following text written in {} or {}? Reply with the language name only.\n\nText:\n{}\n\nLanguage:",
+    name_a, name_b, text
+  );
+  if let Ok(reply) = engine::generate(model_path, config, &prompt, 8) {
+    let reply = reply.to_lowercase();
+    let (has_a, has_b) = (reply.contains(&name_a.to_lowercase()), reply.contains(&name_b.to_lowercase()));
+    if has_a != has_b {
+      return if has_a { a.clone() } else { b.clone() };
+    }
+  }
+  session.expected_lang.clone()
+}
+
+fn set_phase(window: &Window, session_id: &str, phase: InterpreterPhase) {
+  if let Ok(s) = window.state::<Mutex<InterpreterStore>>().locked().get_mut(session_id) {
+    s.phase = phase;
+  }
+  let _ = window.emit("interpreter-state", InterpreterState { session_id: session_id.to_string(), phase });
+}
+
+fn run_turn(window: &Window, session: &InterpreterSession, audio_path: Option<String>, text: Option<String>) -> Result<InterpreterTurn, String> {
+  let (source, asr_lang) = match (text, audio_path) {
+    (Some(t), _) if !t.trim().is_empty() => (t.trim().to_string(), None),
+    (_, Some(audio)) => {
+      set_phase(window, &session.id, InterpreterPhase::Transcribing);
+      let transcript = speech::transcribe(&audio, None)?;
+      (transcript.text, transcript.lang)
+    }
+    _ => return Err("Provide either text or an audio file".into()),
+  };
+  if source.is_empty() {
+    return Err("Nothing was recognized".into());
+  }
+
+  set_phase(window, &session.id, InterpreterPhase::Translating);
+  let (model, config) = window.state::<Mutex<ModelManager>>().locked().model_for_request(session.model_id.as_deref())?;
+  let speaker_lang = detect_speaker(session, &source, asr_lang.as_deref(), &model.path, &config);
+  let listener_lang = session.other(&speaker_lang);
+  preload::record(window, "interpret", &preload::pair(&speaker_lang, &listener_lang), &model.id);
+  let routed = translate::translate_routed(window, &source, &speaker_lang, &listener_lang, session.model_id.as_deref(), &Default::default())?;
+  let (translation, pivot_lang) = (routed.text, routed.pivot_lang);
+
+  let mut audio_path = None;
+  if session.speak && speech::voice_for(&listener_lang).is_some() {
+    set_phase(window, &session.id, InterpreterPhase::Speaking);
+    let out = store::data_file("audio").join(format!("{}-{}.wav", session.id, session.turns.len() + 1));
+    // a failed synthesis still leaves the text translation usable
+    if speech::synthesize(&translation, &listener_lang, &out).is_ok() {
+      audio_path = Some(out.to_string_lossy().to_string());
+    }
+  }
+  Ok(InterpreterTurn { speaker_lang, listener_lang, source, translation, pivot_lang, audio_path })
+}
+
+// ------------------ Tauri commands ------------------
+
+#[tauri::command]
+pub fn start_interpreter(
+  lang_a: String,
+  lang_b: String,
+  model_id: Option<String>,
+  speak: Option<bool>,
+  store: tauri::State<'_, Mutex<InterpreterStore>>
+) -> Result<InterpreterSession, AppError> {
+  if lang::same_language(&lang_a, &lang_b) {
+    return Err(AppError::InvalidInput("The two parties must use different languages".into()));
+  }
+  let mut store = store.locked();
+  store.counter += 1;
+  let millis = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0);
+  let id = format!("interp-{}-{}", millis, store.counter);
+  let session = InterpreterSession {
+    id: id.clone(),
+    expected_lang: lang_a.clone(),
+    lang_a,
+    lang_b,
+    model_id,
+    speak: speak.unwrap_or(true),
+    phase: InterpreterPhase::Listening,
+    turns: Vec::new(),
+  };
+  store.sessions.insert(id, session.clone());
+  Ok(session)
+}
+
+// one utterance from either party, as text or as a recorded audio file;
+// emits "interpreter-state" as the turn moves through transcription, translation and speech
+#[tauri::command(async)]
+pub fn interpreter_turn(
+  session_id: String,
+  audio_path: Option<String>,
+  text: Option<String>,
+  window: Window,
+) -> Result<InterpreterTurn, AppError> {
+  let session = {
+    let store = window.state::<Mutex<InterpreterStore>>();
+    let mut store = store.locked();
+    let s = store.get_mut(&session_id)?;
+    if s.phase != InterpreterPhase::Listening {
+      return Err(AppError::Busy("A turn is already in progress".into()));
+    }
+    // claim the session before releasing the lock
+    s.phase = InterpreterPhase::Translating;
+    s.clone()
+  };
+
+  let result = dispatch::with_priority(Priority::Live, || run_turn(&window, &session, audio_path, text));
+  if let Ok(turn) = &result {
+    if let Ok(s) = window.state::<Mutex<InterpreterStore>>().locked().get_mut(&session_id) {
+      s.expected_lang = turn.listener_lang.clone();
+      s.turns.push(turn.clone());
+    }
+  }
+  set_phase(&window, &session_id, InterpreterPhase::Listening);
+  Ok(result?)
+}
+
+#[tauri::command]
+pub fn get_interpreter_session(session_id: String, store: tauri::State<'_, Mutex<InterpreterStore>>) -> Result<InterpreterSession, AppError> {
+  Ok(store.locked().get_mut(&session_id).map(|s| s.clone())?)
+}
+
+#[tauri::command]
+pub fn end_interpreter(session_id: String, store: tauri::State<'_, Mutex<InterpreterStore>>) -> Result<InterpreterSession, AppError> {
+  store.locked().sessions.remove(&session_id).ok_or_else(|| AppError::not_found("Interpreter session", &session_id))
+}
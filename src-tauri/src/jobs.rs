@@ -0,0 +1,528 @@
+// src-tauri/src/jobs.rs
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tauri::{AppHandle, Manager, Window};
+
+use crate::dispatch::{self, Priority};
+use crate::document::{self, DocumentFormat};
+use crate::error::{AppError, LockExt};
+use crate::events;
+use crate::preload;
+use crate::quality;
+use crate::store;
+use crate::translate::{self, LengthLimit, TranslateOptions};
+
+// Review workflow of a single segment
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SegmentState {
+  // not translated yet
+  Pending,
+  MachineTranslated,
+  // changed by a human, awaiting approval
+  Edited,
+  Approved,
+}
+
+// Whether a job's machine translation is in progress; a job still "running" at startup
+// was interrupted and is resumed from its first pending segment
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+  #[default]
+  Idle,
+  Running,
+  Done,
+  Failed,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct JobSegment {
+  pub index: usize,
+  pub source: String,
+  pub target: Option<String>,
+  pub state: SegmentState,
+  // machine translation still exceeded the job's length limit after retries
+  #[serde(default)]
+  pub over_limit: bool,
+  // quality estimate of the machine translation (0..1), when a quality model is set up
+  #[serde(default)]
+  pub quality: Option<f32>,
+  // scored below the quality threshold; review this one first
+  #[serde(default)]
+  pub low_quality: bool,
+  // the machine translation isn't in the target language, even after a retry
+  #[serde(default)]
+  pub wrong_language: bool,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct DocumentJob {
+  pub id: u64,
+  pub path: String,
+  pub source_lang: String,
+  pub target_lang: String,
+  // unix seconds
+  pub created: i64,
+  pub segments: Vec<JobSegment>,
+  #[serde(default)]
+  pub status: JobStatus,
+  // model used by the last run, reused when resuming
+  #[serde(default)]
+  pub model_id: Option<String>,
+  // pipeline jobs write their translation here once every segment is done
+  #[serde(default)]
+  pub output_path: Option<String>,
+  // how the translation is put together from the segments and the source document
+  #[serde(default)]
+  pub format: Option<DocumentFormat>,
+  #[serde(default)]
+  pub error: Option<String>,
+  // set when no model covers the pair and segments go through this language
+  #[serde(default)]
+  pub pivot_lang: Option<String>,
+  // per-segment length budget (UI strings, subtitles)
+  #[serde(default)]
+  pub length_limit: Option<LengthLimit>,
+  // domain pack applied to every segment (see apply_domain)
+  #[serde(default)]
+  pub domain: Option<String>,
+}
+
+// Job listing without segment contents
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct JobSummary {
+  pub id: u64,
+  pub path: String,
+  pub source_lang: String,
+  pub target_lang: String,
+  pub created: i64,
+  pub status: JobStatus,
+  pub output_path: Option<String>,
+  pub error: Option<String>,
+  pub pivot_lang: Option<String>,
+  pub domain: Option<String>,
+  pub total: usize,
+  pub pending: usize,
+  pub machine_translated: usize,
+  pub edited: usize,
+  pub approved: usize,
+}
+
+impl DocumentJob {
+  fn summary(&self) -> JobSummary {
+    let count = |s: SegmentState| self.segments.iter().filter(|seg| seg.state == s).count();
+    JobSummary {
+      id: self.id,
+      path: self.path.clone(),
+      source_lang: self.source_lang.clone(),
+      target_lang: self.target_lang.clone(),
+      created: self.created,
+      status: self.status,
+      output_path: self.output_path.clone(),
+      error: self.error.clone(),
+      pivot_lang: self.pivot_lang.clone(),
+      domain: self.domain.clone(),
+      total: self.segments.len(),
+      pending: count(SegmentState::Pending),
+      machine_translated: count(SegmentState::MachineTranslated),
+      edited: count(SegmentState::Edited),
+      approved: count(SegmentState::Approved),
+    }
+  }
+
+  fn segment_mut(&mut self, index: usize) -> Result<&mut JobSegment, String> {
+    let id = self.id;
+    self.segments.get_mut(index).ok_or(format!("Job {} has no segment {}", id, index))
+  }
+}
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct JobFile {
+  next_id: u64,
+  jobs: Vec<DocumentJob>,
+}
+
+// Document jobs persisted in ./data/jobs.json (managed by Tauri)
+pub struct JobStore {
+  path: PathBuf,
+  file: JobFile,
+  // jobs with a translation thread in this process
+  active: HashSet<u64>,
+}
+
+impl JobStore {
+  pub fn load() -> Self {
+    let path = store::data_file("jobs.json");
+    let file = store::load_json(&path);
+    Self { path, file, active: HashSet::new() }
+  }
+
+  fn save(&self) -> Result<(), String> {
+    store::save_json(&self.path, &self.file)
+  }
+
+  pub fn create(
+    &mut self,
+    path: &str,
+    source_lang: &str,
+    target_lang: &str,
+    sources: Vec<String>,
+    output: Option<(String, DocumentFormat)>,
+    length_limit: Option<LengthLimit>
+  ) -> Result<JobSummary, String> {
+    let (output_path, format) = output.unzip();
+    self.file.next_id += 1;
+    let job = DocumentJob {
+      id: self.file.next_id,
+      path: path.to_string(),
+      source_lang: source_lang.to_string(),
+      target_lang: target_lang.to_string(),
+      created: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0),
+      segments: sources
+        .into_iter()
+        .enumerate()
+        .map(|(index, source)| JobSegment { index, source, target: None, state: SegmentState::Pending, over_limit: false, quality: None, low_quality: false, wrong_language: false })
+        .collect(),
+      status: JobStatus::Idle,
+      model_id: None,
+      output_path,
+      format,
+      error: None,
+      pivot_lang: None,
+      length_limit,
+      domain: None,
+    };
+    let summary = job.summary();
+    self.file.jobs.push(job);
+    self.save()?;
+    Ok(summary)
+  }
+
+  // the unfinished job translating `path` into `output_path` when its segments are still `sources`,
+  // else a new one
+  pub fn create_or_resume(
+    &mut self,
+    path: &str,
+    source_lang: &str,
+    target_lang: &str,
+    sources: Vec<String>,
+    output_path: String,
+    format: DocumentFormat
+  ) -> Result<JobSummary, String> {
+    let unfinished = self.file.jobs.iter().rev().find(|j| {
+      j.status != JobStatus::Done
+        && j.path == path
+        && j.source_lang == source_lang
+        && j.target_lang == target_lang
+        && j.output_path.as_deref() == Some(output_path.as_str())
+        && j.format == Some(format)
+        && j.segments.iter().map(|s| &s.source).eq(sources.iter())
+    });
+    match unfinished {
+      Some(job) => Ok(job.summary()),
+      None => self.create(path, source_lang, target_lang, sources, Some((output_path, format)), None),
+    }
+  }
+
+  pub fn get(&self, id: u64) -> Result<&DocumentJob, String> {
+    self.file.jobs.iter().find(|j| j.id == id).ok_or(format!("Job {} not found", id))
+  }
+
+  fn get_mut(&mut self, id: u64) -> Result<&mut DocumentJob, String> {
+    self.file.jobs.iter_mut().find(|j| j.id == id).ok_or(format!("Job {} not found", id))
+  }
+
+  pub fn list(&self) -> Vec<JobSummary> {
+    self.file.jobs.iter().map(|j| j.summary()).collect()
+  }
+
+  // apply `f` to one segment and persist
+  pub fn update_segment<F: FnOnce(&mut JobSegment) -> Result<(), String>>(&mut self, id: u64, index: usize, f: F) -> Result<JobSegment, String> {
+    let seg = self.get_mut(id)?.segment_mut(index)?;
+    f(seg)?;
+    let seg = seg.clone();
+    self.save()?;
+    Ok(seg)
+  }
+
+  // takes effect from the next run; segments already translated keep their text
+  pub fn set_domain(&mut self, id: u64, domain: Option<String>) -> Result<JobSummary, String> {
+    let job = self.get_mut(id)?;
+    job.domain = domain;
+    let summary = job.summary();
+    self.save()?;
+    Ok(summary)
+  }
+
+  // mark a job running; errors if another thread is already translating it
+  fn begin(&mut self, id: u64, model_id: Option<String>) -> Result<DocumentJob, String> {
+    if self.active.contains(&id) {
+      return Err(format!("Job {} is already running", id));
+    }
+    let job = self.get_mut(id)?;
+    job.status = JobStatus::Running;
+    job.error = None;
+    if model_id.is_some() {
+      job.model_id = model_id;
+    }
+    let job = job.clone();
+    self.active.insert(id);
+    self.save()?;
+    Ok(job)
+  }
+
+  fn finish(&mut self, id: u64, result: &Result<(), String>) {
+    self.active.remove(&id);
+    if let Ok(job) = self.get_mut(id) {
+      match result {
+        Ok(()) => job.status = JobStatus::Done,
+        Err(e) => {
+          job.status = JobStatus::Failed;
+          job.error = Some(e.clone());
+        }
+      }
+    }
+    let _ = self.save();
+  }
+
+  pub fn delete(&mut self, id: u64) -> Result<(), String> {
+    if self.active.contains(&id) {
+      return Err(format!("Job {} is still running", id));
+    }
+    let before = self.file.jobs.len();
+    self.file.jobs.retain(|j| j.id != id);
+    if self.file.jobs.len() == before {
+      return Err(format!("Job {} not found", id));
+    }
+    self.save()
+  }
+}
+
+// Payload of "job-progress" events
+#[derive(Clone, serde::Serialize)]
+struct JobProgress {
+  job_id: u64,
+  index: usize,
+  total: usize,
+}
+
+// translate the pending segments of a job, saving after each one so an interrupted run
+// resumes where it stopped; pipeline jobs then write their output file
+pub fn run_job<F: FnMut(usize, usize)>(app: &AppHandle, job_id: u64, model_id: Option<String>, mut on_progress: F) -> Result<(), String> {
+  let jobs = app.state::<Mutex<JobStore>>();
+  let job = jobs.locked().begin(job_id, model_id)?;
+  let result = dispatch::with_priority(Priority::Batch, || {
+    let legs = translate::route(app, &job.source_lang, &job.target_lang, job.model_id.as_deref())?;
+    preload::record(app, "translate", &preload::pair(&job.source_lang, &job.target_lang), &legs[0].model_id);
+    jobs.locked().get_mut(job_id)?.pivot_lang = (legs.len() > 1).then(|| legs[0].target_lang.clone());
+    let total = job.segments.len();
+    let options = TranslateOptions { length: job.length_limit.clone(), domain: job.domain.clone(), ..Default::default() };
+    let qe = quality::settings();
+    let check_quality = qe.check_jobs && qe.model_id.is_some();
+    for seg in job.segments.iter().filter(|s| s.state == SegmentState::Pending) {
+      let out = translate::run_route(app, &legs, &seg.source, &options)?;
+      // a failing quality model shouldn't stop the translation itself
+      let estimate = if check_quality {
+        quality::estimate(app, None, &seg.source, &out.text, &job.source_lang, &job.target_lang)
+          .map_err(|e| events::log(format!("quality estimate failed for job {} segment {}: {}", job_id, seg.index, e)))
+          .ok()
+      } else {
+        None
+      };
+      jobs.locked().update_segment(job_id, seg.index, |s| {
+        // the user may have edited it meanwhile
+        if s.state == SegmentState::Pending {
+          s.over_limit = out.length.is_some_and(|l| !l.within_limit);
+          s.wrong_language = out.language.is_some_and(|l| !l.matched);
+          s.quality = estimate.as_ref().map(|e| e.score);
+          s.low_quality = estimate.as_ref().is_some_and(|e| e.flagged);
+          s.target = Some(out.text);
+          s.state = SegmentState::MachineTranslated;
+        }
+        Ok(())
+      })?;
+      events::emit(app, "job-progress", JobProgress { job_id, index: seg.index, total });
+      on_progress(seg.index + 1, total);
+    }
+    if let Some(output) = &job.output_path {
+      let job = jobs.locked().get(job_id)?.clone();
+      let text: Vec<String> = job.segments.iter().map(|s| s.target.clone().unwrap_or_else(|| s.source.clone())).collect();
+      document::write(Path::new(&job.path), job.format.unwrap_or_default(), &text, Path::new(output))?;
+    }
+    Ok(())
+  });
+  jobs.locked().finish(job_id, &result);
+  result
+}
+
+// the job translating the document at `input` into `out`: a new one, or the unfinished one from an
+// earlier run when the document hasn't changed since
+pub fn document_job(app: &AppHandle, input: &Path, format: DocumentFormat, out: &Path, source_lang: &str, target_lang: &str) -> Result<JobSummary, String> {
+  let sources = document::segments(input, format)?;
+  app.state::<Mutex<JobStore>>().locked().create_or_resume(
+    &input.to_string_lossy(),
+    source_lang,
+    target_lang,
+    sources,
+    out.to_string_lossy().to_string(),
+    format,
+  )
+}
+
+// restart jobs that were running when the app last exited
+pub fn resume_interrupted(app: &AppHandle) {
+  let interrupted: Vec<u64> = {
+    let jobs = app.state::<Mutex<JobStore>>();
+    let jobs = jobs.locked();
+    jobs.file.jobs.iter().filter(|j| j.status == JobStatus::Running).map(|j| j.id).collect()
+  };
+  for id in interrupted {
+    let app = app.clone();
+    thread::spawn(move || {
+      let _ = run_job(&app, id, None, |_, _| {});
+    });
+  }
+}
+
+// ------------------ Tauri commands ------------------
+
+// create a job from a plain-text document; each paragraph becomes a segment.
+// `length_limit` caps every segment's translation (e.g. subtitle lines)
+#[tauri::command]
+pub fn create_document_job(
+  path: String,
+  source_lang: String,
+  target_lang: String,
+  length_limit: Option<LengthLimit>,
+  jobs: tauri::State<'_, Mutex<JobStore>>
+) -> Result<JobSummary, AppError> {
+  let text = fs::read_to_string(&path).map_err(|e| AppError::io("read", &path, e))?;
+  let sources: Vec<String> = text.split("\n\n").map(|p| p.trim()).filter(|p| !p.is_empty()).map(|p| p.to_string()).collect();
+  if sources.is_empty() {
+    return Err(format!("{} contains no text", path).into());
+  }
+  Ok(jobs.locked().create(&path, &source_lang, &target_lang, sources, None, length_limit)?)
+}
+
+#[tauri::command]
+pub fn list_jobs(jobs: tauri::State<'_, Mutex<JobStore>>) -> Vec<JobSummary> {
+  jobs.locked().list()
+}
+
+#[tauri::command]
+pub fn get_job(job_id: u64, jobs: tauri::State<'_, Mutex<JobStore>>) -> Result<DocumentJob, AppError> {
+  Ok(jobs.locked().get(job_id).cloned()?)
+}
+
+#[tauri::command]
+pub fn delete_job(job_id: u64, jobs: tauri::State<'_, Mutex<JobStore>>) -> Result<(), AppError> {
+  Ok(jobs.locked().delete(job_id)?)
+}
+
+// machine-translate every pending segment, emitting "job-progress" after each one
+#[tauri::command(async)]
+pub fn machine_translate_job(job_id: u64, model_id: Option<String>, window: Window) -> Result<JobSummary, AppError> {
+  run_job(window.app_handle(), job_id, model_id, |_, _| {})?;
+  let summary = window.state::<Mutex<JobStore>>().locked().get(job_id)?.summary();
+  Ok(summary)
+}
+
+// translate a .txt, .md, .srt or .docx document into "<name>.<target_lang>.<ext>" next to it, chunk
+// by chunk with "job-progress" events. `format` overrides what the extension says. Running it
+// again after a failure (or a quit) continues the job where it stopped
+#[tauri::command(async)]
+pub fn translate_file(
+  path: String,
+  source_lang: String,
+  target_lang: String,
+  format: Option<DocumentFormat>,
+  model_id: Option<String>,
+  window: Window
+) -> Result<JobSummary, AppError> {
+  let input = Path::new(&path);
+  let format = format
+    .or_else(|| DocumentFormat::from_path(input))
+    .ok_or_else(|| AppError::InvalidInput(format!("Unsupported document type: {}", path)))?;
+  let out = translate::output_path(input, input.parent().unwrap_or(Path::new(".")), &target_lang);
+  let job = document_job(window.app_handle(), input, format, &out, &source_lang, &target_lang)?;
+  machine_translate_job(job.id, model_id, window)
+}
+
+// continue a stopped, failed or interrupted job from its first pending segment
+#[tauri::command(async)]
+pub fn resume_job(job_id: u64, model_id: Option<String>, window: Window) -> Result<JobSummary, AppError> {
+  machine_translate_job(job_id, model_id, window)
+}
+
+// human edit of a segment's translation
+#[tauri::command]
+pub fn edit_segment(job_id: u64, index: usize, text: String, jobs: tauri::State<'_, Mutex<JobStore>>) -> Result<JobSegment, AppError> {
+  Ok(jobs.locked().update_segment(job_id, index, |s| {
+    s.target = Some(text);
+    s.state = SegmentState::Edited;
+    s.over_limit = false;
+    s.low_quality = false;
+    s.wrong_language = false;
+    Ok(())
+  })?)
+}
+
+// move a segment to another review state (e.g. approve, or send back to edited)
+#[tauri::command]
+pub fn set_segment_state(
+  job_id: u64,
+  index: usize,
+  state: SegmentState,
+  jobs: tauri::State<'_, Mutex<JobStore>>
+) -> Result<JobSegment, AppError> {
+  Ok(jobs.locked().update_segment(job_id, index, |s| {
+    if state != SegmentState::Pending && s.target.is_none() {
+      return Err(format!("Segment {} has no translation yet", index));
+    }
+    if state == SegmentState::Pending {
+      s.target = None;
+      s.over_limit = false;
+      s.wrong_language = false;
+      s.quality = None;
+      s.low_quality = false;
+    }
+    s.state = state;
+    Ok(())
+  })?)
+}
+
+// write approved translations to `output_path` (paragraphs separated by blank lines);
+// unapproved segments are left out unless `include_source` keeps their source text in place
+#[tauri::command]
+pub fn export_job(
+  job_id: u64,
+  output_path: String,
+  include_source: Option<bool>,
+  jobs: tauri::State<'_, Mutex<JobStore>>
+) -> Result<usize, AppError> {
+  let job = jobs.locked().get(job_id)?.clone();
+  let keep_source = include_source.unwrap_or(false);
+  let mut approved = 0;
+  let mut parts = Vec::new();
+  for seg in &job.segments {
+    match (&seg.state, &seg.target) {
+      (SegmentState::Approved, Some(t)) => {
+        approved += 1;
+        parts.push(t.clone());
+      }
+      _ if keep_source => parts.push(seg.source.clone()),
+      _ => {}
+    }
+  }
+  if approved == 0 {
+    return Err("No approved segments to export".into());
+  }
+  fs::write(&output_path, parts.join("\n\n")).map_err(|e| AppError::io("write", &output_path, e))?;
+  Ok(approved)
+}
@@ -0,0 +1,82 @@
+// src-tauri/src/kiosk.rs
+use std::sync::OnceLock;
+
+use crate::store;
+
+// commands still available in kiosk mode: translation, speech and read-only lookups
+const ALLOWED: &[&str] = &[
+  "get_kiosk_status",
+  "list_models",
+  "get_speech_support",
+  "transcribe_audio",
+  "list_input_devices",
+  "start_listening",
+  "stop_listening",
+  "list_voices",
+  "speak",
+  "stop_speaking",
+  "detect_language",
+  "translate",
+  "translate_candidates",
+  "translate_with_clarification",
+  "translate_code_aware",
+  "start_interpreter",
+  "interpreter_turn",
+  "get_interpreter_session",
+  "end_interpreter",
+  "recognize_text_image",
+  "list_phrasebooks",
+  "get_phrases",
+  "get_phrase_audio",
+  "annotate_text",
+  "read_aloud",
+  "make_readable",
+];
+
+// ./data/kiosk.json, written by whoever sets up the machine
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct KioskConfig {
+  pub enabled: bool,
+  // model loaded at startup, since the model list can't be managed from the UI
+  #[serde(default)]
+  pub model_id: Option<String>,
+}
+
+fn config() -> &'static KioskConfig {
+  static CONFIG: OnceLock<KioskConfig> = OnceLock::new();
+  CONFIG.get_or_init(|| {
+    let mut config: KioskConfig = store::load_json(&store::data_file("kiosk.json"));
+    // `--kiosk` on the command line forces it on for this run
+    if std::env::args().any(|a| a == "--kiosk") {
+      config.enabled = true;
+    }
+    config
+  })
+}
+
+// fixed for the lifetime of the process; leaving kiosk mode needs a restart
+pub fn enabled() -> bool {
+  config().enabled
+}
+
+pub fn startup_model() -> Option<String> {
+  config().model_id.clone().filter(|_| enabled())
+}
+
+// whether the frontend may call `command` right now
+pub fn allows(command: &str) -> bool {
+  !enabled() || ALLOWED.contains(&command)
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct KioskStatus {
+  pub enabled: bool,
+  pub allowed_commands: Vec<String>,
+}
+
+// ------------------ Tauri commands ------------------
+
+#[tauri::command]
+pub fn get_kiosk_status() -> KioskStatus {
+  KioskStatus { enabled: enabled(), allowed_commands: ALLOWED.iter().map(|c| c.to_string()).collect() }
+}
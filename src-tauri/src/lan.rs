@@ -0,0 +1,324 @@
+// src-tauri/src/lan.rs
+use std::io::Read;
+use std::net::UdpSocket;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ring::rand::{SecureRandom, SystemRandom};
+use tauri::{AppHandle, Manager};
+use tiny_http::{Request, Response};
+
+use crate::engine;
+use crate::error::{AppError, LockExt};
+use crate::filter;
+use crate::httpd::{self, HttpServer};
+use crate::store;
+use crate::sync;
+use crate::translate;
+use crate::ModelManager;
+
+const DEFAULT_PORT: u16 = 8765;
+// pairing codes expire after this many seconds
+const PAIRING_TTL: i64 = 300;
+// failed pairing attempts before the code is thrown away
+const PAIRING_ATTEMPTS: u32 = 5;
+// request bodies larger than this are rejected
+const MAX_BODY: u64 = 1 << 20;
+// a device's last_seen is written to disk at most this often (seconds)
+const LAST_SEEN_RESOLUTION: i64 = 60;
+
+// A phone or tablet that paired with this machine
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+struct LanDevice {
+  id: u64,
+  name: String,
+  token: String,
+  // unix seconds
+  paired: i64,
+  last_seen: Option<i64>,
+}
+
+// A paired device as shown in settings (without its token)
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct LanDeviceInfo {
+  pub id: u64,
+  pub name: String,
+  pub paired: i64,
+  pub last_seen: Option<i64>,
+}
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct LanFile {
+  // works without pairing, for clients set up by hand
+  access_token: String,
+  next_id: u64,
+  devices: Vec<LanDevice>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct LanStatus {
+  pub running: bool,
+  pub url: Option<String>,
+  pub access_token: String,
+  pub devices: Vec<LanDeviceInfo>,
+}
+
+// Contents of the pairing QR code
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct PairingPayload {
+  // JSON string to encode in the QR code
+  pub payload: String,
+  pub url: String,
+  pub code: String,
+  pub expires: i64,
+}
+
+struct Pairing {
+  code: String,
+  expires: i64,
+  failures: u32,
+}
+
+struct Running {
+  server: HttpServer,
+  url: String,
+}
+
+// LAN sharing of the loaded model; devices persisted in ./data/lan.json (managed by Tauri)
+pub struct LanShare {
+  path: PathBuf,
+  file: LanFile,
+  pairing: Option<Pairing>,
+  running: Option<Running>,
+}
+
+fn now_secs() -> i64 {
+  SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0)
+}
+
+// hex string of `bytes` random bytes from the OS generator
+fn random_hex(bytes: usize) -> String {
+  let mut buf = vec![0u8; bytes];
+  SystemRandom::new().fill(&mut buf).expect("system random generator unavailable");
+  buf.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn random_code() -> String {
+  let mut buf = [0u8; 4];
+  SystemRandom::new().fill(&mut buf).expect("system random generator unavailable");
+  format!("{:06}", u32::from_le_bytes(buf) % 1_000_000)
+}
+
+// address other devices on the network can reach; connecting a UDP socket sends nothing
+fn lan_ip() -> Option<String> {
+  let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+  socket.connect("192.0.2.1:80").ok()?;
+  socket.local_addr().ok().map(|a| a.ip().to_string()).filter(|ip| ip != "0.0.0.0")
+}
+
+impl LanShare {
+  pub fn load() -> Self {
+    let path = store::data_file("lan.json");
+    let mut file: LanFile = store::load_json(&path);
+    if file.access_token.is_empty() {
+      file.access_token = random_hex(24);
+      let _ = store::save_json(&path, &file);
+    }
+    Self { path, file, pairing: None, running: None }
+  }
+
+  fn save(&self) -> Result<(), String> {
+    store::save_json(&self.path, &self.file)
+  }
+
+  // base URL of the running server, for pairing payloads
+  pub fn url(&self) -> Option<String> {
+    self.running.as_ref().map(|r| r.url.clone())
+  }
+
+  fn status(&self) -> LanStatus {
+    LanStatus {
+      running: self.running.is_some(),
+      url: self.running.as_ref().map(|r| r.url.clone()),
+      access_token: self.file.access_token.clone(),
+      devices: self
+        .file
+        .devices
+        .iter()
+        .map(|d| LanDeviceInfo { id: d.id, name: d.name.clone(), paired: d.paired, last_seen: d.last_seen })
+        .collect(),
+    }
+  }
+
+  // the access token or a paired device's token, compared in constant time; records when the
+  // device was last seen, saving only when that moved by LAST_SEEN_RESOLUTION
+  fn authorize(&mut self, token: &str) -> bool {
+    if httpd::same_secret(token, &self.file.access_token) {
+      return true;
+    }
+    let now = now_secs();
+    let Some(device) = self.file.devices.iter_mut().find(|d| httpd::same_secret(token, &d.token)) else {
+      return false;
+    };
+    if device.last_seen.is_none_or(|seen| now - seen >= LAST_SEEN_RESOLUTION) {
+      device.last_seen = Some(now);
+      let _ = self.save();
+    }
+    true
+  }
+
+  // trade a valid pairing code for a device token
+  fn pair(&mut self, code: &str, name: &str) -> Result<String, String> {
+    let pairing = self.pairing.as_mut().filter(|p| p.expires > now_secs()).ok_or("invalid or expired pairing code")?;
+    if !httpd::same_secret(code, &pairing.code) {
+      // a 6 digit code can be guessed given enough tries
+      pairing.failures += 1;
+      if pairing.failures >= PAIRING_ATTEMPTS {
+        self.pairing = None;
+      }
+      return Err("invalid or expired pairing code".into());
+    }
+    self.pairing = None;
+    self.file.next_id += 1;
+    let device = LanDevice { id: self.file.next_id, name: name.to_string(), token: random_hex(24), paired: now_secs(), last_seen: None };
+    let token = device.token.clone();
+    self.file.devices.push(device);
+    self.save()?;
+    Ok(token)
+  }
+}
+
+fn json_response(status: u16, value: serde_json::Value) -> Response<std::io::Cursor<Vec<u8>>> {
+  Response::from_string(value.to_string())
+    .with_status_code(status)
+    .with_header(httpd::content_type("application/json"))
+}
+
+fn read_body(req: &mut Request) -> Result<serde_json::Value, String> {
+  let mut body = String::new();
+  req.as_reader().take(MAX_BODY).read_to_string(&mut body).map_err(|e| format!("failed to read request: {}", e))?;
+  serde_json::from_str(&body).map_err(|e| format!("invalid JSON: {}", e))
+}
+
+fn field<'a>(body: &'a serde_json::Value, name: &str) -> Option<&'a str> {
+  body.get(name).and_then(|v| v.as_str())
+}
+
+fn route(req: &mut Request, app: &AppHandle) -> Result<serde_json::Value, (u16, String)> {
+  let share = app.state::<Mutex<LanShare>>();
+  let path = req.url().split('?').next().unwrap_or("").to_string();
+  if path == "/v1/pair" {
+    let body = read_body(req).map_err(|e| (400, e))?;
+    let code = field(&body, "code").unwrap_or("");
+    let name = field(&body, "device").unwrap_or("device");
+    let token = share.locked().pair(code, name).map_err(|e| (403, e))?;
+    return Ok(serde_json::json!({ "token": token }));
+  }
+  // sync bundles are sealed with a per-device key, which authenticates them on its own
+  if path == "/v1/sync" {
+    let mut body = Vec::new();
+    req.as_reader().take(MAX_BODY * 16).read_to_end(&mut body).map_err(|e| (400, format!("failed to read request: {}", e)))?;
+    let sealed = sync::handle_request(app, &body)?;
+    return serde_json::to_value(sealed).map_err(|e| (500, e.to_string()));
+  }
+  if !httpd::bearer(req).is_some_and(|token| share.locked().authorize(token)) {
+    return Err((401, "missing or invalid access token".into()));
+  }
+
+  let (model, config) = app.state::<Mutex<ModelManager>>().locked().model_for_request(None).map_err(|e| (503, e))?;
+  match path.as_str() {
+    "/v1/info" => Ok(serde_json::json!({ "model": model.id, "name": model.name })),
+    "/v1/generate" => {
+      let body = read_body(req).map_err(|e| (400, e))?;
+      let prompt = field(&body, "prompt").ok_or((400, "missing prompt".to_string()))?;
+      let max_tokens = body.get("max_tokens").and_then(|v| v.as_u64()).unwrap_or(256) as u32;
+      let (prompt, _) = filter::check_input(app, prompt.to_string()).map_err(|e| (403, e))?;
+      let text = engine::generate(&model.path, &config, &prompt, max_tokens).map_err(|e| (500, e))?;
+      let (text, _) = filter::check_output(app, text).map_err(|e| (403, e))?;
+      Ok(serde_json::json!({ "text": text }))
+    }
+    "/v1/translate" => {
+      let body = read_body(req).map_err(|e| (400, e))?;
+      let text = field(&body, "text").ok_or((400, "missing text".to_string()))?;
+      let target = field(&body, "target_lang").ok_or((400, "missing target_lang".to_string()))?;
+      let source = field(&body, "source_lang").unwrap_or("auto");
+      // optional {"length": {"max_chars": 40}} for UI strings and subtitles, "n_best": 3 for alternates
+      let options: translate::TranslateOptions = serde_json::from_value(body.clone()).unwrap_or_default();
+      let out = translate::translate_routed(app, text, source, target, None, &options).map_err(|e| (500, e))?;
+      Ok(serde_json::json!({ "text": out.text, "pivot_lang": out.pivot_lang, "length": out.length, "candidates": out.candidates }))
+    }
+    _ => Err((404, format!("unknown endpoint {}", path))),
+  }
+}
+
+fn handle(mut req: Request, app: &AppHandle) {
+  let resp = match route(&mut req, app) {
+    Ok(value) => json_response(200, value),
+    Err((status, message)) => json_response(status, serde_json::json!({ "error": message })),
+  };
+  let _ = req.respond(resp);
+}
+
+// ------------------ Tauri commands ------------------
+
+// serve the loaded model to other devices on the network (token required on every request).
+// The server speaks plain HTTP: prompts, replies and tokens cross the network unencrypted, so
+// share only on networks you trust
+#[tauri::command]
+pub fn start_lan_sharing(port: Option<u16>, app: AppHandle, share: tauri::State<'_, Mutex<LanShare>>) -> Result<LanStatus, AppError> {
+  let mut share = share.locked();
+  if share.running.is_some() {
+    return Err(AppError::Busy("LAN sharing is already running".into()));
+  }
+  let ip = lan_ip().ok_or("No network connection found")?;
+  let server = HttpServer::start(("0.0.0.0", port.unwrap_or(DEFAULT_PORT)), move |req| handle(req, &app))
+    .map_err(|e| format!("failed to start LAN server: {}", e))?;
+  let url = format!("http://{}:{}", ip, server.port);
+  share.running = Some(Running { server, url });
+  Ok(share.status())
+}
+
+#[tauri::command]
+pub fn stop_lan_sharing(share: tauri::State<'_, Mutex<LanShare>>) -> Result<(), AppError> {
+  let running = share.locked().running.take().ok_or("LAN sharing is not running")?;
+  running.server.stop();
+  Ok(())
+}
+
+#[tauri::command]
+pub fn get_lan_status(share: tauri::State<'_, Mutex<LanShare>>) -> LanStatus {
+  share.locked().status()
+}
+
+// one-time pairing code plus the payload to show as a QR code
+#[tauri::command]
+pub fn create_pairing(share: tauri::State<'_, Mutex<LanShare>>) -> Result<PairingPayload, AppError> {
+  let mut share = share.locked();
+  let url = share.running.as_ref().map(|r| r.url.clone()).ok_or("Start LAN sharing before pairing a device")?;
+  let code = random_code();
+  let expires = now_secs() + PAIRING_TTL;
+  share.pairing = Some(Pairing { code: code.clone(), expires, failures: 0 });
+  let payload = serde_json::json!({ "app": "multilingual", "v": 1, "url": url, "code": code }).to_string();
+  Ok(PairingPayload { payload, url, code, expires })
+}
+
+#[tauri::command]
+pub fn revoke_lan_device(id: u64, share: tauri::State<'_, Mutex<LanShare>>) -> Result<(), AppError> {
+  let mut share = share.locked();
+  let before = share.file.devices.len();
+  share.file.devices.retain(|d| d.id != id);
+  if share.file.devices.len() == before {
+    return Err(AppError::not_found("Device", &id.to_string()));
+  }
+  Ok(share.save()?)
+}
+
+// issue a new access token; clients using the old one must be set up again
+#[tauri::command]
+pub fn rotate_lan_token(share: tauri::State<'_, Mutex<LanShare>>) -> Result<LanStatus, AppError> {
+  let mut share = share.locked();
+  share.file.access_token = random_hex(24);
+  share.save()?;
+  Ok(share.status())
+}
@@ -0,0 +1,343 @@
+// src-tauri/src/lang.rs
+use whatlang::{Detector, Info, Lang};
+
+use crate::collate;
+
+// ISO 639-1 codes the app knows by name (used when writing prompts)
+const LANGUAGES: &[(&str, &str)] = &[
+  ("ar", "Arabic"),
+  ("bn", "Bengali"),
+  ("de", "German"),
+  ("en", "English"),
+  ("es", "Spanish"),
+  ("fa", "Persian"),
+  ("fi", "Finnish"),
+  ("fr", "French"),
+  ("he", "Hebrew"),
+  ("hi", "Hindi"),
+  ("id", "Indonesian"),
+  ("it", "Italian"),
+  ("ja", "Japanese"),
+  ("ko", "Korean"),
+  ("ml", "Malayalam"),
+  ("mr", "Marathi"),
+  ("nl", "Dutch"),
+  ("pl", "Polish"),
+  ("pt", "Portuguese"),
+  ("ru", "Russian"),
+  ("sv", "Swedish"),
+  ("ta", "Tamil"),
+  ("te", "Telugu"),
+  ("th", "Thai"),
+  ("tr", "Turkish"),
+  ("uk", "Ukrainian"),
+  ("ur", "Urdu"),
+  ("vi", "Vietnamese"),
+  ("zh", "Chinese"),
+];
+
+// "hi" -> "Hindi"; unknown codes are returned unchanged so prompts still make sense
+pub fn language_name(code: &str) -> String {
+  let base = code.split(['-', '_']).next().unwrap_or(code).to_lowercase();
+  LANGUAGES
+    .iter()
+    .find(|(c, _)| *c == base)
+    .map(|(_, name)| name.to_string())
+    .unwrap_or_else(|| code.to_string())
+}
+
+// "german" -> "de", for tools that report languages by their English name; None for names
+// neither the app nor the detector knows
+pub fn code_for_name(name: &str) -> Option<String> {
+  let name = name.trim();
+  LANGUAGES
+    .iter()
+    .find(|(_, n)| n.eq_ignore_ascii_case(name))
+    .map(|(code, _)| code.to_string())
+    .or_else(|| Lang::all().iter().find(|l| l.eng_name().eq_ignore_ascii_case(name)).map(|l| iso_code(*l)))
+}
+
+// "pt-BR" and "pt" count as the same language
+pub fn same_language(a: &str, b: &str) -> bool {
+  let base = |s: &str| s.split(['-', '_']).next().unwrap_or(s).to_lowercase();
+  base(a) == base(b)
+}
+
+// Writing systems we can tell apart without a model
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Script {
+  Latin,
+  Cyrillic,
+  Greek,
+  Arabic,
+  Hebrew,
+  Devanagari,
+  Bengali,
+  Tamil,
+  Telugu,
+  Malayalam,
+  Thai,
+  Hangul,
+  Kana,
+  Han,
+}
+
+fn script_of_char(c: char) -> Option<Script> {
+  let s = match c as u32 {
+    0x41..=0x5A | 0x61..=0x7A | 0xC0..=0x24F => Script::Latin,
+    0x370..=0x3FF => Script::Greek,
+    0x400..=0x4FF => Script::Cyrillic,
+    0x590..=0x5FF => Script::Hebrew,
+    0x600..=0x6FF | 0x750..=0x77F | 0xFB50..=0xFDFF | 0xFE70..=0xFEFF => Script::Arabic,
+    0x900..=0x97F => Script::Devanagari,
+    0x980..=0x9FF => Script::Bengali,
+    0xB80..=0xBFF => Script::Tamil,
+    0xC00..=0xC7F => Script::Telugu,
+    0xD00..=0xD7F => Script::Malayalam,
+    0xE00..=0xE7F => Script::Thai,
+    0x1100..=0x11FF | 0xAC00..=0xD7AF => Script::Hangul,
+    0x3040..=0x30FF => Script::Kana,
+    0x4E00..=0x9FFF | 0x3400..=0x4DBF => Script::Han,
+    _ => return None,
+  };
+  Some(s)
+}
+
+// most frequent script among the letters of `text`; kana anywhere means Japanese text
+pub fn dominant_script(text: &str) -> Option<Script> {
+  let mut counts: Vec<(Script, usize)> = Vec::new();
+  for s in text.chars().filter_map(script_of_char) {
+    match counts.iter_mut().find(|(k, _)| *k == s) {
+      Some((_, n)) => *n += 1,
+      None => counts.push((s, 1)),
+    }
+  }
+  if counts.iter().any(|(s, _)| *s == Script::Kana) {
+    return Some(Script::Kana);
+  }
+  counts.into_iter().max_by_key(|(_, n)| *n).map(|(s, _)| s)
+}
+
+// script a language is normally written in
+pub fn script_for(code: &str) -> Script {
+  match code.split(['-', '_']).next().unwrap_or(code).to_lowercase().as_str() {
+    "ru" | "uk" | "bg" | "sr" | "be" | "kk" => Script::Cyrillic,
+    "el" => Script::Greek,
+    "ar" | "fa" | "ur" | "ps" => Script::Arabic,
+    "he" | "yi" => Script::Hebrew,
+    "hi" | "mr" | "ne" => Script::Devanagari,
+    "bn" => Script::Bengali,
+    "ta" => Script::Tamil,
+    "te" => Script::Telugu,
+    "ml" => Script::Malayalam,
+    "th" => Script::Thai,
+    "ko" => Script::Hangul,
+    "ja" => Script::Kana,
+    "zh" => Script::Han,
+    _ => Script::Latin,
+  }
+}
+
+// Best guess at the language of a text
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct Detection {
+  pub lang: String,
+  // 0..1
+  pub confidence: f32,
+}
+
+// whatlang's languages by their ISO 639-1 code, the codes the rest of the app uses
+const DETECTABLE: &[(Lang, &str)] = &[
+  (Lang::Afr, "af"),
+  (Lang::Aka, "ak"),
+  (Lang::Amh, "am"),
+  (Lang::Ara, "ar"),
+  (Lang::Aze, "az"),
+  (Lang::Bel, "be"),
+  (Lang::Ben, "bn"),
+  (Lang::Bul, "bg"),
+  (Lang::Cat, "ca"),
+  (Lang::Ces, "cs"),
+  (Lang::Cmn, "zh"),
+  (Lang::Cym, "cy"),
+  (Lang::Dan, "da"),
+  (Lang::Deu, "de"),
+  (Lang::Ell, "el"),
+  (Lang::Eng, "en"),
+  (Lang::Epo, "eo"),
+  (Lang::Est, "et"),
+  (Lang::Fin, "fi"),
+  (Lang::Fra, "fr"),
+  (Lang::Guj, "gu"),
+  (Lang::Heb, "he"),
+  (Lang::Hin, "hi"),
+  (Lang::Hrv, "hr"),
+  (Lang::Hun, "hu"),
+  (Lang::Hye, "hy"),
+  (Lang::Ind, "id"),
+  (Lang::Ita, "it"),
+  (Lang::Jav, "jv"),
+  (Lang::Jpn, "ja"),
+  (Lang::Kan, "kn"),
+  (Lang::Kat, "ka"),
+  (Lang::Khm, "km"),
+  (Lang::Kor, "ko"),
+  (Lang::Lat, "la"),
+  (Lang::Lav, "lv"),
+  (Lang::Lit, "lt"),
+  (Lang::Mal, "ml"),
+  (Lang::Mar, "mr"),
+  (Lang::Mkd, "mk"),
+  (Lang::Mya, "my"),
+  (Lang::Nep, "ne"),
+  (Lang::Nld, "nl"),
+  (Lang::Nob, "no"),
+  (Lang::Ori, "or"),
+  (Lang::Pan, "pa"),
+  (Lang::Pes, "fa"),
+  (Lang::Pol, "pl"),
+  (Lang::Por, "pt"),
+  (Lang::Ron, "ro"),
+  (Lang::Rus, "ru"),
+  (Lang::Sin, "si"),
+  (Lang::Slk, "sk"),
+  (Lang::Slv, "sl"),
+  (Lang::Sna, "sn"),
+  (Lang::Spa, "es"),
+  (Lang::Srp, "sr"),
+  (Lang::Swe, "sv"),
+  (Lang::Tam, "ta"),
+  (Lang::Tel, "te"),
+  (Lang::Tgl, "tl"),
+  (Lang::Tha, "th"),
+  (Lang::Tuk, "tk"),
+  (Lang::Tur, "tr"),
+  (Lang::Ukr, "uk"),
+  (Lang::Urd, "ur"),
+  (Lang::Uzb, "uz"),
+  (Lang::Vie, "vi"),
+  (Lang::Yid, "yi"),
+  (Lang::Zul, "zu"),
+];
+
+fn iso_code(lang: Lang) -> String {
+  DETECTABLE.iter().find(|(l, _)| *l == lang).map(|(_, code)| code.to_string()).unwrap_or_else(|| lang.code().to_string())
+}
+
+fn to_detection(info: &Info) -> Detection {
+  Detection { lang: iso_code(info.lang()), confidence: info.confidence() as f32 }
+}
+
+// whether detect() knows this language
+pub fn detectable(code: &str) -> bool {
+  let base = code.split(['-', '_']).next().unwrap_or(code).to_lowercase();
+  DETECTABLE.iter().any(|(_, c)| *c == base)
+}
+
+// language of `text` by whatlang's trigram model (the languages in DETECTABLE); None when the
+// text has no letters to go on
+pub fn detect(text: &str) -> Option<Detection> {
+  whatlang::detect(text).map(|info| to_detection(&info))
+}
+
+// the next most likely languages of `text` after `best`, in the same script: what the detector
+// picks once the languages before are ruled out
+fn alternatives(text: &str, best: &Info, count: usize) -> Vec<Detection> {
+  let mut ruled_out = vec![best.lang()];
+  let mut found = Vec::new();
+  while found.len() < count {
+    let Some(info) = Detector::with_denylist(ruled_out.clone()).detect(text) else { break };
+    if info.script() != best.script() {
+      break;
+    }
+    ruled_out.push(info.lang());
+    found.push(to_detection(&info));
+  }
+  found
+}
+
+// Result of detect_language
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct LanguageGuess {
+  // ISO 639-1 code; None when there is too little text to tell
+  pub lang: Option<String>,
+  pub name: Option<String>,
+  // 0..1
+  pub confidence: f32,
+  pub script: Option<Script>,
+  // other languages of the same script the text may be in, most likely first, with the
+  // detector's confidence in each
+  pub alternatives: Vec<Detection>,
+}
+
+// A language the app can name in prompts
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct LanguageEntry {
+  pub code: String,
+  pub name: String,
+}
+
+// ------------------ Tauri commands ------------------
+
+// the languages the app knows by name, sorted by name in the order of `locale` (the UI language
+// when unset)
+#[tauri::command]
+pub fn list_languages(locale: Option<String>) -> Vec<LanguageEntry> {
+  let mut languages: Vec<LanguageEntry> = LANGUAGES.iter().map(|(code, name)| LanguageEntry { code: code.to_string(), name: name.to_string() }).collect();
+  collate::sort_by(&mut languages, locale.as_deref(), |l| &l.name);
+  languages
+}
+
+// guess the language of `text` locally (script plus whatlang's trigram model), e.g. to fill in
+// the source language before translating; no model is involved
+#[tauri::command]
+pub fn detect_language(text: String) -> LanguageGuess {
+  let script = dominant_script(&text);
+  let info = whatlang::detect(&text);
+  let detection = info.as_ref().map(to_detection);
+  LanguageGuess {
+    name: detection.as_ref().map(|d| language_name(&d.lang)),
+    confidence: detection.as_ref().map(|d| d.confidence).unwrap_or(0.0),
+    lang: detection.map(|d| d.lang),
+    script,
+    alternatives: info.map(|info| alternatives(&text, &info, 3)).unwrap_or_default(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn every_whatlang_language_has_a_code() {
+    assert!(Lang::all().iter().all(|l| DETECTABLE.iter().any(|(d, _)| d == l)));
+  }
+
+  #[test]
+  fn tells_latin_languages_apart() {
+    let samples = [
+      ("da", "Jeg har ikke set hende i dag, men hun kommer måske i morgen efter arbejde."),
+      ("no", "Jeg har ikke sett henne i dag, men hun kommer kanskje i morgen etter jobben."),
+      ("ro", "Nu am văzut-o astăzi, dar poate vine mâine după ce termină lucrul."),
+      ("cs", "Dnes jsem ji neviděl, ale možná přijde zítra, až skončí v práci."),
+      ("hu", "Ma nem láttam őt, de talán holnap eljön, amikor végzett a munkával."),
+    ];
+    for (lang, text) in samples {
+      assert_eq!(detect(text).map(|d| d.lang), Some(lang.to_string()), "{}", text);
+    }
+  }
+
+  #[test]
+  fn detectable_uses_the_base_code() {
+    assert!(detectable("pt-BR"));
+    assert!(detectable("ro"));
+    assert!(!detectable("xx"));
+  }
+
+  #[test]
+  fn nothing_to_go_on() {
+    assert!(detect("").is_none());
+    assert!(detect("1234 !!").is_none());
+  }
+}
@@ -0,0 +1,69 @@
+// src-tauri/src/langguard.rs
+use crate::lang::{self, Detection};
+
+// shorter outputs (names, numbers, "OK") are too little to tell the language from
+const MIN_CHARS: usize = 20;
+// below this the detector is guessing
+const MIN_CONFIDENCE: f32 = 0.34;
+// stricter re-translations tried before the result is only flagged
+pub const GUARD_RETRIES: u32 = 1;
+
+// What to do when a translation comes back in the wrong language
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GuardMode {
+  Off,
+  // report it in the result only
+  Flag,
+  // translate again with a stricter instruction, then flag if it still doesn't match
+  #[default]
+  Retry,
+}
+
+// Outcome of the output language check
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct LanguageCheck {
+  pub expected: String,
+  // None when the output was too short or unclear to tell
+  pub detected: Option<String>,
+  pub confidence: f32,
+  pub retries: u32,
+  pub matched: bool,
+}
+
+// Some when `text` is confidently written in a language other than `expected`
+pub fn wrong_language(text: &str, expected: &str) -> Option<Detection> {
+  if expected == "auto" || text.trim().chars().count() < MIN_CHARS || !lang::detectable(expected) {
+    return None;
+  }
+  lang::detect(text).filter(|d| d.confidence >= MIN_CONFIDENCE && !lang::same_language(&d.lang, expected))
+}
+
+pub fn check(text: &str, expected: &str, retries: u32) -> LanguageCheck {
+  let detected = lang::detect(text);
+  let wrong = wrong_language(text, expected);
+  LanguageCheck {
+    expected: expected.to_string(),
+    detected: detected.as_ref().map(|d| d.lang.clone()),
+    confidence: detected.map(|d| d.confidence).unwrap_or(0.0),
+    retries,
+    matched: wrong.is_none(),
+  }
+}
+
+// a translation prompt that names the mistake the model made
+pub fn strict_prompt(text: &str, source_lang: &str, target_lang: &str, wrong: &str) -> String {
+  let target = lang::language_name(target_lang);
+  let from = if source_lang == "auto" { String::new() } else { format!(" from {}", lang::language_name(source_lang)) };
+  format!(
+    "Translate the following text{} into {}. Your answer must be written entirely in {}, not in {}. Reply with the \
+     {} translation only.\n\nText:\n{}\n\n{} translation:",
+    from,
+    target,
+    target,
+    lang::language_name(wrong),
+    target,
+    text,
+    target
+  )
+}
@@ -1,12 +1,150 @@
 // src-tauri/src/lib.rs
 use std::collections::HashMap;
-use std::io::Write;
-use std::path::PathBuf;
-use std::process::{Child, Command, Stdio};
-use std::sync::Mutex;
-use std::{fs, thread, time::Duration};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use tauri::{Emitter, Manager, Window};
+use tokio::process::Child;
+
+mod align;
+mod ansi;
+mod audio;
+mod backup;
+mod annotate;
+mod bidi;
+mod chat_template;
+mod chunk;
+mod clarify;
+mod codeaware;
+mod collate;
+mod compose;
+mod confidence;
+mod convert;
+mod dedup;
+mod dispatch;
+mod document;
+mod domain;
+mod download;
+mod embed;
+mod encoding;
+mod engine;
+mod error;
+mod eval;
+mod events;
+mod favorites;
+mod filter;
+mod flashcards;
+mod gguf;
+mod gguf_split;
+mod glossary;
+mod gpu;
+mod hardware;
+mod httpd;
+mod instance;
+mod integrity;
+mod interpreter;
+mod kiosk;
+mod jobs;
+mod lan;
+mod lang;
+mod langguard;
+mod license;
+mod localize;
+mod memory;
+mod model_config;
+mod model_watch;
+mod modelcard;
+mod ocr;
+mod openai;
+mod output;
+mod phrasebook;
+mod pipeline;
+mod preload;
+mod procstats;
+mod protocol;
+mod proofread;
+mod quality;
+mod quantize;
+mod queue;
+mod quiz;
+mod readable;
+mod readaloud;
+mod runner;
+mod runtime;
+mod sampling;
+mod schedule;
+mod segments;
+mod selftest;
+mod session;
+mod session_bundle;
+mod server;
+mod settings;
+mod shutdown;
+mod simplify;
+mod speech;
+mod storage;
+mod store;
+mod stream;
+mod summarize;
+mod sync;
+mod system_prompt;
+mod terms;
+mod throttle;
+mod tm;
+mod translate;
+mod transport;
+mod tts;
+mod watch;
+mod watchdog;
+mod webproxy;
+
+use audio::Listeners;
+use bidi::BidiSettings;
+use clarify::Clarifications;
+use convert::Rates;
+use dispatch::Priority;
+use download::DownloadManager;
+use encoding::OutputEncoding;
+use error::{AppError, LockExt};
+use eval::EvalReports;
+use favorites::Favorites;
+use filter::ContentFilter;
+use flashcards::Flashcards;
+use gguf::GgufMetadata;
+use glossary::Glossary;
+use gpu::{GpuDevice, GpuSplit};
+use interpreter::InterpreterStore;
+use jobs::JobStore;
+use lan::LanShare;
+use license::{ModelLicense, ModelWarning};
+use localize::LocalizeSettings;
+use model_config::{ModelConfig, ModelConfigStore};
+use model_watch::ModelWatcher;
+use openai::OpenAiApi;
+use phrasebook::Phrasebooks;
+use pipeline::Pipelines;
+use preload::Preloader;
+use protocol::{Backend, StdinProtocol};
+use quiz::QuizStore;
+use sampling::SamplingParams;
+use schedule::Scheduler;
+use segments::SegmentStore;
+use server::LlamaServer;
+use session::{SessionStore, Turn};
+use shutdown::StopReport;
+use stream::{StreamConfig, TokenStream};
+use sync::SyncState;
+use system_prompt::SystemPrompts;
+use throttle::Throttle;
+use tm::TranslationMemory;
+use transport::{Endpoints, SharedWriter, Transport};
+use tts::Speakers;
+use watch::FolderWatches;
+use watchdog::RestartPolicy;
+use webproxy::WebProxy;
 
 // Simple serializable model summary returned to the frontend
 #[derive(Clone, serde::Serialize)]
@@ -14,68 +152,233 @@ struct ModelInfo {
   id: String,
   name: String,
   path: String,
+  // models directory it was found in
+  dir: String,
   loaded: bool,
+  // number of files for split GGUFs (1 otherwise)
+  parts: u32,
+  // false when some shards of a split GGUF are missing
+  complete: bool,
+  // from the model's manifest or Hugging Face model card, when present
+  license: Option<ModelLicense>,
+  // shared by models that are byte-identical copies of each other
+  duplicate_group: Option<String>,
+  // architecture, size, quantization, ... from the header of .gguf models
+  gguf: Option<GgufMetadata>,
+  // a whisper model for transcribe_audio rather than a text model
+  speech: bool,
+}
+
+// tiny cross-platform python mock that prints tokens slowly (used when no runtime is bundled)
+fn mock_command() -> Command {
+  #[cfg(target_os = "windows")]
+  let mock_cmd = vec!["/C".to_string(), format!("python -u -c \"import time; [print('TOKEN', i) or time.sleep(0.12) for i in range(200)]\"")];
+  #[cfg(not(target_os = "windows"))]
+  let mock_cmd = vec!["-c".to_string(), "python3 -u -c 'import time\nfor i in range(200):\n print(f\"TOKEN {i}\")\n time.sleep(0.12)'\n".to_string()];
+
+  // Use platform-safe approach to spawn the python mock via shell
+  let mut c = Command::new(if cfg!(target_os = "windows") { "cmd" } else { "sh" });
+  c.args(&mock_cmd);
+  c.stdout(Stdio::piped()).stderr(Stdio::piped());
+  c
+}
+
+// normalize RTL text / directional marks of an output line per the bidi settings
+fn bidi_line(window: &Window, line: &str) -> String {
+  let bidi_config = window.state::<Mutex<BidiSettings>>().locked().config.clone();
+  bidi::process_stream_line(line, &bidi_config)
 }
 
-// Manager that keeps the running child process (if any) and loaded model id
+// Manager that keeps the running child processes and loaded model id. The lock is only held for
+// bookkeeping; waiting on a process, reading its output and writing prompts happen outside of it
 struct ModelManager {
-  // optional running child process
-  process: Option<Child>,
+  // running child processes by model id (e.g. a translation model next to a chat model)
+  processes: HashMap<String, Child>,
+  // where prompts to each process go (its stdin or data socket), written to after the lock is released
+  inputs: HashMap<String, SharedWriter>,
+  // control channels of the processes on the socket transport
+  controls: HashMap<String, SharedWriter>,
+  // output bookkeeping of each running process (request ids, speed)
+  streams: HashMap<String, Arc<TokenStream>>,
+  // how prompts are written to each running process
+  protocols: HashMap<String, StdinProtocol>,
+  // processes that are llama-server sidecars, prompted over HTTP
+  servers: HashMap<String, Arc<LlamaServer>>,
   // which model is considered loaded (id)
   loaded: Option<String>,
   // discovered models (id -> ModelInfo)
   models: HashMap<String, ModelInfo>,
+  // user options per model (gpu split, ...)
+  configs: ModelConfigStore,
 }
 
 impl ModelManager {
   fn new() -> Self {
     let mut mgr = Self {
-      process: None,
+      processes: HashMap::new(),
+      inputs: HashMap::new(),
+      controls: HashMap::new(),
+      streams: HashMap::new(),
+      protocols: HashMap::new(),
+      servers: HashMap::new(),
       loaded: None,
       models: HashMap::new(),
+      configs: ModelConfigStore::load(settings::models_dir().join("model_config.json")),
     };
     mgr.scan_models(); // initial scan
     mgr
   }
 
-  // scan ./models directory for files/folders that look like models
+  // switch to the configured models directory: its model configs and the models in it
+  fn reload(&mut self) {
+    self.configs = ModelConfigStore::load(settings::models_dir().join("model_config.json"));
+    self.scan_models();
+  }
+
+  // scan every models directory (see settings) for files/folders that look like models
   fn scan_models(&mut self) {
-    self.models.clear();
-    
-    let dir = PathBuf::from("./models");
-    println!("DEBUG: scanning folder = {:?}", dir);
-
-    if let Ok(entries) = fs::read_dir(dir) {
-      for entry in entries.flatten() {
-        let p = entry.path();
-        if p.is_file() {
-          if let Some(ext) = p.extension() {
-            if ext == "gguf" || ext == "bin" || ext == "pt" {
-              let id = p.file_stem().unwrap().to_string_lossy().to_string();
-              let name = p.file_name().unwrap().to_string_lossy().to_string();
-              self.models.insert(
-                id.clone(),
-                ModelInfo { id, name, path: p.to_string_lossy().to_string(), loaded: false },
-              );
+    // groups of the last scan stay on models at the same path until the new ones are hashed
+    let groups: HashMap<String, (String, String)> =
+      self.models.drain().filter_map(|(id, m)| m.duplicate_group.map(|g| (id, (m.path, g)))).collect();
+    for dir in settings::model_dirs() {
+      self.scan_dir(&dir);
+    }
+    for (id, (path, group)) in groups {
+      if let Some(m) = self.models.get_mut(&id).filter(|m| m.path == path) {
+        m.duplicate_group = Some(group);
+      }
+    }
+
+    // split models are left out: their path is only the first shard
+    let candidates: Vec<(String, PathBuf)> =
+      self.models.values().filter(|m| m.parts == 1).map(|m| (m.id.clone(), PathBuf::from(&m.path))).collect();
+    dedup::group_in_background(candidates);
+  }
+
+  fn scan_dir(&mut self, dir: &Path) {
+    events::log(format!("scanning folder = {:?}", dir));
+
+    // split GGUFs (name-00001-of-00003.gguf): base name -> (first shard path, shard count, shards seen)
+    let mut shards: HashMap<String, (Option<PathBuf>, u32, u32)> = HashMap::new();
+
+    // sorted so ids given out on a collision stay the same between scans
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir).map(|rd| rd.flatten().map(|e| e.path()).collect()).unwrap_or_default();
+    entries.sort();
+    for p in entries {
+      if p.is_file() {
+        if let Some(ext) = p.extension() {
+          if ext == "gguf" || ext == "bin" || ext == "pt" {
+            let name = p.file_name().unwrap().to_string_lossy().to_string();
+            if let Some((base, index, count)) = gguf_split::parse_split_name(&name) {
+              let group = shards.entry(base).or_insert((None, count, 0));
+              group.2 += 1;
+              if index == 1 {
+                group.0 = Some(p.clone());
+              }
+              continue;
             }
+            let id = p.file_stem().unwrap().to_string_lossy().to_string();
+            self.insert_model(id, name, &p, 1, true);
           }
-        } else if p.is_dir() {
-          // treat directory as model package
-          let id = p.file_name().unwrap().to_string_lossy().to_string();
-          let name = id.clone();
-          self.models.insert(
-            id.clone(),
-            ModelInfo { id, name, path: p.to_string_lossy().to_string(), loaded: false },
-          );
         }
+      } else if p.is_dir() {
+        // treat directory as model package
+        let id = p.file_name().unwrap().to_string_lossy().to_string();
+        let name = id.clone();
+        self.insert_model(id, name, &p, 1, true);
+      }
+    }
+
+    // one logical model per split set, pointing at the first shard (the runtime loads the rest)
+    let mut shards: Vec<_> = shards.into_iter().collect();
+    shards.sort_by(|a, b| a.0.cmp(&b.0));
+    for (base, (first, count, seen)) in shards {
+      if let Some(first) = first {
+        let name = format!("{} ({} parts)", base, count);
+        self.insert_model(base, name, &first, count, seen == count);
+      }
+    }
+  }
+
+  // ids stay plain in the first directory that has them; the same name in a later directory
+  // becomes "<id>@<folder name>"
+  fn unique_id(&self, id: String, path: &Path) -> String {
+    if !self.models.contains_key(&id) {
+      return id;
+    }
+    let folder = path.parent().and_then(|d| d.file_name()).map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| "models".into());
+    let base = format!("{}@{}", id, folder);
+    let mut candidate = base.clone();
+    let mut n = 2;
+    while self.models.contains_key(&candidate) {
+      candidate = format!("{}-{}", base, n);
+      n += 1;
+    }
+    candidate
+  }
+
+  fn insert_model(&mut self, id: String, name: String, path: &Path, parts: u32, complete: bool) {
+    let id = self.unique_id(id, path);
+    let loaded = self.loaded.as_deref() == Some(id.as_str());
+    let gguf = if path.extension().is_some_and(|e| e == "gguf") {
+      gguf::read_metadata(path).map_err(|e| events::log(format!("no GGUF metadata for {}: {}", id, e))).ok()
+    } else {
+      None
+    };
+    // the detected prompt format goes into the model's config, where the user can see what an
+    // override would replace
+    let detected = chat_template::detect(&name, gguf.as_ref());
+    if detected.is_some() && self.configs.get(&id).detected_template != detected {
+      if let Err(e) = self.configs.update(&id, |c| c.detected_template = detected) {
+        events::log(format!("failed to save the prompt format of {}: {}", id, e));
       }
     }
+    self.models.insert(
+      id.clone(),
+      ModelInfo {
+        id,
+        name,
+        path: path.to_string_lossy().to_string(),
+        dir: path.parent().map(|d| d.to_string_lossy().to_string()).unwrap_or_default(),
+        loaded,
+        parts,
+        complete,
+        license: license::detect(path),
+        duplicate_group: None,
+        speech: speech::is_whisper(path, gguf.as_ref()),
+        gguf,
+      },
+    );
   }
 
   fn list_models(&self) -> Vec<ModelInfo> {
     self.models.values().cloned().collect()
   }
 
+  // the loaded model, else the default model from the settings
+  fn default_model(&self) -> Option<String> {
+    self.loaded.clone().or_else(|| settings::get().default_model.filter(|id| self.models.contains_key(id)))
+  }
+
+  // model to use for a one-shot request: the given id or the default model, with its config
+  fn model_for_request(&self, id: Option<&str>) -> Result<(ModelInfo, ModelConfig), String> {
+    let id = match id.map(|s| s.to_string()).or(self.default_model()) {
+      Some(id) => id,
+      None => return Err("no model available to run prompt".into()),
+    };
+    match self.models.get(&id) {
+      Some(m) if m.speech => Err(format!("'{}' is a speech-to-text model; use transcribe_audio", id)),
+      Some(m) => Ok((m.clone(), self.configs.get(&id))),
+      None => Err(format!("Model '{}' not found", id)),
+    }
+  }
+
+  // context window of a model (or the loaded model) in tokens
+  fn context_size(&self, id: Option<&str>) -> u32 {
+    let config = id.map(|s| s.to_string()).or(self.default_model()).map(|id| self.configs.get(&id)).unwrap_or_default();
+    engine::context_size(&config)
+  }
+
   fn set_loaded(&mut self, id: &str) {
     if let Some(m) = self.models.get_mut(id) {
       m.loaded = true;
@@ -83,227 +386,1039 @@ impl ModelManager {
     }
   }
 
-  fn clear_loaded(&mut self) {
-    if let Some(id) = &self.loaded {
-      if let Some(m) = self.models.get_mut(id) {
-        m.loaded = false;
-      }
+  // the model and config a process for `id` would start with, when none is running
+  fn launch_target(&mut self, id: &str) -> Result<(ModelInfo, ModelConfig), String> {
+    self.reap_exited();
+    if self.processes.contains_key(id) {
+      return Err(format!("Model '{}' is already running", id));
+    }
+    let Some(model) = self.models.get(id).cloned() else {
+      return Err(format!("Model '{}' not found", id));
+    };
+    if model.speech {
+      return Err(format!("'{}' is a speech-to-text model; use transcribe_audio", id));
     }
-    self.loaded = None;
+    Ok((model, self.configs.get(id)))
   }
 
-  // spawn a child process (mock or real). returns Err(msg) on failure
-  fn spawn_for_model(&mut self, window: &Window, id: &str) -> Result<(), String> {
-    if self.process.is_some() {
-      return Err("A model process is already running".into());
+  // spawn the process prepared by prepare_launch; returns the request id its first output is tagged with
+  fn spawn_prepared(&mut self, window: &Window, id: &str, config: &ModelConfig, launch: Launch) -> Result<u64, String> {
+    self.reap_exited();
+    // started by someone else while this one was being prepared
+    if self.processes.contains_key(id) {
+      return Err(format!("Model '{}' is already running", id));
     }
+    let Launch { command: mut c, backend, server } = launch;
 
-    // get model info
-    let model = if let Some(m) = self.models.get(id) {
-      m.clone()
-    } else {
-      return Err(format!("Model '{}' not found", id));
+    // the process is driven by the async runtime, also when this runs on a plain thread
+    let _runtime = tauri::async_runtime::handle().inner().enter();
+    // prompts after the first go to the running process over stdin (see run_prompt), or over the
+    // data socket of a wrapper that asked for sockets
+    let endpoints = match config.transport.unwrap_or_default() {
+      Transport::Stdio => None,
+      Transport::Socket if backend == Backend::Wrapper => Some(Endpoints::bind()?),
+      Transport::Socket => return Err(format!("'{}' doesn't run with a run.sh/run.bat wrapper, which the socket transport needs", id)),
     };
+    c.stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped());
+    if let Some(endpoints) = &endpoints {
+      endpoints.apply(&mut c);
+      c.stdin(Stdio::null());
+    }
+    shutdown::prepare(&mut c);
+    storage::apply(&mut c);
+    let mut c = tokio::process::Command::from(c);
+    // a process left behind when the manager lets go of it doesn't outlive the app
+    c.kill_on_drop(true);
+    match c.spawn() {
+      Ok(mut child) => {
+        let pid = child.id().unwrap_or_default();
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        // the server's stdout is logging, its tokens come over HTTP; likewise with sockets
+        let logs_only = backend == Backend::Server || endpoints.is_some();
+
+        // store child in manager
+        let mut connecting = None;
+        if let Some(endpoints) = endpoints {
+          let (input, input_slot) = transport::pending();
+          let (control, control_slot) = transport::pending();
+          self.inputs.insert(id.to_string(), input);
+          self.controls.insert(id.to_string(), control);
+          connecting = Some(endpoints.accept(input_slot, control_slot));
+        } else if let Some(stdin) = child.stdin.take() {
+          self.inputs.insert(id.to_string(), transport::ready(stdin));
+        }
+        self.processes.insert(id.to_string(), child);
+        procstats::track(id, pid);
+        let protocol = config.protocol.unwrap_or(StdinProtocol::default_for(backend));
+        let reverse_prompt = (protocol == StdinProtocol::LlamaInteractive)
+          .then(|| config.reverse_prompt.clone().unwrap_or(protocol::DEFAULT_REVERSE_PROMPT.to_string()));
+        let stream_config = StreamConfig {
+          reverse_prompt,
+          strip_echo: backend.echoes_prompt(),
+          encoding: config.encoding.unwrap_or_default(),
+          messages: backend.structured_output(protocol),
+        };
+        let stream = TokenStream::new(id, stream_config);
+        let request_id = stream.begin();
+        self.streams.insert(id.to_string(), stream.clone());
+        self.protocols.insert(id.to_string(), protocol);
+        if let Some(server) = server {
+          server.watch_health(window);
+          self.servers.insert(id.to_string(), server);
+        }
+
+        // runtime logs go out as they come, not after the process ends
+        let (w, err_stream) = (window.clone(), stream.clone());
+        tauri::async_runtime::spawn(async move {
+          if let Some(err) = stderr {
+            err_stream.pump_logs(&w, "stderr", err).await;
+          }
+        });
+
+        // answers come over the data socket once the runtime has connected
+        if let Some(connecting) = connecting {
+          let (w, data_stream) = (window.clone(), stream.clone());
+          tauri::async_runtime::spawn(async move {
+            match connecting.await {
+              Ok(channels) => {
+                let (cw, control_stream) = (w.clone(), data_stream.clone());
+                tauri::async_runtime::spawn(async move { control_stream.pump_logs(&cw, "control", channels.control).await });
+                data_stream.pump_stdout(&w, channels.data, |line| bidi_line(&w, line)).await;
+              }
+              Err(e) => data_stream.stderr_line(&w, &e),
+            }
+          });
+        }
 
-    // Decide how to spawn:
-    // - For dev/demo: spawn a tiny cross-platform python mock that prints tokens slowly
-    // - For real usage: replace this block with the command to run your runtime (llama.cpp, whisper, etc.)
-    #[cfg(target_os = "windows")]
-    let mock_cmd = vec!["/C".to_string(), format!("python -u -c \"import time; [print('TOKEN', i) or time.sleep(0.12) for i in range(200)]\"")];
-    #[cfg(not(target_os = "windows"))]
-    let mock_cmd = vec!["-c".to_string(), "python3 -u -c 'import time\nfor i in range(200):\n print(f\"TOKEN {i}\")\n time.sleep(0.12)'\n".to_string()];
-
-    // Actual real-world example: to use llama.cpp CLI you might run:
-    // let exe = "./bin/llama.exe"; // or path to binary
-    // let args = vec!["-m", &model.path, "--stream"];
-    // Here we implement a simple fallback: if there's a runner script inside the model folder, run it.
-    let mut command_opt: Option<Command> = None;
-
-    // try: ./models/<id>/run.sh or run.bat (packagers often include a wrapper)
-    let model_dir = PathBuf::from(&model.path);
-    if model_dir.is_dir() {
-      let run_sh = model_dir.join("run.sh");
-      let run_bat = model_dir.join("run.bat");
-      if run_sh.exists() {
-        let mut c = if cfg!(target_os = "windows") { Command::new("sh") } else { Command::new("sh") };
-        c.arg(run_sh.to_string_lossy().to_string());
-        command_opt = Some(c);
-      } else if run_bat.exists() {
-        let mut c = Command::new("cmd");
-        c.arg("/C").arg(run_bat.to_string_lossy().to_string());
-        command_opt = Some(c);
+        // clone window for event emission
+        let w = window.clone();
+        let model_id = id.to_string();
+
+        // read stdout and emit tokens
+        tauri::async_runtime::spawn(async move {
+          match stdout {
+            Some(out) if logs_only => stream.pump_logs(&w, "stdout", out).await,
+            Some(out) => stream.pump_stdout(&w, out, |line| bidi_line(&w, line)).await,
+            None => {}
+          }
+          stream.closed(&w);
+          // notify frontend that process stopped
+          let _ = w.emit("model-status", serde_json::json!({"model_id": model_id, "running": false}));
+          watchdog::process_ended(&w, &model_id, pid, &stream).await;
+        });
+
+        // signal started
+        let _ = window.emit("model-status", serde_json::json!({"model_id": id, "running": true}));
+        Ok(request_id)
       }
+      Err(e) => Err(format!("Failed to spawn child: {}", e)),
     }
+  }
 
-    // If no wrapper script, check for a single .exe in ./src-tauri/bin or ./bin
-    if command_opt.is_none() {
-      let local_exe = PathBuf::from("./src-tauri/bin/llama.exe");
-      if local_exe.exists() {
-        // example: llama.exe -m <model_path> --stream
-        let mut c = Command::new(local_exe);
-        c.args(["-m", &model.path, "--stream"]);
-        command_opt = Some(c);
-      }
+  // forget processes that have exited on their own
+  fn reap_exited(&mut self) {
+    self.processes.retain(|_, child| matches!(child.try_wait(), Ok(None)));
+    let processes = &self.processes;
+    self.inputs.retain(|id, _| processes.contains_key(id));
+    self.controls.retain(|id, _| processes.contains_key(id));
+    self.streams.retain(|id, _| processes.contains_key(id));
+    self.protocols.retain(|id, _| processes.contains_key(id));
+    self.servers.retain(|id, _| processes.contains_key(id));
+  }
+
+  // ids of the models with a running process
+  fn running_models(&mut self) -> Vec<String> {
+    self.reap_exited();
+    let mut ids: Vec<String> = self.processes.keys().cloned().collect();
+    ids.sort();
+    ids
+  }
+
+  // which running process a request without a model id means: the loaded model's, or the only one
+  fn resolve_process(&self, id: Option<&str>) -> Result<String, String> {
+    if let Some(id) = id {
+      return if self.processes.contains_key(id) { Ok(id.to_string()) } else { Err(format!("Model '{}' is not running", id)) };
+    }
+    if let Some(loaded) = self.loaded.as_ref().filter(|l| self.processes.contains_key(*l)) {
+      return Ok(loaded.clone());
     }
+    match self.processes.len() {
+      0 => Err("No running process".into()),
+      1 => Ok(self.processes.keys().next().cloned().unwrap_or_default()),
+      _ => Err("Several models are running; say which one".into()),
+    }
+  }
 
-    // fallback to the python mock if nothing else found (this will work on dev machines with python)
-    if command_opt.is_none() {
-      // Use platform-safe approach to spawn the python mock via shell
-      if cfg!(target_os = "windows") {
-        let mut c = Command::new("cmd");
-        c.args(&mock_cmd);
-        c
-          .stdout(Stdio::piped())
-          .stderr(Stdio::piped());
-        command_opt = Some(c);
-      } else {
-        let mut c = Command::new("sh");
-        c.args(&mock_cmd);
-        c
-          .stdout(Stdio::piped())
-          .stderr(Stdio::piped());
-        command_opt = Some(c);
-      }
+  // take a running process out of the manager with the grace period configured for its model,
+  // so the (possibly slow) shutdown can happen without holding the lock
+  fn take_process(&mut self, id: Option<&str>) -> Result<(String, Child, Duration), String> {
+    let id = self.resolve_process(id)?;
+    let child = self.processes.remove(&id).ok_or("No running process")?;
+    self.inputs.remove(&id);
+    self.controls.remove(&id);
+    self.streams.remove(&id);
+    self.protocols.remove(&id);
+    self.servers.remove(&id);
+    let timeout = self.configs.get(&id).stop_timeout_ms.unwrap_or(shutdown::DEFAULT_STOP_TIMEOUT_MS);
+    Ok((id, child, Duration::from_millis(timeout)))
+  }
+
+  // where prompts to `id`'s running process are written
+  fn input(&self, id: &str) -> Result<SharedWriter, String> {
+    if !self.processes.contains_key(id) {
+      return Err(format!("Model '{}' is not running", id));
     }
+    self.inputs.get(id).cloned().ok_or("The runtime doesn't read stdin".into())
+  }
 
-    // now spawn
-    if let Some(mut c) = command_opt {
-      c.stdout(Stdio::piped()).stderr(Stdio::piped());
-      match c.spawn() {
-        Ok(mut child) => {
-          let stdout = child.stdout.take();
-let stderr = child.stderr.take();
-
-          // store child in manager
-          self.process = Some(child);
-
-          // clone window for event emission
-          let w = window.clone();
-
-          // spawn thread to read stdout and emit tokens
-          thread::spawn(move || {
-            use std::io::{BufRead, BufReader};
-            if let Some(out) = stdout {
-              let reader = BufReader::new(out);
-              for line in reader.lines().flatten() {
-                // emit token/line to frontend
-                let _ = w.emit("model-output", line.clone());
-              }
-            }
-            if let Some(err) = stderr {
-              let reader = BufReader::new(err);
-              for line in reader.lines().flatten() {
-                let _ = w.emit("model-output", format!("[ERR] {}", line));
-              }
-            }
-            // notify frontend that process stopped
-            let _ = w.emit("model-status", serde_json::json!({"running": false}));
-          });
+  // a prompt for the running process of `id` in the protocol it speaks: where to write it (once
+  // the lock is released) and the text
+  fn encode_prompt(&self, window: &Window, id: &str, request_id: u64, prompt: &str, sampling: &SamplingParams) -> Result<(SharedWriter, String), String> {
+    let protocol = self.protocols.get(id).copied().unwrap_or(StdinProtocol::Raw);
+    let input = self.input(id)?;
+    let text = protocol.encode(request_id, prompt, sampling);
+    // registered before writing, so an echo printed right away is recognized
+    if let Some(stream) = self.streams.get(id) {
+      stream.expect_echo(&text);
+      stream.tap(window, "stdin", text.trim_end());
+    }
+    Ok((input, text))
+  }
 
-          // signal started
-          let _ = window.emit("model-status", serde_json::json!({"running": true}));
-          Ok(())
-        }
-        Err(e) => Err(format!("Failed to spawn child: {}", e)),
+  // a line for the process of `id` as is: no protocol, no request, no echo handling
+  fn encode_raw(&mut self, window: &Window, id: &str, line: &str) -> Result<(SharedWriter, String), String> {
+    self.reap_exited();
+    if self.servers.contains_key(id) {
+      return Err(format!("'{}' runs as llama-server, which takes no input on stdin", id));
+    }
+    let input = self.input(id)?;
+    if let Some(stream) = self.streams.get(id) {
+      stream.tap(window, "stdin", line);
+    }
+    Ok((input, format!("{}\n", line)))
+  }
+
+  // stop generating the answer to `request_id`, leaving its process running for the next prompt.
+  // Returns the model and, for runtimes interrupted over stdin or their control channel, what to
+  // write where
+  fn cancel_prompt(&mut self, window: &Window, request_id: u64) -> Result<(String, Option<(SharedWriter, String)>), String> {
+    self.reap_exited();
+    // closing the HTTP request is enough for llama-server to stop generating
+    if let Some((id, _)) = self.servers.iter().find(|(_, s)| s.cancel(request_id)) {
+      return Ok((id.clone(), None));
+    }
+    // a prompt still waiting its turn is just taken off the queue
+    if let Some((id, _)) = self.streams.iter().find(|(_, s)| s.cancel_queued(window, request_id)) {
+      return Ok((id.clone(), None));
+    }
+    let (id, stream) = self
+      .streams
+      .iter()
+      .find(|(_, s)| s.in_flight() == Some(request_id))
+      .map(|(id, s)| (id.clone(), s.clone()))
+      .ok_or(format!("No prompt {} in flight", request_id))?;
+    let child = self.processes.get(&id).ok_or("No running process")?;
+    let pending = match (self.controls.get(&id), self.configs.get(&id).interrupt) {
+      (Some(control), _) => Some((control.clone(), transport::cancel_message(request_id))),
+      (None, Some(sequence)) => Some((self.input(&id)?, sequence)),
+      (None, None) if self.protocols.get(&id) == Some(&StdinProtocol::LlamaInteractive) => {
+        shutdown::interrupt(child).ok_or("This runtime can't be interrupted; set an interrupt sequence in the model config")?;
+        None
       }
-    } else {
-      Err("No command to run".into())
+      (None, None) => return Err("This runtime can't be interrupted; set an interrupt sequence in the model config".into()),
+    };
+    stream.cancel(window);
+    Ok((id, pending))
+  }
+}
+
+// Shared state wrapper for Tauri
+
+// ------------------ Tauri commands ------------------
+
+#[tauri::command]
+fn list_models(locale: Option<String>, state: tauri::State<'_, Mutex<ModelManager>>) -> Vec<ModelInfo> {
+  let mgr = state.locked();
+  sorted_models(mgr.list_models(), locale.as_deref())
+}
+
+#[tauri::command(async)]
+fn rescan_models(locale: Option<String>, state: tauri::State<'_, Mutex<ModelManager>>) -> Vec<ModelInfo> {
+  let mut mgr = state.locked();
+  mgr.scan_models();
+  sorted_models(mgr.list_models(), locale.as_deref())
+}
+
+// by name in the order of `locale` (the UI language when unset), ids breaking ties
+fn sorted_models(mut models: Vec<ModelInfo>, locale: Option<&str>) -> Vec<ModelInfo> {
+  models.sort_by(|a, b| a.id.cmp(&b.id));
+  collate::sort_by(&mut models, locale, |m| &m.name);
+  models
+}
+
+// What to run for a model, decided without the manager lock
+struct Launch {
+  command: Command,
+  backend: Backend,
+  server: Option<Arc<LlamaServer>>,
+}
+
+// pick the command for `model`: its wrapper script, a bundled runtime or the mock. Choosing the
+// runtime may benchmark it and the memory check reads the model, so this runs off the manager lock.
+// `sampling` overrides the model's defaults for as long as a CLI runtime runs
+fn prepare_launch(window: &Window, model: &ModelInfo, config: &ModelConfig, sampling: &SamplingParams) -> Result<Launch, String> {
+  // Decide how to spawn:
+  // - For dev/demo: spawn a tiny cross-platform python mock that prints tokens slowly
+  // - For real usage: replace this block with the command to run your runtime (llama.cpp, whisper, etc.)
+  // Actual real-world example: to use llama.cpp CLI you might run:
+  // let exe = "./bin/llama.exe"; // or path to binary
+  // let args = vec!["-m", &model.path, "--stream"];
+  // Here we implement a simple fallback: if there's a runner script inside the model folder, run it.
+  let mut command_opt: Option<(Command, Backend)> = None;
+  let id = model.id.as_str();
+  let sampling = sampling.or(&config.sampling);
+
+  // try: ./models/<id>/run.sh or run.bat (packagers often include a wrapper)
+  let model_dir = PathBuf::from(&model.path);
+  if model_dir.is_dir() {
+    let run_sh = model_dir.join("run.sh");
+    let run_bat = model_dir.join("run.bat");
+    if run_sh.exists() {
+      let mut c = Command::new("sh");
+      c.arg(run_sh.to_string_lossy().to_string());
+      command_opt = Some((c, Backend::Wrapper));
+    } else if run_bat.exists() {
+      let mut c = Command::new("cmd");
+      c.arg("/C").arg(run_bat.to_string_lossy().to_string());
+      command_opt = Some((c, Backend::Wrapper));
+    }
+    if let (Some((c, _)), false) = (command_opt.as_mut(), sampling.is_empty()) {
+      c.env("MULTILINGUAL_SAMPLING", serde_json::to_string(&sampling).unwrap_or_default());
     }
   }
 
-  fn stop_process(&mut self) -> Result<(), String> {
-    if let Some(mut child) = self.process.take() {
-      // try kill gracefully
-      match child.kill() {
-        Ok(_) => {
-          let _ = child.wait();
-          Ok(())
+  // If no wrapper script, use a bundled runtime from ./src-tauri/bin (cuda/vulkan/sycl/cpu builds or llama.exe)
+  let mut server = None;
+  if command_opt.is_none() {
+    if let Some(rt) = runtime::select_runtime(&model.path) {
+      events::log(format!("runtime = {:?} ({})", rt.variant, rt.reason));
+      let server_exe = server::server_exe(&rt.exe).filter(|_| config.server.unwrap_or(true));
+      let ctx = engine::context_size(config) * if server_exe.is_some() { server::SLOTS } else { 1 };
+      let estimate = memory::estimate(id, &model.path, model.gguf.as_ref(), config, rt.offload, ctx);
+      if let Some(message) = memory::preflight(&estimate)? {
+        let _ = window.emit("model-warning", ModelWarning { model_id: id.to_string(), message });
+      }
+      let (mut c, backend) = match server_exe {
+        // llama-server on a free local port, with slots for concurrent prompts
+        Some(exe) => {
+          let port = server::free_port()?;
+          let mut c = Command::new(exe);
+          c.args(["-m", &model.path, "--host", "127.0.0.1", "--port", &port.to_string()]);
+          c.args(["-np".to_string(), server::SLOTS.to_string(), "-c".to_string(), ctx.to_string()]);
+          server = Some(LlamaServer::new(id, &model.path, port));
+          (c, Backend::Server)
+        }
+        // example: llama.exe -m <model_path> --stream
+        None => {
+          let mut c = Command::new(&rt.exe);
+          c.args(["-m", &model.path, "--stream"]);
+          let protocol = config.protocol.unwrap_or(StdinProtocol::default_for(Backend::Runtime));
+          c.args(protocol.runtime_args(config.reverse_prompt.as_deref()));
+          // the server samples per request instead
+          c.args(sampling.runtime_args());
+          if let Some(n) = sampling.max_tokens {
+            c.args(["-n".to_string(), n.to_string()]);
+          }
+          (c, Backend::Runtime)
         }
-        Err(e) => Err(format!("Failed to kill process: {}", e)),
+      };
+      c.args(engine::runtime_args(&model.path, config, rt.offload));
+      // prompts keep going to the same process, so a long conversation would fill its context
+      if config.context_shift.unwrap_or(true) {
+        c.arg("--context-shift");
       }
-    } else {
-      Err("No running process".into())
+      if let Some(split) = &config.gpu {
+        c.args(split.runtime_args());
+      }
+      // smaller batches while the machine is under memory/thermal pressure
+      if let Some(batch) = window.state::<Throttle>().batch_size() {
+        c.args(["-b".to_string(), batch.to_string()]);
+      }
+      command_opt = Some((c, backend));
     }
   }
+
+  // fallback to the python mock if nothing else found (this will work on dev machines with python)
+  let (command, backend) = command_opt.unwrap_or_else(|| (mock_command(), Backend::Mock));
+  Ok(Launch { command, backend, server })
 }
 
-// Shared state wrapper for Tauri
+// start `id`'s process with the lock taken only around the bookkeeping; returns the request id its
+// first output is tagged with. Blocks, so async callers go through start_process_async
+fn start_process(window: &Window, id: &str, sampling: &SamplingParams) -> Result<u64, String> {
+  let state = window.state::<Mutex<ModelManager>>();
+  let (model, config) = state.locked().launch_target(id)?;
+  let launch = prepare_launch(window, &model, &config, sampling)?;
+  let request_id = state.locked().spawn_prepared(window, id, &config, launch);
+  request_id
+}
 
-// ------------------ Tauri commands ------------------
+async fn start_process_async(window: &Window, id: &str, sampling: &SamplingParams) -> Result<u64, String> {
+  let (window, id, sampling) = (window.clone(), id.to_string(), sampling.clone());
+  tauri::async_runtime::spawn_blocking(move || start_process(&window, &id, &sampling)).await.map_err(|e| e.to_string())?
+}
 
 #[tauri::command]
-fn list_models(state: tauri::State<'_, Mutex<ModelManager>>) -> Vec<ModelInfo> {
-  let mgr = state.lock().unwrap();
-  mgr.list_models()
+fn load_model(id: String, app: tauri::AppHandle, state: tauri::State<'_, Mutex<ModelManager>>) -> Result<(), AppError> {
+  state.locked().set_loaded(&id);
+  license::warn_for(&app, &id);
+  chat_template::warn_for(&app, &id);
+  Ok(())
 }
 
 #[tauri::command]
-fn rescan_models(state: tauri::State<'_, Mutex<ModelManager>>) -> Vec<ModelInfo> {
-  let mut mgr = state.lock().unwrap();
-  mgr.scan_models();
-  mgr.list_models()
+async fn start_model(id: String, window: Window) -> Result<(), AppError> {
+  start_process_async(&window, &id, &SamplingParams::default()).await?;
+  license::warn_for(window.app_handle(), &id);
+  chat_template::warn_for(window.app_handle(), &id);
+  Ok(())
+}
+
+// ask the runtime to exit (SIGTERM / CTRL_BREAK) and kill it only after its grace period;
+// "model-status" reports which path was taken
+#[tauri::command]
+async fn stop_model(id: Option<String>, window: Window, state: tauri::State<'_, Mutex<ModelManager>>) -> Result<StopReport, AppError> {
+  let (id, mut child, timeout) = state.locked().take_process(id.as_deref())?;
+  let report = shutdown::terminate(&id, &mut child, timeout).await?;
+  let _ = window.emit("model-status", report.clone());
+  Ok(report)
 }
 
+// interrupt one prompt started with run_prompt (by the id it returned); the model stays loaded
 #[tauri::command]
-fn load_model(id: String, state: tauri::State<'_, Mutex<ModelManager>>) -> Result<(), String> {
-  let mut mgr = state.lock().unwrap();
-  mgr.set_loaded(&id);
+async fn cancel_prompt(request_id: u64, window: Window, state: tauri::State<'_, Mutex<ModelManager>>) -> Result<(), AppError> {
+  let (id, pending) = state.locked().cancel_prompt(&window, request_id)?;
+  if let Some((writer, text)) = pending {
+    transport::send(&writer, &text).await?;
+  }
+  events::log(format!("prompt {} on {} cancelled", request_id, id));
   Ok(())
 }
 
+// developer console: write a line straight to a running runtime's stdin (needs the dev_console
+// setting); what comes back shows up in "runtime-raw" events
 #[tauri::command]
-fn start_model(id: String, window: Window, state: tauri::State<'_, Mutex<ModelManager>>) -> Result<(), String> {
-  let mut mgr = state.lock().unwrap();
-  mgr.spawn_for_model(&window, &id)
+async fn send_raw(model_id: String, line: String, window: Window, state: tauri::State<'_, Mutex<ModelManager>>) -> Result<(), AppError> {
+  if !settings::get().dev_console {
+    return Err(AppError::InvalidInput("Turn on the developer console in the settings first".into()));
+  }
+  if line.contains('\n') {
+    return Err(AppError::InvalidInput("Send one line at a time".into()));
+  }
+  let (input, text) = state.locked().encode_raw(&window, &model_id, &line)?;
+  transport::send(&input, &text).await?;
+  Ok(())
 }
 
 #[tauri::command]
-fn stop_model(state: tauri::State<'_, Mutex<ModelManager>>) -> Result<(), String> {
-  let mut mgr = state.lock().unwrap();
-  mgr.stop_process()
+fn list_running_models(state: tauri::State<'_, Mutex<ModelManager>>) -> Vec<String> {
+  state.locked().running_models()
 }
 
+// `sampling` overrides the model's defaults for this prompt; a CLI runtime that is already
+// running keeps the sampling it was started with unless it speaks JSON lines. A process answers
+// one prompt at a time: prompts sent while it generates wait their turn, `priority` first
+// (interactive by default), and "queue-position" events tell where they stand. A runtime that
+// never reports the end of an answer holds the queue up until the prompt is cancelled
 #[tauri::command]
-fn run_prompt(
+async fn run_prompt(
   prompt: String,
   model: Option<String>,
+  sampling: Option<SamplingParams>,
+  priority: Option<Priority>,
   window: Window,
   state: tauri::State<'_, Mutex<ModelManager>>
-) -> Result<(), String> {
-  let mut mgr = state.lock().unwrap();
-  // ... rest of function body unchanged ...
-  if let Some(child) = mgr.process.as_mut() {
-    if let Some(mut stdin) = child.stdin.as_mut() {
-      if let Err(e) = writeln!(stdin, "{}", prompt) {
-        return Err(format!("failed to write to stdin: {}", e));
+) -> Result<u64, AppError> {
+  if window.state::<Throttle>().is_paused() {
+    return Err(AppError::Busy("generation paused: system is under critical memory/thermal pressure".into()));
+  }
+  let overrides = sampling.unwrap_or_default();
+  overrides.validate()?;
+  let priority = priority.unwrap_or(Priority::Interactive);
+  let (id, running) = {
+    let mut mgr = state.locked();
+    // route to the requested model's process, or the loaded/only running one
+    mgr.reap_exited();
+    let target = match model {
+      Some(id) => Some(id),
+      None => mgr.resolve_process(None).ok().or(mgr.loaded.clone()),
+    };
+    let Some(id) = target else {
+      return Err("no model available to run prompt".into());
+    };
+    let running = mgr.processes.contains_key(&id);
+    (id, running)
+  };
+  // the new process answers this prompt first; it is started without holding the lock
+  let started = match running {
+    true => None,
+    false => match start_process_async(&window, &id, &overrides).await {
+      Ok(request_id) => Some(request_id),
+      // another prompt started it in the meantime; this one queues behind it
+      Err(_) if state.locked().processes.contains_key(&id) => None,
+      Err(e) => return Err(e.into()),
+    },
+  };
+  let (request_id, stream, prompt, sampling, queued) = {
+    let mgr = state.locked();
+    let queued = started.is_none();
+    let request_id = match started {
+      Some(request_id) => request_id,
+      None => {
+        let fixed = !mgr.servers.contains_key(&id) && mgr.protocols.get(&id) != Some(&StdinProtocol::JsonLines);
+        if fixed && !overrides.is_empty() {
+          events::log(format!("{} is already running; sampling overrides apply when it is restarted", id));
+        }
+        stream::next_request_id()
+      }
+    };
+    let sampling = overrides.or(&mgr.configs.get(&id).sampling);
+    // lay the prompt out as a user turn in the model's chat format
+    let config = mgr.configs.get(&id);
+    let template = chat_template::resolve(&config, mgr.models.get(&id).and_then(|m| m.gguf.as_ref()));
+    let turns: Vec<Turn> = system_prompt::system_turn(&[config.system_prompt]).into_iter().chain([Turn::new("user", &prompt)]).collect();
+    let prompt = template.template.format(&turns);
+    // llama-server takes each prompt as its own HTTP request, several at a time
+    if let Some(server) = mgr.servers.get(&id) {
+      return Ok(server.complete(&window, prompt, sampling, priority));
+    }
+    let stream = mgr.streams.get(&id).cloned().ok_or("No running process")?;
+    if queued {
+      stream.enqueue(&window, request_id, priority);
+    }
+    (request_id, stream, prompt, sampling, queued)
+  };
+  if !queued || stream.take_turn(&window, request_id) {
+    if let Err(e) = send_prompt(&window, &id, request_id, &prompt, &sampling, priority).await {
+      stream.error(&window, &e);
+      return Err(e.into());
+    }
+    return Ok(request_id);
+  }
+  // the process is still answering an earlier prompt; this one is written once its turn comes
+  tauri::async_runtime::spawn(async move {
+    if stream.wait_turn(&window, request_id).await {
+      if let Err(e) = send_prompt(&window, &id, request_id, &prompt, &sampling, priority).await {
+        stream.error(&window, &e);
       }
-      return Ok(());
     }
+  });
+  Ok(request_id)
+}
+
+// write prompt `request_id` to `id`'s process once it is its turn; other models aren't held up.
+// Until it is answered, generations of the model at a lower priority wait
+async fn send_prompt(window: &Window, id: &str, request_id: u64, prompt: &str, sampling: &SamplingParams, priority: Priority) -> Result<(), String> {
+  let (input, text) = {
+    let mgr = window.state::<Mutex<ModelManager>>();
+    let mgr = mgr.locked();
+    if let (Some(model), Some(stream)) = (mgr.models.get(id), mgr.streams.get(id)) {
+      stream.hold(dispatch::streaming(&model.path, priority));
+    }
+    mgr.encode_prompt(window, id, request_id, prompt, sampling)?
+  };
+  transport::send(&input, &text).await
+}
+
+#[tauri::command]
+fn list_gpu_devices() -> Vec<GpuDevice> {
+  gpu::detect_devices()
+}
+
+#[tauri::command]
+fn get_model_gpu_split(id: String, state: tauri::State<'_, Mutex<ModelManager>>) -> Option<GpuSplit> {
+  let mgr = state.locked();
+  mgr.configs.get(&id).gpu
+}
+
+// validate against the detected devices before persisting; `None` clears the override
+#[tauri::command]
+fn set_model_gpu_split(
+  id: String,
+  split: Option<GpuSplit>,
+  state: tauri::State<'_, Mutex<ModelManager>>
+) -> Result<(), AppError> {
+  if let Some(s) = &split {
+    s.validate(&gpu::detect_devices())?;
   }
+  let mut mgr = state.locked();
+  if !mgr.models.contains_key(&id) {
+    return Err(AppError::not_found("Model", &id));
+  }
+  Ok(mgr.configs.update(&id, |c| c.gpu = split)?)
+}
+
+#[tauri::command]
+fn get_model_gpu_layers(id: String, state: tauri::State<'_, Mutex<ModelManager>>) -> Option<u32> {
+  state.locked().configs.get(&id).gpu_layers
+}
+
+// layers of this model offloaded to the GPU, 0 for CPU only; `None` goes back to the gpu_layers
+// setting or fitting the model to the VRAM. Takes effect the next time the model is started
+#[tauri::command]
+fn set_model_gpu_layers(id: String, layers: Option<u32>, state: tauri::State<'_, Mutex<ModelManager>>) -> Result<(), AppError> {
+  let mut mgr = state.locked();
+  if !mgr.models.contains_key(&id) {
+    return Err(AppError::not_found("Model", &id));
+  }
+  Ok(mgr.configs.update(&id, |c| c.gpu_layers = layers)?)
+}
 
-  if let Some(id) = model.or(mgr.loaded.clone()) {
-    mgr.spawn_for_model(&window, &id)
-  } else {
-    Err("no model available to run prompt".into())
+// `None` restores the default grace period
+#[tauri::command]
+fn set_model_stop_timeout(id: String, timeout_ms: Option<u64>, state: tauri::State<'_, Mutex<ModelManager>>) -> Result<(), AppError> {
+  let mut mgr = state.locked();
+  if !mgr.models.contains_key(&id) {
+    return Err(AppError::not_found("Model", &id));
   }
+  Ok(mgr.configs.update(&id, |c| c.stop_timeout_ms = timeout_ms)?)
 }
 
+// whether the running model shifts its context when it fills; `None` restores the default (on).
+// Takes effect the next time the model is started
+#[tauri::command]
+fn set_model_context_shift(id: String, enabled: Option<bool>, state: tauri::State<'_, Mutex<ModelManager>>) -> Result<(), AppError> {
+  let mut mgr = state.locked();
+  if !mgr.models.contains_key(&id) {
+    return Err(AppError::not_found("Model", &id));
+  }
+  Ok(mgr.configs.update(&id, |c| c.context_shift = enabled)?)
+}
+
+// whether a model's run.sh/run.bat wrapper talks over stdin/stdout or sockets (see
+// transport::Transport); `None` goes back to stdin/stdout. Takes effect the next time the model is started
+#[tauri::command]
+fn set_model_transport(id: String, transport: Option<Transport>, state: tauri::State<'_, Mutex<ModelManager>>) -> Result<(), AppError> {
+  let mut mgr = state.locked();
+  if !mgr.models.contains_key(&id) {
+    return Err(AppError::not_found("Model", &id));
+  }
+  Ok(mgr.configs.update(&id, |c| c.transport = transport)?)
+}
+
+// character encoding of a model's runtime output; `None` goes back to auto-detection.
+// Takes effect the next time the model is started
+#[tauri::command]
+fn set_model_encoding(id: String, encoding: Option<OutputEncoding>, state: tauri::State<'_, Mutex<ModelManager>>) -> Result<(), AppError> {
+  let mut mgr = state.locked();
+  if !mgr.models.contains_key(&id) {
+    return Err(AppError::not_found("Model", &id));
+  }
+  Ok(mgr.configs.update(&id, |c| c.encoding = encoding)?)
+}
+
+#[tauri::command]
+fn get_model_sampling(id: String, state: tauri::State<'_, Mutex<ModelManager>>) -> SamplingParams {
+  state.locked().configs.get(&id).sampling
+}
+
+// sampling defaults for every prompt to this model; CLI runtimes pick them up on their next start
+#[tauri::command]
+fn set_model_sampling(id: String, sampling: SamplingParams, state: tauri::State<'_, Mutex<ModelManager>>) -> Result<(), AppError> {
+  sampling.validate()?;
+  let mut mgr = state.locked();
+  if !mgr.models.contains_key(&id) {
+    return Err(AppError::not_found("Model", &id));
+  }
+  Ok(mgr.configs.update(&id, |c| c.sampling = sampling)?)
+}
+
+#[tauri::command]
+fn get_model_restart_policy(id: String, state: tauri::State<'_, Mutex<ModelManager>>) -> RestartPolicy {
+  state.locked().configs.get(&id).restart.unwrap_or_default()
+}
+
+// what the watchdog does when this model's process crashes; `None` turns auto-restart off
+#[tauri::command]
+fn set_model_restart_policy(id: String, policy: Option<RestartPolicy>, state: tauri::State<'_, Mutex<ModelManager>>) -> Result<(), AppError> {
+  if let Some(policy) = &policy {
+    policy.validate()?;
+  }
+  let mut mgr = state.locked();
+  if !mgr.models.contains_key(&id) {
+    return Err(AppError::not_found("Model", &id));
+  }
+  Ok(mgr.configs.update(&id, |c| c.restart = policy)?)
+}
+
+// text llama.cpp's interactive mode prints when the model's turn is over; `None` restores "User:".
+// Takes effect the next time the model is started
+#[tauri::command]
+fn set_model_reverse_prompt(id: String, reverse_prompt: Option<String>, state: tauri::State<'_, Mutex<ModelManager>>) -> Result<(), AppError> {
+  if reverse_prompt.as_deref().is_some_and(|r| r.trim().is_empty()) {
+    return Err(AppError::InvalidInput("The reverse prompt is empty".into()));
+  }
+  let mut mgr = state.locked();
+  if !mgr.models.contains_key(&id) {
+    return Err(AppError::not_found("Model", &id));
+  }
+  Ok(mgr.configs.update(&id, |c| c.reverse_prompt = reverse_prompt)?)
+}
+
+// re-run the iGPU vs CPU benchmark with the given model and persist the winner for this machine
+#[tauri::command(async)]
+fn benchmark_runtimes(id: String, state: tauri::State<'_, Mutex<ModelManager>>) -> Result<runtime::BenchmarkResult, AppError> {
+  let path = {
+    let mgr = state.locked();
+    match mgr.models.get(&id) {
+      Some(m) => m.path.clone(),
+      None => return Err(AppError::not_found("Model", &id)),
+    }
+  };
+  Ok(runtime::benchmark_igpu(&path)?)
+}
+
+// quantize a .gguf model in the background; progress arrives as "quantize-progress" events
+#[tauri::command]
+fn quantize_model(
+  id: String,
+  target_quant: String,
+  app: tauri::AppHandle,
+  state: tauri::State<'_, Mutex<ModelManager>>
+) -> Result<String, AppError> {
+  let path = {
+    let mgr = state.locked();
+    match mgr.models.get(&id) {
+      Some(m) => m.path.clone(),
+      None => return Err(AppError::not_found("Model", &id)),
+    }
+  };
+  Ok(quantize::start(app, id, path, target_quant)?)
+}
+
+// merge a split GGUF into a single file
+#[tauri::command]
+fn merge_model(id: String, app: tauri::AppHandle, state: tauri::State<'_, Mutex<ModelManager>>) -> Result<String, AppError> {
+  let model = {
+    let mgr = state.locked();
+    mgr.models.get(&id).cloned().ok_or_else(|| AppError::not_found("Model", &id))?
+  };
+  if model.parts < 2 {
+    return Err(AppError::InvalidInput(format!("Model '{}' is not split", id)));
+  }
+  if !model.complete {
+    return Err(AppError::InvalidInput(format!("Model '{}' is missing some of its {} parts", id, model.parts)));
+  }
+  Ok(gguf_split::merge(app, id, &model.path)?)
+}
+
+// split a GGUF into shards no larger than `max_size` (e.g. "4G")
+#[tauri::command]
+fn split_model(
+  id: String,
+  max_size: String,
+  app: tauri::AppHandle,
+  state: tauri::State<'_, Mutex<ModelManager>>
+) -> Result<String, AppError> {
+  let model = {
+    let mgr = state.locked();
+    mgr.models.get(&id).cloned().ok_or_else(|| AppError::not_found("Model", &id))?
+  };
+  if model.parts > 1 {
+    return Err(AppError::InvalidInput(format!("Model '{}' is already split", id)));
+  }
+  Ok(gguf_split::split(app, id, &model.path, &max_size)?)
+}
 
 // ------------------ run ------------------
+// in kiosk mode reject every command outside the kiosk allowlist before it runs
+fn kiosk_guard<R: tauri::Runtime>(
+  handler: impl Fn(tauri::ipc::Invoke<R>) -> bool + Send + Sync + 'static
+) -> impl Fn(tauri::ipc::Invoke<R>) -> bool + Send + Sync + 'static {
+  move |invoke| {
+    let command = invoke.message.command().to_string();
+    if !kiosk::allows(&command) {
+      invoke.resolver.reject(format!("'{}' is not available in kiosk mode", command));
+      return true;
+    }
+    handler(invoke)
+  }
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-  let manager = Mutex::new(ModelManager::new());
-
+  let context = tauri::generate_context!();
+  // before the stores below read their files
+  store::init(&context.config().identifier);
+  instance::acquire();
+  backup::finish_restore();
+  integrity::check();
   tauri::Builder::default()
-    .manage(manager)
-    .invoke_handler(tauri::generate_handler![
+    .manage(Throttle::new())
+    .manage(Mutex::new(SessionStore::load()))
+    .manage(Mutex::new(Glossary::load()))
+    .manage(Mutex::new(Favorites::load()))
+    .manage(Mutex::new(Flashcards::load()))
+    .manage(Mutex::new(LocalizeSettings::load()))
+    .manage(Mutex::new(BidiSettings::load()))
+    .manage(Mutex::new(TranslationMemory::load()))
+    .manage(Mutex::new(SegmentStore::new()))
+    .manage(Mutex::new(JobStore::load()))
+    .manage(Mutex::new(InterpreterStore::new()))
+    .manage(Mutex::new(Phrasebooks::load()))
+    .manage(Mutex::new(QuizStore::new()))
+    .manage(Mutex::new(Rates::load()))
+    .manage(Mutex::new(WebProxy::new()))
+    .manage(Mutex::new(Scheduler::load()))
+    .manage(Mutex::new(FolderWatches::load()))
+    .manage(Mutex::new(Pipelines::load()))
+    .manage(Mutex::new(EvalReports::load()))
+    .manage(Mutex::new(ContentFilter::load()))
+    .manage(Mutex::new(LanShare::load()))
+    .manage(Mutex::new(SyncState::load()))
+    .manage(Mutex::new(Preloader::load()))
+    .manage(Mutex::new(DownloadManager::load()))
+    .manage(Mutex::new(Clarifications::new()))
+    .manage(Mutex::new(ModelWatcher::new()))
+    .manage(Mutex::new(OpenAiApi::new()))
+    .manage(Mutex::new(SystemPrompts::load()))
+    .manage(Mutex::new(Listeners::new()))
+    .manage(Mutex::new(Speakers::new()))
+    .setup(|app| {
+      // the models directory comes from the settings, so models are scanned once they are read
+      settings::init(app.handle());
+      let mut models = ModelManager::new();
+      if let Some(id) = kiosk::startup_model() {
+        models.set_loaded(&id);
+      }
+      app.manage(Mutex::new(models));
+      model_watch::restart(app.handle());
+      openai::start_enabled(app.handle());
+      events::init(app.handle());
+      throttle::start_monitor(app.handle().clone());
+      procstats::start(app.handle().clone());
+      // jobs marked running belong to the instance holding the data folder, as do the schedule and
+      // the watched folders
+      if !instance::read_only() {
+        schedule::start(app.handle().clone());
+        watch::start_all(app.handle());
+        jobs::resume_interrupted(app.handle());
+      }
+      backup::start();
+      if let Some(id) = kiosk::startup_model() {
+        license::warn_for(app.handle(), &id);
+        chat_template::warn_for(app.handle(), &id);
+      }
+      integrity::notify(app.handle());
+      instance::notify(app.handle());
+      Ok(())
+    })
+    .invoke_handler(kiosk_guard(tauri::generate_handler![
       list_models,
       rescan_models,
       load_model,
       start_model,
       stop_model,
-      run_prompt
-    ])
-    .run(tauri::generate_context!())
+      list_running_models,
+      run_prompt,
+      list_gpu_devices,
+      get_model_gpu_split,
+      set_model_gpu_split,
+      set_model_stop_timeout,
+      throttle::get_system_pressure,
+      throttle::get_throttle_policy,
+      throttle::set_throttle_policy,
+      runtime::list_runtimes,
+      runtime::get_runtime_benchmark,
+      benchmark_runtimes,
+      quantize::list_quant_types,
+      quantize_model,
+      merge_model,
+      split_model,
+      session::create_session,
+      session::get_context_usage,
+      session::send_message,
+      session::summarize_and_compact,
+      summarize::summarize,
+      glossary::list_glossary,
+      glossary::add_glossary_entry,
+      glossary::remove_glossary_entry,
+      favorites::list_favorites,
+      favorites::add_favorite,
+      favorites::remove_favorite,
+      flashcards::list_decks,
+      flashcards::build_deck_from_glossary,
+      flashcards::build_deck_from_favorites,
+      flashcards::get_due_cards,
+      flashcards::answer_card,
+      flashcards::delete_deck,
+      proofread::proofread,
+      simplify::simplify,
+      localize::localize_text,
+      localize::get_localize_config,
+      localize::set_localize_config,
+      bidi::apply_bidi,
+      bidi::get_bidi_config,
+      bidi::set_bidi_config,
+      confidence::generate_scored,
+      tm::list_tm_entries,
+      tm::lookup_tm,
+      tm::remove_tm_entry,
+      segments::register_segment,
+      segments::suggest_alternatives,
+      segments::accept_translation,
+      jobs::create_document_job,
+      jobs::list_jobs,
+      jobs::get_job,
+      jobs::delete_job,
+      jobs::machine_translate_job,
+      jobs::resume_job,
+      jobs::edit_segment,
+      jobs::set_segment_state,
+      jobs::export_job,
+      codeaware::translate_code_aware,
+      compose::compose,
+      interpreter::start_interpreter,
+      interpreter::interpreter_turn,
+      interpreter::get_interpreter_session,
+      interpreter::end_interpreter,
+      speech::get_speech_support,
+      phrasebook::list_phrasebooks,
+      phrasebook::get_phrases,
+      phrasebook::get_phrase_audio,
+      phrasebook::import_phrasebook,
+      phrasebook::remove_phrasebook,
+      quiz::generate_quiz,
+      quiz::get_quiz,
+      quiz::grade_quiz,
+      ocr::recognize_text_image,
+      convert::convert_units,
+      convert::get_exchange_rates,
+      convert::import_exchange_rates,
+      webproxy::start_web_proxy,
+      webproxy::get_proxied_url,
+      webproxy::stop_web_proxy,
+      schedule::list_schedules,
+      schedule::add_schedule,
+      schedule::set_schedule_enabled,
+      schedule::remove_schedule,
+      schedule::run_schedule_now,
+      schedule::get_schedule_history,
+      watch::watch_folder,
+      watch::list_watched_folders,
+      watch::unwatch_folder,
+      watch::get_watch_activity,
+      dispatch::get_dispatch_status,
+      pipeline::list_pipelines,
+      pipeline::save_pipeline,
+      pipeline::remove_pipeline,
+      pipeline::run_pipeline,
+      eval::evaluate_models,
+      eval::list_eval_reports,
+      eval::delete_eval_report,
+      selftest::run_self_test,
+      output::format_text,
+      annotate::annotate_text,
+      filter::get_filter_settings,
+      filter::get_filter_status,
+      filter::set_filter_settings,
+      filter::list_filter_lists,
+      kiosk::get_kiosk_status,
+      lan::start_lan_sharing,
+      lan::stop_lan_sharing,
+      lan::get_lan_status,
+      lan::create_pairing,
+      lan::revoke_lan_device,
+      lan::rotate_lan_token,
+      sync::create_sync_key,
+      sync::list_sync_devices,
+      sync::revoke_sync_key,
+      sync::export_sync_bundle,
+      sync::import_sync_bundle,
+      readaloud::read_aloud,
+      readable::make_readable,
+      session::close_session,
+      session::list_sessions,
+      events::get_event_verbosity,
+      events::set_event_verbosity,
+      license::get_usage_type,
+      license::set_usage_type,
+      dedup::deduplicate_models,
+      preload::preload_hint,
+      preload::get_preload_enabled,
+      preload::set_preload_enabled,
+      translate::get_pivot_language,
+      translate::set_pivot_language,
+      translate::fit_translation_length,
+      translate::translate_candidates,
+      download::download_model,
+      download::list_downloads,
+      download::pause_download,
+      download::resume_download,
+      download::remove_download,
+      terms::extract_terms,
+      align::align_documents,
+      quality::estimate_quality,
+      quality::get_quality_settings,
+      quality::set_quality_settings,
+      domain::list_domains,
+      domain::get_domain,
+      domain::apply_domain,
+      settings::get_settings,
+      settings::set_settings,
+      settings::list_model_dirs,
+      settings::add_model_dir,
+      settings::remove_model_dir,
+      clarify::translate_with_clarification,
+      clarify::provide_clarification,
+      cancel_prompt,
+      session_bundle::export_session_bundle,
+      session_bundle::import_session_bundle,
+      session::append_message,
+      session::delete_session,
+      chat_template::get_chat_template,
+      chat_template::set_chat_template,
+      set_model_reverse_prompt,
+      openai::get_openai_api_status,
+      openai::set_openai_api,
+      set_model_encoding,
+      get_model_sampling,
+      set_model_sampling,
+      system_prompt::list_system_prompts,
+      system_prompt::save_system_prompt,
+      system_prompt::delete_system_prompt,
+      system_prompt::set_system_prompt,
+      translate::translate,
+      runner::generate_runner,
+      lang::detect_language,
+      send_raw,
+      storage::get_storage_usage,
+      speech::transcribe_audio,
+      lang::list_languages,
+      audio::list_input_devices,
+      audio::start_listening,
+      audio::stop_listening,
+      integrity::get_integrity_report,
+      tts::list_voices,
+      tts::speak,
+      tts::stop_speaking,
+      backup::create_backup,
+      backup::list_backups,
+      backup::restore_backup,
+      get_model_restart_policy,
+      set_model_restart_policy,
+      instance::get_instance_status,
+      procstats::get_process_stats,
+      hardware::get_hardware_info,
+      get_model_gpu_layers,
+      set_model_gpu_layers,
+      modelcard::get_model_card,
+      memory::estimate_model_memory,
+      set_model_context_shift,
+      set_model_transport,
+      jobs::translate_file
+    ]))
+    .run(context)
     .expect("error while running tauri application");
 }
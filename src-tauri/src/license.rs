@@ -0,0 +1,155 @@
+// src-tauri/src/license.rs
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::{AppError, LockExt};
+use crate::kiosk;
+use crate::modelcard;
+use crate::store;
+use crate::ModelManager;
+
+// license ids (Hugging Face / SPDX style) that rule out commercial use
+const NON_COMMERCIAL: &[&str] = &["cc-by-nc", "non-commercial", "noncommercial", "research", "mrl", "academic", "llama2-nc"];
+// license ids known to allow commercial use (some with an acceptable use policy)
+const COMMERCIAL: &[&str] = &[
+  "apache-2.0", "mit", "bsd", "cc-by-4.0", "cc-by-sa", "cc0", "openrail", "llama2", "llama3", "gemma", "qwen", "unlicense", "gpl",
+  "lgpl", "mpl",
+];
+
+// What the model is used for, set once in settings
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageType {
+  #[default]
+  Personal,
+  Research,
+  Commercial,
+}
+
+// License of a model as recorded next to it
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct ModelLicense {
+  // "apache-2.0", "cc-by-nc-4.0", "other", ...
+  pub id: String,
+  // human readable name for "other" licenses
+  pub name: Option<String>,
+  // "manifest" | "huggingface"
+  pub source: String,
+  // None when the license isn't one we know
+  pub commercial_use: Option<bool>,
+}
+
+// Payload of "model-warning" events
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ModelWarning {
+  pub model_id: String,
+  pub message: String,
+}
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct LicenseSettings {
+  usage: UsageType,
+}
+
+fn settings_path() -> PathBuf {
+  store::data_file("license.json")
+}
+
+pub fn usage_type() -> UsageType {
+  store::load_json::<LicenseSettings>(&settings_path()).usage
+}
+
+fn commercial_use(id: &str, name: Option<&str>) -> Option<bool> {
+  let text = format!("{} {}", id, name.unwrap_or("")).to_lowercase();
+  if NON_COMMERCIAL.iter().any(|l| text.contains(l)) {
+    return Some(false);
+  }
+  COMMERCIAL.iter().any(|l| id.to_lowercase().starts_with(l)).then_some(true)
+}
+
+// `license:` and `license_name:` from the YAML front matter of a Hugging Face model card
+fn from_model_card(readme: &Path) -> Option<(String, Option<String>)> {
+  let text = fs::read_to_string(readme).ok()?;
+  let front = text.strip_prefix("---")?.split("\n---").next()?;
+  let value = |key: &str| {
+    front
+      .lines()
+      .find_map(|l| l.strip_prefix(key).and_then(|r| r.trim_start().strip_prefix(':')))
+      .map(|v| v.trim().trim_matches(['"', '\'']).to_string())
+      .filter(|v| !v.is_empty())
+  };
+  Some((value("license")?, value("license_name")))
+}
+
+// "license" (string, or {id, name}) from a manifest.json written by whoever packaged the model
+fn from_manifest(manifest: &Path) -> Option<(String, Option<String>)> {
+  let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(manifest).ok()?).ok()?;
+  match json.get("license")? {
+    serde_json::Value::String(id) => Some((id.clone(), None)),
+    obj => Some((obj.get("id")?.as_str()?.to_string(), obj.get("name").and_then(|n| n.as_str()).map(|n| n.to_string()))),
+  }
+}
+
+// license of a model folder or single file; single files use "<stem>.manifest.json" beside them,
+// and the model card wherever modelcard::find finds it
+pub fn detect(model_path: &Path) -> Option<ModelLicense> {
+  let manifest = if model_path.is_dir() {
+    model_path.join("manifest.json")
+  } else {
+    let stem = model_path.file_stem()?.to_string_lossy().to_string();
+    model_path.with_file_name(format!("{}.manifest.json", stem))
+  };
+  let (found, source) = match from_manifest(&manifest) {
+    Some(l) => (l, "manifest"),
+    None => (from_model_card(&modelcard::find(model_path)?)?, "huggingface"),
+  };
+  let (id, name) = found;
+  let commercial_use = commercial_use(&id, name.as_deref());
+  Some(ModelLicense { id, name, source: source.into(), commercial_use })
+}
+
+// why `license` doesn't fit how the app is used, if it doesn't
+pub fn conflict(license: Option<&ModelLicense>, usage: UsageType) -> Option<String> {
+  let public = kiosk::enabled();
+  if usage != UsageType::Commercial && !public {
+    return None;
+  }
+  let context = if usage == UsageType::Commercial { "commercial use" } else { "a public kiosk" };
+  match license {
+    Some(l) if l.commercial_use == Some(false) => {
+      Some(format!("License '{}' does not allow {}", l.name.as_deref().unwrap_or(&l.id), context))
+    }
+    Some(l) if l.commercial_use.is_none() => Some(format!("Check license '{}' before {}", l.name.as_deref().unwrap_or(&l.id), context)),
+    None if usage == UsageType::Commercial => Some("No license recorded for this model; check it before commercial use".into()),
+    _ => None,
+  }
+}
+
+// emit "model-warning" when the model's license conflicts with the configured usage
+pub fn warn_for(app: &AppHandle, model_id: &str) {
+  let license = app.state::<Mutex<ModelManager>>().locked().models.get(model_id).and_then(|m| m.license.clone());
+  if let Some(message) = conflict(license.as_ref(), usage_type()) {
+    let _ = app.emit("model-warning", ModelWarning { model_id: model_id.to_string(), message });
+  }
+}
+
+// ------------------ Tauri commands ------------------
+
+#[tauri::command]
+pub fn get_usage_type() -> UsageType {
+  usage_type()
+}
+
+// change the usage type and re-check the loaded model
+#[tauri::command]
+pub fn set_usage_type(usage: UsageType, app: AppHandle) -> Result<(), AppError> {
+  store::save_json(&settings_path(), &LicenseSettings { usage })?;
+  let loaded = app.state::<Mutex<ModelManager>>().locked().loaded.clone();
+  if let Some(id) = loaded {
+    warn_for(&app, &id);
+  }
+  Ok(())
+}
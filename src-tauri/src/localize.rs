@@ -0,0 +1,370 @@
+// src-tauri/src/localize.rs
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+use regex::{Captures, Regex};
+
+use crate::convert::{self, RateTable, Rates};
+use crate::error::{AppError, LockExt};
+use crate::store;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum DateOrder {
+  Dmy,
+  Mdy,
+  Ymd,
+}
+
+// Number/date/currency formatting rules of a locale
+#[derive(Clone, Copy, Debug)]
+struct Conventions {
+  decimal: char,
+  group: char,
+  date_order: DateOrder,
+  date_sep: char,
+  // "$5" vs "5 $"
+  currency_prefix: bool,
+}
+
+fn conventions(locale: &str) -> Conventions {
+  let lower = locale.to_lowercase().replace('_', "-");
+  let base = lower.split('-').next().unwrap_or("");
+  let (decimal, group) = match base {
+    "de" | "es" | "it" | "nl" | "id" | "tr" | "pt" | "da" | "el" => (',', '.'),
+    "fr" | "ru" | "pl" | "sv" | "fi" | "uk" | "cs" | "nb" | "no" | "sk" | "hu" => (',', '\u{a0}'),
+    _ => ('.', ','),
+  };
+  let (date_order, date_sep) = match base {
+    "en" if lower == "en" || lower == "en-us" => (DateOrder::Mdy, '/'),
+    "ja" | "zh" | "ko" | "hu" | "lt" => (DateOrder::Ymd, '/'),
+    "de" | "ru" | "pl" | "fi" | "uk" | "cs" | "nb" | "no" | "tr" | "sk" | "da" => (DateOrder::Dmy, '.'),
+    "nl" => (DateOrder::Dmy, '-'),
+    _ => (DateOrder::Dmy, '/'),
+  };
+  let currency_prefix = matches!(base, "en" | "ja" | "zh" | "ko" | "hi" | "th" | "he" | "nl");
+  Conventions { decimal, group, date_order, date_sep, currency_prefix }
+}
+
+// Which reformatting passes run for a language pair
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct LocalizeConfig {
+  pub enabled: bool,
+  pub numbers: bool,
+  pub dates: bool,
+  pub currencies: bool,
+  // append converted amounts/measurements for the target locale (see convert)
+  #[serde(default)]
+  pub units: bool,
+}
+
+impl Default for LocalizeConfig {
+  fn default() -> Self {
+    Self { enabled: true, numbers: true, dates: true, currencies: true, units: false }
+  }
+}
+
+// One reformatted span
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct LocalizeChange {
+  // "number" | "date" | "currency" | "unit" | "exchange"
+  pub kind: String,
+  pub original: String,
+  pub replacement: String,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct LocalizeResult {
+  pub text: String,
+  pub changes: Vec<LocalizeChange>,
+}
+
+fn pattern() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| {
+    let num = r"\d{1,3}(?:[,.\u{a0}\u{202f}]\d{3})+(?:[.,]\d+)?|\d+(?:[.,]\d+)?";
+    Regex::new(&format!(
+      r"\b(?P<d1>\d{{1,4}})(?P<ds>[/.\-])(?P<d2>\d{{1,2}})[/.\-](?P<d3>\d{{1,4}})\b|(?P<cur_pre>[$€£¥₹])\s?(?P<num_pre>{num})|(?P<num_post>{num})\s?(?P<cur_post>[$€£¥₹])|(?P<num>{num})",
+      num = num
+    ))
+    .unwrap()
+  })
+}
+
+// parse a number written with the source locale's separators into (integer digits, fraction digits);
+// None when the separators don't fit the locale (e.g. "3,5" read with English conventions)
+fn parse_number(s: &str, conv: &Conventions) -> Option<(String, Option<String>)> {
+  let is_group = |c: char| c == conv.group || (conv.group == '\u{a0}' && c == '\u{202f}');
+  let (int_part, frac) = match s.split_once(conv.decimal) {
+    Some((i, f)) => (i, Some(f.to_string())),
+    None => (s, None),
+  };
+  if frac.as_ref().is_some_and(|f| f.is_empty() || !f.chars().all(|c| c.is_ascii_digit())) {
+    return None;
+  }
+  let groups: Vec<&str> = int_part.split(is_group).collect();
+  let valid = groups.iter().all(|g| g.chars().all(|c| c.is_ascii_digit()))
+    && !groups[0].is_empty()
+    && (groups.len() == 1 || (groups[0].len() <= 3 && groups[1..].iter().all(|g| g.len() == 3)));
+  if !valid {
+    return None;
+  }
+  Some((groups.concat(), frac))
+}
+
+fn format_number(int: &str, frac: Option<&str>, conv: &Conventions) -> String {
+  let mut grouped = String::new();
+  for (i, c) in int.chars().enumerate() {
+    if i > 0 && (int.len() - i).is_multiple_of(3) {
+      grouped.push(conv.group);
+    }
+    grouped.push(c);
+  }
+  match frac {
+    Some(f) => format!("{}{}{}", grouped, conv.decimal, f),
+    None => grouped,
+  }
+}
+
+// reformat a number; integers without separators (years, ids, counts) are left alone, and so is
+// anything that already reads under `to`: a translation may have localized it ("1.000" in German
+// output is a thousand, not the English 1.000). "1,000" in German output stays too, as one
+fn convert_number(s: &str, from: &Conventions, to: &Conventions) -> Option<String> {
+  if s.chars().all(|c| c.is_ascii_digit()) || parse_number(s, to).is_some() {
+    return None;
+  }
+  let (int, frac) = parse_number(s, from)?;
+  let out = format_number(&int, frac.as_deref(), to);
+  (out != s).then_some(out)
+}
+
+// (year, month, day) of a date read in `order`; None when the fields can't be that
+fn date_fields(parts: [&str; 3], order: DateOrder) -> Option<(&str, &str, &str)> {
+  let (y, m, d) = match order {
+    DateOrder::Dmy => (parts[2], parts[1], parts[0]),
+    DateOrder::Mdy => (parts[2], parts[0], parts[1]),
+    DateOrder::Ymd => (parts[0], parts[1], parts[2]),
+  };
+  let (mn, dn): (u32, u32) = (m.parse().ok()?, d.parse().ok()?);
+  ((1..=12).contains(&mn) && (1..=31).contains(&dn) && (y.len() == 2 || y.len() == 4)).then_some((y, m, d))
+}
+
+// reformat a date; one already written the way `to` writes dates is left alone ("04.03.2024" in
+// German output is the 4th of March, not an English April 3rd)
+fn convert_date(caps: &Captures, from: &Conventions, to: &Conventions) -> Option<String> {
+  let parts = [&caps["d1"], &caps["d2"], &caps["d3"]];
+  let separated_as_target = caps[0].chars().filter(|c| !c.is_ascii_digit()).all(|c| c == to.date_sep);
+  if separated_as_target && date_fields(parts, to.date_order).is_some() {
+    return None;
+  }
+  let (y, m, d) = date_fields(parts, from.date_order)?;
+  let sep = to.date_sep;
+  let out = match to.date_order {
+    DateOrder::Dmy => format!("{}{}{}{}{}", d, sep, m, sep, y),
+    DateOrder::Mdy => format!("{}{}{}{}{}", m, sep, d, sep, y),
+    DateOrder::Ymd => format!("{}{}{}{}{}", y, sep, m, sep, d),
+  };
+  (out != caps[0]).then_some(out)
+}
+
+// parse a number written with `locale` separators ("1.234,5" in de)
+pub fn parse_decimal(s: &str, locale: &str) -> Option<f64> {
+  let (int, frac) = parse_number(s, &conventions(locale))?;
+  format!("{}.{}", int, frac.unwrap_or_default()).trim_end_matches('.').parse().ok()
+}
+
+// format `value` with `decimals` fraction digits using `locale` separators
+pub fn format_decimal(value: f64, decimals: usize, locale: &str) -> String {
+  let plain = format!("{:.*}", decimals, value.abs());
+  let (int, frac) = match plain.split_once('.') {
+    Some((i, f)) => (i, Some(f)),
+    None => (plain.as_str(), None),
+  };
+  let sign = if value < 0.0 && plain.chars().any(|c| ('1'..='9').contains(&c)) { "-" } else { "" };
+  format!("{}{}", sign, format_number(int, frac, &conventions(locale)))
+}
+
+// place a currency symbol (or ISO code) before or after the amount as `locale` does
+pub fn format_currency(amount: &str, symbol: &str, locale: &str) -> String {
+  let prefix_gap = if symbol.chars().all(|c| c.is_ascii_alphabetic()) { "\u{a0}" } else { "" };
+  if conventions(locale).currency_prefix {
+    format!("{}{}{}", symbol, prefix_gap, amount)
+  } else {
+    format!("{}\u{a0}{}", amount, symbol)
+  }
+}
+
+// apply the enabled passes to text written with `source` conventions; what already follows
+// `target` conventions is kept
+pub fn localize(text: &str, source: &str, target: &str, config: &LocalizeConfig) -> LocalizeResult {
+  if !config.enabled {
+    return LocalizeResult { text: text.to_string(), changes: Vec::new() };
+  }
+  let from = conventions(source);
+  let to = conventions(target);
+  let mut changes = Vec::new();
+
+  let out = pattern().replace_all(text, |caps: &Captures| {
+    let original = caps[0].to_string();
+    let converted = if caps.name("d1").is_some() {
+      config.dates.then(|| convert_date(caps, &from, &to)).flatten().map(|r| ("date", r))
+    } else if let Some(num) = caps.name("num_pre").or(caps.name("num_post")) {
+      let symbol = caps.name("cur_pre").or(caps.name("cur_post")).map(|m| m.as_str()).unwrap_or("");
+      config.currencies.then(|| {
+        let formatted = if config.numbers {
+          convert_number(num.as_str(), &from, &to).unwrap_or_else(|| num.as_str().to_string())
+        } else {
+          num.as_str().to_string()
+        };
+        let r = if to.currency_prefix { format!("{}{}", symbol, formatted) } else { format!("{}\u{a0}{}", formatted, symbol) };
+        (r != original).then_some(("currency", r))
+      })
+      .flatten()
+    } else {
+      config.numbers.then(|| convert_number(&original, &from, &to)).flatten().map(|r| ("number", r))
+    };
+
+    match converted {
+      Some((kind, replacement)) => {
+        changes.push(LocalizeChange { kind: kind.into(), original, replacement: replacement.clone() });
+        replacement
+      }
+      None => original,
+    }
+  });
+
+  LocalizeResult { text: out.into_owned(), changes }
+}
+
+// the enabled passes plus, with `units`, amounts and measurements converted for the target locale;
+// what translations and localize_text return
+pub fn post_process(text: &str, source: &str, target: &str, config: &LocalizeConfig, rates: &RateTable) -> LocalizeResult {
+  let mut result = localize(text, source, target, config);
+  if config.enabled && config.units {
+    // the text now follows target conventions
+    let converted = convert::convert(&result.text, Some(target), target, rates);
+    result.text = converted.text;
+    result.changes.extend(converted.changes);
+  }
+  result
+}
+
+// "en" + "de" -> "en->de"
+fn pair_key(source: &str, target: &str) -> String {
+  format!("{}->{}", source.to_lowercase(), target.to_lowercase())
+}
+
+// Per language pair configuration persisted in ./data/localize.json (managed by Tauri)
+pub struct LocalizeSettings {
+  path: PathBuf,
+  pairs: HashMap<String, LocalizeConfig>,
+}
+
+impl LocalizeSettings {
+  pub fn load() -> Self {
+    let path = store::data_file("localize.json");
+    let pairs = store::load_json(&path);
+    Self { path, pairs }
+  }
+
+  pub fn get(&self, source: &str, target: &str) -> LocalizeConfig {
+    self.pairs.get(&pair_key(source, target)).cloned().unwrap_or_default()
+  }
+
+  pub fn set(&mut self, source: &str, target: &str, config: LocalizeConfig) -> Result<(), String> {
+    self.pairs.insert(pair_key(source, target), config);
+    store::save_json(&self.path, &self.pairs)
+  }
+}
+
+// ------------------ Tauri commands ------------------
+
+#[tauri::command]
+pub fn localize_text(
+  text: String,
+  source_locale: String,
+  target_locale: String,
+  settings: tauri::State<'_, Mutex<LocalizeSettings>>,
+  rates: tauri::State<'_, Mutex<Rates>>
+) -> LocalizeResult {
+  let config = settings.locked().get(&source_locale, &target_locale);
+  post_process(&text, &source_locale, &target_locale, &config, &rates.locked().table)
+}
+
+#[tauri::command]
+pub fn get_localize_config(
+  source_locale: String,
+  target_locale: String,
+  settings: tauri::State<'_, Mutex<LocalizeSettings>>
+) -> LocalizeConfig {
+  settings.locked().get(&source_locale, &target_locale)
+}
+
+#[tauri::command]
+pub fn set_localize_config(
+  source_locale: String,
+  target_locale: String,
+  config: LocalizeConfig,
+  settings: tauri::State<'_, Mutex<LocalizeSettings>>
+) -> Result<(), AppError> {
+  Ok(settings.locked().set(&source_locale, &target_locale, config)?)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn number(s: &str, from: &str, to: &str) -> Option<String> {
+    convert_number(s, &conventions(from), &conventions(to))
+  }
+
+  fn date(s: &str, from: &str, to: &str) -> Option<String> {
+    let caps = pattern().captures(s).unwrap();
+    convert_date(&caps, &conventions(from), &conventions(to))
+  }
+
+  #[test]
+  fn converts_numbers_both_ways() {
+    assert_eq!(number("1,234.5", "en", "de").as_deref(), Some("1.234,5"));
+    assert_eq!(number("3.5", "en", "de").as_deref(), Some("3,5"));
+    assert_eq!(number("1.234,5", "de", "en").as_deref(), Some("1,234.5"));
+    assert_eq!(number("3,5", "de", "en").as_deref(), Some("3.5"));
+    assert_eq!(number("1,234.5", "en", "fr").as_deref(), Some("1\u{a0}234,5"));
+  }
+
+  #[test]
+  fn keeps_numbers_already_in_target_format() {
+    // a thousand in German, not the English 1.000
+    assert_eq!(number("1.000", "en", "de"), None);
+    assert_eq!(number("3,5", "en", "de"), None);
+    assert_eq!(number("1,000", "de", "en"), None);
+    assert_eq!(number("2024", "en", "de"), None);
+  }
+
+  #[test]
+  fn converts_dates_both_ways() {
+    assert_eq!(date("03/04/2024", "en", "de").as_deref(), Some("04.03.2024"));
+    assert_eq!(date("04.03.2024", "de", "en").as_deref(), Some("03/04/2024"));
+    assert_eq!(date("04/13/2024", "en-us", "en-gb").as_deref(), Some("13/04/2024"));
+    assert_eq!(date("2024/03/04", "ja", "de").as_deref(), Some("04.03.2024"));
+  }
+
+  #[test]
+  fn keeps_dates_already_in_target_format() {
+    assert_eq!(date("04.03.2024", "en", "de"), None);
+    assert_eq!(date("03/04/2024", "de", "en"), None);
+    assert_eq!(date("13/04/2024", "en-us", "en-gb"), None);
+    // neither day-first nor month-first
+    assert_eq!(date("13/13/2024", "en", "de"), None);
+  }
+
+  #[test]
+  fn localize_leaves_localized_output_alone() {
+    let config = LocalizeConfig::default();
+    let out = localize("Am 04.03.2024 kamen 1.000 Leute, 2,5 pro Stunde.", "en", "de", &config);
+    assert_eq!(out.text, "Am 04.03.2024 kamen 1.000 Leute, 2,5 pro Stunde.");
+    assert!(out.changes.is_empty());
+    let out = localize("On 03/04/2024 1,000 people came, 2.5 an hour.", "en", "de", &config);
+    assert_eq!(out.text, "On 04.03.2024 1,000 people came, 2,5 an hour.");
+  }
+}
@@ -0,0 +1,153 @@
+// src-tauri/src/memory.rs
+use std::path::Path;
+use std::sync::Mutex;
+
+use sysinfo::System;
+use tauri::{AppHandle, Manager};
+
+use crate::engine;
+use crate::error::{AppError, LockExt};
+use crate::gguf::{self, GgufMetadata};
+use crate::hardware;
+use crate::model_config::ModelConfig;
+use crate::runtime;
+use crate::server;
+use crate::ModelManager;
+
+// compute buffers and the runtime itself, on top of weights and KV cache
+const OVERHEAD: u64 = 512 << 20;
+const MB: u64 = 1 << 20;
+
+// What a model needs to load and what the machine has free, for the pre-flight check before it
+// is started
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct MemoryEstimate {
+  pub model_id: String,
+  // tensor data from the GGUF header (the file size when the header doesn't tell)
+  pub weights_bytes: u64,
+  // f16 keys and values for the whole context window; 0 when the header lacks the attention shape
+  pub kv_cache_bytes: u64,
+  pub overhead_bytes: u64,
+  // -ngl the model would start with; 0 runs on the CPU
+  pub gpu_layers: u32,
+  pub ram_bytes: u64,
+  pub vram_bytes: u64,
+  pub ram_available_bytes: u64,
+  pub swap_available_bytes: u64,
+  // 0 when unknown or there is no GPU
+  pub vram_total_bytes: u64,
+  // false when the KV cache and buffers, which have to stay in memory, don't fit in free RAM and
+  // swap; it is refused then. Weights are memory-mapped and can be paged back in from the file, so
+  // too little room for them only makes it slow
+  pub fits: bool,
+  // set when it fits only by swapping or paging weights from disk, won't fit in VRAM, or doesn't
+  // fit at all
+  pub warning: Option<String>,
+}
+
+fn gib(bytes: u64) -> String {
+  format!("{:.1} GB", bytes as f64 / (1u64 << 30) as f64)
+}
+
+// keys and values of every layer for `ctx` tokens at 2 bytes each
+fn kv_cache_bytes(meta: &GgufMetadata, ctx: u64) -> u64 {
+  let (Some(layers), Some(embedding), Some(heads)) = (meta.block_count, meta.embedding_length, meta.head_count) else {
+    return 0;
+  };
+  let head_dim = embedding / heads.max(1);
+  let kv_heads = meta.head_count_kv.unwrap_or(heads);
+  2 * layers * ctx * kv_heads * head_dim * 2
+}
+
+// memory `model_id` at `model_path` needs with `ctx` tokens of context; `offload` is whether the
+// runtime can use a GPU. Weights of offloaded layers and their share of the KV cache go to VRAM,
+// the rest to RAM; with unified memory (Apple silicon, iGPUs) all of it comes out of RAM
+pub fn estimate(model_id: &str, model_path: &str, meta: Option<&GgufMetadata>, config: &ModelConfig, offload: bool, ctx: u32) -> MemoryEstimate {
+  let read;
+  let meta = match meta {
+    Some(m) => Some(m),
+    None => {
+      read = gguf::read_metadata(Path::new(model_path)).ok();
+      read.as_ref()
+    }
+  };
+  let weights = meta.and_then(|m| m.tensor_bytes).or_else(|| hardware::model_bytes(Path::new(model_path))).unwrap_or(0);
+  let kv = meta.map(|m| kv_cache_bytes(m, ctx as u64)).unwrap_or(0);
+  let gpu_layers = if offload { hardware::gpu_layers(model_path, config) } else { 0 };
+
+  // share of the model on the GPU: -ngl counts the blocks plus the output layer
+  let blocks = meta.and_then(|m| m.block_count).unwrap_or(0);
+  let on_gpu = |bytes: u64, layers: u64| match (gpu_layers as u64, layers) {
+    (0, _) => 0,
+    (_, 0) => bytes,
+    (n, layers) => bytes * n.min(layers) / layers,
+  };
+  let weights_vram = on_gpu(weights, blocks + 1);
+  let vram = weights_vram + on_gpu(kv, blocks);
+  let vram = if gpu_layers > 0 { vram + OVERHEAD } else { 0 };
+  let hw = hardware::info(false);
+  // integrated GPUs without VRAM of their own (vram_mb unknown) take it out of RAM too
+  let shared = hw.unified_memory || hw.vram_mb == 0;
+  let ram = if shared { weights + kv + OVERHEAD } else { weights + kv + OVERHEAD - vram.min(weights + kv) };
+  // what can't be paged out to the model file
+  let resident = ram.saturating_sub(if shared { weights } else { weights - weights_vram });
+
+  let mut sys = System::new();
+  sys.refresh_memory();
+  let (available, swap) = (sys.available_memory(), sys.free_swap());
+  let vram_total = hw.vram_mb * MB;
+  let fits = resident <= available + swap;
+  let warning = if !fits {
+    Some(format!("needs {} of RAM for its context but only {} is free", gib(resident), gib(available + swap)))
+  } else if ram > available + swap {
+    Some(format!("needs {} of RAM but {} is free; weights will be read from disk as they are used and it will be very slow", gib(ram), gib(available + swap)))
+  } else if ram > available {
+    Some(format!("needs {} of RAM but {} is free; the rest will be swapped and it will be slow", gib(ram), gib(available)))
+  } else if !shared && vram > vram_total {
+    Some(format!("{} GPU layers need {} of VRAM but the GPU has {}; lower gpu_layers", gpu_layers, gib(vram), gib(vram_total)))
+  } else {
+    None
+  };
+  MemoryEstimate {
+    model_id: model_id.to_string(),
+    weights_bytes: weights,
+    kv_cache_bytes: kv,
+    overhead_bytes: OVERHEAD,
+    gpu_layers,
+    ram_bytes: ram,
+    vram_bytes: vram,
+    ram_available_bytes: available,
+    swap_available_bytes: swap,
+    vram_total_bytes: vram_total,
+    fits,
+    warning,
+  }
+}
+
+// the check before a model starts: an error when it can't fit, else the warning to show, if any
+pub fn preflight(estimate: &MemoryEstimate) -> Result<Option<String>, String> {
+  match &estimate.warning {
+    Some(warning) if !estimate.fits => Err(format!("Not starting '{}': it {}. Close other programs, or pick a smaller model or context size", estimate.model_id, warning)),
+    warning => Ok(warning.as_ref().map(|w| format!("This model {}", w))),
+  }
+}
+
+// ------------------ Tauri commands ------------------
+
+// what starting `id` would take, with the context size and GPU layers it would start with (and
+// llama-server's parallel slots when that is bundled)
+#[tauri::command(async)]
+pub fn estimate_model_memory(id: String, app: AppHandle) -> Result<MemoryEstimate, AppError> {
+  let (model, config) = {
+    let state = app.state::<Mutex<ModelManager>>();
+    let mgr = state.locked();
+    let model = mgr.models.get(&id).cloned().ok_or_else(|| AppError::not_found("Model", &id))?;
+    let config = mgr.configs.get(&id);
+    (model, config)
+  };
+  let hw = hardware::info(false);
+  let offload = !hw.gpus.is_empty() || hw.metal || hw.vulkan;
+  let server = config.server.unwrap_or(true) && runtime::bundled_tool(server::SERVER_TOOL).is_some();
+  let ctx = engine::context_size(&config) * if server { server::SLOTS } else { 1 };
+  Ok(estimate(&id, &model.path, model.gguf.as_ref(), &config, offload, ctx))
+}
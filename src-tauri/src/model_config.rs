@@ -0,0 +1,99 @@
+// src-tauri/src/model_config.rs
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::chat_template::{ChatTemplate, ModelTemplate};
+use crate::encoding::OutputEncoding;
+use crate::gpu::GpuSplit;
+use crate::protocol::StdinProtocol;
+use crate::sampling::SamplingParams;
+use crate::store;
+use crate::transport::Transport;
+use crate::watchdog::RestartPolicy;
+
+// Per-model runtime options set by the user (persisted next to the models)
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct ModelConfig {
+  #[serde(default)]
+  pub gpu: Option<GpuSplit>,
+  // layers offloaded to the GPU (-ngl), 0 keeps the model on the CPU; unset uses the gpu_layers
+  // setting, else fits as many as the VRAM holds
+  #[serde(default)]
+  pub gpu_layers: Option<u32>,
+  // context window in tokens (runtime default when unset)
+  #[serde(default)]
+  pub ctx_size: Option<u32>,
+  // languages the model translates between (ISO codes); empty means any
+  #[serde(default)]
+  pub languages: Vec<String>,
+  // grace period between the stop request and a hard kill (shutdown::DEFAULT_STOP_TIMEOUT_MS when unset)
+  #[serde(default)]
+  pub stop_timeout_ms: Option<u64>,
+  // written to the runtime's stdin to stop the current answer (e.g. a wrapper's "/stop" command);
+  // unset sends an interrupt signal, which llama.cpp's interactive mode takes as "stop generating".
+  // Runtimes on the socket transport get a cancel message on their control channel instead
+  #[serde(default)]
+  pub interrupt: Option<String>,
+  // how prompts are written to the process (by default llama_interactive for bundled runtimes, raw otherwise)
+  #[serde(default)]
+  pub protocol: Option<StdinProtocol>,
+  // whether a wrapper runtime talks over stdin/stdout (when unset) or sockets
+  #[serde(default)]
+  pub transport: Option<Transport>,
+  // put in front of every request to this model as a system turn (a session's own takes its place)
+  #[serde(default)]
+  pub system_prompt: Option<String>,
+  // text llama.cpp prints when it wants the next input (protocol::DEFAULT_REVERSE_PROMPT when unset)
+  #[serde(default)]
+  pub reverse_prompt: Option<String>,
+  // run the bundled llama-server and talk to it over HTTP instead of piping to the CLI
+  // (the default when llama-server is bundled)
+  #[serde(default)]
+  pub server: Option<bool>,
+  // character encoding of the runtime's output (auto-detected when unset)
+  #[serde(default)]
+  pub encoding: Option<OutputEncoding>,
+  // sampling defaults of this model; run_prompt can override them per prompt
+  #[serde(default)]
+  pub sampling: SamplingParams,
+  // prompt format, overriding the one detected from the GGUF header
+  #[serde(default)]
+  pub chat_template: Option<ChatTemplate>,
+  // prompt format detected from the GGUF header or the model's name, set when the model is scanned
+  #[serde(default)]
+  pub detected_template: Option<ModelTemplate>,
+  // let a running model drop its oldest tokens when its context fills (llama.cpp --context-shift)
+  // instead of stopping; on when unset. Builds that don't know the flag need it off
+  #[serde(default)]
+  pub context_shift: Option<bool>,
+  // start the model again when its process crashes (off when unset)
+  #[serde(default)]
+  pub restart: Option<RestartPolicy>,
+}
+
+// model id -> ModelConfig, backed by a JSON file
+pub struct ModelConfigStore {
+  path: PathBuf,
+  configs: HashMap<String, ModelConfig>,
+}
+
+impl ModelConfigStore {
+  pub fn load(path: PathBuf) -> Self {
+    let configs = store::load_json(&path);
+    Self { path, configs }
+  }
+
+  pub fn get(&self, id: &str) -> ModelConfig {
+    self.configs.get(id).cloned().unwrap_or_default()
+  }
+
+  // apply `f` to the config of `id` and write the store back to disk
+  pub fn update<F: FnOnce(&mut ModelConfig)>(&mut self, id: &str, f: F) -> Result<(), String> {
+    f(self.configs.entry(id.to_string()).or_default());
+    self.save()
+  }
+
+  fn save(&self) -> Result<(), String> {
+    store::save_json(&self.path, &self.configs)
+  }
+}
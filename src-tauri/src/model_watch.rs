@@ -0,0 +1,112 @@
+// src-tauri/src/model_watch.rs
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::LockExt;
+use crate::events;
+use crate::settings;
+use crate::ModelManager;
+
+// rescan once the folders have been quiet this long, so a model still being copied is picked up
+// when the copy is done rather than half-written
+const SETTLE: Duration = Duration::from_millis(1500);
+
+// Payload of "models-changed" events
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ModelsChanged {
+  pub added: Vec<String>,
+  pub removed: Vec<String>,
+}
+
+// Watcher over every models directory (managed by Tauri)
+#[derive(Default)]
+pub struct ModelWatcher {
+  watcher: Option<RecommendedWatcher>,
+}
+
+impl ModelWatcher {
+  pub fn new() -> Self {
+    Self::default()
+  }
+}
+
+// files the app writes itself next to the models: partial downloads, config files, atomic-save
+// temp files (store::write_atomic) and links being made by dedup
+fn ignored(path: &Path) -> bool {
+  path.extension().is_some_and(|e| e == "part" || e == "json" || e == "tmp" || e == "dedup-tmp")
+}
+
+// an event that may change the model list
+fn relevant(event: &notify::Result<notify::Event>) -> bool {
+  event.as_ref().is_ok_and(|e| !matches!(e.kind, EventKind::Access(_)) && !e.paths.iter().all(|p| ignored(p)))
+}
+
+fn rescan(app: &AppHandle) {
+  let state = app.state::<Mutex<ModelManager>>();
+  let (added, removed) = {
+    let mut mgr = state.locked();
+    let before: HashSet<String> = mgr.models.keys().cloned().collect();
+    mgr.scan_models();
+    let after: HashSet<String> = mgr.models.keys().cloned().collect();
+    let mut added: Vec<String> = after.difference(&before).cloned().collect();
+    let mut removed: Vec<String> = before.difference(&after).cloned().collect();
+    added.sort();
+    removed.sort();
+    (added, removed)
+  };
+  if !added.is_empty() || !removed.is_empty() {
+    events::log(format!("models changed: +{:?} -{:?}", added, removed));
+    let _ = app.emit("models-changed", ModelsChanged { added, removed });
+  }
+}
+
+// (re)start watching the current models directories; called at startup and whenever the list changes
+pub fn restart(app: &AppHandle) {
+  let state = app.state::<Mutex<ModelWatcher>>();
+  let mut state = state.locked();
+  // dropping the old watcher ends its thread
+  state.watcher = None;
+
+  let (tx, rx) = mpsc::channel::<notify::Result<notify::Event>>();
+  let mut watcher = match notify::recommended_watcher(tx) {
+    Ok(w) => w,
+    Err(e) => {
+      events::log(format!("failed to watch the models folders: {}", e));
+      return;
+    }
+  };
+  for dir in settings::model_dirs() {
+    // folders on a disconnected drive are picked up again by the next restart or rescan
+    if let Err(e) = watcher.watch(&dir, RecursiveMode::NonRecursive) {
+      events::log(format!("failed to watch {}: {}", dir.to_string_lossy(), e));
+    }
+  }
+
+  let app = app.clone();
+  thread::spawn(move || {
+    // ends when the watcher (and with it the sender) is dropped
+    while let Ok(event) = rx.recv() {
+      if !relevant(&event) {
+        continue;
+      }
+      // wait for the burst (a copy, an unpacked archive) to end; the app's own writes in the
+      // meantime don't hold the rescan back
+      let mut quiet_since = Instant::now();
+      loop {
+        match rx.recv_timeout(SETTLE.saturating_sub(quiet_since.elapsed())) {
+          Ok(event) if relevant(&event) => quiet_since = Instant::now(),
+          Ok(_) => {}
+          Err(_) => break,
+        }
+      }
+      rescan(&app);
+    }
+  });
+  state.watcher = Some(watcher);
+}
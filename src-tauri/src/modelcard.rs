@@ -0,0 +1,193 @@
+// src-tauri/src/modelcard.rs
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use ammonia::UrlRelative;
+use pulldown_cmark::{html, Options, Parser};
+use regex::{Captures, Regex};
+use tauri::{AppHandle, Manager};
+use url::Url;
+
+use crate::download::DownloadManager;
+use crate::error::{AppError, LockExt};
+use crate::gguf_split;
+use crate::store;
+use crate::ModelManager;
+
+// larger READMEs are not model cards
+const MAX_CARD_BYTES: u64 = 1 << 20;
+// file names a model folder's card goes by, in order of preference
+const FOLDER_CARDS: &[&str] = &["README.md", "readme.md", "MODEL_CARD.md", "model_card.md"];
+
+// where the card of a single-file model is stored: "<stem>.README.md" beside it, named after the
+// base name for split models so all shards share one
+fn file_card(model_path: &Path) -> Option<PathBuf> {
+  let name = model_path.file_name()?.to_string_lossy().to_string();
+  let stem = match gguf_split::parse_split_name(&name) {
+    Some((base, _, _)) => base,
+    None => model_path.file_stem()?.to_string_lossy().to_string(),
+  };
+  Some(model_path.with_file_name(format!("{}.README.md", stem)))
+}
+
+// the card of a model folder or single file, if there is one
+pub fn find(model_path: &Path) -> Option<PathBuf> {
+  if model_path.is_dir() {
+    return FOLDER_CARDS.iter().map(|n| model_path.join(n)).find(|p| p.is_file());
+  }
+  let stem = model_path.file_stem()?.to_string_lossy().to_string();
+  // a split model's card may also be named after its first shard
+  [file_card(model_path), Some(model_path.with_file_name(format!("{}.README.md", stem)))].into_iter().flatten().find(|p| p.is_file())
+}
+
+// "https://huggingface.co/<org>/<repo>/resolve/<revision>/<file>" -> ("<org>/<repo>", "<revision>")
+fn hf_repo(url: &str) -> Option<(String, String)> {
+  let url = Url::parse(url).ok()?;
+  if !matches!(url.host_str()?, "huggingface.co" | "hf.co") {
+    return None;
+  }
+  let segments: Vec<&str> = url.path_segments()?.collect();
+  match segments.as_slice() {
+    [org, repo, "resolve" | "blob" | "raw", revision, _, ..] => Some((format!("{}/{}", org, repo), revision.to_string())),
+    _ => None,
+  }
+}
+
+fn link_pattern() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| Regex::new(r#"(\]\(\s*<?|(?i:\bsrc|\bhref)\s*=\s*["'])([^)\s>"']+)"#).unwrap())
+}
+
+// relative links and images point into the repo, not at the app
+fn absolutize(markdown: &str, base: &Url) -> String {
+  link_pattern()
+    .replace_all(markdown, |caps: &Captures| {
+      let target = &caps[2];
+      if target.starts_with('#') || target.contains(':') {
+        return caps[0].to_string();
+      }
+      match base.join(target) {
+        Ok(abs) => format!("{}{}", &caps[1], abs),
+        Err(_) => caps[0].to_string(),
+      }
+    })
+    .into_owned()
+}
+
+// download the README of the Hugging Face repo `url` points into and store it as the card of the
+// model at `model_path`; Ok(None) for URLs that aren't Hugging Face files
+pub fn fetch(url: &str, model_path: &Path) -> Result<Option<PathBuf>, String> {
+  let Some((repo, revision)) = hf_repo(url) else { return Ok(None) };
+  let card_url = format!("https://huggingface.co/{}/raw/{}/README.md", repo, revision);
+  let mut resp = ureq::get(&card_url)
+    .header("User-Agent", "multilingual-model-downloader")
+    .call()
+    .map_err(|e| format!("failed to fetch {}: {}", card_url, e))?;
+  let text = resp
+    .body_mut()
+    .with_config()
+    .limit(MAX_CARD_BYTES)
+    .read_to_string()
+    .map_err(|e| format!("failed to read {}: {}", card_url, e))?;
+  let base = Url::parse(&format!("https://huggingface.co/{}/resolve/{}/", repo, revision)).map_err(|e| e.to_string())?;
+  let path = file_card(model_path).ok_or("the model has no file name")?;
+  store::write_atomic(&path, absolutize(&text, &base).as_bytes())?;
+  Ok(Some(path))
+}
+
+// the card as HTML that is safe to show in the webview: the YAML front matter (license, tags) is
+// removed, the Markdown rendered and the result cleaned by an HTML sanitizer, which keeps
+// formatting but no scripts, event handlers or links other than http(s) and mailto
+pub fn sanitize(markdown: &str) -> String {
+  let markdown = markdown.replace("\r\n", "\n");
+  let body = match markdown.strip_prefix("---\n") {
+    Some(rest) => rest.split_once("\n---").map(|(_, after)| after.split_once('\n').map(|(_, b)| b).unwrap_or("")).unwrap_or(&markdown),
+    None => &markdown,
+  };
+  let mut html = String::new();
+  html::push_html(&mut html, Parser::new_ext(body, Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS));
+  // relative links would resolve against the app itself; cards fetched from Hugging Face have
+  // theirs made absolute already
+  ammonia::Builder::default()
+    .url_schemes(HashSet::from(["http", "https", "mailto"]))
+    .url_relative(UrlRelative::Deny)
+    .clean(&html)
+    .to_string()
+    .trim()
+    .to_string()
+}
+
+// ------------------ Tauri commands ------------------
+
+// the model's README / Hugging Face model card as sanitized HTML; None when it has none. A
+// model downloaded from Hugging Face before its card was stored gets it fetched now
+#[tauri::command(async)]
+pub fn get_model_card(id: String, app: AppHandle) -> Result<Option<String>, AppError> {
+  let path = {
+    let state = app.state::<Mutex<ModelManager>>();
+    let mgr = state.locked();
+    PathBuf::from(&mgr.models.get(&id).ok_or_else(|| AppError::not_found("Model", &id))?.path)
+  };
+  let card = match find(&path) {
+    Some(card) => Some(card),
+    None => {
+      let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+      let url = app.state::<Mutex<DownloadManager>>().locked().source_url(&file_name);
+      match url {
+        Some(url) => fetch(&url, &path)?,
+        None => None,
+      }
+    }
+  };
+  let Some(card) = card else { return Ok(None) };
+  if fs::metadata(&card).map(|m| m.len()).unwrap_or(0) > MAX_CARD_BYTES {
+    return Err(format!("{} is too large to show", card.to_string_lossy()).into());
+  }
+  let text = fs::read_to_string(&card).map_err(|e| AppError::io("read", &card, e))?;
+  Ok(Some(sanitize(&text)))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn nested_tags_dont_survive() {
+    // what is left of the tag is text, not an element
+    let out = sanitize("<<b>img src=x onerror=alert(1)>");
+    assert!(!out.contains("<img"), "{}", out);
+  }
+
+  #[test]
+  fn script_links_are_dropped() {
+    for card in ["[x](javascript&#58;alert(1))", "[x](javascript:alert(1))", "[x](JaVaScRiPt:alert(1))", "<javascript:alert(1)>", "[x][1]\n\n[1]: javascript:alert(1)", "[x](data:text/html,hi)"] {
+      let out = sanitize(card);
+      let lower = out.to_lowercase();
+      assert!(!lower.contains("href=\"javascript") && !lower.contains("href=\"data:"), "{} -> {}", card, out);
+    }
+  }
+
+  #[test]
+  fn scripts_and_handlers_are_removed() {
+    let out = sanitize("<script>alert(1)</script><a href=\"https://x.org\" onclick=\"alert(1)\">x</a><iframe src=\"https://x.org\"></iframe>");
+    assert!(!out.contains("script") && !out.contains("onclick") && !out.contains("iframe"), "{}", out);
+    assert!(out.contains("href=\"https://x.org\""), "{}", out);
+  }
+
+  #[test]
+  fn front_matter_and_relative_links_go() {
+    let out = sanitize("---\nlicense: mit\n---\n# Title\n\n[rel](config.json) [abs](https://huggingface.co/x)");
+    assert!(!out.contains("license"), "{}", out);
+    assert!(out.contains("<h1>Title</h1>"), "{}", out);
+    assert!(!out.contains("config.json\""), "{}", out);
+    assert!(out.contains("href=\"https://huggingface.co/x\""), "{}", out);
+  }
+
+  #[test]
+  fn code_is_shown_as_text() {
+    let out = sanitize("```html\n<script>alert(1)</script>\n```");
+    assert!(out.contains("&lt;script&gt;"), "{}", out);
+  }
+}
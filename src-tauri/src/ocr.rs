@@ -0,0 +1,161 @@
+// src-tauri/src/ocr.rs
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+
+use tauri::{Manager, Window};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::error::{AppError, LockExt};
+use crate::lang;
+use crate::runtime;
+use crate::ModelManager;
+
+// ISO 639-1 -> tesseract traineddata names
+const TESSERACT_LANGS: &[(&str, &str)] = &[
+  ("ar", "ara"),
+  ("bn", "ben"),
+  ("de", "deu"),
+  ("el", "ell"),
+  ("en", "eng"),
+  ("es", "spa"),
+  ("fa", "fas"),
+  ("fr", "fra"),
+  ("he", "heb"),
+  ("hi", "hin"),
+  ("it", "ita"),
+  ("ja", "jpn"),
+  ("ko", "kor"),
+  ("ml", "mal"),
+  ("mr", "mar"),
+  ("pt", "por"),
+  ("ru", "rus"),
+  ("ta", "tam"),
+  ("te", "tel"),
+  ("th", "tha"),
+  ("uk", "ukr"),
+  ("ur", "urd"),
+  ("zh", "chi_sim"),
+];
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct RecognizedText {
+  pub text: String,
+  // "vision" (multimodal model) or "tesseract"
+  pub engine: String,
+}
+
+fn tesseract_lang(code: &str) -> String {
+  let base = code.split(['-', '_']).next().unwrap_or(code).to_lowercase();
+  // traditional Chinese locales
+  if base == "zh" && matches!(code.to_lowercase().as_str(), "zh-tw" | "zh-hk" | "zh-hant") {
+    return "chi_tra".into();
+  }
+  TESSERACT_LANGS.iter().find(|(c, _)| *c == base).map(|(_, t)| t.to_string()).unwrap_or(base)
+}
+
+// multimodal projector (mmproj*.gguf) shipped next to the model, if any
+fn find_mmproj(model_path: &str) -> Option<PathBuf> {
+  let p = Path::new(model_path);
+  let dir = if p.is_dir() { p } else { p.parent()? };
+  fs::read_dir(dir)
+    .ok()?
+    .flatten()
+    .map(|e| e.path())
+    .find(|f| {
+      let name = f.file_name().map(|n| n.to_string_lossy().to_lowercase()).unwrap_or_default();
+      name.starts_with("mmproj") && name.ends_with(".gguf")
+    })
+}
+
+fn with_vision_model(model_path: &str, mmproj: &Path, image: &str, hint: Option<&str>) -> Result<String, String> {
+  let exe = runtime::bundled_tool("llama-mtmd-cli").ok_or("llama-mtmd-cli not found in ./src-tauri/bin")?;
+  let script = hint.map(|l| format!(" The text is in {}.", lang::language_name(l))).unwrap_or_default();
+  let prompt = format!(
+    "Transcribe all the text in this image exactly as written, keeping line breaks.{} Reply with the transcription only.",
+    script
+  );
+  let out = Command::new(exe)
+    .args(["-m", model_path])
+    .arg("--mmproj")
+    .arg(mmproj)
+    .args(["--image", image, "-p", &prompt, "-n", "1024", "--no-display-prompt"])
+    .stdin(Stdio::null())
+    .output()
+    .map_err(|e| format!("failed to run llama-mtmd-cli: {}", e))?;
+  if !out.status.success() {
+    return Err(format!("vision model failed: {}", String::from_utf8_lossy(&out.stderr).lines().last().unwrap_or("")));
+  }
+  Ok(String::from_utf8_lossy(&out.stdout).to_string())
+}
+
+fn with_tesseract(image: &str, hint: Option<&str>) -> Result<String, String> {
+  let exe = runtime::bundled_tool("tesseract").unwrap_or_else(|| PathBuf::from("tesseract"));
+  // English is added so mixed-script photos (signs, menus with prices) still come through
+  let langs = match hint {
+    Some(l) if !lang::same_language(l, "en") => format!("{}+eng", tesseract_lang(l)),
+    _ => "eng".to_string(),
+  };
+  let out = Command::new(exe)
+    .args([image, "stdout", "-l", &langs])
+    .stdin(Stdio::null())
+    .output()
+    .map_err(|e| format!("failed to run tesseract (is it installed?): {}", e))?;
+  if !out.status.success() {
+    return Err(format!("tesseract failed: {}", String::from_utf8_lossy(&out.stderr).trim()));
+  }
+  Ok(String::from_utf8_lossy(&out.stdout).to_string())
+}
+
+// NFC, trimmed lines, at most one blank line between paragraphs
+fn clean(text: &str) -> String {
+  let text: String = text.nfc().collect();
+  let mut out: Vec<&str> = Vec::new();
+  for line in text.lines().map(|l| l.trim()) {
+    if line.is_empty() && out.last().is_none_or(|l| l.is_empty()) {
+      continue;
+    }
+    out.push(line);
+  }
+  while out.last().is_some_and(|l| l.is_empty()) {
+    out.pop();
+  }
+  out.join("\n")
+}
+
+// ------------------ Tauri commands ------------------
+
+// OCR a photo of printed or handwritten text; a multimodal model (mmproj next to the model file)
+// is preferred since it copes with handwriting, tesseract is the fallback
+#[tauri::command(async)]
+pub fn recognize_text_image(path: String, hint_lang: Option<String>, model_id: Option<String>, window: Window) -> Result<RecognizedText, AppError> {
+  if !Path::new(&path).is_file() {
+    return Err(format!("Image not found: {}", path).into());
+  }
+  let hint = hint_lang.as_deref();
+  let vision = window
+    .state::<Mutex<ModelManager>>()
+    .locked()
+    .model_for_request(model_id.as_deref())
+    .ok()
+    .and_then(|(model, _)| find_mmproj(&model.path).map(|mm| (model.path, mm)));
+
+  let mut vision_err = None;
+  if let Some((model_path, mmproj)) = vision {
+    match with_vision_model(&model_path, &mmproj, &path, hint) {
+      Ok(text) if !text.trim().is_empty() => return Ok(RecognizedText { text: clean(&text), engine: "vision".into() }),
+      Ok(_) => {}
+      Err(e) => vision_err = Some(e),
+    }
+  }
+  let text = with_tesseract(&path, hint).map_err(|e| match &vision_err {
+    Some(v) => format!("{}; {}", v, e),
+    None => e,
+  })?;
+  let text = clean(&text);
+  if text.is_empty() {
+    return Err("No text was recognized in the image".into());
+  }
+  Ok(RecognizedText { text, engine: "tesseract".into() })
+}
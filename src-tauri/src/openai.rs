@@ -0,0 +1,278 @@
+// src-tauri/src/openai.rs
+use std::io::Read;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tauri::{AppHandle, Manager};
+use tiny_http::{Header, Request, Response};
+
+use crate::chat_template;
+use crate::engine;
+use crate::error::{AppError, LockExt};
+use crate::events;
+use crate::httpd::{self, HttpServer};
+use crate::session::Turn;
+use crate::settings;
+use crate::system_prompt;
+use crate::ModelManager;
+
+const DEFAULT_BIND: &str = "127.0.0.1";
+const DEFAULT_PORT: u16 = 8080;
+// reply length when the client doesn't set max_tokens
+const DEFAULT_MAX_TOKENS: u32 = 512;
+const MAX_BODY: u64 = 4 << 20;
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ApiStatus {
+  pub running: bool,
+  // e.g. http://127.0.0.1:8080/v1
+  pub url: Option<String>,
+  pub bind: String,
+  pub port: u16,
+  // whether clients must send the API key as a bearer token
+  pub key_required: bool,
+}
+
+struct Running {
+  server: HttpServer,
+  url: String,
+}
+
+// OpenAI-compatible endpoint for other apps on this machine (managed by Tauri)
+#[derive(Default)]
+pub struct OpenAiApi {
+  running: Option<Running>,
+}
+
+impl OpenAiApi {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  fn status(&self) -> ApiStatus {
+    let settings = settings::get();
+    ApiStatus {
+      running: self.running.is_some(),
+      url: self.running.as_ref().map(|r| r.url.clone()),
+      bind: settings.openai_api_bind.unwrap_or_else(|| DEFAULT_BIND.to_string()),
+      port: settings.openai_api_port.unwrap_or(DEFAULT_PORT),
+      key_required: settings.openai_api_key.is_some(),
+    }
+  }
+}
+
+fn now_secs() -> u64 {
+  SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn completion_id() -> String {
+  static NEXT: AtomicU64 = AtomicU64::new(0);
+  format!("chatcmpl-{}-{}", now_secs(), NEXT.fetch_add(1, Ordering::Relaxed) + 1)
+}
+
+fn json_header() -> Header {
+  httpd::content_type("application/json")
+}
+
+// errors in the shape OpenAI clients expect
+fn error_response(status: u16, message: &str) -> Response<std::io::Cursor<Vec<u8>>> {
+  let kind = if status < 500 { "invalid_request_error" } else { "server_error" };
+  Response::from_string(serde_json::json!({ "error": { "message": message, "type": kind } }).to_string())
+    .with_status_code(status)
+    .with_header(json_header())
+}
+
+// web pages can't call the API: browsers always send Origin on cross-site requests. Without a key
+// only this machine may call, checked on both the peer address and Host so a rebound DNS name
+// can't reach it either
+fn authorize(req: &Request) -> Result<(), (u16, &'static str)> {
+  if httpd::request_header(req, "Origin").is_some() {
+    return Err((403, "Requests from web pages are not allowed"));
+  }
+  match settings::get().openai_api_key {
+    Some(key) if httpd::bearer(req).is_some_and(|token| httpd::same_secret(token, &key)) => Ok(()),
+    Some(_) => Err((401, "Incorrect API key provided")),
+    None => {
+      let local = req.remote_addr().is_some_and(|a| a.ip().is_loopback()) && httpd::request_header(req, "Host").is_some_and(httpd::loopback_host);
+      if local { Ok(()) } else { Err((403, "Set an API key to accept requests from other machines")) }
+    }
+  }
+}
+
+// message content is a string or a list of parts of which only text is understood
+fn content_text(content: &serde_json::Value) -> String {
+  match content {
+    serde_json::Value::String(s) => s.clone(),
+    serde_json::Value::Array(parts) => parts.iter().filter_map(|p| p.get("text").and_then(|t| t.as_str())).collect::<Vec<_>>().join("\n"),
+    _ => String::new(),
+  }
+}
+
+fn list_models(app: &AppHandle) -> serde_json::Value {
+  let models = app.state::<Mutex<ModelManager>>().locked().list_models();
+  let data: Vec<serde_json::Value> = models
+    .iter()
+    .filter(|m| m.complete && !m.speech)
+    .map(|m| serde_json::json!({ "id": m.id, "object": "model", "created": 0, "owned_by": "local" }))
+    .collect();
+  serde_json::json!({ "object": "list", "data": data })
+}
+
+// (response body, stream) for a /v1/chat/completions request
+fn chat_completion(req: &mut Request, app: &AppHandle) -> Result<(serde_json::Value, bool), (u16, String)> {
+  let mut body = String::new();
+  req.as_reader().take(MAX_BODY).read_to_string(&mut body).map_err(|e| (400, format!("failed to read request: {}", e)))?;
+  let body: serde_json::Value = serde_json::from_str(&body).map_err(|e| (400, format!("invalid JSON: {}", e)))?;
+  let messages = body.get("messages").and_then(|m| m.as_array()).filter(|m| !m.is_empty()).ok_or((400, "messages is required".to_string()))?;
+  let mut turns: Vec<Turn> = messages
+    .iter()
+    .map(|m| {
+      // "developer" is the newer name of the system role
+      let role = match m.get("role").and_then(|r| r.as_str()).unwrap_or("user") {
+        "system" | "developer" => "system",
+        "assistant" => "assistant",
+        _ => "user",
+      };
+      Turn::new(role, &content_text(m.get("content").unwrap_or(&serde_json::Value::Null)))
+    })
+    .collect();
+  let max_tokens = body.get("max_completion_tokens").or(body.get("max_tokens")).and_then(|v| v.as_u64()).map(|v| v as u32).unwrap_or(DEFAULT_MAX_TOKENS);
+  let stream = body.get("stream").and_then(|s| s.as_bool()).unwrap_or(false);
+
+  // unknown model names ("gpt-4o" from a client's defaults) fall back to the loaded model
+  let (model, config) = {
+    let mgr = app.state::<Mutex<ModelManager>>();
+    let mgr = mgr.locked();
+    let requested = body.get("model").and_then(|m| m.as_str()).filter(|id| mgr.models.contains_key(*id));
+    mgr.model_for_request(requested).map_err(|e| (503, e))?
+  };
+  // the model's system prompt, unless the client brought its own
+  if !turns.iter().any(|t| t.role == "system") {
+    turns.splice(..0, system_prompt::system_turn(std::slice::from_ref(&config.system_prompt)));
+  }
+  let prompt = chat_template::resolve(&config, model.gguf.as_ref()).template.format(&turns);
+  let text = engine::generate(&model.path, &config, &prompt, max_tokens).map_err(|e| (500, e))?.trim().to_string();
+  let prompt_tokens = engine::estimate_tokens(&prompt);
+  let completion_tokens = engine::estimate_tokens(&text);
+  let id = completion_id();
+  let created = now_secs();
+
+  if stream {
+    // the reply is generated in one piece, so it goes out as a single delta
+    let chunk = |delta: serde_json::Value, finish: Option<&str>| {
+      serde_json::json!({
+        "id": id, "object": "chat.completion.chunk", "created": created, "model": model.id,
+        "choices": [{ "index": 0, "delta": delta, "finish_reason": finish }]
+      })
+    };
+    let events = vec![chunk(serde_json::json!({ "role": "assistant", "content": text }), None), chunk(serde_json::json!({}), Some("stop"))];
+    return Ok((serde_json::Value::Array(events), true));
+  }
+  Ok((
+    serde_json::json!({
+      "id": id, "object": "chat.completion", "created": created, "model": model.id,
+      "choices": [{ "index": 0, "message": { "role": "assistant", "content": text }, "finish_reason": "stop" }],
+      "usage": { "prompt_tokens": prompt_tokens, "completion_tokens": completion_tokens, "total_tokens": prompt_tokens + completion_tokens }
+    }),
+    false,
+  ))
+}
+
+fn handle(mut req: Request, app: &AppHandle) {
+  if let Err((status, message)) = authorize(&req) {
+    let _ = req.respond(error_response(status, message));
+    return;
+  }
+  let path = req.url().split('?').next().unwrap_or("").trim_end_matches('/').to_string();
+  let resp = match (req.method().as_str(), path.as_str()) {
+    ("GET", "/v1/models") => Response::from_string(list_models(app).to_string()).with_header(json_header()),
+    ("POST", "/v1/chat/completions") => match chat_completion(&mut req, app) {
+      Ok((serde_json::Value::Array(events), true)) => {
+        let body: String = events.iter().map(|e| format!("data: {}\n\n", e)).chain(["data: [DONE]\n\n".to_string()]).collect();
+        Response::from_string(body).with_header(httpd::content_type("text/event-stream"))
+      }
+      Ok((value, _)) => Response::from_string(value.to_string()).with_header(json_header()),
+      Err((status, message)) => error_response(status, &message),
+    },
+    _ => error_response(404, &format!("unknown endpoint {} {}", req.method(), path)),
+  };
+  let _ = req.respond(resp);
+}
+
+// bind the configured address and serve until stopped
+fn start(app: &AppHandle, api: &mut OpenAiApi) -> Result<(), String> {
+  if api.running.is_some() {
+    return Ok(());
+  }
+  let status = api.status();
+  let ip: IpAddr = status.bind.parse().map_err(|_| format!("{} is not an IP address", status.bind))?;
+  let app = app.clone();
+  let server = HttpServer::start((ip, status.port), move |req| handle(req, &app))
+    .map_err(|e| format!("failed to start the API server on {}:{}: {}", ip, status.port, e))?;
+  let host = if ip.is_unspecified() { DEFAULT_BIND.to_string() } else { ip.to_string() };
+  let url = format!("http://{}:{}/v1", host, server.port);
+  api.running = Some(Running { server, url });
+  Ok(())
+}
+
+fn stop(api: &mut OpenAiApi) {
+  if let Some(running) = api.running.take() {
+    running.server.stop();
+  }
+}
+
+// start the API at launch when it was left on
+pub fn start_enabled(app: &AppHandle) {
+  if !settings::get().openai_api {
+    return;
+  }
+  let state = app.state::<Mutex<OpenAiApi>>();
+  let mut api = state.locked();
+  if let Err(e) = start(app, &mut api) {
+    events::log(e);
+  }
+}
+
+// ------------------ Tauri commands ------------------
+
+#[tauri::command]
+pub fn get_openai_api_status(api: tauri::State<'_, Mutex<OpenAiApi>>) -> ApiStatus {
+  api.locked().status()
+}
+
+// turn the local /v1/chat/completions and /v1/models endpoint on or off; the choice, address and
+// port are remembered. Binding anything but loopback exposes the model to the network, so an API
+// key is required then
+#[tauri::command]
+pub fn set_openai_api(
+  enabled: bool,
+  bind: Option<String>,
+  port: Option<u16>,
+  api_key: Option<String>,
+  app: AppHandle,
+  api: tauri::State<'_, Mutex<OpenAiApi>>
+) -> Result<ApiStatus, AppError> {
+  let bind = bind.map(|b| b.trim().to_string()).filter(|b| !b.is_empty());
+  let api_key = api_key.map(|k| k.trim().to_string()).filter(|k| !k.is_empty());
+  if let Some(b) = &bind {
+    let ip: IpAddr = b.parse().map_err(|_| format!("{} is not an IP address", b))?;
+    if !ip.is_loopback() && api_key.is_none() {
+      return Err(AppError::InvalidInput("Set an API key before making the API reachable from other machines".into()));
+    }
+  }
+  settings::update(|s| {
+    s.openai_api = enabled;
+    s.openai_api_bind = bind;
+    s.openai_api_port = port;
+    s.openai_api_key = api_key;
+  })?;
+  let mut api = api.locked();
+  // restart so a changed address or port takes effect
+  stop(&mut api);
+  if enabled {
+    start(&app, &mut api)?;
+  }
+  Ok(api.status())
+}
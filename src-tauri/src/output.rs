@@ -0,0 +1,357 @@
+// src-tauri/src/output.rs
+use std::sync::{Mutex, OnceLock};
+
+use regex::{Captures, Regex};
+use tauri::{Manager, Window};
+
+use crate::annotate::{self, AnnotatedToken, ReadingSystem};
+use crate::engine;
+use crate::error::{AppError, LockExt};
+use crate::lang::{self, Script};
+use crate::model_config::ModelConfig;
+use crate::ModelManager;
+
+// Shape of a result returned to the frontend; commands leave the model's answer untouched when unset
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+  // markdown stripped to plain text
+  Text,
+  // tidied markdown
+  Markdown,
+  Html,
+  // html with <ruby> readings over kanji (furigana) or hanzi (pinyin)
+  Ruby,
+}
+
+fn inline_pattern() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| {
+    Regex::new(r"`(?P<code>[^`]+)`|\*\*(?P<bold>[^*]+)\*\*|__(?P<bold2>[^_]+)__|\*(?P<em>[^*\s][^*]*)\*|\[(?P<label>[^\]]+)\]\((?P<url>[^)\s]+)\)").unwrap()
+  })
+}
+
+fn list_item(line: &str) -> Option<(bool, &str)> {
+  let t = line.trim_start();
+  if let Some(rest) = t.strip_prefix("- ").or(t.strip_prefix("* ")).or(t.strip_prefix("+ ")) {
+    return Some((false, rest));
+  }
+  let digits = t.chars().take_while(|c| c.is_ascii_digit()).count();
+  (digits > 0 && t[digits..].starts_with(". ")).then(|| (true, &t[digits + 2..]))
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+  let level = line.chars().take_while(|c| *c == '#').count();
+  ((1..=6).contains(&level) && line[level..].starts_with(' ')).then(|| (level, line[level..].trim()))
+}
+
+fn escape_html(s: &str) -> String {
+  s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+// models like to wrap the whole answer in a ```markdown fence
+fn unwrap_fence(text: &str) -> &str {
+  let t = text.trim();
+  if let (Some(first), true) = (t.lines().next(), t.ends_with("```")) {
+    let lang = first.trim_start_matches("```").trim();
+    // a bare fence only counts as a wrapper when there are no fences inside it
+    let wrapper = matches!(lang, "markdown" | "md") || (matches!(lang, "" | "text") && t.matches("```").count() == 2);
+    if first.starts_with("```") && wrapper {
+      return t[first.len()..t.len() - 3].trim();
+    }
+  }
+  t
+}
+
+// trailing spaces trimmed, runs of blank lines collapsed, unterminated code fences closed
+pub fn to_markdown(raw: &str) -> String {
+  let mut out: Vec<&str> = Vec::new();
+  let mut fences = 0;
+  for line in unwrap_fence(raw).lines().map(|l| l.trim_end()) {
+    if line.is_empty() && out.last().is_none_or(|l| l.is_empty()) {
+      continue;
+    }
+    if line.trim_start().starts_with("```") {
+      fences += 1;
+    }
+    out.push(line);
+  }
+  let mut text = out.join("\n").trim().to_string();
+  if fences % 2 == 1 {
+    text.push_str("\n```");
+  }
+  text
+}
+
+// markdown syntax removed; links become "label (url)"
+pub fn to_text(raw: &str) -> String {
+  let md = to_markdown(raw);
+  let mut lines = Vec::new();
+  for line in md.lines() {
+    if line.trim_start().starts_with("```") {
+      continue;
+    }
+    let line = match (heading(line), list_item(line)) {
+      (Some((_, title)), _) => title.to_string(),
+      (_, Some((false, item))) => format!("• {}", item),
+      _ => line.trim_start_matches("> ").to_string(),
+    };
+    let line = inline_pattern().replace_all(&line, |c: &Captures| {
+      if let Some(url) = c.name("url") {
+        return format!("{} ({})", &c["label"], url.as_str());
+      }
+      ["code", "bold", "bold2", "em"].iter().find_map(|n| c.name(n)).map(|m| m.as_str().to_string()).unwrap_or_default()
+    });
+    lines.push(line.into_owned());
+  }
+  lines.join("\n")
+}
+
+// links the html may carry; others (javascript:, data:, relative) are shown as their label
+fn safe_url(url: &str) -> bool {
+  let url = url.to_ascii_lowercase();
+  ["http://", "https://", "mailto:"].iter().any(|scheme| url.starts_with(scheme))
+}
+
+// inline markdown to html; every piece of the model's text is escaped on its way in
+fn inline_html(text: &str) -> String {
+  let mut html = String::new();
+  let mut last = 0;
+  for c in inline_pattern().captures_iter(text) {
+    let whole = c.get(0).unwrap();
+    html.push_str(&escape_html(&text[last..whole.start()]));
+    last = whole.end();
+    let element = if let Some(m) = c.name("code") {
+      format!("<code>{}</code>", escape_html(m.as_str()))
+    } else if let Some(m) = c.name("bold").or(c.name("bold2")) {
+      format!("<strong>{}</strong>", escape_html(m.as_str()))
+    } else if let Some(m) = c.name("em") {
+      format!("<em>{}</em>", escape_html(m.as_str()))
+    } else if safe_url(&c["url"]) {
+      format!("<a href=\"{}\">{}</a>", escape_html(&c["url"]), escape_html(&c["label"]))
+    } else {
+      escape_html(&c["label"])
+    };
+    html.push_str(&element);
+  }
+  html.push_str(&escape_html(&text[last..]));
+  html
+}
+
+// block-level markdown (headings, lists, quotes, code fences, paragraphs) to html;
+// `inline` renders the text inside each block
+fn blocks_to_html(md: &str, inline: &dyn Fn(&str) -> String) -> String {
+  let mut html = Vec::new();
+  let mut paragraph: Vec<String> = Vec::new();
+  let mut list: Option<(bool, Vec<String>)> = None;
+  let mut code: Option<Vec<String>> = None;
+
+  fn flush(html: &mut Vec<String>, paragraph: &mut Vec<String>, list: &mut Option<(bool, Vec<String>)>) {
+    if !paragraph.is_empty() {
+      html.push(format!("<p>{}</p>", paragraph.join("<br>\n")));
+      paragraph.clear();
+    }
+    if let Some((ordered, items)) = list.take() {
+      let tag = if ordered { "ol" } else { "ul" };
+      html.push(format!("<{}>\n{}\n</{}>", tag, items.join("\n"), tag));
+    }
+  }
+
+  for line in md.lines() {
+    if line.trim_start().starts_with("```") {
+      match code.take() {
+        Some(lines) => html.push(format!("<pre><code>{}</code></pre>", escape_html(&lines.join("\n")))),
+        None => {
+          flush(&mut html, &mut paragraph, &mut list);
+          code = Some(Vec::new());
+        }
+      }
+      continue;
+    }
+    if let Some(lines) = code.as_mut() {
+      lines.push(line.to_string());
+      continue;
+    }
+    if line.trim().is_empty() {
+      flush(&mut html, &mut paragraph, &mut list);
+    } else if let Some((level, title)) = heading(line) {
+      flush(&mut html, &mut paragraph, &mut list);
+      html.push(format!("<h{}>{}</h{}>", level, inline(title), level));
+    } else if let Some((ordered, item)) = list_item(line) {
+      if !paragraph.is_empty() || list.as_ref().is_some_and(|(o, _)| *o != ordered) {
+        flush(&mut html, &mut paragraph, &mut list);
+      }
+      list.get_or_insert((ordered, Vec::new())).1.push(format!("<li>{}</li>", inline(item)));
+    } else if let Some(quote) = line.strip_prefix("> ") {
+      flush(&mut html, &mut paragraph, &mut list);
+      html.push(format!("<blockquote>{}</blockquote>", inline(quote)));
+    } else {
+      if list.is_some() {
+        flush(&mut html, &mut paragraph, &mut list);
+      }
+      paragraph.push(inline(line.trim()));
+    }
+  }
+  if let Some(lines) = code {
+    html.push(format!("<pre><code>{}</code></pre>", escape_html(&lines.join("\n"))));
+  }
+  flush(&mut html, &mut paragraph, &mut list);
+  html.join("\n")
+}
+
+pub fn to_html(raw: &str) -> String {
+  blocks_to_html(&to_markdown(raw), &inline_html)
+}
+
+// Base text with its reading, as returned by the model
+#[derive(Clone, Debug, serde::Deserialize)]
+struct RubySegment {
+  text: String,
+  #[serde(default)]
+  reading: String,
+}
+
+fn ruby_schema() -> serde_json::Value {
+  serde_json::json!({
+    "type": "object",
+    "properties": {
+      "segments": {
+        "type": "array",
+        "items": {
+          "type": "object",
+          "properties": { "text": { "type": "string" }, "reading": { "type": "string" } },
+          "required": ["text", "reading"]
+        }
+      }
+    },
+    "required": ["segments"]
+  })
+}
+
+fn has_han(text: &str) -> bool {
+  text.chars().any(|c| matches!(c as u32, 0x4E00..=0x9FFF | 0x3400..=0x4DBF))
+}
+
+// ask the model to split a line into words with readings when no local dictionary is installed;
+// None when its segments don't spell out the original line (the line is then rendered without readings)
+fn annotate_line(line: &str, japanese: bool, model_path: &str, config: &ModelConfig) -> Option<Vec<AnnotatedToken>> {
+  let reading = if japanese { "the hiragana reading (furigana) of words containing kanji" } else { "the pinyin with tone marks of each word" };
+  let prompt = format!(
+    "Split the following text into words. For each word give its exact text and {}. \
+     Use an empty reading for kana, punctuation, numbers and latin text. \
+     The texts joined together must reproduce the input exactly.\n\
+     Reply with JSON only: {{\"segments\": [{{\"text\": \"...\", \"reading\": \"...\"}}]}}\n\nText: {}",
+    reading, line
+  );
+  let max_tokens = engine::estimate_tokens(line) * 12 + 64;
+  let value = engine::generate_json(model_path, config, &prompt, max_tokens, &ruby_schema()).ok()?;
+  let segments: Vec<RubySegment> = serde_json::from_value(value.get("segments")?.clone()).ok()?;
+  let joined: String = segments.iter().map(|s| s.text.as_str()).collect();
+  if joined.split_whitespace().collect::<String>() != line.split_whitespace().collect::<String>() {
+    return None;
+  }
+  Some(
+    segments
+      .into_iter()
+      .map(|s| {
+        let reading = s.reading.trim().to_string();
+        AnnotatedToken { reading: (!reading.is_empty()).then_some(reading), text: s.text }
+      })
+      .collect(),
+  )
+}
+
+fn ruby_html(tokens: &[AnnotatedToken]) -> String {
+  tokens
+    .iter()
+    .map(|t| match &t.reading {
+      Some(r) if has_han(&t.text) && *r != t.text => {
+        format!("<ruby>{}<rp>(</rp><rt>{}</rt><rp>)</rp></ruby>", escape_html(&t.text), escape_html(r))
+      }
+      _ => escape_html(&t.text),
+    })
+    .collect()
+}
+
+// html where every line with kanji/hanzi carries ruby readings, from the bundled
+// dictionaries when present and the model otherwise
+pub fn to_ruby(raw: &str, lang: &str, model_path: &str, config: &ModelConfig) -> String {
+  let md = to_markdown(raw);
+  // "auto" or a pair without a clear language: decide from the text itself
+  let japanese = match lang::dominant_script(&md) {
+    Some(Script::Kana) => true,
+    Some(Script::Han) => false,
+    _ => lang::script_for(lang) == Script::Kana,
+  };
+  let system = if japanese {
+    ReadingSystem::Furigana
+  } else {
+    annotate::default_system(lang).filter(|s| *s != ReadingSystem::Furigana).unwrap_or(ReadingSystem::Pinyin)
+  };
+  let inline = |text: &str| {
+    let plain = to_text(text);
+    if !has_han(&plain) {
+      return inline_html(text);
+    }
+    let tokens = annotate::annotate(&plain, system).map(|a| a.tokens).ok().or_else(|| annotate_line(&plain, japanese, model_path, config));
+    match tokens {
+      Some(tokens) => ruby_html(&tokens),
+      None => escape_html(&plain),
+    }
+  };
+  blocks_to_html(&md, &inline)
+}
+
+// convert a raw model answer to `format`; `lang` is the language of the answer
+pub fn format_output(raw: &str, format: OutputFormat, lang: &str, model_path: &str, config: &ModelConfig) -> String {
+  match format {
+    OutputFormat::Text => to_text(raw),
+    OutputFormat::Markdown => to_markdown(raw),
+    OutputFormat::Html => to_html(raw),
+    OutputFormat::Ruby => to_ruby(raw, lang, model_path, config),
+  }
+}
+
+// apply an optional per-request format (None keeps the answer as the model wrote it)
+pub fn apply(raw: String, format: Option<OutputFormat>, lang: &str, model_path: &str, config: &ModelConfig) -> String {
+  match format {
+    Some(f) => format_output(&raw, f, lang, model_path, config),
+    None => raw,
+  }
+}
+
+// ------------------ Tauri commands ------------------
+
+// reformat text the app already has (e.g. a saved translation) without regenerating it
+#[tauri::command(async)]
+pub fn format_text(
+  text: String,
+  format: OutputFormat,
+  lang: Option<String>,
+  model_id: Option<String>,
+  window: Window
+) -> Result<String, AppError> {
+  let (model, config) = window.state::<Mutex<ModelManager>>().locked().model_for_request(model_id.as_deref())?;
+  Ok(format_output(&text, format, lang.as_deref().unwrap_or("auto"), &model.path, &config))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn only_web_and_mail_links_are_kept() {
+    assert_eq!(inline_html("[site](https://example.org/?a=1&b=2)"), "<a href=\"https://example.org/?a=1&amp;b=2\">site</a>");
+    assert_eq!(inline_html("[mail](mailto:a@b.org)"), "<a href=\"mailto:a@b.org\">mail</a>");
+    assert_eq!(inline_html("[click](javascript:alert%281%29)"), "click");
+    assert_eq!(inline_html("[x](JavaScript:alert`1`)"), "x");
+    assert_eq!(inline_html("[x](data:text/html,hi)"), "x");
+  }
+
+  #[test]
+  fn text_and_labels_are_escaped() {
+    assert_eq!(inline_html("<b>hi</b>"), "&lt;b&gt;hi&lt;/b&gt;");
+    assert_eq!(inline_html("[<img src=x>](https://a.org/\"onmouseover=x)"), "<a href=\"https://a.org/&quot;onmouseover=x\">&lt;img src=x&gt;</a>");
+    assert_eq!(inline_html("**<i>** and `<s>`"), "<strong>&lt;i&gt;</strong> and <code>&lt;s&gt;</code>");
+  }
+}
@@ -0,0 +1,283 @@
+// src-tauri/src/phrasebook.rs
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::error::{AppError, LockExt};
+use crate::lang;
+use crate::speech;
+use crate::store;
+
+// packs shipped with the app, installed on first start
+const BUILTIN_PACKS: &[&str] = &[
+  include_str!("../phrasebooks/travel-de.json"),
+  include_str!("../phrasebooks/travel-es.json"),
+  include_str!("../phrasebooks/travel-fr.json"),
+];
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Phrase {
+  pub id: String,
+  pub category: String,
+  pub text: String,
+  // meaning in English
+  #[serde(default)]
+  pub gloss: Option<String>,
+  // pre-synthesized audio, relative to the pack folder
+  #[serde(default)]
+  pub audio: Option<String>,
+}
+
+// A pack as stored in ./data/phrasebooks/<id>/pack.json
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct PhrasePack {
+  pub id: String,
+  pub name: String,
+  pub lang: String,
+  #[serde(default)]
+  pub version: u32,
+  pub phrases: Vec<Phrase>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct PhrasebookSummary {
+  pub id: String,
+  pub name: String,
+  pub lang: String,
+  pub version: u32,
+  pub categories: Vec<String>,
+  pub phrases: usize,
+}
+
+// A phrase with its audio resolved to an absolute path (if any exists yet)
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct PhraseView {
+  pub pack_id: String,
+  pub id: String,
+  pub category: String,
+  pub text: String,
+  pub gloss: Option<String>,
+  pub audio_path: Option<String>,
+}
+
+fn packs_dir() -> PathBuf {
+  store::data_file("phrasebooks")
+}
+
+fn pack_dir(id: &str) -> PathBuf {
+  packs_dir().join(id)
+}
+
+fn valid_id(id: &str) -> bool {
+  !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn parse_pack(json: &str, origin: &str) -> Result<PhrasePack, String> {
+  let pack: PhrasePack = serde_json::from_str(json).map_err(|e| format!("invalid phrasebook {}: {}", origin, e))?;
+  if !valid_id(&pack.id) {
+    return Err(format!("invalid phrasebook id '{}' in {}", pack.id, origin));
+  }
+  Ok(pack)
+}
+
+// Installed phrasebook packs (managed by Tauri)
+pub struct Phrasebooks {
+  packs: Vec<PhrasePack>,
+}
+
+impl Phrasebooks {
+  pub fn load() -> Self {
+    // install built-in packs that are missing or older than the shipped version
+    for json in BUILTIN_PACKS {
+      if let Ok(pack) = parse_pack(json, "built-in pack") {
+        let file = pack_dir(&pack.id).join("pack.json");
+        let installed: Option<PhrasePack> = fs::read_to_string(&file).ok().and_then(|s| serde_json::from_str(&s).ok());
+        if installed.is_none_or(|p| p.version < pack.version) {
+          let _ = store::save_json(&file, &pack);
+        }
+      }
+    }
+
+    let mut packs: Vec<PhrasePack> = fs::read_dir(packs_dir())
+      .map(|rd| {
+        rd.flatten()
+          .filter_map(|e| fs::read_to_string(e.path().join("pack.json")).ok())
+          .filter_map(|s| parse_pack(&s, "installed pack").ok())
+          .collect()
+      })
+      .unwrap_or_default();
+    packs.sort_by(|a, b| a.id.cmp(&b.id));
+    Self { packs }
+  }
+
+  // every phrase in `lang` across installed packs
+  pub fn phrases(&self, lang: &str) -> Vec<Phrase> {
+    self.packs.iter().filter(|p| lang::same_language(&p.lang, lang)).flat_map(|p| p.phrases.iter().cloned()).collect()
+  }
+
+  fn get(&self, id: &str) -> Result<&PhrasePack, String> {
+    self.packs.iter().find(|p| p.id == id).ok_or(format!("Phrasebook '{}' not found", id))
+  }
+
+  // copy a pack (pack.json, or a folder containing it plus audio files) into ./data/phrasebooks
+  pub fn import(&mut self, source: &Path) -> Result<PhrasebookSummary, String> {
+    let (file, folder) = if source.is_dir() {
+      (source.join("pack.json"), source.to_path_buf())
+    } else {
+      (source.to_path_buf(), source.parent().map(|p| p.to_path_buf()).unwrap_or_default())
+    };
+    let json = fs::read_to_string(&file).map_err(|e| format!("failed to read {}: {}", file.to_string_lossy(), e))?;
+    let mut pack = parse_pack(&json, &file.to_string_lossy())?;
+
+    let dest = pack_dir(&pack.id);
+    for phrase in pack.phrases.iter_mut() {
+      let Some(audio) = phrase.audio.clone() else { continue };
+      let from = folder.join(&audio);
+      let name = Path::new(&audio).file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+      // packs may reference audio that wasn't bundled; it will be synthesized on demand instead
+      if name.is_empty() || !from.is_file() {
+        phrase.audio = None;
+        continue;
+      }
+      let to = dest.join("audio").join(&name);
+      fs::create_dir_all(dest.join("audio")).map_err(|e| format!("failed to create {}: {}", dest.to_string_lossy(), e))?;
+      fs::copy(&from, &to).map_err(|e| format!("failed to copy {}: {}", from.to_string_lossy(), e))?;
+      phrase.audio = Some(format!("audio/{}", name));
+    }
+    store::save_json(&dest.join("pack.json"), &pack)?;
+
+    let summary = summarize(&pack);
+    self.packs.retain(|p| p.id != pack.id);
+    self.packs.push(pack);
+    self.packs.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(summary)
+  }
+
+  pub fn packs(&self) -> &[PhrasePack] {
+    &self.packs
+  }
+
+  // install or replace a pack received without its audio folder (e.g. from sync)
+  pub fn install(&mut self, mut pack: PhrasePack) -> Result<(), String> {
+    if !valid_id(&pack.id) {
+      return Err(format!("invalid phrasebook id '{}'", pack.id));
+    }
+    // keep audio already on disk for phrases we have
+    let existing = self.packs.iter().find(|p| p.id == pack.id);
+    for phrase in pack.phrases.iter_mut() {
+      phrase.audio = existing.and_then(|p| p.phrases.iter().find(|x| x.id == phrase.id)).and_then(|x| x.audio.clone());
+    }
+    store::save_json(&pack_dir(&pack.id).join("pack.json"), &pack)?;
+    self.packs.retain(|p| p.id != pack.id);
+    self.packs.push(pack);
+    self.packs.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(())
+  }
+
+  pub fn remove(&mut self, id: &str) -> Result<(), String> {
+    self.get(id)?;
+    fs::remove_dir_all(pack_dir(id)).map_err(|e| format!("failed to remove phrasebook '{}': {}", id, e))?;
+    self.packs.retain(|p| p.id != id);
+    Ok(())
+  }
+
+  // record audio synthesized for a phrase so later lookups reuse it
+  fn set_audio(&mut self, pack_id: &str, phrase_id: &str, audio: &str) -> Result<(), String> {
+    let pack = self.packs.iter_mut().find(|p| p.id == pack_id).ok_or(format!("Phrasebook '{}' not found", pack_id))?;
+    if let Some(phrase) = pack.phrases.iter_mut().find(|p| p.id == phrase_id) {
+      phrase.audio = Some(audio.to_string());
+    }
+    store::save_json(&pack_dir(pack_id).join("pack.json"), pack)
+  }
+}
+
+fn summarize(pack: &PhrasePack) -> PhrasebookSummary {
+  let mut categories: Vec<String> = Vec::new();
+  for p in &pack.phrases {
+    if !categories.contains(&p.category) {
+      categories.push(p.category.clone());
+    }
+  }
+  PhrasebookSummary {
+    id: pack.id.clone(),
+    name: pack.name.clone(),
+    lang: pack.lang.clone(),
+    version: pack.version,
+    categories,
+    phrases: pack.phrases.len(),
+  }
+}
+
+fn view(pack: &PhrasePack, phrase: &Phrase) -> PhraseView {
+  let audio_path = phrase
+    .audio
+    .as_ref()
+    .map(|a| pack_dir(&pack.id).join(a))
+    .filter(|p| p.is_file())
+    .map(|p| p.to_string_lossy().to_string());
+  PhraseView {
+    pack_id: pack.id.clone(),
+    id: phrase.id.clone(),
+    category: phrase.category.clone(),
+    text: phrase.text.clone(),
+    gloss: phrase.gloss.clone(),
+    audio_path,
+  }
+}
+
+// ------------------ Tauri commands ------------------
+
+#[tauri::command]
+pub fn list_phrasebooks(books: tauri::State<'_, Mutex<Phrasebooks>>) -> Vec<PhrasebookSummary> {
+  books.locked().packs.iter().map(summarize).collect()
+}
+
+// phrases of every pack in `lang`, optionally limited to one category
+#[tauri::command]
+pub fn get_phrases(category: Option<String>, lang: String, books: tauri::State<'_, Mutex<Phrasebooks>>) -> Vec<PhraseView> {
+  let books = books.locked();
+  books
+    .packs
+    .iter()
+    .filter(|p| p.lang.eq_ignore_ascii_case(&lang))
+    .flat_map(|pack| {
+      pack
+        .phrases
+        .iter()
+        .filter(|ph| category.as_deref().is_none_or(|c| ph.category.eq_ignore_ascii_case(c)))
+        .map(move |ph| view(pack, ph))
+    })
+    .collect()
+}
+
+// path to the phrase's audio, synthesizing and caching it when the pack has none
+#[tauri::command(async)]
+pub fn get_phrase_audio(pack_id: String, phrase_id: String, books: tauri::State<'_, Mutex<Phrasebooks>>) -> Result<String, AppError> {
+  let (lang, text, existing) = {
+    let books = books.locked();
+    let pack = books.get(&pack_id)?;
+    let phrase = pack.phrases.iter().find(|p| p.id == phrase_id).ok_or(format!("Phrase '{}' not found in '{}'", phrase_id, pack_id))?;
+    (pack.lang.clone(), phrase.text.clone(), view(pack, phrase).audio_path)
+  };
+  if let Some(path) = existing {
+    return Ok(path);
+  }
+  if !valid_id(&phrase_id) {
+    return Err(AppError::InvalidInput(format!("Phrase id '{}' can't be used as a file name", phrase_id)));
+  }
+  let relative = format!("audio/{}.wav", phrase_id);
+  let out = pack_dir(&pack_id).join(&relative);
+  speech::synthesize(&text, &lang, &out)?;
+  books.locked().set_audio(&pack_id, &phrase_id, &relative)?;
+  Ok(out.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub fn import_phrasebook(path: String, books: tauri::State<'_, Mutex<Phrasebooks>>) -> Result<PhrasebookSummary, AppError> {
+  Ok(books.locked().import(Path::new(&path))?)
+}
+
+#[tauri::command]
+pub fn remove_phrasebook(id: String, books: tauri::State<'_, Mutex<Phrasebooks>>) -> Result<(), AppError> {
+  Ok(books.locked().remove(&id)?)
+}
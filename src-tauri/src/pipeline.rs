@@ -0,0 +1,286 @@
+// src-tauri/src/pipeline.rs
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use tauri::{Manager, Window};
+
+use crate::engine;
+use crate::error::{AppError, LockExt};
+use crate::events;
+use crate::integrity;
+use crate::speech;
+use crate::store;
+use crate::translate;
+use crate::ModelManager;
+
+// One step of a pipeline. Stages pass text along, except transcribe (audio in) and speak (audio out)
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum PipelineStage {
+  // speech to text; `model` picks a whisper model ("whisper-small")
+  Transcribe {
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    lang: Option<String>,
+  },
+  // `source_lang` defaults to the language detected or produced by the previous stage
+  Translate {
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    source_lang: Option<String>,
+    target_lang: String,
+  },
+  // free-form generation; "{input}" in the template is replaced with the previous output
+  Prompt {
+    #[serde(default)]
+    model: Option<String>,
+    template: String,
+    #[serde(default)]
+    max_tokens: Option<u32>,
+  },
+  // text to speech; `voice` names a piper voice ("piper-de_DE-thorsten-medium"), else one for `lang`
+  Speak {
+    #[serde(default)]
+    voice: Option<String>,
+    #[serde(default)]
+    lang: Option<String>,
+  },
+}
+
+impl PipelineStage {
+  fn kind(&self) -> &'static str {
+    match self {
+      PipelineStage::Transcribe { .. } => "transcribe",
+      PipelineStage::Translate { .. } => "translate",
+      PipelineStage::Prompt { .. } => "prompt",
+      PipelineStage::Speak { .. } => "speak",
+    }
+  }
+
+  fn takes_audio(&self) -> bool {
+    matches!(self, PipelineStage::Transcribe { .. })
+  }
+
+  fn makes_audio(&self) -> bool {
+    matches!(self, PipelineStage::Speak { .. })
+  }
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Pipeline {
+  pub name: String,
+  #[serde(default)]
+  pub description: String,
+  pub stages: Vec<PipelineStage>,
+}
+
+impl Pipeline {
+  // every stage must accept what the previous one produces
+  fn validate(&self) -> Result<(), String> {
+    if self.name.trim().is_empty() {
+      return Err("Pipeline name is empty".into());
+    }
+    if self.stages.is_empty() {
+      return Err(format!("Pipeline '{}' has no stages", self.name));
+    }
+    for pair in self.stages.windows(2) {
+      if pair[0].makes_audio() != pair[1].takes_audio() {
+        let produced = if pair[0].makes_audio() { "audio" } else { "text" };
+        return Err(format!("Pipeline '{}': {} stage produces {}, which {} can't take", self.name, pair[0].kind(), produced, pair[1].kind()));
+      }
+    }
+    Ok(())
+  }
+}
+
+// Payload of "pipeline-stage" events
+#[derive(Clone, Debug, serde::Serialize)]
+struct StageEvent {
+  pipeline: String,
+  index: usize,
+  total: usize,
+  kind: String,
+  // "started" | "done" | "failed"
+  status: String,
+  output: Option<String>,
+  error: Option<String>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct StageResult {
+  pub kind: String,
+  pub output: String,
+  pub millis: u64,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct PipelineResult {
+  pub pipeline: String,
+  // text, or the path of the wav file when the last stage speaks
+  pub output: String,
+  pub audio: bool,
+  pub stages: Vec<StageResult>,
+}
+
+// Named pipelines persisted in ./data/pipelines.json (managed by Tauri); the file can be edited by hand
+pub struct Pipelines {
+  path: PathBuf,
+  list: Vec<Pipeline>,
+}
+
+impl Pipelines {
+  pub fn load() -> Self {
+    let path = store::data_file("pipelines.json");
+    let list = store::load_json(&path);
+    Self { path, list }
+  }
+
+  // pick up hand edits. An edit that doesn't parse is set aside like any damaged file and the
+  // pipelines from before it are written back, so a later save can't lose them
+  fn reload(&mut self) -> Result<(), String> {
+    let Ok(text) = fs::read(&self.path) else {
+      self.list.clear();
+      return Ok(());
+    };
+    match serde_json::from_slice(&text) {
+      Ok(list) => {
+        self.list = list;
+        Ok(())
+      }
+      Err(e) => {
+        integrity::quarantine(&self.path, &e.to_string());
+        store::save_json(&self.path, &self.list)?;
+        Err(format!("pipelines.json is not valid ({}); the edit was set aside and the previous pipelines kept", e))
+      }
+    }
+  }
+
+  fn get(&self, name: &str) -> Result<Pipeline, String> {
+    self.list.iter().find(|p| p.name == name).cloned().ok_or(format!("Pipeline '{}' not found", name))
+  }
+}
+
+// where spoken output goes: ./data/pipelines/<name>-<unix millis>.wav
+fn audio_out(name: &str) -> PathBuf {
+  let stamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0);
+  let safe: String = name.chars().map(|c| if c.is_alphanumeric() || c == '-' { c } else { '_' }).collect();
+  store::data_file("pipelines").join(format!("{}-{}.wav", safe, stamp))
+}
+
+// run one stage; `lang` tracks the language of the current text for later stages
+fn run_stage(window: &Window, pipeline: &str, stage: &PipelineStage, input: &str, lang: &mut Option<String>) -> Result<String, String> {
+  let model_for = |id: &Option<String>| window.state::<Mutex<ModelManager>>().locked().model_for_request(id.as_deref());
+  match stage {
+    PipelineStage::Transcribe { model, lang: hint } => {
+      let transcript = speech::transcribe_with(model.as_deref(), input, hint.as_deref())?;
+      *lang = transcript.lang;
+      Ok(transcript.text)
+    }
+    PipelineStage::Translate { model, source_lang, target_lang } => {
+      let (model, config) = model_for(model)?;
+      let source = source_lang.clone().or(lang.clone()).unwrap_or_else(|| "auto".into());
+      let out = translate::translate_text(&model.path, &config, input, &source, target_lang)?;
+      *lang = Some(target_lang.clone());
+      Ok(out)
+    }
+    PipelineStage::Prompt { model, template, max_tokens } => {
+      let (model, config) = model_for(model)?;
+      let prompt = template.replace("{input}", input);
+      engine::generate(&model.path, &config, &prompt, max_tokens.unwrap_or(engine::estimate_tokens(input) * 3 + 128))
+    }
+    PipelineStage::Speak { voice, lang: voice_lang } => {
+      let voice = match voice {
+        Some(name) => speech::voice_named(name).ok_or(format!("Voice '{}' not found", name))?,
+        None => {
+          let l = voice_lang.clone().or(lang.clone()).ok_or("speak stage needs a voice or a language")?;
+          speech::voice_for(&l).ok_or(format!("No voice for '{}'", l))?
+        }
+      };
+      let out = audio_out(pipeline);
+      speech::synthesize_with(&voice, input, &out)?;
+      Ok(out.to_string_lossy().to_string())
+    }
+  }
+}
+
+// ------------------ Tauri commands ------------------
+
+#[tauri::command]
+pub fn list_pipelines(pipelines: tauri::State<'_, Mutex<Pipelines>>) -> Vec<Pipeline> {
+  pipelines.locked().list.clone()
+}
+
+// add or replace a pipeline by name
+#[tauri::command]
+pub fn save_pipeline(pipeline: Pipeline, pipelines: tauri::State<'_, Mutex<Pipelines>>) -> Result<(), AppError> {
+  pipeline.validate()?;
+  let mut pipelines = pipelines.locked();
+  match pipelines.list.iter_mut().find(|p| p.name == pipeline.name) {
+    Some(existing) => *existing = pipeline,
+    None => pipelines.list.push(pipeline),
+  }
+  Ok(store::save_json(&pipelines.path, &pipelines.list)?)
+}
+
+#[tauri::command]
+pub fn remove_pipeline(name: String, pipelines: tauri::State<'_, Mutex<Pipelines>>) -> Result<(), AppError> {
+  let mut pipelines = pipelines.locked();
+  let before = pipelines.list.len();
+  pipelines.list.retain(|p| p.name != name);
+  if pipelines.list.len() == before {
+    return Err(AppError::not_found("Pipeline", &name));
+  }
+  Ok(store::save_json(&pipelines.path, &pipelines.list)?)
+}
+
+// run a pipeline end to end, emitting "pipeline-stage" as each stage starts and finishes;
+// `input` is text, or an audio file path when the first stage transcribes
+#[tauri::command(async)]
+pub fn run_pipeline(name: String, input: String, window: Window) -> Result<PipelineResult, AppError> {
+  // reload so hand edits to pipelines.json apply without a restart
+  let pipeline = {
+    let state = window.state::<Mutex<Pipelines>>();
+    let mut pipelines = state.locked();
+    pipelines.reload()?;
+    pipelines.get(&name)?
+  };
+  pipeline.validate()?;
+  if pipeline.stages[0].takes_audio() && !Path::new(&input).is_file() {
+    return Err(format!("Audio file not found: {}", input).into());
+  }
+
+  let total = pipeline.stages.len();
+  let mut current = input;
+  let mut lang = None;
+  let mut stages = Vec::new();
+  for (index, stage) in pipeline.stages.iter().enumerate() {
+    let event = |status: &str, output: Option<String>, error: Option<String>| StageEvent {
+      pipeline: name.clone(),
+      index,
+      total,
+      kind: stage.kind().into(),
+      status: status.into(),
+      output,
+      error,
+    };
+    events::emit(&window, "pipeline-stage", event("started", None, None));
+    let started = Instant::now();
+    match run_stage(&window, &name, stage, &current, &mut lang) {
+      Ok(out) => {
+        events::emit(&window, "pipeline-stage", event("done", Some(out.clone()), None));
+        stages.push(StageResult { kind: stage.kind().into(), output: out.clone(), millis: started.elapsed().as_millis() as u64 });
+        current = out;
+      }
+      Err(e) => {
+        events::emit(&window, "pipeline-stage", event("failed", None, Some(e.clone())));
+        return Err(format!("{} stage of '{}' failed: {}", stage.kind(), name, e).into());
+      }
+    }
+  }
+  let audio = pipeline.stages.last().is_some_and(|s| s.makes_audio());
+  Ok(PipelineResult { pipeline: name, output: current, audio, stages })
+}
@@ -0,0 +1,173 @@
+// src-tauri/src/preload.rs
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use sysinfo::System;
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::error::{AppError, LockExt};
+use crate::events;
+use crate::store;
+use crate::throttle::{PressureLevel, Throttle};
+use crate::ModelManager;
+
+// a model read less than this long ago is assumed to still be in the page cache
+const WARM_TTL: Duration = Duration::from_secs(600);
+// memory left free after a speculative load
+const HEADROOM: u64 = 1 << 30;
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct PreloadFile {
+  // speculative loads on UI focus; usage is still tracked when off
+  #[serde(default = "default_enabled")]
+  enabled: bool,
+  // "translate:fi>en" -> model id -> times used
+  usage: HashMap<String, HashMap<String, u32>>,
+}
+
+fn default_enabled() -> bool {
+  true
+}
+
+// Which model each action/language pair uses, persisted in ./data/preload.json (managed by Tauri)
+pub struct Preloader {
+  path: PathBuf,
+  file: PreloadFile,
+  warmed: HashMap<String, Instant>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct PreloadHint {
+  pub model_id: Option<String>,
+  pub started: bool,
+  // why nothing was started, or what was
+  pub reason: String,
+}
+
+fn usage_key(action: &str, langs: &str) -> String {
+  if langs.is_empty() {
+    action.to_string()
+  } else {
+    format!("{}:{}", action, langs)
+  }
+}
+
+// "fi" + "en" -> "fi>en", the language part of usage keys
+pub fn pair(source: &str, target: &str) -> String {
+  format!("{}>{}", source.to_lowercase(), target.to_lowercase())
+}
+
+impl Preloader {
+  pub fn load() -> Self {
+    let path = store::data_file("preload.json");
+    let mut file: PreloadFile = store::load_json(&path);
+    if !path.exists() {
+      file.enabled = true;
+    }
+    Self { path, file, warmed: HashMap::new() }
+  }
+
+  // most used model for the action and languages, falling back to the action alone
+  fn likely_model(&self, action: &str, langs: &str) -> Option<String> {
+    let best = |key: &str| self.file.usage.get(key).and_then(|m| m.iter().max_by_key(|(id, n)| (**n, std::cmp::Reverse(*id))).map(|(id, _)| id.clone()));
+    best(&usage_key(action, langs)).or_else(|| {
+      let prefix = format!("{}:", action);
+      let mut totals: HashMap<&String, u32> = HashMap::new();
+      for (key, models) in &self.file.usage {
+        if key == action || key.starts_with(&prefix) {
+          for (id, n) in models {
+            *totals.entry(id).or_default() += n;
+          }
+        }
+      }
+      totals.into_iter().max_by_key(|(id, n)| (*n, std::cmp::Reverse(*id))).map(|(id, _)| id.clone())
+    })
+  }
+}
+
+// note that `action` on `langs` ("" or pair()/a language code) ran on `model_id`
+pub fn record<R: Runtime>(manager: &impl Manager<R>, action: &str, langs: &str, model_id: &str) {
+  let preloader = manager.state::<Mutex<Preloader>>();
+  let mut preloader = preloader.locked();
+  *preloader.file.usage.entry(usage_key(action, langs)).or_default().entry(model_id.to_string()).or_default() += 1;
+  let _ = store::save_json(&preloader.path, &preloader.file);
+}
+
+fn model_files(path: &Path) -> Vec<PathBuf> {
+  if path.is_file() {
+    return vec![path.to_path_buf()];
+  }
+  fs::read_dir(path).map(|rd| rd.flatten().map(|e| e.path()).filter(|p| p.is_file()).collect()).unwrap_or_default()
+}
+
+// read the model's files once so the runtime starts from the page cache
+fn warm(files: Vec<PathBuf>) {
+  let mut buf = vec![0u8; 8 << 20];
+  for path in files {
+    let Ok(mut file) = File::open(&path) else { continue };
+    while matches!(file.read(&mut buf), Ok(n) if n > 0) {}
+  }
+}
+
+// ------------------ Tauri commands ------------------
+
+// called when the user focuses a feature (e.g. the translate box for fi->en): starts reading the
+// model that action usually needs into memory, when there is room for it
+#[tauri::command]
+pub fn preload_hint(action: String, langs: Option<String>, app: AppHandle) -> Result<PreloadHint, AppError> {
+  let hint = |model_id: Option<String>, started: bool, reason: &str| PreloadHint { model_id, started, reason: reason.into() };
+  let model_id = {
+    let preloader = app.state::<Mutex<Preloader>>();
+    let preloader = preloader.locked();
+    if !preloader.file.enabled {
+      return Ok(hint(None, false, "speculative loading is disabled"));
+    }
+    match preloader.likely_model(&action, langs.as_deref().unwrap_or("")) {
+      Some(id) => id,
+      None => return Ok(hint(None, false, "no usage recorded for this action yet")),
+    }
+  };
+  let Some(path) = app.state::<Mutex<ModelManager>>().locked().models.get(&model_id).map(|m| PathBuf::from(&m.path)) else {
+    return Ok(hint(Some(model_id), false, "model is no longer installed"));
+  };
+  if app.state::<Throttle>().report().level != PressureLevel::Normal {
+    return Ok(hint(Some(model_id), false, "system is under memory or thermal pressure"));
+  }
+
+  let files = model_files(&path);
+  let size: u64 = files.iter().filter_map(|f| fs::metadata(f).ok()).map(|m| m.len()).sum();
+  let mut sys = System::new();
+  sys.refresh_memory();
+  if sys.available_memory() < size + HEADROOM {
+    return Ok(hint(Some(model_id), false, "not enough free memory"));
+  }
+
+  {
+    let preloader = app.state::<Mutex<Preloader>>();
+    let mut preloader = preloader.locked();
+    if preloader.warmed.get(&model_id).is_some_and(|t| t.elapsed() < WARM_TTL) {
+      return Ok(hint(Some(model_id), false, "already preloaded"));
+    }
+    preloader.warmed.insert(model_id.clone(), Instant::now());
+  }
+  events::log(format!("preloading {} ({} bytes) for {}", model_id, size, usage_key(&action, langs.as_deref().unwrap_or(""))));
+  thread::spawn(move || warm(files));
+  Ok(hint(Some(model_id), true, "preloading"))
+}
+
+#[tauri::command]
+pub fn get_preload_enabled(preloader: tauri::State<'_, Mutex<Preloader>>) -> bool {
+  preloader.locked().file.enabled
+}
+
+#[tauri::command]
+pub fn set_preload_enabled(enabled: bool, preloader: tauri::State<'_, Mutex<Preloader>>) -> Result<(), AppError> {
+  let mut preloader = preloader.locked();
+  preloader.file.enabled = enabled;
+  Ok(store::save_json(&preloader.path, &preloader.file)?)
+}
@@ -0,0 +1,235 @@
+// src-tauri/src/procstats.rs
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind, MINIMUM_CPU_UPDATE_INTERVAL};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::{AppError, LockExt};
+use crate::gpu;
+use crate::hardware;
+use crate::ModelManager;
+
+// how often "model-stats" goes out while a model is running
+const INTERVAL: Duration = Duration::from_secs(2);
+// samples kept per model for request snapshots: ten minutes at INTERVAL
+const KEPT_SAMPLES: usize = 300;
+
+// Resource use of a model's process, counting the processes it started (a run.sh wrapper's runtime)
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ProcessStats {
+  pub model_id: String,
+  pub pid: u32,
+  // of one core, as top shows it: a runtime busy on 4 cores is at 400
+  pub cpu_percent: f32,
+  // CPU time used since the process started, all cores together
+  pub cpu_time_ms: u64,
+  // resident memory, what the model actually occupies in RAM
+  pub memory_bytes: u64,
+  pub virtual_memory_bytes: u64,
+  // None when the GPU doesn't report memory per process (only NVIDIA's does)
+  pub gpu_memory_bytes: Option<u64>,
+  pub uptime_secs: u64,
+}
+
+// What a model's processes used while one request was generated; sent as `resources` with the
+// request's final "model-output" event. Requests a llama-server answers at the same time share
+// its process, so each of them reports all of it
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ResourceSnapshot {
+  // the most resident memory seen during the request
+  pub peak_memory_bytes: u64,
+  // the most GPU memory seen while the model ran; None when it can't be told per process
+  pub peak_gpu_memory_bytes: Option<u64>,
+  // average over the request, of one core as in ProcessStats
+  pub cpu_percent: f32,
+}
+
+// where a request's resource use is counted from: when it started and the sampler's last look
+// at the process before that, if it had one
+#[derive(Clone, Copy, Debug)]
+pub struct UsageMark {
+  at: Instant,
+  before: Option<Sample>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Sample {
+  at: Instant,
+  cpu_percent: f32,
+  cpu_time_ms: u64,
+  memory_bytes: u64,
+  gpu_memory_bytes: Option<u64>,
+}
+
+// the process of each model that has been started and what the sampler saw of it
+struct Tracked {
+  pid: u32,
+  samples: VecDeque<Sample>,
+}
+
+fn tracked() -> MutexGuard<'static, HashMap<String, Tracked>> {
+  static TRACKED: OnceLock<Mutex<HashMap<String, Tracked>>> = OnceLock::new();
+  TRACKED.get_or_init(|| Mutex::new(HashMap::new())).locked()
+}
+
+// pids of the running model processes by model id
+fn model_pids(app: &AppHandle) -> Vec<(String, u32)> {
+  let state = app.state::<Mutex<ModelManager>>();
+  let mut mgr = state.locked();
+  let ids = mgr.running_models();
+  ids.into_iter().filter_map(|id| mgr.processes.get(&id).and_then(|c| c.id()).map(|pid| (id, pid))).collect()
+}
+
+fn refresh(sys: &mut System) {
+  sys.refresh_processes_specifics(ProcessesToUpdate::All, true, ProcessRefreshKind::nothing().with_cpu().with_memory().with_exe(UpdateKind::Never));
+}
+
+// GPU memory by pid when there is an NVIDIA GPU; asking nvidia-smi elsewhere is pointless
+fn gpu_memory() -> HashMap<u32, u64> {
+  if hardware::info(false).cuda {
+    gpu::process_memory()
+  } else {
+    HashMap::new()
+  }
+}
+
+// stats of each model process and its descendants, from the last two refreshes of `sys`
+fn collect(sys: &System, models: &[(String, u32)], gpu_memory: &HashMap<u32, u64>) -> Vec<ProcessStats> {
+  let mut children: HashMap<Pid, Vec<Pid>> = HashMap::new();
+  for (pid, process) in sys.processes() {
+    if let Some(parent) = process.parent() {
+      children.entry(parent).or_default().push(*pid);
+    }
+  }
+  models
+    .iter()
+    .filter_map(|(model_id, pid)| {
+      let root = sys.process(Pid::from_u32(*pid))?;
+      let mut stats = ProcessStats {
+        model_id: model_id.clone(),
+        pid: *pid,
+        cpu_percent: 0.0,
+        cpu_time_ms: 0,
+        memory_bytes: 0,
+        virtual_memory_bytes: 0,
+        gpu_memory_bytes: (!gpu_memory.is_empty()).then_some(0),
+        uptime_secs: root.run_time(),
+      };
+      let mut pending = vec![Pid::from_u32(*pid)];
+      while let Some(pid) = pending.pop() {
+        if let Some(process) = sys.process(pid) {
+          stats.cpu_percent += process.cpu_usage();
+          stats.cpu_time_ms += process.accumulated_cpu_time();
+          stats.memory_bytes += process.memory();
+          stats.virtual_memory_bytes += process.virtual_memory();
+        }
+        if let Some(used) = stats.gpu_memory_bytes.as_mut() {
+          *used += gpu_memory.get(&pid.as_u32()).copied().unwrap_or(0);
+        }
+        pending.extend(children.get(&pid).into_iter().flatten());
+      }
+      Some(stats)
+    })
+    .collect()
+}
+
+// keep what the sampler saw of each model for the snapshots of its requests, and forget the
+// processes that are gone
+fn record(models: &[(String, u32)], stats: &[ProcessStats]) {
+  let mut tracked = tracked();
+  tracked.retain(|id, t| models.iter().any(|(m, pid)| m == id && *pid == t.pid));
+  for s in stats {
+    let Some(model) = tracked.get_mut(&s.model_id).filter(|t| t.pid == s.pid) else { continue };
+    if model.samples.len() == KEPT_SAMPLES {
+      model.samples.pop_front();
+    }
+    model.samples.push_back(Sample {
+      at: Instant::now(),
+      cpu_percent: s.cpu_percent,
+      cpu_time_ms: s.cpu_time_ms,
+      memory_bytes: s.memory_bytes,
+      gpu_memory_bytes: s.gpu_memory_bytes,
+    });
+  }
+}
+
+// the process just started for `model_id`; its requests get resource snapshots from now on
+pub fn track(model_id: &str, pid: u32) {
+  tracked().insert(model_id.to_string(), Tracked { pid, samples: VecDeque::new() });
+}
+
+// taken when a request of `model_id` starts; None when its process isn't tracked. Only looks at
+// what the sampler has already seen, so starting a request doesn't scan every process
+pub fn mark(model_id: &str) -> Option<UsageMark> {
+  let tracked = tracked();
+  let model = tracked.get(model_id)?;
+  Some(UsageMark { at: Instant::now(), before: model.samples.back().copied() })
+}
+
+// what `model_id`'s processes used since `mark`, from the samples taken meanwhile. CPU use is the
+// CPU time between the last sample before the request and the last one during it; a request
+// shorter than INTERVAL has no sample of its own and reports the latest one (memory barely
+// changes once the model is loaded)
+pub fn snapshot(model_id: &str, mark: &UsageMark) -> Option<ResourceSnapshot> {
+  let tracked = tracked();
+  let samples = &tracked.get(model_id)?.samples;
+  let latest = samples.back()?;
+  let during: Vec<&Sample> = samples.iter().filter(|s| s.at >= mark.at).collect();
+  let peak = |value: fn(&Sample) -> Option<u64>| during.iter().filter_map(|s| value(s)).max().or(value(latest));
+  let cpu_percent = match (mark.before, during.last()) {
+    (Some(before), Some(last)) => {
+      let elapsed_ms = last.at.duration_since(before.at).as_millis().max(1) as f32;
+      last.cpu_time_ms.saturating_sub(before.cpu_time_ms) as f32 * 100.0 / elapsed_ms
+    }
+    _ => latest.cpu_percent,
+  };
+  Some(ResourceSnapshot {
+    peak_memory_bytes: peak(|s| Some(s.memory_bytes)).unwrap_or(0),
+    peak_gpu_memory_bytes: peak(|s| s.gpu_memory_bytes),
+    cpu_percent,
+  })
+}
+
+// send "model-stats" with every running model's stats every INTERVAL
+pub fn start(app: AppHandle) {
+  thread::spawn(move || {
+    let mut sys = System::new();
+    loop {
+      let models = model_pids(&app);
+      if models.is_empty() {
+        record(&models, &[]);
+      } else {
+        refresh(&mut sys);
+        let stats = collect(&sys, &models, &gpu_memory());
+        record(&models, &stats);
+        if !stats.is_empty() {
+          let _ = app.emit("model-stats", stats);
+        }
+      }
+      thread::sleep(INTERVAL);
+    }
+  });
+}
+
+// ------------------ Tauri commands ------------------
+
+// CPU, memory and uptime of `model_id`'s process, or of every running model's. CPU use is measured
+// over a short interval, so this takes a moment
+#[tauri::command(async)]
+pub fn get_process_stats(model_id: Option<String>, app: AppHandle) -> Result<Vec<ProcessStats>, AppError> {
+  let mut models = model_pids(&app);
+  if let Some(id) = &model_id {
+    models.retain(|(m, _)| m == id);
+    if models.is_empty() {
+      return Err(AppError::InvalidInput(format!("Model '{}' is not running", id)));
+    }
+  }
+  let mut sys = System::new();
+  refresh(&mut sys);
+  thread::sleep(MINIMUM_CPU_UPDATE_INTERVAL.max(Duration::from_millis(200)));
+  refresh(&mut sys);
+  Ok(collect(&sys, &models, &gpu_memory()))
+}
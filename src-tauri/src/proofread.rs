@@ -0,0 +1,161 @@
+// src-tauri/src/proofread.rs
+use std::sync::Mutex;
+
+use tauri::{Manager, Window};
+
+use crate::engine;
+use crate::error::{AppError, LockExt};
+use crate::lang;
+use crate::ModelManager;
+
+// One change between the original and the corrected text.
+// `start`/`end` are char offsets into the original text.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct Edit {
+  pub start: usize,
+  pub end: usize,
+  pub original: String,
+  pub replacement: String,
+  pub explanation: Option<String>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ProofreadResult {
+  pub corrected: String,
+  pub edits: Vec<Edit>,
+}
+
+fn build_prompt(text: &str, lang_name: &str) -> String {
+  format!(
+    "You are a careful {lang} proofreader. Correct spelling, grammar and punctuation in the text below \
+without changing its meaning or style.\n\
+Reply with JSON only, in this exact shape:\n\
+{{\"corrected\": \"<full corrected text>\", \"edits\": [{{\"original\": \"<wrong words>\", \"replacement\": \"<fixed words>\", \"explanation\": \"<short reason>\"}}]}}\n\
+If there is nothing to fix, return the text unchanged with an empty edits list.\n\nText:\n{text}",
+    lang = lang_name,
+    text = text
+  )
+}
+
+// turn the model's edit list into spans, searching forward so repeated words map to the right place
+fn locate_edits(text: &str, raw: &[serde_json::Value]) -> Vec<Edit> {
+  let mut edits = Vec::new();
+  let mut cursor = 0; // byte offset
+  for e in raw {
+    let original = e.get("original").and_then(|v| v.as_str()).unwrap_or("");
+    let replacement = e.get("replacement").and_then(|v| v.as_str()).unwrap_or("");
+    if original.is_empty() || original == replacement {
+      continue;
+    }
+    let found = text[cursor..].find(original).map(|i| i + cursor).or_else(|| text.find(original));
+    if let Some(byte_start) = found {
+      let byte_end = byte_start + original.len();
+      edits.push(Edit {
+        start: text[..byte_start].chars().count(),
+        end: text[..byte_end].chars().count(),
+        original: original.to_string(),
+        replacement: replacement.to_string(),
+        explanation: e.get("explanation").and_then(|v| v.as_str()).map(|s| s.to_string()),
+      });
+      cursor = byte_end;
+    }
+  }
+  edits.sort_by_key(|e| e.start);
+  edits
+}
+
+// word-level diff (LCS) between original and corrected, used when the model gives no edit list
+fn diff_edits(original: &str, corrected: &str) -> Vec<Edit> {
+  // (char offset, word)
+  let words = |s: &str| -> Vec<(usize, String)> {
+    let mut out = Vec::new();
+    let mut start = None;
+    for (i, c) in s.chars().enumerate() {
+      match (c.is_whitespace(), start) {
+        (false, None) => start = Some(i),
+        (true, Some(st)) => {
+          out.push((st, s.chars().skip(st).take(i - st).collect()));
+          start = None;
+        }
+        _ => {}
+      }
+    }
+    if let Some(st) = start {
+      out.push((st, s.chars().skip(st).collect()));
+    }
+    out
+  };
+  let a = words(original);
+  let b = words(corrected);
+
+  let mut lcs = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+  for i in (0..a.len()).rev() {
+    for j in (0..b.len()).rev() {
+      lcs[i][j] = if a[i].1 == b[j].1 { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+    }
+  }
+
+  let mut edits = Vec::new();
+  let (mut i, mut j) = (0, 0);
+  while i < a.len() || j < b.len() {
+    if i < a.len() && j < b.len() && a[i].1 == b[j].1 {
+      i += 1;
+      j += 1;
+      continue;
+    }
+    // collect a run of differing words on both sides
+    let (i0, j0) = (i, j);
+    while (i < a.len() || j < b.len()) && !(i < a.len() && j < b.len() && a[i].1 == b[j].1) {
+      if j >= b.len() || (i < a.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+        i += 1;
+      } else {
+        j += 1;
+      }
+    }
+    let (start, end) = if i > i0 {
+      (a[i0].0, a[i - 1].0 + a[i - 1].1.chars().count())
+    } else {
+      let pos = a.get(i0).map(|w| w.0).unwrap_or(original.chars().count());
+      (pos, pos)
+    };
+    edits.push(Edit {
+      start,
+      end,
+      original: a[i0..i].iter().map(|w| w.1.as_str()).collect::<Vec<_>>().join(" "),
+      replacement: b[j0..j].iter().map(|w| w.1.as_str()).collect::<Vec<_>>().join(" "),
+      explanation: None,
+    });
+  }
+  edits
+}
+
+fn parse_output(text: &str, output: &str) -> ProofreadResult {
+  if let Some(json) = engine::extract_json(output) {
+    if let Some(corrected) = json.get("corrected").and_then(|v| v.as_str()) {
+      let raw = json.get("edits").and_then(|v| v.as_array()).cloned().unwrap_or_default();
+      let mut edits = locate_edits(text, &raw);
+      if edits.is_empty() && corrected != text {
+        edits = diff_edits(text, corrected);
+      }
+      return ProofreadResult { corrected: corrected.to_string(), edits };
+    }
+  }
+  // no usable JSON: treat the whole reply as the corrected text
+  let corrected = output.trim().to_string();
+  let edits = diff_edits(text, &corrected);
+  ProofreadResult { corrected, edits }
+}
+
+// ------------------ Tauri commands ------------------
+
+#[tauri::command(async)]
+pub fn proofread(text: String, lang: String, model_id: Option<String>, window: Window) -> Result<ProofreadResult, AppError> {
+  if text.trim().is_empty() {
+    return Ok(ProofreadResult { corrected: text, edits: Vec::new() });
+  }
+  let (model, config) = window.state::<Mutex<ModelManager>>().locked().model_for_request(model_id.as_deref())?;
+  let prompt = build_prompt(&text, &lang::language_name(&lang));
+  let max_tokens = engine::estimate_tokens(&text) * 3 + 256;
+  let output = engine::generate(&model.path, &config, &prompt, max_tokens)?;
+  Ok(parse_output(&text, &output))
+}
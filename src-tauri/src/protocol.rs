@@ -0,0 +1,232 @@
+// src-tauri/src/protocol.rs
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::sampling::SamplingParams;
+
+// llama.cpp's interactive mode reads a line ending in "\" as "more input follows"
+const LLAMA_CONTINUATION: &str = "\\";
+// printed by llama.cpp before it waits for the next input, unless the model config names another
+pub const DEFAULT_REVERSE_PROMPT: &str = "User:";
+
+// How prompts are written to a running model process
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StdinProtocol {
+  // one line per prompt; line breaks inside the prompt become spaces
+  Raw,
+  // one {"id", "prompt", "sampling"} object per line, for wrappers that parse their input
+  JsonLines,
+  // llama.cpp -i: multi-line prompts with "\" continuations, turns ended by a reverse prompt
+  LlamaInteractive,
+}
+
+// What spawn_for_model started for a model
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+  // run.sh / run.bat shipped in the model folder
+  Wrapper,
+  // a bundled llama.cpp build
+  Runtime,
+  // llama-server from the bundled build; prompts go over HTTP, not stdin
+  Server,
+  // the python token printer used when nothing else is available
+  Mock,
+}
+
+impl StdinProtocol {
+  pub fn default_for(backend: Backend) -> Self {
+    match backend {
+      Backend::Runtime => StdinProtocol::LlamaInteractive,
+      Backend::Wrapper | Backend::Server | Backend::Mock => StdinProtocol::Raw,
+    }
+  }
+
+  // extra runtime arguments the protocol depends on
+  pub fn runtime_args(self, reverse_prompt: Option<&str>) -> Vec<String> {
+    match self {
+      StdinProtocol::LlamaInteractive => {
+        vec!["-i".into(), "-r".into(), reverse_prompt.unwrap_or(DEFAULT_REVERSE_PROMPT).to_string()]
+      }
+      StdinProtocol::Raw | StdinProtocol::JsonLines => Vec::new(),
+    }
+  }
+
+  // the bytes that submit `prompt` as one request; only JSON-lines can carry per-prompt sampling,
+  // the others are sampled as set when the process started
+  pub fn encode(self, request_id: u64, prompt: &str, sampling: &SamplingParams) -> String {
+    let lines: Vec<&str> = prompt.trim_end().lines().collect();
+    match self {
+      StdinProtocol::Raw => format!("{}\n", lines.iter().map(|l| l.trim()).filter(|l| !l.is_empty()).collect::<Vec<_>>().join(" ")),
+      StdinProtocol::JsonLines => format!("{}\n", serde_json::json!({ "id": request_id, "prompt": prompt, "sampling": sampling })),
+      StdinProtocol::LlamaInteractive => {
+        // a line that itself ends in "\" would otherwise swallow the line break after it
+        let lines: Vec<String> = lines.iter().map(|l| if l.ends_with(LLAMA_CONTINUATION) { format!("{} ", l) } else { l.to_string() }).collect();
+        format!("{}\n", lines.join(&format!("{}\n", LLAMA_CONTINUATION)))
+      }
+    }
+  }
+}
+
+// Counters a runtime reports about a request
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct RuntimeStats {
+  pub prompt_tokens: Option<u32>,
+  pub output_tokens: Option<u32>,
+  pub tokens_per_sec: Option<f32>,
+  pub millis: Option<u64>,
+}
+
+// One line of structured output from a wrapper or sidecar, e.g.
+// {"type": "token", "id": 3, "text": "Hola"} ... {"type": "done", "id": 3}.
+// `id` is the request id from a JSON-lines prompt; runners reading raw prompts leave it out
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OutputMessage {
+  Token {
+    #[serde(default)]
+    id: Option<u64>,
+    text: String,
+  },
+  Stats {
+    #[serde(default)]
+    id: Option<u64>,
+    #[serde(flatten)]
+    stats: RuntimeStats,
+  },
+  Done {
+    #[serde(default)]
+    id: Option<u64>,
+  },
+  Error {
+    #[serde(default)]
+    id: Option<u64>,
+    message: String,
+  },
+}
+
+impl OutputMessage {
+  // a structured line, or None for free text; wrappers that print these objects are recognized by
+  // them, nothing has to be configured
+  pub fn parse(line: &str) -> Option<Self> {
+    let line = line.trim();
+    if !line.starts_with('{') || !line.contains("\"type\"") {
+      return None;
+    }
+    serde_json::from_str(line).ok()
+  }
+
+  pub fn id(&self) -> Option<u64> {
+    match self {
+      OutputMessage::Token { id, .. } | OutputMessage::Stats { id, .. } | OutputMessage::Done { id } | OutputMessage::Error { id, .. } => *id,
+    }
+  }
+}
+
+// Output of a one-shot run written in structured messages, put back together
+pub struct Collected {
+  pub text: String,
+  pub stats: Option<RuntimeStats>,
+}
+
+// Ok(None) when `output` isn't structured; a runner's error message becomes the Err
+pub fn collect(output: &str) -> Result<Option<Collected>, String> {
+  let mut collected: Option<Collected> = None;
+  for message in output.lines().filter_map(OutputMessage::parse) {
+    let c = collected.get_or_insert(Collected { text: String::new(), stats: None });
+    match message {
+      OutputMessage::Token { text, .. } => c.text.push_str(&text),
+      OutputMessage::Stats { stats, .. } => c.stats = Some(stats),
+      OutputMessage::Done { .. } => break,
+      OutputMessage::Error { message, .. } => return Err(format!("runner error: {}", message)),
+    }
+  }
+  Ok(collected)
+}
+
+impl Backend {
+  // CLI runtimes and wrappers tend to print the prompt back before answering
+  pub fn echoes_prompt(self) -> bool {
+    matches!(self, Backend::Runtime | Backend::Wrapper)
+  }
+
+  // whether stdout may carry OutputMessage lines: wrappers and JSON-lines runners print them, while
+  // on a CLI runtime a JSON line is the model's own answer
+  pub fn structured_output(self, protocol: StdinProtocol) -> bool {
+    self == Backend::Wrapper || protocol == StdinProtocol::JsonLines
+  }
+}
+
+// write an encoded prompt to a process (its stdin or data socket)
+pub async fn write(input: &mut (impl AsyncWrite + Unpin), text: &str) -> Result<(), String> {
+  let written = match input.write_all(text.as_bytes()).await {
+    Ok(()) => input.flush().await,
+    Err(e) => Err(e),
+  };
+  written.map_err(|e| format!("failed to write to stdin: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn raw_prompts_are_one_line() {
+    let encoded = StdinProtocol::Raw.encode(1, "  Translate this:\n\n  Hola mundo \n", &SamplingParams::default());
+    assert_eq!(encoded, "Translate this: Hola mundo\n");
+  }
+
+  #[test]
+  fn json_lines_carry_id_and_sampling() {
+    let sampling = SamplingParams { temperature: Some(0.5), ..Default::default() };
+    let encoded = StdinProtocol::JsonLines.encode(7, "line one\nline two", &sampling);
+    assert!(encoded.ends_with('\n') && encoded.matches('\n').count() == 1, "{:?}", encoded);
+    let value: serde_json::Value = serde_json::from_str(&encoded).unwrap();
+    assert_eq!(value["id"], 7);
+    assert_eq!(value["prompt"], "line one\nline two");
+    assert_eq!(value["sampling"]["temperature"], 0.5);
+  }
+
+  #[test]
+  fn llama_lines_are_continued() {
+    let encoded = StdinProtocol::LlamaInteractive.encode(1, "first\nsecond\nthird\n", &SamplingParams::default());
+    assert_eq!(encoded, "first\\\nsecond\\\nthird\n");
+    // a backslash of the prompt's own isn't read as a continuation
+    let encoded = StdinProtocol::LlamaInteractive.encode(1, "C:\\dir\\\nnext", &SamplingParams::default());
+    assert_eq!(encoded, "C:\\dir\\ \\\nnext\n");
+  }
+
+  #[test]
+  fn parses_structured_lines() {
+    match OutputMessage::parse(r#" {"type": "token", "id": 3, "text": "Hola"} "#) {
+      Some(OutputMessage::Token { id: Some(3), text }) => assert_eq!(text, "Hola"),
+      other => panic!("{:?}", other),
+    }
+    match OutputMessage::parse(r#"{"type": "stats", "output_tokens": 12, "tokens_per_sec": 30.5}"#) {
+      Some(OutputMessage::Stats { id: None, stats }) => {
+        assert_eq!(stats.output_tokens, Some(12));
+        assert_eq!(stats.prompt_tokens, None);
+      }
+      other => panic!("{:?}", other),
+    }
+    assert!(matches!(OutputMessage::parse(r#"{"type":"done","id":3}"#), Some(OutputMessage::Done { id: Some(3) })));
+    assert_eq!(OutputMessage::parse(r#"{"type":"error","message":"oom"}"#).and_then(|m| m.id()), None);
+  }
+
+  #[test]
+  fn free_text_isnt_a_message() {
+    for line in ["Hola mundo", r#"{"text": "no type"}"#, r#"{"type": "unknown"}"#, r#"{"type": "token"}"#, r#"{"type": "token", "text": "#] {
+      assert!(OutputMessage::parse(line).is_none(), "{}", line);
+    }
+  }
+
+  #[test]
+  fn collects_a_structured_run() {
+    let output = "loading model\n{\"type\":\"token\",\"text\":\"Hel\"}\n{\"type\":\"token\",\"text\":\"lo\"}\n{\"type\":\"stats\",\"millis\":40}\n{\"type\":\"done\"}\n{\"type\":\"token\",\"text\":\"late\"}";
+    let collected = collect(output).unwrap().unwrap();
+    assert_eq!(collected.text, "Hello");
+    assert_eq!(collected.stats.and_then(|s| s.millis), Some(40));
+    assert!(collect("just text\n").unwrap().is_none());
+    assert_eq!(collect("{\"type\":\"error\",\"message\":\"oom\"}").err().as_deref(), Some("runner error: oom"));
+  }
+}
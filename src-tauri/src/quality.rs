@@ -0,0 +1,175 @@
+// src-tauri/src/quality.rs
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::thread;
+
+use tauri::{Manager, Runtime};
+
+use crate::engine;
+use crate::error::{AppError, LockExt};
+use crate::lang;
+use crate::store;
+use crate::ModelManager;
+
+// ./data/quality.json
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct QualitySettings {
+  // model used for quality estimation; None turns the job stage off
+  pub model_id: Option<String>,
+  // segments scoring below this (0..1) are flagged for review
+  pub threshold: f32,
+  // score machine-translated segments of document jobs
+  pub check_jobs: bool,
+}
+
+impl Default for QualitySettings {
+  fn default() -> Self {
+    Self { model_id: None, threshold: 0.6, check_jobs: true }
+  }
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct QualityEstimate {
+  // 0..1, higher is better
+  pub score: f32,
+  pub flagged: bool,
+  pub model_id: String,
+}
+
+fn settings_path() -> PathBuf {
+  store::data_file("quality.json")
+}
+
+pub fn settings() -> QualitySettings {
+  store::load_json(&settings_path())
+}
+
+// reference-free models (COMET-kiwi style, usually ONNX) ship a scorer next to them:
+// ./models/<id>/estimate.sh or estimate.bat reads {"source", "translation", ...} on stdin and
+// prints a score
+fn scorer(model_path: &Path) -> Option<Command> {
+  if !model_path.is_dir() {
+    return None;
+  }
+  let sh = model_path.join("estimate.sh");
+  let bat = model_path.join("estimate.bat");
+  if sh.exists() {
+    let mut c = Command::new("sh");
+    c.arg(sh);
+    Some(c)
+  } else if bat.exists() {
+    let mut c = Command::new("cmd");
+    c.arg("/C").arg(bat);
+    Some(c)
+  } else {
+    None
+  }
+}
+
+// a bare number or {"score": n}, as printed
+fn parse_score(out: &str) -> Option<f32> {
+  let out = out.trim();
+  match engine::extract_json(out) {
+    Some(json) => Some(json.get("score")?.as_f64()? as f32),
+    None => out.lines().rev().find_map(|l| l.trim().parse::<f32>().ok()),
+  }
+}
+
+fn run_scorer(mut c: Command, input: serde_json::Value) -> Result<f32, String> {
+  let mut child = c
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .map_err(|e| format!("Failed to start quality scorer: {}", e))?;
+  if let Some(mut stdin) = child.stdin.take() {
+    let text = input.to_string();
+    thread::spawn(move || {
+      let _ = stdin.write_all(text.as_bytes());
+    });
+  }
+  let out = child.wait_with_output().map_err(|e| format!("quality scorer failed: {}", e))?;
+  if !out.status.success() {
+    return Err(format!("quality scorer exited with {}: {}", out.status, String::from_utf8_lossy(&out.stderr).trim()));
+  }
+  let text = String::from_utf8_lossy(&out.stdout);
+  let score = parse_score(&text).ok_or_else(|| format!("quality scorer printed no score: {}", text.chars().take(200).collect::<String>()))?;
+  // scorers print 0..1 (COMET style) or 0..100
+  Ok(if score > 1.0 { score / 100.0 } else { score }.clamp(0.0, 1.0))
+}
+
+// score a translation without a reference: the model's own scorer when it has one, otherwise
+// the model is asked for a 0-100 rating (GEMBA style)
+pub fn estimate<R: Runtime>(
+  manager: &impl Manager<R>,
+  model_id: Option<&str>,
+  source: &str,
+  translation: &str,
+  source_lang: &str,
+  target_lang: &str,
+) -> Result<QualityEstimate, String> {
+  let settings = settings();
+  let (model, config) =
+    manager.state::<Mutex<ModelManager>>().locked().model_for_request(model_id.or(settings.model_id.as_deref()))?;
+  let score = match scorer(Path::new(&model.path)) {
+    Some(c) => run_scorer(
+      c,
+      serde_json::json!({ "source": source, "translation": translation, "source_lang": source_lang, "target_lang": target_lang }),
+    )?,
+    None => {
+      let name = |code: &str, prefix: &str| if code == "auto" { String::new() } else { format!("{} {} ", prefix, lang::language_name(code)) };
+      let prompt = format!(
+        "Score the following translation {}{}on a scale from 0 to 100, where 0 means no meaning is preserved \
+         and 100 means a perfect translation with correct meaning and grammar.\n\nSource: {}\nTranslation: {}\n\nScore:",
+        name(source_lang, "from"),
+        name(target_lang, "to"),
+        source,
+        translation
+      );
+      let schema = serde_json::json!({
+        "type": "object",
+        "properties": { "score": { "type": "integer", "minimum": 0, "maximum": 100 } },
+        "required": ["score"]
+      });
+      let json = engine::generate_json(&model.path, &config, &prompt, 16, &schema)?;
+      // asked for 0..100, so a 1 is 1 out of 100
+      (parse_score(&json.to_string()).ok_or("model returned no score")? / 100.0).clamp(0.0, 1.0)
+    }
+  };
+  Ok(QualityEstimate { score, flagged: score < settings.threshold, model_id: model.id })
+}
+
+// ------------------ Tauri commands ------------------
+
+#[tauri::command(async)]
+pub fn estimate_quality(
+  source: String,
+  translation: String,
+  source_lang: Option<String>,
+  target_lang: Option<String>,
+  model_id: Option<String>,
+  app: tauri::AppHandle
+) -> Result<QualityEstimate, AppError> {
+  Ok(estimate(&app, model_id.as_deref(), &source, &translation, source_lang.as_deref().unwrap_or("auto"), target_lang.as_deref().unwrap_or("auto"))?)
+}
+
+#[tauri::command]
+pub fn get_quality_settings() -> QualitySettings {
+  settings()
+}
+
+#[tauri::command]
+pub fn set_quality_settings(settings: QualitySettings, state: tauri::State<'_, Mutex<ModelManager>>) -> Result<(), AppError> {
+  if let Some(id) = &settings.model_id {
+    if !state.locked().models.contains_key(id) {
+      return Err(AppError::not_found("Model", id));
+    }
+  }
+  if !(0.0..=1.0).contains(&settings.threshold) {
+    return Err(AppError::InvalidInput("The threshold must be between 0 and 1".into()));
+  }
+  Ok(store::save_json(&settings_path(), &settings)?)
+}
@@ -0,0 +1,152 @@
+// src-tauri/src/quantize.rs
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::thread;
+
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::LockExt;
+use crate::events;
+use crate::runtime;
+use crate::storage;
+use crate::ModelManager;
+
+// quantization types accepted by llama-quantize that make sense for end users
+const QUANT_TYPES: &[&str] = &[
+  "Q2_K", "Q3_K_S", "Q3_K_M", "Q3_K_L", "IQ4_XS", "Q4_0", "Q4_1", "Q4_K_S", "Q4_K_M", "Q5_0", "Q5_1", "Q5_K_S",
+  "Q5_K_M", "Q6_K", "Q8_0",
+];
+
+// Payload of "quantize-progress" events
+#[derive(Clone, serde::Serialize)]
+struct QuantizeProgress {
+  id: String,
+  target: String,
+  // tensors converted so far / total tensors, when the tool reports them
+  current: Option<u32>,
+  total: Option<u32>,
+  line: String,
+}
+
+// Payload of the final "quantize-status" event
+#[derive(Clone, serde::Serialize)]
+struct QuantizeStatus {
+  id: String,
+  target: String,
+  done: bool,
+  output: String,
+  error: Option<String>,
+}
+
+// "llama-7b-f16.gguf" + Q4_K_M -> "llama-7b-Q4_K_M.gguf"
+fn output_path(source: &Path, target: &str) -> PathBuf {
+  let stem = source.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+  let lower = stem.to_lowercase();
+  let base = ["-f16", "-f32", "-bf16", ".f16", ".f32", ".bf16"]
+    .iter()
+    .find(|suffix| lower.ends_with(*suffix))
+    .map(|suffix| &stem[..stem.len() - suffix.len()])
+    .unwrap_or(&stem);
+  source.with_file_name(format!("{}-{}.gguf", base, target))
+}
+
+// llama-quantize prints one line per tensor starting with "[  12/ 291]"
+fn parse_progress(line: &str) -> Option<(u32, u32)> {
+  let inner = line.trim_start().strip_prefix('[')?.split(']').next()?;
+  let (cur, total) = inner.split_once('/')?;
+  Some((cur.trim().parse().ok()?, total.trim().parse().ok()?))
+}
+
+// send each line of `pipe` as "quantize-progress"; returns the last lines, for an error message
+fn forward(app: &AppHandle, id: &str, target: &str, pipe: impl Read) -> Vec<String> {
+  let mut tail = Vec::new();
+  for line in BufReader::new(pipe).lines().map_while(Result::ok) {
+    let progress = parse_progress(&line);
+    events::emit(
+      app,
+      "quantize-progress",
+      QuantizeProgress { id: id.to_string(), target: target.to_string(), current: progress.map(|p| p.0), total: progress.map(|p| p.1), line: line.clone() },
+    );
+    tail.push(line);
+    if tail.len() > 20 {
+      tail.remove(0);
+    }
+  }
+  tail
+}
+
+// start llama-quantize in the background; returns the path of the file being written
+pub fn start(app: AppHandle, id: String, source: String, target: String) -> Result<String, String> {
+  let target = target.to_uppercase();
+  if !QUANT_TYPES.contains(&target.as_str()) {
+    return Err(format!("Unsupported quantization type '{}'", target));
+  }
+  let source_path = PathBuf::from(&source);
+  if source_path.extension().is_none_or(|e| e != "gguf") {
+    return Err("Only .gguf models can be quantized".into());
+  }
+  let tool = runtime::bundled_tool("llama-quantize").ok_or("llama-quantize is not bundled with this build")?;
+  let output = output_path(&source_path, &target);
+  if output.exists() {
+    return Err(format!("{} already exists", output.to_string_lossy()));
+  }
+  let output_str = output.to_string_lossy().to_string();
+
+  let mut child = storage::command(tool)
+    .arg(&source)
+    .arg(&output)
+    .arg(&target)
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .map_err(|e| format!("Failed to spawn llama-quantize: {}", e))?;
+
+  let out_path = output_str.clone();
+  thread::spawn(move || {
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+
+    // llama-quantize logs (the per-tensor lines too) to stderr; stdout is forwarded the same way
+    // on its own thread so neither pipe blocks
+    let stdout_done = {
+      let (app, id, target) = (app.clone(), id.clone(), target.clone());
+      thread::spawn(move || {
+        if let Some(out) = stdout {
+          forward(&app, &id, &target, out);
+        }
+      })
+    };
+    let tail = stderr.map(|err| forward(&app, &id, &target, err)).unwrap_or_default();
+    let _ = stdout_done.join();
+
+    let error = match child.wait() {
+      Ok(status) if status.success() => None,
+      Ok(status) => Some(format!("llama-quantize exited with {}: {}", status, tail.join("\n"))),
+      Err(e) => Some(format!("llama-quantize failed: {}", e)),
+    };
+
+    if error.is_some() {
+      let _ = std::fs::remove_file(&out_path);
+    } else {
+      // register the new file so it shows up in list_models right away
+      let state = app.state::<Mutex<ModelManager>>();
+      state.locked().scan_models();
+    }
+
+    let _ = app.emit(
+      "quantize-status",
+      QuantizeStatus { id, target, done: error.is_none(), output: out_path, error },
+    );
+  });
+
+  Ok(output_str)
+}
+
+// ------------------ Tauri commands ------------------
+
+#[tauri::command]
+pub fn list_quant_types() -> Vec<String> {
+  QUANT_TYPES.iter().map(|s| s.to_string()).collect()
+}
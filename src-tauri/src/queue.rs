@@ -0,0 +1,120 @@
+// src-tauri/src/queue.rs
+use std::sync::Mutex;
+
+use tauri::{Emitter, Runtime};
+use tokio::sync::Notify;
+
+use crate::dispatch::Priority;
+use crate::error::LockExt;
+
+// Payload of "queue-position" events, sent for every waiting prompt of a model whenever its queue
+// changes
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct QueuePosition {
+  pub model_id: String,
+  pub request_id: u64,
+  pub priority: Priority,
+  // 1 is next; 0 when the prompt is being written to the model
+  pub position: usize,
+}
+
+// Where a prompt stands when it asks for its turn
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Turn {
+  // it was taken off the queue and the process is answering it now
+  Started,
+  Waiting,
+  // it was cancelled or its process ended
+  Gone,
+}
+
+struct Waiting {
+  request_id: u64,
+  priority: Priority,
+}
+
+// Prompts for one model process waiting for it to finish the answer it is generating. A process
+// reads one prompt at a time; writing the next before the answer ends mixes the two up
+pub struct PromptQueue {
+  model_id: String,
+  // highest priority first, then in the order they came in
+  waiting: Mutex<Vec<Waiting>>,
+  // woken when the queue changes or the process finishes a request
+  changed: Notify,
+}
+
+impl PromptQueue {
+  pub fn new(model_id: &str) -> Self {
+    Self { model_id: model_id.to_string(), waiting: Mutex::new(Vec::new()), changed: Notify::new() }
+  }
+
+  fn announce<R: Runtime>(&self, target: &impl Emitter<R>, waiting: &[Waiting]) {
+    for (i, w) in waiting.iter().enumerate() {
+      let _ = target.emit("queue-position", QueuePosition { model_id: self.model_id.clone(), request_id: w.request_id, priority: w.priority, position: i + 1 });
+    }
+  }
+
+  // queue `request_id` behind the prompts of the same or a higher priority
+  pub fn push<R: Runtime>(&self, target: &impl Emitter<R>, request_id: u64, priority: Priority) {
+    let mut waiting = self.waiting.locked();
+    let at = waiting.iter().position(|w| w.priority < priority).unwrap_or(waiting.len());
+    waiting.insert(at, Waiting { request_id, priority });
+    self.announce(target, &waiting[at..]);
+    drop(waiting);
+    self.changed.notify_waiters();
+  }
+
+  // take `request_id` off the queue (a cancel); false when it isn't waiting
+  pub fn remove<R: Runtime>(&self, target: &impl Emitter<R>, request_id: u64) -> bool {
+    let mut waiting = self.waiting.locked();
+    let Some(at) = waiting.iter().position(|w| w.request_id == request_id) else { return false };
+    waiting.remove(at);
+    self.announce(target, &waiting[at..]);
+    drop(waiting);
+    self.changed.notify_waiters();
+    true
+  }
+
+  // empty the queue (the process ended); returns the ids that were waiting
+  pub fn drain(&self) -> Vec<u64> {
+    let ids = self.waiting.locked().drain(..).map(|w| w.request_id).collect();
+    self.changed.notify_waiters();
+    ids
+  }
+
+  // the process finished a request; the next prompt may go
+  pub fn wake(&self) {
+    self.changed.notify_waiters();
+  }
+
+  // start `request_id` if it is first in line and `claim` finds the process free. `claim` runs
+  // under the queue lock, so two prompts can't both find it free
+  pub fn take<R: Runtime>(&self, target: &impl Emitter<R>, request_id: u64, claim: impl Fn() -> bool) -> Turn {
+    let mut waiting = self.waiting.locked();
+    match waiting.iter().position(|w| w.request_id == request_id) {
+      None => Turn::Gone,
+      Some(0) if claim() => {
+        let started = waiting.remove(0);
+        let _ = target.emit("queue-position", QueuePosition { model_id: self.model_id.clone(), request_id, priority: started.priority, position: 0 });
+        self.announce(target, &waiting);
+        Turn::Started
+      }
+      Some(_) => Turn::Waiting,
+    }
+  }
+
+  // wait until `request_id` is started; false when it was taken off the queue instead
+  pub async fn wait<R: Runtime>(&self, target: &impl Emitter<R>, request_id: u64, claim: impl Fn() -> bool) -> bool {
+    loop {
+      // registered before looking, so a change in between isn't missed
+      let changed = self.changed.notified();
+      tokio::pin!(changed);
+      changed.as_mut().enable();
+      match self.take(target, request_id, &claim) {
+        Turn::Started => return true,
+        Turn::Gone => return false,
+        Turn::Waiting => changed.await,
+      }
+    }
+  }
+}
@@ -0,0 +1,265 @@
+// src-tauri/src/quiz.rs
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use serde_json::json;
+use tauri::{Manager, Window};
+
+use crate::engine;
+use crate::error::{AppError, LockExt};
+use crate::favorites::Favorites;
+use crate::lang;
+use crate::phrasebook::Phrasebooks;
+use crate::tm::TranslationMemory;
+use crate::ModelManager;
+
+const DEFAULT_QUESTIONS: usize = 5;
+// study items handed to the model per quiz
+const MAX_MATERIAL: usize = 20;
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuestionKind {
+  // pick one of `options`
+  Choice,
+  // fill the ___ gap in `prompt`
+  Cloze,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Question {
+  pub kind: QuestionKind,
+  pub prompt: String,
+  #[serde(default)]
+  pub options: Vec<String>,
+  // option text for choice questions, the missing word(s) for cloze
+  pub answer: String,
+}
+
+// Question as sent to the frontend (answer withheld until grading)
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct QuestionView {
+  pub kind: QuestionKind,
+  pub prompt: String,
+  pub options: Vec<String>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct QuizView {
+  pub id: u64,
+  pub lang: String,
+  pub level: String,
+  pub topic: Option<String>,
+  pub questions: Vec<QuestionView>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct QuestionResult {
+  pub correct: bool,
+  pub given: String,
+  pub expected: String,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct QuizResult {
+  pub score: usize,
+  pub total: usize,
+  pub results: Vec<QuestionResult>,
+}
+
+struct Quiz {
+  view: QuizView,
+  questions: Vec<Question>,
+}
+
+// Generated quizzes awaiting answers (managed by Tauri)
+pub struct QuizStore {
+  quizzes: HashMap<u64, Quiz>,
+  counter: u64,
+}
+
+impl QuizStore {
+  pub fn new() -> Self {
+    Self { quizzes: HashMap::new(), counter: 0 }
+  }
+}
+
+// (text in `lang`, meaning) pairs from the TM, favorites and phrasebooks
+fn study_material(window: &Window, lang: &str, topic: Option<&str>) -> Vec<(String, String)> {
+  let mut items: Vec<(String, String)> = Vec::new();
+  for e in window.state::<Mutex<TranslationMemory>>().locked().entries(None, None) {
+    if lang::same_language(&e.target_lang, lang) {
+      items.push((e.target, e.source));
+    } else if lang::same_language(&e.source_lang, lang) {
+      items.push((e.source, e.target));
+    }
+  }
+  for f in window.state::<Mutex<Favorites>>().locked().items() {
+    if lang::same_language(&f.target_lang, lang) {
+      items.push((f.translated_text, f.source_text));
+    } else if lang::same_language(&f.source_lang, lang) {
+      items.push((f.source_text, f.translated_text));
+    }
+  }
+  for p in window.state::<Mutex<Phrasebooks>>().locked().phrases(lang) {
+    let meaning = p.gloss.unwrap_or_default();
+    items.push((p.text, format!("{} [{}]", meaning, p.category)));
+  }
+  // most recent history first
+  items.reverse();
+
+  if let Some(topic) = topic.map(|t| t.to_lowercase()).filter(|t| !t.trim().is_empty()) {
+    let on_topic: Vec<(String, String)> = items
+      .iter()
+      .filter(|(text, meaning)| text.to_lowercase().contains(&topic) || meaning.to_lowercase().contains(&topic))
+      .cloned()
+      .collect();
+    // an unmatched topic is still passed to the model as a theme
+    if !on_topic.is_empty() {
+      items = on_topic;
+    }
+  }
+  items.truncate(MAX_MATERIAL);
+  items
+}
+
+fn quiz_schema(count: usize) -> serde_json::Value {
+  json!({
+    "type": "object",
+    "properties": {
+      "questions": {
+        "type": "array",
+        "minItems": count,
+        "maxItems": count,
+        "items": {
+          "type": "object",
+          "properties": {
+            "kind": { "type": "string", "enum": ["choice", "cloze"] },
+            "prompt": { "type": "string" },
+            "options": { "type": "array", "items": { "type": "string" } },
+            "answer": { "type": "string" }
+          },
+          "required": ["kind", "prompt", "options", "answer"]
+        }
+      }
+    },
+    "required": ["questions"]
+  })
+}
+
+fn build_prompt(lang_name: &str, level: &str, topic: Option<&str>, material: &[(String, String)], count: usize) -> String {
+  let list: Vec<String> = material.iter().map(|(t, m)| format!("- {} = {}", t, m)).collect();
+  let topic = topic.map(|t| format!(" The theme is \"{}\".", t)).unwrap_or_default();
+  format!(
+    "Write a {count}-question {lang} quiz for a learner at CEFR level {level}, based on the phrases the learner studied below.{topic}\n\
+Mix two kinds of questions:\n\
+- \"choice\": a question with 4 options; \"answer\" is the exact text of the correct option.\n\
+- \"cloze\": a {lang} sentence with one word or short phrase replaced by ___; \"options\" is empty and \"answer\" is the missing text.\n\
+Reply with JSON only: {{\"questions\": [{{\"kind\", \"prompt\", \"options\", \"answer\"}}]}}.\n\nStudied phrases:\n{list}",
+    count = count,
+    lang = lang_name,
+    level = level,
+    topic = topic,
+    list = list.join("\n")
+  )
+}
+
+// drop malformed questions instead of failing the whole quiz
+fn valid(q: &Question) -> bool {
+  if q.prompt.trim().is_empty() || q.answer.trim().is_empty() {
+    return false;
+  }
+  match q.kind {
+    QuestionKind::Choice => q.options.len() >= 2 && q.options.iter().any(|o| normalize(o) == normalize(&q.answer)),
+    QuestionKind::Cloze => q.prompt.contains("___"),
+  }
+}
+
+// case/punctuation-insensitive comparison key
+fn normalize(s: &str) -> String {
+  s.chars()
+    .filter(|c| !c.is_ascii_punctuation() && !matches!(c, '¿' | '¡' | '。' | '、'))
+    .collect::<String>()
+    .to_lowercase()
+    .split_whitespace()
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+fn grade(q: &Question, given: &str) -> bool {
+  // choice answers may come back as the option index
+  let given = match (q.kind, given.trim().parse::<usize>()) {
+    (QuestionKind::Choice, Ok(i)) if i < q.options.len() => q.options[i].as_str(),
+    _ => given,
+  };
+  normalize(given) == normalize(&q.answer)
+}
+
+// ------------------ Tauri commands ------------------
+
+// multiple-choice/cloze quiz in `lang` built from the user's TM, favorites and phrasebooks
+#[tauri::command(async)]
+pub fn generate_quiz(
+  lang: String,
+  level: String,
+  topic: Option<String>,
+  count: Option<usize>,
+  model_id: Option<String>,
+  window: Window,
+) -> Result<QuizView, AppError> {
+  let count = count.unwrap_or(DEFAULT_QUESTIONS).clamp(1, 20);
+  let level = level.to_uppercase();
+  let material = study_material(&window, &lang, topic.as_deref());
+  if material.is_empty() {
+    return Err(format!("No translations or phrasebook phrases in {} to build a quiz from", lang::language_name(&lang)).into());
+  }
+
+  let (model, config) = window.state::<Mutex<ModelManager>>().locked().model_for_request(model_id.as_deref())?;
+  let prompt = build_prompt(&lang::language_name(&lang), &level, topic.as_deref(), &material, count);
+  let out = engine::generate_json(&model.path, &config, &prompt, 160 * count as u32, &quiz_schema(count))?;
+  let questions: Vec<Question> = out
+    .get("questions")
+    .and_then(|v| serde_json::from_value::<Vec<Question>>(v.clone()).ok())
+    .unwrap_or_default()
+    .into_iter()
+    .filter(valid)
+    .collect();
+  if questions.is_empty() {
+    return Err("The model did not produce any usable questions".into());
+  }
+
+  let store = window.state::<Mutex<QuizStore>>();
+  let mut store = store.locked();
+  store.counter += 1;
+  let view = QuizView {
+    id: store.counter,
+    lang,
+    level,
+    topic,
+    questions: questions.iter().map(|q| QuestionView { kind: q.kind, prompt: q.prompt.clone(), options: q.options.clone() }).collect(),
+  };
+  store.quizzes.insert(view.id, Quiz { view: view.clone(), questions });
+  Ok(view)
+}
+
+// grade answers locally (one per question, in order; choice answers may be option text or index)
+#[tauri::command]
+pub fn grade_quiz(quiz_id: u64, answers: Vec<String>, store: tauri::State<'_, Mutex<QuizStore>>) -> Result<QuizResult, AppError> {
+  let store = store.locked();
+  let quiz = store.quizzes.get(&quiz_id).ok_or_else(|| AppError::not_found("Quiz", &quiz_id.to_string()))?;
+  let results: Vec<QuestionResult> = quiz
+    .questions
+    .iter()
+    .enumerate()
+    .map(|(i, q)| {
+      let given = answers.get(i).cloned().unwrap_or_default();
+      QuestionResult { correct: grade(q, &given), given, expected: q.answer.clone() }
+    })
+    .collect();
+  Ok(QuizResult { score: results.iter().filter(|r| r.correct).count(), total: results.len(), results })
+}
+
+#[tauri::command]
+pub fn get_quiz(quiz_id: u64, store: tauri::State<'_, Mutex<QuizStore>>) -> Result<QuizView, AppError> {
+  store.locked().quizzes.get(&quiz_id).map(|q| q.view.clone()).ok_or_else(|| AppError::not_found("Quiz", &quiz_id.to_string()))
+}
@@ -0,0 +1,276 @@
+// src-tauri/src/readable.rs
+use crate::chunk;
+use crate::lang::{self, Script};
+
+// shorter words are left whole
+const MIN_HYPHENATE: usize = 5;
+// abbreviations whose full stop doesn't end a sentence
+const ABBREVIATIONS: &[&str] = &[
+  "mr", "mrs", "ms", "dr", "prof", "st", "vs", "etc", "e.g", "i.e", "no", "fig", "approx", "z.b", "bzw", "usw", "ca", "sr", "sra",
+];
+// consonant pairs kept together at the start of a syllable ("ta-ble", "mo-ther")
+const ONSETS: &[&str] = &[
+  "bl", "br", "cl", "cr", "dr", "fl", "fr", "gl", "gr", "pl", "pr", "tr", "ch", "sh", "th", "ph", "wh", "gh", "qu", "sch", "schl",
+  "schm", "schn", "schr", "schw", "scr", "spl", "spr", "str",
+];
+
+// Accessibility formatting applied to results after generation (same output for the same text)
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ReadingOptions {
+  // split longer words into syllables ("syl·la·ble")
+  pub hyphenate: bool,
+  // what goes between syllables; defaults to a middle dot
+  pub separator: Option<String>,
+  pub sentence_per_line: bool,
+  // semicolons, dashes, brackets and typographic quotes replaced by plain commas, stops and quotes
+  pub simple_punctuation: bool,
+}
+
+impl ReadingOptions {
+  fn any(&self) -> bool {
+    self.hyphenate || self.sentence_per_line || self.simple_punctuation
+  }
+}
+
+fn is_vowel(c: char) -> bool {
+  let base = c.to_lowercase().next().unwrap_or(c);
+  "aeiouyàáâãäåæèéêëìíîïòóôõöøœùúûüýÿāēīōūăęěąőűаеёиоуыэюяіїєαεηιουωάέήίόύώ".contains(base)
+}
+
+// syllables of one word by vowel groups: one consonant between vowels starts the next syllable,
+// longer clusters split before the last consonant unless the tail is a known onset
+fn syllables(word: &str, english: bool) -> Vec<String> {
+  let chars: Vec<char> = word.chars().collect();
+  let lower: Vec<char> = word.to_lowercase().chars().collect();
+  if chars.len() < MIN_HYPHENATE || lower.len() != chars.len() {
+    return vec![word.to_string()];
+  }
+  let mut vowel: Vec<bool> = lower.iter().map(|c| is_vowel(*c)).collect();
+  // a "y" before a vowel is a consonant ("yes", "be-yond")
+  for i in 0..lower.len() {
+    if lower[i] == 'y' && vowel.get(i + 1) == Some(&true) {
+      vowel[i] = false;
+    }
+  }
+  // silent final e in English ("make", "table" stays "ta-ble")
+  let n = lower.len();
+  if english && lower[n - 1] == 'e' && !vowel[n - 2] && !(lower[n - 2] == 'l' && n > 2 && !vowel[n - 3]) {
+    vowel[n - 1] = false;
+  }
+  // "qu" counts as a consonant
+  for i in 1..n {
+    if lower[i] == 'u' && lower[i - 1] == 'q' {
+      vowel[i] = false;
+    }
+  }
+
+  // vowel group boundaries
+  let mut nuclei: Vec<(usize, usize)> = Vec::new();
+  let mut i = 0;
+  while i < n {
+    if vowel[i] {
+      let start = i;
+      while i < n && vowel[i] {
+        i += 1;
+      }
+      nuclei.push((start, i));
+    } else {
+      i += 1;
+    }
+  }
+
+  let mut cuts: Vec<usize> = Vec::new();
+  for pair in nuclei.windows(2) {
+    let (from, to) = (pair[0].1, pair[1].0);
+    let cluster: String = lower[from..to].iter().collect();
+    let cut = match to - from {
+      0 => continue,
+      1 => from,
+      _ => {
+        let onset = ONSETS.iter().filter(|o| cluster.ends_with(*o)).map(|o| o.chars().count()).max();
+        to - onset.unwrap_or(1)
+      }
+    };
+    // no syllable of a single letter at either end ("a-bout" -> "about")
+    if cut >= 2 && n - cut >= 2 && chars[from..to].iter().all(|c| c.is_alphabetic()) {
+      cuts.push(cut);
+    }
+  }
+
+  let mut out = Vec::new();
+  let mut last = 0;
+  for cut in cuts {
+    out.push(chars[last..cut].iter().collect());
+    last = cut;
+  }
+  out.push(chars[last..].iter().collect());
+  out
+}
+
+// hyphenate the words of `text`; scripts written without syllable-friendly words are left alone
+pub fn hyphenate(text: &str, lang: &str, separator: &str) -> String {
+  let script = match lang {
+    "" | "auto" => lang::dominant_script(text).unwrap_or(Script::Latin),
+    l => lang::script_for(l),
+  };
+  if !matches!(script, Script::Latin | Script::Cyrillic | Script::Greek) {
+    return text.to_string();
+  }
+  let english = lang::same_language(lang, "en");
+  let mut out = String::with_capacity(text.len() * 5 / 4);
+  let mut word = String::new();
+  for c in text.chars().chain(std::iter::once(' ')) {
+    if c.is_alphabetic() {
+      word.push(c);
+      continue;
+    }
+    if !word.is_empty() {
+      out.push_str(&syllables(&word, english).join(separator));
+      word.clear();
+    }
+    out.push(c);
+  }
+  out.pop();
+  out
+}
+
+fn ends_with_abbreviation(sentence: &str) -> bool {
+  let last = sentence.trim_end().trim_end_matches('.').rsplit(|c: char| c.is_whitespace() || c == '(').next().unwrap_or("");
+  let last = last.to_lowercase();
+  // initials ("J. R. R. Tolkien")
+  (last.chars().count() == 1 && last.chars().all(|c| c.is_alphabetic())) || ABBREVIATIONS.contains(&last.as_str())
+}
+
+// one sentence per line, keeping blank lines between paragraphs
+pub fn sentence_per_line(text: &str) -> String {
+  text
+    .split("\n\n")
+    .map(|para| {
+      let mut lines: Vec<String> = Vec::new();
+      for piece in chunk::sentences(&para.replace('\n', " ")) {
+        // "3.5", "example.com" and abbreviations were split where no sentence ends
+        let joins = lines.last().is_some_and(|prev| {
+          let stop = prev.trim_end().ends_with(['.', '!', '?']);
+          stop && (prev.len() == prev.trim_end().len() || ends_with_abbreviation(prev))
+        });
+        match lines.last_mut() {
+          Some(prev) if joins => prev.push_str(&piece),
+          _ => lines.push(piece),
+        }
+      }
+      lines.iter().map(|l| l.trim()).filter(|l| !l.is_empty()).collect::<Vec<_>>().join("\n")
+    })
+    .collect::<Vec<_>>()
+    .join("\n\n")
+}
+
+// uppercase the first letter after each full stop that was a semicolon
+fn capitalize_after(text: &str, marker: char) -> String {
+  let mut out = String::with_capacity(text.len());
+  let mut pending = false;
+  for c in text.chars() {
+    if c == marker {
+      out.push('.');
+      pending = true;
+      continue;
+    }
+    if pending && c.is_alphabetic() {
+      out.extend(c.to_uppercase());
+      pending = false;
+      continue;
+    }
+    if pending && !c.is_whitespace() {
+      pending = false;
+    }
+    out.push(c);
+  }
+  out
+}
+
+// plain punctuation only: straight quotes, commas for dashes and brackets, full stops for semicolons
+pub fn simplify_punctuation(text: &str) -> String {
+  let mut s: String = text
+    .chars()
+    .map(|c| match c {
+      '“' | '”' | '„' | '«' | '»' | '「' | '」' | '『' | '』' => '"',
+      '‘' | '’' | '‚' | '‹' | '›' => '\'',
+      '–' | '—' | '―' => ',',
+      '(' | ')' | '[' | ']' => ',',
+      _ => c,
+    })
+    .collect();
+  s = s.replace('…', "...");
+  s = capitalize_after(&s, ';');
+  // ", ,", " ," and ",." left over from dashes and brackets
+  let mut out = String::with_capacity(s.len());
+  for c in s.chars() {
+    if c == ',' {
+      while out.ends_with(' ') {
+        out.pop();
+      }
+      if out.ends_with([',', '.', '!', '?', ':']) || out.is_empty() || out.ends_with('\n') {
+        continue;
+      }
+    }
+    if matches!(c, '.' | '!' | '?' | ':') && out.ends_with(',') {
+      out.pop();
+    }
+    if c == ' ' && out.ends_with(' ') {
+      continue;
+    }
+    // "!!!" and "??" read as one mark
+    if matches!(c, '!' | '?') && out.ends_with(c) {
+      continue;
+    }
+    out.push(c);
+  }
+  // a comma has to be followed by a space
+  let mut spaced = String::with_capacity(out.len());
+  let mut chars = out.chars().peekable();
+  while let Some(c) = chars.next() {
+    spaced.push(c);
+    if c == ',' && chars.peek().is_some_and(|n| n.is_alphanumeric()) {
+      spaced.push(' ');
+    }
+  }
+  spaced.trim_end_matches(',').to_string()
+}
+
+// apply the requested options; fenced code blocks are left untouched
+pub fn apply(text: String, options: Option<&ReadingOptions>, lang: &str) -> String {
+  let Some(options) = options.filter(|o| o.any()) else { return text };
+  let separator = options.separator.clone().unwrap_or_else(|| "·".to_string());
+  text
+    .split("```")
+    .enumerate()
+    .map(|(i, part)| {
+      if i % 2 == 1 {
+        return part.to_string();
+      }
+      // keep the line breaks around code blocks
+      let body = part.trim();
+      let (lead, trail) = (&part[..part.len() - part.trim_start().len()], &part[part.trim_end().len()..]);
+      let mut part = body.to_string();
+      if options.simple_punctuation {
+        part = simplify_punctuation(&part);
+      }
+      if options.sentence_per_line {
+        part = sentence_per_line(&part);
+      }
+      if options.hyphenate {
+        part = hyphenate(&part, lang, &separator);
+      }
+      format!("{}{}{}", lead, part, trail)
+    })
+    .collect::<Vec<_>>()
+    .join("```")
+}
+
+// ------------------ Tauri commands ------------------
+
+// reformat text the app already has (a saved translation, a phrase) for easier reading
+#[tauri::command]
+pub fn make_readable(text: String, options: ReadingOptions, lang: Option<String>) -> String {
+  apply(text, Some(&options), lang.as_deref().unwrap_or("auto"))
+}
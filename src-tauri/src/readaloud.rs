@@ -0,0 +1,115 @@
+// src-tauri/src/readaloud.rs
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tauri::{Emitter, Manager, Window};
+
+use crate::engine;
+use crate::error::{AppError, LockExt};
+use crate::lang::{self, Script};
+use crate::speech;
+use crate::store;
+use crate::ModelManager;
+
+// longer selections are cut here; piper takes a while per sentence
+const MAX_CHARS: usize = 4000;
+
+// Selected text as spoken; also emitted as "read-aloud" so a hotkey-triggered read shows up in the UI
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ReadAloudResult {
+  pub text: String,
+  pub lang: String,
+  pub voice: String,
+  pub audio_path: String,
+}
+
+pub(crate) fn run_capture(program: &str, args: &[&str]) -> Option<String> {
+  let out = Command::new(program).args(args).stdin(Stdio::null()).stderr(Stdio::null()).output().ok()?;
+  let text = String::from_utf8_lossy(&out.stdout).to_string();
+  (out.status.success() && !text.trim().is_empty()).then_some(text)
+}
+
+// the current selection: on X11/Wayland the primary selection is whatever is highlighted, elsewhere
+// the hotkey handler copies the selection first and we read the clipboard
+fn read_selection() -> Option<String> {
+  if cfg!(target_os = "windows") {
+    run_capture("powershell", &["-NoProfile", "-Command", "Get-Clipboard -Raw"])
+  } else if cfg!(target_os = "macos") {
+    run_capture("pbpaste", &[])
+  } else {
+    run_capture("wl-paste", &["--primary", "--no-newline"])
+      .or_else(|| run_capture("xclip", &["-o", "-selection", "primary"]))
+      .or_else(|| run_capture("xsel", &["--primary", "--output"]))
+      .or_else(|| run_capture("wl-paste", &["--no-newline"]))
+      .or_else(|| run_capture("xclip", &["-o", "-selection", "clipboard"]))
+  }
+}
+
+// language of `text` among those we have a voice for: by script when that settles it, then the model
+fn detect_language(window: &Window, text: &str, candidates: &[String]) -> Result<String, String> {
+  let script = lang::dominant_script(text).unwrap_or(Script::Latin);
+  let matching: Vec<&String> = candidates.iter().filter(|l| lang::script_for(l) == script).collect();
+  match matching.as_slice() {
+    [] => return Err("No installed voice can read text in this script".into()),
+    [only] => return Ok(only.to_string()),
+    _ => {}
+  }
+
+  let names: Vec<String> = matching.iter().map(|l| lang::language_name(l)).collect();
+  let sample: String = text.chars().take(400).collect();
+  let prompt = format!(
+    "Which of these languages is the following text written in: {}? Reply with the language name only.\n\nText:\n{}\n\nLanguage:",
+    names.join(", "),
+    sample
+  );
+  let reply = {
+    let manager = window.state::<Mutex<ModelManager>>();
+    let loaded = manager.locked().model_for_request(None);
+    match loaded {
+      Ok((model, config)) => engine::generate(&model.path, &config, &prompt, 8).unwrap_or_default().to_lowercase(),
+      Err(_) => String::new(),
+    }
+  };
+  // without a model (or a usable answer) English is the likeliest Latin-script guess
+  let guess = matching
+    .iter()
+    .zip(&names)
+    .find(|(_, name)| reply.contains(&name.to_lowercase()))
+    .map(|(code, _)| code.to_string())
+    .or_else(|| matching.iter().find(|l| l.as_str() == "en").map(|l| l.to_string()))
+    .unwrap_or_else(|| matching[0].to_string());
+  Ok(guess)
+}
+
+// ------------------ Tauri commands ------------------
+
+// speak `text`, or the current selection when none is given (global hotkey path)
+#[tauri::command(async)]
+pub fn read_aloud(text: Option<String>, lang: Option<String>, window: Window) -> Result<ReadAloudResult, AppError> {
+  let text = text.filter(|t| !t.trim().is_empty()).or_else(read_selection).ok_or("No text selected")?;
+  let text: String = text.trim().chars().take(MAX_CHARS).collect();
+
+  let candidates = speech::voice_languages();
+  if candidates.is_empty() {
+    return Err("No text-to-speech voices installed".into());
+  }
+  let lang = match lang {
+    Some(l) => l,
+    None => detect_language(&window, &text, &candidates)?,
+  };
+  let voice = speech::voice_for(&lang).ok_or(format!("No voice for '{}'", lang))?;
+
+  let stamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0);
+  let out = store::data_file("readaloud").join(format!("{}.wav", stamp));
+  speech::synthesize_with(&voice, &text, &out)?;
+
+  let result = ReadAloudResult {
+    text,
+    lang,
+    voice: voice.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default(),
+    audio_path: out.to_string_lossy().to_string(),
+  };
+  let _ = window.emit("read-aloud", result.clone());
+  Ok(result)
+}
@@ -0,0 +1,277 @@
+// src-tauri/src/runner.rs
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::error::{AppError, LockExt};
+use crate::protocol::StdinProtocol;
+use crate::ModelManager;
+
+// answers the llama.cpp CLI prints are ended with this, which is how the app tells them apart
+const END_MARKER: &str = "[end of text]";
+const DEFAULT_MAX_TOKENS: u32 = 512;
+
+// What a generated runner starts
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunnerBackend {
+  // the llama.cpp CLI, once per prompt, with a GGUF file from the folder
+  LlamaCli,
+  // a python script in the folder that speaks the runner protocol itself
+  Python,
+  // any other command that speaks the runner protocol itself
+  Command,
+}
+
+// Which scripts to write
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunnerPlatform {
+  Unix,
+  Windows,
+  Both,
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct RunnerOptions {
+  // llama-cli: the binary (default "llama-cli" on the PATH); command: the program to run.
+  // A relative path with a directory in it is taken as inside the model folder
+  pub exe: Option<String>,
+  // llama-cli: the GGUF file in the folder (default the first one found)
+  pub model_file: Option<String>,
+  // python: the script in the folder (default run.py)
+  pub script: Option<String>,
+  // extra arguments, e.g. ["-ngl", "99"]
+  pub args: Vec<String>,
+  pub max_tokens: Option<u32>,
+  pub ctx_size: Option<u32>,
+  // how prompts are written to the runner; saved in the model's config. llama-cli runners read raw lines
+  pub protocol: Option<StdinProtocol>,
+  // the platform the app runs on when unset
+  pub platform: Option<RunnerPlatform>,
+  // replace existing run.sh / run.bat
+  pub overwrite: bool,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct GeneratedRunner {
+  pub model_id: String,
+  pub backend: RunnerBackend,
+  pub protocol: StdinProtocol,
+  // files written
+  pub files: Vec<String>,
+}
+
+fn first_gguf(dir: &Path) -> Option<String> {
+  let mut files: Vec<String> = fs::read_dir(dir)
+    .ok()?
+    .flatten()
+    .filter(|e| e.path().extension().is_some_and(|x| x.eq_ignore_ascii_case("gguf")))
+    .map(|e| e.file_name().to_string_lossy().to_string())
+    .collect();
+  files.sort();
+  files.into_iter().next()
+}
+
+// single-quoted for sh
+fn sh_quote(s: &str) -> String {
+  format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+// double-quoted for cmd; cmd has no escape for a quote inside quotes, so those are refused
+fn bat_quote(s: &str) -> Result<String, String> {
+  if s.contains('"') {
+    return Err(format!("'{}' contains a double quote, which run.bat can't pass on", s));
+  }
+  Ok(format!("\"{}\"", s.replace('%', "%%")))
+}
+
+// a bare name is looked up on the PATH, an absolute path is used as is
+fn in_folder(exe: &str) -> bool {
+  !Path::new(exe).is_absolute() && exe.contains(['/', '\\'])
+}
+
+// a file in the model folder, wherever the script is started from
+fn sh_local(file: &str) -> String {
+  format!("\"$DIR\"/{}", sh_quote(file))
+}
+
+fn bat_local(file: &str) -> Result<String, String> {
+  bat_quote(&file.replace('/', "\\")).map(|q| format!("\"%~dp0{}", &q[1..]))
+}
+
+fn sh_exe(exe: &str) -> String {
+  if in_folder(exe) { sh_local(exe) } else { sh_quote(exe) }
+}
+
+fn bat_exe(exe: &str) -> Result<String, String> {
+  if in_folder(exe) { bat_local(exe) } else { bat_quote(exe) }
+}
+
+// The scripts follow what the app expects of a runner (see engine::run and spawn_for_model):
+// - started with no arguments, from any working directory
+// - one-shot calls (MULTILINGUAL_ONESHOT set) get the whole prompt on stdin and exit after answering
+// - otherwise prompts arrive one per line on stdin for as long as the app runs the model, and
+//   each answer ends with "[end of text]" (or a {"type": "done"} line from JSON-lines runners)
+// - stdout is the answer, stderr is logging
+// - a model set to the socket transport talks over MULTILINGUAL_DATA_SOCKET instead (see
+//   transport::Transport); these scripts stick to stdin/stdout
+fn sh_script(model_id: &str, backend: RunnerBackend, options: &RunnerOptions, model_file: Option<&str>) -> String {
+  let header = format!(
+    "#!/bin/sh\n# runner for {} written by Multilingual ({:?}); prompts on stdin, answers on stdout, logs on stderr\nDIR=\"$(cd \"$(dirname \"$0\")\" && pwd)\"\n",
+    model_id, backend
+  );
+  let args: Vec<String> = options.args.iter().map(|a| sh_quote(a)).collect();
+  let args = if args.is_empty() { String::new() } else { format!(" {}", args.join(" ")) };
+  match backend {
+    RunnerBackend::LlamaCli => {
+      let exe = sh_exe(options.exe.as_deref().unwrap_or("llama-cli"));
+      let ctx = options.ctx_size.map(|c| format!(" -c {}", c)).unwrap_or_default();
+      format!(
+        "{header}RUNTIME={exe}\nMODEL={model}\nPROMPT=\"$(mktemp)\"\ntrap 'rm -f \"$PROMPT\"' EXIT\n\n\
+         answer() {{\n  \"$RUNTIME\" -m \"$MODEL\" -f \"$PROMPT\" -n {n}{ctx} --no-display-prompt{args} </dev/null\n}}\n\n\
+         if [ -n \"${{MULTILINGUAL_ONESHOT:-}}\" ]; then\n  cat > \"$PROMPT\"\n  answer\n  exit $?\nfi\n\n\
+         while IFS= read -r line; do\n  [ -z \"$line\" ] && continue\n  printf '%s\\n' \"$line\" > \"$PROMPT\"\n  answer\n  echo '{end}'\ndone\n",
+        header = header,
+        exe = exe,
+        model = sh_local(model_file.unwrap_or("model.gguf")),
+        n = options.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+        ctx = ctx,
+        args = args,
+        end = END_MARKER,
+      )
+    }
+    RunnerBackend::Python => {
+      let script = sh_local(options.script.as_deref().unwrap_or("run.py"));
+      format!("{}PYTHON=\"$(command -v python3 || command -v python)\"\nexec \"$PYTHON\" -u {}{}\n", header, script, args)
+    }
+    RunnerBackend::Command => {
+      let exe = sh_exe(options.exe.as_deref().unwrap_or_default());
+      format!("{}exec {}{}\n", header, exe, args)
+    }
+  }
+}
+
+fn bat_script(model_id: &str, backend: RunnerBackend, options: &RunnerOptions, model_file: Option<&str>) -> Result<String, String> {
+  let header = format!(
+    "@echo off\r\nrem runner for {} written by Multilingual ({:?}); prompts on stdin, answers on stdout, logs on stderr\r\nsetlocal EnableDelayedExpansion\r\n",
+    model_id, backend
+  );
+  let args = options.args.iter().map(|a| bat_quote(a)).collect::<Result<Vec<_>, _>>()?;
+  let args = if args.is_empty() { String::new() } else { format!(" {}", args.join(" ")) };
+  let script = match backend {
+    RunnerBackend::LlamaCli => {
+      let exe = bat_exe(options.exe.as_deref().unwrap_or("llama-cli.exe"))?;
+      let model = bat_local(model_file.unwrap_or("model.gguf"))?;
+      let ctx = options.ctx_size.map(|c| format!(" -c {}", c)).unwrap_or_default();
+      let run = format!(
+        "{exe} -m {model} -f \"%PROMPT%\" -n {n}{ctx} --no-display-prompt{args} <nul",
+        exe = exe,
+        model = model,
+        n = options.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+        ctx = ctx,
+        args = args
+      );
+      // `set /p` leaves the variable alone at the end of input, so it is cleared before each read
+      format!(
+        "{header}set \"PROMPT=%TEMP%\\multilingual-%RANDOM%%RANDOM%.txt\"\r\n\r\n\
+         if defined MULTILINGUAL_ONESHOT (\r\n  findstr \"^\" > \"%PROMPT%\"\r\n  {run}\r\n  set \"CODE=!ERRORLEVEL!\"\r\n  del \"%PROMPT%\" 2>nul\r\n  exit /b !CODE!\r\n)\r\n\r\n\
+         :next\r\nset \"line=\"\r\nset /p \"line=\"\r\nif not defined line goto done\r\n> \"%PROMPT%\" echo(!line!\r\n{run}\r\necho {end}\r\ngoto next\r\n\r\n\
+         :done\r\ndel \"%PROMPT%\" 2>nul\r\n",
+        header = header,
+        run = run,
+        end = END_MARKER,
+      )
+    }
+    RunnerBackend::Python => {
+      let script = bat_local(options.script.as_deref().unwrap_or("run.py"))?;
+      format!("{}python -u {}{}\r\n", header, script, args)
+    }
+    RunnerBackend::Command => {
+      let exe = bat_exe(options.exe.as_deref().unwrap_or_default())?;
+      format!("{}{}{}\r\n", header, exe, args)
+    }
+  };
+  Ok(script)
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) {
+  use std::os::unix::fs::PermissionsExt;
+  let _ = fs::set_permissions(path, fs::Permissions::from_mode(0o755));
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) {}
+
+// ------------------ Tauri commands ------------------
+
+// write run.sh and/or run.bat into a model folder so the app (and anyone packaging the model)
+// can run it; the protocol the runner reads is saved in the model's config
+#[tauri::command]
+pub fn generate_runner(
+  model_id: String,
+  backend: RunnerBackend,
+  options: Option<RunnerOptions>,
+  state: tauri::State<'_, Mutex<ModelManager>>
+) -> Result<GeneratedRunner, AppError> {
+  let options = options.unwrap_or_default();
+  let mut mgr = state.locked();
+  let model = mgr.models.get(&model_id).ok_or_else(|| AppError::not_found("Model", &model_id))?.clone();
+  let dir = PathBuf::from(&model.path);
+  if !dir.is_dir() {
+    return Err(format!("'{}' is a single file; runners go in a model folder, so move it into one first", model_id).into());
+  }
+
+  let model_file = match backend {
+    RunnerBackend::LlamaCli => {
+      let file = options.model_file.clone().or_else(|| first_gguf(&dir)).ok_or(format!("No .gguf file in {}", dir.to_string_lossy()))?;
+      if !dir.join(&file).is_file() {
+        return Err(format!("{} not found in {}", file, dir.to_string_lossy()).into());
+      }
+      Some(file)
+    }
+    RunnerBackend::Python => {
+      let script = options.script.as_deref().unwrap_or("run.py");
+      if !dir.join(script).is_file() {
+        return Err(format!("{} not found in {}", script, dir.to_string_lossy()).into());
+      }
+      None
+    }
+    RunnerBackend::Command => {
+      if options.exe.as_deref().is_none_or(|e| e.trim().is_empty()) {
+        return Err("A command runner needs the command in options.exe".into());
+      }
+      None
+    }
+  };
+  let protocol = match (backend, options.protocol) {
+    (RunnerBackend::LlamaCli, Some(p)) if p != StdinProtocol::Raw => {
+      return Err("llama-cli runners read one raw prompt per line".into());
+    }
+    (_, p) => p.unwrap_or(StdinProtocol::Raw),
+  };
+
+  let platform = options.platform.unwrap_or(if cfg!(windows) { RunnerPlatform::Windows } else { RunnerPlatform::Unix });
+  let mut scripts = Vec::new();
+  if platform != RunnerPlatform::Windows {
+    scripts.push((dir.join("run.sh"), sh_script(&model_id, backend, &options, model_file.as_deref())));
+  }
+  if platform != RunnerPlatform::Unix {
+    scripts.push((dir.join("run.bat"), bat_script(&model_id, backend, &options, model_file.as_deref())?));
+  }
+  if !options.overwrite {
+    if let Some((path, _)) = scripts.iter().find(|(p, _)| p.exists()) {
+      return Err(format!("{} already exists; pass overwrite to replace it", path.to_string_lossy()).into());
+    }
+  }
+  let mut files = Vec::new();
+  for (path, script) in scripts {
+    fs::write(&path, script).map_err(|e| AppError::io("write", &path, e))?;
+    make_executable(&path);
+    files.push(path.to_string_lossy().to_string());
+  }
+  mgr.configs.update(&model_id, |c| c.protocol = Some(protocol))?;
+  Ok(GeneratedRunner { model_id, backend, protocol, files })
+}
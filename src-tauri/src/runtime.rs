@@ -0,0 +1,288 @@
+// src-tauri/src/runtime.rs
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use std::thread;
+
+use sysinfo::System;
+
+use crate::events;
+use crate::gpu;
+use crate::settings;
+use crate::storage;
+use crate::store;
+
+// bundled runtimes live in ./src-tauri/bin/<variant>/, the legacy single binary in ./src-tauri/bin/
+const BIN_DIR: &str = "./src-tauri/bin";
+const BENCH_TOKENS: u32 = 32;
+const BENCH_TIMEOUT: Duration = Duration::from_secs(120);
+
+// set while the runtimes are being timed, so several model starts don't each run the benchmark
+static BENCHMARKING: AtomicBool = AtomicBool::new(false);
+
+#[cfg(target_os = "windows")]
+const RUNTIME_EXE: &str = "llama.exe";
+#[cfg(not(target_os = "windows"))]
+const RUNTIME_EXE: &str = "llama";
+
+// Build flavours of the runtime we know how to pick between
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuntimeVariant {
+  Cuda,
+  Rocm,
+  Vulkan,
+  Sycl,
+  Cpu,
+}
+
+impl RuntimeVariant {
+  const ALL: [RuntimeVariant; 5] = [
+    RuntimeVariant::Cuda,
+    RuntimeVariant::Rocm,
+    RuntimeVariant::Vulkan,
+    RuntimeVariant::Sycl,
+    RuntimeVariant::Cpu,
+  ];
+
+  fn dir_name(&self) -> &'static str {
+    match self {
+      RuntimeVariant::Cuda => "cuda",
+      RuntimeVariant::Rocm => "rocm",
+      RuntimeVariant::Vulkan => "vulkan",
+      RuntimeVariant::Sycl => "sycl",
+      RuntimeVariant::Cpu => "cpu",
+    }
+  }
+
+  // builds that need a discrete GPU
+  fn is_discrete(&self) -> bool {
+    matches!(self, RuntimeVariant::Cuda | RuntimeVariant::Rocm)
+  }
+
+  // builds that can accelerate on an integrated GPU
+  fn is_igpu(&self) -> bool {
+    matches!(self, RuntimeVariant::Vulkan | RuntimeVariant::Sycl)
+  }
+}
+
+// The runtime binary chosen for this machine
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct RuntimeChoice {
+  // None for the legacy ./src-tauri/bin/llama.exe
+  pub variant: Option<RuntimeVariant>,
+  pub exe: String,
+  // offload everything to the GPU (-ngl 99) or nothing
+  pub offload: bool,
+  // how the choice was made: "discrete-gpu", "persisted", "unbenchmarked" (CPU path until the
+  // background benchmark is done), "cpu", "legacy"
+  pub reason: String,
+}
+
+// Result of timing one runtime, persisted per machine
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct BenchmarkResult {
+  pub variant: RuntimeVariant,
+  pub offload: bool,
+  pub tokens_per_sec: f32,
+}
+
+// machine -> winning benchmark
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct ChoiceFile {
+  machines: HashMap<String, BenchmarkResult>,
+}
+
+fn machine_id() -> String {
+  System::host_name().unwrap_or_else(|| "unknown".into())
+}
+
+// benchmark results, kept next to the models
+fn choice_file() -> PathBuf {
+  settings::models_dir().join("runtime_choice.json")
+}
+
+fn load_choices() -> ChoiceFile {
+  store::load_json(&choice_file())
+}
+
+fn save_choice(result: &BenchmarkResult) -> Result<(), String> {
+  let mut file = load_choices();
+  file.machines.insert(machine_id(), result.clone());
+  store::save_json(&choice_file(), &file)
+}
+
+fn variant_exe(variant: RuntimeVariant) -> PathBuf {
+  PathBuf::from(BIN_DIR).join(variant.dir_name()).join(RUNTIME_EXE)
+}
+
+// runtime variants present on disk
+pub fn bundled_variants() -> Vec<RuntimeVariant> {
+  RuntimeVariant::ALL.into_iter().filter(|v| variant_exe(*v).exists()).collect()
+}
+
+// locate a helper tool shipped with the runtime (llama-quantize, llama-gguf-split, ...)
+// in ./src-tauri/bin or any variant subfolder
+pub fn bundled_tool(name: &str) -> Option<PathBuf> {
+  let file = if cfg!(target_os = "windows") { format!("{}.exe", name) } else { name.to_string() };
+  let root = PathBuf::from(BIN_DIR);
+  std::iter::once(root.join(&file))
+    .chain(RuntimeVariant::ALL.iter().map(|v| root.join(v.dir_name()).join(&file)))
+    .find(|p| p.exists())
+}
+
+// a helper tool of the same build as `runtime_exe` (so it uses the same GPU backend), else any
+// bundled one
+pub fn sibling_tool(runtime_exe: &str, name: &str) -> Option<PathBuf> {
+  let file = if cfg!(target_os = "windows") { format!("{}.exe", name) } else { name.to_string() };
+  let beside = Path::new(runtime_exe).with_file_name(file);
+  if beside.exists() {
+    return Some(beside);
+  }
+  bundled_tool(name)
+}
+
+fn legacy_exe() -> Option<PathBuf> {
+  let p = PathBuf::from(BIN_DIR).join("llama.exe");
+  p.exists().then_some(p)
+}
+
+// time a short generation; returns tokens/sec
+fn benchmark(exe: &Path, model_path: &str, offload: bool) -> Result<f32, String> {
+  let mut c = storage::command(exe);
+  c.args(["-m", model_path, "-p", "Hello", "-n", &BENCH_TOKENS.to_string()]);
+  c.args(["-ngl", if offload { "99" } else { "0" }]);
+  c.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+
+  let started = Instant::now();
+  let mut child = c.spawn().map_err(|e| format!("failed to spawn benchmark: {}", e))?;
+  loop {
+    match child.try_wait() {
+      Ok(Some(status)) if status.success() => break,
+      Ok(Some(status)) => return Err(format!("benchmark exited with {}", status)),
+      Ok(None) if started.elapsed() > BENCH_TIMEOUT => {
+        let _ = child.kill();
+        let _ = child.wait();
+        return Err("benchmark timed out".into());
+      }
+      Ok(None) => thread::sleep(Duration::from_millis(50)),
+      Err(e) => return Err(format!("benchmark failed: {}", e)),
+    }
+  }
+  let secs = started.elapsed().as_secs_f32().max(0.001);
+  Ok(BENCH_TOKENS as f32 / secs)
+}
+
+// benchmark the iGPU builds against the CPU path, persist and return the winner. Takes minutes; one
+// benchmark runs at a time
+pub fn benchmark_igpu(model_path: &str) -> Result<BenchmarkResult, String> {
+  if BENCHMARKING.swap(true, Ordering::SeqCst) {
+    return Err("a runtime benchmark is already running".into());
+  }
+  let result = time_igpu(model_path);
+  BENCHMARKING.store(false, Ordering::SeqCst);
+  result
+}
+
+fn time_igpu(model_path: &str) -> Result<BenchmarkResult, String> {
+  let bundled = bundled_variants();
+  let mut candidates: Vec<(RuntimeVariant, bool)> = bundled.iter().filter(|v| v.is_igpu()).map(|v| (*v, true)).collect();
+  if candidates.is_empty() {
+    return Err("no Vulkan/SYCL runtime is bundled".into());
+  }
+  // CPU path: the dedicated cpu build if present, otherwise an iGPU build without offload
+  if bundled.contains(&RuntimeVariant::Cpu) {
+    candidates.push((RuntimeVariant::Cpu, false));
+  } else {
+    candidates.push((candidates[0].0, false));
+  }
+
+  let mut best: Option<BenchmarkResult> = None;
+  for (variant, offload) in candidates {
+    match benchmark(&variant_exe(variant), model_path, offload) {
+      Ok(tps) => {
+        events::log(format!("runtime benchmark {:?} offload={} -> {:.1} tok/s", variant, offload, tps));
+        if best.as_ref().is_none_or(|b| tps > b.tokens_per_sec) {
+          best = Some(BenchmarkResult { variant, offload, tokens_per_sec: tps });
+        }
+      }
+      Err(e) => events::log(format!("runtime benchmark {:?} offload={} failed: {}", variant, offload, e)),
+    }
+  }
+
+  let best = best.ok_or("all runtime benchmarks failed")?;
+  save_choice(&best)?;
+  Ok(best)
+}
+
+fn choice_from(result: &BenchmarkResult, reason: &str) -> RuntimeChoice {
+  RuntimeChoice {
+    variant: Some(result.variant),
+    exe: variant_exe(result.variant).to_string_lossy().to_string(),
+    offload: result.offload,
+    reason: reason.into(),
+  }
+}
+
+// pick the runtime binary for this machine; None when nothing is bundled
+pub fn select_runtime(model_path: &str) -> Option<RuntimeChoice> {
+  let bundled = bundled_variants();
+
+  // a discrete GPU with a matching build wins outright
+  if !gpu::detect_devices().is_empty() {
+    if let Some(v) = bundled.iter().find(|v| v.is_discrete()) {
+      return Some(RuntimeChoice {
+        variant: Some(*v),
+        exe: variant_exe(*v).to_string_lossy().to_string(),
+        offload: true,
+        reason: "discrete-gpu".into(),
+      });
+    }
+  }
+
+  // iGPU builds: use the persisted decision for this machine. Without one the benchmark runs in the
+  // background and this start takes the CPU path, which works everywhere
+  if let Some(&igpu) = bundled.iter().find(|v| v.is_igpu()) {
+    if let Some(saved) = load_choices().machines.get(&machine_id()) {
+      if bundled.contains(&saved.variant) {
+        return Some(choice_from(saved, "persisted"));
+      }
+    }
+    let path = model_path.to_string();
+    thread::spawn(move || match benchmark_igpu(&path) {
+      Ok(result) => events::log(format!("runtime benchmark picked {:?} offload={}", result.variant, result.offload)),
+      Err(e) => events::log(format!("runtime benchmark: {}", e)),
+    });
+    let variant = if bundled.contains(&RuntimeVariant::Cpu) { RuntimeVariant::Cpu } else { igpu };
+    return Some(choice_from(&BenchmarkResult { variant, offload: false, tokens_per_sec: 0.0 }, "unbenchmarked"));
+  }
+
+  if bundled.contains(&RuntimeVariant::Cpu) {
+    return Some(choice_from(
+      &BenchmarkResult { variant: RuntimeVariant::Cpu, offload: false, tokens_per_sec: 0.0 },
+      "cpu",
+    ));
+  }
+
+  legacy_exe().map(|exe| RuntimeChoice {
+    variant: None,
+    exe: exe.to_string_lossy().to_string(),
+    offload: false,
+    reason: "legacy".into(),
+  })
+}
+
+// ------------------ Tauri commands ------------------
+
+#[tauri::command]
+pub fn list_runtimes() -> Vec<RuntimeVariant> {
+  bundled_variants()
+}
+
+// persisted iGPU/CPU decision for this machine, if any
+#[tauri::command]
+pub fn get_runtime_benchmark() -> Option<BenchmarkResult> {
+  load_choices().machines.get(&machine_id()).cloned()
+}
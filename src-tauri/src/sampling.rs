@@ -0,0 +1,86 @@
+// src-tauri/src/sampling.rs
+
+// How the next tokens are picked; unset fields fall back to the model's defaults, then the runtime's
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct SamplingParams {
+  pub temperature: Option<f32>,
+  pub top_p: Option<f32>,
+  pub top_k: Option<u32>,
+  pub repeat_penalty: Option<f32>,
+  pub max_tokens: Option<u32>,
+  // same seed, same prompt, same output
+  pub seed: Option<u64>,
+}
+
+impl SamplingParams {
+  pub fn is_empty(&self) -> bool {
+    *self == Self::default()
+  }
+
+  // these values, with the gaps filled from `defaults`
+  pub fn or(&self, defaults: &SamplingParams) -> SamplingParams {
+    SamplingParams {
+      temperature: self.temperature.or(defaults.temperature),
+      top_p: self.top_p.or(defaults.top_p),
+      top_k: self.top_k.or(defaults.top_k),
+      repeat_penalty: self.repeat_penalty.or(defaults.repeat_penalty),
+      max_tokens: self.max_tokens.or(defaults.max_tokens),
+      seed: self.seed.or(defaults.seed),
+    }
+  }
+
+  pub fn validate(&self) -> Result<(), String> {
+    if self.temperature.is_some_and(|t| !(0.0..=5.0).contains(&t)) {
+      return Err("temperature must be between 0 and 5".into());
+    }
+    if self.top_p.is_some_and(|p| !(0.0..=1.0).contains(&p)) {
+      return Err("top_p must be between 0 and 1".into());
+    }
+    if self.repeat_penalty.is_some_and(|r| r <= 0.0) {
+      return Err("repeat_penalty must be greater than 0".into());
+    }
+    if self.max_tokens == Some(0) {
+      return Err("max_tokens must be greater than 0".into());
+    }
+    Ok(())
+  }
+
+  // llama.cpp command line flags; max_tokens is left to the caller, which knows its own limit
+  pub fn runtime_args(&self) -> Vec<String> {
+    let mut args = Vec::new();
+    if let Some(t) = self.temperature {
+      args.extend(["--temp".to_string(), t.to_string()]);
+    }
+    if let Some(p) = self.top_p {
+      args.extend(["--top-p".to_string(), p.to_string()]);
+    }
+    if let Some(k) = self.top_k {
+      args.extend(["--top-k".to_string(), k.to_string()]);
+    }
+    if let Some(r) = self.repeat_penalty {
+      args.extend(["--repeat-penalty".to_string(), r.to_string()]);
+    }
+    if let Some(s) = self.seed {
+      args.extend(["-s".to_string(), s.to_string()]);
+    }
+    args
+  }
+
+  // fields of a llama-server /completion request
+  pub fn server_fields(&self, body: &mut serde_json::Value) {
+    let fields = [
+      ("temperature", self.temperature.map(serde_json::Value::from)),
+      ("top_p", self.top_p.map(serde_json::Value::from)),
+      ("top_k", self.top_k.map(serde_json::Value::from)),
+      ("repeat_penalty", self.repeat_penalty.map(serde_json::Value::from)),
+      ("n_predict", self.max_tokens.map(serde_json::Value::from)),
+      ("seed", self.seed.map(serde_json::Value::from)),
+    ];
+    for (name, value) in fields {
+      if let Some(value) = value {
+        body[name] = value;
+      }
+    }
+  }
+}
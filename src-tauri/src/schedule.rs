@@ -0,0 +1,435 @@
+// src-tauri/src/schedule.rs
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use chrono::{DateTime, Datelike, Local, Timelike};
+use tauri::{AppHandle, Manager};
+
+use crate::error::{AppError, LockExt};
+use crate::events;
+use crate::store;
+use crate::translate;
+use crate::ModelManager;
+
+// run records kept per schedule
+const HISTORY_LEN: usize = 50;
+const TICK: Duration = Duration::from_secs(20);
+
+// Parsed 5-field cron expression: minute hour day-of-month month day-of-week
+#[derive(Clone, Debug)]
+pub struct Cron {
+  minutes: Vec<u32>,
+  hours: Vec<u32>,
+  days: Vec<u32>,
+  months: Vec<u32>,
+  weekdays: Vec<u32>,
+  // whether day-of-month / day-of-week were restricted (cron ORs them when both are)
+  days_set: bool,
+  weekdays_set: bool,
+}
+
+// "*", "5", "1-5", "*/15", "0-30/10" and comma lists of those
+fn parse_field(field: &str, min: u32, max: u32) -> Result<Vec<u32>, String> {
+  let mut values = Vec::new();
+  for part in field.split(',') {
+    let (range, step) = match part.split_once('/') {
+      Some((r, s)) => (r, s.parse::<u32>().map_err(|_| format!("invalid step in '{}'", part))?),
+      None => (part, 1),
+    };
+    let (lo, hi) = match range {
+      "*" => (min, max),
+      r => match r.split_once('-') {
+        Some((a, b)) => (
+          a.parse().map_err(|_| format!("invalid value '{}'", a))?,
+          b.parse().map_err(|_| format!("invalid value '{}'", b))?,
+        ),
+        None => {
+          let v = r.parse().map_err(|_| format!("invalid value '{}'", r))?;
+          (v, if step > 1 { max } else { v })
+        }
+      },
+    };
+    if step == 0 || lo < min || hi > max || lo > hi {
+      return Err(format!("'{}' is outside {}-{}", part, min, max));
+    }
+    values.extend((lo..=hi).step_by(step as usize));
+  }
+  Ok(values)
+}
+
+impl Cron {
+  pub fn parse(expr: &str) -> Result<Self, String> {
+    let expanded = match expr.trim() {
+      "@hourly" => "0 * * * *",
+      "@daily" | "@nightly" => "0 0 * * *",
+      "@weekly" => "0 0 * * 0",
+      "@monthly" => "0 0 1 * *",
+      other => other,
+    };
+    let fields: Vec<&str> = expanded.split_whitespace().collect();
+    if fields.len() != 5 {
+      return Err(format!("Cron expression '{}' must have 5 fields (minute hour day month weekday)", expr));
+    }
+    let mut weekdays = parse_field(fields[4], 0, 7)?;
+    // 7 is Sunday too
+    for w in weekdays.iter_mut() {
+      *w %= 7;
+    }
+    Ok(Self {
+      minutes: parse_field(fields[0], 0, 59)?,
+      hours: parse_field(fields[1], 0, 23)?,
+      days: parse_field(fields[2], 1, 31)?,
+      months: parse_field(fields[3], 1, 12)?,
+      weekdays,
+      days_set: fields[2] != "*",
+      weekdays_set: fields[4] != "*",
+    })
+  }
+
+  pub fn matches(&self, t: &DateTime<Local>) -> bool {
+    let day = self.days.contains(&t.day());
+    let weekday = self.weekdays.contains(&t.weekday().num_days_from_sunday());
+    let day_ok = match (self.days_set, self.weekdays_set) {
+      (true, true) => day || weekday,
+      _ => day && weekday,
+    };
+    self.minutes.contains(&t.minute()) && self.hours.contains(&t.hour()) && self.months.contains(&t.month()) && day_ok
+  }
+}
+
+// What a schedule does when it fires
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ScheduleAction {
+  // translate documents in `path` that changed since the last successful run
+  TranslateFolder {
+    path: String,
+    output_dir: String,
+    source_lang: String,
+    target_lang: String,
+    #[serde(default)]
+    model_id: Option<String>,
+  },
+  RescanModels,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct RunRecord {
+  pub started: i64,
+  pub finished: i64,
+  pub ok: bool,
+  pub message: String,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Schedule {
+  pub id: u64,
+  pub name: String,
+  pub cron: String,
+  pub action: ScheduleAction,
+  pub enabled: bool,
+  // unix seconds
+  pub last_run: Option<i64>,
+  pub last_success: Option<i64>,
+  pub history: Vec<RunRecord>,
+}
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct ScheduleFile {
+  next_id: u64,
+  schedules: Vec<Schedule>,
+}
+
+// Recurring jobs persisted in ./data/schedules.json (managed by Tauri)
+pub struct Scheduler {
+  path: PathBuf,
+  file: ScheduleFile,
+  running: HashSet<u64>,
+}
+
+impl Scheduler {
+  pub fn load() -> Self {
+    let path = store::data_file("schedules.json");
+    let file = store::load_json(&path);
+    Self { path, file, running: HashSet::new() }
+  }
+
+  fn save(&self) -> Result<(), String> {
+    store::save_json(&self.path, &self.file)
+  }
+
+  fn get(&self, id: u64) -> Result<&Schedule, String> {
+    self.file.schedules.iter().find(|s| s.id == id).ok_or(format!("Schedule {} not found", id))
+  }
+
+  fn get_mut(&mut self, id: u64) -> Result<&mut Schedule, String> {
+    self.file.schedules.iter_mut().find(|s| s.id == id).ok_or(format!("Schedule {} not found", id))
+  }
+
+  // claim a schedule for running; errors if it is already running
+  fn begin(&mut self, id: u64, now: i64) -> Result<Schedule, String> {
+    if self.running.contains(&id) {
+      return Err(format!("Schedule {} is already running", id));
+    }
+    let s = self.get_mut(id)?;
+    s.last_run = Some(now);
+    let s = s.clone();
+    self.running.insert(id);
+    let _ = self.save();
+    Ok(s)
+  }
+
+  fn finish(&mut self, id: u64, record: RunRecord) {
+    self.running.remove(&id);
+    if let Ok(s) = self.get_mut(id) {
+      if record.ok {
+        s.last_success = Some(record.started);
+      }
+      s.history.push(record);
+      let excess = s.history.len().saturating_sub(HISTORY_LEN);
+      s.history.drain(..excess);
+    }
+    let _ = self.save();
+  }
+}
+
+// Payload of "schedule-run" events
+#[derive(Clone, serde::Serialize)]
+struct ScheduleRun {
+  id: u64,
+  // "started" | "finished"
+  status: String,
+  record: Option<RunRecord>,
+}
+
+fn now_secs() -> i64 {
+  SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0)
+}
+
+fn modified_secs(path: &Path) -> i64 {
+  fs::metadata(path)
+    .and_then(|m| m.modified())
+    .ok()
+    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+    .map(|d| d.as_secs() as i64)
+    .unwrap_or(0)
+}
+
+fn run_action(app: &AppHandle, schedule: &Schedule) -> Result<String, String> {
+  match &schedule.action {
+    ScheduleAction::RescanModels => {
+      let mgr = app.state::<Mutex<ModelManager>>();
+      let mut mgr = mgr.locked();
+      mgr.scan_models();
+      Ok(format!("{} models found", mgr.list_models().len()))
+    }
+    ScheduleAction::TranslateFolder { path, output_dir, source_lang, target_lang, model_id } => {
+      let since = schedule.last_success.unwrap_or(0);
+      let entries = fs::read_dir(path).map_err(|e| format!("failed to read {}: {}", path, e))?;
+      let (mut done, mut failed) = (0, Vec::new());
+      for file in entries.flatten().map(|e| e.path()) {
+        let ext = file.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+        if !file.is_file() || !translate::DOCUMENT_EXTENSIONS.contains(&ext.as_str()) || translate::is_translation_output(&file, target_lang) {
+          continue;
+        }
+        let out = translate::output_path(&file, Path::new(output_dir), target_lang);
+        if modified_secs(&file) < since && out.exists() {
+          continue;
+        }
+        match translate::translate_file(app, &file, Path::new(output_dir), source_lang, target_lang, model_id.as_deref(), |_, _| {}) {
+          Ok(_) => done += 1,
+          Err(e) => failed.push(e),
+        }
+      }
+      if failed.is_empty() {
+        Ok(format!("{} documents translated", done))
+      } else {
+        Err(format!("{} documents translated, {} failed: {}", done, failed.len(), failed.join("; ")))
+      }
+    }
+  }
+}
+
+fn run_schedule(app: AppHandle, id: u64) -> Result<(), String> {
+  let started = now_secs();
+  let schedule = app.state::<Mutex<Scheduler>>().locked().begin(id, started)?;
+  events::emit(&app, "schedule-run", ScheduleRun { id, status: "started".into(), record: None });
+  thread::spawn(move || {
+    let result = run_action(&app, &schedule);
+    let record = RunRecord {
+      started,
+      finished: now_secs(),
+      ok: result.is_ok(),
+      message: result.unwrap_or_else(|e| e),
+    };
+    app.state::<Mutex<Scheduler>>().locked().finish(id, record.clone());
+    events::emit(&app, "schedule-run", ScheduleRun { id, status: "finished".into(), record: Some(record) });
+  });
+  Ok(())
+}
+
+// background thread firing due schedules; runs missed while the app was closed are skipped
+pub fn start(app: AppHandle) {
+  thread::spawn(move || {
+    let mut last_minute = 0;
+    loop {
+      let now = Local::now();
+      let minute = now.timestamp() / 60;
+      if minute != last_minute {
+        last_minute = minute;
+        let due: Vec<u64> = {
+          let scheduler = app.state::<Mutex<Scheduler>>();
+          let scheduler = scheduler.locked();
+          scheduler
+            .file
+            .schedules
+            .iter()
+            .filter(|s| s.enabled && s.last_run.is_none_or(|t| t / 60 != minute))
+            .filter(|s| Cron::parse(&s.cron).map(|c| c.matches(&now)).unwrap_or(false))
+            .map(|s| s.id)
+            .collect()
+        };
+        for id in due {
+          let _ = run_schedule(app.clone(), id);
+        }
+      }
+      thread::sleep(TICK);
+    }
+  });
+}
+
+// ------------------ Tauri commands ------------------
+
+#[tauri::command]
+pub fn list_schedules(scheduler: tauri::State<'_, Mutex<Scheduler>>) -> Vec<Schedule> {
+  scheduler.locked().file.schedules.clone()
+}
+
+// `cron` is "minute hour day month weekday" (e.g. "0 2 * * *" for every night at 2:00) or @daily/@weekly/...
+#[tauri::command]
+pub fn add_schedule(
+  name: String,
+  cron: String,
+  action: ScheduleAction,
+  scheduler: tauri::State<'_, Mutex<Scheduler>>
+) -> Result<Schedule, AppError> {
+  Cron::parse(&cron)?;
+  if let ScheduleAction::TranslateFolder { path, .. } = &action {
+    if !Path::new(path).is_dir() {
+      return Err(format!("Folder not found: {}", path).into());
+    }
+  }
+  let mut scheduler = scheduler.locked();
+  scheduler.file.next_id += 1;
+  let schedule = Schedule {
+    id: scheduler.file.next_id,
+    name,
+    cron,
+    action,
+    enabled: true,
+    last_run: None,
+    last_success: None,
+    history: Vec::new(),
+  };
+  scheduler.file.schedules.push(schedule.clone());
+  scheduler.save()?;
+  Ok(schedule)
+}
+
+#[tauri::command]
+pub fn set_schedule_enabled(id: u64, enabled: bool, scheduler: tauri::State<'_, Mutex<Scheduler>>) -> Result<Schedule, AppError> {
+  let mut scheduler = scheduler.locked();
+  let s = scheduler.get_mut(id)?;
+  s.enabled = enabled;
+  let s = s.clone();
+  scheduler.save()?;
+  Ok(s)
+}
+
+#[tauri::command]
+pub fn remove_schedule(id: u64, scheduler: tauri::State<'_, Mutex<Scheduler>>) -> Result<(), AppError> {
+  let mut scheduler = scheduler.locked();
+  scheduler.get(id)?;
+  scheduler.file.schedules.retain(|s| s.id != id);
+  Ok(scheduler.save()?)
+}
+
+#[tauri::command]
+pub fn run_schedule_now(id: u64, app: AppHandle) -> Result<(), AppError> {
+  Ok(run_schedule(app, id)?)
+}
+
+#[tauri::command]
+pub fn get_schedule_history(id: u64, scheduler: tauri::State<'_, Mutex<Scheduler>>) -> Result<Vec<RunRecord>, AppError> {
+  Ok(scheduler.locked().get(id).map(|s| s.history.clone())?)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::TimeZone;
+
+  fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+    Local.with_ymd_and_hms(year, month, day, hour, minute, 0).unwrap()
+  }
+
+  #[test]
+  fn fields_expand_to_values() {
+    assert_eq!(parse_field("*", 0, 5).unwrap(), [0, 1, 2, 3, 4, 5]);
+    assert_eq!(parse_field("1-3,5", 0, 59).unwrap(), [1, 2, 3, 5]);
+    assert_eq!(parse_field("*/15", 0, 59).unwrap(), [0, 15, 30, 45]);
+    assert_eq!(parse_field("0-30/10", 0, 59).unwrap(), [0, 10, 20, 30]);
+    // a start with a step runs to the end of the range
+    assert_eq!(parse_field("50/5", 0, 59).unwrap(), [50, 55]);
+  }
+
+  #[test]
+  fn bad_fields_are_refused() {
+    for field in ["60", "5-1", "*/0", "a", "1-", "", "-1"] {
+      assert!(parse_field(field, 0, 59).is_err(), "{}", field);
+    }
+    assert!(Cron::parse("* * * *").is_err());
+    assert!(Cron::parse("0 0 0 * *").is_err());
+  }
+
+  #[test]
+  fn matches_weekday_schedules() {
+    // 2026-03-02 is a Monday
+    let cron = Cron::parse("30 9 * * 1-5").unwrap();
+    assert!(cron.matches(&at(2026, 3, 2, 9, 30)));
+    assert!(!cron.matches(&at(2026, 3, 2, 9, 31)));
+    assert!(!cron.matches(&at(2026, 3, 1, 9, 30)));
+  }
+
+  #[test]
+  fn seven_is_sunday() {
+    let cron = Cron::parse("0 12 * * 7").unwrap();
+    assert!(cron.matches(&at(2026, 3, 1, 12, 0)));
+  }
+
+  #[test]
+  fn day_and_weekday_are_ored_when_both_set() {
+    // the 15th, or any Monday
+    let cron = Cron::parse("0 0 15 * 1").unwrap();
+    assert!(cron.matches(&at(2026, 3, 15, 0, 0)));
+    assert!(cron.matches(&at(2026, 3, 2, 0, 0)));
+    assert!(!cron.matches(&at(2026, 3, 3, 0, 0)));
+    // only the day restricted: weekday doesn't widen it
+    let cron = Cron::parse("0 0 15 * *").unwrap();
+    assert!(!cron.matches(&at(2026, 3, 2, 0, 0)));
+  }
+
+  #[test]
+  fn shorthands() {
+    let cron = Cron::parse("@daily").unwrap();
+    assert!(cron.matches(&at(2026, 3, 4, 0, 0)));
+    assert!(!cron.matches(&at(2026, 3, 4, 1, 0)));
+    let cron = Cron::parse("@monthly").unwrap();
+    assert!(cron.matches(&at(2026, 4, 1, 0, 0)));
+    assert!(!cron.matches(&at(2026, 4, 2, 0, 0)));
+  }
+}
@@ -0,0 +1,147 @@
+// src-tauri/src/segments.rs
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use tauri::{Manager, Window};
+
+use crate::engine;
+use crate::error::{AppError, LockExt};
+use crate::lang;
+use crate::tm::TranslationMemory;
+use crate::ModelManager;
+
+// A translated unit the user can review and post-edit
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct Segment {
+  pub id: u64,
+  pub source_lang: String,
+  pub target_lang: String,
+  pub source: String,
+  pub translation: String,
+  pub model_id: Option<String>,
+  // true once the user accepted or edited the translation
+  pub accepted: bool,
+}
+
+// Segments shown in the UI during this run (managed by Tauri)
+pub struct SegmentStore {
+  segments: HashMap<u64, Segment>,
+  next_id: u64,
+}
+
+impl SegmentStore {
+  pub fn new() -> Self {
+    Self { segments: HashMap::new(), next_id: 0 }
+  }
+
+  pub fn register(&mut self, source_lang: &str, target_lang: &str, source: &str, translation: &str, model_id: Option<String>) -> Segment {
+    self.next_id += 1;
+    let seg = Segment {
+      id: self.next_id,
+      source_lang: source_lang.to_string(),
+      target_lang: target_lang.to_string(),
+      source: source.to_string(),
+      translation: translation.to_string(),
+      model_id,
+      accepted: false,
+    };
+    self.segments.insert(seg.id, seg.clone());
+    seg
+  }
+
+  pub fn get(&self, id: u64) -> Result<Segment, String> {
+    self.segments.get(&id).cloned().ok_or(format!("Segment {} not found", id))
+  }
+
+  pub fn get_mut(&mut self, id: u64) -> Result<&mut Segment, String> {
+    self.segments.get_mut(&id).ok_or(format!("Segment {} not found", id))
+  }
+}
+
+fn alternatives_prompt(seg: &Segment, avoid: &[String], n: usize) -> String {
+  let avoid_list = avoid.iter().map(|a| format!("- {}", a)).collect::<Vec<_>>().join("\n");
+  format!(
+    "Translate the following {src} text into {tgt}. Give {n} different translations that vary in wording or tone, \
+one per line, numbered 1. to {n}. Do not repeat any of these existing translations:\n{avoid}\n\nText: {text}\n\nTranslations:",
+    src = lang::language_name(&seg.source_lang),
+    tgt = lang::language_name(&seg.target_lang),
+    n = n,
+    avoid = avoid_list,
+    text = seg.source
+  )
+}
+
+// "1. foo", "2) bar", "- baz" -> candidate strings
+fn parse_numbered(output: &str) -> Vec<String> {
+  output
+    .lines()
+    .map(|l| l.trim())
+    .map(|l| l.trim_start_matches(|c: char| c.is_ascii_digit()).trim_start_matches(['.', ')', '-', ':']).trim())
+    .filter(|l| !l.is_empty())
+    .map(|l| l.trim_matches('"').to_string())
+    .collect()
+}
+
+// ------------------ Tauri commands ------------------
+
+// make a translation reviewable (returns its segment id)
+#[tauri::command]
+pub fn register_segment(
+  source_lang: String,
+  target_lang: String,
+  source: String,
+  translation: String,
+  model_id: Option<String>,
+  segments: tauri::State<'_, Mutex<SegmentStore>>
+) -> Segment {
+  segments.locked().register(&source_lang, &target_lang, &source, &translation, model_id)
+}
+
+// up to `n` alternative translations for a segment, asking the model again for any it missed
+#[tauri::command(async)]
+pub fn suggest_alternatives(segment_id: u64, n: usize, window: Window) -> Result<Vec<String>, AppError> {
+  let n = n.clamp(1, 10);
+  let seg = window.state::<Mutex<SegmentStore>>().locked().get(segment_id)?;
+  let (model, config) = window.state::<Mutex<ModelManager>>().locked().model_for_request(seg.model_id.as_deref())?;
+
+  let mut seen = vec![seg.translation.clone()];
+  let mut alternatives = Vec::new();
+  for _ in 0..2 {
+    let missing = n - alternatives.len();
+    let prompt = alternatives_prompt(&seg, &seen, missing);
+    let max_tokens = (engine::estimate_tokens(&seg.source) * 2 + 16) * missing as u32;
+    let output = engine::generate(&model.path, &config, &prompt, max_tokens)?;
+    for candidate in parse_numbered(&output) {
+      if alternatives.len() < n && !seen.iter().any(|s| s.trim() == candidate.trim()) {
+        seen.push(candidate.clone());
+        alternatives.push(candidate);
+      }
+    }
+    if alternatives.len() >= n {
+      break;
+    }
+  }
+  Ok(alternatives)
+}
+
+// store the user's chosen/edited translation and remember it in the translation memory
+#[tauri::command]
+pub fn accept_translation(
+  segment_id: u64,
+  text: String,
+  segments: tauri::State<'_, Mutex<SegmentStore>>,
+  tm: tauri::State<'_, Mutex<TranslationMemory>>
+) -> Result<Segment, AppError> {
+  if text.trim().is_empty() {
+    return Err(AppError::InvalidInput("Translation must not be empty".into()));
+  }
+  let seg = {
+    let mut store = segments.locked();
+    let seg = store.get_mut(segment_id)?;
+    seg.translation = text;
+    seg.accepted = true;
+    seg.clone()
+  };
+  tm.locked().upsert(&seg.source_lang, &seg.target_lang, &seg.source, &seg.translation, "accepted")?;
+  Ok(seg)
+}
@@ -0,0 +1,239 @@
+// src-tauri/src/selftest.rs
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tauri::{Listener, Manager, Window};
+
+use crate::engine;
+use crate::error::LockExt;
+use crate::runtime;
+use crate::sampling::SamplingParams;
+use crate::shutdown;
+use crate::store;
+use crate::{mock_command, ModelInfo, ModelManager};
+
+// how long to wait for the first streamed line and for the process to exit after stop
+const STREAM_TIMEOUT: Duration = Duration::from_secs(20);
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+  Pass,
+  Fail,
+  Skip,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct SelfTestCheck {
+  // "storage" | "scan" | "runtime" | "generate" | "stream" | "cancel" | "stop"
+  pub subsystem: String,
+  pub status: CheckStatus,
+  pub detail: String,
+  pub millis: u64,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct SelfTestReport {
+  // no check failed (skips don't count)
+  pub passed: bool,
+  pub model: Option<String>,
+  pub checks: Vec<SelfTestCheck>,
+}
+
+struct Checks(Vec<SelfTestCheck>);
+
+impl Checks {
+  // time `f` and record its outcome; Ok(None) records a skip
+  fn run<F: FnOnce() -> Result<Option<String>, String>>(&mut self, subsystem: &str, f: F) -> bool {
+    let started = Instant::now();
+    let (status, detail) = match f() {
+      Ok(Some(detail)) => (CheckStatus::Pass, detail),
+      Ok(None) => (CheckStatus::Skip, "not applicable".into()),
+      Err(e) => (CheckStatus::Fail, e),
+    };
+    self.push(subsystem, status, detail, started.elapsed().as_millis() as u64);
+    status == CheckStatus::Pass
+  }
+
+  fn push(&mut self, subsystem: &str, status: CheckStatus, detail: String, millis: u64) {
+    self.0.push(SelfTestCheck { subsystem: subsystem.into(), status, detail, millis });
+  }
+
+  fn skip(&mut self, subsystem: &str, reason: &str) {
+    self.push(subsystem, CheckStatus::Skip, reason.into(), 0);
+  }
+}
+
+// smallest complete model on disk, so the test stays quick
+fn smallest_model(models: Vec<ModelInfo>) -> Option<ModelInfo> {
+  models
+    .into_iter()
+    .filter(|m| m.complete && !m.speech)
+    .min_by_key(|m| fs::metadata(&m.path).map(|md| if md.is_file() { md.len() } else { 0 }).unwrap_or(u64::MAX))
+}
+
+// spawn the model the way start_model does, wait for streamed output, then stop it mid-stream
+fn stream_model(window: &Window, model_id: &str, checks: &mut Checks) {
+  let (tx, rx) = mpsc::channel::<String>();
+  let out_tx = tx.clone();
+  let output = window.listen_any("model-output", move |e| {
+    // runtime logs on stderr don't count as streamed output
+    let Ok(event) = serde_json::from_str::<serde_json::Value>(e.payload()) else { return };
+    if event["kind"] == "token" {
+      let _ = out_tx.send(format!("output:{}", event["text"].as_str().unwrap_or("")));
+    }
+  });
+  let status = window.listen_any("model-status", move |e| {
+    if e.payload().contains("false") {
+      let _ = tx.send("stopped".into());
+    }
+  });
+
+  let mgr = window.state::<Mutex<ModelManager>>();
+  checks.run("stream", || {
+    crate::start_process(window, model_id, &SamplingParams::default())?;
+    match rx.recv_timeout(STREAM_TIMEOUT) {
+      Ok(msg) if msg.starts_with("output:") => Ok(Some(format!("first output: {}", msg.trim_start_matches("output:").chars().take(60).collect::<String>()))),
+      Ok(_) => Err("process exited before producing output".into()),
+      Err(_) => Err(format!("no output within {}s", STREAM_TIMEOUT.as_secs())),
+    }
+  });
+
+  if !mgr.locked().processes.contains_key(model_id) {
+    checks.skip("cancel", "no process to cancel");
+    checks.skip("stop", "no process to stop");
+  } else {
+    let cancelled = checks.run("cancel", || {
+      let (id, mut child, timeout) = mgr.locked().take_process(Some(model_id))?;
+      shutdown::terminate_blocking(&id, &mut child, timeout).map(|r| Some(format!("process stopped mid-stream ({})", r.method)))
+    });
+    checks.run("stop", || {
+      if !cancelled {
+        return Ok(None);
+      }
+      let deadline = Instant::now() + STOP_TIMEOUT;
+      while Instant::now() < deadline {
+        if let Ok(msg) = rx.recv_timeout(deadline - Instant::now()) {
+          if msg == "stopped" {
+            return Ok(Some("model-status reported the process stopped".into()));
+          }
+        }
+      }
+      Err(format!("no stop notification within {}s", STOP_TIMEOUT.as_secs()))
+    });
+  }
+  window.unlisten(output);
+  window.unlisten(status);
+}
+
+// no models installed: exercise the process plumbing with the python mock instead
+fn stream_mock(checks: &mut Checks) {
+  let mut child = match mock_command().spawn() {
+    Ok(c) => c,
+    Err(e) => {
+      checks.push("stream", CheckStatus::Fail, format!("failed to spawn the mock: {}", e), 0);
+      checks.skip("cancel", "mock did not start");
+      checks.skip("stop", "mock did not start");
+      return;
+    }
+  };
+  let (tx, rx) = mpsc::channel();
+  if let Some(out) = child.stdout.take() {
+    thread::spawn(move || {
+      for line in BufReader::new(out).lines().map_while(Result::ok) {
+        if tx.send(line).is_err() {
+          break;
+        }
+      }
+    });
+  }
+  checks.run("stream", || match rx.recv_timeout(STREAM_TIMEOUT) {
+    Ok(line) => Ok(Some(format!("mock output: {}", line))),
+    Err(_) => Err("the mock produced no output (is python3 installed?)".into()),
+  });
+  let cancelled = checks.run("cancel", || child.kill().map(|_| Some("mock killed mid-stream".into())).map_err(|e| e.to_string()));
+  checks.run("stop", || {
+    if !cancelled {
+      return Ok(None);
+    }
+    child.wait().map(|s| Some(format!("mock exited ({})", s))).map_err(|e| e.to_string())
+  });
+}
+
+// ------------------ Tauri commands ------------------
+
+// exercise storage, model scan, runtime selection, a one-shot generation and a streamed
+// process (spawn, cancel, stop) and report pass/fail per subsystem
+#[tauri::command(async)]
+pub fn run_self_test(window: Window) -> SelfTestReport {
+  let mut checks = Checks(Vec::new());
+
+  checks.run("storage", || {
+    let path = store::data_file("selftest.json");
+    store::save_json(&path, &serde_json::json!({"ok": true}))?;
+    let back: serde_json::Value = store::load_json(&path);
+    let _ = fs::remove_file(&path);
+    if back["ok"] == true {
+      Ok(Some(format!("{} is writable", path.parent().unwrap_or(&path).to_string_lossy())))
+    } else {
+      Err(format!("could not read back {}", path.to_string_lossy()))
+    }
+  });
+
+  let mut models = Vec::new();
+  checks.run("scan", || {
+    let state = window.state::<Mutex<ModelManager>>();
+    let mut mgr = state.locked();
+    mgr.scan_models();
+    models = mgr.list_models();
+    let incomplete = models.iter().filter(|m| !m.complete).count();
+    match incomplete {
+      0 => Ok(Some(format!("{} models found", models.len()))),
+      n => Err(format!("{} models found, {} with missing shards", models.len(), n)),
+    }
+  });
+  let model = smallest_model(models);
+
+  checks.run("runtime", || match &model {
+    Some(m) => match runtime::select_runtime(&m.path) {
+      Some(rt) => Ok(Some(format!("{:?}: {}", rt.variant, rt.reason))),
+      None => Ok(Some("no bundled runtime; wrapper scripts or the mock will be used".into())),
+    },
+    None => Ok(None),
+  });
+
+  match &model {
+    Some(m) => {
+      checks.run("generate", || {
+        let (info, config) = window.state::<Mutex<ModelManager>>().locked().model_for_request(Some(&m.id))?;
+        let out = engine::generate(&info.path, &config, "Reply with the single word OK.", 8)?;
+        if out.trim().is_empty() {
+          Err("model returned no text".into())
+        } else {
+          Ok(Some(format!("{}: {}", m.id, out.chars().take(60).collect::<String>())))
+        }
+      });
+      if window.state::<Mutex<ModelManager>>().locked().running_models().contains(&m.id) {
+        for subsystem in ["stream", "cancel", "stop"] {
+          checks.skip(subsystem, "this model is already running; stop it to test streaming");
+        }
+      } else {
+        stream_model(&window, &m.id, &mut checks);
+      }
+    }
+    None => {
+      checks.skip("generate", "no models installed");
+      stream_mock(&mut checks);
+    }
+  }
+
+  SelfTestReport {
+    passed: checks.0.iter().all(|c| c.status != CheckStatus::Fail),
+    model: model.map(|m| m.id),
+    checks: checks.0,
+  }
+}
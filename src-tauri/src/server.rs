@@ -0,0 +1,150 @@
+// src-tauri/src/server.rs
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tauri::{Emitter, Window};
+
+use crate::dispatch::{self, Priority};
+use crate::error::LockExt;
+use crate::runtime;
+use crate::sampling::SamplingParams;
+use crate::stream::{StreamConfig, TokenStream};
+
+// name of the llama.cpp HTTP server binary among the bundled tools
+pub const SERVER_TOOL: &str = "llama-server";
+// prompts the server works on at the same time (its -np slots share the context window)
+pub const SLOTS: u32 = 4;
+// loading a large model from a slow disk can take a while
+const LOAD_TIMEOUT: Duration = Duration::from_secs(180);
+const HEALTH_POLL: Duration = Duration::from_millis(250);
+
+// llama-server of the same build as the chosen runtime, else any bundled one
+pub fn server_exe(runtime_exe: &str) -> Option<PathBuf> {
+  runtime::sibling_tool(runtime_exe, SERVER_TOOL)
+}
+
+// a port nobody listens on right now; the server binds it a moment later
+pub fn free_port() -> Result<u16, String> {
+  let listener = TcpListener::bind(("127.0.0.1", 0)).map_err(|e| format!("no free local port: {}", e))?;
+  listener.local_addr().map(|a| a.port()).map_err(|e| format!("no free local port: {}", e))
+}
+
+// A llama-server sidecar serving one model on a localhost port
+pub struct LlamaServer {
+  model_id: String,
+  model_path: String,
+  base_url: String,
+  ready: AtomicBool,
+  // cancel flags of the completions in flight, by request id
+  requests: Mutex<HashMap<u64, Arc<AtomicBool>>>,
+}
+
+impl LlamaServer {
+  pub fn new(model_id: &str, model_path: &str, port: u16) -> Arc<Self> {
+    Arc::new(Self {
+      model_id: model_id.to_string(),
+      model_path: model_path.to_string(),
+      base_url: format!("http://127.0.0.1:{}", port),
+      ready: AtomicBool::new(false),
+      requests: Mutex::new(HashMap::new()),
+    })
+  }
+
+  // block until /health answers 200 (the model is loaded) or `timeout` passes
+  pub fn wait_ready(&self, timeout: Duration) -> Result<(), String> {
+    let deadline = Instant::now() + timeout;
+    while !self.ready.load(Ordering::Relaxed) {
+      // 503 while loading, connection refused before the port is bound
+      if ureq::get(format!("{}/health", self.base_url)).call().is_ok() {
+        self.ready.store(true, Ordering::Relaxed);
+        break;
+      }
+      if Instant::now() >= deadline {
+        return Err(format!("llama-server for '{}' did not become ready within {}s", self.model_id, timeout.as_secs()));
+      }
+      thread::sleep(HEALTH_POLL);
+    }
+    Ok(())
+  }
+
+  // announce the server as ready once the model is loaded
+  pub fn watch_health(self: &Arc<Self>, window: &Window) {
+    let (server, window) = (self.clone(), window.clone());
+    thread::spawn(move || {
+      let ready = server.wait_ready(LOAD_TIMEOUT).is_ok();
+      let _ = window.emit("model-status", serde_json::json!({"model_id": server.model_id, "running": true, "ready": ready}));
+    });
+  }
+
+  // stream a completion of `prompt` as "model-output" events; returns its request id right away
+  pub fn complete(self: &Arc<Self>, window: &Window, prompt: String, sampling: SamplingParams, priority: Priority) -> u64 {
+    let stream = TokenStream::new(&self.model_id, StreamConfig::default());
+    let request_id = stream.begin();
+    let cancel = Arc::new(AtomicBool::new(false));
+    self.requests.locked().insert(request_id, cancel.clone());
+
+    let (server, window) = (self.clone(), window.clone());
+    thread::spawn(move || {
+      let _busy = dispatch::streaming(&server.model_path, priority);
+      if let Err(e) = server.run_completion(&window, &stream, &prompt, &sampling, &cancel) {
+        stream.stderr_line(&window, &e);
+      }
+      if cancel.load(Ordering::Relaxed) {
+        stream.cancel(&window);
+      } else {
+        stream.closed(&window);
+      }
+      server.requests.locked().remove(&request_id);
+    });
+    request_id
+  }
+
+  fn run_completion(&self, window: &Window, stream: &TokenStream, prompt: &str, sampling: &SamplingParams, cancel: &AtomicBool) -> Result<(), String> {
+    self.wait_ready(LOAD_TIMEOUT)?;
+    let mut body = serde_json::json!({ "prompt": prompt, "stream": true, "cache_prompt": true });
+    sampling.server_fields(&mut body);
+    let body = body.to_string();
+    let resp = ureq::post(format!("{}/completion", self.base_url))
+      .header("Content-Type", "application/json")
+      .send(body.as_bytes())
+      .map_err(|e| format!("llama-server request failed: {}", e))?;
+    // server-sent events, one "data: {...}" line per token
+    let reader = BufReader::new(resp.into_body().into_reader());
+    for line in reader.lines() {
+      // dropping the response closes the connection, which makes the server stop generating
+      if cancel.load(Ordering::Relaxed) {
+        return Ok(());
+      }
+      let line = line.map_err(|e| format!("llama-server stream broke off: {}", e))?;
+      let Some(data) = line.strip_prefix("data: ") else { continue };
+      let Ok(event) = serde_json::from_str::<serde_json::Value>(data) else { continue };
+      if let Some(error) = event.get("error") {
+        return Err(format!("llama-server: {}", error));
+      }
+      if let Some(text) = event.get("content").and_then(|c| c.as_str()).filter(|t| !t.is_empty()) {
+        stream.token(window, text);
+      }
+      if event.get("stop").and_then(|s| s.as_bool()).unwrap_or(false) {
+        break;
+      }
+    }
+    Ok(())
+  }
+
+  // stop a completion of this server; false when `request_id` isn't one of its requests
+  pub fn cancel(&self, request_id: u64) -> bool {
+    match self.requests.locked().get(&request_id) {
+      Some(flag) => {
+        flag.store(true, Ordering::Relaxed);
+        true
+      }
+      None => false,
+    }
+  }
+}
@@ -0,0 +1,453 @@
+// src-tauri/src/session.rs
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tauri::{Emitter, Manager, Window};
+
+use crate::chat_template::{self, ChatTemplate};
+use crate::dispatch::{self, Priority};
+use crate::domain;
+use crate::engine;
+use crate::error::{AppError, LockExt};
+use crate::events;
+use crate::filter;
+use crate::output::{self, OutputFormat};
+use crate::preload;
+use crate::readable::{self, ReadingOptions};
+use crate::store;
+use crate::system_prompt;
+use crate::ModelManager;
+
+// tokens reserved for each assistant reply
+const REPLY_TOKENS: u32 = 512;
+// turns left untouched by summarize_and_compact
+const KEEP_RECENT_TURNS: usize = 4;
+// vocabulary items kept in a closing summary
+const MAX_VOCABULARY: usize = 12;
+
+// One message in a conversation
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Turn {
+  // "user" | "assistant" | "system"
+  pub role: String,
+  pub text: String,
+  // estimated token count of `text`
+  pub tokens: u32,
+}
+
+impl Turn {
+  pub fn new(role: &str, text: &str) -> Self {
+    Self { role: role.to_string(), text: text.to_string(), tokens: engine::estimate_tokens(text) }
+  }
+}
+
+// A word or phrase worth remembering from a conversation
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct VocabularyItem {
+  pub term: String,
+  pub meaning: String,
+}
+
+// Written in the background when a session is closed, for the history sidebar
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct SessionSummary {
+  pub text: String,
+  pub vocabulary: Vec<VocabularyItem>,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Session {
+  pub id: String,
+  // model used for this conversation (falls back to the loaded model)
+  pub model_id: Option<String>,
+  pub turns: Vec<Turn>,
+  // closed sessions take no more messages
+  #[serde(default)]
+  pub closed: bool,
+  // true while the closing summary is queued or being written
+  #[serde(default)]
+  pub summarizing: bool,
+  #[serde(default)]
+  pub summary: Option<SessionSummary>,
+  // domain pack whose system prompt, terms and rules shape the replies
+  #[serde(default)]
+  pub domain: Option<String>,
+  // instructions in front of every message, instead of the model's (see set_system_prompt)
+  #[serde(default)]
+  pub system_prompt: Option<String>,
+}
+
+impl Session {
+  // tokens currently occupying the context window
+  pub fn used_tokens(&self) -> u32 {
+    self.turns.iter().map(|t| t.tokens).sum()
+  }
+
+  // plain role-labelled transcript ending with an open assistant turn
+  pub fn build_prompt(&self) -> String {
+    ChatTemplate::Plain.format(&self.turns)
+  }
+}
+
+// Payload of "context-usage" events
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ContextUsage {
+  pub session_id: String,
+  pub used_tokens: u32,
+  pub context_size: u32,
+  pub percent: f32,
+}
+
+// Payload of "context-shift" events, sent when a conversation outgrew the model's context window
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ContextShift {
+  pub session_id: String,
+  // older turns replaced by a summary in the session
+  pub summarized_turns: usize,
+  // turns left out of this prompt only, when even the summary didn't make enough room
+  pub dropped_turns: usize,
+}
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct SessionFile {
+  counter: u64,
+  sessions: HashMap<String, Session>,
+}
+
+// All conversations, persisted in ./data/sessions.json so they survive restarts (managed by Tauri)
+pub struct SessionStore {
+  path: PathBuf,
+  file: SessionFile,
+}
+
+impl SessionStore {
+  pub fn load() -> Self {
+    let path = store::data_file("sessions.json");
+    let mut file: SessionFile = store::load_json(&path);
+    // summaries being written when the app quit are gone with their thread
+    for session in file.sessions.values_mut() {
+      session.summarizing = false;
+    }
+    Self { path, file }
+  }
+
+  fn save(&self) -> Result<(), String> {
+    store::save_json(&self.path, &self.file)
+  }
+
+  pub fn create(&mut self, model_id: Option<String>) -> Result<Session, String> {
+    self.file.counter += 1;
+    let millis = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0);
+    let id = format!("session-{}-{}", millis, self.file.counter);
+    let session = Session { id: id.clone(), model_id, turns: Vec::new(), closed: false, summarizing: false, summary: None, domain: None, system_prompt: None };
+    self.file.sessions.insert(id, session.clone());
+    self.save()?;
+    Ok(session)
+  }
+
+  pub fn get(&self, id: &str) -> Result<&Session, String> {
+    self.file.sessions.get(id).ok_or(format!("Session '{}' not found", id))
+  }
+
+  // change a session and write the store back to disk
+  pub fn update<T, F: FnOnce(&mut Session) -> Result<T, String>>(&mut self, id: &str, f: F) -> Result<T, String> {
+    let session = self.file.sessions.get_mut(id).ok_or(format!("Session '{}' not found", id))?;
+    let result = f(session)?;
+    self.save()?;
+    Ok(result)
+  }
+
+  pub fn delete(&mut self, id: &str) -> Result<(), String> {
+    self.file.sessions.remove(id).ok_or(format!("Session '{}' not found", id))?;
+    self.save()
+  }
+
+  // add a session from elsewhere, under a new id if its own is taken; returns the id used
+  pub fn insert(&mut self, mut session: Session) -> Result<String, String> {
+    if self.file.sessions.contains_key(&session.id) {
+      self.file.counter += 1;
+      session.id = format!("{}-{}", session.id, self.file.counter);
+    }
+    let id = session.id.clone();
+    self.file.sessions.insert(id.clone(), session);
+    self.save()?;
+    Ok(id)
+  }
+
+  // newest first
+  pub fn list(&self) -> Vec<Session> {
+    let mut sessions: Vec<Session> = self.file.sessions.values().cloned().collect();
+    sessions.sort_by(|a, b| b.id.cmp(&a.id));
+    sessions
+  }
+}
+
+fn usage_for(session: &Session, context_size: u32) -> ContextUsage {
+  let used = session.used_tokens();
+  ContextUsage {
+    session_id: session.id.clone(),
+    used_tokens: used,
+    context_size,
+    percent: used as f32 / context_size.max(1) as f32 * 100.0,
+  }
+}
+
+fn summary_schema() -> serde_json::Value {
+  serde_json::json!({
+    "type": "object",
+    "properties": {
+      "summary": { "type": "string" },
+      "vocabulary": {
+        "type": "array",
+        "items": {
+          "type": "object",
+          "properties": { "term": { "type": "string" }, "meaning": { "type": "string" } },
+          "required": ["term", "meaning"]
+        }
+      }
+    },
+    "required": ["summary", "vocabulary"]
+  })
+}
+
+// short summary plus the key words and phrases of a finished conversation
+fn write_summary(window: &Window, session: &Session) -> Result<SessionSummary, String> {
+  let (model, config) = window.state::<Mutex<ModelManager>>().locked().model_for_request(session.model_id.as_deref())?;
+  let prompt = format!(
+    "Summarize the conversation below in two or three sentences for a history list, then list up to {} key words or \
+     phrases a language learner should remember from it, each with a short meaning. Reply with JSON only: \
+     {{\"summary\": \"...\", \"vocabulary\": [{{\"term\": \"...\", \"meaning\": \"...\"}}]}}.\n\n{}",
+    MAX_VOCABULARY,
+    session.build_prompt().trim_end_matches("Assistant:")
+  );
+  let value = engine::generate_json(&model.path, &config, &prompt, REPLY_TOKENS, &summary_schema())?;
+  let text = value.get("summary").and_then(|v| v.as_str()).unwrap_or("").trim().to_string();
+  if text.is_empty() {
+    return Err("model returned an empty summary".into());
+  }
+  let vocabulary: Vec<VocabularyItem> = value
+    .get("vocabulary")
+    .and_then(|v| serde_json::from_value::<Vec<VocabularyItem>>(v.clone()).ok())
+    .unwrap_or_default()
+    .into_iter()
+    .filter(|v| !v.term.trim().is_empty())
+    .take(MAX_VOCABULARY)
+    .collect();
+  Ok(SessionSummary { text, vocabulary })
+}
+
+// drop the oldest turns (after the system turns in front) until they fit in `budget` tokens; the
+// newest turn always stays. Returns how many were dropped
+fn slide_window(turns: &mut Vec<Turn>, budget: u32) -> usize {
+  let mut dropped = 0;
+  while turns.iter().map(|t| t.tokens).sum::<u32>() > budget {
+    match turns.iter().position(|t| t.role != "system") {
+      Some(i) if i + 1 < turns.len() => {
+        turns.remove(i);
+        dropped += 1;
+      }
+      _ => break,
+    }
+  }
+  dropped
+}
+
+// replace all but the most recent turns with a model-written summary; returns how many were replaced
+fn compact(window: &Window, session_id: &str) -> Result<(usize, ContextUsage), String> {
+  let sessions = window.state::<Mutex<SessionStore>>();
+  let (model_id, old_count, mut old) = {
+    let store = sessions.locked();
+    let session = store.get(session_id)?;
+    if session.turns.len() <= KEEP_RECENT_TURNS {
+      return Err("Not enough history to compact".into());
+    }
+    let old_count = session.turns.len() - KEEP_RECENT_TURNS;
+    (session.model_id.clone(), old_count, Session { turns: session.turns[..old_count].to_vec(), ..session.clone() })
+  };
+
+  let (model, config) = window.state::<Mutex<ModelManager>>().locked().model_for_request(model_id.as_deref())?;
+  let ctx = engine::context_size(&config);
+  // history longer than the context itself is summarized from its most recent part
+  slide_window(&mut old.turns, ctx.saturating_sub(REPLY_TOKENS * 2));
+  let prompt = format!(
+    "Summarize the conversation below in a few sentences. Keep names, decisions, languages and any terminology agreed on.\n\n{}",
+    old.build_prompt().trim_end_matches("Assistant:")
+  );
+  let summary = engine::generate(&model.path, &config, &prompt, REPLY_TOKENS)?;
+
+  let usage = sessions.locked().update(session_id, |session| {
+    // the session may have grown meanwhile; only the turns we summarized are replaced
+    let summary_turn = Turn::new("system", &format!("Summary of the earlier conversation: {}", summary));
+    session.turns.splice(..old_count.min(session.turns.len()), [summary_turn]);
+    Ok(usage_for(session, ctx))
+  })?;
+  Ok((old_count, usage))
+}
+
+// make room when the conversation and a reply no longer fit in the context window: older turns
+// are summarized in the session, and whatever still doesn't fit is left out of this prompt.
+// Emits "context-shift" when anything was done
+fn shift_context(window: &Window, session_id: &str, turns: &mut Vec<Turn>, ctx: u32) {
+  let budget = ctx.saturating_sub(REPLY_TOKENS);
+  if turns.iter().map(|t| t.tokens).sum::<u32>() <= budget {
+    return;
+  }
+  let mut summarized_turns = 0;
+  match compact(window, session_id) {
+    Ok((count, _)) => {
+      summarized_turns = count;
+      if let Ok(session) = window.state::<Mutex<SessionStore>>().locked().get(session_id) {
+        *turns = session.turns.clone();
+      }
+    }
+    Err(e) => events::log(format!("could not summarize {} to free context: {}", session_id, e)),
+  }
+  let dropped_turns = slide_window(turns, budget);
+  events::emit(window, "context-shift", ContextShift { session_id: session_id.to_string(), summarized_turns, dropped_turns });
+}
+
+// ------------------ Tauri commands ------------------
+
+#[tauri::command]
+pub fn create_session(model_id: Option<String>, sessions: tauri::State<'_, Mutex<SessionStore>>) -> Result<Session, AppError> {
+  Ok(sessions.locked().create(model_id)?)
+}
+
+#[tauri::command]
+pub fn get_context_usage(
+  session_id: String,
+  sessions: tauri::State<'_, Mutex<SessionStore>>,
+  state: tauri::State<'_, Mutex<ModelManager>>
+) -> Result<ContextUsage, AppError> {
+  let session = sessions.locked().get(&session_id)?.clone();
+  let ctx = state.locked().context_size(session.model_id.as_deref());
+  Ok(usage_for(&session, ctx))
+}
+
+// add a user message, generate the reply with the whole conversation as context
+// and emit "context-usage" once the turn is complete. A conversation that outgrew the context
+// window is shifted first (see shift_context)
+#[tauri::command(async)]
+pub fn send_message(
+  session_id: String,
+  text: String,
+  output_format: Option<OutputFormat>,
+  reading: Option<ReadingOptions>,
+  window: Window,
+) -> Result<String, AppError> {
+  // filter reports reach the frontend as "content-filter" events
+  let (text, _) = filter::check_input(&window, text)?;
+  let sessions = window.state::<Mutex<SessionStore>>();
+  let (model_id, mut turns, domain, system_prompt) = sessions.locked().update(&session_id, |session| {
+    if session.closed {
+      return Err(format!("Session '{}' is closed", session_id));
+    }
+    let domain = session.domain.as_deref().map(domain::pack).transpose()?;
+    session.turns.push(Turn::new("user", &text));
+    Ok((session.model_id.clone(), session.turns.clone(), domain, session.system_prompt.clone()))
+  })?;
+
+  let (model, config) = window.state::<Mutex<ModelManager>>().locked().model_for_request(model_id.as_deref())?;
+  let ctx = engine::context_size(&config);
+  // the system prompt and the domain's instructions go in front of the transcript without becoming a turn
+  let system = [system_prompt.or(config.system_prompt.clone()), domain.as_ref().map(|pack| pack.system_turn(&text))];
+  let system = system_prompt::system_turn(&system);
+  shift_context(&window, &session_id, &mut turns, ctx.saturating_sub(system.as_ref().map(|t| t.tokens).unwrap_or(0)));
+  if let Some(turn) = system {
+    turns.insert(0, turn);
+  }
+  let prompt = chat_template::resolve(&config, model.gguf.as_ref()).template.format(&turns);
+  preload::record(&window, "chat", "", &model.id);
+  let reply = match engine::generate(&model.path, &config, &prompt, REPLY_TOKENS).and_then(|r| filter::check_output(&window, r).map(|(r, _)| r)) {
+    Ok(r) => match &domain {
+      Some(pack) => pack.post_process(&r, "auto"),
+      None => r,
+    },
+    Err(e) => {
+      // drop the unanswered user turn so a retry doesn't duplicate it
+      let _ = sessions.locked().update(&session_id, |session| {
+        session.turns.pop();
+        Ok(())
+      });
+      return Err(e.into());
+    }
+  };
+
+  let usage = sessions.locked().update(&session_id, |session| {
+    session.turns.push(Turn::new("assistant", &reply));
+    Ok(usage_for(session, ctx))
+  })?;
+  events::emit(&window, "context-usage", usage);
+  // the session keeps the raw reply as context
+  Ok(output::apply(readable::apply(reply, reading.as_ref(), "auto"), output_format, "auto", &model.path, &config))
+}
+
+// replace all but the most recent turns with a model-written summary to free context space
+#[tauri::command(async)]
+pub fn summarize_and_compact(session_id: String, window: Window) -> Result<ContextUsage, AppError> {
+  let (_, usage) = compact(&window, &session_id)?;
+  events::emit(&window, "context-usage", usage.clone());
+  Ok(usage)
+}
+
+// close a session; with `summarize` a summary and vocabulary list are written at batch priority
+// and stored on the session ("session-summary" is emitted when done)
+#[tauri::command]
+pub fn close_session(session_id: String, summarize: Option<bool>, window: Window) -> Result<Session, AppError> {
+  let sessions = window.state::<Mutex<SessionStore>>();
+  let session = sessions.locked().update(&session_id, |session| {
+    session.closed = true;
+    session.summarizing = summarize.unwrap_or(false) && session.summary.is_none() && session.turns.iter().any(|t| t.role == "user");
+    Ok(session.clone())
+  })?;
+  if session.summarizing {
+    let snapshot = session.clone();
+    let window = window.clone();
+    thread::spawn(move || {
+      let result = dispatch::with_priority(Priority::Batch, || write_summary(&window, &snapshot));
+      let sessions = window.state::<Mutex<SessionStore>>();
+      let updated = sessions.locked().update(&snapshot.id, |session| {
+        session.summarizing = false;
+        session.summary = result.ok();
+        Ok(session.clone())
+      });
+      // deleted while the summary was being written
+      let Ok(updated) = updated else { return };
+      let _ = window.emit("session-summary", updated);
+    });
+  }
+  Ok(session)
+}
+
+// all saved sessions, newest first, with their summaries
+#[tauri::command]
+pub fn list_sessions(sessions: tauri::State<'_, Mutex<SessionStore>>) -> Vec<Session> {
+  sessions.locked().list()
+}
+
+// add a turn without generating a reply (a message written elsewhere, an instruction as a "system"
+// turn); it is part of the context of the next send_message
+#[tauri::command]
+pub fn append_message(session_id: String, role: String, text: String, sessions: tauri::State<'_, Mutex<SessionStore>>) -> Result<Session, AppError> {
+  if !matches!(role.as_str(), "user" | "assistant" | "system") {
+    return Err(format!("Unknown role '{}' (expected user, assistant or system)", role).into());
+  }
+  if text.trim().is_empty() {
+    return Err(AppError::InvalidInput("The message is empty".into()));
+  }
+  Ok(sessions.locked().update(&session_id, |session| {
+    if session.closed {
+      return Err(format!("Session '{}' is closed", session_id));
+    }
+    session.turns.push(Turn::new(&role, &text));
+    Ok(session.clone())
+  })?)
+}
+
+#[tauri::command]
+pub fn delete_session(session_id: String, sessions: tauri::State<'_, Mutex<SessionStore>>) -> Result<(), AppError> {
+  Ok(sessions.locked().delete(&session_id)?)
+}
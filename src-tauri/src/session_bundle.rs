@@ -0,0 +1,148 @@
+// src-tauri/src/session_bundle.rs
+use std::fs;
+use std::num::NonZeroU32;
+use std::sync::Mutex;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use tauri::{AppHandle, Manager};
+
+use crate::domain::{self, DomainPack};
+use crate::error::{AppError, LockExt};
+use crate::glossary::{Glossary, GlossaryEntry};
+use crate::session::{Session, SessionStore};
+
+// marks the file format, and is bound into the encryption so other files can't pass for a bundle
+const FORMAT: &str = "multilingual-session-v1";
+// PBKDF2 rounds for turning the passphrase into a key
+const ROUNDS: u32 = 200_000;
+const MIN_PASSPHRASE_CHARS: usize = 8;
+
+// What a shared session file decrypts to
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+struct SessionBundle {
+  session: Session,
+  // glossary entries whose terms occur in the conversation
+  glossary: Vec<GlossaryEntry>,
+  // the session's domain pack, so the receiver gets the same prompt, terms and rules
+  domain: Option<DomainPack>,
+}
+
+// The file on disk: the bundle sealed with a key derived from the passphrase
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SealedSession {
+  format: String,
+  // base64
+  salt: String,
+  nonce: String,
+  data: String,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ImportedSession {
+  pub session: Session,
+  // glossary entries added (entries the receiver already had are skipped)
+  pub glossary_added: usize,
+  pub domain_installed: bool,
+}
+
+fn random_bytes(n: usize) -> Vec<u8> {
+  let mut buf = vec![0u8; n];
+  SystemRandom::new().fill(&mut buf).expect("system random generator unavailable");
+  buf
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<LessSafeKey, String> {
+  let mut key = [0u8; 32];
+  let rounds = NonZeroU32::new(ROUNDS).expect("ROUNDS is not zero");
+  pbkdf2::derive(pbkdf2::PBKDF2_HMAC_SHA256, rounds, salt, passphrase.as_bytes(), &mut key);
+  let unbound = UnboundKey::new(&CHACHA20_POLY1305, &key).map_err(|_| "key derivation failed".to_string())?;
+  Ok(LessSafeKey::new(unbound))
+}
+
+fn seal(bundle: &SessionBundle, passphrase: &str) -> Result<SealedSession, String> {
+  let salt = random_bytes(16);
+  let key = derive_key(passphrase, &salt)?;
+  let nonce_bytes = random_bytes(NONCE_LEN);
+  let nonce = Nonce::try_assume_unique_for_key(&nonce_bytes).map_err(|_| "bad nonce".to_string())?;
+  let mut data = serde_json::to_vec(bundle).map_err(|e| format!("failed to serialize session: {}", e))?;
+  key.seal_in_place_append_tag(nonce, Aad::from(FORMAT.as_bytes()), &mut data).map_err(|_| "encryption failed".to_string())?;
+  Ok(SealedSession { format: FORMAT.to_string(), salt: BASE64.encode(salt), nonce: BASE64.encode(nonce_bytes), data: BASE64.encode(data) })
+}
+
+fn open(sealed: &SealedSession, passphrase: &str) -> Result<SessionBundle, String> {
+  if sealed.format != FORMAT {
+    return Err(format!("unsupported session file format '{}'", sealed.format));
+  }
+  let salt = BASE64.decode(&sealed.salt).map_err(|_| "invalid salt".to_string())?;
+  let key = derive_key(passphrase, &salt)?;
+  let nonce_bytes = BASE64.decode(&sealed.nonce).map_err(|_| "invalid nonce".to_string())?;
+  let nonce = Nonce::try_assume_unique_for_key(&nonce_bytes).map_err(|_| "invalid nonce".to_string())?;
+  let mut data = BASE64.decode(&sealed.data).map_err(|_| "invalid session file encoding".to_string())?;
+  let plain = key
+    .open_in_place(nonce, Aad::from(FORMAT.as_bytes()), &mut data)
+    .map_err(|_| "session file could not be decrypted (wrong passphrase or damaged file)".to_string())?;
+  serde_json::from_slice(plain).map_err(|e| format!("invalid session file: {}", e))
+}
+
+// entries whose source or target term appears in any turn
+fn referenced_entries(session: &Session, glossary: &Glossary) -> Vec<GlossaryEntry> {
+  let text = session.turns.iter().map(|t| t.text.to_lowercase()).collect::<Vec<_>>().join("\n");
+  glossary
+    .entries(None, None)
+    .into_iter()
+    .filter(|e| text.contains(&e.source.to_lowercase()) || text.contains(&e.target.to_lowercase()))
+    .collect()
+}
+
+// ------------------ Tauri commands ------------------
+
+// write session `id` with the glossary entries and domain pack it uses to one file, encrypted with
+// `passphrase`, for handing to a colleague
+#[tauri::command]
+pub fn export_session_bundle(id: String, path: String, passphrase: String, app: AppHandle) -> Result<(), AppError> {
+  if passphrase.chars().count() < MIN_PASSPHRASE_CHARS {
+    return Err(AppError::InvalidInput(format!("The passphrase needs at least {} characters", MIN_PASSPHRASE_CHARS)));
+  }
+  let mut session = app.state::<Mutex<SessionStore>>().locked().get(&id)?.clone();
+  session.summarizing = false;
+  let glossary = referenced_entries(&session, &app.state::<Mutex<Glossary>>().locked());
+  let domain = session.domain.as_deref().map(domain::pack).transpose()?;
+  let sealed = seal(&SessionBundle { session, glossary, domain }, &passphrase)?;
+  let json = serde_json::to_string(&sealed).map_err(|e| format!("failed to serialize session file: {}", e))?;
+  fs::write(&path, json).map_err(|e| AppError::io("write", &path, e))
+}
+
+// open a file written by export_session_bundle; the session gets a new id if one with its id is open
+#[tauri::command]
+pub fn import_session_bundle(path: String, passphrase: String, app: AppHandle) -> Result<ImportedSession, AppError> {
+  let json = fs::read(&path).map_err(|e| AppError::io("read", &path, e))?;
+  let sealed: SealedSession = serde_json::from_slice(&json).map_err(|_| format!("{} is not a session file", path))?;
+  let bundle = open(&sealed, &passphrase)?;
+
+  let domain_installed = match &bundle.domain {
+    Some(pack) => domain::install(pack)?,
+    None => false,
+  };
+  let glossary_added = {
+    let state = app.state::<Mutex<Glossary>>();
+    let mut glossary = state.locked();
+    let mut added = 0;
+    for entry in bundle.glossary {
+      let known = glossary.entries(Some(&entry.source_lang), Some(&entry.target_lang)).iter().any(|e| e.source == entry.source && e.target == entry.target);
+      if !known {
+        glossary.add(entry)?;
+        added += 1;
+      }
+    }
+    added
+  };
+  let state = app.state::<Mutex<SessionStore>>();
+  let mut sessions = state.locked();
+  let id = sessions.insert(bundle.session)?;
+  let session = sessions.get(&id)?.clone();
+  Ok(ImportedSession { session, glossary_added, domain_installed })
+}
@@ -0,0 +1,217 @@
+// src-tauri/src/settings.rs
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use tauri::{AppHandle, Manager};
+
+use crate::collate;
+use crate::error::{AppError, LockExt};
+use crate::model_watch;
+use crate::storage;
+use crate::store;
+use crate::ModelManager;
+
+// used until a models directory is chosen, in the app folder (see store::app_path)
+const DEFAULT_MODELS_DIR: &str = "models";
+
+// <app config dir>/settings.json; unset fields keep the built-in defaults
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Settings {
+  // main models folder: downloads and model configs go here
+  pub models_dir: Option<String>,
+  // further folders scanned for models (external drives, network shares), after models_dir
+  pub model_dirs: Vec<String>,
+  // model used when a request names none and nothing is loaded
+  pub default_model: Option<String>,
+  // layers offloaded to the GPU (-ngl) for models without their own gpu_layers; unset fits as many
+  // as the VRAM holds
+  pub gpu_layers: Option<u32>,
+  // CPU threads for generation (-t); unset leaves it to the runtime
+  pub threads: Option<u32>,
+  // context window for models without their own ctx_size
+  pub ctx_size: Option<u32>,
+  // language of the interface (BCP 47, e.g. "de" or "ja-JP"); lists are sorted the way its readers expect
+  pub ui_lang: Option<String>,
+  // where runtimes write temporary files (TMPDIR/TEMP); unset uses the system temp dir
+  pub scratch_dir: Option<String>,
+  // where backups of the app's data go (backups/ in the app folder when unset); best on another drive
+  pub backup_dir: Option<String>,
+  // hours between scheduled backups: unset backs up daily, 0 turns scheduled backups off
+  pub backup_interval_hours: Option<u32>,
+  // backups kept before the oldest are deleted (7 when unset)
+  pub backup_keep: Option<u32>,
+  // keep runtime log lines with their color codes (TokenEvent.raw) for the raw log view
+  pub raw_logs: bool,
+  // developer console: send_raw and "runtime-raw" events with the runtime's unprocessed I/O
+  pub dev_console: bool,
+  // serve the OpenAI-compatible API (openai.rs) at startup
+  pub openai_api: bool,
+  // address and port it listens on (127.0.0.1:8080 when unset)
+  pub openai_api_bind: Option<String>,
+  pub openai_api_port: Option<u16>,
+  // bearer token clients must send; unset accepts any local client
+  pub openai_api_key: Option<String>,
+}
+
+// settings file and its current contents, set up once the app config dir is known
+static SETTINGS: OnceLock<(PathBuf, Mutex<Settings>)> = OnceLock::new();
+
+// read the settings from the app config dir; called first thing in setup
+pub fn init(app: &AppHandle) {
+  let dir = app.path().app_config_dir().unwrap_or_else(|_| store::config_dir());
+  let path = dir.join("settings.json");
+  let settings = store::load_json(&path);
+  let _ = SETTINGS.set((path, Mutex::new(settings)));
+}
+
+pub fn get() -> Settings {
+  SETTINGS.get().map(|(_, s)| s.locked().clone()).unwrap_or_default()
+}
+
+pub fn models_dir() -> PathBuf {
+  get().models_dir.map(PathBuf::from).unwrap_or_else(|| store::app_path(DEFAULT_MODELS_DIR))
+}
+
+// every folder to scan for models, the main one first
+pub fn model_dirs() -> Vec<PathBuf> {
+  let mut dirs = vec![models_dir()];
+  for dir in get().model_dirs {
+    let dir = PathBuf::from(dir);
+    if !dirs.contains(&dir) {
+      dirs.push(dir);
+    }
+  }
+  dirs
+}
+
+// the settings file, once loaded
+pub fn path() -> Option<PathBuf> {
+  SETTINGS.get().map(|(path, _)| path.clone())
+}
+
+// change the settings and write them to disk
+pub fn update<F: FnOnce(&mut Settings)>(f: F) -> Result<Settings, String> {
+  let (path, current) = SETTINGS.get().ok_or("Settings are not loaded yet")?;
+  let mut settings = current.locked();
+  let mut next = settings.clone();
+  f(&mut next);
+  store::save_json(path, &next)?;
+  *settings = next.clone();
+  Ok(next)
+}
+
+// A folder models are loaded from
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ModelDir {
+  pub path: String,
+  // the main folder (models_dir), which can't be removed
+  pub primary: bool,
+  // false while a drive or share is disconnected
+  pub available: bool,
+  pub models: usize,
+}
+
+fn list_dirs(mgr: &ModelManager) -> Vec<ModelDir> {
+  model_dirs()
+    .into_iter()
+    .enumerate()
+    .map(|(i, dir)| ModelDir {
+      path: dir.to_string_lossy().to_string(),
+      primary: i == 0,
+      available: dir.is_dir(),
+      models: mgr.models.values().filter(|m| Path::new(&m.dir) == dir).count(),
+    })
+    .collect()
+}
+
+// ------------------ Tauri commands ------------------
+
+#[tauri::command]
+pub fn get_settings() -> Settings {
+  get()
+}
+
+// save new settings; changed model folders are rescanned right away
+#[tauri::command(async)]
+pub fn set_settings(settings: Settings, app: AppHandle, state: tauri::State<'_, Mutex<ModelManager>>) -> Result<Settings, AppError> {
+  if let Some(dir) = &settings.models_dir {
+    if !PathBuf::from(dir).is_dir() {
+      return Err(AppError::InvalidInput(format!("{} is not a folder", dir)));
+    }
+  }
+  if let Some(lang) = &settings.ui_lang {
+    collate::check_locale(lang)?;
+  }
+  if let Some(dir) = &settings.scratch_dir {
+    storage::check_writable_dir(dir, "scratch dir")?;
+  }
+  if let Some(dir) = &settings.backup_dir {
+    storage::check_writable_dir(dir, "backup folder")?;
+  }
+  if settings.backup_keep == Some(0) {
+    return Err(AppError::InvalidInput("At least one backup must be kept".into()));
+  }
+  if settings.ctx_size == Some(0) || settings.threads == Some(0) {
+    return Err(AppError::InvalidInput("Context size and threads must be greater than 0".into()));
+  }
+
+  let mut mgr = state.locked();
+  let before = get();
+  let dirs_changed = settings.models_dir != before.models_dir || settings.model_dirs != before.model_dirs;
+  // a model in a newly added folder can only be checked after the rescan
+  if let (false, Some(id)) = (dirs_changed, &settings.default_model) {
+    if !mgr.models.contains_key(id) {
+      return Err(AppError::not_found("Model", id));
+    }
+  }
+  let settings = update(|s| *s = settings)?;
+  if settings.models_dir != before.models_dir {
+    mgr.reload();
+  } else if dirs_changed {
+    mgr.scan_models();
+  }
+  drop(mgr);
+  if dirs_changed {
+    model_watch::restart(&app);
+  }
+  Ok(settings)
+}
+
+#[tauri::command]
+pub fn list_model_dirs(state: tauri::State<'_, Mutex<ModelManager>>) -> Vec<ModelDir> {
+  list_dirs(&state.locked())
+}
+
+// scan another folder for models; its models get "@<folder>" ids where a name is already taken
+#[tauri::command(async)]
+pub fn add_model_dir(path: String, app: AppHandle, state: tauri::State<'_, Mutex<ModelManager>>) -> Result<Vec<ModelDir>, AppError> {
+  let dir = PathBuf::from(path.trim());
+  if !dir.is_dir() {
+    return Err(AppError::InvalidInput(format!("{} is not a folder", path)));
+  }
+  if model_dirs().contains(&dir) {
+    return Err(format!("{} is already a models folder", path).into());
+  }
+  update(|s| s.model_dirs.push(dir.to_string_lossy().to_string()))?;
+  model_watch::restart(&app);
+  let mut mgr = state.locked();
+  mgr.scan_models();
+  Ok(list_dirs(&mgr))
+}
+
+#[tauri::command(async)]
+pub fn remove_model_dir(path: String, app: AppHandle, state: tauri::State<'_, Mutex<ModelManager>>) -> Result<Vec<ModelDir>, AppError> {
+  let dir = PathBuf::from(path.trim());
+  if dir == models_dir() {
+    return Err("The main models folder can't be removed; choose another one in the settings instead".into());
+  }
+  if !get().model_dirs.iter().any(|d| Path::new(d) == dir) {
+    return Err(format!("{} is not a models folder", path).into());
+  }
+  update(|s| s.model_dirs.retain(|d| Path::new(d) != dir))?;
+  model_watch::restart(&app);
+  let mut mgr = state.locked();
+  mgr.scan_models();
+  Ok(list_dirs(&mgr))
+}
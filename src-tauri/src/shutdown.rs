@@ -0,0 +1,115 @@
+// src-tauri/src/shutdown.rs
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use tokio::process::Child;
+use tokio::task;
+use tokio::time;
+
+// how long a runtime gets to flush its KV cache / temp files before it is killed
+pub const DEFAULT_STOP_TIMEOUT_MS: u64 = 5000;
+
+// How a model process ended; sent as the "model-status" payload after a stop
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct StopReport {
+  pub model_id: String,
+  pub running: bool,
+  // "graceful" when the process exited on the stop request, "killed" after the timeout,
+  // "exited" when it was already gone
+  pub method: String,
+  // "SIGTERM" or "CTRL_BREAK"
+  pub signal: Option<String>,
+  pub waited_ms: u64,
+  pub exit_code: Option<i32>,
+}
+
+// start runtimes in their own process group on Windows so CTRL_BREAK reaches only them; the
+// std Command is turned into a tokio one when it is spawned
+pub fn prepare(command: &mut Command) {
+  #[cfg(windows)]
+  {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+    command.creation_flags(CREATE_NEW_PROCESS_GROUP);
+  }
+  #[cfg(not(windows))]
+  let _ = command;
+}
+
+// send `signal` to the child; false when it is gone or can't be signalled
+#[cfg(unix)]
+fn signal(child: &Child, signal: libc::c_int) -> bool {
+  let Some(pid) = child.id().and_then(|pid| libc::pid_t::try_from(pid).ok()) else { return false };
+  // SAFETY: kill() takes plain integers and touches no memory of ours
+  unsafe { libc::kill(pid, signal) == 0 }
+}
+
+#[cfg(unix)]
+fn request_stop(child: &Child) -> Option<&'static str> {
+  signal(child, libc::SIGTERM).then_some("SIGTERM")
+}
+
+#[cfg(windows)]
+fn request_stop(child: &Child) -> Option<&'static str> {
+  let pid = child.id()?;
+  #[link(name = "kernel32")]
+  extern "system" {
+    fn GenerateConsoleCtrlEvent(ctrl_event: u32, process_group_id: u32) -> i32;
+  }
+  const CTRL_BREAK_EVENT: u32 = 1;
+  // the child leads its own process group (see prepare), so its pid is the group id
+  let sent = unsafe { GenerateConsoleCtrlEvent(CTRL_BREAK_EVENT, pid) } != 0;
+  sent.then_some("CTRL_BREAK")
+}
+
+#[cfg(not(any(unix, windows)))]
+fn request_stop(_child: &Child) -> Option<&'static str> {
+  None
+}
+
+// stop the answer being generated without ending the process. Only for llama.cpp's interactive
+// mode, which returns to the prompt on SIGINT; other runtimes exit on it
+#[cfg(unix)]
+pub fn interrupt(child: &Child) -> Option<&'static str> {
+  signal(child, libc::SIGINT).then_some("SIGINT")
+}
+
+// CTRL_C can't be sent to a single process group on Windows
+#[cfg(not(unix))]
+pub fn interrupt(_child: &Child) -> Option<&'static str> {
+  None
+}
+
+// ask the process to exit, wait up to `timeout`, then kill it
+pub async fn terminate(model_id: &str, child: &mut Child, timeout: Duration) -> Result<StopReport, String> {
+  let report = |method: &str, signal: Option<&str>, waited: Duration, code: Option<i32>| StopReport {
+    model_id: model_id.to_string(),
+    running: false,
+    method: method.into(),
+    signal: signal.map(|s| s.to_string()),
+    waited_ms: waited.as_millis() as u64,
+    exit_code: code,
+  };
+  if let Ok(Some(status)) = child.try_wait() {
+    return Ok(report("exited", None, Duration::ZERO, status.code()));
+  }
+
+  let started = Instant::now();
+  let signal = request_stop(child);
+  if signal.is_some() {
+    match time::timeout(timeout, child.wait()).await {
+      Ok(Ok(status)) => return Ok(report("graceful", signal, started.elapsed(), status.code())),
+      Ok(Err(e)) => return Err(format!("Failed to wait for process: {}", e)),
+      Err(_) => {}
+    }
+  }
+  // kill() waits for the process to be gone
+  child.kill().await.map_err(|e| format!("Failed to kill process: {}", e))?;
+  let status = child.try_wait().ok().flatten();
+  Ok(report("killed", signal, started.elapsed(), status.and_then(|s| s.code())))
+}
+
+// terminate() for callers that block anyway (the self-test), on a runtime worker or a plain thread
+pub fn terminate_blocking(model_id: &str, child: &mut Child, timeout: Duration) -> Result<StopReport, String> {
+  task::block_in_place(|| tauri::async_runtime::block_on(terminate(model_id, child, timeout)))
+}
@@ -0,0 +1,109 @@
+// src-tauri/src/simplify.rs
+use std::sync::Mutex;
+
+use tauri::{Manager, Window};
+
+use crate::chunk;
+use crate::engine;
+use crate::error::{AppError, LockExt};
+use crate::filter::{self, FilterReport};
+use crate::lang;
+use crate::output::{self, OutputFormat};
+use crate::preload;
+use crate::readable::{self, ReadingOptions};
+use crate::ModelManager;
+
+// CEFR target: (instructions, max average words per sentence)
+fn level_spec(level: &str) -> Result<(&'static str, f32), String> {
+  match level.to_uppercase().as_str() {
+    "A2" => Ok((
+      "Use only very common everyday words. Write short sentences with one idea each. \
+Use present and simple past tense only. Avoid idioms, passive voice and subordinate clauses.",
+      10.0,
+    )),
+    "B1" => Ok((
+      "Use common words and explain any rare or technical word in simple terms. \
+Keep sentences short and direct. Avoid idioms and complex grammar.",
+      15.0,
+    )),
+    "B2" => Ok((
+      "Use clear, standard language. Replace rare words and jargon with common alternatives. \
+Split long sentences and keep the structure easy to follow.",
+      20.0,
+    )),
+    other => Err(format!("Unsupported level '{}', expected A2, B1 or B2", other)),
+  }
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct SimplifyResult {
+  pub text: String,
+  pub level: String,
+  pub avg_sentence_words: f32,
+  // true when the first attempt was too complex and a stricter retry was used
+  pub retried: bool,
+  // what the content filter changed, if enabled
+  pub filter: Vec<FilterReport>,
+}
+
+fn avg_sentence_words(text: &str) -> f32 {
+  let sentences = chunk::sentences(text);
+  let counted: Vec<usize> = sentences.iter().map(|s| s.split_whitespace().count()).filter(|n| *n > 0).collect();
+  if counted.is_empty() {
+    return 0.0;
+  }
+  counted.iter().sum::<usize>() as f32 / counted.len() as f32
+}
+
+fn build_prompt(text: &str, lang_name: &str, level: &str, rules: &str, strict: bool) -> String {
+  let extra = if strict { "\nYour previous version was still too complex: use even shorter sentences and simpler words." } else { "" };
+  format!(
+    "Rewrite the text below in {lang} for a reader at CEFR level {level}. {rules}{extra}\n\
+Keep all the important information. Do not translate names. Reply with the rewritten text only.\n\nText:\n{text}",
+    lang = lang_name,
+    level = level,
+    rules = rules,
+    extra = extra,
+    text = text
+  )
+}
+
+// ------------------ Tauri commands ------------------
+
+#[tauri::command(async)]
+pub fn simplify(
+  text: String,
+  lang: String,
+  level: String,
+  model_id: Option<String>,
+  output_format: Option<OutputFormat>,
+  reading: Option<ReadingOptions>,
+  window: Window,
+) -> Result<SimplifyResult, AppError> {
+  let (rules, max_avg) = level_spec(&level)?;
+  let (text, input_report) = filter::check_input(&window, text)?;
+  let level = level.to_uppercase();
+  let (model, config) = window.state::<Mutex<ModelManager>>().locked().model_for_request(model_id.as_deref())?;
+  preload::record(&window, "simplify", &lang, &model.id);
+  let lang_name = lang::language_name(&lang);
+  let max_tokens = engine::estimate_tokens(&text) * 2 + 128;
+
+  let mut out = engine::generate(&model.path, &config, &build_prompt(&text, &lang_name, &level, rules, false), max_tokens)?;
+  let mut avg = avg_sentence_words(&out);
+  let mut retried = false;
+  // word counts are meaningless for scripts without spaces (zh, ja, th), so only check when words exist
+  if avg > max_avg && out.split_whitespace().count() > 1 {
+    let second = engine::generate(&model.path, &config, &build_prompt(&text, &lang_name, &level, rules, true), max_tokens)?;
+    let second_avg = avg_sentence_words(&second);
+    if second_avg < avg {
+      out = second;
+      avg = second_avg;
+    }
+    retried = true;
+  }
+
+  let (out, output_report) = filter::check_output(&window, out)?;
+  let text = output::apply(readable::apply(out, reading.as_ref(), &lang), output_format, &lang, &model.path, &config);
+  let filter = input_report.into_iter().chain(output_report).collect();
+  Ok(SimplifyResult { text, level, avg_sentence_words: avg, retried, filter })
+}
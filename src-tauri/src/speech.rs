@@ -0,0 +1,498 @@
+// src-tauri/src/speech.rs
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tauri::{Emitter, Window};
+
+use crate::error::{AppError, LockExt};
+use crate::gguf::GgufMetadata;
+use crate::lang;
+use crate::runtime;
+use crate::server;
+use crate::settings;
+use crate::storage;
+use crate::{ModelInfo, ModelManager};
+
+// name of whisper.cpp's HTTP server among the bundled tools
+const WHISPER_SERVER: &str = "whisper-server";
+// it loads the model before it starts listening
+const SERVER_LOAD_TIMEOUT: Duration = Duration::from_secs(60);
+const SERVER_POLL: Duration = Duration::from_millis(200);
+const MULTIPART_BOUNDARY: &str = "multilingual-whisper-boundary";
+
+// whisper.cpp models (ggml-*.bin) in <models dir>/speech
+fn speech_dir() -> PathBuf {
+  settings::models_dir().join("speech")
+}
+
+// piper voices (<lang>_<REGION>-<name>.onnx) in <models dir>/speech/voices
+fn voices_dir() -> PathBuf {
+  speech_dir().join("voices")
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct Transcript {
+  pub text: String,
+  // language reported by the recognizer, if any
+  pub lang: Option<String>,
+}
+
+// whisper.cpp vocabulary sizes: English-only, multilingual, and large-v3
+const WHISPER_VOCAB: std::ops::RangeInclusive<i32> = 51864..=51866;
+
+// ggml .bin files from whisper.cpp: the "ggml" magic followed by a whisper-sized vocabulary
+// (old ggml LLMs share the magic but not the vocabulary)
+fn is_ggml_whisper(path: &Path) -> bool {
+  let mut header = [0u8; 8];
+  let read = fs::File::open(path).and_then(|mut f| f.read_exact(&mut header));
+  read.is_ok() && header[..4] == 0x67676d6cu32.to_le_bytes() && WHISPER_VOCAB.contains(&i32::from_le_bytes([header[4], header[5], header[6], header[7]]))
+}
+
+// whisper models in the models folders, next to the LLMs: ggml .bin files, which whisper-cli runs,
+// and conversions to GGUF, which it can't but which mustn't be taken for LLMs either
+pub fn is_whisper(path: &Path, gguf: Option<&GgufMetadata>) -> bool {
+  let name = path.file_name().map(|n| n.to_string_lossy().to_lowercase()).unwrap_or_default();
+  if name.ends_with(".bin") {
+    return is_ggml_whisper(path);
+  }
+  gguf.and_then(|g| g.architecture.as_deref()) == Some("whisper") || (name.ends_with(".gguf") && name.contains("whisper"))
+}
+
+// whisper-cli loads ggml .bin models only
+fn runs_in_whisper_cli(path: &str) -> bool {
+  path.to_lowercase().ends_with(".bin")
+}
+
+// first whisper model, or the one whose name matches `name` ("whisper-small" or "small" -> ggml-small.bin)
+fn asr_model(name: Option<&str>) -> Option<PathBuf> {
+  let wanted = name.map(|n| n.to_lowercase().trim_start_matches("whisper-").to_string());
+  let mut models: Vec<PathBuf> = fs::read_dir(speech_dir())
+    .ok()?
+    .flatten()
+    .map(|e| e.path())
+    .filter(|p| {
+      let name = p.file_name().map(|n| n.to_string_lossy().to_lowercase()).unwrap_or_default();
+      name.starts_with("ggml-") && name.ends_with(".bin") && wanted.as_ref().is_none_or(|w| name.contains(w.as_str()))
+    })
+    .collect();
+  models.sort();
+  models.into_iter().next()
+}
+
+// piper voice whose file name starts with the language code ("de" matches "de_DE-thorsten-medium.onnx")
+pub fn voice_for(lang: &str) -> Option<PathBuf> {
+  let base = lang.split(['-', '_']).next().unwrap_or(lang).to_lowercase();
+  let mut voices: Vec<PathBuf> = fs::read_dir(voices_dir())
+    .ok()?
+    .flatten()
+    .map(|e| e.path())
+    .filter(|p| p.extension().map(|x| x == "onnx").unwrap_or(false))
+    .filter(|p| {
+      let name = p.file_name().map(|n| n.to_string_lossy().to_lowercase()).unwrap_or_default();
+      name.split(['_', '-']).next() == Some(base.as_str())
+    })
+    .collect();
+  voices.sort();
+  voices.into_iter().next()
+}
+
+// language codes with at least one installed voice ("de_DE-thorsten-medium.onnx" -> "de")
+pub fn voice_languages() -> Vec<String> {
+  let mut langs: Vec<String> = fs::read_dir(voices_dir())
+    .map(|rd| {
+      rd.flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().map(|x| x == "onnx").unwrap_or(false))
+        .filter_map(|p| p.file_name().map(|n| n.to_string_lossy().to_lowercase()))
+        .filter_map(|n| n.split(['_', '-']).next().map(|l| l.to_string()))
+        .collect()
+    })
+    .unwrap_or_default();
+  langs.sort();
+  langs.dedup();
+  langs
+}
+
+// every installed piper voice, sorted by file name
+pub fn voice_files() -> Vec<PathBuf> {
+  let mut voices: Vec<PathBuf> = fs::read_dir(voices_dir())
+    .map(|rd| rd.flatten().map(|e| e.path()).filter(|p| p.extension().map(|x| x == "onnx").unwrap_or(false)).collect())
+    .unwrap_or_default();
+  voices.sort();
+  voices
+}
+
+// piper voice by file stem ("de_DE-thorsten-medium", or "piper-" prefixed as in pipeline configs)
+pub fn voice_named(name: &str) -> Option<PathBuf> {
+  let path = voices_dir().join(format!("{}.onnx", name.trim_start_matches("piper-")));
+  path.is_file().then_some(path)
+}
+
+// speech to text with whisper.cpp; `lang` None lets whisper detect the language
+pub fn transcribe(audio_path: &str, lang: Option<&str>) -> Result<Transcript, String> {
+  transcribe_with(None, audio_path, lang)
+}
+
+// transcribe with a specific whisper model (None picks the first one installed)
+pub fn transcribe_with(model: Option<&str>, audio_path: &str, lang: Option<&str>) -> Result<Transcript, String> {
+  let exe = runtime::bundled_tool("whisper-cli").ok_or("whisper-cli not found in ./src-tauri/bin")?;
+  let model = asr_model(model).ok_or(format!("No whisper model {}(ggml-*.bin) in {}", model.map(|m| format!("'{}' ", m)).unwrap_or_default(), speech_dir().to_string_lossy()))?;
+  transcribe_file(&exe, &model, audio_path, lang)
+}
+
+pub(crate) fn transcribe_file(exe: &Path, model: &Path, audio_path: &str, lang: Option<&str>) -> Result<Transcript, String> {
+  if !Path::new(audio_path).is_file() {
+    return Err(format!("Audio file not found: {}", audio_path));
+  }
+  let out = storage::command(exe)
+    .arg("-m")
+    .arg(model)
+    .args(["-f", audio_path, "-l", lang.unwrap_or("auto"), "-nt"])
+    .stdin(Stdio::null())
+    .output()
+    .map_err(|e| format!("failed to run whisper-cli: {}", e))?;
+  if !out.status.success() {
+    return Err(format!("whisper-cli failed: {}", String::from_utf8_lossy(&out.stderr).trim()));
+  }
+  let text = String::from_utf8_lossy(&out.stdout).lines().map(|l| l.trim()).filter(|l| !l.is_empty()).collect::<Vec<_>>().join(" ");
+  // "whisper_full_with_state: auto-detected language: en (p = 0.97)"
+  let detected = String::from_utf8_lossy(&out.stderr).lines().find_map(detected_language);
+  Ok(Transcript { text, lang: detected.or(lang.map(|l| l.to_string())) })
+}
+
+// A whisper-server holding one model in memory, for live transcription: whisper-cli would load
+// the model again for every pass
+pub(crate) struct WhisperServer {
+  child: Child,
+  base_url: String,
+}
+
+impl WhisperServer {
+  // start a whisper-server for `model` and wait until it listens; an error when none is bundled
+  pub fn start(model: &Path) -> Result<Self, String> {
+    let exe = runtime::bundled_tool(WHISPER_SERVER).ok_or("whisper-server not found in ./src-tauri/bin")?;
+    let port = server::free_port()?;
+    let child = storage::command(&exe)
+      .arg("-m")
+      .arg(model)
+      .args(["--host", "127.0.0.1", "--port", &port.to_string()])
+      .stdin(Stdio::null())
+      .stdout(Stdio::null())
+      .stderr(Stdio::null())
+      .spawn()
+      .map_err(|e| format!("failed to start whisper-server: {}", e))?;
+    let mut server = Self { child, base_url: format!("http://127.0.0.1:{}", port) };
+    let started = Instant::now();
+    while ureq::get(&server.base_url).call().is_err() {
+      if server.child.try_wait().ok().flatten().is_some() {
+        return Err("whisper-server exited while loading the model".into());
+      }
+      if started.elapsed() >= SERVER_LOAD_TIMEOUT {
+        return Err(format!("whisper-server did not start within {}s", SERVER_LOAD_TIMEOUT.as_secs()));
+      }
+      thread::sleep(SERVER_POLL);
+    }
+    Ok(server)
+  }
+
+  // transcribe a wav file; the language is reported by its English name and turned into a code
+  pub fn transcribe(&self, wav: &Path, lang: Option<&str>) -> Result<Transcript, String> {
+    let audio = fs::read(wav).map_err(|e| format!("failed to read {}: {}", wav.to_string_lossy(), e))?;
+    let mut body = Vec::new();
+    for (name, value) in [("response_format", "verbose_json"), ("language", lang.unwrap_or("auto"))] {
+      body.extend(format!("--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n", MULTIPART_BOUNDARY, name, value).into_bytes());
+    }
+    body.extend(format!("--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"audio.wav\"\r\nContent-Type: audio/wav\r\n\r\n", MULTIPART_BOUNDARY).into_bytes());
+    body.extend(audio);
+    body.extend(format!("\r\n--{}--\r\n", MULTIPART_BOUNDARY).into_bytes());
+    let reply = ureq::post(format!("{}/inference", self.base_url))
+      .header("Content-Type", format!("multipart/form-data; boundary={}", MULTIPART_BOUNDARY))
+      .send(&body[..])
+      .and_then(|mut resp| resp.body_mut().read_to_string())
+      .map_err(|e| format!("whisper-server request failed: {}", e))?;
+    let reply: serde_json::Value = serde_json::from_str(&reply).map_err(|e| format!("whisper-server sent an invalid reply: {}", e))?;
+    if let Some(error) = reply.get("error") {
+      return Err(format!("whisper-server: {}", error));
+    }
+    let text = reply.get("text").and_then(|t| t.as_str()).unwrap_or_default().split_whitespace().collect::<Vec<_>>().join(" ");
+    let detected = reply.get("language").and_then(|l| l.as_str()).and_then(lang::code_for_name);
+    Ok(Transcript { text, lang: detected.or(lang.map(|l| l.to_string())) })
+  }
+}
+
+impl Drop for WhisperServer {
+  fn drop(&mut self) {
+    let _ = self.child.kill();
+    let _ = self.child.wait();
+  }
+}
+
+// "whisper_full_with_state: auto-detected language: en (p = 0.97)" -> "en"
+fn detected_language(line: &str) -> Option<String> {
+  line.split("auto-detected language:").nth(1).and_then(|r| r.split_whitespace().next()).filter(|l| !l.is_empty()).map(|l| l.to_string())
+}
+
+// A stretch of recognized speech
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct TranscriptSegment {
+  pub start_ms: u64,
+  pub end_ms: u64,
+  pub text: String,
+}
+
+// Payload of "transcription-output" events: one per segment, then a final one with the whole text
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct TranscriptionEvent {
+  pub transcription_id: u64,
+  pub segment: Option<TranscriptSegment>,
+  pub is_final: bool,
+  // set on the final event
+  pub text: Option<String>,
+  pub lang: Option<String>,
+  pub error: Option<String>,
+}
+
+// "00:01:02.345" -> 62345; the fraction is read as decimals ("02.5" is 2500) and may follow a comma
+// as in SRT
+fn parse_timestamp(ts: &str) -> Option<u64> {
+  let (hms, fraction) = ts.trim().split_once(['.', ','])?;
+  if fraction.is_empty() || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+    return None;
+  }
+  let parts: Vec<u64> = hms.split(':').map(|p| p.parse().ok()).collect::<Option<_>>()?;
+  let secs = parts.iter().fold(0, |acc, p| acc * 60 + p);
+  let ms: u64 = format!("{:0<3}", &fraction[..fraction.len().min(3)]).parse().ok()?;
+  Some(secs * 1000 + ms)
+}
+
+// "[00:00:00.000 --> 00:00:02.500]   Hello there." as whisper-cli prints it with timestamps on
+fn parse_segment(line: &str) -> Option<TranscriptSegment> {
+  let rest = line.trim_start().strip_prefix('[')?;
+  let (times, text) = rest.split_once(']')?;
+  let (start, end) = times.split_once("-->")?;
+  let text = text.trim();
+  if text.is_empty() {
+    return None;
+  }
+  Some(TranscriptSegment { start_ms: parse_timestamp(start)?, end_ms: parse_timestamp(end)?, text: text.to_string() })
+}
+
+// run whisper-cli on `audio_path`, sending each segment as it is recognized
+fn stream_transcription(window: &Window, id: u64, exe: &Path, model: &Path, audio_path: &Path, lang: Option<&str>) -> Result<Transcript, String> {
+  let mut child = storage::command(exe)
+    .arg("-m")
+    .arg(model)
+    .arg("-f")
+    .arg(audio_path)
+    .args(["-l", lang.unwrap_or("auto")])
+    .stdin(Stdio::null())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .map_err(|e| format!("failed to run whisper-cli: {}", e))?;
+  let mut stderr = child.stderr.take();
+  let err_reader = thread::spawn(move || {
+    let mut buf = String::new();
+    if let Some(e) = stderr.as_mut() {
+      let _ = e.read_to_string(&mut buf);
+    }
+    buf
+  });
+  let mut segments: Vec<String> = Vec::new();
+  if let Some(out) = child.stdout.take() {
+    for line in BufReader::new(out).lines().map_while(Result::ok) {
+      if let Some(segment) = parse_segment(&line) {
+        segments.push(segment.text.clone());
+        let event = TranscriptionEvent { transcription_id: id, segment: Some(segment), is_final: false, text: None, lang: None, error: None };
+        let _ = window.emit("transcription-output", event);
+      }
+    }
+  }
+  let status = child.wait().map_err(|e| format!("whisper-cli failed: {}", e))?;
+  let err = err_reader.join().unwrap_or_default();
+  if !status.success() {
+    return Err(format!("whisper-cli failed: {}", err.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or("").trim()));
+  }
+  let detected = err.lines().find_map(detected_language);
+  Ok(Transcript { text: segments.join(" "), lang: detected.or(lang.map(|l| l.to_string())) })
+}
+
+// a ggml whisper model from the models list or a ggml-*.bin in the speech folder by name; None
+// picks the first of either. Whisper GGUFs in the list are refused: whisper-cli can't load them
+pub(crate) fn whisper_model(mgr: &ModelManager, model_id: Option<&str>) -> Result<PathBuf, String> {
+  let mut listed: Vec<&ModelInfo> = mgr.models.values().filter(|m| m.speech).collect();
+  listed.sort_by(|a, b| a.id.cmp(&b.id));
+  let from_list = match model_id {
+    Some(id) => match listed.iter().find(|m| m.id == id) {
+      Some(m) if !runs_in_whisper_cli(&m.path) => return Err(format!("'{}' is a GGUF whisper model; whisper-cli needs the ggml .bin file (ggml-*.bin)", id)),
+      found => found.map(|m| PathBuf::from(&m.path)),
+    },
+    None => listed.iter().find(|m| runs_in_whisper_cli(&m.path)).map(|m| PathBuf::from(&m.path)),
+  };
+  from_list.or_else(|| asr_model(model_id)).ok_or(match model_id {
+    Some(id) => format!("No whisper model '{}'", id),
+    None => format!("No whisper model in the models folders or {}", speech_dir().to_string_lossy()),
+  })
+}
+
+fn next_transcription_id() -> u64 {
+  static NEXT: AtomicU64 = AtomicU64::new(0);
+  NEXT.fetch_add(1, Ordering::Relaxed) + 1
+}
+
+// text to speech with piper, writing a wav file to `out_path`
+pub fn synthesize(text: &str, lang: &str, out_path: &Path) -> Result<(), String> {
+  let voice = voice_for(lang).ok_or(format!("No voice for '{}' in {}", lang, voices_dir().to_string_lossy()))?;
+  synthesize_with(&voice, text, out_path)
+}
+
+// text to speech with a specific piper voice file
+pub fn synthesize_with(voice: &Path, text: &str, out_path: &Path) -> Result<(), String> {
+  let exe = runtime::bundled_tool("piper").ok_or("piper not found in ./src-tauri/bin")?;
+  if let Some(parent) = out_path.parent() {
+    fs::create_dir_all(parent).map_err(|e| format!("failed to create {}: {}", parent.to_string_lossy(), e))?;
+  }
+  let mut child = Command::new(exe)
+    .arg("--model")
+    .arg(voice)
+    .arg("--output_file")
+    .arg(out_path)
+    .stdin(Stdio::piped())
+    .stdout(Stdio::null())
+    .stderr(Stdio::piped())
+    .spawn()
+    .map_err(|e| format!("failed to run piper: {}", e))?;
+  if let Some(mut stdin) = child.stdin.take() {
+    stdin.write_all(text.as_bytes()).map_err(|e| format!("failed to write to piper: {}", e))?;
+  }
+  let out = child.wait_with_output().map_err(|e| format!("piper failed: {}", e))?;
+  if !out.status.success() {
+    return Err(format!("piper failed: {}", String::from_utf8_lossy(&out.stderr).trim()));
+  }
+  Ok(())
+}
+
+// What the interpreter can do on this machine
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct SpeechSupport {
+  pub asr: bool,
+  pub tts: bool,
+  // voice file names found in <models dir>/speech/voices
+  pub voices: Vec<String>,
+}
+
+// ------------------ Tauri commands ------------------
+
+#[tauri::command]
+pub fn get_speech_support() -> SpeechSupport {
+  let voices: Vec<String> = voice_files().iter().filter_map(|p| p.file_stem().map(|s| s.to_string_lossy().to_string())).collect();
+  SpeechSupport {
+    asr: runtime::bundled_tool("whisper-cli").is_some() && asr_model(None).is_some(),
+    tts: runtime::bundled_tool("piper").is_some() && !voices.is_empty(),
+    voices,
+  }
+}
+
+// transcribe an audio file, or WAV bytes sent by the frontend, with whisper.cpp. `model_id` picks
+// a whisper GGUF from the models list or a ggml-*.bin in the speech folder by name; unset, the first
+// of either. Returns an id right away; segments with timestamps follow as "transcription-output" events
+#[tauri::command]
+pub fn transcribe_audio(
+  audio_path: Option<String>,
+  audio_bytes: Option<Vec<u8>>,
+  language: Option<String>,
+  model_id: Option<String>,
+  window: Window,
+  state: tauri::State<'_, Mutex<ModelManager>>
+) -> Result<u64, AppError> {
+  let exe = runtime::bundled_tool("whisper-cli").ok_or("whisper-cli not found in ./src-tauri/bin")?;
+  let model = whisper_model(&state.locked(), model_id.as_deref())?;
+  let id = next_transcription_id();
+  // bytes go to a file in the scratch dir for whisper-cli to read, removed once it is done
+  let (path, temporary) = match (audio_path, audio_bytes) {
+    (Some(path), None) => {
+      if !Path::new(&path).is_file() {
+        return Err(format!("Audio file not found: {}", path).into());
+      }
+      (PathBuf::from(path), false)
+    }
+    (None, Some(bytes)) => {
+      let path = storage::scratch_dir().join(format!("multilingual-transcription-{}.wav", id));
+      fs::write(&path, bytes).map_err(|e| AppError::io("write", &path, e))?;
+      (path, true)
+    }
+    _ => return Err(AppError::InvalidInput("Pass either audio_path or audio_bytes".into())),
+  };
+  let lang = language.filter(|l| l != "auto");
+  thread::spawn(move || {
+    let result = stream_transcription(&window, id, &exe, &model, &path, lang.as_deref());
+    if temporary {
+      let _ = fs::remove_file(&path);
+    }
+    let event = match result {
+      Ok(t) => TranscriptionEvent { transcription_id: id, segment: None, is_final: true, text: Some(t.text), lang: t.lang, error: None },
+      Err(e) => TranscriptionEvent { transcription_id: id, segment: None, is_final: true, text: None, lang: None, error: Some(e) },
+    };
+    let _ = window.emit("transcription-output", event);
+  });
+  Ok(id)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_whisper_timestamps() {
+    assert_eq!(parse_timestamp("00:01:02.345"), Some(62_345));
+    assert_eq!(parse_timestamp(" 01:00:00.000 "), Some(3_600_000));
+    assert_eq!(parse_timestamp("00:02.5"), Some(2_500));
+    assert_eq!(parse_timestamp("00:00:01,250"), Some(1_250));
+    assert_eq!(parse_timestamp("00:00:01.23456"), Some(1_234));
+    for bad in ["00:00:01", "00:00:01.", "aa:00:01.000", "00:00:01.-5", ""] {
+      assert_eq!(parse_timestamp(bad), None, "{}", bad);
+    }
+  }
+
+  #[test]
+  fn parses_segments() {
+    let segment = parse_segment("[00:00:00.000 --> 00:00:02.500]   Hello there.").unwrap();
+    assert_eq!((segment.start_ms, segment.end_ms, segment.text.as_str()), (0, 2_500, "Hello there."));
+    assert!(parse_segment("[00:00:02.500 --> 00:00:03.000]   ").is_none());
+    assert!(parse_segment("whisper_init_from_file: loading model").is_none());
+  }
+
+  fn model_file(name: &str, header: &[u8]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("multilingual-speech-{}-{}", std::process::id(), name));
+    fs::write(&path, header).unwrap();
+    path
+  }
+
+  #[test]
+  fn recognizes_ggml_whisper_files() {
+    let whisper = [b"lmgg".as_slice(), &51865i32.to_le_bytes()].concat();
+    let llm = [b"lmgg".as_slice(), &32000i32.to_le_bytes()].concat();
+    let cases = [("ggml-base.bin", whisper.as_slice(), true), ("ggml-llama.bin", llm.as_slice(), false), ("short.bin", b"lm".as_slice(), false)];
+    for (name, header, expected) in cases {
+      let path = model_file(name, header);
+      assert_eq!(is_whisper(&path, None), expected, "{}", name);
+      let _ = fs::remove_file(&path);
+    }
+  }
+
+  #[test]
+  fn recognizes_whisper_gguf_without_running_it() {
+    let gguf = GgufMetadata { architecture: Some("whisper".into()), ..Default::default() };
+    assert!(is_whisper(Path::new("model.gguf"), Some(&gguf)));
+    assert!(is_whisper(Path::new("whisper-small-q8.gguf"), None));
+    assert!(!is_whisper(Path::new("llama-3.gguf"), None));
+    assert!(runs_in_whisper_cli("/models/ggml-small.BIN"));
+    assert!(!runs_in_whisper_cli("/models/whisper-small.gguf"));
+  }
+}
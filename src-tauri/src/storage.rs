@@ -0,0 +1,108 @@
+// src-tauri/src/storage.rs
+use std::env;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use sysinfo::Disks;
+
+use crate::backup;
+use crate::settings;
+use crate::store;
+
+// Where runtimes put temporary files: the scratch_dir setting, else the system temp dir
+pub fn scratch_dir() -> PathBuf {
+  settings::get().scratch_dir.map(PathBuf::from).unwrap_or_else(env::temp_dir)
+}
+
+// point a runtime's temp files at the scratch dir: TMPDIR/TEMP/TMP for runtimes that use the
+// system temp dir, MULTILINGUAL_SCRATCH_DIR for wrappers that take a path of their own
+pub fn apply(command: &mut Command) {
+  let Some(dir) = settings::get().scratch_dir.map(PathBuf::from) else { return };
+  // runtimes fail in odd ways when their temp dir is missing
+  if fs::create_dir_all(&dir).is_err() {
+    return;
+  }
+  for var in ["TMPDIR", "TEMP", "TMP", "MULTILINGUAL_SCRATCH_DIR"] {
+    command.env(var, &dir);
+  }
+}
+
+// a Command for a runtime or tool, with its temp files going to the scratch dir
+pub fn command(program: impl AsRef<OsStr>) -> Command {
+  let mut c = Command::new(program);
+  apply(&mut c);
+  c
+}
+
+// folder settings (scratch_dir, backup_dir) must be folders we can write to; `what` names the setting
+pub fn check_writable_dir(dir: &str, what: &str) -> Result<(), String> {
+  let dir = PathBuf::from(dir);
+  fs::create_dir_all(&dir).map_err(|e| format!("can't use {} as {}: {}", dir.to_string_lossy(), what, e))?;
+  let probe = dir.join(".multilingual-probe");
+  fs::write(&probe, b"").map_err(|e| format!("{} is not writable: {}", dir.to_string_lossy(), e))?;
+  let _ = fs::remove_file(probe);
+  Ok(())
+}
+
+// One folder in the storage report
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct StorageEntry {
+  // "models" | "data" | "scratch" | "backups"
+  pub kind: String,
+  pub path: String,
+  pub bytes: u64,
+  // free space on the drive the folder is on, when it can be told
+  pub free_bytes: Option<u64>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct StorageUsage {
+  pub entries: Vec<StorageEntry>,
+  pub total_bytes: u64,
+}
+
+// size of everything under `path`; unreadable entries count as empty
+fn dir_size(path: &Path) -> u64 {
+  let Ok(meta) = fs::symlink_metadata(path) else { return 0 };
+  if !meta.is_dir() {
+    return meta.len();
+  }
+  fs::read_dir(path).map(|rd| rd.flatten().map(|e| dir_size(&e.path())).sum()).unwrap_or(0)
+}
+
+// free space of the disk with the longest mount point `path` is under
+fn free_space(disks: &Disks, path: &Path) -> Option<u64> {
+  let path = fs::canonicalize(path).ok()?;
+  disks
+    .list()
+    .iter()
+    .filter(|d| path.starts_with(d.mount_point()))
+    .max_by_key(|d| d.mount_point().as_os_str().len())
+    .map(|d| d.available_space())
+}
+
+// ------------------ Tauri commands ------------------
+
+// disk use of the model folders, the app's data and the runtimes' scratch dir. An unset scratch
+// dir is the system temp dir, of which only what the app's runtimes left there can't be told apart,
+// so the whole folder is counted
+#[tauri::command(async)]
+pub fn get_storage_usage() -> StorageUsage {
+  let disks = Disks::new_with_refreshed_list();
+  let mut folders: Vec<(&str, PathBuf)> = settings::model_dirs().into_iter().map(|d| ("models", d)).collect();
+  folders.push(("data", store::data_file("")));
+  folders.push(("scratch", scratch_dir()));
+  folders.push(("backups", backup::backup_dir()));
+  let entries: Vec<StorageEntry> = folders
+    .into_iter()
+    .map(|(kind, path)| StorageEntry {
+      kind: kind.to_string(),
+      bytes: dir_size(&path),
+      free_bytes: free_space(&disks, &path),
+      path: path.to_string_lossy().to_string(),
+    })
+    .collect();
+  StorageUsage { total_bytes: entries.iter().map(|e| e.bytes).sum(), entries }
+}
@@ -0,0 +1,91 @@
+// src-tauri/src/store.rs
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::events;
+use crate::instance;
+use crate::integrity;
+
+// where earlier versions kept the data, relative to the working directory
+const LEGACY_DATA_DIR: &str = "./data";
+
+// the app's own folder: data/, and the default models/ and backups/ folders
+static APP_DIR: OnceLock<PathBuf> = OnceLock::new();
+// the folder Tauri's app_config_dir() resolves to, where settings.json lives
+static CONFIG_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+// settle the app folder before anything reads its files: the folder Tauri's app_data_dir() resolves
+// to for `identifier`. A ./data folder left by an earlier version is moved into it once
+pub fn init(identifier: &str) {
+  if let Some(dir) = dirs::config_dir() {
+    let _ = CONFIG_DIR.set(dir.join(identifier));
+  }
+  let Some(dir) = dirs::data_dir().map(|d| d.join(identifier)) else { return };
+  let data = dir.join("data");
+  let legacy = Path::new(LEGACY_DATA_DIR);
+  if !data.exists() && legacy.is_dir() {
+    let _ = fs::create_dir_all(&dir);
+    if let Err(e) = fs::rename(legacy, &data) {
+      // e.g. on another drive; keep using it where it is rather than starting empty
+      events::log(format!("failed to move {} to {}: {}", legacy.to_string_lossy(), data.to_string_lossy(), e));
+      return;
+    }
+  }
+  let _ = APP_DIR.set(dir);
+}
+
+// a file or folder in the app folder; the working directory until init has found it
+pub fn app_path(name: &str) -> PathBuf {
+  APP_DIR.get().cloned().unwrap_or_else(|| PathBuf::from(".")).join(name)
+}
+
+// the app config folder; the data folder until init has found it
+pub fn config_dir() -> PathBuf {
+  CONFIG_DIR.get().cloned().unwrap_or_else(|| data_file(""))
+}
+
+// user data (glossary, decks, ...) lives here, separate from the models
+pub fn data_file(name: &str) -> PathBuf {
+  app_path("data").join(name)
+}
+
+// read a JSON file, falling back to the default value when missing. A file that can't be read as a
+// `T` is quarantined first, so the default saved over it later doesn't lose it for good
+pub fn load_json<T: DeserializeOwned + Default>(path: &Path) -> T {
+  let Ok(text) = fs::read(path) else { return T::default() };
+  match serde_json::from_slice(&text) {
+    Ok(value) => value,
+    Err(e) => {
+      integrity::quarantine(path, &e.to_string());
+      T::default()
+    }
+  }
+}
+
+// write to <name>.tmp and rename it over the file, so a crash mid-save leaves the old or the new
+// contents but never half of them (integrity::check finishes saves cut off before the rename).
+// The temp file is flushed to disk first: without that a power cut can leave the rename done but
+// the contents not. Fails while another instance holds the data folder
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> Result<(), String> {
+  instance::check_writable()?;
+  let tmp = integrity::temp_path(path);
+  let written = fs::File::create(&tmp).and_then(|mut f| {
+    f.write_all(bytes)?;
+    f.sync_all()
+  });
+  written.map_err(|e| format!("failed to write {}: {}", path.to_string_lossy(), e))?;
+  fs::rename(&tmp, path).map_err(|e| format!("failed to write {}: {}", path.to_string_lossy(), e))
+}
+
+pub fn save_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent).map_err(|e| format!("failed to create {}: {}", parent.to_string_lossy(), e))?;
+  }
+  let json = serde_json::to_string_pretty(value).map_err(|e| format!("failed to serialize {}: {}", path.to_string_lossy(), e))?;
+  write_atomic(path, json.as_bytes())
+}
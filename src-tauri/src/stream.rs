@@ -0,0 +1,468 @@
+// src-tauri/src/stream.rs
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use tauri::{Emitter, Runtime};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, BufReader};
+
+use crate::ansi;
+use crate::encoding::{LineDecoder, OutputEncoding};
+use crate::engine;
+use crate::error::LockExt;
+use crate::dispatch::{Priority, Streaming};
+use crate::procstats::{self, ResourceSnapshot, UsageMark};
+use crate::protocol::{OutputMessage, RuntimeStats};
+use crate::queue::{PromptQueue, Turn};
+use crate::settings;
+
+// printed by llama.cpp when the model ends its answer
+const END_OF_TEXT: &str = "[end of text]";
+// runtime log lines kept for crash reports
+const LOG_TAIL: usize = 20;
+
+// What a "model-output" event carries
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenKind {
+  // generated text
+  Token,
+  // runtime diagnostics (stderr)
+  Log,
+  // the request (or the process) finished; `text` is empty
+  Done,
+  // the request was stopped by cancel_prompt; `text` is empty
+  Cancelled,
+  // counters reported by the runner, in `stats`
+  Stats,
+  // the runner gave up on the request; `text` is its message
+  Error,
+}
+
+// Payload of "model-output" events
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct TokenEvent {
+  pub model_id: String,
+  // the run_prompt call the output belongs to (0 for output before the first prompt)
+  pub request_id: u64,
+  pub kind: TokenKind,
+  pub text: String,
+  pub is_final: bool,
+  // average since the request started
+  pub tokens_per_sec: f32,
+  // unix milliseconds
+  pub timestamp: u64,
+  // log line with its color codes, for the raw log view (only with the raw_logs setting)
+  pub raw: Option<String>,
+  pub stats: Option<RuntimeStats>,
+  // on the event that ends a request: what the model's processes used while generating it
+  pub resources: Option<ResourceSnapshot>,
+}
+
+// Payload of "runtime-raw" events (developer console only)
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct RawIo {
+  pub model_id: String,
+  // "stdout" | "stderr" | "stdin"
+  pub channel: &'static str,
+  // as the runtime wrote (or was sent) it: color codes, echoes and protocol lines included
+  pub text: String,
+  pub timestamp: u64,
+}
+
+struct Progress {
+  started: Instant,
+  tokens: u32,
+  // set once the Done event went out, so the process exit doesn't send another
+  finished: bool,
+  // output the runtime still flushes after a cancel is dropped until the next request
+  cancelled: bool,
+  // the process's CPU time and memory when the request started
+  usage: Option<UsageMark>,
+  // counts the request with the dispatcher until its final event
+  busy: Option<Streaming>,
+}
+
+// How a process's output is read
+#[derive(Clone, Debug, Default)]
+pub struct StreamConfig {
+  // printed by llama.cpp's interactive mode when the model's turn is over and it waits for input
+  pub reverse_prompt: Option<String>,
+  // whether the backend prints prompts back before answering
+  pub strip_echo: bool,
+  pub encoding: OutputEncoding,
+  // whether stdout may carry structured messages (see Backend::structured_output)
+  pub messages: bool,
+}
+
+// Output bookkeeping of one model process, shared by its reader threads
+pub struct TokenStream {
+  model_id: String,
+  // printed by llama.cpp's interactive mode when the model's turn is over and it waits for input
+  reverse_prompt: Option<String>,
+  // whether the backend prints prompts back before answering
+  strip_echo: bool,
+  // lines of the last prompt not yet seen echoed
+  echo: Mutex<VecDeque<String>>,
+  encoding: OutputEncoding,
+  // whether lines are looked at as structured messages at all
+  messages: bool,
+  // set by the first structured message; from then on free text is logging
+  structured: AtomicBool,
+  request_id: AtomicU64,
+  progress: Mutex<Progress>,
+  // the last LOG_TAIL log lines, for the watchdog's crash report
+  logs: Mutex<VecDeque<String>>,
+  // prompts waiting for the current request to finish
+  queue: PromptQueue,
+}
+
+// a line as it is compared against the prompt: without llama.cpp's "> " input marker and "\" line
+// continuations, which only some runtimes print back
+fn echo_form(line: &str) -> String {
+  let line = line.trim();
+  let line = line.strip_prefix('>').unwrap_or(line);
+  line.trim_end_matches('\\').trim().to_string()
+}
+
+fn now_millis() -> u64 {
+  SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+// ids are unique across processes so the frontend can key on them alone
+pub fn next_request_id() -> u64 {
+  static NEXT: AtomicU64 = AtomicU64::new(0);
+  NEXT.fetch_add(1, Ordering::Relaxed) + 1
+}
+
+impl TokenStream {
+  pub fn new(model_id: &str, config: StreamConfig) -> Arc<Self> {
+    Arc::new(Self {
+      model_id: model_id.to_string(),
+      reverse_prompt: config.reverse_prompt.filter(|r| !r.trim().is_empty()),
+      strip_echo: config.strip_echo,
+      echo: Mutex::new(VecDeque::new()),
+      encoding: config.encoding,
+      messages: config.messages,
+      structured: AtomicBool::new(false),
+      request_id: AtomicU64::new(0),
+      progress: Mutex::new(Progress { started: Instant::now(), tokens: 0, finished: false, cancelled: false, usage: None, busy: None }),
+      logs: Mutex::new(VecDeque::new()),
+      queue: PromptQueue::new(model_id),
+    })
+  }
+
+  // start a new request; output from here on is tagged with the returned id
+  pub fn begin(&self) -> u64 {
+    let id = next_request_id();
+    self.request_id.store(id, Ordering::Relaxed);
+    let usage = procstats::mark(&self.model_id);
+    *self.progress.locked() = Progress { started: Instant::now(), tokens: 0, finished: false, cancelled: false, usage, busy: None };
+    id
+  }
+
+  // start request `id` unless another one is still generating
+  fn try_begin(&self, id: u64) -> bool {
+    let mut progress = self.progress.locked();
+    if self.request_id.load(Ordering::Relaxed) != 0 && !progress.finished {
+      return false;
+    }
+    self.request_id.store(id, Ordering::Relaxed);
+    *progress = Progress { started: Instant::now(), tokens: 0, finished: false, cancelled: false, usage: procstats::mark(&self.model_id), busy: None };
+    true
+  }
+
+  // keep `busy` until the current request's final event
+  pub fn hold(&self, busy: Streaming) {
+    let mut progress = self.progress.locked();
+    if !progress.finished {
+      progress.busy = Some(busy);
+    }
+  }
+
+  // queue request `id` (from next_request_id) behind the prompts already waiting
+  pub fn enqueue<R: Runtime>(&self, target: &impl Emitter<R>, id: u64, priority: Priority) {
+    self.queue.push(target, id, priority);
+  }
+
+  // start queued request `id` if it is its turn now: first in line and nothing generating
+  pub fn take_turn<R: Runtime>(&self, target: &impl Emitter<R>, id: u64) -> bool {
+    self.queue.take(target, id, || self.try_begin(id)) == Turn::Started
+  }
+
+  // wait for queued request `id`'s turn and start it; false when it was cancelled or the process
+  // ended first
+  pub async fn wait_turn<R: Runtime>(&self, target: &impl Emitter<R>, id: u64) -> bool {
+    self.queue.wait(target, id, || self.try_begin(id)).await
+  }
+
+  // cancel request `id` while it waits its turn; false when it isn't queued
+  pub fn cancel_queued<R: Runtime>(&self, target: &impl Emitter<R>, id: u64) -> bool {
+    if !self.queue.remove(target, id) {
+      return false;
+    }
+    let _ = target.emit("model-output", self.unstarted(id, TokenKind::Cancelled, String::new()));
+    true
+  }
+
+  // the final event of a request that never got its turn
+  fn unstarted(&self, request_id: u64, kind: TokenKind, text: String) -> TokenEvent {
+    TokenEvent {
+      model_id: self.model_id.clone(),
+      request_id,
+      kind,
+      text,
+      is_final: true,
+      tokens_per_sec: 0.0,
+      timestamp: now_millis(),
+      raw: None,
+      stats: None,
+      resources: None,
+    }
+  }
+
+  // the request output is currently tagged with, if it is still generating
+  pub fn in_flight(&self) -> Option<u64> {
+    let id = self.request_id.load(Ordering::Relaxed);
+    (id != 0 && !self.progress.locked().finished).then_some(id)
+  }
+
+  fn event(&self, kind: TokenKind, text: String) -> Option<TokenEvent> {
+    let mut progress = self.progress.locked();
+    if progress.cancelled && kind == TokenKind::Token {
+      return None;
+    }
+    if matches!(kind, TokenKind::Done | TokenKind::Cancelled | TokenKind::Error) {
+      if progress.finished {
+        return None;
+      }
+      progress.finished = true;
+      progress.cancelled = kind == TokenKind::Cancelled;
+    }
+    if kind == TokenKind::Token {
+      progress.tokens += engine::estimate_tokens(&text);
+    }
+    let is_final = matches!(kind, TokenKind::Done | TokenKind::Cancelled | TokenKind::Error);
+    let mut event = TokenEvent {
+      model_id: self.model_id.clone(),
+      request_id: self.request_id.load(Ordering::Relaxed),
+      kind,
+      is_final,
+      text,
+      tokens_per_sec: progress.tokens as f32 / progress.started.elapsed().as_secs_f32().max(0.001),
+      timestamp: now_millis(),
+      raw: None,
+      stats: None,
+      resources: None,
+    };
+    let usage = if is_final { progress.usage.take() } else { None };
+    let busy = if is_final { progress.busy.take() } else { None };
+    drop(progress);
+    drop(busy);
+    if is_final {
+      self.queue.wake();
+    }
+    event.resources = usage.and_then(|mark| procstats::snapshot(&self.model_id, &mark));
+    Some(event)
+  }
+
+  fn send<R: Runtime>(&self, target: &impl Emitter<R>, kind: TokenKind, text: String) {
+    if let Some(event) = self.event(kind, text) {
+      let _ = target.emit("model-output", event);
+    }
+  }
+
+  // `text` was just written to the process; if the backend prints it back, those lines are dropped
+  pub fn expect_echo(&self, text: &str) {
+    if self.strip_echo {
+      *self.echo.locked() = text.lines().map(echo_form).filter(|l| !l.is_empty()).collect();
+    }
+  }
+
+  // drop output lines that repeat the prompt; the first line that doesn't ends the echo
+  fn is_echo(&self, line: &str) -> bool {
+    let mut echo = self.echo.locked();
+    let Some(next) = echo.front() else { return false };
+    let line = echo_form(line);
+    if line.is_empty() {
+      return true;
+    }
+    if line == *next {
+      echo.pop_front();
+      return true;
+    }
+    echo.clear();
+    false
+  }
+
+  // where the model's turn ends in `line`: the end-of-text marker or the reverse prompt. The
+  // reverse prompt only counts at the end of the line, where the runtime prints it to wait for
+  // input; the same words inside generated text are part of the answer
+  fn turn_end(&self, line: &str) -> Option<usize> {
+    let marker = line.find(END_OF_TEXT);
+    let reverse = self.reverse_prompt.as_deref().map(str::trim).and_then(|r| {
+      let text = line.trim_end();
+      text.ends_with(r).then(|| text.len() - r.len())
+    });
+    marker.into_iter().chain(reverse).min()
+  }
+
+  // one line of stdout: generated text, possibly ending the request. Nothing after the end of the
+  // turn is sent: it is the reverse prompt and whatever the runtime echoes of the next input
+  fn stdout_line<R: Runtime>(&self, target: &impl Emitter<R>, line: &str) {
+    if self.is_echo(line) {
+      return;
+    }
+    match self.turn_end(line) {
+      Some(at) => {
+        if !line[..at].trim().is_empty() {
+          self.send(target, TokenKind::Token, line[..at].to_string());
+        }
+        self.send(target, TokenKind::Done, String::new());
+      }
+      None => self.send(target, TokenKind::Token, line.to_string()),
+    }
+  }
+
+  // copy a line of the process's I/O to the developer console, when it is on
+  pub fn tap<R: Runtime>(&self, target: &impl Emitter<R>, channel: &'static str, text: &str) {
+    if settings::get().dev_console {
+      let _ = target.emit("runtime-raw", RawIo { model_id: self.model_id.clone(), channel, text: text.to_string(), timestamp: now_millis() });
+    }
+  }
+
+  // one decoded line of stdout: a structured message, or free text passed through `process`
+  fn output_line<R: Runtime>(&self, target: &impl Emitter<R>, line: &str, process: &impl Fn(&str) -> String) {
+    match OutputMessage::parse(line).filter(|_| self.messages) {
+      Some(message) => {
+        self.structured.store(true, Ordering::Relaxed);
+        self.message(target, message, process);
+      }
+      None if self.structured.load(Ordering::Relaxed) => self.stderr_line(target, line),
+      None => self.stdout_line(target, &process(line)),
+    }
+  }
+
+  // messages for an earlier request (one that was cancelled, say) are dropped
+  fn message<R: Runtime>(&self, target: &impl Emitter<R>, message: OutputMessage, process: &impl Fn(&str) -> String) {
+    if message.id().is_some_and(|id| id != self.request_id.load(Ordering::Relaxed)) {
+      return;
+    }
+    match message {
+      OutputMessage::Token { text, .. } => self.send(target, TokenKind::Token, process(&text)),
+      OutputMessage::Stats { stats, .. } => {
+        if let Some(mut event) = self.event(TokenKind::Stats, String::new()) {
+          event.stats = Some(stats);
+          let _ = target.emit("model-output", event);
+        }
+      }
+      OutputMessage::Done { .. } => self.send(target, TokenKind::Done, String::new()),
+      OutputMessage::Error { message, .. } => self.send(target, TokenKind::Error, message),
+    }
+  }
+
+  // forward stdout until the process closes it, passing each line through `process` first. A
+  // reverse prompt is printed without a line break while the runtime waits for input, so
+  // unfinished lines are checked for it too
+  pub async fn pump_stdout<R: Runtime>(&self, target: &impl Emitter<R>, mut out: impl AsyncRead + Unpin, process: impl Fn(&str) -> String) {
+    let mut decoder = LineDecoder::new(self.encoding);
+    let mut pending: Vec<u8> = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+      let n = match out.read(&mut buf).await {
+        Ok(0) | Err(_) => break,
+        Ok(n) => n,
+      };
+      pending.extend_from_slice(&buf[..n]);
+      while let Some(at) = pending.iter().position(|&b| b == b'\n') {
+        let line: Vec<u8> = pending.drain(..=at).collect();
+        let line = decoder.decode(&line);
+        self.tap(target, "stdout", line.trim_end_matches(['\r', '\n']));
+        self.output_line(target, &ansi::strip(line.trim_end_matches(['\r', '\n'])), &process);
+      }
+      if let Some(reverse) = self.reverse_prompt.as_deref().map(str::trim) {
+        let text = ansi::strip(&decoder.peek(&pending));
+        if text.trim_end().ends_with(reverse) {
+          self.tap(target, "stdout", &decoder.peek(&pending));
+          pending.clear();
+          self.stdout_line(target, &process(&text));
+        }
+      }
+    }
+    if !pending.is_empty() {
+      let line = decoder.decode(&pending);
+      self.tap(target, "stdout", &line);
+      self.output_line(target, &ansi::strip(&line), &process);
+    }
+  }
+
+  // forward runtime logs (stderr, or the stdout of a server) line by line until the stream closes
+  pub async fn pump_logs<R: Runtime>(&self, target: &impl Emitter<R>, channel: &'static str, out: impl AsyncRead + Unpin) {
+    let mut decoder = LineDecoder::new(self.encoding);
+    let mut reader = BufReader::new(out);
+    let mut line = Vec::new();
+    while reader.read_until(b'\n', &mut line).await.is_ok_and(|n| n > 0) {
+      let text = decoder.decode(&line);
+      self.tap(target, channel, text.trim_end_matches(['\r', '\n']));
+      self.stderr_line(target, text.trim_end_matches(['\r', '\n']));
+      self.keep_log(text.trim_end_matches(['\r', '\n']));
+      line.clear();
+    }
+  }
+
+  fn keep_log(&self, line: &str) {
+    if line.trim().is_empty() {
+      return;
+    }
+    let mut logs = self.logs.locked();
+    if logs.len() == LOG_TAIL {
+      logs.pop_front();
+    }
+    logs.push_back(ansi::strip(line));
+  }
+
+  // the last log lines the runtime printed, oldest first
+  pub fn recent_logs(&self) -> Vec<String> {
+    self.logs.locked().iter().cloned().collect()
+  }
+
+  // a piece of generated text as is (the HTTP backend gets tokens, not lines)
+  pub fn token<R: Runtime>(&self, target: &impl Emitter<R>, text: &str) {
+    self.send(target, TokenKind::Token, text.to_string());
+  }
+
+  pub fn stderr_line<R: Runtime>(&self, target: &impl Emitter<R>, line: &str) {
+    if let Some(mut event) = self.event(TokenKind::Log, ansi::strip(line)) {
+      if ansi::has_controls(line) && settings::get().raw_logs {
+        event.raw = Some(line.to_string());
+      }
+      let _ = target.emit("model-output", event);
+    }
+  }
+
+  // end the current request as cancelled; false when it had already finished
+  pub fn cancel<R: Runtime>(&self, target: &impl Emitter<R>) -> bool {
+    match self.event(TokenKind::Cancelled, String::new()) {
+      Some(event) => {
+        let _ = target.emit("model-output", event);
+        true
+      }
+      None => false,
+    }
+  }
+
+  // end the current request with the runner's (or the app's) error message
+  pub fn error<R: Runtime>(&self, target: &impl Emitter<R>, message: &str) {
+    self.send(target, TokenKind::Error, message.to_string());
+  }
+
+  // the process closed its output; finishes the current request if it hasn't ended yet and fails
+  // the prompts still waiting
+  pub fn closed<R: Runtime>(&self, target: &impl Emitter<R>) {
+    self.send(target, TokenKind::Done, String::new());
+    for id in self.queue.drain() {
+      let _ = target.emit("model-output", self.unstarted(id, TokenKind::Error, "The model stopped before this prompt's turn".into()));
+    }
+  }
+}
@@ -0,0 +1,139 @@
+// src-tauri/src/summarize.rs
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+
+use tauri::{Manager, Window};
+
+use crate::chunk;
+use crate::engine;
+use crate::error::{AppError, LockExt};
+use crate::events;
+use crate::filter;
+use crate::lang;
+use crate::model_config::ModelConfig;
+use crate::output::{self, OutputFormat};
+use crate::preload;
+use crate::readable::{self, ReadingOptions};
+use crate::ModelManager;
+
+// prompt + instructions overhead kept free in every chunk request
+const PROMPT_OVERHEAD_TOKENS: u32 = 200;
+// give up reducing after this many rounds (protects against a model that never shortens)
+const MAX_REDUCE_ROUNDS: u32 = 4;
+
+// Payload of "summarize-progress" events
+#[derive(Clone, serde::Serialize)]
+struct SummarizeProgress {
+  // "map" for chunk summaries, "reduce" for combining them
+  stage: String,
+  round: u32,
+  index: usize,
+  total: usize,
+  summary: String,
+}
+
+// how long the final summary should be: (instruction, max tokens)
+fn length_spec(length: &str) -> Result<(&'static str, u32), String> {
+  match length {
+    "short" => Ok(("in 2-3 sentences", 160)),
+    "medium" | "" => Ok(("in one or two paragraphs", 400)),
+    "long" => Ok(("in detail, as several paragraphs covering every section", 900)),
+    other => Err(format!("Unknown summary length '{}', expected short, medium or long", other)),
+  }
+}
+
+// a path to a readable text file, or the text itself
+fn read_input(text_or_path: &str) -> Result<String, String> {
+  let p = Path::new(text_or_path);
+  if text_or_path.len() < 1024 && !text_or_path.contains('\n') && p.is_file() {
+    return fs::read_to_string(p).map_err(|e| format!("failed to read {}: {}", text_or_path, e));
+  }
+  Ok(text_or_path.to_string())
+}
+
+struct Summarizer<'a> {
+  model_path: &'a str,
+  config: &'a ModelConfig,
+  lang_name: String,
+  window: &'a Window,
+}
+
+impl Summarizer<'_> {
+  fn summarize_one(&self, text: &str, how: &str, max_tokens: u32) -> Result<String, String> {
+    let prompt = format!(
+      "Summarize the following text {} in {}. Reply with the summary only.\n\nText:\n{}\n\nSummary:",
+      how, self.lang_name, text
+    );
+    engine::generate(self.model_path, self.config, &prompt, max_tokens)
+  }
+
+  fn progress(&self, stage: &str, round: u32, index: usize, total: usize, summary: &str) {
+    events::emit(
+      self.window,
+      "summarize-progress",
+      SummarizeProgress { stage: stage.into(), round, index, total, summary: summary.to_string() },
+    );
+  }
+}
+
+// map-reduce summarization: summarize each chunk, then summarize the summaries
+// until they fit into a single request
+pub fn summarize_text(
+  window: &Window,
+  model_path: &str,
+  config: &ModelConfig,
+  text: &str,
+  target_lang: &str,
+  length: &str,
+) -> Result<String, String> {
+  let (how, final_tokens) = length_spec(length)?;
+  let ctx = engine::context_size(config);
+  let chunk_budget = ctx.saturating_sub(final_tokens + PROMPT_OVERHEAD_TOKENS).max(256);
+  let s = Summarizer { model_path, config, lang_name: lang::language_name(target_lang), window };
+
+  let mut current = text.to_string();
+  for round in 0..MAX_REDUCE_ROUNDS {
+    let chunks = chunk::split_into_chunks(&current, chunk_budget);
+    if chunks.len() <= 1 {
+      break;
+    }
+    let stage = if round == 0 { "map" } else { "reduce" };
+    let mut partials = Vec::with_capacity(chunks.len());
+    for (i, c) in chunks.iter().enumerate() {
+      let partial = s.summarize_one(c, "in a short paragraph", 256)?;
+      s.progress(stage, round, i, chunks.len(), &partial);
+      partials.push(partial);
+    }
+    current = partials.join("\n\n");
+  }
+
+  let summary = s.summarize_one(&current, how, final_tokens)?;
+  s.progress("final", 0, 0, 1, &summary);
+  Ok(summary)
+}
+
+// ------------------ Tauri commands ------------------
+
+#[tauri::command(async)]
+pub fn summarize(
+  text_or_path: String,
+  target_lang: String,
+  length: String,
+  model_id: Option<String>,
+  output_format: Option<OutputFormat>,
+  reading: Option<ReadingOptions>,
+  window: Window,
+) -> Result<String, AppError> {
+  let text = read_input(&text_or_path)?;
+  if text.trim().is_empty() {
+    return Err("Nothing to summarize".into());
+  }
+  let (model, config) = window.state::<Mutex<ModelManager>>().locked().model_for_request(model_id.as_deref())?;
+  preload::record(&window, "summarize", &target_lang, &model.id);
+  // filter reports reach the frontend as "content-filter" events
+  let (text, _) = filter::check_input(&window, text)?;
+  let summary = summarize_text(&window, &model.path, &config, &text, &target_lang, &length)?;
+  let (summary, _) = filter::check_output(&window, summary)?;
+  Ok(output::apply(readable::apply(summary, reading.as_ref(), &target_lang), output_format, &target_lang, &model.path, &config))
+}
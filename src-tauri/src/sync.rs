@@ -0,0 +1,399 @@
+// src-tauri/src/sync.rs
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::digest::{digest, SHA256};
+use ring::rand::{SecureRandom, SystemRandom};
+use tauri::{AppHandle, Manager};
+
+use crate::error::{AppError, LockExt};
+use crate::favorites::{Favorite, Favorites};
+use crate::glossary::{Glossary, GlossaryEntry};
+use crate::lan::LanShare;
+use crate::phrasebook::{PhrasePack, Phrasebooks};
+use crate::store;
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncKind {
+  Favorite,
+  Glossary,
+  Phrasebook,
+}
+
+// Last-writer-wins state of one synced item; a deleted item keeps its entry as a tombstone
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+struct ItemMeta {
+  kind: SyncKind,
+  // unix millis of the last change
+  modified: i64,
+  // device that made it, breaks ties between equal timestamps
+  origin: String,
+  deleted: bool,
+  // hash of the value, to notice local edits between syncs
+  hash: String,
+}
+
+impl ItemMeta {
+  fn wins_over(&self, other: &ItemMeta) -> bool {
+    (self.modified, &self.origin) > (other.modified, &other.origin)
+  }
+}
+
+// One item as exchanged with the companion app
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct SyncEntry {
+  pub key: String,
+  pub kind: SyncKind,
+  pub modified: i64,
+  pub origin: String,
+  pub deleted: bool,
+  // Favorite, GlossaryEntry or PhrasePack as JSON; None for tombstones
+  pub value: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct SyncBundle {
+  pub device: String,
+  pub entries: Vec<SyncEntry>,
+}
+
+// What the companion app posts to /v1/sync and gets back: the bundle sealed with the shared key
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct SealedBundle {
+  pub key_id: String,
+  // base64
+  pub nonce: String,
+  pub data: String,
+}
+
+// A device allowed to sync, with the key it got from the pairing QR code (never sent over the network)
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+struct SyncKey {
+  id: String,
+  name: String,
+  // base64, 32 bytes
+  key: String,
+  created: i64,
+  last_sync: Option<i64>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct SyncDevice {
+  pub id: String,
+  pub name: String,
+  pub created: i64,
+  pub last_sync: Option<i64>,
+}
+
+// Contents of the sync pairing QR code
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct SyncPairing {
+  pub payload: String,
+  pub key_id: String,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct SyncSummary {
+  pub applied: usize,
+  pub sent: usize,
+}
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct SyncFile {
+  device_id: String,
+  keys: Vec<SyncKey>,
+  items: HashMap<String, ItemMeta>,
+}
+
+// Sync keys and per-item merge state persisted in ./data/sync.json (managed by Tauri)
+pub struct SyncState {
+  path: PathBuf,
+  file: SyncFile,
+}
+
+fn now_millis() -> i64 {
+  SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as i64).unwrap_or(0)
+}
+
+fn random_bytes(n: usize) -> Vec<u8> {
+  let mut buf = vec![0u8; n];
+  SystemRandom::new().fill(&mut buf).expect("system random generator unavailable");
+  buf
+}
+
+fn hash_of<T: serde::Serialize>(value: &T) -> String {
+  let json = serde_json::to_vec(value).unwrap_or_default();
+  digest(&SHA256, &json).as_ref().iter().take(16).map(|b| format!("{:02x}", b)).collect()
+}
+
+impl SyncState {
+  pub fn load() -> Self {
+    let path = store::data_file("sync.json");
+    let mut file: SyncFile = store::load_json(&path);
+    if file.device_id.is_empty() {
+      file.device_id = random_bytes(8).iter().map(|b| format!("{:02x}", b)).collect();
+      let _ = store::save_json(&path, &file);
+    }
+    Self { path, file }
+  }
+
+  fn save(&self) -> Result<(), String> {
+    store::save_json(&self.path, &self.file)
+  }
+
+  fn key(&self, id: &str) -> Result<LessSafeKey, String> {
+    let entry = self.file.keys.iter().find(|k| k.id == id).ok_or(format!("Unknown sync key '{}'", id))?;
+    let bytes = BASE64.decode(&entry.key).map_err(|_| "corrupt sync key".to_string())?;
+    let unbound = UnboundKey::new(&CHACHA20_POLY1305, &bytes).map_err(|_| "corrupt sync key".to_string())?;
+    Ok(LessSafeKey::new(unbound))
+  }
+}
+
+fn seal(key: &LessSafeKey, key_id: &str, bundle: &SyncBundle) -> Result<SealedBundle, String> {
+  let nonce_bytes = random_bytes(NONCE_LEN);
+  let nonce = Nonce::try_assume_unique_for_key(&nonce_bytes).map_err(|_| "bad nonce".to_string())?;
+  let mut data = serde_json::to_vec(bundle).map_err(|e| format!("failed to serialize sync bundle: {}", e))?;
+  key.seal_in_place_append_tag(nonce, Aad::from(key_id.as_bytes()), &mut data).map_err(|_| "encryption failed".to_string())?;
+  Ok(SealedBundle { key_id: key_id.to_string(), nonce: BASE64.encode(nonce_bytes), data: BASE64.encode(data) })
+}
+
+fn open(key: &LessSafeKey, sealed: &SealedBundle) -> Result<SyncBundle, String> {
+  let nonce_bytes = BASE64.decode(&sealed.nonce).map_err(|_| "invalid nonce".to_string())?;
+  let nonce = Nonce::try_assume_unique_for_key(&nonce_bytes).map_err(|_| "invalid nonce".to_string())?;
+  let mut data = BASE64.decode(&sealed.data).map_err(|_| "invalid bundle encoding".to_string())?;
+  let plain = key
+    .open_in_place(nonce, Aad::from(sealed.key_id.as_bytes()), &mut data)
+    .map_err(|_| "bundle could not be decrypted (wrong key or tampered)".to_string())?;
+  serde_json::from_slice(plain).map_err(|e| format!("invalid sync bundle: {}", e))
+}
+
+// every local item keyed by content, so the same favorite gets the same key on every device
+fn local_items(app: &AppHandle) -> HashMap<String, (SyncKind, serde_json::Value)> {
+  let mut items = HashMap::new();
+  for f in app.state::<Mutex<Favorites>>().locked().items() {
+    let key = format!("fav:{}", hash_of(&(&f.source_lang, &f.target_lang, &f.source_text, &f.translated_text)));
+    items.insert(key, (SyncKind::Favorite, serde_json::to_value(&f).unwrap_or_default()));
+  }
+  for g in app.state::<Mutex<Glossary>>().locked().entries(None, None) {
+    let key = format!("glo:{}", hash_of(&(&g.source_lang, &g.target_lang, &g.source)));
+    items.insert(key, (SyncKind::Glossary, serde_json::to_value(&g).unwrap_or_default()));
+  }
+  for p in app.state::<Mutex<Phrasebooks>>().locked().packs() {
+    let mut pack = p.clone();
+    // audio files stay on each device
+    for phrase in pack.phrases.iter_mut() {
+      phrase.audio = None;
+    }
+    items.insert(format!("pb:{}", pack.id), (SyncKind::Phrasebook, serde_json::to_value(&pack).unwrap_or_default()));
+  }
+  items
+}
+
+// value fields that matter for change detection (local ids differ between devices)
+fn content_hash(kind: SyncKind, value: &serde_json::Value) -> String {
+  let mut v = value.clone();
+  if kind != SyncKind::Phrasebook {
+    if let Some(obj) = v.as_object_mut() {
+      obj.remove("id");
+    }
+  }
+  hash_of(&v)
+}
+
+// record local additions, edits and deletions since the last sync as new writes by this device
+fn refresh(state: &mut SyncState, items: &HashMap<String, (SyncKind, serde_json::Value)>) {
+  let now = now_millis();
+  let me = state.file.device_id.clone();
+  for (key, (kind, value)) in items {
+    let hash = content_hash(*kind, value);
+    let changed = state.file.items.get(key).is_none_or(|m| m.deleted || m.hash != hash);
+    if changed {
+      state.file.items.insert(key.clone(), ItemMeta { kind: *kind, modified: now, origin: me.clone(), deleted: false, hash });
+    }
+  }
+  for (key, meta) in state.file.items.iter_mut() {
+    if !meta.deleted && !items.contains_key(key) {
+      *meta = ItemMeta { kind: meta.kind, modified: now, origin: me.clone(), deleted: true, hash: String::new() };
+    }
+  }
+}
+
+// make the local stores match a winning remote entry
+fn apply(app: &AppHandle, entry: &SyncEntry, local: &HashMap<String, (SyncKind, serde_json::Value)>) -> Result<(), String> {
+  let local_id = local.get(&entry.key).and_then(|(_, v)| v.get("id")).cloned();
+  match entry.kind {
+    SyncKind::Favorite => {
+      let favorites = app.state::<Mutex<Favorites>>();
+      let mut favorites = favorites.locked();
+      if let Some(id) = local_id.and_then(|v| v.as_u64()) {
+        favorites.remove(id)?;
+      }
+      if let (false, Some(value)) = (entry.deleted, &entry.value) {
+        let fav: Favorite = serde_json::from_value(value.clone()).map_err(|e| format!("invalid favorite: {}", e))?;
+        favorites.add(fav)?;
+      }
+    }
+    SyncKind::Glossary => {
+      let glossary = app.state::<Mutex<Glossary>>();
+      let mut glossary = glossary.locked();
+      if let Some(id) = local_id.and_then(|v| v.as_u64()) {
+        glossary.remove(id)?;
+      }
+      if let (false, Some(value)) = (entry.deleted, &entry.value) {
+        let term: GlossaryEntry = serde_json::from_value(value.clone()).map_err(|e| format!("invalid glossary entry: {}", e))?;
+        glossary.add(term)?;
+      }
+    }
+    SyncKind::Phrasebook => {
+      let books = app.state::<Mutex<Phrasebooks>>();
+      let mut books = books.locked();
+      match (entry.deleted, &entry.value) {
+        (false, Some(value)) => {
+          let pack: PhrasePack = serde_json::from_value(value.clone()).map_err(|e| format!("invalid phrasebook: {}", e))?;
+          books.install(pack)?;
+        }
+        _ if local_id.is_some() => books.remove(entry.key.trim_start_matches("pb:"))?,
+        _ => {}
+      }
+    }
+  }
+  Ok(())
+}
+
+// merge a remote bundle into local state (per-item last-writer-wins, so merging is order independent
+// and repeatable) and return the merged state to send back
+fn merge(app: &AppHandle, state: &mut SyncState, remote: &SyncBundle) -> Result<(SyncBundle, usize), String> {
+  let items = local_items(app);
+  refresh(state, &items);
+  let mut applied = 0;
+  for entry in &remote.entries {
+    let incoming = ItemMeta {
+      kind: entry.kind,
+      modified: entry.modified,
+      origin: entry.origin.clone(),
+      deleted: entry.deleted,
+      hash: entry.value.as_ref().map(|v| content_hash(entry.kind, v)).unwrap_or_default(),
+    };
+    let wins = state.file.items.get(&entry.key).is_none_or(|m| incoming.wins_over(m));
+    if !wins {
+      continue;
+    }
+    // a tombstone for something we never had only needs recording
+    if !entry.deleted || items.contains_key(&entry.key) {
+      apply(app, entry, &items)?;
+      applied += 1;
+    }
+    state.file.items.insert(entry.key.clone(), incoming);
+  }
+
+  let items = local_items(app);
+  let entries = state
+    .file
+    .items
+    .iter()
+    .map(|(key, meta)| SyncEntry {
+      key: key.clone(),
+      kind: meta.kind,
+      modified: meta.modified,
+      origin: meta.origin.clone(),
+      deleted: meta.deleted,
+      value: if meta.deleted { None } else { items.get(key).map(|(_, v)| v.clone()) },
+    })
+    .collect();
+  Ok((SyncBundle { device: state.file.device_id.clone(), entries }, applied))
+}
+
+// POST /v1/sync on the LAN server: decrypt, merge, reply with the sealed merged state
+pub fn handle_request(app: &AppHandle, body: &[u8]) -> Result<SealedBundle, (u16, String)> {
+  let sealed: SealedBundle = serde_json::from_slice(body).map_err(|e| (400, format!("invalid request: {}", e)))?;
+  let state = app.state::<Mutex<SyncState>>();
+  let mut state = state.locked();
+  let key = state.key(&sealed.key_id).map_err(|e| (403, e))?;
+  let remote = open(&key, &sealed).map_err(|e| (403, e))?;
+  let (merged, _) = merge(app, &mut state, &remote).map_err(|e| (500, e))?;
+  if let Some(k) = state.file.keys.iter_mut().find(|k| k.id == sealed.key_id) {
+    k.last_sync = Some(now_millis() / 1000);
+  }
+  state.save().map_err(|e| (500, e))?;
+  seal(&key, &sealed.key_id, &merged).map_err(|e| (500, e))
+}
+
+// ------------------ Tauri commands ------------------
+
+// new key for a companion device; the payload goes into a QR code so the key never crosses the network
+#[tauri::command]
+pub fn create_sync_key(name: String, app: AppHandle) -> Result<SyncPairing, AppError> {
+  let url = app.state::<Mutex<LanShare>>().locked().url().ok_or("Start LAN sharing before adding a sync device")?;
+  let state = app.state::<Mutex<SyncState>>();
+  let mut state = state.locked();
+  let key = SyncKey {
+    id: random_bytes(6).iter().map(|b| format!("{:02x}", b)).collect(),
+    name,
+    key: BASE64.encode(random_bytes(32)),
+    created: now_millis() / 1000,
+    last_sync: None,
+  };
+  let payload = serde_json::json!({ "app": "multilingual-sync", "v": 1, "url": url, "key_id": key.id, "key": key.key }).to_string();
+  let key_id = key.id.clone();
+  state.file.keys.push(key);
+  state.save()?;
+  Ok(SyncPairing { payload, key_id })
+}
+
+#[tauri::command]
+pub fn list_sync_devices(state: tauri::State<'_, Mutex<SyncState>>) -> Vec<SyncDevice> {
+  let state = state.locked();
+  state
+    .file
+    .keys
+    .iter()
+    .map(|k| SyncDevice { id: k.id.clone(), name: k.name.clone(), created: k.created, last_sync: k.last_sync })
+    .collect()
+}
+
+#[tauri::command]
+pub fn revoke_sync_key(key_id: String, state: tauri::State<'_, Mutex<SyncState>>) -> Result<(), AppError> {
+  let mut state = state.locked();
+  let before = state.file.keys.len();
+  state.file.keys.retain(|k| k.id != key_id);
+  if state.file.keys.len() == before {
+    return Err(AppError::InvalidInput(format!("Unknown sync key '{}'", key_id)));
+  }
+  Ok(state.save()?)
+}
+
+// write the current state sealed for `key_id`, for moving it to the device by file
+#[tauri::command]
+pub fn export_sync_bundle(key_id: String, path: String, app: AppHandle) -> Result<SyncSummary, AppError> {
+  let state = app.state::<Mutex<SyncState>>();
+  let mut state = state.locked();
+  let key = state.key(&key_id)?;
+  let empty = SyncBundle { device: String::new(), entries: Vec::new() };
+  let (bundle, _) = merge(&app, &mut state, &empty)?;
+  state.save()?;
+  let sealed = seal(&key, &key_id, &bundle)?;
+  let json = serde_json::to_string(&sealed).map_err(|e| format!("failed to serialize bundle: {}", e))?;
+  fs::write(&path, json).map_err(|e| AppError::io("write", &path, e))?;
+  Ok(SyncSummary { applied: 0, sent: bundle.entries.len() })
+}
+
+// merge a sealed bundle file exported by the companion app
+#[tauri::command]
+pub fn import_sync_bundle(path: String, app: AppHandle) -> Result<SyncSummary, AppError> {
+  let json = fs::read(&path).map_err(|e| AppError::io("read", &path, e))?;
+  let sealed: SealedBundle = serde_json::from_slice(&json).map_err(|e| format!("invalid bundle {}: {}", path, e))?;
+  let state = app.state::<Mutex<SyncState>>();
+  let mut state = state.locked();
+  let key = state.key(&sealed.key_id)?;
+  let remote = open(&key, &sealed)?;
+  let (merged, applied) = merge(&app, &mut state, &remote)?;
+  state.save()?;
+  Ok(SyncSummary { applied, sent: merged.entries.len() })
+}
@@ -0,0 +1,184 @@
+// src-tauri/src/system_prompt.rs
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::error::{AppError, LockExt};
+use crate::session::{SessionStore, Turn};
+use crate::store;
+use crate::ModelManager;
+
+// (id, name, prompt) of the presets shipped with the app; a saved preset with the same id replaces one
+const BUILTIN_PRESETS: &[(&str, &str, &str)] = &[
+  (
+    "translator",
+    "Translator",
+    "You are a professional translator. Translate what the user writes faithfully, keeping its meaning, tone and \
+     formatting. Reply with the translation only.",
+  ),
+  (
+    "proofreader",
+    "Proofreader",
+    "You are a careful proofreader. Correct spelling, grammar and punctuation in the user's text without changing its \
+     meaning or language, then list the changes you made.",
+  ),
+  (
+    "tutor",
+    "Language tutor",
+    "You are a patient language tutor. Answer in the language the user is learning, keep sentences simple, and gently \
+     point out mistakes in what the user writes with a short explanation.",
+  ),
+  (
+    "localizer",
+    "Localizer",
+    "You localize software strings. Keep placeholders, markup and keyboard shortcuts exactly as they are and match the \
+     conventions of the target locale.",
+  ),
+];
+
+// A named system prompt to pick from
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct SystemPromptPreset {
+  pub id: String,
+  pub name: String,
+  pub prompt: String,
+  #[serde(default, skip_deserializing)]
+  pub builtin: bool,
+}
+
+// What set_system_prompt changed
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct AppliedSystemPrompt {
+  pub prompt: Option<String>,
+  pub session_id: Option<String>,
+  pub model_id: Option<String>,
+}
+
+// ids come from names: "My Reviewer" -> "my-reviewer"
+fn preset_id(name: &str) -> String {
+  let id: String = name.trim().to_lowercase().chars().map(|c| if c.is_alphanumeric() { c } else { '-' }).collect();
+  id.split('-').filter(|p| !p.is_empty()).collect::<Vec<_>>().join("-")
+}
+
+fn builtin_presets() -> Vec<SystemPromptPreset> {
+  BUILTIN_PRESETS
+    .iter()
+    .map(|(id, name, prompt)| SystemPromptPreset { id: id.to_string(), name: name.to_string(), prompt: prompt.to_string(), builtin: true })
+    .collect()
+}
+
+// the turn a system prompt and any further instructions (a domain pack's, say) are sent as
+pub fn system_turn(parts: &[Option<String>]) -> Option<Turn> {
+  let parts: Vec<&str> = parts.iter().flatten().map(|p| p.trim()).filter(|p| !p.is_empty()).collect();
+  (!parts.is_empty()).then(|| Turn::new("system", &parts.join("\n\n")))
+}
+
+// Presets the user saved, persisted in ./data/system_prompts.json (managed by Tauri)
+pub struct SystemPrompts {
+  path: PathBuf,
+  saved: Vec<SystemPromptPreset>,
+}
+
+impl SystemPrompts {
+  pub fn load() -> Self {
+    let path = store::data_file("system_prompts.json");
+    let saved = store::load_json(&path);
+    Self { path, saved }
+  }
+
+  // built-in and saved presets sorted by name
+  pub fn list(&self) -> Vec<SystemPromptPreset> {
+    let mut presets: Vec<SystemPromptPreset> =
+      builtin_presets().into_iter().filter(|b| !self.saved.iter().any(|s| s.id == b.id)).collect();
+    presets.extend(self.saved.iter().cloned());
+    presets.sort_by_key(|p| p.name.to_lowercase());
+    presets
+  }
+
+  pub fn get(&self, id: &str) -> Result<SystemPromptPreset, String> {
+    self.list().into_iter().find(|p| p.id == id).ok_or(format!("System prompt preset '{}' not found", id))
+  }
+
+  pub fn save(&mut self, name: &str, prompt: &str) -> Result<SystemPromptPreset, String> {
+    let id = preset_id(name);
+    if id.is_empty() {
+      return Err("The preset needs a name".into());
+    }
+    if prompt.trim().is_empty() {
+      return Err("The system prompt is empty".into());
+    }
+    let preset = SystemPromptPreset { id, name: name.trim().to_string(), prompt: prompt.trim().to_string(), builtin: false };
+    self.saved.retain(|p| p.id != preset.id);
+    self.saved.push(preset.clone());
+    store::save_json(&self.path, &self.saved)?;
+    Ok(preset)
+  }
+
+  // deleting a saved copy of a built-in preset brings the shipped one back
+  pub fn delete(&mut self, id: &str) -> Result<(), String> {
+    let before = self.saved.len();
+    self.saved.retain(|p| p.id != id);
+    if self.saved.len() == before {
+      return Err(match BUILTIN_PRESETS.iter().any(|(b, _, _)| *b == id) {
+        true => format!("'{}' is a built-in preset", id),
+        false => format!("System prompt preset '{}' not found", id),
+      });
+    }
+    store::save_json(&self.path, &self.saved)
+  }
+}
+
+// ------------------ Tauri commands ------------------
+
+#[tauri::command]
+pub fn list_system_prompts(presets: tauri::State<'_, Mutex<SystemPrompts>>) -> Vec<SystemPromptPreset> {
+  presets.locked().list()
+}
+
+// save a preset under an id made from its name, replacing one with that id
+#[tauri::command]
+pub fn save_system_prompt(name: String, prompt: String, presets: tauri::State<'_, Mutex<SystemPrompts>>) -> Result<SystemPromptPreset, AppError> {
+  Ok(presets.locked().save(&name, &prompt)?)
+}
+
+#[tauri::command]
+pub fn delete_system_prompt(preset_id: String, presets: tauri::State<'_, Mutex<SystemPrompts>>) -> Result<(), AppError> {
+  Ok(presets.locked().delete(&preset_id)?)
+}
+
+// set the system prompt of a session and/or a model, as text or a preset's (neither clears it).
+// It goes in front of every request as a system turn in the model's chat format; a session's
+// prompt takes the place of its model's
+#[tauri::command]
+pub fn set_system_prompt(
+  session_id: Option<String>,
+  model_id: Option<String>,
+  prompt: Option<String>,
+  preset_id: Option<String>,
+  presets: tauri::State<'_, Mutex<SystemPrompts>>,
+  sessions: tauri::State<'_, Mutex<SessionStore>>,
+  state: tauri::State<'_, Mutex<ModelManager>>
+) -> Result<AppliedSystemPrompt, AppError> {
+  if session_id.is_none() && model_id.is_none() {
+    return Err(AppError::InvalidInput("Pass a session_id or a model_id to set the system prompt of".into()));
+  }
+  let prompt = match (prompt, preset_id) {
+    (Some(_), Some(_)) => return Err(AppError::InvalidInput("Pass either a prompt or a preset_id, not both".into())),
+    (Some(p), None) => Some(p.trim().to_string()).filter(|p| !p.is_empty()),
+    (None, Some(id)) => Some(presets.locked().get(&id)?.prompt),
+    (None, None) => None,
+  };
+  if let Some(id) = &model_id {
+    let mut mgr = state.locked();
+    if !mgr.models.contains_key(id) {
+      return Err(AppError::not_found("Model", id));
+    }
+    mgr.configs.update(id, |c| c.system_prompt = prompt.clone())?;
+  }
+  if let Some(id) = &session_id {
+    sessions.locked().update(id, |session| {
+      session.system_prompt = prompt.clone();
+      Ok(())
+    })?;
+  }
+  Ok(AppliedSystemPrompt { prompt, session_id, model_id })
+}
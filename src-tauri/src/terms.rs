@@ -0,0 +1,346 @@
+// src-tauri/src/terms.rs
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::chunk;
+use crate::document;
+use crate::embed;
+use crate::error::{AppError, LockExt};
+use crate::glossary::Glossary;
+use crate::jobs::JobStore;
+use crate::translate::DOCUMENT_EXTENSIONS;
+use crate::ModelManager;
+
+// longest candidate, in words
+const MAX_TERM_WORDS: usize = 4;
+// short words in more than this share of sentences are treated as function words ("the", "und", "les", ...)
+const COMMON_WORD_SHARE: f32 = 0.2;
+// longer English function words, which frequency alone can't tell from domain terms
+const STOPWORDS: &[&str] = &[
+  "about", "after", "also", "because", "been", "before", "between", "could", "does", "each", "from", "have", "however", "into",
+  "more", "most", "only", "other", "should", "some", "such", "than", "that", "their", "then", "there", "these", "they", "this",
+  "those", "through", "used", "uses", "using", "very", "were", "what", "when", "where", "which", "while", "will", "with",
+  "would", "your",
+];
+// candidates whose character trigrams overlap this much are variants of one term
+const VARIANT_SIMILARITY: f32 = 0.75;
+// candidates whose embeddings are this close are used the same way ("neural net", "neural network")
+const EMBEDDING_SIMILARITY: f32 = 0.9;
+// candidates embedded per term asked for; the rest are only grouped by spelling
+const EMBEDDED_PER_TERM: usize = 4;
+
+// A possible glossary term found in the documents
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct TermCandidate {
+  // most frequent spelling of the term
+  pub term: String,
+  pub frequency: usize,
+  // number of documents it occurs in
+  pub documents: usize,
+  // other spellings and inflections grouped under it ("neural networks" for "neural network"), and
+  // with embeddings terms used the same way
+  pub variants: Vec<String>,
+  // a sentence showing it in use
+  pub context: String,
+  pub score: f32,
+  pub in_glossary: bool,
+}
+
+#[derive(Default)]
+struct Occurrences {
+  frequency: usize,
+  documents: HashSet<usize>,
+  // surface form -> count
+  forms: HashMap<String, usize>,
+  context: String,
+}
+
+// A term with the variants grouped under it so far
+struct Cluster<'a> {
+  // trigrams and embedding of the head, which candidates are compared with
+  shape: HashSet<String>,
+  vector: Option<&'a Vec<f32>>,
+  head: TermCandidate,
+  documents: HashSet<usize>,
+}
+
+// the documents under `path`: the file itself, or every text document in the folder (recursively)
+fn documents(path: &Path) -> Result<Vec<PathBuf>, String> {
+  if path.is_file() {
+    return Ok(vec![path.to_path_buf()]);
+  }
+  if !path.is_dir() {
+    return Err(format!("{} does not exist", path.to_string_lossy()));
+  }
+  let mut files = Vec::new();
+  let mut stack = vec![path.to_path_buf()];
+  while let Some(dir) = stack.pop() {
+    for entry in fs::read_dir(&dir).into_iter().flatten().flatten() {
+      let p = entry.path();
+      let ext = p.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+      if p.is_dir() {
+        stack.push(p);
+      } else if DOCUMENT_EXTENSIONS.contains(&ext.as_str()) {
+        files.push(p);
+      }
+    }
+  }
+  files.sort();
+  Ok(files)
+}
+
+// words of a sentence, keeping inner hyphens and apostrophes ("state-of-the-art", "l'état")
+fn words(sentence: &str) -> Vec<&str> {
+  sentence
+    .split(|c: char| !(c.is_alphanumeric() || c == '-' || c == '\'' || c == '’'))
+    .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()))
+    .filter(|w| !w.is_empty())
+    .collect()
+}
+
+fn trigrams(term: &str) -> HashSet<String> {
+  let chars: Vec<char> = format!("  {} ", term.to_lowercase()).chars().collect();
+  chars.windows(3).map(|w| w.iter().collect()).collect()
+}
+
+// Dice coefficient of character trigrams; catches plurals, case and hyphenation variants
+fn similarity(a: &HashSet<String>, b: &HashSet<String>) -> f32 {
+  if a.is_empty() || b.is_empty() {
+    return 0.0;
+  }
+  2.0 * a.intersection(b).count() as f32 / (a.len() + b.len()) as f32
+}
+
+// What extract_terms found
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct TermExtraction {
+  pub terms: Vec<TermCandidate>,
+  // why the terms were grouped by spelling only (no model, or it can't embed); None when
+  // embeddings were used
+  pub embedding_error: Option<String>,
+}
+
+// mine candidate terms: word n-grams that recur, don't start or end with a function word and
+// aren't just a fragment of a longer recurring term, scored by frequency, spread and length.
+// Candidates are then clustered so each term is listed once with its variants: near-identical
+// spellings, and candidates whose vectors from `embed` are close. `embed` gets the best scoring
+// candidates and returns one vector each, or None to group by spelling only
+pub fn extract(texts: &[String], limit: usize, embed: impl FnOnce(&[String]) -> Option<Vec<Vec<f32>>>) -> Vec<TermCandidate> {
+  let sentences: Vec<(usize, String)> =
+    texts.iter().enumerate().flat_map(|(doc, text)| chunk::sentences(text).into_iter().map(move |s| (doc, s.trim().to_string()))).collect();
+
+  // how many sentences each word appears in, to find the corpus' own function words
+  let mut sentence_freq: HashMap<String, usize> = HashMap::new();
+  for (_, sentence) in &sentences {
+    let unique: HashSet<String> = words(sentence).iter().map(|w| w.to_lowercase()).collect();
+    for w in unique {
+      *sentence_freq.entry(w).or_default() += 1;
+    }
+  }
+  let common_limit = ((sentences.len() as f32 * COMMON_WORD_SHARE) as usize).max(3);
+  let is_function_word = |w: &str| {
+    let lower = w.to_lowercase();
+    let len = lower.chars().count();
+    len < 3
+      || lower.chars().all(|c| c.is_numeric())
+      || STOPWORDS.contains(&lower.as_str())
+      || (len <= 4 && sentence_freq.get(&lower).is_some_and(|n| *n > common_limit))
+  };
+
+  let mut grams: HashMap<String, Occurrences> = HashMap::new();
+  for (doc, sentence) in &sentences {
+    let ws = words(sentence);
+    for start in 0..ws.len() {
+      for len in 1..=MAX_TERM_WORDS.min(ws.len() - start) {
+        let span = &ws[start..start + len];
+        if is_function_word(span[0]) || is_function_word(span[len - 1]) {
+          continue;
+        }
+        let form = span.join(" ");
+        let occ = grams.entry(form.to_lowercase()).or_default();
+        occ.frequency += 1;
+        occ.documents.insert(*doc);
+        *occ.forms.entry(form).or_default() += 1;
+        if occ.context.is_empty() {
+          occ.context = sentence.clone();
+        }
+      }
+    }
+  }
+
+  // a fragment that (almost) only occurs inside one longer term adds nothing on its own
+  let recurring: Vec<(&String, &Occurrences)> = grams.iter().filter(|(_, o)| o.frequency >= 2).collect();
+  let mut inside_longer: HashMap<String, usize> = HashMap::new();
+  for (key, occ) in &recurring {
+    let ws: Vec<&str> = key.split(' ').collect();
+    for start in 0..ws.len() {
+      for end in start + 1..=ws.len() {
+        if end - start < ws.len() {
+          let best = inside_longer.entry(ws[start..end].join(" ")).or_default();
+          *best = (*best).max(occ.frequency);
+        }
+      }
+    }
+  }
+  let mut scored: Vec<(String, &Occurrences, f32)> = recurring
+    .iter()
+    .filter(|(key, occ)| inside_longer.get(key.as_str()).is_none_or(|n| n * 10 < occ.frequency * 8))
+    .map(|(key, occ)| {
+      let n_words = key.split(' ').count() as f32;
+      let score = occ.frequency as f32 * (1.0 + n_words).ln() * (1.0 + occ.documents.len() as f32).ln().max(0.5);
+      (key.to_string(), *occ, score)
+    })
+    .collect();
+  scored.sort_by(|a, b| b.2.total_cmp(&a.2).then_with(|| a.0.cmp(&b.0)));
+  let surfaces: Vec<String> = scored
+    .iter()
+    .map(|(key, occ, _)| occ.forms.iter().max_by_key(|(f, n)| (**n, std::cmp::Reverse(*f))).map(|(f, _)| f.clone()).unwrap_or(key.clone()))
+    .collect();
+  let pool = surfaces.len().min(limit * EMBEDDED_PER_TERM);
+  let vectors = embed(&surfaces[..pool]).filter(|v| v.len() == pool).unwrap_or_default();
+
+  // greedy clustering: each candidate joins the best-scoring term it is similar to
+  let mut clusters: Vec<Cluster> = Vec::new();
+  for (i, ((key, occ, score), surface)) in scored.into_iter().zip(surfaces).enumerate() {
+    let shape = trigrams(&key);
+    let vector = vectors.get(i);
+    let close = |c: &Cluster| {
+      similarity(&c.shape, &shape) >= VARIANT_SIMILARITY
+        || c.vector.zip(vector).is_some_and(|(a, b)| embed::similarity(a, b) >= EMBEDDING_SIMILARITY)
+    };
+    if let Some(cluster) = clusters.iter_mut().find(|c| close(c)) {
+      let head = &mut cluster.head;
+      head.frequency += occ.frequency;
+      head.score += score;
+      cluster.documents.extend(&occ.documents);
+      head.documents = cluster.documents.len();
+      head.variants.push(surface);
+      continue;
+    }
+    if clusters.len() >= limit * 2 {
+      continue;
+    }
+    let candidate = TermCandidate {
+      term: surface,
+      frequency: occ.frequency,
+      documents: occ.documents.len(),
+      variants: Vec::new(),
+      context: occ.context.clone(),
+      score,
+      in_glossary: false,
+    };
+    clusters.push(Cluster { shape, vector, head: candidate, documents: occ.documents.clone() });
+  }
+
+  let mut out: Vec<TermCandidate> = clusters.into_iter().map(|c| c.head).collect();
+  out.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.term.cmp(&b.term)));
+  out.truncate(limit);
+  out
+}
+
+// ------------------ Tauri commands ------------------
+
+// candidate domain terms for review before adding them to the glossary, from a document or a
+// folder of documents (`path`) and/or the source text of document jobs (`jobs`); terms already in
+// the glossary for `source_lang` are flagged. Terms are clustered with embeddings from `model_id`
+// (else the loaded or default model) when it can embed, else by spelling only
+#[tauri::command(async)]
+#[allow(clippy::too_many_arguments)]
+pub fn extract_terms(
+  path: Option<String>,
+  jobs: Option<Vec<u64>>,
+  source_lang: Option<String>,
+  limit: Option<usize>,
+  model_id: Option<String>,
+  glossary: tauri::State<'_, Mutex<Glossary>>,
+  job_store: tauri::State<'_, Mutex<JobStore>>,
+  models: tauri::State<'_, Mutex<ModelManager>>
+) -> Result<TermExtraction, AppError> {
+  if path.is_none() && jobs.as_ref().is_none_or(|j| j.is_empty()) {
+    return Err("Give a path or document jobs to extract terms from".to_string().into());
+  }
+  let mut texts = Vec::new();
+  if let Some(path) = &path {
+    let files = documents(Path::new(path))?;
+    texts.extend(files.iter().filter_map(|f| document::text(f).ok()));
+  }
+  {
+    let job_store = job_store.locked();
+    for id in jobs.iter().flatten() {
+      let job = job_store.get(*id)?;
+      texts.push(job.segments.iter().map(|s| s.source.as_str()).collect::<Vec<_>>().join("\n\n"));
+    }
+  }
+  if texts.iter().all(|t| t.trim().is_empty()) {
+    return Err(format!("No text found in {}", path.as_deref().unwrap_or("the jobs")).into());
+  }
+
+  let model = models.locked().model_for_request(model_id.as_deref());
+  let mut embedding_error = None;
+  let mut terms = extract(&texts, limit.unwrap_or(100).max(1), |candidates| {
+    let vectors = model.and_then(|(model, config)| embed::embed(&model.path, &config, candidates));
+    vectors.map_err(|e| embedding_error = Some(e)).ok()
+  });
+  let known: HashSet<String> =
+    glossary.locked().entries(source_lang.as_deref(), None).iter().map(|e| e.source.trim().to_lowercase()).collect();
+  for t in &mut terms {
+    t.in_glossary = known.contains(&t.term.to_lowercase()) || t.variants.iter().any(|v| known.contains(&v.to_lowercase()));
+  }
+  Ok(TermExtraction { terms, embedding_error })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const TEXT: &str = "The learning rate controls training. A small learning rate is slow. The step size controls \
+    training too. A large step size diverges.";
+
+  // "learning rate" and "step size" share a vector, every other candidate gets one of its own
+  fn synonyms(candidates: &[String]) -> Option<Vec<Vec<f32>>> {
+    let n = candidates.len() + 1;
+    let one_hot = |i: usize| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect();
+    Some(
+      candidates
+        .iter()
+        .enumerate()
+        .map(|(i, c)| if ["learning rate", "step size"].contains(&c.to_lowercase().as_str()) { one_hot(0) } else { one_hot(i + 1) })
+        .collect(),
+    )
+  }
+
+  fn find<'a>(terms: &'a [TermCandidate], term: &str) -> Option<&'a TermCandidate> {
+    terms.iter().find(|t| t.term.eq_ignore_ascii_case(term) || t.variants.iter().any(|v| v.eq_ignore_ascii_case(term)))
+  }
+
+  #[test]
+  fn finds_recurring_terms() {
+    let terms = extract(&[TEXT.to_string()], 10, |_| None);
+    let rate = find(&terms, "learning rate").expect("learning rate");
+    assert_eq!((rate.frequency, rate.documents), (2, 1));
+    assert!(find(&terms, "learning").is_none(), "{:?}", terms);
+  }
+
+  #[test]
+  fn groups_spelling_variants() {
+    let texts = ["The neural network learns. Neural networks generalize.".to_string(), "Two neural networks. A neural network overfits.".to_string()];
+    let terms = extract(&texts, 10, |_| None);
+    let head = find(&terms, "neural networks").expect("neural networks");
+    assert_eq!((head.frequency, head.documents), (4, 2), "{:?}", terms);
+    assert!(find(&terms, "neural network").is_some_and(|t| t.term == head.term), "{:?}", terms);
+  }
+
+  #[test]
+  fn clusters_synonyms_only_with_embeddings() {
+    let terms = extract(&[TEXT.to_string()], 10, |_| None);
+    assert_ne!(find(&terms, "learning rate").unwrap().term, find(&terms, "step size").unwrap().term);
+    let terms = extract(&[TEXT.to_string()], 10, synonyms);
+    let head = find(&terms, "learning rate").unwrap();
+    assert_eq!(head.term, find(&terms, "step size").unwrap().term);
+    assert_eq!(head.frequency, 4);
+    // unrelated candidates stay apart
+    assert!(find(&terms, "controls training").is_some_and(|t| t.variants.is_empty()), "{:?}", terms);
+  }
+}
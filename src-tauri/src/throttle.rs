@@ -0,0 +1,247 @@
+// src-tauri/src/throttle.rs
+use std::sync::Mutex;
+use std::{thread, time::Duration};
+
+use sysinfo::{Components, System};
+use tauri::{AppHandle, Manager};
+
+use crate::error::{AppError, LockExt};
+use crate::events;
+
+// llama.cpp default logical batch size
+const DEFAULT_BATCH: u32 = 2048;
+
+// Thresholds and reactions, editable from the frontend
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct ThrottlePolicy {
+  pub enabled: bool,
+  pub memory_warn_pct: f32,
+  pub memory_critical_pct: f32,
+  pub temp_warn_c: f32,
+  pub temp_critical_c: f32,
+  // halve the runtime batch size on a warning and quarter it when critical (applies to newly
+  // spawned processes)
+  pub auto_reduce_batch: bool,
+  // refuse new prompts while pressure is critical
+  pub pause_on_critical: bool,
+  pub interval_ms: u64,
+}
+
+impl Default for ThrottlePolicy {
+  fn default() -> Self {
+    Self {
+      enabled: true,
+      memory_warn_pct: 85.0,
+      memory_critical_pct: 95.0,
+      temp_warn_c: 85.0,
+      temp_critical_c: 95.0,
+      auto_reduce_batch: true,
+      pause_on_critical: false,
+      interval_ms: 2000,
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PressureLevel {
+  Normal,
+  Warning,
+  Critical,
+}
+
+// runtime batch size at a pressure level; from the level alone, so easing from critical to warning
+// grows the batch again
+fn batch_for(level: PressureLevel) -> u32 {
+  match level {
+    PressureLevel::Normal => DEFAULT_BATCH,
+    PressureLevel::Warning => DEFAULT_BATCH / 2,
+    PressureLevel::Critical => DEFAULT_BATCH / 4,
+  }
+}
+
+// Payload of the "system-pressure" event and the get_system_pressure command
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct PressureReport {
+  pub level: PressureLevel,
+  pub memory_used_pct: f32,
+  // hottest sensor, when the platform exposes any
+  pub max_temp_c: Option<f32>,
+  pub batch_size: u32,
+  pub paused: bool,
+}
+
+struct ThrottleState {
+  policy: ThrottlePolicy,
+  level: PressureLevel,
+  memory_used_pct: f32,
+  max_temp_c: Option<f32>,
+  batch_size: u32,
+}
+
+// Shared throttle state (managed by Tauri)
+pub struct Throttle {
+  inner: Mutex<ThrottleState>,
+}
+
+impl Throttle {
+  pub fn new() -> Self {
+    Self {
+      inner: Mutex::new(ThrottleState {
+        policy: ThrottlePolicy::default(),
+        level: PressureLevel::Normal,
+        memory_used_pct: 0.0,
+        max_temp_c: None,
+        batch_size: DEFAULT_BATCH,
+      }),
+    }
+  }
+
+  pub fn policy(&self) -> ThrottlePolicy {
+    self.inner.locked().policy.clone()
+  }
+
+  pub fn set_policy(&self, policy: ThrottlePolicy) -> Result<(), String> {
+    if policy.memory_warn_pct > policy.memory_critical_pct || policy.temp_warn_c > policy.temp_critical_c {
+      return Err("warning thresholds must not exceed critical thresholds".into());
+    }
+    let mut st = self.inner.locked();
+    st.batch_size = if policy.auto_reduce_batch { batch_for(st.level) } else { DEFAULT_BATCH };
+    st.policy = policy;
+    Ok(())
+  }
+
+  pub fn report(&self) -> PressureReport {
+    let st = self.inner.locked();
+    Self::report_locked(&st)
+  }
+
+  // Some(batch) when the batch size has been reduced and must be passed to the runtime
+  pub fn batch_size(&self) -> Option<u32> {
+    let st = self.inner.locked();
+    (st.batch_size < DEFAULT_BATCH).then_some(st.batch_size)
+  }
+
+  pub fn is_paused(&self) -> bool {
+    let st = self.inner.locked();
+    Self::paused_locked(&st)
+  }
+
+  fn paused_locked(st: &ThrottleState) -> bool {
+    st.policy.enabled && st.policy.pause_on_critical && st.level == PressureLevel::Critical
+  }
+
+  fn report_locked(st: &ThrottleState) -> PressureReport {
+    PressureReport {
+      level: st.level,
+      memory_used_pct: st.memory_used_pct,
+      max_temp_c: st.max_temp_c,
+      batch_size: st.batch_size,
+      paused: Self::paused_locked(st),
+    }
+  }
+
+  // record a new sample; returns a report when the pressure level changed
+  fn sample(&self, memory_used_pct: f32, max_temp_c: Option<f32>) -> Option<PressureReport> {
+    let mut st = self.inner.locked();
+    st.memory_used_pct = memory_used_pct;
+    st.max_temp_c = max_temp_c;
+    if !st.policy.enabled {
+      return None;
+    }
+
+    let p = &st.policy;
+    let temp = max_temp_c.unwrap_or(0.0);
+    let level = if memory_used_pct >= p.memory_critical_pct || temp >= p.temp_critical_c {
+      PressureLevel::Critical
+    } else if memory_used_pct >= p.memory_warn_pct || temp >= p.temp_warn_c {
+      PressureLevel::Warning
+    } else {
+      PressureLevel::Normal
+    };
+
+    if level == st.level {
+      return None;
+    }
+    st.level = level;
+    if st.policy.auto_reduce_batch {
+      st.batch_size = batch_for(level);
+    }
+    Some(Self::report_locked(&st))
+  }
+}
+
+// background thread sampling memory/temperatures and emitting "system-pressure" on level changes
+pub fn start_monitor(app: AppHandle) {
+  thread::spawn(move || {
+    let mut sys = System::new();
+    let mut components = Components::new_with_refreshed_list();
+    loop {
+      let throttle = app.state::<Throttle>();
+      let interval = throttle.policy().interval_ms.max(250);
+
+      sys.refresh_memory();
+      components.refresh(false);
+      let total = sys.total_memory();
+      let used_pct = if total > 0 {
+        (total - sys.available_memory()) as f32 / total as f32 * 100.0
+      } else {
+        0.0
+      };
+      let max_temp = components.iter().filter_map(|c| c.temperature()).fold(None, |acc: Option<f32>, t| {
+        Some(acc.map_or(t, |a| a.max(t)))
+      });
+
+      if let Some(report) = throttle.sample(used_pct, max_temp) {
+        events::emit(&app, "system-pressure", report);
+      }
+      thread::sleep(Duration::from_millis(interval));
+    }
+  });
+}
+
+// ------------------ Tauri commands ------------------
+
+#[tauri::command]
+pub fn get_system_pressure(throttle: tauri::State<'_, Throttle>) -> PressureReport {
+  throttle.report()
+}
+
+#[tauri::command]
+pub fn get_throttle_policy(throttle: tauri::State<'_, Throttle>) -> ThrottlePolicy {
+  throttle.policy()
+}
+
+#[tauri::command]
+pub fn set_throttle_policy(policy: ThrottlePolicy, throttle: tauri::State<'_, Throttle>) -> Result<(), AppError> {
+  Ok(throttle.set_policy(policy)?)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn batch_size_follows_the_level() {
+    let throttle = Throttle::new();
+    let batch = |memory: f32| throttle.sample(memory, None).map(|r| r.batch_size);
+    assert_eq!(batch(90.0), Some(DEFAULT_BATCH / 2));
+    assert_eq!(batch(97.0), Some(DEFAULT_BATCH / 4));
+    // easing to a warning grows the batch again, and going back and forth doesn't ratchet it down
+    for _ in 0..5 {
+      assert_eq!(batch(90.0), Some(DEFAULT_BATCH / 2));
+      assert_eq!(batch(97.0), Some(DEFAULT_BATCH / 4));
+    }
+    assert_eq!(batch(50.0), Some(DEFAULT_BATCH));
+    assert_eq!(throttle.batch_size(), None);
+  }
+
+  #[test]
+  fn batch_size_stays_without_auto_reduce() {
+    let throttle = Throttle::new();
+    throttle.set_policy(ThrottlePolicy { auto_reduce_batch: false, ..ThrottlePolicy::default() }).unwrap();
+    assert_eq!(throttle.sample(97.0, None).map(|r| r.batch_size), Some(DEFAULT_BATCH));
+    throttle.set_policy(ThrottlePolicy::default()).unwrap();
+    assert_eq!(throttle.batch_size(), Some(DEFAULT_BATCH / 4));
+  }
+}
@@ -0,0 +1,165 @@
+// src-tauri/src/tm.rs
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::{AppError, LockExt};
+use crate::store;
+
+// A source/target pair remembered for reuse in later translations
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct TmEntry {
+  pub id: u64,
+  pub source_lang: String,
+  pub target_lang: String,
+  pub source: String,
+  pub target: String,
+  // "accepted" (human-approved), "imported" or "aligned" (from bilingual documents)
+  pub origin: String,
+  // unix seconds of the last update
+  pub updated: i64,
+}
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct TmFile {
+  next_id: u64,
+  entries: Vec<TmEntry>,
+}
+
+// Translation memory persisted in ./data/tm.json (managed by Tauri)
+pub struct TranslationMemory {
+  path: PathBuf,
+  file: TmFile,
+}
+
+// origin of entries a person approved
+const ACCEPTED: &str = "accepted";
+
+// whitespace-insensitive key used for exact matches
+fn normalize(text: &str) -> String {
+  text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+impl TranslationMemory {
+  pub fn load() -> Self {
+    let path = store::data_file("tm.json");
+    let file = store::load_json(&path);
+    Self { path, file }
+  }
+
+  // insert or replace the translation of `source` for this language pair
+  pub fn upsert(&mut self, source_lang: &str, target_lang: &str, source: &str, target: &str, origin: &str) -> Result<TmEntry, String> {
+    let entry = self.put(source_lang, target_lang, source, target, origin);
+    store::save_json(&self.path, &self.file)?;
+    Ok(entry)
+  }
+
+  // upsert many (source, target) pairs with a single save; returns how many were stored. Entries a
+  // person accepted are never replaced by imported ones
+  pub fn import(&mut self, source_lang: &str, target_lang: &str, pairs: &[(String, String)], origin: &str) -> Result<usize, String> {
+    let mut stored = 0;
+    for (source, target) in pairs {
+      let key = normalize(source);
+      let accepted = self
+        .file
+        .entries
+        .iter()
+        .any(|e| e.origin == ACCEPTED && e.source_lang == source_lang && e.target_lang == target_lang && normalize(&e.source) == key);
+      if accepted && origin != ACCEPTED {
+        continue;
+      }
+      self.put(source_lang, target_lang, source, target, origin);
+      stored += 1;
+    }
+    store::save_json(&self.path, &self.file)?;
+    Ok(stored)
+  }
+
+  fn put(&mut self, source_lang: &str, target_lang: &str, source: &str, target: &str, origin: &str) -> TmEntry {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0);
+    let key = normalize(source);
+    let existing = self
+      .file
+      .entries
+      .iter_mut()
+      .find(|e| e.source_lang == source_lang && e.target_lang == target_lang && normalize(&e.source) == key);
+    match existing {
+      Some(e) => {
+        e.target = target.to_string();
+        e.origin = origin.to_string();
+        e.updated = now;
+        e.clone()
+      }
+      None => {
+        self.file.next_id += 1;
+        let e = TmEntry {
+          id: self.file.next_id,
+          source_lang: source_lang.to_string(),
+          target_lang: target_lang.to_string(),
+          source: source.to_string(),
+          target: target.to_string(),
+          origin: origin.to_string(),
+          updated: now,
+        };
+        self.file.entries.push(e.clone());
+        e
+      }
+    }
+  }
+
+  // exact (whitespace-normalized) match for a source segment
+  pub fn lookup(&self, source_lang: &str, target_lang: &str, source: &str) -> Option<TmEntry> {
+    let key = normalize(source);
+    self
+      .file
+      .entries
+      .iter()
+      .find(|e| e.source_lang == source_lang && e.target_lang == target_lang && normalize(&e.source) == key)
+      .cloned()
+  }
+
+  pub fn entries(&self, source_lang: Option<&str>, target_lang: Option<&str>) -> Vec<TmEntry> {
+    self
+      .file
+      .entries
+      .iter()
+      .filter(|e| source_lang.is_none_or(|l| e.source_lang == l) && target_lang.is_none_or(|l| e.target_lang == l))
+      .cloned()
+      .collect()
+  }
+
+  pub fn remove(&mut self, id: u64) -> Result<(), String> {
+    let before = self.file.entries.len();
+    self.file.entries.retain(|e| e.id != id);
+    if self.file.entries.len() == before {
+      return Err(format!("TM entry {} not found", id));
+    }
+    store::save_json(&self.path, &self.file)
+  }
+}
+
+// ------------------ Tauri commands ------------------
+
+#[tauri::command]
+pub fn list_tm_entries(
+  source_lang: Option<String>,
+  target_lang: Option<String>,
+  tm: tauri::State<'_, Mutex<TranslationMemory>>
+) -> Vec<TmEntry> {
+  tm.locked().entries(source_lang.as_deref(), target_lang.as_deref())
+}
+
+#[tauri::command]
+pub fn lookup_tm(
+  source_lang: String,
+  target_lang: String,
+  source: String,
+  tm: tauri::State<'_, Mutex<TranslationMemory>>
+) -> Option<TmEntry> {
+  tm.locked().lookup(&source_lang, &target_lang, &source)
+}
+
+#[tauri::command]
+pub fn remove_tm_entry(id: u64, tm: tauri::State<'_, Mutex<TranslationMemory>>) -> Result<(), AppError> {
+  Ok(tm.locked().remove(id)?)
+}
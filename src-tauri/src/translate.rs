@@ -0,0 +1,748 @@
+// src-tauri/src/translate.rs
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use crate::bidi::{self, BidiInfo, BidiSettings};
+use crate::confidence::{self, ConfidenceReport};
+use crate::convert::Rates;
+use crate::dispatch::{self, Adjustment, RequestHints};
+use crate::document::DocumentFormat;
+use crate::domain::{self, DomainTerm};
+use crate::engine::{self, Candidate, Generation};
+use crate::error::{AppError, LockExt};
+use crate::filter;
+use crate::glossary::Glossary;
+use crate::lang;
+use crate::langguard::{self, GuardMode, LanguageCheck};
+use crate::jobs;
+use crate::localize::{self, LocalizeChange, LocalizeConfig, LocalizeSettings};
+use crate::model_config::ModelConfig;
+use crate::preload;
+use crate::readable::{self, ReadingOptions};
+use crate::store;
+use crate::ModelManager;
+
+// "pt-BR" -> "Portuguese (pt-BR)", so regional variants reach the model
+fn language_label(code: &str) -> String {
+  let name = lang::language_name(code);
+  if name == code { name } else { format!("{} ({})", name, code) }
+}
+
+fn translation_prompt(text: &str, source_lang: &str, target_lang: &str) -> String {
+  // placeholders stand in for inline code/URLs (see codeaware)
+  let keep = if text.contains('⟦') { " Keep every ⟦n⟧ marker exactly as it is." } else { "" };
+  // "auto" leaves the source language to the model
+  let from = if source_lang == "auto" { String::new() } else { format!(" from {}", language_label(source_lang)) };
+  format!(
+    "Translate the following text{} to {}. Reply with the translation only.{}\n\nText:\n{}\n\nTranslation:",
+    from,
+    language_label(target_lang),
+    keep,
+    text
+  )
+}
+
+// documents the file pipeline accepts (see DocumentFormat)
+pub const DOCUMENT_EXTENSIONS: &[&str] = &["txt", "md", "markdown", "srt", "docx"];
+
+// translate one piece of text with the given model
+pub fn translate_text(model_path: &str, config: &ModelConfig, text: &str, source_lang: &str, target_lang: &str) -> Result<String, String> {
+  translate_with_context(model_path, config, text, source_lang, target_lang, "")
+}
+
+// same, with instructions (e.g. a domain pack's) ahead of the prompt
+fn translate_with_context(
+  model_path: &str,
+  config: &ModelConfig,
+  text: &str,
+  source_lang: &str,
+  target_lang: &str,
+  context: &str,
+) -> Result<String, String> {
+  translate_detailed(model_path, config, text, source_lang, target_lang, context).map(|g| g.text)
+}
+
+// same, keeping the token logprobs of backends that report them
+fn translate_detailed(
+  model_path: &str,
+  config: &ModelConfig,
+  text: &str,
+  source_lang: &str,
+  target_lang: &str,
+  context: &str,
+) -> Result<Generation, String> {
+  let prompt = format!("{}{}", context, translation_prompt(text, source_lang, target_lang));
+  engine::generate_detailed(model_path, config, &prompt, engine::estimate_tokens(text) * 3 + 64)
+}
+
+// ./data/translate.json
+#[derive(serde::Serialize, serde::Deserialize)]
+struct TranslateSettings {
+  // language used as a bridge when no model covers a pair directly
+  pivot_lang: String,
+}
+
+impl Default for TranslateSettings {
+  fn default() -> Self {
+    Self { pivot_lang: "en".into() }
+  }
+}
+
+fn settings_path() -> PathBuf {
+  store::data_file("translate.json")
+}
+
+pub fn pivot_language() -> String {
+  store::load_json::<TranslateSettings>(&settings_path()).pivot_lang
+}
+
+// One model call of a routed translation
+#[derive(Clone, Debug)]
+pub struct Leg {
+  pub model_id: String,
+  pub model_path: String,
+  pub config: ModelConfig,
+  pub source_lang: String,
+  pub target_lang: String,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct RoutedTranslation {
+  pub text: String,
+  // set when the text went through the pivot language
+  pub pivot_lang: Option<String>,
+  // models used, in order
+  pub model_ids: Vec<String>,
+  // set when a length limit was requested
+  pub length: Option<LengthCheck>,
+  // output language check of the final leg, unless the guard is off
+  pub language: Option<LanguageCheck>,
+  // the n best translations when requested, best first (`text` is the first)
+  pub candidates: Vec<Candidate>,
+  // changes made to meet the request's deadline
+  pub adjustments: Vec<Adjustment>,
+  // glossary terms found in the text that the model was told to use
+  pub terms: Vec<DomainTerm>,
+  // per sentence confidence when asked for and the backend reports logprobs. Offsets are into the
+  // model's translation, before localization and reading options
+  pub confidence: Option<ConfidenceReport>,
+  // numbers, dates, currencies and units reformatted for the target locale
+  pub localized: Vec<LocalizeChange>,
+  // base direction and direction runs of `text` for rendering; `mixed` flags text with both
+  // right-to-left and left-to-right runs
+  pub direction: BidiInfo,
+}
+
+// Form of address in the translation, for languages that make the distinction
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Formality {
+  Formal,
+  Informal,
+}
+
+impl Formality {
+  fn instruction(self, target_lang: &str) -> String {
+    let register = match self {
+      Formality::Formal => "a formal register and polite forms of address (e.g. Sie, vous, usted)",
+      Formality::Informal => "an informal register and familiar forms of address (e.g. du, tu, tú)",
+    };
+    format!("Use {} wherever {} distinguishes them.\n\n", register, lang::language_name(target_lang))
+  }
+}
+
+// Per-request translation options
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct TranslateOptions {
+  // fit the result into a length budget
+  pub length: Option<LengthLimit>,
+  // return this many candidate translations with scores
+  pub n_best: Option<usize>,
+  // domain pack (see domain.rs) whose prompt, terms and rules apply to every leg
+  pub domain: Option<String>,
+  // what to do when the output isn't in the target language
+  pub guard: GuardMode,
+  // None leaves the register to the model
+  pub formality: Option<Formality>,
+  // terms to translate as given where they occur (the translate command adds the saved glossary)
+  pub glossary: Vec<DomainTerm>,
+  // score the translation per sentence from token logprobs (see confidence)
+  pub confidence: bool,
+  // number/date/currency passes for the pair; None uses the pair's saved settings (see localize).
+  // Skipped when the source language is "auto" and can't be detected
+  pub localize: Option<LocalizeConfig>,
+  // hyphenation, sentence per line and plain punctuation (see readable)
+  pub reading: Option<ReadingOptions>,
+  // priority and soft deadline ("priority", "deadline_ms" at the top level)
+  #[serde(flatten)]
+  pub hints: RequestHints,
+}
+
+// most candidates one request can ask for
+const MAX_N_BEST: usize = 8;
+
+// Length budget for UI strings and subtitles: a fixed number of characters and/or a
+// tolerance around the source length (20.0 = within ±20% of it)
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct LengthLimit {
+  pub max_chars: Option<usize>,
+  pub tolerance_pct: Option<f32>,
+}
+
+// How a translation measured up against its LengthLimit
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct LengthCheck {
+  pub chars: usize,
+  pub min_chars: Option<usize>,
+  pub max_chars: Option<usize>,
+  // compress/expand rounds needed
+  pub retries: u32,
+  pub within_limit: bool,
+}
+
+// rewrites tried before settling for the closest attempt
+const LENGTH_RETRIES: u32 = 2;
+
+impl LengthLimit {
+  // (min, max) characters allowed for a translation of `source`
+  fn bounds(&self, source: &str) -> (Option<usize>, Option<usize>) {
+    // in percent, so whole lengths stay exact (100 * 1.2 is 120.00001 as f32)
+    let len = source.chars().count() as f64;
+    let pct = self.tolerance_pct.map(|p| p.abs() as f64);
+    let min = pct.map(|p| (len * (100.0 - p) / 100.0).floor().max(0.0) as usize);
+    let max = match (self.max_chars, pct.map(|p| (len * (100.0 + p) / 100.0).ceil() as usize)) {
+      (Some(a), Some(b)) => Some(a.min(b)),
+      (a, b) => a.or(b),
+    };
+    (min.filter(|m| *m > 0 && max.is_none_or(|max| *m <= max)), max)
+  }
+}
+
+// characters outside the bounds, 0 when within
+fn overshoot(chars: usize, (min, max): (Option<usize>, Option<usize>)) -> usize {
+  max.map(|m| chars.saturating_sub(m)).unwrap_or(0) + min.map(|m| m.saturating_sub(chars)).unwrap_or(0)
+}
+
+fn length_prompt(source: &str, translation: &str, target_lang: &str, (min, max): (Option<usize>, Option<usize>)) -> String {
+  let chars = translation.chars().count();
+  let goal = match (min, max) {
+    (_, Some(max)) if chars > max => format!(
+      "It is {} characters long but must fit in at most {} characters. Shorten it: use shorter words and common abbreviations, drop filler, keep the meaning.",
+      chars, max
+    ),
+    (Some(min), _) => format!(
+      "It is {} characters long but should be at least {} characters. Restore anything left out of the original; do not add new content.",
+      chars, min
+    ),
+    _ => String::new(),
+  };
+  format!(
+    "Original:\n{}\n\n{} translation:\n{}\n\n{} Reply with the rewritten {} text only.\n\nRewritten:",
+    source,
+    lang::language_name(target_lang),
+    translation,
+    goal,
+    lang::language_name(target_lang)
+  )
+}
+
+// rewrite `translation` with the last leg's model until it fits `limit`, keeping the closest attempt
+pub fn fit_length(leg: &Leg, source: &str, translation: String, limit: &LengthLimit) -> Result<(String, LengthCheck), String> {
+  let bounds = limit.bounds(source);
+  let mut best = translation;
+  let mut retries = 0;
+  while overshoot(best.chars().count(), bounds) > 0 && retries < LENGTH_RETRIES {
+    retries += 1;
+    let prompt = length_prompt(source, &best, &leg.target_lang, bounds);
+    let attempt = engine::generate(&leg.model_path, &leg.config, &prompt, engine::estimate_tokens(&best) * 2 + 32)?.trim().to_string();
+    if !attempt.is_empty() && overshoot(attempt.chars().count(), bounds) < overshoot(best.chars().count(), bounds) {
+      best = attempt;
+    }
+  }
+  let chars = best.chars().count();
+  let check = LengthCheck { chars, min_chars: bounds.0, max_chars: bounds.1, retries, within_limit: overshoot(chars, bounds) == 0 };
+  Ok((best, check))
+}
+
+fn supports(config: &ModelConfig, lang: &str, any_if_unset: bool) -> bool {
+  if config.languages.is_empty() {
+    return any_if_unset;
+  }
+  lang == "auto" || config.languages.iter().any(|l| lang::same_language(l, lang))
+}
+
+// pick the model(s) for a pair: the requested (or loaded) model, else a model configured for both
+// languages, else two models bridged by the pivot language (e.g. fi->en->th).
+// Models without a language list only count when requested or loaded
+pub fn route<R: Runtime>(manager: &impl Manager<R>, source_lang: &str, target_lang: &str, model_id: Option<&str>) -> Result<Vec<Leg>, String> {
+  let mgr = manager.state::<Mutex<ModelManager>>();
+  let mgr = mgr.locked();
+  let preferred = model_id.map(|m| m.to_string()).or(mgr.default_model());
+  // speech-to-text models can't translate text
+  let mut ids: Vec<&String> = mgr.models.values().filter(|m| !m.speech).map(|m| &m.id).collect();
+  ids.sort_by_key(|id| (Some(*id) != preferred.as_ref(), id.to_string()));
+  let candidates: Vec<(&String, ModelConfig, bool)> =
+    ids.into_iter().map(|id| (id, mgr.configs.get(id), Some(id) == preferred.as_ref())).collect();
+
+  let leg = |source: &str, target: &str| {
+    candidates.iter().find(|(_, config, pref)| supports(config, source, *pref) && supports(config, target, *pref)).map(|(id, config, _)| Leg {
+      model_id: id.to_string(),
+      model_path: mgr.models[*id].path.clone(),
+      config: config.clone(),
+      source_lang: source.to_string(),
+      target_lang: target.to_string(),
+    })
+  };
+  if let Some(direct) = leg(source_lang, target_lang) {
+    return Ok(vec![direct]);
+  }
+  if preferred.is_none() && mgr.models.values().filter(|m| !m.speech).all(|m| mgr.configs.get(&m.id).languages.is_empty()) {
+    return Err("no model available to run prompt".into());
+  }
+  let pivot = pivot_language();
+  if !lang::same_language(source_lang, &pivot) && !lang::same_language(target_lang, &pivot) {
+    if let (Some(first), Some(second)) = (leg(source_lang, &pivot), leg(&pivot, target_lang)) {
+      return Ok(vec![first, second]);
+    }
+  }
+  Err(format!(
+    "No installed model translates {} to {}, directly or via {}",
+    lang::language_name(source_lang),
+    lang::language_name(target_lang),
+    lang::language_name(&pivot)
+  ))
+}
+
+// `n` scored translations from one generation: the backend's own n-best list when it has one,
+// otherwise the model lists and rates alternatives itself in a single JSON answer
+fn n_best(leg: &Leg, text: &str, n: usize, context: &str) -> Result<Vec<Candidate>, String> {
+  let max_tokens = (engine::estimate_tokens(text) * 3 + 32) * n as u32;
+  let prompt = format!("{}{}", context, translation_prompt(text, &leg.source_lang, &leg.target_lang));
+  let mut candidates = match engine::generate_n_best(&leg.model_path, &leg.config, &prompt, max_tokens, n)? {
+    Some(c) => c,
+    None => {
+      let from = if leg.source_lang == "auto" { String::new() } else { format!(" from {}", lang::language_name(&leg.source_lang)) };
+      let prompt = format!(
+        "{}Translate the following text{} to {}. Give {} different translations, best first, and rate how accurate and \
+         natural each one is from 0 to 100.\n\nText:\n{}",
+        context,
+        from,
+        lang::language_name(&leg.target_lang),
+        n,
+        text
+      );
+      let schema = serde_json::json!({
+        "type": "object",
+        "properties": {
+          "candidates": {
+            "type": "array",
+            "maxItems": n,
+            "items": {
+              "type": "object",
+              "properties": { "text": { "type": "string" }, "score": { "type": "number", "minimum": 0, "maximum": 100 } },
+              "required": ["text", "score"]
+            }
+          }
+        },
+        "required": ["candidates"]
+      });
+      let json = engine::generate_json(&leg.model_path, &leg.config, &prompt, max_tokens, &schema)?;
+      let rated: Vec<Candidate> = json.get("candidates").and_then(|c| serde_json::from_value(c.clone()).ok()).unwrap_or_default();
+      rated.into_iter().map(|c| Candidate { score: c.score / 100.0, ..c }).collect()
+    }
+  };
+  candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+  let mut seen = HashSet::new();
+  candidates.retain(|c| !c.text.trim().is_empty() && seen.insert(c.text.trim().to_string()));
+  candidates.truncate(n);
+  if candidates.is_empty() {
+    return Err("model returned no translations".into());
+  }
+  Ok(candidates)
+}
+
+// glossary terms that occur in `text` for a leg's language pair
+fn terms_in<'a>(glossary: &'a [DomainTerm], text: &str, source_lang: &str, target_lang: &str) -> Vec<&'a DomainTerm> {
+  let lower = text.to_lowercase();
+  glossary
+    .iter()
+    .filter(|t| source_lang == "auto" || lang::same_language(&t.source_lang, source_lang))
+    .filter(|t| lang::same_language(&t.target_lang, target_lang))
+    .filter(|t| t.occurs_in(&lower))
+    .collect()
+}
+
+fn glossary_context(terms: &[&DomainTerm]) -> String {
+  if terms.is_empty() {
+    return String::new();
+  }
+  let listed: Vec<String> = terms.iter().map(|t| format!("- {} → {}", t.source, t.target)).collect();
+  format!("Translate these terms as given:\n{}\n\n", listed.join("\n"))
+}
+
+// `text` with directional marks and Arabic presentation forms handled as the bidi settings say,
+// and its direction runs
+fn apply_bidi<R: Runtime>(manager: &impl Manager<R>, text: &str) -> (String, BidiInfo) {
+  let config = manager.state::<Mutex<BidiSettings>>().locked().config.clone();
+  bidi::apply(text, &config)
+}
+
+// run `text` through each leg of a route; the last leg yields n-best candidates or a confidence
+// report when asked, a domain pack adds its instructions to every leg and its rules to the result,
+// glossary terms and the formality go into the prompt of the legs they apply to, and the result is
+// fitted to the length limit when one is given, then localized and formatted for reading. The
+// content filter sees the text going in and every translation coming out, which then gets the
+// directional marks of the bidi settings
+pub fn run_route<R: Runtime>(
+  manager: &(impl Manager<R> + Emitter<R>),
+  legs: &[Leg],
+  text: &str,
+  options: &TranslateOptions,
+) -> Result<RoutedTranslation, String> {
+  let n = options.n_best.unwrap_or(1).clamp(1, MAX_N_BEST);
+  let pack = options.domain.as_deref().map(domain::pack).transpose()?;
+  let (text, _) = filter::check_input(manager, text.to_string())?;
+  let text = text.as_str();
+  let mut current = text.to_string();
+  let mut candidates = Vec::new();
+  let mut language = None;
+  let mut used_terms: Vec<DomainTerm> = Vec::new();
+  // the scored output and its report; dropped when a rewrite replaces that output
+  let mut scored: Option<(String, ConfidenceReport)> = None;
+  for (i, leg) in legs.iter().enumerate() {
+    let input = current.clone();
+    let last = i == legs.len() - 1;
+    let mut context = pack.as_ref().map(|p| p.translation_context(&input, &leg.source_lang, &leg.target_lang)).unwrap_or_default();
+    let terms = terms_in(&options.glossary, &input, &leg.source_lang, &leg.target_lang);
+    context.push_str(&glossary_context(&terms));
+    used_terms.extend(terms.into_iter().cloned());
+    if let (Some(formality), true) = (options.formality, last) {
+      context.push_str(&formality.instruction(&leg.target_lang));
+    }
+    if n > 1 && last {
+      candidates = n_best(leg, &input, n, &context)?;
+      // candidates in the requested language go first
+      if options.guard != GuardMode::Off {
+        candidates.sort_by_key(|c| langguard::wrong_language(&c.text, &leg.target_lang).is_some());
+      }
+      current = candidates[0].text.clone();
+    } else if options.confidence && last {
+      let generation = translate_detailed(&leg.model_path, &leg.config, &input, &leg.source_lang, &leg.target_lang, &context)?;
+      scored = generation.logprobs.as_deref().and_then(|lp| confidence::score(&generation.text, lp)).map(|r| (generation.text.clone(), r));
+      current = generation.text;
+    } else {
+      current = translate_with_context(&leg.model_path, &leg.config, &input, &leg.source_lang, &leg.target_lang, &context)?;
+    }
+    if options.guard != GuardMode::Off {
+      let (guarded, check) = guard_language(leg, &input, current, &context, options.guard)?;
+      if candidates.first().is_some_and(|c| c.text != guarded) {
+        let score = candidates[0].score;
+        candidates.insert(0, Candidate { text: guarded.clone(), score });
+        candidates.truncate(n);
+      }
+      current = guarded;
+      if last {
+        language = Some(check);
+      }
+    }
+  }
+  let mut confidence = scored.filter(|(text, _)| *text == current).map(|(_, report)| report);
+  if let (Some(pack), Some(last)) = (&pack, legs.last()) {
+    current = pack.post_process(&current, &last.target_lang);
+    for c in candidates.iter_mut() {
+      c.text = pack.post_process(&c.text, &last.target_lang);
+    }
+  }
+  let mut length = None;
+  if let (Some(limit), Some(last)) = (&options.length, legs.last()) {
+    let (fitted, check) = fit_length(last, text, current.clone(), limit)?;
+    if fitted != current {
+      confidence = None;
+    }
+    current = fitted;
+    length = Some(check);
+  }
+  let mut localized = Vec::new();
+  if let (Some(first), Some(last)) = (legs.first(), legs.last()) {
+    let source = match first.source_lang.as_str() {
+      "auto" => lang::detect(text).map(|d| d.lang),
+      known => Some(known.to_string()),
+    };
+    if let Some(source) = source {
+      let config = options.localize.clone().unwrap_or_else(|| manager.state::<Mutex<LocalizeSettings>>().locked().get(&source, &last.target_lang));
+      let rates = manager.state::<Mutex<Rates>>();
+      let rates = rates.locked();
+      let result = localize::post_process(&current, &source, &last.target_lang, &config, &rates.table);
+      current = result.text;
+      localized = result.changes;
+      for c in candidates.iter_mut() {
+        c.text = localize::post_process(&c.text, &source, &last.target_lang, &config, &rates.table).text;
+      }
+    }
+    current = readable::apply(current, options.reading.as_ref(), &last.target_lang);
+    for c in candidates.iter_mut() {
+      c.text = readable::apply(std::mem::take(&mut c.text), options.reading.as_ref(), &last.target_lang);
+    }
+  }
+  let (current, _) = filter::check_output(manager, current)?;
+  let (current, direction) = apply_bidi(manager, &current);
+  for c in candidates.iter_mut() {
+    c.text = apply_bidi(manager, &filter::check_output(manager, std::mem::take(&mut c.text))?.0).0;
+  }
+  Ok(RoutedTranslation {
+    text: current,
+    pivot_lang: (legs.len() > 1).then(|| legs[0].target_lang.clone()),
+    model_ids: legs.iter().map(|l| l.model_id.clone()).collect(),
+    length,
+    language,
+    candidates,
+    adjustments: Vec::new(),
+    terms: used_terms,
+    confidence,
+    localized,
+    direction,
+  })
+}
+
+// check that a leg's output is in its target language; in Retry mode a mismatch is translated
+// again with a stricter instruction, keeping the retry only when it fixed the language
+fn guard_language(leg: &Leg, input: &str, output: String, context: &str, mode: GuardMode) -> Result<(String, LanguageCheck), String> {
+  let mut output = output;
+  let mut retries = 0;
+  if mode == GuardMode::Retry {
+    for _ in 0..langguard::GUARD_RETRIES {
+      let Some(wrong) = langguard::wrong_language(&output, &leg.target_lang) else { break };
+      retries += 1;
+      let prompt = format!("{}{}", context, langguard::strict_prompt(input, &leg.source_lang, &leg.target_lang, &wrong.lang));
+      let attempt = engine::generate(&leg.model_path, &leg.config, &prompt, engine::estimate_tokens(input) * 3 + 64)?.trim().to_string();
+      if !attempt.is_empty() && langguard::wrong_language(&attempt, &leg.target_lang).is_none() {
+        output = attempt;
+      }
+    }
+  }
+  let check = langguard::check(&output, &leg.target_lang, retries);
+  Ok((output, check))
+}
+
+// route and translate in one step
+pub fn translate_routed<R: Runtime>(
+  manager: &(impl Manager<R> + Emitter<R>),
+  text: &str,
+  source_lang: &str,
+  target_lang: &str,
+  model_id: Option<&str>,
+  options: &TranslateOptions,
+) -> Result<RoutedTranslation, String> {
+  let (routed, adjustments) = dispatch::with_hints(&options.hints, || {
+    let mut legs = route(manager, source_lang, target_lang, model_id)?;
+    // a model the caller asked for by id is kept even when it is slow
+    if model_id.is_none() {
+      meet_deadline(manager, &mut legs, text);
+    }
+    run_route(manager, &legs, text, options)
+  });
+  Ok(RoutedTranslation { adjustments, ..routed? })
+}
+
+// swap legs whose model can't finish before the deadline for the fastest measured model
+// configured for the same pair; models never measured are left alone
+fn meet_deadline<R: Runtime>(manager: &impl Manager<R>, legs: &mut [Leg], text: &str) {
+  let Some(remaining) = dispatch::remaining() else { return };
+  let budget = remaining / legs.len().max(1) as u32;
+  let tokens = engine::estimate_tokens(text) * 3 + 64;
+  let mgr = manager.state::<Mutex<ModelManager>>();
+  let mgr = mgr.locked();
+  for leg in legs.iter_mut() {
+    let Some(expected) = dispatch::expected(&leg.model_path, tokens) else { continue };
+    if expected <= budget {
+      continue;
+    }
+    let faster = mgr
+      .models
+      .values()
+      .filter(|m| m.id != leg.model_id)
+      .map(|m| (m, mgr.configs.get(&m.id)))
+      .filter(|(_, config)| supports(config, &leg.source_lang, false) && supports(config, &leg.target_lang, false))
+      .filter_map(|(m, config)| dispatch::expected(&m.path, tokens).filter(|t| *t < expected).map(|t| (t, m, config)))
+      .min_by_key(|(t, _, _)| *t);
+    if let Some((_, model, config)) = faster {
+      dispatch::note(Adjustment {
+        kind: "model".into(),
+        from: leg.model_id.clone(),
+        to: model.id.clone(),
+        expected_ms: expected.as_millis() as u64,
+        budget_ms: budget.as_millis() as u64,
+      });
+      leg.model_id = model.id.clone();
+      leg.model_path = model.path.clone();
+      leg.config = config;
+    }
+  }
+}
+
+// "notes.md" + "de" -> "<output_dir>/notes.de.md"
+pub fn output_path(input: &Path, output_dir: &Path, target_lang: &str) -> PathBuf {
+  let stem = input.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+  let ext = input.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+  output_dir.join(format!("{}.{}{}", stem, target_lang, ext))
+}
+
+// files written by output_path ("notes.de.md") must not be picked up again as input
+pub fn is_translation_output(input: &Path, target_lang: &str) -> bool {
+  let stem = input.file_stem().map(|s| s.to_string_lossy().to_lowercase()).unwrap_or_default();
+  stem.ends_with(&format!(".{}", target_lang.to_lowercase()))
+}
+
+// document pipeline: translate a document into `output_dir` as a resumable job; an unfinished job
+// for the same document and output is picked up where it stopped
+pub fn translate_file<F: FnMut(usize, usize)>(
+  app: &AppHandle,
+  input: &Path,
+  output_dir: &Path,
+  source_lang: &str,
+  target_lang: &str,
+  model_id: Option<&str>,
+  on_chunk: F
+) -> Result<PathBuf, String> {
+  let format = DocumentFormat::from_path(input).ok_or(format!("Unsupported document type: {}", input.to_string_lossy()))?;
+  fs::create_dir_all(output_dir).map_err(|e| format!("failed to create {}: {}", output_dir.to_string_lossy(), e))?;
+  let out = output_path(input, output_dir, target_lang);
+  let job = jobs::document_job(app, input, format, &out, source_lang, target_lang)?;
+  jobs::run_job(app, job.id, model_id.map(|m| m.to_string()), on_chunk)?;
+  Ok(out)
+}
+
+// ------------------ Tauri commands ------------------
+
+// translate `text` with everything a translation takes: model routing (pivot language included),
+// the saved glossary for the pair plus any terms in `options`, formality, domain pack, output
+// language guard, length limit, confidence scores, locale formatting and reading options
+#[tauri::command(async)]
+pub fn translate(
+  text: String,
+  source_lang: String,
+  target_lang: String,
+  model_id: Option<String>,
+  options: Option<TranslateOptions>,
+  app: AppHandle
+) -> Result<RoutedTranslation, AppError> {
+  if text.trim().is_empty() {
+    return Err("Nothing to translate".into());
+  }
+  if target_lang.trim().is_empty() || target_lang == "auto" {
+    return Err(AppError::InvalidInput("The target language must be a language code".into()));
+  }
+  let mut options = options.unwrap_or_default();
+  let saved = app.state::<Mutex<Glossary>>().locked().entries(None, None);
+  options.glossary.extend(saved.into_iter().map(|e| DomainTerm { source_lang: e.source_lang, target_lang: e.target_lang, source: e.source, target: e.target }));
+  let routed = translate_routed(&app, &text, &source_lang, &target_lang, model_id.as_deref(), &options)?;
+  preload::record(&app, "translate", &preload::pair(&source_lang, &target_lang), routed.model_ids.last().map(|s| s.as_str()).unwrap_or(""));
+  Ok(routed)
+}
+
+// the `n_best` best translations with scores from a single generation, for offering alternates
+#[tauri::command(async)]
+pub fn translate_candidates(
+  text: String,
+  source_lang: String,
+  target_lang: String,
+  n_best: usize,
+  model_id: Option<String>,
+  app: AppHandle
+) -> Result<RoutedTranslation, AppError> {
+  let options = TranslateOptions { n_best: Some(n_best), ..Default::default() };
+  let routed = translate_routed(&app, &text, &source_lang, &target_lang, model_id.as_deref(), &options)?;
+  preload::record(&app, "translate", &preload::pair(&source_lang, &target_lang), routed.model_ids.last().map(|s| s.as_str()).unwrap_or(""));
+  Ok(routed)
+}
+
+#[tauri::command]
+pub fn get_pivot_language() -> String {
+  pivot_language()
+}
+
+// shorten (or restore) an existing translation to fit `limit`, e.g. after a manual edit
+#[tauri::command(async)]
+pub fn fit_translation_length(
+  source: String,
+  translation: String,
+  target_lang: String,
+  limit: LengthLimit,
+  model_id: Option<String>,
+  app: AppHandle
+) -> Result<RoutedTranslation, AppError> {
+  let (model, config) = app.state::<Mutex<ModelManager>>().locked().model_for_request(model_id.as_deref())?;
+  let leg = Leg { model_id: model.id.clone(), model_path: model.path.clone(), config, source_lang: "auto".into(), target_lang };
+  let (text, check) = fit_length(&leg, &source, translation, &limit)?;
+  let (text, direction) = apply_bidi(&app, &text);
+  Ok(RoutedTranslation {
+    text,
+    pivot_lang: None,
+    model_ids: vec![leg.model_id],
+    length: Some(check),
+    language: None,
+    candidates: Vec::new(),
+    adjustments: Vec::new(),
+    terms: Vec::new(),
+    confidence: None,
+    localized: Vec::new(),
+    direction,
+  })
+}
+
+#[tauri::command]
+pub fn set_pivot_language(lang: String) -> Result<(), AppError> {
+  if lang.trim().is_empty() || lang == "auto" {
+    return Err(AppError::InvalidInput("The pivot language must be a language code".into()));
+  }
+  Ok(store::save_json(&settings_path(), &TranslateSettings { pivot_lang: lang })?)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn limit(max_chars: Option<usize>, tolerance_pct: Option<f32>) -> LengthLimit {
+    LengthLimit { max_chars, tolerance_pct }
+  }
+
+  #[test]
+  fn bounds_from_a_fixed_budget() {
+    assert_eq!(limit(Some(40), None).bounds("anything"), (None, Some(40)));
+    assert_eq!(limit(None, None).bounds("anything"), (None, None));
+  }
+
+  #[test]
+  fn bounds_from_a_tolerance() {
+    let source = "x".repeat(100);
+    assert_eq!(limit(None, Some(20.0)).bounds(&source), (Some(80), Some(120)));
+    // a negative tolerance means the same
+    assert_eq!(limit(None, Some(-20.0)).bounds(&source), (Some(80), Some(120)));
+    // characters, not bytes
+    assert_eq!(limit(None, Some(50.0)).bounds("äöüß"), (Some(2), Some(6)));
+  }
+
+  #[test]
+  fn the_tighter_maximum_wins() {
+    let source = "x".repeat(100);
+    assert_eq!(limit(Some(90), Some(20.0)).bounds(&source), (Some(80), Some(90)));
+    // a minimum above the fixed maximum is dropped
+    assert_eq!(limit(Some(50), Some(20.0)).bounds(&source), (None, Some(50)));
+    // and so is a minimum of nothing
+    assert_eq!(limit(None, Some(100.0)).bounds(&source), (None, Some(200)));
+  }
+
+  #[test]
+  fn overshoot_counts_characters_outside() {
+    assert_eq!(overshoot(45, (None, Some(40))), 5);
+    assert_eq!(overshoot(30, (Some(35), Some(40))), 5);
+    assert_eq!(overshoot(38, (Some(35), Some(40))), 0);
+    assert_eq!(overshoot(1000, (None, None)), 0);
+  }
+}
@@ -0,0 +1,186 @@
+// src-tauri/src/transport.rs
+use std::process::Command;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::{Mutex, OwnedMutexGuard};
+use tokio::time;
+
+use crate::protocol;
+
+// how long a runtime gets to connect to its sockets after it was started
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+// where a wrapper finds its channels
+const DATA_VAR: &str = "MULTILINGUAL_DATA_SOCKET";
+const CONTROL_VAR: &str = "MULTILINGUAL_CONTROL_SOCKET";
+
+// How prompts and answers travel between the app and a wrapper runtime (run.sh / run.bat)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Transport {
+  // prompts on stdin, answers on stdout
+  #[default]
+  Stdio,
+  // two Unix domain sockets (named pipes on Windows) the app listens on and the wrapper connects
+  // to, named by MULTILINGUAL_DATA_SOCKET and MULTILINGUAL_CONTROL_SOCKET:
+  // - data: prompts in, in the model's stdin protocol; answers out, as they would go to stdout
+  // - control: {"type": "cancel", "id": <request id>} lines in; anything written back is logged
+  // stdout and stderr are logging then. Prompts of any size go through without pipe buffers
+  // filling up, and a cancel doesn't wait behind a prompt being written
+  Socket,
+}
+
+pub type Reader = Box<dyn AsyncRead + Send + Unpin>;
+pub type Writer = Box<dyn AsyncWrite + Send + Unpin>;
+// where prompts (or control messages) for a process are written; None until the runtime has
+// connected, or when it never did
+pub type SharedWriter = Arc<Mutex<Option<Writer>>>;
+
+// a writer that is there from the start (stdin)
+pub fn ready(writer: impl AsyncWrite + Send + Unpin + 'static) -> SharedWriter {
+  Arc::new(Mutex::new(Some(Box::new(writer))))
+}
+
+// a writer whose connection is still to come: writes wait on the returned guard, which is filled
+// in and released once the runtime has connected
+pub fn pending() -> (SharedWriter, OwnedMutexGuard<Option<Writer>>) {
+  let writer: SharedWriter = Arc::new(Mutex::new(None));
+  // nobody else has the writer yet, so this can't fail
+  let guard = writer.clone().try_lock_owned().expect("new mutex is unlocked");
+  (writer, guard)
+}
+
+// write `text` to a process's input or control channel
+pub async fn send(writer: &SharedWriter, text: &str) -> Result<(), String> {
+  match writer.lock().await.as_mut() {
+    Some(w) => protocol::write(w, text).await,
+    None => Err("The runtime didn't connect to its socket".into()),
+  }
+}
+
+// the control message that stops the answer to `request_id`
+pub fn cancel_message(request_id: u64) -> String {
+  format!("{}\n", serde_json::json!({ "type": "cancel", "id": request_id }))
+}
+
+#[cfg(unix)]
+mod endpoint {
+  use std::fs;
+  use std::path::PathBuf;
+
+  use tokio::net::UnixListener;
+
+  use super::{Reader, Writer};
+
+  pub struct Endpoint {
+    path: PathBuf,
+    listener: UnixListener,
+  }
+
+  impl Endpoint {
+    // socket paths are limited to about 100 bytes, so they go in the system temp dir rather than
+    // the scratch dir
+    pub fn bind(name: &str) -> Result<Self, String> {
+      let path = std::env::temp_dir().join(format!("{}.sock", name));
+      let _ = fs::remove_file(&path);
+      let listener = UnixListener::bind(&path).map_err(|e| format!("failed to create socket {}: {}", path.to_string_lossy(), e))?;
+      Ok(Self { path, listener })
+    }
+
+    pub fn address(&self) -> String {
+      self.path.to_string_lossy().to_string()
+    }
+
+    pub async fn accept(&self) -> Result<(Reader, Writer), String> {
+      let (stream, _) = self.listener.accept().await.map_err(|e| format!("socket {} failed: {}", self.path.to_string_lossy(), e))?;
+      let (reader, writer) = stream.into_split();
+      Ok((Box::new(reader), Box::new(writer)))
+    }
+  }
+
+  // the file is only needed until the runtime has connected
+  impl Drop for Endpoint {
+    fn drop(&mut self) {
+      let _ = fs::remove_file(&self.path);
+    }
+  }
+}
+
+#[cfg(windows)]
+mod endpoint {
+  use std::sync::Mutex;
+
+  use tokio::net::windows::named_pipe::{NamedPipeServer, ServerOptions};
+
+  use super::{Reader, Writer};
+  use crate::error::LockExt;
+
+  pub struct Endpoint {
+    name: String,
+    server: Mutex<Option<NamedPipeServer>>,
+  }
+
+  impl Endpoint {
+    pub fn bind(name: &str) -> Result<Self, String> {
+      let name = format!(r"\\.\pipe\{}", name);
+      let server = ServerOptions::new().first_pipe_instance(true).create(&name).map_err(|e| format!("failed to create pipe {}: {}", name, e))?;
+      Ok(Self { name, server: Mutex::new(Some(server)) })
+    }
+
+    pub fn address(&self) -> String {
+      self.name.clone()
+    }
+
+    // a pipe instance takes one client
+    pub async fn accept(&self) -> Result<(Reader, Writer), String> {
+      let server = self.server.locked().take().ok_or(format!("pipe {} is already connected", self.name))?;
+      server.connect().await.map_err(|e| format!("pipe {} failed: {}", self.name, e))?;
+      let (reader, writer) = tokio::io::split(server);
+      Ok((Box::new(reader), Box::new(writer)))
+    }
+  }
+}
+
+use endpoint::Endpoint;
+
+// The data and control channels of one process, listening before it starts
+pub struct Endpoints {
+  data: Endpoint,
+  control: Endpoint,
+}
+
+// What came in over the channels once the runtime connected
+pub struct Channels {
+  pub data: Reader,
+  pub control: Reader,
+}
+
+impl Endpoints {
+  pub fn bind() -> Result<Self, String> {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    let name = format!("multilingual-{}-{}", std::process::id(), NEXT.fetch_add(1, Ordering::Relaxed) + 1);
+    Ok(Self { data: Endpoint::bind(&format!("{}-data", name))?, control: Endpoint::bind(&format!("{}-control", name))? })
+  }
+
+  // tell the runtime where to connect
+  pub fn apply(&self, command: &mut Command) {
+    command.env(DATA_VAR, self.data.address()).env(CONTROL_VAR, self.control.address());
+  }
+
+  // wait for the runtime to connect to both channels; the write halves go to `input` and
+  // `control`, which releases the writes waiting on them
+  pub async fn accept(self, mut input: OwnedMutexGuard<Option<Writer>>, mut control: OwnedMutexGuard<Option<Writer>>) -> Result<Channels, String> {
+    let connect = async {
+      let (data, control) = tokio::join!(self.data.accept(), self.control.accept());
+      Ok::<_, String>((data?, control?))
+    };
+    let ((data_in, data_out), (control_in, control_out)) = time::timeout(CONNECT_TIMEOUT, connect)
+      .await
+      .map_err(|_| format!("The runtime didn't connect to its sockets within {}s", CONNECT_TIMEOUT.as_secs()))??;
+    *input = Some(data_out);
+    *control = Some(control_out);
+    Ok(Channels { data: data_in, control: control_in })
+  }
+}
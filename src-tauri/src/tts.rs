@@ -0,0 +1,287 @@
+// src-tauri/src/tts.rs
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use tauri::{Emitter, Manager, Window};
+
+use crate::audio;
+use crate::chunk;
+use crate::error::{AppError, LockExt};
+use crate::lang;
+use crate::readaloud;
+use crate::runtime;
+use crate::speech;
+use crate::storage;
+
+// A voice text can be spoken with: an installed piper voice, or one of the system's own
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct Voice {
+  // "piper:<file stem>" or "system:<voice>"; what speak takes as `voice`
+  pub id: String,
+  pub name: String,
+  // BCP 47 when known ("de-DE")
+  pub lang: Option<String>,
+  // "piper" | "system"
+  pub engine: String,
+}
+
+// Payload of "speech-progress" events: one as each sentence starts, then a final one
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct SpeechProgress {
+  pub speech_id: u64,
+  // sentence being spoken (0-based); on the final event, how many were spoken
+  pub index: usize,
+  pub total: usize,
+  pub sentence: Option<String>,
+  pub voice: String,
+  pub done: bool,
+  // set on the final event when speaking failed
+  pub error: Option<String>,
+}
+
+// Speech in progress (managed by Tauri); each maps to its stop flag
+pub struct Speakers {
+  active: HashMap<u64, Arc<AtomicBool>>,
+  counter: u64,
+}
+
+impl Speakers {
+  pub fn new() -> Self {
+    Self { active: HashMap::new(), counter: 0 }
+  }
+}
+
+enum Engine {
+  Piper(PathBuf),
+  // system voice to use; None for the system default
+  System(Option<String>),
+}
+
+// "de_DE-thorsten-medium" -> "de-DE"
+fn piper_lang(stem: &str) -> String {
+  stem.split('-').next().unwrap_or(stem).replace('_', "-")
+}
+
+fn piper_voices() -> Vec<Voice> {
+  if runtime::bundled_tool("piper").is_none() {
+    return Vec::new();
+  }
+  speech::voice_files()
+    .iter()
+    .filter_map(|p| p.file_stem().map(|s| s.to_string_lossy().to_string()))
+    .map(|stem| Voice { id: format!("piper:{}", stem), lang: Some(piper_lang(&stem)), name: stem, engine: "piper".into() })
+    .collect()
+}
+
+fn system_voice(ident: &str, name: &str, lang: Option<&str>) -> Voice {
+  Voice { id: format!("system:{}", ident), name: name.to_string(), lang: lang.map(|l| l.replace('_', "-")), engine: "system".into() }
+}
+
+// voices of the platform's speech synthesizer: `say` on macOS, SAPI on Windows, espeak-ng elsewhere
+fn system_voices() -> Vec<Voice> {
+  if cfg!(target_os = "macos") {
+    // "Anna                de_DE    # Hallo! Ich heiße Anna."
+    let out = readaloud::run_capture("say", &["-v", "?"]).unwrap_or_default();
+    out
+      .lines()
+      .filter_map(|l| {
+        let mut words: Vec<&str> = l.split('#').next()?.split_whitespace().collect();
+        let lang = words.pop()?;
+        let name = words.join(" ");
+        (!name.is_empty()).then(|| system_voice(&name, &name, Some(lang)))
+      })
+      .collect()
+  } else if cfg!(target_os = "windows") {
+    let script = "Add-Type -AssemblyName System.Speech; (New-Object System.Speech.Synthesis.SpeechSynthesizer).GetInstalledVoices() | ForEach-Object { $_.VoiceInfo.Name + '|' + $_.VoiceInfo.Culture.Name }";
+    let out = readaloud::run_capture("powershell", &["-NoProfile", "-Command", script]).unwrap_or_default();
+    out
+      .lines()
+      .filter_map(|l| l.trim().split_once('|'))
+      .map(|(name, lang)| system_voice(name, name, Some(lang).filter(|l| !l.is_empty())))
+      .collect()
+  } else {
+    // "Pty Language       Age/Gender VoiceName          File                 Other Languages"
+    // " 5  de              --/M      German             gmw/de"
+    let out = readaloud::run_capture("espeak-ng", &["--voices"]).or_else(|| readaloud::run_capture("espeak", &["--voices"])).unwrap_or_default();
+    out
+      .lines()
+      .skip(1)
+      .filter_map(|l| {
+        let cols: Vec<&str> = l.split_whitespace().collect();
+        let (lang, name) = (cols.get(1)?, cols.get(3)?);
+        Some(system_voice(lang, &name.replace('_', " "), Some(lang)))
+      })
+      .collect()
+  }
+}
+
+// the voice asked for, else a piper voice for `lang`, else a system voice for it
+fn resolve(voice: Option<&str>, lang: &str) -> Result<(Engine, String), String> {
+  let has_piper = runtime::bundled_tool("piper").is_some();
+  if let Some(v) = voice {
+    if let Some(ident) = v.strip_prefix("system:") {
+      return Ok((Engine::System(Some(ident.to_string())), v.to_string()));
+    }
+    let name = v.strip_prefix("piper:").unwrap_or(v);
+    if let Some(path) = speech::voice_named(name).filter(|_| has_piper) {
+      return Ok((Engine::Piper(path), format!("piper:{}", name.trim_start_matches("piper-"))));
+    }
+    return system_voices()
+      .into_iter()
+      .find(|s| s.name == v)
+      .map(|s| (Engine::System(s.id.strip_prefix("system:").map(|i| i.to_string())), s.id))
+      .ok_or(format!("Voice '{}' not found", v));
+  }
+  if let Some(path) = speech::voice_for(lang).filter(|_| has_piper) {
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    return Ok((Engine::Piper(path), format!("piper:{}", stem)));
+  }
+  system_voices()
+    .into_iter()
+    .find(|s| s.lang.as_deref().is_some_and(|l| lang::same_language(l, lang)))
+    .map(|s| (Engine::System(s.id.strip_prefix("system:").map(|i| i.to_string())), s.id))
+    .ok_or(format!("No voice for {}", lang::language_name(lang)))
+}
+
+// wait for a system synthesizer to finish, killing it when `stop` is set
+fn wait_or_stop(mut child: Child, stop: &AtomicBool) -> Result<(), String> {
+  loop {
+    if let Some(status) = child.try_wait().map_err(|e| format!("speech synthesizer failed: {}", e))? {
+      return if status.success() { Ok(()) } else { Err(format!("speech synthesizer exited with {}", status)) };
+    }
+    if stop.load(Ordering::Relaxed) {
+      let _ = child.kill();
+      let _ = child.wait();
+      return Ok(());
+    }
+    thread::sleep(Duration::from_millis(50));
+  }
+}
+
+// speak with the platform synthesizer, passing the text on stdin so it needs no quoting
+fn speak_system(voice: Option<&str>, text: &str, stop: &AtomicBool) -> Result<(), String> {
+  let mut command = if cfg!(target_os = "macos") {
+    let mut c = Command::new("say");
+    if let Some(v) = voice {
+      c.args(["-v", v]);
+    }
+    c
+  } else if cfg!(target_os = "windows") {
+    let mut c = Command::new("powershell");
+    c.args([
+      "-NoProfile",
+      "-Command",
+      "Add-Type -AssemblyName System.Speech; $s = New-Object System.Speech.Synthesis.SpeechSynthesizer; if ($env:MULTILINGUAL_VOICE) { $s.SelectVoice($env:MULTILINGUAL_VOICE) }; $s.Speak([Console]::In.ReadToEnd())",
+    ]);
+    if let Some(v) = voice {
+      c.env("MULTILINGUAL_VOICE", v);
+    }
+    c
+  } else {
+    let exe = if Command::new("espeak-ng").arg("--version").stdout(Stdio::null()).stderr(Stdio::null()).status().is_ok() { "espeak-ng" } else { "espeak" };
+    let mut c = Command::new(exe);
+    if let Some(v) = voice {
+      c.args(["-v", v]);
+    }
+    c.arg("--stdin");
+    c
+  };
+  let mut child = command
+    .stdin(Stdio::piped())
+    .stdout(Stdio::null())
+    .stderr(Stdio::null())
+    .spawn()
+    .map_err(|e| format!("no system speech synthesizer available: {}", e))?;
+  if let Some(mut stdin) = child.stdin.take() {
+    stdin.write_all(text.as_bytes()).map_err(|e| format!("failed to write to the speech synthesizer: {}", e))?;
+  }
+  wait_or_stop(child, stop)
+}
+
+// synthesize a sentence with piper into the scratch dir and play it
+fn speak_piper(voice: &Path, text: &str, wav: &Path, stop: &AtomicBool) -> Result<(), String> {
+  speech::synthesize_with(voice, text, wav)?;
+  let (samples, rate) = audio::read_wav(wav)?;
+  audio::play(&samples, rate, stop)
+}
+
+// speak `sentences` one by one, sending "speech-progress" as each starts and once all are done
+fn speak_all(window: &Window, id: u64, engine: Engine, voice: String, sentences: Vec<String>, stop: &AtomicBool) {
+  let wav = storage::scratch_dir().join(format!("multilingual-speech-{}.wav", id));
+  let total = sentences.len();
+  let mut spoken = 0;
+  let mut error = None;
+  for (index, sentence) in sentences.into_iter().enumerate() {
+    if stop.load(Ordering::Relaxed) {
+      break;
+    }
+    let event = SpeechProgress { speech_id: id, index, total, sentence: Some(sentence.clone()), voice: voice.clone(), done: false, error: None };
+    let _ = window.emit("speech-progress", event);
+    let result = match &engine {
+      Engine::Piper(path) => speak_piper(path, &sentence, &wav, stop),
+      Engine::System(name) => speak_system(name.as_deref(), &sentence, stop),
+    };
+    if let Err(e) = result {
+      error = Some(e);
+      break;
+    }
+    spoken += 1;
+  }
+  let _ = fs::remove_file(&wav);
+  let _ = window.emit("speech-progress", SpeechProgress { speech_id: id, index: spoken, total, sentence: None, voice, done: true, error });
+}
+
+// ------------------ Tauri commands ------------------
+
+// piper voices first, then the system's; only those for `language` when given
+#[tauri::command(async)]
+pub fn list_voices(language: Option<String>) -> Vec<Voice> {
+  let mut voices = piper_voices();
+  voices.extend(system_voices());
+  if let Some(language) = language {
+    voices.retain(|v| v.lang.as_deref().is_some_and(|l| lang::same_language(l, &language)));
+  }
+  voices
+}
+
+// read `text` aloud sentence by sentence. `voice` is an id from list_voices (or a voice name);
+// unset, a piper voice for `language` is used, falling back to the system synthesizer, and
+// `language` itself is detected from the text when not given. Returns an id right away; progress
+// follows as "speech-progress" events until done or stop_speaking
+#[tauri::command]
+pub fn speak(text: String, voice: Option<String>, language: Option<String>, window: Window, speakers: tauri::State<'_, Mutex<Speakers>>) -> Result<u64, AppError> {
+  let sentences: Vec<String> = chunk::sentences(&text).into_iter().map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect();
+  if sentences.is_empty() {
+    return Err("Nothing to speak".into());
+  }
+  let lang = language.or_else(|| lang::detect(&text).map(|d| d.lang)).unwrap_or_else(|| "en".into());
+  let (engine, voice) = resolve(voice.as_deref(), &lang)?;
+  let stop = Arc::new(AtomicBool::new(false));
+  let id = {
+    let mut speakers = speakers.locked();
+    speakers.counter += 1;
+    let id = speakers.counter;
+    speakers.active.insert(id, stop.clone());
+    id
+  };
+  thread::spawn(move || {
+    speak_all(&window, id, engine, voice, sentences, &stop);
+    window.state::<Mutex<Speakers>>().locked().active.remove(&id);
+  });
+  Ok(id)
+}
+
+// stop speaking after cutting the current sentence short
+#[tauri::command]
+pub fn stop_speaking(speech_id: u64, speakers: tauri::State<'_, Mutex<Speakers>>) -> Result<(), AppError> {
+  let stop = speakers.locked().active.remove(&speech_id).ok_or(format!("Speech {} not found", speech_id))?;
+  stop.store(true, Ordering::Relaxed);
+  Ok(())
+}
@@ -0,0 +1,296 @@
+// src-tauri/src/watch.rs
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use notify::event::ModifyKind;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tauri::{AppHandle, Manager};
+
+use crate::error::{AppError, LockExt};
+use crate::events;
+use crate::store;
+use crate::translate;
+
+// activity log entries kept across all watches
+const LOG_LEN: usize = 500;
+// a file counts as fully written once its size is unchanged for this long
+const SETTLE: Duration = Duration::from_millis(1500);
+
+// A folder whose new documents are translated automatically
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct FolderWatch {
+  pub id: u64,
+  pub path: String,
+  pub output_dir: String,
+  // "auto" lets the model infer it
+  pub source_lang: String,
+  pub target_lang: String,
+  #[serde(default)]
+  pub model_id: Option<String>,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct ActivityEntry {
+  // unix seconds
+  pub time: i64,
+  pub watch_id: u64,
+  pub file: String,
+  // "queued" | "translating" | "done" | "failed"
+  pub status: String,
+  pub message: Option<String>,
+}
+
+// Payload of "watch-file-status" events
+#[derive(Clone, Debug, serde::Serialize)]
+struct FileStatus {
+  watch_id: u64,
+  file: String,
+  status: String,
+  // chunks translated so far / total while translating
+  done: usize,
+  total: usize,
+  output: Option<String>,
+  error: Option<String>,
+}
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct WatchFile {
+  next_id: u64,
+  watches: Vec<FolderWatch>,
+  log: Vec<ActivityEntry>,
+}
+
+// Watched folders persisted in ./data/watches.json (managed by Tauri)
+pub struct FolderWatches {
+  path: PathBuf,
+  file: WatchFile,
+  watchers: HashMap<u64, RecommendedWatcher>,
+}
+
+impl FolderWatches {
+  pub fn load() -> Self {
+    let path = store::data_file("watches.json");
+    let file = store::load_json(&path);
+    Self { path, file, watchers: HashMap::new() }
+  }
+
+  fn save(&self) -> Result<(), String> {
+    store::save_json(&self.path, &self.file)
+  }
+
+  fn log(&mut self, entry: ActivityEntry) {
+    self.file.log.push(entry);
+    let excess = self.file.log.len().saturating_sub(LOG_LEN);
+    self.file.log.drain(..excess);
+    let _ = self.save();
+  }
+}
+
+fn now_secs() -> i64 {
+  SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0)
+}
+
+fn report(app: &AppHandle, status: FileStatus) {
+  if status.status != "translating" {
+    let entry = ActivityEntry {
+      time: now_secs(),
+      watch_id: status.watch_id,
+      file: status.file.clone(),
+      status: status.status.clone(),
+      message: status.error.clone().or(status.output.clone()),
+    };
+    app.state::<Mutex<FolderWatches>>().locked().log(entry);
+  }
+  events::emit(app, "watch-file-status", status);
+}
+
+fn is_candidate(watch: &FolderWatch, file: &Path) -> bool {
+  let ext = file.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+  let hidden = file.file_name().map(|n| n.to_string_lossy().starts_with('.')).unwrap_or(true);
+  !hidden
+    && translate::DOCUMENT_EXTENSIONS.contains(&ext.as_str())
+    && !translate::is_translation_output(file, &watch.target_lang)
+}
+
+// wait until the file stops growing (editors and copies write in several steps)
+fn wait_until_settled(file: &Path) -> bool {
+  let mut last = None;
+  for _ in 0..40 {
+    let size = fs::metadata(file).map(|m| m.len()).ok();
+    if size.is_none() {
+      return false;
+    }
+    if size == last {
+      return true;
+    }
+    last = size;
+    thread::sleep(SETTLE);
+  }
+  true
+}
+
+fn process(app: &AppHandle, watch: &FolderWatch, file: &Path) {
+  let name = file.to_string_lossy().to_string();
+  let status = |status: &str, done, total, output: Option<String>, error: Option<String>| FileStatus {
+    watch_id: watch.id,
+    file: name.clone(),
+    status: status.into(),
+    done,
+    total,
+    output,
+    error,
+  };
+  if !wait_until_settled(file) {
+    return;
+  }
+  report(app, status("translating", 0, 0, None, None));
+  let result = translate::translate_file(
+    app,
+    file,
+    Path::new(&watch.output_dir),
+    &watch.source_lang,
+    &watch.target_lang,
+    watch.model_id.as_deref(),
+    |done, total| {
+      events::emit(app, "watch-file-status", status("translating", done, total, None, None));
+    },
+  );
+  match result {
+    Ok(out) => report(app, status("done", 0, 0, Some(out.to_string_lossy().to_string()), None)),
+    Err(e) => report(app, status("failed", 0, 0, None, Some(e))),
+  }
+}
+
+// start the notify watcher for one folder; files are translated one at a time on a worker thread
+fn spawn_watcher(app: &AppHandle, watch: &FolderWatch) -> Result<RecommendedWatcher, String> {
+  let (tx, rx) = mpsc::channel::<notify::Result<notify::Event>>();
+  let mut watcher = notify::recommended_watcher(tx).map_err(|e| format!("failed to watch {}: {}", watch.path, e))?;
+  watcher
+    .watch(Path::new(&watch.path), RecursiveMode::NonRecursive)
+    .map_err(|e| format!("failed to watch {}: {}", watch.path, e))?;
+
+  let (app, watch) = (app.clone(), watch.clone());
+  thread::spawn(move || {
+    let mut recent: HashSet<PathBuf> = HashSet::new();
+    // ends when the watcher (and with it the sender) is dropped
+    while let Ok(event) = rx.recv() {
+      let Ok(event) = event else { continue };
+      if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(ModifyKind::Data(_)) | EventKind::Modify(ModifyKind::Name(_))) {
+        continue;
+      }
+      // a single save produces several events; collect the burst before translating
+      let mut files: Vec<PathBuf> = event.paths;
+      while let Ok(Ok(more)) = rx.recv_timeout(SETTLE) {
+        files.extend(more.paths);
+      }
+      for file in files {
+        if !file.is_file() || !is_candidate(&watch, &file) || !recent.insert(file.clone()) {
+          continue;
+        }
+        report(&app, FileStatus {
+          watch_id: watch.id,
+          file: file.to_string_lossy().to_string(),
+          status: "queued".into(),
+          done: 0,
+          total: 0,
+          output: None,
+          error: None,
+        });
+      }
+      let batch: Vec<PathBuf> = recent.drain().collect();
+      for file in batch {
+        process(&app, &watch, &file);
+      }
+    }
+  });
+  Ok(watcher)
+}
+
+// resume persisted watches at startup
+pub fn start_all(app: &AppHandle) {
+  let state = app.state::<Mutex<FolderWatches>>();
+  let mut watches = state.locked();
+  for w in watches.file.watches.clone() {
+    if let Ok(watcher) = spawn_watcher(app, &w) {
+      watches.watchers.insert(w.id, watcher);
+    }
+  }
+}
+
+// ------------------ Tauri commands ------------------
+
+// translate every document dropped into `path` into `output_dir`
+#[tauri::command]
+pub fn watch_folder(
+  path: String,
+  target_lang: String,
+  output_dir: String,
+  source_lang: Option<String>,
+  model_id: Option<String>,
+  app: AppHandle,
+  watches: tauri::State<'_, Mutex<FolderWatches>>
+) -> Result<FolderWatch, AppError> {
+  if !Path::new(&path).is_dir() {
+    return Err(format!("Folder not found: {}", path).into());
+  }
+  fs::create_dir_all(&output_dir).map_err(|e| AppError::io("create", &output_dir, e))?;
+  let mut watches = watches.locked();
+  if watches.file.watches.iter().any(|w| w.path == path && w.target_lang == target_lang) {
+    return Err(format!("{} is already watched for {}", path, target_lang).into());
+  }
+  let watch = FolderWatch {
+    id: watches.file.next_id + 1,
+    path,
+    output_dir,
+    source_lang: source_lang.unwrap_or_else(|| "auto".into()),
+    target_lang,
+    model_id,
+  };
+  let watcher = spawn_watcher(&app, &watch)?;
+  watches.file.next_id = watch.id;
+  watches.file.watches.push(watch.clone());
+  watches.watchers.insert(watch.id, watcher);
+  watches.save()?;
+  Ok(watch)
+}
+
+#[tauri::command]
+pub fn list_watched_folders(watches: tauri::State<'_, Mutex<FolderWatches>>) -> Vec<FolderWatch> {
+  watches.locked().file.watches.clone()
+}
+
+#[tauri::command]
+pub fn unwatch_folder(id: u64, watches: tauri::State<'_, Mutex<FolderWatches>>) -> Result<(), AppError> {
+  let mut watches = watches.locked();
+  let before = watches.file.watches.len();
+  watches.file.watches.retain(|w| w.id != id);
+  if watches.file.watches.len() == before {
+    return Err(AppError::not_found("Watch", &id.to_string()));
+  }
+  // dropping the watcher stops its worker thread
+  watches.watchers.remove(&id);
+  Ok(watches.save()?)
+}
+
+// newest first, optionally for a single watch
+#[tauri::command]
+pub fn get_watch_activity(
+  watch_id: Option<u64>,
+  limit: Option<usize>,
+  watches: tauri::State<'_, Mutex<FolderWatches>>
+) -> Vec<ActivityEntry> {
+  let watches = watches.locked();
+  watches
+    .file
+    .log
+    .iter()
+    .rev()
+    .filter(|e| watch_id.is_none_or(|id| e.watch_id == id))
+    .take(limit.unwrap_or(100))
+    .cloned()
+    .collect()
+}
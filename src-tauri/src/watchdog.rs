@@ -0,0 +1,167 @@
+// src-tauri/src/watchdog.rs
+use std::collections::HashMap;
+use std::process::ExitStatus;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use tauri::{Emitter, Manager, Window};
+use tokio::time;
+
+use crate::error::LockExt;
+use crate::events;
+use crate::sampling::SamplingParams;
+use crate::stream::TokenStream;
+use crate::ModelManager;
+
+// how long a process that closed its output gets to actually exit before it is left alone
+const EXIT_WAIT: Duration = Duration::from_secs(2);
+const POLL: Duration = Duration::from_millis(50);
+
+// Whether and how often a model is started again after a crash (per model, in ModelConfig)
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct RestartPolicy {
+  pub enabled: bool,
+  // restarts allowed within window_secs; past that the model stays down
+  pub max_restarts: u32,
+  pub window_secs: u64,
+  // pause before restarting, so a crash loop doesn't spin
+  pub delay_ms: u64,
+}
+
+impl Default for RestartPolicy {
+  fn default() -> Self {
+    Self { enabled: false, max_restarts: 3, window_secs: 300, delay_ms: 2000 }
+  }
+}
+
+impl RestartPolicy {
+  pub fn validate(&self) -> Result<(), String> {
+    if self.enabled && (self.max_restarts == 0 || self.window_secs == 0) {
+      return Err("max_restarts and window_secs must be greater than 0".into());
+    }
+    Ok(())
+  }
+}
+
+// Payload of "model-crashed" events
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct CrashReport {
+  pub model_id: String,
+  pub exit_code: Option<i32>,
+  // the signal that ended the process (unix)
+  pub signal: Option<i32>,
+  // killed by the OOM killer or said it ran out of memory
+  pub out_of_memory: bool,
+  // the runtime's last log lines, oldest first
+  pub stderr_tail: Vec<String>,
+  // whether the restart policy starts it again
+  pub restarting: bool,
+  // restarts within the policy's window so far, this one included
+  pub restarts: u32,
+}
+
+// recent restarts per model id, for the policy's limit
+static RESTARTS: OnceLock<Mutex<HashMap<String, Vec<Instant>>>> = OnceLock::new();
+
+#[cfg(unix)]
+fn signal(status: &ExitStatus) -> Option<i32> {
+  use std::os::unix::process::ExitStatusExt;
+  status.signal()
+}
+
+#[cfg(not(unix))]
+fn signal(_status: &ExitStatus) -> Option<i32> {
+  None
+}
+
+const SIGKILL: i32 = 9;
+
+fn out_of_memory(signal: Option<i32>, logs: &[String]) -> bool {
+  signal == Some(SIGKILL)
+    || logs.iter().any(|l| {
+      let l = l.to_lowercase();
+      l.contains("out of memory") || l.contains("bad_alloc") || l.contains("failed to allocate")
+    })
+}
+
+// count a restart of `model_id` if the policy still allows one
+fn allow_restart(model_id: &str, policy: &RestartPolicy) -> (bool, u32) {
+  let mut restarts = RESTARTS.get_or_init(|| Mutex::new(HashMap::new())).locked();
+  let recent = restarts.entry(model_id.to_string()).or_default();
+  let window = Duration::from_secs(policy.window_secs);
+  recent.retain(|t| t.elapsed() < window);
+  if (recent.len() as u32) < policy.max_restarts {
+    recent.push(Instant::now());
+    (true, recent.len() as u32)
+  } else {
+    (false, recent.len() as u32)
+  }
+}
+
+// wait for the process `pid` of `model_id` to exit, taking it out of the manager. None when it was
+// stopped on purpose (stop_model takes it out first) or is still running
+async fn wait_for_exit(window: &Window, model_id: &str, pid: u32) -> Option<ExitStatus> {
+  let state = window.state::<Mutex<ModelManager>>();
+  let started = Instant::now();
+  loop {
+    {
+      let mut mgr = state.locked();
+      // an exited tokio Child no longer has an id; the pid the stream was started with tells
+      // whether this is still the same process
+      let child = mgr.processes.get_mut(model_id).filter(|c| c.id().is_none_or(|id| id == pid))?;
+      if let Ok(Some(status)) = child.try_wait() {
+        mgr.processes.remove(model_id);
+        mgr.inputs.remove(model_id);
+        mgr.controls.remove(model_id);
+        mgr.streams.remove(model_id);
+        mgr.protocols.remove(model_id);
+        mgr.servers.remove(model_id);
+        return Some(status);
+      }
+    }
+    if started.elapsed() > EXIT_WAIT {
+      return None;
+    }
+    time::sleep(POLL).await;
+  }
+}
+
+// called once a model process's output has closed: a process that ended on its own with an error
+// is reported as "model-crashed" and, when its restart policy allows, started again
+pub async fn process_ended(window: &Window, model_id: &str, pid: u32, stream: &Arc<TokenStream>) {
+  let Some(status) = wait_for_exit(window, model_id, pid).await else { return };
+  if status.success() {
+    return;
+  }
+  // the log reader may still be flushing the last lines
+  time::sleep(Duration::from_millis(200)).await;
+  let stderr_tail = stream.recent_logs();
+  let signal = signal(&status);
+  let policy = window.state::<Mutex<ModelManager>>().locked().configs.get(model_id).restart.unwrap_or_default();
+  let (restarting, restarts) = if policy.enabled { allow_restart(model_id, &policy) } else { (false, 0) };
+  let report = CrashReport {
+    model_id: model_id.to_string(),
+    exit_code: status.code(),
+    signal,
+    out_of_memory: out_of_memory(signal, &stderr_tail),
+    stderr_tail,
+    restarting,
+    restarts,
+  };
+  events::log(format!("{} exited unexpectedly ({})", model_id, status));
+  let _ = window.emit("model-crashed", report);
+  if !restarting {
+    return;
+  }
+
+  time::sleep(Duration::from_millis(policy.delay_ms)).await;
+  // started again by hand in the meantime
+  if window.state::<Mutex<ModelManager>>().locked().processes.contains_key(model_id) {
+    return;
+  }
+  match crate::start_process_async(window, model_id, &SamplingParams::default()).await {
+    Ok(_) => events::log(format!("{} restarted after a crash ({} of {})", model_id, restarts, policy.max_restarts)),
+    Err(e) => events::log(format!("restarting {} failed: {}", model_id, e)),
+  }
+}
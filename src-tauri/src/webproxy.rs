@@ -0,0 +1,422 @@
+// src-tauri/src/webproxy.rs
+use std::collections::HashMap;
+use std::io::{self, Read};
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use regex::{Captures, Regex};
+use tauri::{AppHandle, Manager};
+use tiny_http::{Method, Request, Response};
+use ureq::config::Config;
+use ureq::http::Uri;
+use ureq::unversioned::resolver::{DefaultResolver, ResolvedSocketAddrs, Resolver};
+use ureq::unversioned::transport::{DefaultConnector, NextTimeout};
+use url::Url;
+
+use crate::dispatch::{self, Priority};
+use crate::engine;
+use crate::error::{AppError, LockExt};
+use crate::httpd::{self, HttpServer};
+use crate::lang;
+use crate::ModelManager;
+
+// elements whose text must not be translated
+const SKIP_TAGS: &[&str] = &["script", "style", "code", "pre", "noscript", "textarea", "svg", "kbd", "samp"];
+// characters of text per model call
+const BATCH_CHARS: usize = 1500;
+// text nodes translated per page; the rest is served untranslated
+const MAX_NODES: usize = 600;
+const USER_AGENT: &str = "Mozilla/5.0 (multilingual translation proxy)";
+const MAX_REDIRECTS: u32 = 5;
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+// largest form body passed on to the page
+const MAX_FORM_BODY: u64 = 1024 * 1024;
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ProxyInfo {
+  pub port: u16,
+  pub source_lang: Option<String>,
+  pub target_lang: String,
+  pub model_id: Option<String>,
+}
+
+#[derive(Clone)]
+struct ProxyConfig {
+  source_lang: Option<String>,
+  target_lang: String,
+  model_id: Option<String>,
+}
+
+struct Running {
+  server: HttpServer,
+  info: ProxyInfo,
+}
+
+// Local translating proxy (managed by Tauri)
+pub struct WebProxy {
+  running: Option<Running>,
+}
+
+impl WebProxy {
+  pub fn new() -> Self {
+    Self { running: None }
+  }
+}
+
+fn tag_pattern() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| Regex::new(r"(?s)<!--.*?-->|<![^>]*>|</?([A-Za-z][A-Za-z0-9-]*)[^>]*>").unwrap())
+}
+
+fn attr_pattern() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| Regex::new(r#"(?i)\b(href|src|action)\s*=\s*("[^"]*"|'[^']*')"#).unwrap())
+}
+
+fn post_pattern() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| Regex::new(r#"(?i)\bmethod\s*=\s*["']?post\b"#).unwrap())
+}
+
+// true for addresses a web page has no business reaching through the proxy: this machine, the
+// local network, link-local and carrier-grade NAT ranges
+fn is_local(ip: IpAddr) -> bool {
+  match ip {
+    IpAddr::V4(v4) => {
+      let [a, b, _, _] = v4.octets();
+      v4.is_loopback()
+        || v4.is_private()
+        || v4.is_link_local()
+        || v4.is_unspecified()
+        || v4.is_broadcast()
+        || v4.is_multicast()
+        || a == 0
+        || (a == 100 && (64..128).contains(&b))
+    }
+    IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+      Some(v4) => is_local(IpAddr::V4(v4)),
+      None => {
+        let first = v6.segments()[0];
+        v6.is_loopback() || v6.is_unspecified() || v6.is_multicast() || first & 0xfe00 == 0xfc00 || first & 0xffc0 == 0xfe80
+      }
+    },
+  }
+}
+
+// Refuses hosts that resolve to local addresses. Every connection, redirects included, is resolved
+// here, so a DNS answer that changes after the first lookup is caught too
+#[derive(Debug, Default)]
+struct PublicResolver(DefaultResolver);
+
+impl Resolver for PublicResolver {
+  fn resolve(&self, uri: &Uri, config: &Config, timeout: NextTimeout) -> Result<ResolvedSocketAddrs, ureq::Error> {
+    let addrs = self.0.resolve(uri, config, timeout)?;
+    if let Some(addr) = addrs.iter().find(|a| is_local(a.ip())) {
+      let message = format!("{} is a local address and can't be opened through the proxy", addr.ip());
+      return Err(ureq::Error::Io(io::Error::new(io::ErrorKind::PermissionDenied, message)));
+    }
+    Ok(addrs)
+  }
+}
+
+fn agent() -> &'static ureq::Agent {
+  static AGENT: OnceLock<ureq::Agent> = OnceLock::new();
+  AGENT.get_or_init(|| {
+    let config = ureq::Agent::config_builder().proxy(None).max_redirects(MAX_REDIRECTS).timeout_global(Some(FETCH_TIMEOUT)).build();
+    ureq::Agent::with_parts(config, DefaultConnector::default(), PublicResolver::default())
+  })
+}
+
+fn decode_entities(s: &str) -> String {
+  s.replace("&nbsp;", "\u{a0}")
+    .replace("&lt;", "<")
+    .replace("&gt;", ">")
+    .replace("&quot;", "\"")
+    .replace("&#39;", "'")
+    .replace("&amp;", "&")
+}
+
+fn encode_entities(s: &str) -> String {
+  s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn proxy_url(port: u16, target: &str) -> String {
+  let query: String = url::form_urlencoded::Serializer::new(String::new()).append_pair("url", target).finish();
+  format!("http://127.0.0.1:{}/proxy?{}", port, query)
+}
+
+fn attr_value(s: &str) -> String {
+  s.replace('&', "&amp;").replace('"', "&quot;")
+}
+
+// links and forms go back through the proxy, everything else (css, images, scripts) is loaded from
+// the origin
+fn rewrite_tag(tag: &str, name: &str, base: &Url, port: u16) -> String {
+  attr_pattern()
+    .replace_all(tag, |caps: &Captures| {
+      let quoted = &caps[2];
+      let value = decode_entities(&quoted[1..quoted.len() - 1]);
+      if value.starts_with('#') || value.starts_with("javascript:") || value.starts_with("mailto:") || value.starts_with("data:") {
+        return caps[0].to_string();
+      }
+      let Ok(abs) = base.join(&value) else { return caps[0].to_string() };
+      let through_proxy = match caps[1].to_ascii_lowercase().as_str() {
+        "href" => matches!(name, "a" | "area"),
+        "action" => name == "form",
+        _ => false,
+      };
+      let new = if through_proxy { proxy_url(port, abs.as_str()) } else { abs.to_string() };
+      format!("{}=\"{}\"", &caps[1], attr_value(&new))
+    })
+    .into_owned()
+}
+
+// a GET form replaces the query of its action with its fields, so the page it submits to rides
+// along as a hidden field; handle() adds the other fields back onto it
+fn form_target(tag: &str, base: &Url) -> Option<String> {
+  if post_pattern().is_match(tag) {
+    return None;
+  }
+  let action = attr_pattern()
+    .captures_iter(tag)
+    .find(|c| c[1].eq_ignore_ascii_case("action"))
+    .map(|c| decode_entities(&c[2][1..c[2].len() - 1]))
+    .unwrap_or_default();
+  let mut abs = base.join(&action).ok()?;
+  abs.set_query(None);
+  Some(format!("<input type=\"hidden\" name=\"url\" value=\"{}\">", attr_value(abs.as_str())))
+}
+
+// html split into markup and translatable text nodes
+enum Piece {
+  Markup(String),
+  Text(String),
+}
+
+fn tokenize(html: &str, base: &Url, port: u16) -> Vec<Piece> {
+  let mut pieces = Vec::new();
+  let mut last = 0;
+  let mut skip: Option<String> = None;
+  for caps in tag_pattern().captures_iter(html) {
+    let m = caps.get(0).unwrap();
+    let text = &html[last..m.start()];
+    if !text.is_empty() {
+      pieces.push(if skip.is_some() { Piece::Markup(text.to_string()) } else { Piece::Text(text.to_string()) });
+    }
+    last = m.end();
+    let tag = m.as_str();
+    let Some(name) = caps.get(1).map(|n| n.as_str().to_lowercase()) else {
+      pieces.push(Piece::Markup(tag.to_string()));
+      continue;
+    };
+    let closing = tag.starts_with("</");
+    match &skip {
+      Some(s) if closing && *s == name => skip = None,
+      None if !closing && !tag.ends_with("/>") && SKIP_TAGS.contains(&name.as_str()) => skip = Some(name.clone()),
+      _ => {}
+    }
+    pieces.push(Piece::Markup(if closing { tag.to_string() } else { rewrite_tag(tag, &name, base, port) }));
+    if !closing && name == "form" {
+      if let Some(hidden) = form_target(tag, base) {
+        pieces.push(Piece::Markup(hidden));
+      }
+    }
+  }
+  pieces.push(Piece::Markup(html[last..].to_string()));
+  pieces
+}
+
+fn line_pattern() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| Regex::new(r"(?m)^\s*\[(\d+)\]\s?(.*)$").unwrap())
+}
+
+// translate many short strings in numbered batches; missing lines come back as None
+fn translate_batch(texts: &[String], config: &ProxyConfig, app: &AppHandle) -> Result<Vec<Option<String>>, String> {
+  let (model, model_config) = app.state::<Mutex<ModelManager>>().locked().model_for_request(config.model_id.as_deref())?;
+  let from = config.source_lang.as_deref().map(|l| format!(" from {}", lang::language_name(l))).unwrap_or_default();
+  let mut out = vec![None; texts.len()];
+  let mut start = 0;
+  while start < texts.len() {
+    let mut end = start;
+    let mut chars = 0;
+    while end < texts.len() && (end == start || chars + texts[end].len() < BATCH_CHARS) {
+      chars += texts[end].len();
+      end += 1;
+    }
+    let lines: Vec<String> = (start..end).map(|i| format!("[{}] {}", i - start + 1, texts[i])).collect();
+    let prompt = format!(
+      "Translate each numbered line of this web page{} into {}. Keep the [n] markers, reply with exactly one line per marker and nothing else.\n\n{}\n",
+      from,
+      lang::language_name(&config.target_lang),
+      lines.join("\n")
+    );
+    let reply = engine::generate(&model.path, &model_config, &prompt, engine::estimate_tokens(&lines.join("\n")) * 3 + 64)?;
+    for caps in line_pattern().captures_iter(&reply) {
+      let n: usize = caps[1].parse().unwrap_or(0);
+      if n >= 1 && start + n - 1 < end {
+        out[start + n - 1] = Some(caps[2].trim().to_string());
+      }
+    }
+    start = end;
+  }
+  Ok(out)
+}
+
+fn translate_html(html: &str, base: &Url, port: u16, config: &ProxyConfig, cache: &Mutex<HashMap<String, String>>, app: &AppHandle) -> Result<String, String> {
+  let mut pieces = tokenize(html, base, port);
+
+  // collect unique, uncached text (whitespace collapsed; layout whitespace is kept around it)
+  let mut pending: Vec<String> = Vec::new();
+  {
+    let cache = cache.locked();
+    for p in &pieces {
+      if let Piece::Text(t) = p {
+        let key = decode_entities(t).split_whitespace().collect::<Vec<_>>().join(" ");
+        if key.chars().any(|c| c.is_alphabetic()) && !cache.contains_key(&key) && !pending.contains(&key) && pending.len() < MAX_NODES {
+          pending.push(key);
+        }
+      }
+    }
+  }
+  if !pending.is_empty() {
+    let translated = translate_batch(&pending, config, app)?;
+    let mut cache = cache.locked();
+    for (src, tr) in pending.into_iter().zip(translated) {
+      if let Some(tr) = tr.filter(|t| !t.is_empty()) {
+        cache.insert(src, tr);
+      }
+    }
+  }
+
+  let cache = cache.locked();
+  let mut out = String::with_capacity(html.len());
+  for p in pieces.drain(..) {
+    match p {
+      Piece::Markup(m) => out.push_str(&m),
+      Piece::Text(t) => {
+        let key = decode_entities(&t).split_whitespace().collect::<Vec<_>>().join(" ");
+        match cache.get(&key) {
+          Some(tr) => {
+            let lead = &t[..t.len() - t.trim_start().len()];
+            let trail = &t[t.trim_end().len()..];
+            out.push_str(lead);
+            out.push_str(&encode_entities(tr));
+            out.push_str(trail);
+          }
+          None => out.push_str(&t),
+        }
+      }
+    }
+  }
+  Ok(out)
+}
+
+fn respond_error(req: Request, status: u16, message: &str) {
+  let body = format!("<html><body><h1>Translation proxy</h1><p>{}</p></body></html>", encode_entities(message));
+  let _ = req.respond(Response::from_string(body).with_status_code(status).with_header(httpd::content_type("text/html; charset=utf-8")));
+}
+
+fn handle(mut req: Request, port: u16, config: &ProxyConfig, cache: &Mutex<HashMap<String, String>>, app: &AppHandle) {
+  let Ok(local) = Url::parse(&format!("http://127.0.0.1{}", req.url())) else {
+    return respond_error(req, 400, "Bad request");
+  };
+  if local.path() != "/proxy" {
+    return respond_error(req, 404, "Open /proxy?url=<page> to browse a translated page");
+  }
+  let Some(target) = local.query_pairs().find(|(k, _)| k == "url").map(|(_, v)| v.into_owned()) else {
+    return respond_error(req, 400, "Missing url parameter");
+  };
+  let Ok(mut base) = Url::parse(&target).map_err(|_| ()).and_then(|u| if matches!(u.scheme(), "http" | "https") { Ok(u) } else { Err(()) }) else {
+    return respond_error(req, 400, "Only http and https pages can be proxied");
+  };
+  // fields of a GET form submitted through the proxy
+  let fields: Vec<(String, String)> = local.query_pairs().filter(|(k, _)| k != "url").map(|(k, v)| (k.into_owned(), v.into_owned())).collect();
+  if !fields.is_empty() {
+    base.query_pairs_mut().extend_pairs(fields);
+  }
+  let fetched = if *req.method() == Method::Post {
+    let form_type = req.headers().iter().find(|h| h.field.equiv("Content-Type")).map(|h| h.value.as_str().to_string());
+    let mut body = Vec::new();
+    if let Err(e) = req.as_reader().take(MAX_FORM_BODY).read_to_end(&mut body) {
+      return respond_error(req, 400, &format!("failed to read the form: {}", e));
+    }
+    let post = agent().post(base.as_str()).header("User-Agent", USER_AGENT);
+    let post = match form_type {
+      Some(t) => post.header("Content-Type", t),
+      None => post,
+    };
+    post.send(&body[..])
+  } else {
+    agent().get(base.as_str()).header("User-Agent", USER_AGENT).call()
+  };
+  let mut resp = match fetched {
+    Ok(r) => r,
+    Err(e) => return respond_error(req, 502, &format!("failed to fetch {}: {}", base, e)),
+  };
+  let content_type = resp.headers().get("content-type").and_then(|v| v.to_str().ok()).unwrap_or("application/octet-stream").to_string();
+  if !content_type.starts_with("text/html") {
+    let body = resp.body_mut().read_to_vec().unwrap_or_default();
+    let _ = req.respond(Response::from_data(body).with_header(httpd::content_type(&content_type)));
+    return;
+  }
+  let html = match resp.body_mut().read_to_string() {
+    Ok(h) => h,
+    Err(e) => return respond_error(req, 502, &format!("failed to read {}: {}", base, e)),
+  };
+  match dispatch::with_priority(Priority::Live, || translate_html(&html, &base, port, config, cache, app)) {
+    Ok(page) => {
+      let _ = req.respond(Response::from_string(page).with_header(httpd::content_type("text/html; charset=utf-8")));
+    }
+    Err(e) => respond_error(req, 500, &e),
+  }
+}
+
+// ------------------ Tauri commands ------------------
+
+// serve translated pages on localhost; open proxy_url(port, page) in a browser
+#[tauri::command]
+pub fn start_web_proxy(
+  target_lang: String,
+  source_lang: Option<String>,
+  model_id: Option<String>,
+  port: Option<u16>,
+  app: AppHandle,
+  proxy: tauri::State<'_, Mutex<WebProxy>>
+) -> Result<ProxyInfo, AppError> {
+  let mut proxy = proxy.locked();
+  if let Some(r) = &proxy.running {
+    return Err(AppError::Busy(format!("The translation proxy is already running on port {}", r.info.port)));
+  }
+  let config = ProxyConfig { source_lang: source_lang.clone(), target_lang: target_lang.clone(), model_id: model_id.clone() };
+  let cache = Arc::new(Mutex::new(HashMap::new()));
+  // the handler needs the port, which is only known once the server is bound
+  let bound = Arc::new(OnceLock::new());
+  let handler_port = bound.clone();
+  let server = HttpServer::start(("127.0.0.1", port.unwrap_or(0)), move |req| {
+    handle(req, *handler_port.wait(), &config, &cache, &app)
+  })
+  .map_err(|e| format!("failed to start proxy: {}", e))?;
+  let port = server.port;
+  let _ = bound.set(port);
+
+  let info = ProxyInfo { port, source_lang, target_lang, model_id };
+  proxy.running = Some(Running { server, info: info.clone() });
+  Ok(info)
+}
+
+// local address that shows `url` translated (the proxy must be running)
+#[tauri::command]
+pub fn get_proxied_url(url: String, proxy: tauri::State<'_, Mutex<WebProxy>>) -> Result<String, AppError> {
+  let proxy = proxy.locked();
+  let running = proxy.running.as_ref().ok_or("The translation proxy is not running")?;
+  Ok(proxy_url(running.info.port, &url))
+}
+
+#[tauri::command]
+pub fn stop_web_proxy(proxy: tauri::State<'_, Mutex<WebProxy>>) -> Result<(), AppError> {
+  let running = proxy.locked().running.take().ok_or("The translation proxy is not running")?;
+  running.server.stop();
+  Ok(())
+}